@@ -0,0 +1,189 @@
+//! System accessibility preferences for widgets.
+//!
+//! The preferences of the platform (reduced motion, high contrast and a preferred scale) are reached through an
+//! [`AccessibilityBackend`](trait.AccessibilityBackend.html), which applications implement on top of their platform integration.
+//! Widgets read them from their update auxiliary, via [`HasAccessibility`](trait.HasAccessibility.html),
+//! and while drawing, from the [`DrawContext`](../draw/struct.DrawContext.html#structfield.accessibility):
+//!
+//! ```ignore
+//! impl<A: HasAccessibility + HasFrameTiming> Widget for Spinner<A> {
+//!     type UpdateAux = A;
+//!
+//!     fn update(&mut self, aux: &mut A) {
+//!         if !aux.accessibility().preferences().reduced_motion {
+//!             self.angle += self.speed * aux.frame().dt.as_secs_f32();
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Since preferences can change while the application is running, widgets which cache anything derived from them (e.g. a theme)
+//! can [`listen`](struct.Accessibility.html#method.listen) for changes.
+
+use {
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+    std::time::Duration,
+};
+
+/// Accessibility preferences, typically those set in the settings of the operating system.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessibilityPreferences {
+    /// Animations should be minimized, e.g. by skipping to their end.
+    pub reduced_motion: bool,
+    /// Colors should be more distinct, e.g. with stronger borders and no translucency.
+    pub high_contrast: bool,
+    /// Preferred scale of text and controls, on top of the scale factor of the display; `1.0` is the default size.
+    pub scale: f32,
+}
+
+impl Default for AccessibilityPreferences {
+    fn default() -> Self {
+        AccessibilityPreferences { reduced_motion: false, high_contrast: false, scale: 1.0 }
+    }
+}
+
+impl AccessibilityPreferences {
+    /// Returns the duration an animation should take, which is zero (i.e. it should jump to its end) if motion is reduced.
+    #[inline]
+    pub fn animation_duration(&self, duration: Duration) -> Duration {
+        if self.reduced_motion {
+            Duration::default()
+        } else {
+            duration
+        }
+    }
+}
+
+/// Access to accessibility preferences, e.g. those of the platform.
+pub trait AccessibilityBackend {
+    /// Returns the current preferences.
+    fn preferences(&mut self) -> AccessibilityPreferences;
+}
+
+/// Fixed preferences, which is the default backend of [`Accessibility`](struct.Accessibility.html) and is also useful for tests.
+impl AccessibilityBackend for AccessibilityPreferences {
+    #[inline]
+    fn preferences(&mut self) -> AccessibilityPreferences {
+        *self
+    }
+}
+
+/// Emitted by [`Accessibility`](struct.Accessibility.html) when the preferences change.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessibilityChanged {
+    pub old: AccessibilityPreferences,
+    pub new: AccessibilityPreferences,
+}
+
+/// The accessibility service, wrapping a [backend](trait.AccessibilityBackend.html) and the last preferences read from it.
+pub struct Accessibility {
+    backend: Box<dyn AccessibilityBackend>,
+    preferences: AccessibilityPreferences,
+    pub event: RcEventQueue<AccessibilityChanged>,
+}
+
+impl std::fmt::Debug for Accessibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Accessibility").field("preferences", &self.preferences).finish()
+    }
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Accessibility::new(AccessibilityPreferences::default())
+    }
+}
+
+impl Accessibility {
+    pub fn new(mut backend: impl AccessibilityBackend + 'static) -> Self {
+        Accessibility {
+            preferences: backend.preferences(),
+            backend: Box::new(backend),
+            event: RcEventQueue::new(),
+        }
+    }
+
+    /// Replaces the backend (e.g. once the platform integration is available), reading the preferences from it.
+    pub fn set_backend(&mut self, backend: impl AccessibilityBackend + 'static) -> bool {
+        self.backend = Box::new(backend);
+        self.poll()
+    }
+
+    /// Reads the preferences from the backend, emitting [`AccessibilityChanged`](struct.AccessibilityChanged.html)
+    /// (and returning `true`) if they differ from the previous preferences.
+    ///
+    /// This should be invoked whenever the platform reports a change of settings, or otherwise periodically (e.g. when the window is focused).
+    pub fn poll(&mut self) -> bool {
+        let preferences = self.backend.preferences();
+        if preferences != self.preferences {
+            let old = std::mem::replace(&mut self.preferences, preferences);
+            self.event.emit_owned(AccessibilityChanged { old, new: preferences });
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the preferences as of the last [`poll`](struct.Accessibility.html#method.poll).
+    #[inline]
+    pub fn preferences(&self) -> AccessibilityPreferences {
+        self.preferences
+    }
+
+    /// Creates a listener for `AccessibilityChanged` events.
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<AccessibilityChanged> {
+        self.event.listen()
+    }
+}
+
+/// Implemented by auxiliaries (typically the `UpdateAux` of widgets) which provide accessibility preferences.
+pub trait HasAccessibility {
+    fn accessibility(&self) -> &Accessibility;
+}
+
+impl HasAccessibility for Accessibility {
+    #[inline]
+    fn accessibility(&self) -> &Accessibility {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::cell::Cell, std::rc::Rc};
+
+    // preferences which can be changed from outside, like those of the platform.
+    struct Shared(Rc<Cell<AccessibilityPreferences>>);
+
+    impl AccessibilityBackend for Shared {
+        fn preferences(&mut self) -> AccessibilityPreferences {
+            self.0.get()
+        }
+    }
+
+    fn transition<A: HasAccessibility>(aux: &A) -> Duration {
+        aux.accessibility().preferences().animation_duration(Duration::from_millis(200))
+    }
+
+    #[test]
+    fn test_accessibility() {
+        let platform = Rc::new(Cell::new(AccessibilityPreferences::default()));
+        let mut accessibility = Accessibility::default();
+        let listener = accessibility.listen();
+
+        assert!(!accessibility.set_backend(Shared(platform.clone())));
+        assert_eq!(transition(&accessibility), Duration::from_millis(200));
+
+        let reduced = AccessibilityPreferences { reduced_motion: true, ..Default::default() };
+        platform.set(reduced);
+        assert!(accessibility.poll());
+        assert!(!accessibility.poll());
+        assert_eq!(transition(&accessibility), Duration::default());
+
+        assert_eq!(
+            listener.peek(),
+            vec![AccessibilityChanged { old: AccessibilityPreferences::default(), new: reduced }]
+        );
+    }
+}
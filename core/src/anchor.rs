@@ -0,0 +1,226 @@
+//! Anchored and proportional positioning.
+//!
+//! A [`Placement`](struct.Placement.html) describes where a widget goes relative to its parent
+//! (anchored to an edge or the center, sized in pixels or as a percentage of the parent, optionally with a fixed aspect ratio).
+//! It's resolved against the parent rectangle on each layout pass:
+//!
+//! ```ignore
+//! let placement = Placement::new()
+//!     .horizontal(Anchor::Center(0.0.into()))
+//!     .vertical(Anchor::End(Length::Pixels(10.0)))
+//!     .width(Length::Percent(50.0))
+//!     .aspect_ratio(16.0 / 9.0);
+//!
+//! self.bounds = placement.resolve(parent_bounds);
+//! ```
+
+use crate::display::{Point, Rect, Size};
+
+/// A length, either absolute or relative to the parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Pixels(f32),
+    /// Percentage (0-100) of the parent's length along the same axis.
+    Percent(f32),
+}
+
+impl Length {
+    /// Resolves the length in pixels, given the parent's length.
+    pub fn resolve(self, parent: f32) -> f32 {
+        match self {
+            Length::Pixels(px) => px,
+            Length::Percent(pc) => parent * pc / 100.0,
+        }
+    }
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Pixels(0.0)
+    }
+}
+
+impl From<f32> for Length {
+    fn from(px: f32) -> Self {
+        Length::Pixels(px)
+    }
+}
+
+/// Where a rectangle is placed along a single axis of its parent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    /// Offset from the start (left/top) edge.
+    Start(Length),
+    /// Offset from the center.
+    Center(Length),
+    /// Offset from the end (right/bottom) edge, inwards.
+    End(Length),
+    /// Stretched between both edges, inset by the given lengths. The size is ignored.
+    Fill(Length, Length),
+}
+
+impl Default for Anchor {
+    fn default() -> Self {
+        Anchor::Start(Length::default())
+    }
+}
+
+impl Anchor {
+    /// Resolves the start position and length along this axis.
+    fn resolve(self, start: f32, parent: f32, length: f32) -> (f32, f32) {
+        match self {
+            Anchor::Start(offset) => (start + offset.resolve(parent), length),
+            Anchor::Center(offset) => {
+                (start + (parent - length) / 2.0 + offset.resolve(parent), length)
+            }
+            Anchor::End(offset) => (start + parent - length - offset.resolve(parent), length),
+            Anchor::Fill(start_inset, end_inset) => {
+                let (start_inset, end_inset) =
+                    (start_inset.resolve(parent), end_inset.resolve(parent));
+                (start + start_inset, (parent - start_inset - end_inset).max(0.0))
+            }
+        }
+    }
+}
+
+/// Position and size of a rectangle relative to its parent.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub horizontal: Anchor,
+    pub vertical: Anchor,
+    pub width: Length,
+    pub height: Length,
+    /// Width divided by height.
+    pub aspect_ratio: Option<f32>,
+}
+
+impl Placement {
+    /// Creates a placement at the top-left of the parent with a size of zero.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a placement which fills the parent entirely.
+    pub fn fill() -> Self {
+        Placement::new()
+            .horizontal(Anchor::Fill(Length::default(), Length::default()))
+            .vertical(Anchor::Fill(Length::default(), Length::default()))
+    }
+
+    pub fn horizontal(mut self, anchor: Anchor) -> Self {
+        self.horizontal = anchor;
+        self
+    }
+
+    pub fn vertical(mut self, anchor: Anchor) -> Self {
+        self.vertical = anchor;
+        self
+    }
+
+    pub fn width(mut self, width: Length) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn height(mut self, height: Length) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Constrains the size to an aspect ratio (width divided by height).
+    ///
+    /// The resolved size is shrunk along one axis such that it fits within the size it would otherwise have.
+    /// If only one axis has a size of zero (i.e. it wasn't set), it's derived from the other axis instead.
+    pub fn aspect_ratio(mut self, ratio: f32) -> Self {
+        self.aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Resolves the rectangle within `parent`.
+    pub fn resolve(&self, parent: Rect) -> Rect {
+        let mut size = Size::new(
+            self.width.resolve(parent.size.width),
+            self.height.resolve(parent.size.height),
+        );
+
+        let axis_size = |anchor: Anchor, start: f32, parent: f32, length: f32| {
+            anchor.resolve(start, parent, length).1
+        };
+        size.width = axis_size(self.horizontal, parent.origin.x, parent.size.width, size.width);
+        size.height = axis_size(self.vertical, parent.origin.y, parent.size.height, size.height);
+
+        if let Some(ratio) = self.aspect_ratio.filter(|ratio| *ratio > 0.0) {
+            size = if size.height == 0.0 {
+                Size::new(size.width, size.width / ratio)
+            } else if size.width == 0.0 || size.width / size.height > ratio {
+                Size::new(size.height * ratio, size.height)
+            } else {
+                Size::new(size.width, size.width / ratio)
+            };
+        }
+
+        // a filled axis which was shrunk by the aspect ratio is centered within the fill.
+        let place = |anchor: Anchor, start: f32, parent: f32, length: f32| match anchor {
+            Anchor::Fill(..) => {
+                let (fill_start, fill_length) = anchor.resolve(start, parent, length);
+                fill_start + (fill_length - length) / 2.0
+            }
+            _ => anchor.resolve(start, parent, length).0,
+        };
+
+        Rect::new(
+            Point::new(
+                place(self.horizontal, parent.origin.x, parent.size.width, size.width),
+                place(self.vertical, parent.origin.y, parent.size.height, size.height),
+            ),
+            size,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parent() -> Rect {
+        Rect::new(Point::new(10.0, 20.0), Size::new(200.0, 100.0))
+    }
+
+    #[test]
+    fn test_anchored_placement() {
+        let placement = Placement::new()
+            .horizontal(Anchor::End(Length::Pixels(10.0)))
+            .vertical(Anchor::Center(Length::default()))
+            .width(Length::Percent(25.0))
+            .height(Length::Pixels(40.0));
+
+        assert_eq!(
+            placement.resolve(parent()),
+            Rect::new(Point::new(150.0, 50.0), Size::new(50.0, 40.0))
+        );
+
+        assert_eq!(Placement::fill().resolve(parent()), parent());
+
+        let placement = Placement::new()
+            .horizontal(Anchor::Fill(Length::Pixels(10.0), Length::Percent(10.0)))
+            .height(Length::Percent(50.0));
+        assert_eq!(
+            placement.resolve(parent()),
+            Rect::new(Point::new(20.0, 20.0), Size::new(170.0, 50.0))
+        );
+    }
+
+    #[test]
+    fn test_aspect_ratio() {
+        // fits within the parent, centered along the shrunk axis.
+        let placement = Placement::fill().aspect_ratio(1.0);
+        assert_eq!(
+            placement.resolve(parent()),
+            Rect::new(Point::new(60.0, 20.0), Size::new(100.0, 100.0))
+        );
+
+        // height derived from width.
+        let placement = Placement::new().width(Length::Percent(50.0)).aspect_ratio(2.0);
+        assert_eq!(placement.resolve(parent()).size, Size::new(100.0, 50.0));
+    }
+}
@@ -0,0 +1,422 @@
+//! Animation of values over time, with easing.
+//!
+//! An [`Animator`](struct.Animator.html) interpolates a value (anything implementing [`Lerp`](trait.Lerp.html)) towards a target,
+//! advanced once per frame by the [frame timing](../draw/trait.HasFrameTiming.html), and emits every new value to its queue:
+//!
+//! ```ignore
+//! // in the constructor:
+//! let offset = Animator::new(0.0, Duration::from_millis(200), Easing::EaseInOut);
+//! let listener = offset.listen();
+//!
+//! // when the panel is toggled:
+//! self.offset.animate_to(if open { 0.0 } else { -width });
+//!
+//! // in update:
+//! if self.offset.update(aux) {
+//!     self.command_group.repaint();
+//! }
+//! ```
+//!
+//! To honor [reduced motion](../accessibility/struct.AccessibilityPreferences.html#structfield.reduced_motion), create animators with
+//! [`animation_duration`](../accessibility/struct.AccessibilityPreferences.html#method.animation_duration); a zero duration jumps straight to the target.
+
+use {
+    crate::{
+        display::{Color, Point, Rect, Size, Vector},
+        draw::HasFrameTiming,
+    },
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+    std::time::Duration,
+};
+
+/// Linear interpolation between two values.
+pub trait Lerp {
+    /// Returns the value a fraction `t` of the way from `self` to `to`.
+    ///
+    /// `t` may be outside of `0.0..=1.0` (e.g. for easings which overshoot).
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for f64 {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t as f64
+    }
+}
+
+impl Lerp for Point {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        *self + (*to - *self) * t
+    }
+}
+
+impl Lerp for Vector {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        *self + (*to - *self) * t
+    }
+}
+
+impl Lerp for Size {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        *self + (*to - *self) * t
+    }
+}
+
+impl Lerp for Rect {
+    #[inline]
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Rect::new(Lerp::lerp(&self.origin, &to.origin, t), Lerp::lerp(&self.size, &to.size, t))
+    }
+}
+
+/// Colors are interpolated per component, in sRGB (as in CSS transitions).
+impl Lerp for Color {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Color::new(
+            self.red.lerp(&to.red, t),
+            self.green.lerp(&to.green, t),
+            self.blue.lerp(&to.blue, t),
+            self.alpha.lerp(&to.alpha, t),
+        )
+    }
+}
+
+/// Maps the linear progress of an animation to the fraction of the way the value has moved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    /// Starts slowly; `cubic-bezier(0.42, 0, 1, 1)`.
+    EaseIn,
+    /// Ends slowly; `cubic-bezier(0, 0, 0.58, 1)`.
+    EaseOut,
+    /// Starts and ends slowly; `cubic-bezier(0.42, 0, 0.58, 1)`.
+    EaseInOut,
+    /// A cubic Bézier curve from `(0, 0)` to `(1, 1)` with the control points `(x1, y1)` and `(x2, y2)`, as in CSS.
+    ///
+    /// `x1` and `x2` should be within `0.0..=1.0`, such that the curve is a function of progress.
+    CubicBezier(f32, f32, f32, f32),
+    /// A damped spring (of unit mass) released from the start towards the end, which overshoots if it's underdamped.
+    ///
+    /// Stiffness and damping are relative to the duration of the animation, as if it lasted one second.
+    /// The spring is snapped to the end when the animation finishes, so the duration should be long enough for it to settle.
+    Spring {
+        stiffness: f32,
+        damping: f32,
+    },
+}
+
+impl Easing {
+    /// Returns the eased fraction for the progress `t`, which is clamped to `0.0..=1.0`.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+            Easing::Spring { stiffness, damping } => {
+                if t >= 1.0 {
+                    1.0
+                } else {
+                    spring(stiffness, damping, t)
+                }
+            }
+        }
+    }
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    // one-dimensional Bézier with endpoints 0 and 1.
+    let bezier = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * s * a + 3.0 * r * s * s * b + s * s * s
+    };
+    let slope = |a: f32, b: f32, s: f32| {
+        let r = 1.0 - s;
+        3.0 * r * r * a + 6.0 * r * s * (b - a) + 3.0 * s * s * (1.0 - b)
+    };
+
+    // solve for the parameter at which the curve reaches `x`; Newton's method, falling back to bisection.
+    let mut s = x;
+    let mut solved = false;
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            solved = true;
+            break;
+        }
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s -= error / d;
+    }
+
+    if !solved || !(0.0..=1.0).contains(&s) {
+        let (mut low, mut high) = (0.0, 1.0);
+        s = x;
+        for _ in 0..32 {
+            if bezier(x1, x2, s) < x {
+                low = s;
+            } else {
+                high = s;
+            }
+            s = (low + high) / 2.0;
+        }
+    }
+
+    bezier(y1, y2, s)
+}
+
+fn spring(stiffness: f32, damping: f32, t: f32) -> f32 {
+    let omega = stiffness.max(0.0).sqrt();
+    if omega == 0.0 {
+        return 0.0;
+    }
+    let zeta = damping / (2.0 * omega);
+
+    if zeta < 1.0 {
+        let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+        let decay = (-zeta * omega * t).exp();
+        1.0 - decay * ((omega_d * t).cos() + zeta * omega / omega_d * (omega_d * t).sin())
+    } else if zeta == 1.0 {
+        1.0 - (-omega * t).exp() * (1.0 + omega * t)
+    } else {
+        let root = omega * (zeta * zeta - 1.0).sqrt();
+        let (r1, r2) = (-zeta * omega + root, -zeta * omega - root);
+        1.0 - (r2 * (r1 * t).exp() - r1 * (r2 * t).exp()) / (r2 - r1)
+    }
+}
+
+/// Emitted by an [`Animator`](struct.Animator.html) whenever its value changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationFrame<T> {
+    pub value: T,
+    /// Whether the value reached the target, in which case this is the last frame of the animation.
+    pub finished: bool,
+}
+
+/// Animates a value towards a target over a fixed duration.
+///
+/// See the [module documentation](index.html).
+#[derive(Debug)]
+pub struct Animator<T: Lerp + Clone + 'static> {
+    from: T,
+    to: T,
+    value: T,
+    duration: Duration,
+    elapsed: Duration,
+    easing: Easing,
+    running: bool,
+    pub event: RcEventQueue<AnimationFrame<T>>,
+}
+
+impl<T: Lerp + Clone + 'static> Animator<T> {
+    /// Creates an animator at rest at `value`, which animates over `duration` with `easing`.
+    pub fn new(value: T, duration: Duration, easing: Easing) -> Self {
+        Animator {
+            from: value.clone(),
+            to: value.clone(),
+            value,
+            duration,
+            elapsed: Duration::default(),
+            easing,
+            running: false,
+            event: RcEventQueue::new(),
+        }
+    }
+
+    /// Starts animating from the current value to `target`, interrupting any running animation.
+    pub fn animate_to(&mut self, target: T) {
+        self.animate(self.value.clone(), target);
+    }
+
+    /// Starts animating from `from` to `to`, interrupting any running animation.
+    pub fn animate(&mut self, from: T, to: T) {
+        self.from = from;
+        self.to = to;
+        self.elapsed = Duration::default();
+        self.running = true;
+    }
+
+    /// Jumps to `value` and stops, emitting it as a finished frame.
+    pub fn set(&mut self, value: T) {
+        self.from = value.clone();
+        self.to = value.clone();
+        self.value = value;
+        self.running = false;
+        self.event.emit_owned(AnimationFrame { value: self.value.clone(), finished: true });
+    }
+
+    /// Advances a running animation by `dt`, emitting the new value. Returns `true` if the value changed.
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        if !self.running {
+            return false;
+        }
+
+        self.elapsed += dt;
+        let finished = self.elapsed >= self.duration;
+        self.value = if finished {
+            self.to.clone()
+        } else {
+            let progress = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+            self.from.lerp(&self.to, self.easing.apply(progress))
+        };
+        self.running = !finished;
+
+        self.event.emit_owned(AnimationFrame { value: self.value.clone(), finished });
+        true
+    }
+
+    /// Advances a running animation by the time since the previous frame (see [`tick`](struct.Animator.html#method.tick)).
+    #[inline]
+    pub fn update(&mut self, aux: &impl HasFrameTiming) -> bool {
+        self.tick(aux.frame().dt)
+    }
+
+    /// Returns the current value.
+    #[inline]
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the value being animated towards, which is the current value at rest.
+    #[inline]
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+
+    /// Returns `true` while the value is moving towards the target.
+    #[inline]
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Returns the linear progress of the animation, from `0.0` to `1.0` (which it is at rest).
+    pub fn progress(&self) -> f32 {
+        if !self.running || self.duration == Duration::default() {
+            1.0
+        } else {
+            (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+        }
+    }
+
+    /// Sets the duration of subsequent animations (and of the running one, which may then finish on the next tick).
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Sets the easing of subsequent animations (and of the running one).
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    /// Creates a listener for the frames of the animation.
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<AnimationFrame<T>> {
+        self.event.listen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, float_cmp::approx_eq};
+
+    #[test]
+    fn test_easing() {
+        let easings = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.1, 0.7, 0.1, 1.0),
+            Easing::Spring { stiffness: 100.0, damping: 10.0 },
+        ];
+        for easing in &easings {
+            assert!(approx_eq!(f32, easing.apply(0.0), 0.0, epsilon = 1e-5), "{:?}", easing);
+            assert!(approx_eq!(f32, easing.apply(1.0), 1.0, epsilon = 1e-5), "{:?}", easing);
+            assert_eq!(easing.apply(2.0), easing.apply(1.0));
+        }
+
+        // the linear curve is the identity, and ease-in-out is symmetric.
+        for &t in &[0.1, 0.25, 0.5, 0.9] {
+            assert!(approx_eq!(
+                f32,
+                Easing::CubicBezier(0.0, 0.0, 1.0, 1.0).apply(t),
+                t,
+                epsilon = 1e-4
+            ));
+            assert!(approx_eq!(
+                f32,
+                Easing::EaseInOut.apply(t),
+                1.0 - Easing::EaseInOut.apply(1.0 - t),
+                epsilon = 1e-4
+            ));
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+
+        // underdamped springs overshoot, critically damped ones don't.
+        let bouncy = Easing::Spring { stiffness: 200.0, damping: 5.0 };
+        assert!((1..100).any(|i| bouncy.apply(i as f32 / 100.0) > 1.0));
+        let critical = Easing::Spring { stiffness: 100.0, damping: 20.0 };
+        assert!((1..100).all(|i| critical.apply(i as f32 / 100.0) <= 1.0));
+    }
+
+    #[test]
+    fn test_animator() {
+        let mut animator = Animator::new(0.0f32, Duration::from_millis(100), Easing::Linear);
+        let listener = animator.listen();
+
+        assert!(!animator.tick(Duration::from_millis(16)));
+        animator.animate_to(10.0);
+        assert!(animator.is_running());
+        assert!(animator.tick(Duration::from_millis(50)));
+        assert!(approx_eq!(f32, *animator.value(), 5.0));
+        assert!(approx_eq!(f32, animator.progress(), 0.5));
+
+        // retargeting continues from the current value.
+        animator.animate_to(-5.0);
+        assert!(animator.tick(Duration::from_millis(200)));
+        assert!(!animator.is_running());
+        assert_eq!(*animator.value(), -5.0);
+
+        assert_eq!(
+            listener.peek(),
+            vec![
+                AnimationFrame { value: 5.0, finished: false },
+                AnimationFrame { value: -5.0, finished: true },
+            ]
+        );
+
+        // a zero duration jumps to the target on the next tick.
+        animator.set_duration(Duration::default());
+        animator.animate_to(1.0);
+        assert!(animator.tick(Duration::default()));
+        assert_eq!(*animator.value(), 1.0);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let from = Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0));
+        let to = Rect::new(Point::new(10.0, 20.0), Size::new(20.0, 0.0));
+        assert_eq!(
+            Lerp::lerp(&from, &to, 0.5),
+            Rect::new(Point::new(5.0, 10.0), Size::new(15.0, 5.0))
+        );
+
+        let color = Color::new(0.0, 0.0, 0.0, 1.0).lerp(&Color::new(1.0, 0.5, 0.0, 0.0), 0.5);
+        assert_eq!(color, Color::new(0.5, 0.25, 0.0, 0.5));
+    }
+}
@@ -0,0 +1,235 @@
+//! Resolution of logical asset names to resource descriptors.
+//!
+//! An [`AssetRegistry`](struct.AssetRegistry.html) looks up names such as `"icons/close"` in its sources, in the order they were added.
+//! Sources are either directories on disk or bundles embedded into the executable (see [`asset_bundle!`](../macro.asset_bundle.html)):
+//!
+//! ```ignore
+//! let mut assets = AssetRegistry::new()
+//!     .with_directory(concat!(env!("CARGO_MANIFEST_DIR"), "/assets"))
+//!     .with_bundle(reclutch::asset_bundle!("assets"; "icons/close.png", "fonts/ui.ttf"));
+//!
+//! let close = display.new_resource(assets.resolve("icons/close")?)?;
+//! ```
+//!
+//! The file extension may be omitted from names, and determines whether the asset is an image or a font.
+//! In debug builds, files resolved from directories are watched such that changes can be picked up through [`changed`](struct.AssetRegistry.html#method.changed).
+
+use {
+    crate::{
+        display::{ImageData, ResourceData, ResourceDescriptor, SharedData},
+        error,
+    },
+    std::path::{Path, PathBuf},
+};
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "ico"];
+const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "ttc"];
+
+/// Assets embedded into the executable, as a list of names (relative paths) and data.
+///
+/// Usually created through [`asset_bundle!`](../macro.asset_bundle.html).
+#[derive(Debug, Clone, Copy)]
+pub struct AssetBundle {
+    assets: &'static [(&'static str, &'static [u8])],
+}
+
+impl AssetBundle {
+    pub const fn new(assets: &'static [(&'static str, &'static [u8])]) -> Self {
+        AssetBundle { assets }
+    }
+
+    fn find(&self, name: &str) -> Option<(&'static str, &'static [u8])> {
+        self.assets.iter().find(|(path, _)| *path == name || strip_extension(path) == name).copied()
+    }
+}
+
+/// Embeds files into an [`AssetBundle`](asset/struct.AssetBundle.html).
+///
+/// The root directory is relative to the manifest directory of the crate invoking the macro,
+/// and each file is named by its path relative to the root.
+///
+/// # Example
+/// ```ignore
+/// let bundle = asset_bundle!("assets"; "icons/close.png", "fonts/ui.ttf");
+/// ```
+#[macro_export]
+macro_rules! asset_bundle {
+    ($root:literal; $($name:literal),* $(,)?) => {
+        $crate::asset::AssetBundle::new(&[$(
+            ($name, include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $root, "/", $name)) as &'static [u8])
+        ),*])
+    };
+}
+
+/// Where assets are looked up.
+#[derive(Debug, Clone)]
+pub enum AssetSource {
+    Directory(PathBuf),
+    Bundle(AssetBundle),
+}
+
+/// Resolves logical asset names to resource descriptors.
+#[derive(Debug, Default)]
+pub struct AssetRegistry {
+    sources: Vec<AssetSource>,
+    // only populated in debug builds.
+    watched: Vec<(String, PathBuf, Option<std::time::SystemTime>)>,
+}
+
+fn strip_extension(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(dot) if !name[dot..].contains('/') => &name[..dot],
+        _ => name,
+    }
+}
+
+fn extension(name: &str) -> Option<String> {
+    Path::new(name).extension().map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+fn modified(path: &Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn descriptor(name: &str, data: ResourceData) -> Result<ResourceDescriptor, error::ResourceError> {
+    match extension(name) {
+        Some(ext) if IMAGE_EXTENSIONS.contains(&ext.as_str()) => {
+            Ok(ResourceDescriptor::Image(ImageData::Encoded(data)))
+        }
+        Some(ext) if FONT_EXTENSIONS.contains(&ext.as_str()) => Ok(ResourceDescriptor::Font(data)),
+        _ => Err(error::ResourceError::InvalidPath(name.to_string())),
+    }
+}
+
+impl AssetRegistry {
+    /// Creates a registry without any sources.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a directory to look up assets in.
+    pub fn with_directory(mut self, path: impl Into<PathBuf>) -> Self {
+        self.sources.push(AssetSource::Directory(path.into()));
+        self
+    }
+
+    /// Adds an embedded bundle to look up assets in.
+    pub fn with_bundle(mut self, bundle: AssetBundle) -> Self {
+        self.sources.push(AssetSource::Bundle(bundle));
+        self
+    }
+
+    /// Returns the sources, in the order they're searched.
+    #[inline]
+    pub fn sources(&self) -> &[AssetSource] {
+        &self.sources
+    }
+
+    fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+        let path = dir.join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+
+        // the name may be lacking its extension.
+        let parent = path.parent()?;
+        let stem = path.file_name()?.to_string_lossy().to_string();
+        std::fs::read_dir(parent).ok()?.filter_map(Result::ok).map(|entry| entry.path()).find(
+            |path| {
+                path.is_file()
+                    && path.file_stem().map(|file_stem| file_stem.to_string_lossy() == stem)
+                        == Some(true)
+            },
+        )
+    }
+
+    /// Looks up an asset by name, returning the first match within the sources.
+    pub fn resolve(&mut self, name: &str) -> Result<ResourceDescriptor, error::ResourceError> {
+        for source in &self.sources {
+            match source {
+                AssetSource::Directory(dir) => {
+                    if let Some(path) = Self::find_file(dir, name) {
+                        let file_name = path.to_string_lossy().to_string();
+
+                        #[cfg(debug_assertions)]
+                        {
+                            let stamp = modified(&path);
+                            self.watched.retain(|(watched, _, _)| watched != name);
+                            self.watched.push((name.to_string(), path.clone(), stamp));
+                        }
+
+                        return descriptor(&file_name, ResourceData::File(path));
+                    }
+                }
+                AssetSource::Bundle(bundle) => {
+                    if let Some((path, data)) = bundle.find(name) {
+                        return descriptor(path, ResourceData::Data(SharedData::Static(data)));
+                    }
+                }
+            }
+        }
+
+        Err(error::ResourceError::InvalidPath(name.to_string()))
+    }
+
+    /// Returns the names of assets which were resolved from a directory and have since changed on disk.
+    ///
+    /// Such assets should be resolved (and their resources recreated) again. This is always empty in release builds.
+    pub fn changed(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+        for (name, path, stamp) in &mut self.watched {
+            let current = modified(path);
+            if current != *stamp {
+                *stamp = current;
+                changed.push(name.clone());
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_bundle() {
+        let mut assets = AssetRegistry::new()
+            .with_bundle(crate::asset_bundle!("../reclutch/examples/shaping"; "NotoSans.ttf"));
+
+        match assets.resolve("NotoSans").unwrap() {
+            ResourceDescriptor::Font(ResourceData::Data(SharedData::Static(data))) => {
+                assert!(!data.is_empty())
+            }
+            _ => panic!("expected an embedded font"),
+        }
+
+        assert!(assets.resolve("icons/missing").is_err());
+    }
+
+    #[test]
+    fn test_asset_directory() {
+        let dir = std::env::temp_dir().join(format!("reclutch-assets-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("icons")).unwrap();
+        let icon = dir.join("icons/close.png");
+        std::fs::write(&icon, b"not really a png").unwrap();
+
+        let mut assets = AssetRegistry::new().with_directory(&dir);
+        match assets.resolve("icons/close").unwrap() {
+            ResourceDescriptor::Image(ImageData::Encoded(ResourceData::File(path))) => {
+                assert_eq!(path, icon)
+            }
+            _ => panic!("expected an image file"),
+        }
+        assert!(assets.changed().is_empty());
+
+        let file = std::fs::OpenOptions::new().write(true).open(&icon).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        if cfg!(debug_assertions) {
+            assert_eq!(assets.changed(), vec!["icons/close".to_string()]);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
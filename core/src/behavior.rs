@@ -0,0 +1,415 @@
+//! Reusable interaction behaviors.
+//!
+//! A behavior is kept as a field of a widget, which forwards its input events to it.
+//! The behavior keeps track of the interaction and emits higher-level events, which the widget (or others) listen to.
+
+use {
+    crate::{
+        display::{Point, Rect, RectExt, ResizeEdge, Size, Vector},
+        input::{CursorIcon, InputEvent, MouseButton},
+    },
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+};
+
+/// Emitted by [`ResizeBorder`](struct.ResizeBorder.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeEvent {
+    /// The pointer moved onto (or off) a grabbable edge, such that the cursor should change.
+    CursorChanged(CursorIcon),
+    /// An edge was grabbed.
+    Started(ResizeEdge),
+    /// The grabbed edge was dragged.
+    Resized {
+        edge: ResizeEdge,
+        /// The new bounds, within the minimum and maximum size.
+        bounds: Rect,
+        /// The change in size since the previous event.
+        delta: Vector,
+    },
+    /// The grabbed edge was released.
+    Finished(ResizeEdge),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Grab {
+    edge: ResizeEdge,
+    origin: Point,
+    initial: Rect,
+    last: Rect,
+}
+
+/// Lets the edges and corners of a rectangle (e.g. a floating panel) be dragged to resize it.
+///
+/// Edges can be grabbed within `tolerance` of either side of the bounds.
+/// The owner applies the bounds of [`Resized`](enum.ResizeEvent.html#variant.Resized) events,
+/// and sets the cursor on [`CursorChanged`](enum.ResizeEvent.html#variant.CursorChanged) events:
+///
+/// ```ignore
+/// if !self.resize.handle(&event, self.bounds) {
+///     // not resizing; handle the event as usual
+/// }
+///
+/// for event in self.resize_listener.peek() {
+///     if let ResizeEvent::Resized { bounds, .. } = event {
+///         self.bounds = bounds;
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ResizeBorder {
+    pub tolerance: f32,
+    pub min_size: Size,
+    pub max_size: Size,
+    grab: Option<Grab>,
+    cursor: CursorIcon,
+    pub event: RcEventQueue<ResizeEvent>,
+}
+
+impl ResizeBorder {
+    pub fn new(tolerance: f32, min_size: Size, max_size: Size) -> Self {
+        ResizeBorder {
+            tolerance,
+            min_size,
+            max_size,
+            grab: None,
+            cursor: CursorIcon::Default,
+            event: RcEventQueue::new(),
+        }
+    }
+
+    /// Returns the edge of `bounds` which would be grabbed at `point`, if any.
+    pub fn edge_at(&self, bounds: Rect, point: Point) -> Option<ResizeEdge> {
+        if !bounds.inflate(self.tolerance, self.tolerance).contains(point) {
+            return None;
+        }
+
+        let left = point.x - bounds.min_x() <= self.tolerance;
+        let right = !left && bounds.max_x() - point.x <= self.tolerance;
+        let top = point.y - bounds.min_y() <= self.tolerance;
+        let bottom = !top && bounds.max_y() - point.y <= self.tolerance;
+
+        match (left, top, right, bottom) {
+            (true, true, _, _) => Some(ResizeEdge::TopLeft),
+            (true, _, _, true) => Some(ResizeEdge::BottomLeft),
+            (_, true, true, _) => Some(ResizeEdge::TopRight),
+            (_, _, true, true) => Some(ResizeEdge::BottomRight),
+            (true, ..) => Some(ResizeEdge::Left),
+            (_, true, ..) => Some(ResizeEdge::Top),
+            (_, _, true, _) => Some(ResizeEdge::Right),
+            (.., true) => Some(ResizeEdge::Bottom),
+            _ => None,
+        }
+    }
+
+    /// Handles an input event (in the same coordinate space as `bounds`), returning `true` if it was consumed by resizing.
+    pub fn handle(&mut self, event: &InputEvent, bounds: Rect) -> bool {
+        match *event {
+            InputEvent::PointerDown { position, button: MouseButton::Left, .. } => {
+                if let Some(edge) = self.edge_at(bounds, position) {
+                    self.grab =
+                        Some(Grab { edge, origin: position, initial: bounds, last: bounds });
+                    self.event.emit_owned(ResizeEvent::Started(edge));
+                    true
+                } else {
+                    false
+                }
+            }
+            InputEvent::PointerMove { position, .. } => {
+                if let Some(mut grab) = self.grab {
+                    let resized = self.resize(&grab, position - grab.origin);
+                    if resized != grab.last {
+                        self.event.emit_owned(ResizeEvent::Resized {
+                            edge: grab.edge,
+                            bounds: resized,
+                            delta: (resized.size - grab.last.size).to_vector(),
+                        });
+                        grab.last = resized;
+                        self.grab = Some(grab);
+                    }
+                    true
+                } else {
+                    self.set_cursor(self.edge_at(bounds, position).map(Into::into));
+                    false
+                }
+            }
+            InputEvent::PointerUp { position, button: MouseButton::Left, .. } => {
+                if let Some(grab) = self.grab.take() {
+                    self.event.emit_owned(ResizeEvent::Finished(grab.edge));
+                    self.set_cursor(self.edge_at(bounds, position).map(Into::into));
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the edge being dragged, if any.
+    #[inline]
+    pub fn resizing(&self) -> Option<ResizeEdge> {
+        self.grab.map(|grab| grab.edge)
+    }
+
+    /// Returns the cursor requested by the last [`CursorChanged`](enum.ResizeEvent.html#variant.CursorChanged) event.
+    #[inline]
+    pub fn cursor(&self) -> CursorIcon {
+        self.cursor
+    }
+
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<ResizeEvent> {
+        self.event.listen()
+    }
+
+    fn resize(&self, grab: &Grab, delta: Vector) -> Rect {
+        let mut rect = grab.initial.resize_edge(grab.edge, delta, self.min_size);
+
+        if rect.size.width > self.max_size.width {
+            if grab.edge.is_left() {
+                rect.origin.x = rect.max_x() - self.max_size.width;
+            }
+            rect.size.width = self.max_size.width;
+        }
+
+        if rect.size.height > self.max_size.height {
+            if grab.edge.is_top() {
+                rect.origin.y = rect.max_y() - self.max_size.height;
+            }
+            rect.size.height = self.max_size.height;
+        }
+
+        rect
+    }
+
+    fn set_cursor(&mut self, cursor: Option<CursorIcon>) {
+        let cursor = cursor.unwrap_or_default();
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            self.event.emit_owned(ResizeEvent::CursorChanged(cursor));
+        }
+    }
+}
+
+/// A pointer gesture driving a [`DragBehavior`](struct.DragBehavior.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DragInput {
+    Press(Point),
+    Move(Point),
+    Release(Point),
+}
+
+/// Events which can drive a [`DragBehavior`](struct.DragBehavior.html).
+///
+/// This is implemented for [`InputEvent`](../input/enum.InputEvent.html) (dragging with the left button),
+/// and can be implemented for application-specific events.
+pub trait DragSource {
+    /// Converts the event into a gesture, given the area in which a press starts dragging.
+    fn drag_input(&self, area: &Rect) -> Option<DragInput>;
+}
+
+impl DragSource for DragInput {
+    fn drag_input(&self, area: &Rect) -> Option<DragInput> {
+        match *self {
+            DragInput::Press(position) if !area.contains(position) => None,
+            input => Some(input),
+        }
+    }
+}
+
+impl DragSource for InputEvent {
+    fn drag_input(&self, area: &Rect) -> Option<DragInput> {
+        match *self {
+            InputEvent::PointerDown { position, button: MouseButton::Left, .. }
+                if area.contains(position) =>
+            {
+                Some(DragInput::Press(position))
+            }
+            InputEvent::PointerMove { position, .. } => Some(DragInput::Move(position)),
+            InputEvent::PointerUp { position, button: MouseButton::Left, .. } => {
+                Some(DragInput::Release(position))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Emitted by [`DragBehavior`](struct.DragBehavior.html) (unless mapped to another output).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DragEvent {
+    /// Dragging started at the given position (the anchor).
+    Started(Point),
+    Moved {
+        position: Point,
+        /// Offset of the pointer from the anchor.
+        delta: Vector,
+    },
+    Finished(Point),
+}
+
+/// Tracks a drag gesture; anchored on press, reporting the offset from the anchor on move, until release.
+///
+/// The events it handles are any [`DragSource`](trait.DragSource.html), and the events it emits are
+/// [`DragEvent`](enum.DragEvent.html)s mapped to `O`, such that it can stand in for existing event types:
+///
+/// ```ignore
+/// let drag = DragBehavior::with_output(|event| match event {
+///     DragEvent::Started(position) => Some(TitlebarEvent::BeginClick(position)),
+///     DragEvent::Moved { delta, .. } => Some(TitlebarEvent::Move(delta)),
+///     DragEvent::Finished(_) => Some(TitlebarEvent::EndClick),
+/// });
+///
+/// for event in self.global_listener.peek() {
+///     self.drag.handle(&event, self.bounds());
+/// }
+/// ```
+pub struct DragBehavior<O: Clone = DragEvent> {
+    anchor: Option<Point>,
+    output: Box<dyn FnMut(DragEvent) -> Option<O>>,
+    pub event: RcEventQueue<O>,
+}
+
+impl<O: Clone + std::fmt::Debug> std::fmt::Debug for DragBehavior<O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DragBehavior")
+            .field("anchor", &self.anchor)
+            .field("event", &self.event)
+            .finish()
+    }
+}
+
+impl DragBehavior {
+    pub fn new() -> Self {
+        DragBehavior::with_output(Some)
+    }
+}
+
+impl Default for DragBehavior {
+    fn default() -> Self {
+        DragBehavior::new()
+    }
+}
+
+impl<O: Clone> DragBehavior<O> {
+    /// Creates a drag behavior which emits `output` of each drag event (if any).
+    pub fn with_output(output: impl FnMut(DragEvent) -> Option<O> + 'static) -> Self {
+        DragBehavior { anchor: None, output: Box::new(output), event: RcEventQueue::new() }
+    }
+
+    /// Handles an event, where a press within `area` starts dragging. Returns `true` if the event was part of a drag.
+    pub fn handle<E: DragSource + ?Sized>(&mut self, event: &E, area: Rect) -> bool {
+        let event = match (event.drag_input(&area), self.anchor) {
+            (Some(DragInput::Press(position)), None) => {
+                self.anchor = Some(position);
+                DragEvent::Started(position)
+            }
+            (Some(DragInput::Move(position)), Some(anchor)) => {
+                DragEvent::Moved { position, delta: position - anchor }
+            }
+            (Some(DragInput::Release(position)), Some(_)) => {
+                self.anchor = None;
+                DragEvent::Finished(position)
+            }
+            _ => return false,
+        };
+
+        if let Some(output) = (self.output)(event) {
+            self.event.emit_borrowed(&output);
+        }
+
+        true
+    }
+
+    /// Returns the position at which dragging started, if dragging.
+    #[inline]
+    pub fn anchor(&self) -> Option<Point> {
+        self.anchor
+    }
+
+    #[inline]
+    pub fn is_dragging(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<O> {
+        self.event.listen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::input::Modifiers};
+
+    fn pointer(position: (f32, f32)) -> (Point, Modifiers) {
+        (Point::new(position.0, position.1), Modifiers::default())
+    }
+
+    #[test]
+    fn test_resize_border() {
+        let bounds = Rect::new(Point::new(100.0, 100.0), Size::new(200.0, 100.0));
+        let mut border = ResizeBorder::new(4.0, Size::new(50.0, 50.0), Size::new(300.0, 150.0));
+        let listener = border.listen();
+
+        assert_eq!(border.edge_at(bounds, Point::new(97.0, 150.0)), Some(ResizeEdge::Left));
+        assert_eq!(border.edge_at(bounds, Point::new(302.0, 198.0)), Some(ResizeEdge::BottomRight));
+        assert_eq!(border.edge_at(bounds, Point::new(150.0, 150.0)), None);
+        assert_eq!(border.edge_at(bounds, Point::new(90.0, 150.0)), None);
+
+        let (position, modifiers) = pointer((101.0, 101.0));
+        assert!(!border.handle(&InputEvent::PointerMove { position, modifiers }, bounds));
+        assert_eq!(border.cursor(), CursorIcon::NwseResize);
+
+        let button = MouseButton::Left;
+        assert!(border.handle(&InputEvent::PointerDown { position, button, modifiers }, bounds));
+        assert_eq!(border.resizing(), Some(ResizeEdge::TopLeft));
+
+        // grows beyond the maximum width, then shrinks below the minimum height.
+        let (position, modifiers) = pointer((-49.0, 171.0));
+        assert!(border.handle(&InputEvent::PointerMove { position, modifiers }, bounds));
+        assert!(border.handle(&InputEvent::PointerUp { position, button, modifiers }, bounds));
+        assert_eq!(border.resizing(), None);
+
+        assert_eq!(
+            listener.peek(),
+            vec![
+                ResizeEvent::CursorChanged(CursorIcon::NwseResize),
+                ResizeEvent::Started(ResizeEdge::TopLeft),
+                ResizeEvent::Resized {
+                    edge: ResizeEdge::TopLeft,
+                    bounds: Rect::new(Point::new(0.0, 150.0), Size::new(300.0, 50.0)),
+                    delta: Vector::new(100.0, -50.0),
+                },
+                ResizeEvent::Finished(ResizeEdge::TopLeft),
+                ResizeEvent::CursorChanged(CursorIcon::Default),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drag_behavior() {
+        let area = Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 30.0));
+        let mut drag = DragBehavior::with_output(|event| match event {
+            DragEvent::Moved { delta, .. } => Some(delta),
+            _ => None,
+        });
+        let listener = drag.listen();
+
+        assert!(!drag.handle(&DragInput::Press(Point::new(50.0, 50.0)), area));
+        assert!(!drag.handle(&DragInput::Move(Point::new(60.0, 50.0)), area));
+
+        let (position, modifiers) = pointer((10.0, 10.0));
+        let button = MouseButton::Left;
+        assert!(drag.handle(&InputEvent::PointerDown { position, button, modifiers }, area));
+        assert_eq!(drag.anchor(), Some(position));
+
+        // dragging continues outside the area.
+        assert!(drag.handle(&DragInput::Move(Point::new(200.0, 5.0)), area));
+        let (position, modifiers) = pointer((15.0, 20.0));
+        assert!(drag.handle(&InputEvent::PointerMove { position, modifiers }, area));
+        assert!(drag.handle(&InputEvent::PointerUp { position, button, modifiers }, area));
+        assert!(!drag.is_dragging());
+
+        assert_eq!(listener.peek(), vec![Vector::new(190.0, -5.0), Vector::new(5.0, 10.0)]);
+    }
+}
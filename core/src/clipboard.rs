@@ -0,0 +1,140 @@
+//! Clipboard access for widgets.
+//!
+//! The platform clipboard is reached through a [`ClipboardBackend`](trait.ClipboardBackend.html),
+//! which applications implement on top of their windowing or clipboard library of choice.
+//! Widgets reach the [`Clipboard`](struct.Clipboard.html) through their update auxiliary, via [`HasClipboard`](trait.HasClipboard.html):
+//!
+//! ```ignore
+//! impl<A: HasClipboard> Widget for TextBox<A> {
+//!     type UpdateAux = A;
+//!
+//!     fn update(&mut self, aux: &mut A) {
+//!         // on Ctrl+C:
+//!         aux.clipboard().set_text(self.selected_text()).ok();
+//!     }
+//! }
+//! ```
+
+use crate::error::ClipboardError;
+
+/// Access to a clipboard, e.g. that of the platform.
+pub trait ClipboardBackend {
+    /// Returns the text on the clipboard, or `None` if the clipboard is empty or doesn't hold text.
+    fn text(&mut self) -> Result<Option<String>, ClipboardError>;
+    /// Replaces the contents of the clipboard with `text`.
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+/// A clipboard which only exists within the application.
+///
+/// This is the default backend of [`Clipboard`](struct.Clipboard.html), and is also useful for tests.
+#[derive(Debug, Clone, Default)]
+pub struct LocalClipboard {
+    text: Option<String>,
+}
+
+impl ClipboardBackend for LocalClipboard {
+    fn text(&mut self) -> Result<Option<String>, ClipboardError> {
+        Ok(self.text.clone())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        self.text = Some(text.to_string());
+        Ok(())
+    }
+}
+
+/// The clipboard service, wrapping a [backend](trait.ClipboardBackend.html).
+pub struct Clipboard {
+    backend: Box<dyn ClipboardBackend>,
+}
+
+impl std::fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Clipboard").finish()
+    }
+}
+
+impl Default for Clipboard {
+    fn default() -> Self {
+        Clipboard::new(LocalClipboard::default())
+    }
+}
+
+impl Clipboard {
+    pub fn new(backend: impl ClipboardBackend + 'static) -> Self {
+        Clipboard { backend: Box::new(backend) }
+    }
+
+    /// Replaces the backend, e.g. once the platform clipboard is available.
+    pub fn set_backend(&mut self, backend: impl ClipboardBackend + 'static) {
+        self.backend = Box::new(backend);
+    }
+
+    /// Returns the text on the clipboard, if any.
+    #[inline]
+    pub fn text(&mut self) -> Result<Option<String>, ClipboardError> {
+        self.backend.text()
+    }
+
+    /// Replaces the contents of the clipboard with `text`.
+    #[inline]
+    pub fn set_text(&mut self, text: impl AsRef<str>) -> Result<(), ClipboardError> {
+        self.backend.set_text(text.as_ref())
+    }
+}
+
+/// Implemented by auxiliaries (typically the `UpdateAux` of widgets) which provide a clipboard.
+pub trait HasClipboard {
+    fn clipboard(&mut self) -> &mut Clipboard;
+}
+
+impl HasClipboard for Clipboard {
+    #[inline]
+    fn clipboard(&mut self) -> &mut Clipboard {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Globals {
+        clipboard: Clipboard,
+    }
+
+    impl HasClipboard for Globals {
+        fn clipboard(&mut self) -> &mut Clipboard {
+            &mut self.clipboard
+        }
+    }
+
+    struct Unavailable;
+
+    impl ClipboardBackend for Unavailable {
+        fn text(&mut self) -> Result<Option<String>, ClipboardError> {
+            Err(ClipboardError::Unavailable)
+        }
+
+        fn set_text(&mut self, _text: &str) -> Result<(), ClipboardError> {
+            Err(ClipboardError::Unavailable)
+        }
+    }
+
+    fn copy<A: HasClipboard>(aux: &mut A, text: &str) -> Result<(), ClipboardError> {
+        aux.clipboard().set_text(text)
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let mut globals = Globals { clipboard: Clipboard::default() };
+        assert_eq!(globals.clipboard().text().unwrap(), None);
+
+        copy(&mut globals, "hello").unwrap();
+        assert_eq!(globals.clipboard().text().unwrap().as_deref(), Some("hello"));
+
+        globals.clipboard().set_backend(Unavailable);
+        assert!(matches!(copy(&mut globals, "hello"), Err(ClipboardError::Unavailable)));
+    }
+}
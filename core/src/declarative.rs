@@ -0,0 +1,221 @@
+//! Optional declarative layer over retained widgets.
+//!
+//! Rather than mutating widgets directly, a `view(state)` function produces a description of the widget tree every frame.
+//! Descriptions are diffed against those of the previous frame, and only the changes are patched onto the retained widgets.
+//!
+//! A container describes its children through a [`DescribedList`](struct.DescribedList.html) (reconciled within its own [`patch`](trait.Describe.html#tymethod.patch)),
+//! such that the whole tree is diffed from the root down.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+/// A description of a widget, as produced by a view function.
+pub trait Describe: PartialEq {
+    type Widget;
+
+    /// Creates a new widget matching this description.
+    fn create(&self) -> Self::Widget;
+
+    /// Updates `widget` (which currently matches `old`) to match this description.
+    ///
+    /// This is only invoked if the description changed (i.e. `self != old`).
+    fn patch(&self, old: &Self, widget: &mut Self::Widget);
+}
+
+/// A single retained widget, along with the description it currently matches.
+pub struct Described<D: Describe> {
+    description: D,
+    widget: D::Widget,
+}
+
+impl<D: Describe> Described<D> {
+    /// Creates the widget from `description`.
+    pub fn new(description: D) -> Self {
+        let widget = description.create();
+        Described { description, widget }
+    }
+
+    /// Patches the widget if `description` differs from the current description, returning whether it did.
+    pub fn update(&mut self, description: D) -> bool {
+        if description != self.description {
+            description.patch(&self.description, &mut self.widget);
+            self.description = description;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the description the widget currently matches.
+    #[inline]
+    pub fn description(&self) -> &D {
+        &self.description
+    }
+
+    #[inline]
+    pub fn widget(&self) -> &D::Widget {
+        &self.widget
+    }
+
+    #[inline]
+    pub fn widget_mut(&mut self) -> &mut D::Widget {
+        &mut self.widget
+    }
+
+    /// Returns the inner widget, discarding the description.
+    #[inline]
+    pub fn into_widget(self) -> D::Widget {
+        self.widget
+    }
+}
+
+/// Statistics of a single [`reconcile`](struct.DescribedList.html#method.reconcile).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reconciliation {
+    pub created: usize,
+    pub patched: usize,
+    pub removed: usize,
+}
+
+impl Reconciliation {
+    /// Returns `true` if nothing was created, patched or removed (order changes aside).
+    pub fn is_empty(&self) -> bool {
+        self.created == 0 && self.patched == 0 && self.removed == 0
+    }
+}
+
+/// A list of retained widgets which are matched to descriptions by key.
+///
+/// Keys identify a widget across frames, so that reordering descriptions moves widgets instead of rebuilding them.
+pub struct DescribedList<K: Hash + Eq + Clone, D: Describe> {
+    entries: Vec<(K, Described<D>)>,
+}
+
+impl<K: Hash + Eq + Clone, D: Describe> Default for DescribedList<K, D> {
+    fn default() -> Self {
+        DescribedList { entries: Vec::new() }
+    }
+}
+
+impl<K: Hash + Eq + Clone, D: Describe> DescribedList<K, D> {
+    /// Creates a new, empty list.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Updates the list to match `descriptions`, in order.
+    ///
+    /// Widgets with a key which is still present are kept (and patched if their description changed),
+    /// widgets with a new key are created and widgets with a key which is no longer present are dropped.
+    /// If a key is repeated, only the first is kept.
+    pub fn reconcile(&mut self, descriptions: impl IntoIterator<Item = (K, D)>) -> Reconciliation {
+        let mut old: HashMap<K, Described<D>> = self.entries.drain(..).collect();
+        let mut seen = HashSet::new();
+        let mut stats = Reconciliation::default();
+
+        for (key, description) in descriptions {
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+
+            let described = match old.remove(&key) {
+                Some(mut described) => {
+                    if described.update(description) {
+                        stats.patched += 1;
+                    }
+                    described
+                }
+                None => {
+                    stats.created += 1;
+                    Described::new(description)
+                }
+            };
+
+            self.entries.push((key, described));
+        }
+
+        stats.removed = old.len();
+        stats
+    }
+
+    /// Returns the widget with the given key.
+    pub fn get(&self, key: &K) -> Option<&D::Widget> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, described)| described.widget())
+    }
+
+    /// Returns the widget with the given key, mutably.
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut D::Widget> {
+        self.entries.iter_mut().find(|(k, _)| k == key).map(|(_, described)| described.widget_mut())
+    }
+
+    /// Returns the widgets in the order of the last reconciled descriptions.
+    pub fn widgets(&self) -> impl Iterator<Item = &D::Widget> {
+        self.entries.iter().map(|(_, described)| described.widget())
+    }
+
+    /// Returns the widgets (mutably) in the order of the last reconciled descriptions.
+    pub fn widgets_mut(&mut self) -> impl Iterator<Item = &mut D::Widget> {
+        self.entries.iter_mut().map(|(_, described)| described.widget_mut())
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(PartialEq)]
+    struct LabelDescription(&'static str);
+
+    struct Label {
+        text: &'static str,
+        patches: u32,
+    }
+
+    impl Describe for LabelDescription {
+        type Widget = Label;
+
+        fn create(&self) -> Label {
+            Label { text: self.0, patches: 0 }
+        }
+
+        fn patch(&self, _old: &Self, widget: &mut Label) {
+            widget.text = self.0;
+            widget.patches += 1;
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut list = DescribedList::new();
+
+        let stats = list.reconcile(vec![(1, LabelDescription("a")), (2, LabelDescription("b"))]);
+        assert_eq!(stats, Reconciliation { created: 2, patched: 0, removed: 0 });
+
+        // reorder, change one and add one.
+        let stats = list.reconcile(vec![
+            (2, LabelDescription("b")),
+            (3, LabelDescription("c")),
+            (1, LabelDescription("A")),
+        ]);
+        assert_eq!(stats, Reconciliation { created: 1, patched: 1, removed: 0 });
+        assert_eq!(list.widgets().map(|label| label.text).collect::<Vec<_>>(), vec!["b", "c", "A"]);
+        assert_eq!(list.get(&1).unwrap().patches, 1);
+        assert_eq!(list.get(&2).unwrap().patches, 0);
+
+        let stats = list.reconcile(vec![(3, LabelDescription("c"))]);
+        assert_eq!(stats, Reconciliation { created: 0, patched: 0, removed: 2 });
+        assert_eq!(list.len(), 1);
+    }
+}
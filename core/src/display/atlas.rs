@@ -0,0 +1,195 @@
+//! Packing of many small images (such as icons) into a single image resource.
+//!
+//! Each icon is drawn as a sub-rectangle of the atlas, so a toolbar full of icons only needs one texture.
+
+use {
+    super::{
+        DisplayListBuilder, Filter, GraphicsDisplay, ImageData, Point, RasterImageFormat,
+        RasterImageInfo, Rect, ResourceData, ResourceDescriptor, ResourceReference, SharedData,
+        Size,
+    },
+    crate::error,
+    std::{collections::HashMap, hash::Hash, sync::Arc},
+};
+
+/// Transparent gap between icons, so that filtering doesn't bleed neighbouring icons into each other.
+const PADDING: u32 = 1;
+
+/// Collects icons to be packed into an [`IconAtlas`](struct.IconAtlas.html).
+#[derive(Debug, Clone)]
+pub struct IconAtlasBuilder<K: Hash + Eq + Clone> {
+    icons: Vec<(K, (u32, u32), Vec<u8>)>,
+}
+
+impl<K: Hash + Eq + Clone> Default for IconAtlasBuilder<K> {
+    fn default() -> Self {
+        IconAtlasBuilder { icons: Vec::new() }
+    }
+}
+
+impl<K: Hash + Eq + Clone> IconAtlasBuilder<K> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds an icon from raw RGBA pixels (see [`RasterImageFormat::Rgba8`](../enum.RasterImageFormat.html#variant.Rgba8)).
+    ///
+    /// Returns an error if `pixels` doesn't match the size.
+    pub fn add(
+        &mut self,
+        key: K,
+        size: (u32, u32),
+        pixels: Vec<u8>,
+    ) -> Result<&mut Self, error::ResourceError> {
+        if pixels.len() != (size.0 * size.1 * 4) as usize {
+            return Err(error::ResourceError::InvalidData);
+        }
+
+        self.icons.push((key, size, pixels));
+        Ok(self)
+    }
+
+    /// Packs the icons and creates the atlas image resource.
+    pub fn build<D: Sized>(
+        self,
+        display: &mut dyn GraphicsDisplay<D>,
+    ) -> Result<IconAtlas<K>, error::ResourceError> {
+        let (info, pixels, regions) = self.pack();
+
+        let resource = display.new_resource(ResourceDescriptor::Image(ImageData::Raw(
+            ResourceData::Data(SharedData::RefCount(Arc::new(pixels))),
+            info,
+        )))?;
+
+        Ok(IconAtlas { resource, regions })
+    }
+
+    /// Shelf-packs the icons (tallest first) into rows of a roughly square image.
+    fn pack(mut self) -> (RasterImageInfo, Vec<u8>, HashMap<K, Rect>) {
+        self.icons.sort_by_key(|(_, size, _)| std::cmp::Reverse(size.1));
+
+        let area: u32 =
+            self.icons.iter().map(|(_, size, _)| (size.0 + PADDING) * (size.1 + PADDING)).sum();
+        let widest = self.icons.iter().map(|(_, size, _)| size.0 + PADDING).max().unwrap_or(0);
+        let width = ((area as f32).sqrt().ceil() as u32).max(widest).next_power_of_two();
+
+        let mut positions = Vec::with_capacity(self.icons.len());
+        let (mut x, mut y, mut row_height) = (0, 0, 0);
+        for (_, size, _) in &self.icons {
+            if x + size.0 + PADDING > width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+
+            positions.push((x, y));
+            x += size.0 + PADDING;
+            row_height = row_height.max(size.1 + PADDING);
+        }
+        let height = (y + row_height).max(1);
+
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        let mut regions = HashMap::with_capacity(self.icons.len());
+        for ((key, size, icon), (x, y)) in self.icons.into_iter().zip(positions) {
+            let row_len = (size.0 * 4) as usize;
+            for row in 0..size.1 {
+                let dst = (((y + row) * width + x) * 4) as usize;
+                let src = row as usize * row_len;
+                pixels[dst..dst + row_len].copy_from_slice(&icon[src..src + row_len]);
+            }
+
+            regions.insert(
+                key,
+                Rect::new(Point::new(x as _, y as _), Size::new(size.0 as _, size.1 as _)),
+            );
+        }
+
+        (
+            RasterImageInfo { size: (width, height), format: RasterImageFormat::Rgba8 },
+            pixels,
+            regions,
+        )
+    }
+}
+
+/// Many icons packed into a single image resource.
+#[derive(Debug, Clone)]
+pub struct IconAtlas<K: Hash + Eq + Clone> {
+    resource: ResourceReference,
+    regions: HashMap<K, Rect>,
+}
+
+impl<K: Hash + Eq + Clone> IconAtlas<K> {
+    /// Returns the image resource of the entire atlas.
+    #[inline]
+    pub fn resource(&self) -> ResourceReference {
+        self.resource
+    }
+
+    /// Returns the region of an icon within the atlas image.
+    #[inline]
+    pub fn region(&self, key: &K) -> Option<Rect> {
+        self.regions.get(key).copied()
+    }
+
+    /// Pushes an icon into `dst`, returning `false` if there's no such icon.
+    pub fn push_icon(
+        &self,
+        builder: &mut DisplayListBuilder,
+        key: &K,
+        dst: Rect,
+        filter: Option<Filter>,
+    ) -> bool {
+        if let Some(src) = self.region(key) {
+            builder.push_image(src, dst, self.resource, filter);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Removes the atlas image resource from the display.
+    pub fn remove<D: Sized>(self, display: &mut dyn GraphicsDisplay<D>) {
+        display.remove_resource(self.resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icon(size: (u32, u32), value: u8) -> Vec<u8> {
+        vec![value; (size.0 * size.1 * 4) as usize]
+    }
+
+    #[test]
+    fn test_atlas_packing() {
+        let mut builder = IconAtlasBuilder::new();
+        builder
+            .add("close", (16, 16), icon((16, 16), 1))
+            .unwrap()
+            .add("minimize", (16, 8), icon((16, 8), 2))
+            .unwrap()
+            .add("logo", (32, 32), icon((32, 32), 3))
+            .unwrap();
+        assert!(builder.add("broken", (4, 4), vec![0; 3]).is_err());
+
+        let (info, pixels, regions) = builder.pack();
+        assert_eq!(pixels.len(), (info.size.0 * info.size.1 * 4) as usize);
+        assert_eq!(regions.len(), 3);
+
+        let atlas = Rect::new(Point::zero(), Size::new(info.size.0 as _, info.size.1 as _));
+        let rects: Vec<_> = ["close", "minimize", "logo"].iter().map(|key| regions[key]).collect();
+        for (i, a) in rects.iter().enumerate() {
+            assert!(atlas.contains_rect(a));
+            assert!(!rects[i + 1..].iter().any(|b| a.intersects(b)));
+        }
+
+        // pixels were copied into the regions.
+        let close = regions["close"];
+        let at = |x: f32, y: f32| pixels[((y as u32 * info.size.0 + x as u32) * 4) as usize];
+        assert_eq!(at(close.min_x(), close.min_y()), 1);
+        assert_eq!(at(close.max_x() - 1.0, close.max_y() - 1.0), 1);
+        assert_eq!(at(regions["logo"].min_x(), regions["logo"].min_y()), 3);
+    }
+}
@@ -0,0 +1,1662 @@
+//! Building blocks for implementing [`GraphicsDisplay`](../trait.GraphicsDisplay.html) on top of a new graphics API.
+//!
+//! Most of a retained-mode backend is bookkeeping which has nothing to do with the graphics API itself;
+//! - [`ResourceRegistry`](struct.ResourceRegistry.html) hands out resource references and checks them when they're used.
+//! - [`resolve_commands`](fn.resolve_commands.html) pairs image and text items with their loaded resources.
+//! - [`StateStack`](struct.StateStack.html) tracks the transformation, clips and layers through `Save`/`Restore`.
+//! - [`CommandGroupStore`](struct.CommandGroupStore.html) keeps command groups in draw order and implements maintenance.
+//! - [`EffectCache`](struct.EffectCache.html) keeps the output of expensive filters across frames.
+//!
+//! What's left is translating the state changes and items into calls to the graphics API.
+//! [`RecordingDisplay`](struct.RecordingDisplay.html) is a complete backend built from these, which records what would be drawn
+//! rather than drawing it, and serves as a reference (as well as a display for tests).
+//!
+//! The [`conformance`](conformance/index.html) module (behind the `test-util` feature) checks that a backend
+//! behaves like the others, so it can be run from the tests of a third-party backend:
+//!
+//! ```ignore
+//! #[test]
+//! fn my_display_conforms() {
+//!     reclutch::display::backend::conformance::check_display(|| MyDisplay::new((256, 256)));
+//! }
+//! ```
+
+use {
+    super::*,
+    std::collections::{hash_map::Entry, HashMap},
+};
+
+/// Loaded resources of a backend, keyed by the references handed out for them.
+#[derive(Debug, Clone)]
+pub struct ResourceRegistry<R> {
+    resources: HashMap<u64, (ResourceReference, R)>,
+    next_id: u64,
+}
+
+impl<R> Default for ResourceRegistry<R> {
+    fn default() -> Self {
+        ResourceRegistry { resources: HashMap::new(), next_id: 0 }
+    }
+}
+
+impl<R> ResourceRegistry<R> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Stores the resource loaded from `descriptor`, returning a reference of the matching type.
+    pub fn insert(&mut self, descriptor: &ResourceDescriptor, resource: R) -> ResourceReference {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let reference = match descriptor {
+            ResourceDescriptor::Image(_) => ResourceReference::Image(id),
+            ResourceDescriptor::Font(_) => ResourceReference::Font(id),
+        };
+        self.resources.insert(id, (reference, resource));
+        reference
+    }
+
+    /// Returns the resource referenced by `reference`.
+    ///
+    /// Fails with [`InvalidResource`](../../error/enum.DisplayError.html#variant.InvalidResource) if the resource doesn't exist (e.g. it was removed),
+    /// or [`MismatchedResource`](../../error/enum.DisplayError.html#variant.MismatchedResource) if it's of another type (e.g. a font used as an image).
+    pub fn get(&self, reference: ResourceReference) -> Result<&R, error::DisplayError> {
+        match self.resources.get(&reference.id()) {
+            Some((stored, resource)) if *stored == reference => Ok(resource),
+            Some(_) => Err(error::DisplayError::MismatchedResource(reference.id())),
+            None => Err(error::DisplayError::InvalidResource(reference.id())),
+        }
+    }
+
+    /// Mutable version of [`get`](struct.ResourceRegistry.html#method.get), for example to reload a resource.
+    pub fn get_mut(&mut self, reference: ResourceReference) -> Result<&mut R, error::DisplayError> {
+        match self.resources.get_mut(&reference.id()) {
+            Some((stored, resource)) if *stored == reference => Ok(resource),
+            Some(_) => Err(error::DisplayError::MismatchedResource(reference.id())),
+            None => Err(error::DisplayError::InvalidResource(reference.id())),
+        }
+    }
+
+    /// Removes and returns a resource. References of the wrong type are ignored.
+    pub fn remove(&mut self, reference: ResourceReference) -> Option<R> {
+        match self.resources.get(&reference.id()) {
+            Some((stored, _)) if *stored == reference => {
+                self.resources.remove(&reference.id()).map(|(_, resource)| resource)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the references of all the stored resources, in no particular order.
+    pub fn references(&self) -> impl Iterator<Item = ResourceReference> + '_ {
+        self.resources.values().map(|(reference, _)| *reference)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// Removes all the resources, reporting them as leaked (for [`shutdown`](../trait.GraphicsDisplay.html#method.shutdown)).
+    pub fn take_leaks(&mut self) -> Vec<LeakedResource> {
+        let mut leaks: Vec<_> = self
+            .resources
+            .drain()
+            .map(|(_, (reference, _))| LeakedResource { reference, backtrace: None })
+            .collect();
+        leaks.sort_by_key(|leak| leak.reference.id());
+        leaks
+    }
+}
+
+/// A display command, with the resource it uses (if any) already looked up.
+pub enum ResolvedCommand<'a, R> {
+    /// A command which doesn't use a resource.
+    Command(&'a DisplayCommand),
+    /// An [`Image`](../enum.GraphicsDisplayItem.html#variant.Image) item.
+    Image { item: &'a GraphicsDisplayItem, filter: Option<&'a Filter>, image: &'a R },
+    /// A text item.
+    Text { item: &'a TextDisplayItem, filter: Option<&'a Filter>, font: &'a R },
+}
+
+/// Looks up the resources used by `commands`, failing for each command which references a missing or mismatched resource.
+pub fn resolve_commands<'a, R>(
+    commands: impl IntoIterator<Item = &'a DisplayCommand>,
+    resources: &'a ResourceRegistry<R>,
+) -> impl Iterator<Item = Result<ResolvedCommand<'a, R>, error::DisplayError>> {
+    commands.into_iter().map(move |command| {
+        Ok(match command {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(item @ GraphicsDisplayItem::Image { resource, .. }),
+                filter,
+            ) => ResolvedCommand::Image {
+                item,
+                filter: filter.as_ref(),
+                image: resources.get(*resource)?,
+            },
+            DisplayCommand::Item(DisplayItem::Text(item), filter) => ResolvedCommand::Text {
+                item,
+                filter: filter.as_ref(),
+                font: resources.get(item.font)?,
+            },
+            command => ResolvedCommand::Command(command),
+        })
+    })
+}
+
+/// How a command applied to a [`StateStack`](struct.StateStack.html) changed the draw state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StateChange {
+    /// The command draws something (an item, backdrop filter or clear) rather than changing the state.
+    Draw,
+    /// The state was saved.
+    Saved,
+    /// The state was saved and a layer with the given opacity begun.
+    LayerBegun(f32),
+    /// The state was restored; if it ended a layer, the layer opacity, meaning the layer should now be composited.
+    Restored(Option<f32>),
+    /// A restore without a matching save, which should be ignored.
+    Unbalanced,
+    /// The transformation changed.
+    Transformed,
+    /// A clip was added.
+    Clipped,
+}
+
+#[derive(Debug, Clone)]
+struct DrawState {
+    transform: Transform,
+    clips: Vec<(Transform, DisplayClip)>,
+    clip_bounds: Option<Rect>,
+    layer: Option<f32>,
+}
+
+/// The draw state (transformation, clips and layers) while executing display commands.
+///
+/// Backends which can't save and restore state natively (or need the device-space clip, e.g. for scissoring)
+/// apply every command to this and act on the returned [`StateChange`](enum.StateChange.html).
+#[derive(Debug, Clone)]
+pub struct StateStack {
+    current: DrawState,
+    saved: Vec<DrawState>,
+}
+
+impl Default for StateStack {
+    fn default() -> Self {
+        StateStack::new(Transform::identity(), None)
+    }
+}
+
+impl StateStack {
+    /// Creates a state with an initial transformation and clip (in device coordinates), such as the present cull.
+    pub fn new(transform: Transform, clip_bounds: Option<Rect>) -> Self {
+        StateStack {
+            current: DrawState { transform, clips: Vec::new(), clip_bounds, layer: None },
+            saved: Vec::new(),
+        }
+    }
+
+    /// Applies a command to the state.
+    pub fn apply(&mut self, command: &DisplayCommand) -> StateChange {
+        match command {
+            DisplayCommand::Item(..)
+            | DisplayCommand::BackdropFilter(..)
+            | DisplayCommand::Clear(_) => StateChange::Draw,
+            DisplayCommand::Clip(clip) => {
+                self.clip(clip.clone());
+                StateChange::Clipped
+            }
+            DisplayCommand::Save => {
+                self.save(None);
+                StateChange::Saved
+            }
+            DisplayCommand::SaveLayer(opacity) => {
+                self.save(Some(*opacity));
+                StateChange::LayerBegun(*opacity)
+            }
+            DisplayCommand::Restore => match self.restore() {
+                Some(layer) => StateChange::Restored(layer),
+                None => StateChange::Unbalanced,
+            },
+            DisplayCommand::Translate(offset) => {
+                self.concat(&Transform::create_translation(offset.x, offset.y));
+                StateChange::Transformed
+            }
+            DisplayCommand::Scale(scale) => {
+                self.concat(&Transform::create_scale(scale.x, scale.y));
+                StateChange::Transformed
+            }
+            DisplayCommand::Rotate(angle) => {
+                self.concat(&Transform::create_rotation(*angle));
+                StateChange::Transformed
+            }
+            DisplayCommand::Transform(transform) => {
+                self.concat(transform);
+                StateChange::Transformed
+            }
+        }
+    }
+
+    /// Applies `transform` before the current transformation (i.e. in the current coordinate space).
+    pub fn concat(&mut self, transform: &Transform) {
+        self.current.transform = transform.post_transform(&self.current.transform);
+    }
+
+    /// Adds a clip, in the current coordinate space.
+    pub fn clip(&mut self, clip: DisplayClip) {
+        let bounds = self.current.transform.transform_rect(&clip.bounds());
+        self.current.clip_bounds = Some(match self.current.clip_bounds {
+            // an empty clip intersects with nothing.
+            Some(current) => current.intersection(&bounds).unwrap_or_default(),
+            None => bounds,
+        });
+        self.current.clips.push((self.current.transform, clip));
+    }
+
+    fn save(&mut self, layer: Option<f32>) {
+        let mut state = self.current.clone();
+        state.layer = layer;
+        self.saved.push(std::mem::replace(&mut self.current, state));
+    }
+
+    fn restore(&mut self) -> Option<Option<f32>> {
+        let saved = self.saved.pop()?;
+        Some(std::mem::replace(&mut self.current, saved).layer)
+    }
+
+    /// Restores until only `depth` states are saved, returning the opacities of the layers which were ended (innermost first).
+    pub fn restore_to(&mut self, depth: usize) -> Vec<f32> {
+        let mut layers = Vec::new();
+        while self.saved.len() > depth {
+            layers.extend(self.restore().flatten());
+        }
+        layers
+    }
+
+    /// Number of saved states.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.saved.len()
+    }
+
+    /// Maps the current coordinate space to device coordinates.
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        self.current.transform
+    }
+
+    /// The active clips, each with the transformation which was current when it was added.
+    #[inline]
+    pub fn clips(&self) -> &[(Transform, DisplayClip)] {
+        &self.current.clips
+    }
+
+    /// Device-space bounds of the active clips, or `None` if unclipped.
+    #[inline]
+    pub fn clip_bounds(&self) -> Option<Rect> {
+        self.current.clip_bounds
+    }
+
+    /// Returns `false` if something with `bounds` (in the current coordinate space) is definitely clipped out.
+    pub fn is_visible(&self, bounds: Rect) -> bool {
+        self.current
+            .clip_bounds
+            .is_none_or(|clip| clip.intersects(&self.current.transform.transform_rect(&bounds)))
+    }
+}
+
+/// A command group, as stored by a [`CommandGroupStore`](struct.CommandGroupStore.html).
+#[derive(Clone)]
+pub struct StoredCommandGroup {
+    pub commands: Vec<DisplayCommand>,
+    /// Bounds of the commands, in the coordinate space of the commands.
+    pub bounds: Rect,
+    pub z_order: ZOrder,
+    /// Whether the state is restored after the command group is drawn.
+    pub protected: bool,
+    pub clip: Option<DisplayClip>,
+    pub transform: Transform,
+    pub layer: DisplayLayer,
+    /// Scale the command group is rendered at before being downsampled; `1.0` if it isn't supersampled.
+    pub render_scale: f32,
+    /// Name of the command group for debugging, if any.
+    pub label: Option<String>,
+    /// Display-space rectangle the command group is clipped to, regardless of its transformation and clip.
+    pub scissor: Option<Rect>,
+    transient: bool,
+    maintained: bool,
+    sequence: u64,
+}
+
+impl StoredCommandGroup {
+    /// Bounds of the command group in display coordinates, including the clip and scissor.
+    pub fn display_bounds(&self) -> Rect {
+        let bounds = match self.clip {
+            Some(ref clip) => self.bounds.intersection(&clip.bounds()).unwrap_or_default(),
+            None => self.bounds,
+        };
+        let bounds = self.transform.transform_rect(&bounds);
+        match self.scissor {
+            Some(scissor) => bounds.intersection(&scissor).unwrap_or_default(),
+            None => bounds,
+        }
+    }
+
+    /// Adds the region covered by the command group to `damage`.
+    pub fn add_damage(&self, damage: &mut Damage) {
+        damage.add_command_group(&self.commands, self.bounds, self.clip.as_ref(), &self.transform);
+    }
+}
+
+/// Command groups of a retained-mode backend, ordered the same way as in the Skia display;
+/// by layer, then z-order, then the order in which they were pushed (or last moved to the front).
+///
+/// Transient command groups are subject to maintenance; one which goes an entire frame without being
+/// maintained is removed in the following [`present`](struct.CommandGroupStore.html#method.present).
+///
+/// Every change which affects what's drawn is tracked as [`Damage`](../enum.Damage.html).
+#[derive(Clone, Default)]
+pub struct CommandGroupStore {
+    groups: HashMap<u64, StoredCommandGroup>,
+    layers: HashMap<DisplayLayer, (bool, f32)>,
+    damage: DamageTracker,
+    next_id: u64,
+    next_sequence: u64,
+    maintenance_count: u64,
+}
+
+impl CommandGroupStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: bool,
+        transient: bool,
+    ) -> Result<CommandGroupHandle, error::FontError> {
+        let bounds = display_list_bounds(commands)?;
+        let handle = CommandGroupHandle::new(self.next_id);
+        self.next_id += 1;
+
+        let sequence = self.sequence();
+        let group = StoredCommandGroup {
+            commands: commands.to_owned(),
+            bounds,
+            z_order,
+            protected,
+            clip: None,
+            transform: Transform::identity(),
+            layer: DisplayLayer::default(),
+            render_scale: 1.0,
+            label: None,
+            scissor: None,
+            transient,
+            maintained: true,
+            sequence,
+        };
+        self.damage.add_group(&group);
+        self.groups.insert(handle.id(), group);
+        Ok(handle)
+    }
+
+    #[inline]
+    pub fn get(&self, handle: CommandGroupHandle) -> Option<&StoredCommandGroup> {
+        self.groups.get(&handle.id())
+    }
+
+    /// Returns a command group, to change it directly.
+    ///
+    /// Changes made through this aren't tracked as damage; prefer [`set_clip`](struct.CommandGroupStore.html#method.set_clip),
+    /// [`set_transform`](struct.CommandGroupStore.html#method.set_transform) and [`set_layer`](struct.CommandGroupStore.html#method.set_layer).
+    #[inline]
+    pub fn get_mut(&mut self, handle: CommandGroupHandle) -> Option<&mut StoredCommandGroup> {
+        self.groups.get_mut(&handle.id())
+    }
+
+    /// Replaces the commands of a command group (keeping its clip, transformation, layer, render scale, scissor and label), which also maintains it.
+    ///
+    /// Unless `in_place`, the command group is moved to the front.
+    /// If the bounds of the commands can't be computed, the command group is left unchanged.
+    pub fn modify(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: bool,
+        transient: bool,
+        in_place: bool,
+    ) {
+        let bounds = match display_list_bounds(commands) {
+            Ok(bounds) => bounds,
+            Err(_) => return,
+        };
+        let sequence = self.sequence();
+        if let Some(group) = self.groups.get_mut(&handle.id()) {
+            self.damage.add_group(group);
+            group.commands = commands.to_owned();
+            group.bounds = bounds;
+            group.z_order = z_order;
+            group.protected = protected;
+            group.transient = transient;
+            group.maintained = true;
+            if !in_place {
+                group.sequence = sequence;
+            }
+            self.damage.add_group(group);
+        }
+    }
+
+    /// Keeps a command group alive through the next present, moving it to the front unless `in_place`.
+    pub fn maintain(&mut self, handle: CommandGroupHandle, in_place: bool) {
+        let sequence = self.sequence();
+        if let Some(group) = self.groups.get_mut(&handle.id()) {
+            group.maintained = true;
+            if !in_place {
+                group.sequence = sequence;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, handle: CommandGroupHandle) -> Option<StoredCommandGroup> {
+        let group = self.groups.remove(&handle.id())?;
+        self.damage.add_group(&group);
+        Some(group)
+    }
+
+    /// Changes a property of a command group, damaging the region it covered before and after.
+    fn change(&mut self, handle: CommandGroupHandle, f: impl FnOnce(&mut StoredCommandGroup)) {
+        if let Some(group) = self.groups.get_mut(&handle.id()) {
+            self.damage.add_group(group);
+            f(group);
+            self.damage.add_group(group);
+        }
+    }
+
+    pub fn set_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.change(handle, |group| group.clip = clip);
+    }
+
+    pub fn set_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.change(handle, |group| group.transform = transform);
+    }
+
+    pub fn set_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.change(handle, |group| group.layer = layer);
+    }
+
+    /// Sets the render scale of a command group, which damages it if the scale actually changed.
+    pub fn set_render_scale(&mut self, handle: CommandGroupHandle, scale: f32) {
+        if self.get(handle).is_some_and(|group| group.render_scale != scale) {
+            self.change(handle, |group| group.render_scale = scale);
+        }
+    }
+
+    /// Sets the scissor of a command group, which damages it if the scissor actually changed.
+    pub fn set_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        if self.get(handle).is_some_and(|group| group.scissor != scissor) {
+            self.change(handle, |group| group.scissor = scissor);
+        }
+    }
+
+    /// Sets the label of a command group, which doesn't change what's drawn.
+    pub fn set_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        if let Some(group) = self.get_mut(handle) {
+            group.label = label.map(str::to_owned);
+        }
+    }
+
+    pub fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        let state = self.layer_state(layer);
+        if state.0 != visible {
+            self.layers.insert(layer, (visible, state.1));
+            self.damage_layer(layer);
+        }
+    }
+
+    pub fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        let state = self.layer_state(layer);
+        let opacity = opacity.clamp(0.0, 1.0);
+        if state.1 != opacity {
+            self.layers.insert(layer, (state.0, opacity));
+            self.damage_layer(layer);
+        }
+    }
+
+    fn damage_layer(&mut self, layer: DisplayLayer) {
+        for group in self.groups.values().filter(|group| group.layer == layer) {
+            self.damage.add_group(group);
+        }
+    }
+
+    /// Marks the entire display as damaged, such as when it's resized.
+    #[inline]
+    pub fn invalidate_all(&mut self) {
+        self.damage.pending.invalidate_all();
+        self.damage.redraw.invalidate_all();
+    }
+
+    /// Returns (and resets) the damage since the last call.
+    ///
+    /// Command groups which weren't maintained count as damage, since the next present removes them.
+    pub fn take_damage(&mut self) -> Damage {
+        let mut damage = std::mem::take(&mut self.damage.pending);
+        for group in self.groups.values().filter(|group| group.transient && !group.maintained) {
+            group.add_damage(&mut damage);
+        }
+        damage
+    }
+
+    /// Returns (and resets) the damage since the last call, independently of [`take_damage`](struct.CommandGroupStore.html#method.take_damage).
+    ///
+    /// This is for the backend itself, to invalidate what it cached from previous frames (e.g. an [`EffectCache`](struct.EffectCache.html)),
+    /// whereas `take_damage` is for the application.
+    #[inline]
+    pub fn take_redraw_damage(&mut self) -> Damage {
+        std::mem::take(&mut self.damage.redraw)
+    }
+
+    /// Returns whether a layer is visible, and its opacity.
+    pub fn layer_state(&self, layer: DisplayLayer) -> (bool, f32) {
+        self.layers.get(&layer).copied().unwrap_or((true, 1.0))
+    }
+
+    /// Removes the transient command groups which weren't maintained since they were last aged, and ages the others,
+    /// such that they have to be maintained again to outlive the next call (or present).
+    pub fn expire(&mut self) {
+        self.expire_within(Some(Rect::zero()));
+    }
+
+    // command groups expiring within `cull` are about to be redrawn without them, so the application needn't redraw them again.
+    fn expire_within(&mut self, cull: Option<Rect>) {
+        self.maintenance_count += 1;
+        let mut expired = Vec::new();
+
+        for (&id, group) in &mut self.groups {
+            if group.transient {
+                if !group.maintained {
+                    expired.push(id);
+                }
+                group.maintained = false;
+            }
+        }
+
+        // the expired command groups are still on the surface, so they're redrawn by the next present.
+        for id in expired {
+            if let Some(group) = self.groups.remove(&id) {
+                if cull.is_none_or(|cull| cull.contains_rect(&group.display_bounds())) {
+                    group.add_damage(&mut self.damage.redraw);
+                } else {
+                    self.damage.add_group(&group);
+                }
+            }
+        }
+    }
+
+    /// Returns the number of times maintenance was applied (see [`GraphicsDisplay::maintenance_count`](../trait.GraphicsDisplay.html#method.maintenance_count)).
+    #[inline]
+    pub fn maintenance_count(&self) -> u64 {
+        self.maintenance_count
+    }
+
+    /// Applies maintenance to the command groups (see [`expire`](struct.CommandGroupStore.html#method.expire)),
+    /// returning the command groups within `cull` to draw, in order.
+    ///
+    /// Command groups entirely outside the cull are aged regardless, however they aren't drawn, and neither are those in hidden layers.
+    pub fn present(&mut self, cull: Option<Rect>) -> Vec<CommandGroupHandle> {
+        self.expire_within(cull);
+
+        let mut visible: Vec<_> = self
+            .groups
+            .iter()
+            .filter(|(_, group)| cull.is_none_or(|cull| cull.intersects(&group.display_bounds())))
+            .map(|(&id, group)| (group.layer, group.z_order, group.sequence, id))
+            .collect();
+
+        visible.sort_unstable();
+        visible
+            .into_iter()
+            .filter(|&(layer, ..)| self.layer_state(layer).0)
+            .map(|(.., id)| CommandGroupHandle::new(id))
+            .collect()
+    }
+
+    /// Returns the handles of all the command groups, in no particular order.
+    pub fn handles(&self) -> impl Iterator<Item = CommandGroupHandle> + '_ {
+        self.groups.keys().map(|&id| CommandGroupHandle::new(id))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Removes all the command groups, returning their handles (for [`shutdown`](../trait.GraphicsDisplay.html#method.shutdown)).
+    pub fn take_handles(&mut self) -> Vec<CommandGroupHandle> {
+        let mut handles: Vec<_> = self.groups.drain().map(|(id, _)| id).collect();
+        handles.sort_unstable();
+        handles.into_iter().map(CommandGroupHandle::new).collect()
+    }
+
+    fn sequence(&mut self) -> u64 {
+        self.next_sequence += 1;
+        self.next_sequence
+    }
+}
+
+// damage since the last `take_damage`, and since the last `take_redraw_damage`.
+#[derive(Debug, Clone, Copy, Default)]
+struct DamageTracker {
+    pending: Damage,
+    redraw: Damage,
+}
+
+impl DamageTracker {
+    fn add_group(&mut self, group: &StoredCommandGroup) {
+        group.add_damage(&mut self.pending);
+        group.add_damage(&mut self.redraw);
+    }
+}
+
+/// Identifies a filter applied to a region of a surface.
+///
+/// The parameters of the filter are compared bit for bit, so that the key can be hashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EffectKey {
+    filter: (u8, u32, u32),
+    region: [u32; 4],
+}
+
+impl EffectKey {
+    /// Creates a key for `filter`, reading from `region` (in display coordinates).
+    pub fn new(filter: Filter, region: Rect) -> Self {
+        let filter = match filter {
+            Filter::Blur(x, y) => (0, x.to_bits(), y.to_bits()),
+            Filter::Invert => (1, 0, 0),
+        };
+        let region =
+            [region.min_x(), region.min_y(), region.max_x(), region.max_y()].map(f32::to_bits);
+        EffectKey { filter, region }
+    }
+
+    /// Returns the region the filter reads from.
+    pub fn region(&self) -> Rect {
+        let [x0, y0, x1, y1] = self.region.map(f32::from_bits);
+        Rect::from_points([Point::new(x0, y0), Point::new(x1, y1)])
+    }
+}
+
+struct CachedEffect<T> {
+    content: u64,
+    // whether the region hasn't been damaged since the content was hashed.
+    valid: bool,
+    used: bool,
+    // whether differing content was cached under the same key within a frame (e.g. in two layers).
+    shared: bool,
+    value: T,
+}
+
+/// Caches the output of expensive filters (such as a blur of a static backdrop) across frames.
+///
+/// An output is reused if the filter, its region and its input content (as hashed by the backend) are all the same.
+/// The input is only hashed again once the region is damaged, so an idle frame costs nothing but a lookup.
+/// Backends which can't cheaply read back their surface (such as GPU backends) can return a new value from `content` each time,
+/// such that damaged outputs are always recomputed:
+///
+/// ```ignore
+/// // at the start of a present.
+/// self.effects.invalidate(self.groups.take_redraw_damage());
+///
+/// let blurred = self.effects.get_or_insert_with(
+///     EffectKey::new(filter, region),
+///     || hash_pixels(region),
+///     || apply_filter(region, filter),
+/// );
+///
+/// // at the end of a present.
+/// self.effects.trim();
+/// ```
+pub struct EffectCache<T> {
+    entries: HashMap<EffectKey, CachedEffect<T>>,
+}
+
+impl<T> Default for EffectCache<T> {
+    fn default() -> Self {
+        EffectCache { entries: HashMap::new() }
+    }
+}
+
+impl<T> EffectCache<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the cached output for `key` if its input is unchanged, otherwise caches (and returns) the output of `value`.
+    ///
+    /// `content` hashes the input, and is only called if the region was damaged since the output was cached
+    /// (or if the entry was already used in this frame).
+    pub fn get_or_insert_with(
+        &mut self,
+        key: EffectKey,
+        content: impl FnOnce() -> u64,
+        value: impl FnOnce() -> T,
+    ) -> &T {
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) => {
+                let entry = entry.into_mut();
+                if entry.valid && !entry.used && !entry.shared {
+                    entry.used = true;
+                    return &entry.value;
+                }
+
+                let content = content();
+                if entry.content != content {
+                    // differing content within a frame means the key is used for more than one input.
+                    entry.shared |= entry.used;
+                    entry.content = content;
+                    entry.value = value();
+                }
+                entry.valid = true;
+                entry.used = true;
+                &entry.value
+            }
+            Entry::Vacant(entry) => {
+                &entry
+                    .insert(CachedEffect {
+                        content: content(),
+                        valid: true,
+                        used: true,
+                        shared: false,
+                        value: value(),
+                    })
+                    .value
+            }
+        }
+    }
+
+    /// Marks the entries reading from a damaged region, so that their input is checked before they're reused.
+    pub fn invalidate(&mut self, damage: Damage) {
+        for (key, entry) in &mut self.entries {
+            entry.valid &= match damage {
+                Damage::Unchanged => true,
+                Damage::Region(region) => !key.region().intersects(&region),
+                Damage::Full => false,
+            };
+        }
+    }
+
+    /// Ends a frame, removing the entries which were invalidated and not reused since.
+    ///
+    /// Entries which weren't damaged are kept even if they weren't used, since they may have been outside the cull.
+    pub fn trim(&mut self) {
+        self.entries.retain(|_, entry| entry.valid || entry.used);
+        for entry in self.entries.values_mut() {
+            entry.used = false;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A drawing command recorded by a [`RecordingDisplay`](struct.RecordingDisplay.html).
+#[derive(Clone)]
+pub struct RecordedCommand {
+    pub group: CommandGroupHandle,
+    pub command: DisplayCommand,
+    /// Maps the coordinates of the command to display coordinates.
+    pub transform: Transform,
+    /// Display-space bounds of the clip (including the cull), if any.
+    pub clip: Option<Rect>,
+    /// Product of the opacities of the layers (both display layers and `SaveLayer`s) the command is drawn into.
+    pub opacity: f32,
+    /// Render scale of the command group.
+    pub render_scale: f32,
+    /// Label of the command group.
+    pub label: Option<String>,
+}
+
+/// A display which records the commands it would draw rather than drawing them.
+///
+/// Besides being the reference for backends built from this module, it's useful for testing widgets without a graphics context.
+#[derive(Default)]
+pub struct RecordingDisplay {
+    size: (u32, u32),
+    resources: ResourceRegistry<ResourceDescriptor>,
+    groups: CommandGroupStore,
+    frame: Vec<RecordedCommand>,
+    frames: u64,
+    errors: Vec<error::DisplayError>,
+}
+
+impl RecordingDisplay {
+    pub fn new(size: (u32, u32)) -> Self {
+        RecordingDisplay { size, ..Default::default() }
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the commands drawn by the last present.
+    #[inline]
+    pub fn last_frame(&self) -> &[RecordedCommand] {
+        &self.frame
+    }
+
+    /// Number of frames presented.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Returns the descriptor a resource was created from.
+    pub fn resource(&self, reference: ResourceReference) -> Option<&ResourceDescriptor> {
+        self.resources.get(reference).ok()
+    }
+
+    #[inline]
+    pub fn command_groups(&self) -> &CommandGroupStore {
+        &self.groups
+    }
+}
+
+/// Whether a command group pushed with `always_alive` has to be maintained to stay alive.
+///
+/// Like the Skia display, only groups which are explicitly `always_alive` are exempt from maintenance.
+pub fn is_transient(always_alive: Option<bool>) -> bool {
+    always_alive.unwrap_or(true)
+}
+
+impl GraphicsDisplay for RecordingDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = size;
+        self.groups.invalidate_all();
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        Ok(self.resources.insert(&descriptor, descriptor.clone()))
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.resources.remove(reference);
+    }
+
+    fn image_metadata(&self, reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        match self.resources.get(reference) {
+            Ok(ResourceDescriptor::Image(data)) => data.metadata().ok().flatten(),
+            _ => None,
+        }
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+        Ok(self.groups.push(
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+        )?)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.groups.get(handle).map(|group| &group.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            false,
+        );
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.groups.remove(handle).map(|group| group.commands)
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, false);
+    }
+
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.groups.set_clip(handle, clip);
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.groups.set_transform(handle, transform);
+    }
+
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.groups.set_layer(handle, layer);
+    }
+
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        self.groups.set_layer_visible(layer, visible);
+    }
+
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        self.groups.set_layer_opacity(layer, opacity);
+    }
+
+    fn set_command_group_render_scale(&mut self, handle: CommandGroupHandle, scale: f32) {
+        self.groups.set_render_scale(handle, scale);
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        self.groups.set_scissor(handle, scissor);
+    }
+
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        self.groups.set_label(handle, label);
+    }
+
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.groups.get(handle)?.label.as_deref()
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            true,
+        );
+    }
+
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, true);
+    }
+
+    fn expire_command_groups(&mut self) {
+        self.groups.expire();
+    }
+
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        Some(self.groups.maintenance_count())
+    }
+
+    fn before_exit(&mut self) {}
+
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+        LeakReport {
+            resources: self.resources.take_leaks(),
+            command_groups: self.groups.take_handles(),
+        }
+    }
+
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn take_damage(&mut self) -> Damage {
+        self.groups.take_damage()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+        let mut frame = Vec::new();
+        // unprotected command groups leak their state into the following command groups.
+        let mut state = StateStack::new(Transform::identity(), cull);
+
+        for handle in self.groups.present(cull) {
+            let group = match self.groups.get(handle) {
+                Some(group) => group,
+                None => continue,
+            };
+
+            let layer_opacity = self.groups.layer_state(group.layer).1;
+            let depth = state.depth();
+            // supersampled command groups are drawn offscreen, so their state never leaks.
+            let isolated = group.protected
+                || group.clip.is_some()
+                || group.scissor.is_some()
+                || group.transform != Transform::identity()
+                || group.render_scale != 1.0;
+            if isolated {
+                state.apply(&DisplayCommand::Save);
+            }
+            // the scissor is in display coordinates, so it's applied before the transformation.
+            if let Some(scissor) = group.scissor {
+                state.clip(DisplayClip::Rectangle { rect: scissor, antialias: false });
+            }
+            state.concat(&group.transform);
+            if let Some(ref clip) = group.clip {
+                state.clip(clip.clone());
+            }
+
+            let mut opacities = vec![layer_opacity];
+            let resolved = resolve_commands(&group.commands, &self.resources);
+            for (command, resolved) in group.commands.iter().zip(resolved) {
+                // the resources are only checked, since nothing is actually drawn.
+                resolved?;
+
+                match state.apply(command) {
+                    StateChange::Draw => {
+                        // unknown bounds (e.g. a font failed to load) are never culled.
+                        let visible = command
+                            .bounds()
+                            .ok()
+                            .flatten()
+                            .is_none_or(|bounds| state.is_visible(bounds));
+                        if visible {
+                            frame.push(RecordedCommand {
+                                group: handle,
+                                command: command.clone(),
+                                transform: state.transform(),
+                                clip: state.clip_bounds(),
+                                opacity: opacities.iter().product(),
+                                render_scale: group.render_scale,
+                                label: group.label.clone(),
+                            });
+                        }
+                    }
+                    StateChange::LayerBegun(opacity) => opacities.push(opacity),
+                    StateChange::Restored(Some(_)) => {
+                        opacities.pop();
+                    }
+                    _ => {}
+                }
+            }
+
+            if isolated {
+                state.restore_to(depth);
+            }
+        }
+
+        self.frame = frame;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// Checks which every [`GraphicsDisplay`](../../trait.GraphicsDisplay.html) implementation should pass.
+///
+/// Every check takes a freshly created display and panics with a description of the violated behaviour.
+/// Only behaviour which can be observed through the trait is checked, so pixel output has to be tested separately.
+#[cfg(any(test, feature = "test-util"))]
+pub mod conformance {
+    use super::*;
+
+    fn rectangle(x: f32, color: Color) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::new(x, 0.0), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(color.into()),
+            None,
+        );
+        builder.build()
+    }
+
+    /// Runs every check, each against a new display created by `new_display`.
+    pub fn check_display<G, F>(mut new_display: F)
+    where
+        G: GraphicsDisplay,
+        F: FnMut() -> G,
+    {
+        check_present(&mut new_display());
+        check_command_groups(&mut new_display());
+        check_maintenance(&mut new_display());
+        check_resources(&mut new_display());
+        check_damage(&mut new_display());
+    }
+
+    /// Presenting succeeds on an empty display, with and without a cull, and after resizing.
+    pub fn check_present(display: &mut dyn GraphicsDisplay) {
+        display.present(None).expect("failed to present an empty display");
+        display
+            .present(Some(Rect::new(Point::new(1.0, 1.0), Size::new(8.0, 8.0))))
+            .expect("failed to present an empty display with a cull");
+        display.resize((64, 32)).expect("failed to resize");
+        display.present(None).expect("failed to present after resizing");
+    }
+
+    /// Command groups can be retrieved, modified and removed through their handles.
+    pub fn check_command_groups(display: &mut dyn GraphicsDisplay) {
+        let red = rectangle(0.0, Color::new(1.0, 0.0, 0.0, 1.0));
+        let blue = rectangle(20.0, Color::new(0.0, 0.0, 1.0, 1.0));
+
+        let a = display.push_command_group(&red, ZOrder::default(), None, None).unwrap();
+        let b = display.push_command_group(&red, ZOrder(1), Some(false), None).unwrap();
+        assert_ne!(a, b, "command groups were given the same handle");
+        assert_eq!(
+            display.get_command_group(a).map(<[_]>::len),
+            Some(red.len()),
+            "pushed command group wasn't returned"
+        );
+
+        display.modify_command_group(a, &blue, ZOrder::default(), None, None);
+        display.modify_command_group_in_place(b, &blue, ZOrder(1), Some(false), None);
+        display.maintain_command_group(a);
+        display.maintain_command_group_in_place(b);
+        display.set_command_group_clip(
+            a,
+            Some(DisplayClip::Rectangle {
+                rect: Rect::new(Point::zero(), Size::new(5.0, 5.0)),
+                antialias: false,
+            }),
+        );
+        display.set_command_group_transform(b, Transform::create_translation(2.0, 2.0));
+        display.set_command_group_layer(b, DisplayLayer::Overlay);
+        display.set_layer_opacity(DisplayLayer::Overlay, 0.5);
+        display.present(None).expect("failed to present command groups");
+
+        assert!(display.get_command_group(a).is_some(), "modified command group was lost");
+        assert_eq!(
+            display.remove_command_group(a).map(|commands| commands.len()),
+            Some(blue.len()),
+            "removing a command group didn't return its commands"
+        );
+        assert!(display.get_command_group(a).is_none(), "removed command group was returned");
+        assert!(display.remove_command_group(a).is_none(), "command group was removed twice");
+        assert!(
+            display.get_command_group(b).is_some(),
+            "removing a command group affected another"
+        );
+
+        // handles which were never handed out are ignored.
+        let bogus = CommandGroupHandle::new(u64::MAX);
+        display.maintain_command_group(bogus);
+        display.set_command_group_clip(bogus, None);
+        assert!(display.get_command_group(bogus).is_none());
+        display.present(None).expect("failed to present after removing a command group");
+    }
+
+    /// Maintained command groups survive presents; others (pushed with the defaults) are removed once they've gone a frame without maintenance.
+    pub fn check_maintenance(display: &mut dyn GraphicsDisplay) {
+        let commands = rectangle(0.0, Color::new(0.0, 1.0, 0.0, 1.0));
+        let maintained =
+            display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+        let abandoned =
+            display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+
+        for frame in 0..3 {
+            display.present(None).expect("failed to present");
+            display.maintain_command_group(maintained);
+            assert!(
+                display.get_command_group(maintained).is_some(),
+                "maintained command group was removed (frame {})",
+                frame
+            );
+        }
+
+        assert!(
+            display.get_command_group(abandoned).is_none(),
+            "unmaintained command group wasn't removed"
+        );
+    }
+
+    /// Resources can be used until they're removed, after which presenting the commands using them fails.
+    pub fn check_resources(display: &mut dyn GraphicsDisplay) {
+        let image = display
+            .new_resource(ResourceDescriptor::Image(ImageData::Raw(
+                vec![255, 0, 255, 255].into(),
+                RasterImageInfo { size: (1, 1), format: RasterImageFormat::Rgba8 },
+            )))
+            .expect("failed to create a raw image");
+        assert!(
+            matches!(image, ResourceReference::Image(_)),
+            "image resource was given a font reference"
+        );
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_image(None, Rect::new(Point::zero(), Size::new(4.0, 4.0)), image, None);
+        let commands = builder.build();
+
+        let group =
+            display.push_command_group(&commands, ZOrder::default(), None, Some(false)).unwrap();
+        display.present(None).expect("failed to present an image");
+
+        display.remove_resource(image);
+        assert!(display.present(None).is_err(), "presenting a removed image didn't fail");
+
+        display.remove_command_group(group);
+        display.present(None).expect("failed to present after removing the image");
+    }
+
+    /// Changes to command groups are covered by the reported damage (displays which don't track damage always report everything).
+    pub fn check_damage(display: &mut dyn GraphicsDisplay) {
+        let covers = |damage: Damage, rect: Rect| match damage {
+            Damage::Unchanged => false,
+            Damage::Region(region) => region.contains_rect(&rect),
+            Damage::Full => true,
+        };
+        let first = Rect::new(Point::zero(), Size::new(10.0, 10.0));
+        let moved = Rect::new(Point::new(20.0, 0.0), Size::new(10.0, 10.0));
+        let commands = rectangle(0.0, Color::new(0.0, 0.0, 0.0, 1.0));
+
+        let group = display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+        assert!(covers(display.take_damage(), first), "pushing didn't damage the command group");
+        display.present_damage().expect("failed to present the damage");
+
+        display.modify_command_group(
+            group,
+            &rectangle(20.0, Color::new(0.0, 0.0, 0.0, 1.0)),
+            ZOrder::default(),
+            None,
+            None,
+        );
+        let damage = display.take_damage();
+        assert!(
+            covers(damage, first) && covers(damage, moved),
+            "modifying didn't damage both the old and new commands"
+        );
+
+        display.set_command_group_transform(group, Transform::create_translation(0.0, 20.0));
+        assert!(
+            covers(display.take_damage(), moved.translate(Vector::new(0.0, 20.0))),
+            "transforming didn't damage the command group"
+        );
+
+        display.remove_command_group(group);
+        assert!(
+            covers(display.take_damage(), moved.translate(Vector::new(0.0, 20.0))),
+            "removing didn't damage the command group"
+        );
+        display.present_damage().expect("failed to present the damage");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_display_conformance() {
+        conformance::check_display(|| RecordingDisplay::new((32, 32)));
+    }
+
+    #[test]
+    fn test_resource_registry() {
+        let mut registry = ResourceRegistry::new();
+        let descriptor = ResourceDescriptor::Image(ImageData::Encoded(vec![].into()));
+        let image = registry.insert(&descriptor, "image");
+
+        assert_eq!(registry.get(image).ok(), Some(&"image"));
+        assert!(matches!(
+            registry.get(ResourceReference::Font(image.id())),
+            Err(error::DisplayError::MismatchedResource(_))
+        ));
+        assert_eq!(registry.remove(ResourceReference::Font(image.id())), None);
+        assert_eq!(registry.remove(image), Some("image"));
+        assert!(matches!(registry.get(image), Err(error::DisplayError::InvalidResource(_))));
+    }
+
+    #[test]
+    fn test_state_stack() {
+        let clip = DisplayClip::Rectangle {
+            rect: Rect::new(Point::zero(), Size::new(10.0, 10.0)),
+            antialias: false,
+        };
+        let mut state = StateStack::default();
+
+        assert_eq!(state.apply(&DisplayCommand::Restore), StateChange::Unbalanced);
+        assert_eq!(state.apply(&DisplayCommand::SaveLayer(0.5)), StateChange::LayerBegun(0.5));
+        assert_eq!(
+            state.apply(&DisplayCommand::Translate(Vector::new(5.0, 5.0))),
+            StateChange::Transformed
+        );
+        assert_eq!(
+            state.apply(&DisplayCommand::Scale(Vector::new(2.0, 2.0))),
+            StateChange::Transformed
+        );
+        assert_eq!(state.apply(&DisplayCommand::Clip(clip)), StateChange::Clipped);
+
+        // transformations apply in the order of the commands, innermost first.
+        assert_eq!(state.transform().transform_point(Point::new(1.0, 1.0)), Point::new(7.0, 7.0));
+        assert_eq!(
+            state.clip_bounds(),
+            Some(Rect::new(Point::new(5.0, 5.0), Size::new(20.0, 20.0)))
+        );
+        assert!(!state.is_visible(Rect::new(Point::new(11.0, 0.0), Size::new(5.0, 5.0))));
+
+        assert_eq!(state.apply(&DisplayCommand::Save), StateChange::Saved);
+        assert_eq!(
+            state.apply(&DisplayCommand::Transform(Transform::create_translation(-2.0, 0.0))),
+            StateChange::Transformed
+        );
+        assert_eq!(state.transform().transform_point(Point::new(1.0, 1.0)), Point::new(3.0, 7.0));
+        assert_eq!(state.apply(&DisplayCommand::Restore), StateChange::Restored(None));
+        assert_eq!(state.transform().transform_point(Point::new(1.0, 1.0)), Point::new(7.0, 7.0));
+
+        assert_eq!(state.apply(&DisplayCommand::Save), StateChange::Saved);
+        assert_eq!(state.restore_to(0), vec![0.5]);
+        assert_eq!(state.transform(), Transform::identity());
+        assert_eq!(state.clip_bounds(), None);
+        assert!(state.clips().is_empty());
+    }
+
+    #[test]
+    fn test_recording_display() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+
+        let front = display.push_command_group(&commands, ZOrder(1), None, Some(false)).unwrap();
+        let back = display.push_command_group(&commands, ZOrder(0), None, Some(false)).unwrap();
+        let hidden = display.push_command_group(&commands, ZOrder(0), None, Some(false)).unwrap();
+        display.set_command_group_transform(front, Transform::create_translation(50.0, 0.0));
+        display.set_command_group_layer(back, DisplayLayer::Overlay);
+        display.set_layer_opacity(DisplayLayer::Overlay, 0.5);
+        display.set_command_group_transform(hidden, Transform::create_translation(80.0, 80.0));
+
+        display.present(Some(Rect::new(Point::zero(), Size::new(70.0, 70.0)))).unwrap();
+
+        let frame = display.last_frame();
+        // ordered by layer, then z-order; the command group outside the cull is skipped.
+        assert_eq!(
+            frame.iter().map(|command| command.group).collect::<Vec<_>>(),
+            vec![front, back]
+        );
+        assert_eq!(frame[0].transform, Transform::create_translation(50.0, 0.0));
+        assert_eq!(frame[0].opacity, 1.0);
+        assert_eq!(frame[1].opacity, 0.5);
+        assert_eq!(frame[1].clip, Some(Rect::new(Point::zero(), Size::new(70.0, 70.0))));
+
+        let leaks = display.shutdown();
+        assert_eq!(leaks.command_groups, vec![front, back, hidden]);
+    }
+
+    #[test]
+    fn test_command_group_labels() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+
+        let mut titlebar = CommandGroup::with_label("titlebar");
+        let mut body = CommandGroup::new();
+        titlebar.push(&mut display, &commands, ZOrder(1), None, None);
+        body.push(&mut display, &commands, ZOrder(0), None, None);
+        let titlebar_handle = titlebar.handle().unwrap();
+        assert_eq!(display.command_group_label(titlebar_handle), Some("titlebar"));
+        assert_eq!(display.command_group_label(body.handle().unwrap()), None);
+
+        display.present(None).unwrap();
+        display.take_damage();
+
+        // labels are given to the display on the next push, without a repaint, and don't damage anything.
+        body.set_label("body");
+        titlebar.push(&mut display, &commands, ZOrder(1), None, None);
+        body.push(&mut display, &commands, ZOrder(0), None, None);
+        assert_eq!(display.take_damage(), Damage::Unchanged);
+
+        display.present(None).unwrap();
+        let labels: Vec<_> =
+            display.last_frame().iter().map(|command| command.label.as_deref()).collect();
+        assert_eq!(labels, vec![Some("body"), Some("titlebar")]);
+
+        // modifying the commands keeps the label.
+        titlebar.repaint();
+        titlebar.push(&mut display, &commands, ZOrder(1), None, None);
+        assert_eq!(display.command_group_label(titlebar_handle), Some("titlebar"));
+    }
+
+    #[test]
+    fn test_damage_tracking() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut builder = DisplayListBuilder::new();
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(10.0, 10.0));
+        builder.push_rectangle(
+            rect,
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+
+        let group = display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+        assert_eq!(display.present_damage().unwrap(), Damage::Region(rect));
+        assert_eq!(display.last_frame().len(), 1);
+
+        // maintaining doesn't damage anything.
+        display.maintain_command_group(group);
+        assert_eq!(display.present_damage().unwrap(), Damage::Unchanged);
+        assert_eq!(display.frames(), 1);
+
+        // hiding the layer damages its command groups.
+        display.set_layer_visible(DisplayLayer::Content, false);
+        display.set_layer_visible(DisplayLayer::Content, false);
+        assert_eq!(display.take_damage(), Damage::Region(rect));
+        display.set_layer_visible(DisplayLayer::Content, true);
+
+        display.take_damage();
+
+        // the command group wasn't maintained since the last present, which is damage until presenting removes it.
+        assert_eq!(display.take_damage(), Damage::Region(rect));
+        assert_eq!(display.present_damage().unwrap(), Damage::Region(rect));
+        assert!(display.get_command_group(group).is_none());
+        assert_eq!(display.take_damage(), Damage::Unchanged);
+
+        // command groups expiring outside the cull are damaged for the next present.
+        let group = display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+        display.present(None).unwrap();
+        display.take_damage();
+        display.present(Some(Rect::new(Point::new(50.0, 50.0), Size::new(10.0, 10.0)))).unwrap();
+        assert!(display.get_command_group(group).is_none());
+        assert_eq!(display.take_damage(), Damage::Region(rect));
+
+        display.resize((50, 50)).unwrap();
+        assert_eq!(display.take_damage(), Damage::Full);
+    }
+
+    #[test]
+    fn test_render_scale() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+
+        let mut zoomed = CommandGroup::new();
+        zoomed.set_render_scale(4.0);
+        let mut plain = CommandGroup::new();
+        {
+            // the whole subtree is supersampled, but a command group can ask for more.
+            let mut subtree =
+                TransformedDisplay::new(&mut display, Transform::identity()).with_render_scale(2.0);
+            zoomed.push(&mut subtree, &commands, ZOrder(1), None, None);
+            plain.push(&mut subtree, &commands, ZOrder(0), None, None);
+        }
+        display.take_damage();
+
+        // setting the same scale again doesn't damage anything.
+        display.set_command_group_render_scale(plain.handle().unwrap(), 2.0);
+        assert!(display.take_damage().is_unchanged());
+
+        display.present(None).unwrap();
+        let scales =
+            display.last_frame().iter().map(|command| command.render_scale).collect::<Vec<_>>();
+        assert_eq!(scales, vec![2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_scissor() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+
+        let viewport = Rect::new(Point::zero(), Size::new(50.0, 50.0));
+        let mut inside = CommandGroup::new();
+        let mut outside = CommandGroup::new();
+        {
+            // nested scissors intersect, regardless of the transformation of the inner wrapper.
+            let mut container =
+                TransformedDisplay::new(&mut display, Transform::identity()).with_scissor(viewport);
+            let mut content =
+                TransformedDisplay::new(&mut container, Transform::create_translation(20.0, 20.0))
+                    .with_scissor(Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 100.0)));
+            inside.push(&mut content, &commands, ZOrder::default(), None, Some(false));
+
+            let mut scrolled =
+                TransformedDisplay::new(&mut content, Transform::create_translation(40.0, 40.0));
+            outside.push(&mut scrolled, &commands, ZOrder::default(), None, Some(false));
+        }
+        display.take_damage();
+
+        // setting the same scissor again doesn't damage anything.
+        display.set_command_group_scissor(
+            inside.handle().unwrap(),
+            Some(Rect::new(Point::new(10.0, 10.0), Size::new(40.0, 40.0))),
+        );
+        assert!(display.take_damage().is_unchanged());
+
+        display.present(None).unwrap();
+        let frame = display.last_frame();
+        assert_eq!(frame.len(), 1);
+        assert_eq!(frame[0].group, inside.handle().unwrap());
+        assert_eq!(frame[0].clip, Some(Rect::new(Point::new(10.0, 10.0), Size::new(40.0, 40.0))));
+
+        // without the scissor, the command group isn't clipped out.
+        display.set_command_group_scissor(outside.handle().unwrap(), None);
+        display.present(None).unwrap();
+        assert_eq!(display.last_frame().len(), 2);
+    }
+
+    #[test]
+    fn test_command_group_transform() {
+        let mut display = RecordingDisplay::new((100, 100));
+        let mut command_group = CommandGroup::new();
+        command_group.set_transform(Transform::create_scale(2.0, 2.0));
+
+        let offset = Transform::create_translation(10.0, 0.0);
+        let composed = Transform::create_scale(2.0, 2.0).post_transform(&offset);
+        for repaint in [true, false, true] {
+            if repaint {
+                command_group.repaint();
+            }
+            // the transformation of the command group survives the wrapper pushing or maintaining it.
+            let mut parent = TransformedDisplay::new(&mut display, offset);
+            command_group.push(&mut parent, &[], ZOrder::default(), None, None);
+            let handle = command_group.handle().unwrap();
+            assert_eq!(display.command_groups().get(handle).unwrap().transform, composed);
+        }
+
+        command_group.set_transform(Transform::identity());
+        command_group.push(&mut display, &[], ZOrder::default(), None, None);
+        let handle = command_group.handle().unwrap();
+        assert_eq!(display.command_groups().get(handle).unwrap().transform, Transform::identity());
+    }
+
+    #[test]
+    fn test_effect_cache() {
+        let region = Rect::new(Point::new(10.0, 10.0), Size::new(10.0, 10.0));
+        let key = EffectKey::new(Filter::Blur(5.0, 5.0), region);
+        assert_eq!(key.region(), region);
+        assert_ne!(key, EffectKey::new(Filter::Blur(5.0, 6.0), region));
+
+        let mut cache = EffectCache::new();
+        let (hashes, filters) = (std::cell::Cell::new(0), std::cell::Cell::new(0));
+        let frame = |cache: &mut EffectCache<u64>, damage: Damage, content: u64| {
+            cache.invalidate(damage);
+            let value = *cache.get_or_insert_with(
+                key,
+                || {
+                    hashes.set(hashes.get() + 1);
+                    content
+                },
+                || {
+                    filters.set(filters.get() + 1);
+                    content * 2
+                },
+            );
+            cache.trim();
+            value
+        };
+
+        assert_eq!(frame(&mut cache, Damage::Unchanged, 1), 2);
+        assert_eq!((hashes.get(), filters.get()), (1, 1));
+
+        // idle frames don't even hash the input.
+        assert_eq!(frame(&mut cache, Damage::Unchanged, 1), 2);
+        let elsewhere = Rect::new(Point::new(50.0, 50.0), Size::new(10.0, 10.0));
+        assert_eq!(frame(&mut cache, Damage::Region(elsewhere), 1), 2);
+        assert_eq!((hashes.get(), filters.get()), (1, 1));
+
+        // damage which didn't change the input only costs a hash.
+        assert_eq!(frame(&mut cache, Damage::Region(region), 1), 2);
+        assert_eq!((hashes.get(), filters.get()), (2, 1));
+
+        assert_eq!(frame(&mut cache, Damage::Full, 3), 6);
+        assert_eq!((hashes.get(), filters.get()), (3, 2));
+
+        // damaged entries which aren't used are dropped at the end of the frame.
+        cache.invalidate(Damage::Full);
+        cache.trim();
+        assert!(cache.is_empty());
+    }
+}
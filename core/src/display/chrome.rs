@@ -0,0 +1,362 @@
+//! Standard window chrome (titlebar, caption buttons, border and shadow) for windows and panels drawing their own decorations.
+//!
+//! The chrome is laid out once per resize with [`ChromeLayout::new`](struct.ChromeLayout.html#method.new),
+//! which is also used to hit test the caption buttons, and then pushed into a display list:
+//!
+//! ```ignore
+//! let layout = ChromeLayout::new(bounds, &theme, &[CaptionButton::Minimize, CaptionButton::Maximize, CaptionButton::Close]);
+//!
+//! let mut builder = DisplayListBuilder::new();
+//! chrome::push_shadow(&mut builder, layout.frame, &theme);
+//! // draw the content within `layout.content`
+//! chrome::push_titlebar(&mut builder, &layout, &theme, &self.chrome_state, Some(title));
+//! chrome::push_border(&mut builder, layout.frame, &theme);
+//! ```
+
+use super::{
+    Color, DisplayListBuilder, Filter, FontInfo, GraphicsDisplayPaint, GraphicsDisplayStroke,
+    Point, Rect, ResourceReference, Size, TextDisplayItem, Vector,
+};
+
+/// A drop shadow beneath the window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+    pub color: Color,
+    /// Blur radius (sigma) of the shadow.
+    pub blur: f32,
+    pub offset: Vector,
+    /// How far the shadow extends beyond the window (before blurring).
+    pub spread: f32,
+}
+
+/// Colors and metrics of the chrome.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromeTheme {
+    pub titlebar_height: f32,
+    pub titlebar_color: Color,
+    /// Titlebar color of inactive (unfocused) windows.
+    pub inactive_titlebar_color: Color,
+    /// If set, blurs whatever is behind the titlebar by this radius (for translucent titlebars).
+    pub titlebar_backdrop_blur: Option<f32>,
+    pub title_color: Color,
+    pub title_size: f32,
+    /// Padding between the start of the titlebar and the title.
+    pub padding: f32,
+    pub button_width: f32,
+    /// Color of the glyphs drawn on caption buttons.
+    pub glyph_color: Color,
+    /// Size of the glyphs drawn on caption buttons.
+    pub glyph_size: f32,
+    pub button_hover_color: Color,
+    pub button_pressed_color: Color,
+    /// Hover color of the close button, which is typically red.
+    pub close_hover_color: Color,
+    pub border_color: Color,
+    pub border_thickness: f32,
+    /// Corner radius of the window; the titlebar is rounded at the top and the border along the whole window.
+    pub corner_radius: f32,
+    pub shadow: Option<Shadow>,
+}
+
+impl Default for ChromeTheme {
+    fn default() -> Self {
+        ChromeTheme {
+            titlebar_height: 30.0,
+            titlebar_color: Color::new(1.0, 1.0, 1.0, 1.0),
+            inactive_titlebar_color: Color::new(0.95, 0.95, 0.95, 1.0),
+            titlebar_backdrop_blur: None,
+            title_color: Color::new(0.0, 0.0, 0.0, 1.0),
+            title_size: 14.0,
+            padding: 10.0,
+            button_width: 46.0,
+            glyph_color: Color::new(0.0, 0.0, 0.0, 1.0),
+            glyph_size: 10.0,
+            button_hover_color: Color::new(0.0, 0.0, 0.0, 0.1),
+            button_pressed_color: Color::new(0.0, 0.0, 0.0, 0.2),
+            close_hover_color: Color::new(0.91, 0.07, 0.14, 1.0),
+            border_color: Color::new(0.0, 0.0, 0.0, 0.3),
+            border_thickness: 1.0,
+            corner_radius: 0.0,
+            shadow: Some(Shadow {
+                color: Color::new(0.0, 0.0, 0.0, 0.3),
+                blur: 8.0,
+                offset: Vector::new(0.0, 2.0),
+                spread: 0.0,
+            }),
+        }
+    }
+}
+
+/// A button at the end of the titlebar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaptionButton {
+    Minimize,
+    Maximize,
+    /// Shown instead of `Maximize` for maximized windows.
+    Restore,
+    Close,
+}
+
+/// The interactive state of the chrome.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromeState {
+    /// Whether the window is focused.
+    pub active: bool,
+    pub hovered: Option<CaptionButton>,
+    pub pressed: Option<CaptionButton>,
+}
+
+impl Default for ChromeState {
+    fn default() -> Self {
+        ChromeState { active: true, hovered: None, pressed: None }
+    }
+}
+
+/// The title of a window, as drawn in the titlebar.
+#[derive(Debug, Clone, Copy)]
+pub struct Title<'a> {
+    pub text: &'a str,
+    pub font: ResourceReference,
+    pub font_info: &'a FontInfo,
+}
+
+/// Placement of the parts of the chrome within a window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChromeLayout {
+    /// The entire window.
+    pub frame: Rect,
+    pub titlebar: Rect,
+    /// The caption buttons, from left to right.
+    pub buttons: Vec<(CaptionButton, Rect)>,
+    /// The area below the titlebar.
+    pub content: Rect,
+}
+
+impl ChromeLayout {
+    /// Lays out the chrome of a window occupying `frame`, with `buttons` (given from left to right) at the end of the titlebar.
+    pub fn new(frame: Rect, theme: &ChromeTheme, buttons: &[CaptionButton]) -> Self {
+        let titlebar_height = theme.titlebar_height.min(frame.size.height);
+        let titlebar = Rect::new(frame.origin, Size::new(frame.size.width, titlebar_height));
+        let content = Rect::new(
+            Point::new(frame.origin.x, frame.origin.y + titlebar_height),
+            Size::new(frame.size.width, frame.size.height - titlebar_height),
+        );
+
+        let first_button = titlebar.max_x() - theme.button_width * buttons.len() as f32;
+        let buttons = buttons
+            .iter()
+            .enumerate()
+            .map(|(i, button)| {
+                (
+                    *button,
+                    Rect::new(
+                        Point::new(first_button + theme.button_width * i as f32, titlebar.min_y()),
+                        Size::new(theme.button_width, titlebar_height),
+                    ),
+                )
+            })
+            .collect();
+
+        ChromeLayout { frame, titlebar, buttons, content }
+    }
+
+    /// Returns the caption button at `point`, if any.
+    pub fn button_at(&self, point: Point) -> Option<CaptionButton> {
+        self.buttons.iter().find(|(_, rect)| rect.contains(point)).map(|(button, _)| *button)
+    }
+
+    /// Returns `true` if `point` is on the part of the titlebar which drags the window (i.e. not on a caption button).
+    pub fn is_drag_area(&self, point: Point) -> bool {
+        self.titlebar.contains(point) && self.button_at(point).is_none()
+    }
+}
+
+fn fill(color: Color) -> GraphicsDisplayPaint {
+    GraphicsDisplayPaint::Fill(color.into())
+}
+
+fn glyph_stroke(theme: &ChromeTheme) -> GraphicsDisplayStroke {
+    GraphicsDisplayStroke { color: theme.glyph_color.into(), thickness: 1.0, ..Default::default() }
+}
+
+/// Pushes the drop shadow of a window occupying `frame`, if the theme has one.
+///
+/// This should be pushed before the window (including its content) is drawn.
+pub fn push_shadow(builder: &mut DisplayListBuilder, frame: Rect, theme: &ChromeTheme) {
+    if let Some(shadow) = theme.shadow {
+        builder.push_round_rectangle(
+            frame.translate(shadow.offset).inflate(shadow.spread, shadow.spread),
+            [theme.corner_radius; 4],
+            fill(shadow.color),
+            Some(Filter::Blur(shadow.blur, shadow.blur)),
+        );
+    }
+}
+
+/// Pushes the glyph of a caption button, centered in `rect`.
+pub fn push_caption_glyph(
+    builder: &mut DisplayListBuilder,
+    button: CaptionButton,
+    rect: Rect,
+    theme: &ChromeTheme,
+) {
+    let half = theme.glyph_size / 2.0;
+    let center = rect.center();
+    let glyph = Rect::new(center - Vector::new(half, half), Size::new(half * 2.0, half * 2.0));
+    let stroke = glyph_stroke(theme);
+
+    match button {
+        CaptionButton::Minimize => builder.push_line(
+            Point::new(glyph.min_x(), center.y),
+            Point::new(glyph.max_x(), center.y),
+            stroke,
+            None,
+        ),
+        CaptionButton::Maximize => {
+            builder.push_rectangle(glyph, GraphicsDisplayPaint::Stroke(stroke), None)
+        }
+        CaptionButton::Restore => {
+            let offset = theme.glyph_size / 5.0;
+            let front = Rect::new(
+                Point::new(glyph.min_x(), glyph.min_y() + offset),
+                Size::new(glyph.size.width - offset, glyph.size.height - offset),
+            );
+            let back = front.translate(Vector::new(offset, -offset));
+            // only the parts of the back window which aren't covered by the front window.
+            builder.push_line(
+                back.origin,
+                Point::new(back.max_x(), back.min_y()),
+                stroke.clone(),
+                None,
+            );
+            builder.push_line(
+                Point::new(back.max_x(), back.min_y()),
+                Point::new(back.max_x(), back.max_y()),
+                stroke.clone(),
+                None,
+            );
+            builder.push_rectangle(front, GraphicsDisplayPaint::Stroke(stroke), None);
+        }
+        CaptionButton::Close => {
+            builder.push_line(glyph.origin, glyph.max(), stroke.clone(), None);
+            builder.push_line(
+                Point::new(glyph.max_x(), glyph.min_y()),
+                Point::new(glyph.min_x(), glyph.max_y()),
+                stroke,
+                None,
+            );
+        }
+    }
+}
+
+/// Pushes the titlebar; its background, the title (if any) and the caption buttons.
+pub fn push_titlebar(
+    builder: &mut DisplayListBuilder,
+    layout: &ChromeLayout,
+    theme: &ChromeTheme,
+    state: &ChromeState,
+    title: Option<Title<'_>>,
+) {
+    let radii = [theme.corner_radius, theme.corner_radius, 0.0, 0.0];
+
+    if let Some(blur) = theme.titlebar_backdrop_blur {
+        builder.push_round_rectangle_backdrop(layout.titlebar, radii, Filter::Blur(blur, blur));
+    }
+
+    builder.push_round_rectangle(
+        layout.titlebar,
+        radii,
+        fill(if state.active { theme.titlebar_color } else { theme.inactive_titlebar_color }),
+        None,
+    );
+
+    if let Some(title) = title {
+        builder.push_text(
+            TextDisplayItem {
+                text: title.text.to_string().into(),
+                font: title.font,
+                font_info: title.font_info.clone(),
+                size: theme.title_size,
+                bottom_left: Point::new(
+                    layout.titlebar.min_x() + theme.padding,
+                    layout.titlebar.center().y + theme.title_size * 0.35,
+                ),
+                color: theme.title_color.into(),
+            },
+            None,
+        );
+    }
+
+    for (button, rect) in &layout.buttons {
+        let background = if state.pressed == Some(*button) {
+            Some(theme.button_pressed_color)
+        } else if state.hovered == Some(*button) {
+            Some(if *button == CaptionButton::Close {
+                theme.close_hover_color
+            } else {
+                theme.button_hover_color
+            })
+        } else {
+            None
+        };
+
+        if let Some(background) = background {
+            builder.push_rectangle(*rect, fill(background), None);
+        }
+
+        push_caption_glyph(builder, *button, *rect, theme);
+    }
+}
+
+/// Pushes the border of a window occupying `frame`.
+///
+/// This should be pushed after the content, such that it isn't covered by it.
+pub fn push_border(builder: &mut DisplayListBuilder, frame: Rect, theme: &ChromeTheme) {
+    if theme.border_thickness <= 0.0 {
+        return;
+    }
+
+    let inset = theme.border_thickness / 2.0;
+    builder.push_round_rectangle(
+        frame.inflate(-inset, -inset),
+        [theme.corner_radius; 4],
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            color: theme.border_color.into(),
+            thickness: theme.border_thickness,
+            ..Default::default()
+        }),
+        None,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chrome_layout() {
+        let theme = ChromeTheme::default();
+        let frame = Rect::new(Point::new(10.0, 10.0), Size::new(300.0, 200.0));
+        let layout =
+            ChromeLayout::new(frame, &theme, &[CaptionButton::Minimize, CaptionButton::Close]);
+
+        assert_eq!(layout.titlebar, Rect::new(frame.origin, Size::new(300.0, 30.0)));
+        assert_eq!(layout.content, Rect::new(Point::new(10.0, 40.0), Size::new(300.0, 170.0)));
+        assert_eq!(layout.buttons[1].1.max_x(), frame.max_x());
+
+        assert_eq!(layout.button_at(Point::new(300.0, 20.0)), Some(CaptionButton::Close));
+        assert_eq!(layout.button_at(Point::new(250.0, 20.0)), Some(CaptionButton::Minimize));
+        assert!(layout.is_drag_area(Point::new(100.0, 20.0)));
+        assert!(!layout.is_drag_area(Point::new(300.0, 20.0)));
+        assert!(!layout.is_drag_area(Point::new(100.0, 50.0)));
+
+        let mut builder = DisplayListBuilder::new();
+        let state = ChromeState { hovered: Some(CaptionButton::Close), ..Default::default() };
+        push_shadow(&mut builder, frame, &theme);
+        push_titlebar(&mut builder, &layout, &theme, &state, None);
+        push_border(&mut builder, frame, &theme);
+
+        // shadow, titlebar, minimize glyph, close hover and glyph (2 lines), border.
+        assert_eq!(builder.build().len(), 7);
+    }
+}
@@ -0,0 +1,483 @@
+//! Measuring and combining paths, for building shapes out of simpler ones (e.g. a titlebar merged with the outline of a panel).
+//!
+//! Everything here works on [flattened](../tessellation/fn.flatten.html) paths, so curves are approximated to within a tolerance
+//! and the paths which are returned only consist of lines.
+
+use {super::tessellation::*, super::*, std::collections::HashMap};
+
+/// Boolean operation between the fills of two paths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathOp {
+    /// Covers what either path covers.
+    Union,
+    /// Covers what both paths cover.
+    Intersect,
+    /// Covers what the first path covers and the second doesn't.
+    Difference,
+}
+
+impl PathOp {
+    fn apply(self, a: bool, b: bool) -> bool {
+        match self {
+            PathOp::Union => a || b,
+            PathOp::Intersect => a && b,
+            PathOp::Difference => a && !b,
+        }
+    }
+}
+
+/// Combines the fills of two paths (with the non-zero rule), approximating curves to within `tolerance`.
+///
+/// The result is a closed polygon for each boundary (holes included), wound such that it fills the same with either fill rule.
+/// Every pair of segments is tested against each other, so this is meant for the simple shapes of widgets rather than complex paths.
+pub fn path_op(a: &VectorPath, b: &VectorPath, op: PathOp, tolerance: f32) -> VectorPath {
+    let tolerance = tolerance.max(1e-4);
+    let polygons = |path| -> Vec<Vec<Point>> {
+        flatten(path, true, tolerance).into_iter().map(|(points, _)| points).collect()
+    };
+    let (a, b) = (polygons(a), polygons(b));
+
+    let mut segments: Vec<(Point, Point)> = Vec::new();
+    for polygon in a.iter().chain(b.iter()) {
+        for (i, &from) in polygon.iter().enumerate() {
+            let to = polygon[(i + 1) % polygon.len()];
+            if from != to {
+                segments.push((from, to));
+            }
+        }
+    }
+
+    // every segment is split wherever it meets another, so that each piece is entirely inside or outside of either path.
+    let mut splits = vec![vec![0.0, 1.0]; segments.len()];
+    for i in 0..segments.len() {
+        for j in i + 1..segments.len() {
+            let (t, u) = intersections(segments[i], segments[j]);
+            splits[i].extend(t);
+            splits[j].extend(u);
+        }
+    }
+
+    // points are snapped to a grid finer than the tolerance, so that the ends of adjacent pieces are found to be equal.
+    // its spacing is a power of two, so that whole (and most fractional) coordinates are kept exactly.
+    let grid = 2f32.powi((tolerance / 16.0).log2().floor() as i32);
+    let snap = |point: Point| ((point.x / grid).round() as i64, (point.y / grid).round() as i64);
+    let unsnap = |(x, y): (i64, i64)| Point::new(x as f32 * grid, y as f32 * grid);
+
+    let mut pieces = Vec::new();
+    for ((from, to), mut splits) in segments.into_iter().zip(splits) {
+        splits.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        for pair in splits.windows(2) {
+            let (start, end) = (snap(from.lerp(to, pair[0])), snap(from.lerp(to, pair[1])));
+            if start != end {
+                // overlapping pieces of both paths are the same boundary, so they're only kept once.
+                pieces.push(if start < end { (start, end) } else { (end, start) });
+            }
+        }
+    }
+    pieces.sort_unstable();
+    pieces.dedup();
+
+    // pieces which separate the inside of the result from the outside are kept, directed with the inside to the left.
+    let offset = grid / 4.0;
+    let mut edges: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+    for (start, end) in pieces {
+        let (from, to) = (unsnap(start), unsnap(end));
+        let middle = from.lerp(to, 0.5);
+        let normal = Vector::new(from.y - to.y, to.x - from.x).normalize() * offset;
+        let inside = |point| op.apply(winding(&a, point) != 0, winding(&b, point) != 0);
+        match (inside(middle + normal), inside(middle - normal)) {
+            (true, false) => edges.entry(start).or_default().push(end),
+            (false, true) => edges.entry(end).or_default().push(start),
+            _ => {}
+        }
+    }
+
+    // winding numbers add up over edges, so the kept edges can be joined into loops in any order.
+    let mut starts: Vec<_> = edges.keys().copied().collect();
+    starts.sort_unstable();
+    let mut builder = VectorPathBuilder::new();
+    for start in starts {
+        while let Some(next) = edges.get_mut(&start).and_then(Vec::pop) {
+            builder.move_to(unsnap(start));
+            let mut current = next;
+            while current != start {
+                builder.line_to(unsnap(current));
+                current = match edges.get_mut(&current).and_then(Vec::pop) {
+                    Some(next) => next,
+                    None => break,
+                };
+            }
+            builder.line_to(unsnap(start));
+        }
+    }
+    builder.build()
+}
+
+/// Returns where two segments meet, as parameters along each of them.
+///
+/// Where the segments overlap, the ends of the overlap are returned.
+fn intersections(a: (Point, Point), b: (Point, Point)) -> (Vec<f32>, Vec<f32>) {
+    let (r, s) = (a.1 - a.0, b.1 - b.0);
+    let denominator = r.cross(s);
+    let offset = b.0 - a.0;
+    let parameter = |point: Point, (from, to): (Point, Point)| {
+        let direction = to - from;
+        (point - from).dot(direction) / direction.square_length()
+    };
+
+    if denominator.abs() <= 1e-9 * r.length() * s.length() {
+        // parallel, so they only meet if they're collinear.
+        if offset.cross(r).abs() > 1e-6 * r.length().max(1.0) * offset.length().max(1.0) {
+            return (Vec::new(), Vec::new());
+        }
+        let inside = |t: &f32| *t > 0.0 && *t < 1.0;
+        let t = [parameter(b.0, a), parameter(b.1, a)].iter().copied().filter(inside).collect();
+        let u = [parameter(a.0, b), parameter(a.1, b)].iter().copied().filter(inside).collect();
+        return (t, u);
+    }
+
+    let t = offset.cross(s) / denominator;
+    let u = offset.cross(r) / denominator;
+    if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        (vec![t], vec![u])
+    } else {
+        (Vec::new(), Vec::new())
+    }
+}
+
+/// Returns the winding number of polygons around a point.
+fn winding(polygons: &[Vec<Point>], point: Point) -> i32 {
+    let mut winding = 0;
+    for polygon in polygons {
+        for (i, &from) in polygon.iter().enumerate() {
+            let to = polygon[(i + 1) % polygon.len()];
+            let side = (to - from).cross(point - from);
+            if from.y <= point.y && to.y > point.y && side > 0.0 {
+                winding += 1;
+            } else if from.y > point.y && to.y <= point.y && side < 0.0 {
+                winding -= 1;
+            }
+        }
+    }
+    winding
+}
+
+/// Measures lengths along a path, and finds points at a distance along it (e.g. to place markers or animate along it).
+///
+/// The contours of the path are measured one after another, as if they were joined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathMeasure {
+    // the points of each contour, with the distance along the path to each.
+    contours: Vec<Vec<(Point, f32)>>,
+    length: f32,
+}
+
+impl PathMeasure {
+    /// Measures a path, approximating curves to within `tolerance` (as with [`flatten`](../tessellation/fn.flatten.html), only the last contour is closed if `is_closed`).
+    pub fn new(path: &VectorPath, is_closed: bool, tolerance: f32) -> Self {
+        let mut length = 0.0;
+        let contours = flatten(path, is_closed, tolerance.max(1e-4))
+            .into_iter()
+            .map(|(mut points, closed)| {
+                if closed {
+                    points.push(points[0]);
+                }
+                let mut previous = points[0];
+                points
+                    .into_iter()
+                    .map(|point| {
+                        length += (point - previous).length();
+                        previous = point;
+                        (point, length)
+                    })
+                    .collect()
+            })
+            .collect();
+        PathMeasure { contours, length }
+    }
+
+    /// Returns the total length of the path.
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// Returns the point at `distance` along the path and the (normalized) direction of the path there,
+    /// or `None` if the distance is outside of the path.
+    pub fn position_at(&self, distance: f32) -> Option<(Point, Vector)> {
+        if !(0.0..=self.length).contains(&distance) {
+            return None;
+        }
+
+        let contour = self
+            .contours
+            .iter()
+            .find(|contour| contour.last().is_some_and(|&(_, end)| end >= distance))?;
+        let index = contour.iter().position(|&(_, end)| end >= distance)?.max(1);
+        let ((from, start), (to, end)) = (*contour.get(index - 1)?, *contour.get(index)?);
+        let t = if end > start { (distance - start) / (end - start) } else { 0.0 };
+        let direction = to - from;
+        let direction = if direction.square_length() > 0.0 {
+            direction.normalize()
+        } else {
+            Vector::new(1.0, 0.0)
+        };
+        Some((from.lerp(to, t), direction))
+    }
+
+    /// Returns the part of the path between two distances along it, as open polylines (one for each contour it spans).
+    pub fn segment(&self, start: f32, end: f32) -> VectorPath {
+        let mut builder = VectorPathBuilder::new();
+        for contour in &self.contours {
+            let (first, last) = match (contour.first(), contour.last()) {
+                (Some(&(_, first)), Some(&(_, last))) => (first, last),
+                _ => continue,
+            };
+            let (from, to) = (start.max(first), end.min(last));
+            if from >= to {
+                continue;
+            }
+
+            builder.move_to(point_along(contour, from));
+            for &(point, _) in
+                contour.iter().filter(|&&(_, distance)| distance > from && distance < to)
+            {
+                builder.line_to(point);
+            }
+            builder.line_to(point_along(contour, to));
+        }
+        builder.build()
+    }
+}
+
+/// Returns the point at a distance along the path, which is within the contour.
+fn point_along(contour: &[(Point, f32)], distance: f32) -> Point {
+    let index =
+        contour.iter().position(|&(_, end)| end >= distance).unwrap_or(contour.len() - 1).max(1);
+    let ((from, start), (to, end)) = (contour[index - 1], contour[index]);
+    let t = if end > start { (distance - start) / (end - start) } else { 0.0 };
+    from.lerp(to, t)
+}
+
+/// Returns the dashes of a path (see [`StrokeDash`](../struct.StrokeDash.html)), within a trimmed part of it
+/// (see [`trim`](../struct.GraphicsDisplayStroke.html#structfield.trim)), as open polylines.
+///
+/// Without dashes or a trim, this is the flattened path.
+pub fn dash_path(
+    path: &VectorPath,
+    is_closed: bool,
+    dash: Option<&StrokeDash>,
+    trim: Option<(f32, f32)>,
+    tolerance: f32,
+) -> VectorPath {
+    let measure = PathMeasure::new(path, is_closed, tolerance);
+    let (start, end) = trim.unwrap_or((0.0, 1.0));
+    let (start, end) =
+        (start.clamp(0.0, 1.0) * measure.length(), end.clamp(0.0, 1.0) * measure.length());
+
+    let intervals = match dash {
+        Some(dash) => {
+            let mut intervals: Vec<f32> =
+                dash.intervals.iter().map(|interval| interval.max(0.0)).collect();
+            if intervals.len() % 2 == 1 {
+                intervals.extend_from_within(..);
+            }
+            intervals
+        }
+        None => Vec::new(),
+    };
+    let period: f32 = intervals.iter().sum();
+    // dashes which are all gaps (or too dense to see) are drawn solid, as in Skia.
+    if intervals.is_empty() || period <= 0.0 || measure.length() / period > 1e5 {
+        return measure.segment(start, end);
+    }
+
+    let mut dashes = VectorPath::new();
+    let mut distance = -dash.map_or(0.0, |dash| dash.phase).rem_euclid(period);
+    'dashes: loop {
+        for (i, interval) in intervals.iter().enumerate() {
+            if distance >= end {
+                break 'dashes;
+            }
+            if i % 2 == 0 {
+                let (from, to) = (distance.max(start), (distance + interval).min(end));
+                if from < to {
+                    dashes.extend(measure.segment(from, to));
+                }
+            }
+            distance += interval;
+        }
+    }
+    dashes
+}
+
+/// Returns the exact bounds of a path (to within `tolerance`), unlike [`vector_path_bounds`](../fn.vector_path_bounds.html) which includes control points.
+pub fn path_bounds(path: &VectorPath, tolerance: f32) -> Rect {
+    Rect::from_points(
+        flatten(path, false, tolerance.max(1e-4)).into_iter().flat_map(|(points, _)| points),
+    )
+}
+
+/// Returns the convex hull of a path (to within `tolerance`), as a polygon wound clockwise on the screen.
+pub fn convex_hull(path: &VectorPath, tolerance: f32) -> Vec<Point> {
+    let mut points: Vec<Point> = flatten(path, false, tolerance.max(1e-4))
+        .into_iter()
+        .flat_map(|(points, _)| points)
+        .collect();
+    points.sort_by(|a, b| (a.x, a.y).partial_cmp(&(b.x, b.y)).unwrap_or(std::cmp::Ordering::Equal));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Andrew's monotone chain; the lower hull, then the upper hull.
+    let mut hull: Vec<Point> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for &point in &points {
+            while hull.len() >= start + 2 {
+                let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+                if (b - a).cross(point - a) <= 0.0 {
+                    hull.pop();
+                } else {
+                    break;
+                }
+            }
+            hull.push(point);
+        }
+        // the last point of each half is the first of the other.
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> VectorPath {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(x, y));
+        builder.line_to(Point::new(x + size, y));
+        builder.line_to(Point::new(x + size, y + size));
+        builder.line_to(Point::new(x, y + size));
+        builder.build()
+    }
+
+    fn area(path: &VectorPath) -> f32 {
+        flatten(path, true, 0.1)
+            .iter()
+            .map(|(points, _)| {
+                points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .map(|(a, b)| a.x * b.y - b.x * a.y)
+                    .sum::<f32>()
+                    / 2.0
+            })
+            .sum::<f32>()
+            .abs()
+    }
+
+    #[test]
+    fn test_path_ops() {
+        let (a, b) = (square(0.0, 0.0, 10.0), square(5.0, 5.0, 10.0));
+
+        let union = path_op(&a, &b, PathOp::Union, 0.1);
+        assert!((area(&union) - 175.0).abs() < 0.1);
+        assert_eq!(path_bounds(&union, 0.1), Rect::new(Point::zero(), Size::new(15.0, 15.0)));
+
+        let intersection = path_op(&a, &b, PathOp::Intersect, 0.1);
+        assert!((area(&intersection) - 25.0).abs() < 0.1);
+        assert_eq!(
+            path_bounds(&intersection, 0.1),
+            Rect::new(Point::new(5.0, 5.0), Size::new(5.0, 5.0))
+        );
+
+        let difference = path_op(&a, &b, PathOp::Difference, 0.1);
+        assert!((area(&difference) - 75.0).abs() < 0.1);
+
+        // a shared edge doesn't leave a seam, and a hole is kept.
+        let union = path_op(&a, &square(10.0, 0.0, 10.0), PathOp::Union, 0.1);
+        assert_eq!(flatten(&union, true, 0.1).len(), 1);
+        let ring =
+            path_op(&square(0.0, 0.0, 30.0), &square(10.0, 10.0, 10.0), PathOp::Difference, 0.1);
+        assert_eq!(flatten(&ring, true, 0.1).len(), 2);
+        assert!(path_op(&a, &square(20.0, 20.0, 5.0), PathOp::Intersect, 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_path_measure() {
+        let measure = PathMeasure::new(&square(0.0, 0.0, 10.0), true, 0.1);
+        assert_eq!(measure.length(), 40.0);
+        assert_eq!(measure.position_at(0.0), Some((Point::zero(), Vector::new(1.0, 0.0))));
+        assert_eq!(measure.position_at(15.0), Some((Point::new(10.0, 5.0), Vector::new(0.0, 1.0))));
+        assert_eq!(measure.position_at(40.0), Some((Point::zero(), Vector::new(0.0, -1.0))));
+        assert_eq!(measure.position_at(41.0), None);
+
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(10.0, 0.0));
+        builder.arc_to(Point::zero(), Vector::new(10.0, 10.0), 0.0, 180.0);
+        let measure = PathMeasure::new(&builder.build(), false, 0.01);
+        assert!((measure.length() - std::f32::consts::PI * 10.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_dash_path() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(10.0, 0.0));
+        let line = builder.build();
+        let dash = |intervals: &[f32], phase| StrokeDash { intervals: intervals.to_vec(), phase };
+        let starts = |path: &VectorPath| {
+            path.iter()
+                .filter_map(|event| match event {
+                    VectorPathEvent::MoveTo { to } => Some(to.x),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let dashed = dash_path(&line, false, Some(&dash(&[2.0, 1.0], 0.0)), None, 0.1);
+        assert_eq!(starts(&dashed), vec![0.0, 3.0, 6.0, 9.0]);
+        assert_eq!(PathMeasure::new(&dashed, false, 0.1).length(), 7.0);
+
+        // the phase moves the dashes backwards along the path, and an odd number of intervals is repeated.
+        let shifted = dash_path(&line, false, Some(&dash(&[2.0, 1.0], 1.0)), None, 0.1);
+        assert_eq!(starts(&shifted), vec![0.0, 2.0, 5.0, 8.0]);
+        let odd = dash_path(&line, false, Some(&dash(&[3.0], 0.0)), None, 0.1);
+        assert_eq!(starts(&odd), vec![0.0, 6.0]);
+
+        // trimming keeps the dashes in place.
+        let trimmed =
+            dash_path(&line, false, Some(&dash(&[2.0, 1.0], 0.0)), Some((0.35, 1.0)), 0.1);
+        assert_eq!(starts(&trimmed)[..2], [3.5, 6.0]);
+        let trimmed = dash_path(&square(0.0, 0.0, 10.0), true, None, Some((0.0, 0.5)), 0.1);
+        assert_eq!(
+            PathMeasure::new(&trimmed, false, 0.1).position_at(20.0).unwrap().0,
+            Point::new(10.0, 10.0)
+        );
+        assert!(dash_path(&line, false, None, Some((0.5, 0.5)), 0.1).is_empty());
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let mut builder = VectorPathBuilder::from_path(square(0.0, 0.0, 10.0));
+        builder.move_to(Point::new(5.0, 5.0));
+        builder.line_to(Point::new(5.0, 20.0));
+        let hull = convex_hull(&builder.build(), 0.1);
+        assert_eq!(
+            hull,
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(10.0, 0.0),
+                Point::new(10.0, 10.0),
+                Point::new(5.0, 20.0),
+                Point::new(0.0, 10.0),
+            ]
+        );
+    }
+}
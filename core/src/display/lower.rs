@@ -0,0 +1,571 @@
+//! Lowering of display commands into a minimal set of primitives.
+//!
+//! A [`DisplayCommand`](../enum.DisplayCommand.html) can express a lot (text layout, every kind of shape and clip, filters on individual items),
+//! all of which a backend has to implement. Simple backends (such as a software rasterizer or an SVG writer) can instead consume
+//! [`LoweredCommand`](enum.LoweredCommand.html)s, where;
+//! - Every shape (lines, rectangles, ellipses) and clip is a vector path.
+//! - Text is a run of positioned glyphs, so no shaping or font metrics are needed.
+//! - Strokes are solid; dashes and trims are applied to their paths.
+//! - Filters only apply to layers, rather than individual items.
+//! - Transformations are full matrices.
+
+use super::*;
+
+/// A glyph (by its ID within a font) at a position relative to the origin of its [`GlyphRun`](struct.GlyphRun.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    pub id: u32,
+    pub position: Point,
+}
+
+/// Glyphs of a single font, size and color.
+#[derive(Clone)]
+pub struct GlyphRun {
+    pub font: ResourceReference,
+    pub font_info: FontInfo,
+    pub size: f32,
+    /// Position of the baseline of the first glyph.
+    pub origin: Point,
+    pub glyphs: Vec<PositionedGlyph>,
+    pub color: StyleColor,
+}
+
+impl GlyphRun {
+    /// Positions the glyphs of a text item.
+    ///
+    /// Simple text is laid out by the advances of the font (without kerning or shaping), whereas shaped text keeps its positions.
+    pub fn from_text(item: &TextDisplayItem) -> Result<Self, error::FontError> {
+        let mut glyphs = Vec::with_capacity(item.text.len());
+        match item.text {
+            DisplayText::Simple(ref text) => {
+                let font = &item.font_info.font;
+                let scale = item.size / font.metrics().units_per_em as f32;
+                let mut x = 0.0;
+                for character in text.chars() {
+                    let id =
+                        font.glyph_for_char(character).ok_or(error::FontError::CodepointError)?;
+                    glyphs.push(PositionedGlyph { id, position: Point::new(x, 0.0) });
+                    x += font.advance(id)?.x * scale;
+                }
+            }
+            DisplayText::Shaped(ref shaped) => {
+                let mut pen = Point::zero();
+                for glyph in shaped {
+                    // shaped offsets point upwards, unlike display coordinates.
+                    let offset = Vector::new(glyph.offset.x, -glyph.offset.y);
+                    glyphs.push(PositionedGlyph { id: glyph.codepoint, position: pen + offset });
+                    pen += glyph.advance;
+                }
+            }
+        }
+
+        Ok(GlyphRun {
+            font: item.font,
+            font_info: item.font_info.clone(),
+            size: item.size,
+            origin: item.bottom_left,
+            glyphs,
+            color: item.color.clone(),
+        })
+    }
+
+    /// Returns the (unhinted) outlines of the glyphs as a single path, to be filled with the color of the run.
+    ///
+    /// Every contour is closed, and the path is in the same coordinate space as the origin.
+    pub fn outline(&self) -> Result<VectorPath, error::FontError> {
+        let font = &self.font_info.font;
+        let scale = self.size / font.metrics().units_per_em as f32;
+        let mut builder = OutlineBuilder {
+            builder: VectorPathBuilder::new(),
+            transform: Transform::identity(),
+            start: Point::zero(),
+            current: Point::zero(),
+        };
+        for glyph in &self.glyphs {
+            let origin = self.origin + glyph.position.to_vector();
+            // font units point upwards.
+            builder.transform =
+                Transform::create_scale(scale, -scale).post_translate(origin.to_vector());
+            font.outline(glyph.id, font_kit::hinting::HintingOptions::None, &mut builder)?;
+        }
+        Ok(builder.builder.build())
+    }
+}
+
+// Receives glyph outlines (in font units) from font-kit.
+struct OutlineBuilder {
+    builder: VectorPathBuilder,
+    transform: Transform,
+    // start of the current contour and current position, in font units.
+    start: Point,
+    current: Point,
+}
+
+impl lyon_path::builder::FlatPathBuilder for OutlineBuilder {
+    fn move_to(&mut self, to: Point) {
+        self.start = to;
+        self.current = to;
+        self.builder.move_to(self.transform.transform_point(to));
+    }
+
+    fn line_to(&mut self, to: Point) {
+        self.current = to;
+        self.builder.line_to(self.transform.transform_point(to));
+    }
+
+    fn close(&mut self) {
+        if self.current != self.start {
+            self.line_to(self.start);
+        }
+    }
+
+    fn current_position(&self) -> Point {
+        self.current
+    }
+}
+
+impl lyon_path::builder::PathBuilder for OutlineBuilder {
+    fn quadratic_bezier_to(&mut self, control: Point, to: Point) {
+        self.current = to;
+        self.builder
+            .quad_to(self.transform.transform_point(control), self.transform.transform_point(to));
+    }
+
+    fn cubic_bezier_to(&mut self, c1: Point, c2: Point, to: Point) {
+        self.current = to;
+        self.builder.cubic_to(
+            self.transform.transform_point(c1),
+            self.transform.transform_point(c2),
+            self.transform.transform_point(to),
+        );
+    }
+
+    // glyph outlines never contain arcs, so this is only approximate (the rotation is ignored).
+    fn arc(&mut self, center: Point, radii: Vector, sweep_angle: Angle, _x_rotation: Angle) {
+        let start = self.current - center;
+        let start_angle = Angle::radians(start.y.atan2(start.x));
+        let end_angle = start_angle + sweep_angle;
+        self.current =
+            center + Vector::new(end_angle.radians.cos(), end_angle.radians.sin()) * radii.length();
+        // the arc is flipped by the transformation, so its direction is too.
+        let center = self.transform.transform_point(center);
+        let radii = self.transform.transform_vector(radii);
+        self.builder.arc_to(
+            center,
+            Vector::new(radii.x.abs(), radii.y.abs()),
+            -start_angle.to_degrees(),
+            -sweep_angle.to_degrees(),
+        );
+    }
+}
+
+/// A display command within the minimal set of primitives.
+#[derive(Clone)]
+pub enum LoweredCommand {
+    /// Fills or strokes a vector path.
+    Path { path: VectorPath, is_closed: bool, paint: GraphicsDisplayPaint },
+    /// Draws an image, exactly as [`GraphicsDisplayItem::Image`](../enum.GraphicsDisplayItem.html#variant.Image).
+    Image {
+        src: Option<Rect>,
+        dst: Rect,
+        resource: ResourceReference,
+        orientation: ImageOrientation,
+        adjustments: ImageAdjustments,
+    },
+    /// Draws a run of glyphs.
+    Glyphs(GlyphRun),
+    /// Intersects the clip with the interior of a (closed) vector path.
+    Clip { path: VectorPath, antialias: bool },
+    /// Applies a filter to what has already been drawn, within a (closed) vector path.
+    BackdropFilter { path: VectorPath, filter: Filter },
+    /// Saves the draw state.
+    Save,
+    /// Saves the draw state and begins a layer, which is composited with the opacity and filter once restored.
+    SaveLayer { opacity: f32, filter: Option<Filter> },
+    /// Restores the last saved draw state.
+    Restore,
+    /// Applies a transformation before the current transformation (i.e. in the current coordinate space).
+    Transform(Transform),
+    /// Fills the clipped region with a solid color.
+    Clear(Color),
+}
+
+/// Returns a closed path around a rectangle.
+pub fn rectangle_path(rect: Rect) -> VectorPath {
+    let (min, max) = (rect.min(), rect.max());
+    let mut builder = VectorPathBuilder::new();
+    builder.move_to(min);
+    builder.line_to(Point::new(max.x, min.y));
+    builder.line_to(max);
+    builder.line_to(Point::new(min.x, max.y));
+    builder.line_to(min);
+    builder.build()
+}
+
+/// Returns a closed path around an ellipse.
+pub fn ellipse_path(center: Point, radii: Vector) -> VectorPath {
+    round_rectangle_path(
+        Rect::new(center - radii, (radii * 2.0).to_size()),
+        [radii.x.max(radii.y); 4],
+    )
+}
+
+/// Returns a path along an arc of an ellipse, which starts and ends at the center if `use_center` (and should then be closed).
+pub fn arc_path(
+    center: Point,
+    radii: Vector,
+    start_angle: Angle,
+    sweep_angle: Angle,
+    use_center: bool,
+) -> VectorPath {
+    let sweep = sweep_angle.to_degrees().clamp(-360.0, 360.0);
+    let start = center
+        + Vector::new(radii.x * start_angle.radians.cos(), radii.y * start_angle.radians.sin());
+    let mut builder = VectorPathBuilder::new();
+    builder.move_to(if use_center { center } else { start });
+    builder.arc_to(center, radii, start_angle.to_degrees(), sweep);
+    builder.build()
+}
+
+/// Returns a closed path around a rectangle with rounded corners.
+///
+/// The corner radii are clockwise from the top-left (as interpreted by the Skia backend), and are limited to half the size of the rectangle.
+pub fn round_rectangle_path(rect: Rect, radii: [f32; 4]) -> VectorPath {
+    let max = Vector::new(rect.size.width / 2.0, rect.size.height / 2.0);
+    let radius = |r: f32| Vector::new(r.max(0.0).min(max.x), r.max(0.0).min(max.y));
+    let [top_left, top_right, bottom_right, bottom_left] =
+        [radius(radii[0]), radius(radii[1]), radius(radii[2]), radius(radii[3])];
+    // conics of this weight are exact quarter-ellipses.
+    let weight = std::f32::consts::FRAC_1_SQRT_2;
+
+    let (min, max) = (rect.min(), rect.max());
+    let mut builder = VectorPathBuilder::new();
+    builder.move_to(Point::new(min.x + top_left.x, min.y));
+    builder.line_to(Point::new(max.x - top_right.x, min.y));
+    builder.conic_to(Point::new(max.x, min.y), Point::new(max.x, min.y + top_right.y), weight);
+    builder.line_to(Point::new(max.x, max.y - bottom_right.y));
+    builder.conic_to(Point::new(max.x, max.y), Point::new(max.x - bottom_right.x, max.y), weight);
+    builder.line_to(Point::new(min.x + bottom_left.x, max.y));
+    builder.conic_to(Point::new(min.x, max.y), Point::new(min.x, max.y - bottom_left.y), weight);
+    builder.line_to(Point::new(min.x, min.y + top_left.y));
+    builder.conic_to(Point::new(min.x, min.y), Point::new(min.x + top_left.x, min.y), weight);
+    builder.build()
+}
+
+/// Returns the outline of a clip as a closed path.
+pub fn clip_path(clip: &DisplayClip) -> VectorPath {
+    match clip {
+        DisplayClip::Rectangle { rect, .. } => rectangle_path(*rect),
+        DisplayClip::RoundRectangle { rect, radii } => round_rectangle_path(*rect, *radii),
+        DisplayClip::Ellipse { center, radii } => ellipse_path(*center, *radii),
+        DisplayClip::Path { path, .. } => path.clone(),
+    }
+}
+
+/// Maximum distance between curves and the line segments of their dashes (which are measured along flattened paths).
+const DASH_TOLERANCE: f32 = 0.05;
+
+/// Returns whether an item is stroked with dashes or a trim, which lowering applies to its path.
+///
+/// Backends which can dash strokes themselves should still draw such items lowered, so that dashes line up across backends.
+pub fn has_dashed_stroke(item: &GraphicsDisplayItem) -> bool {
+    let stroke = match item {
+        GraphicsDisplayItem::Line { stroke, .. } => stroke,
+        GraphicsDisplayItem::Rectangle { paint: GraphicsDisplayPaint::Stroke(stroke), .. }
+        | GraphicsDisplayItem::RoundRectangle {
+            paint: GraphicsDisplayPaint::Stroke(stroke), ..
+        }
+        | GraphicsDisplayItem::Ellipse { paint: GraphicsDisplayPaint::Stroke(stroke), .. }
+        | GraphicsDisplayItem::Arc { paint: GraphicsDisplayPaint::Stroke(stroke), .. }
+        | GraphicsDisplayItem::Path { paint: GraphicsDisplayPaint::Stroke(stroke), .. } => stroke,
+        _ => return false,
+    };
+    stroke.dash.is_some() || stroke.trim.is_some()
+}
+
+fn lower_item(item: &DisplayItem) -> Result<LoweredCommand, error::FontError> {
+    let path = |path, is_closed, paint: &GraphicsDisplayPaint| LoweredCommand::Path {
+        path,
+        is_closed,
+        paint: paint.clone(),
+    };
+
+    let command = match item {
+        DisplayItem::Graphics(item) => match &*item.center_aligned() {
+            GraphicsDisplayItem::Line { a, b, stroke } => {
+                let mut builder = VectorPathBuilder::new();
+                builder.move_to(*a);
+                builder.line_to(*b);
+                LoweredCommand::Path {
+                    path: builder.build(),
+                    is_closed: false,
+                    paint: GraphicsDisplayPaint::Stroke(stroke.clone()),
+                }
+            }
+            GraphicsDisplayItem::Rectangle { rect, paint } => {
+                path(rectangle_path(*rect), true, paint)
+            }
+            GraphicsDisplayItem::RoundRectangle { rect, radii, paint } => {
+                path(round_rectangle_path(*rect, *radii), true, paint)
+            }
+            GraphicsDisplayItem::Ellipse { center, radii, paint } => {
+                path(ellipse_path(*center, *radii), true, paint)
+            }
+            GraphicsDisplayItem::Arc {
+                center,
+                radii,
+                start_angle,
+                sweep_angle,
+                use_center,
+                paint,
+            } => path(
+                arc_path(*center, *radii, *start_angle, *sweep_angle, *use_center),
+                *use_center,
+                paint,
+            ),
+            GraphicsDisplayItem::Image { src, dst, resource, orientation, adjustments } => {
+                LoweredCommand::Image {
+                    src: *src,
+                    dst: *dst,
+                    resource: *resource,
+                    orientation: *orientation,
+                    adjustments: *adjustments,
+                }
+            }
+            GraphicsDisplayItem::Path { path: vector_path, is_closed, paint } => {
+                path(vector_path.clone(), *is_closed, paint)
+            }
+        },
+        DisplayItem::Text(item) => LoweredCommand::Glyphs(GlyphRun::from_text(item)?),
+    };
+
+    Ok(match command {
+        LoweredCommand::Path { path, is_closed, paint: GraphicsDisplayPaint::Stroke(stroke) }
+            if stroke.dash.is_some() || stroke.trim.is_some() =>
+        {
+            LoweredCommand::Path {
+                path: geometry::dash_path(
+                    &path,
+                    is_closed,
+                    stroke.dash.as_ref(),
+                    stroke.trim,
+                    DASH_TOLERANCE,
+                ),
+                is_closed: false,
+                paint: GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    dash: None,
+                    trim: None,
+                    ..stroke
+                }),
+            }
+        }
+        command => command,
+    })
+}
+
+/// Lowers a single display command, appending the result to `out`.
+pub fn lower_command(
+    command: &DisplayCommand,
+    out: &mut Vec<LoweredCommand>,
+) -> Result<(), error::FontError> {
+    match command {
+        DisplayCommand::Item(item, None) => out.push(lower_item(item)?),
+        DisplayCommand::Item(item, Some(filter)) => {
+            let item = lower_item(item)?;
+            out.push(LoweredCommand::SaveLayer { opacity: 1.0, filter: Some(*filter) });
+            out.push(item);
+            out.push(LoweredCommand::Restore);
+        }
+        DisplayCommand::BackdropFilter(clip, filter) => {
+            out.push(LoweredCommand::BackdropFilter { path: clip_path(clip), filter: *filter })
+        }
+        DisplayCommand::Clip(clip) => out.push(LoweredCommand::Clip {
+            path: clip_path(clip),
+            antialias: match clip {
+                DisplayClip::Rectangle { antialias, .. } => *antialias,
+                _ => true,
+            },
+        }),
+        DisplayCommand::Save => out.push(LoweredCommand::Save),
+        DisplayCommand::SaveLayer(opacity) => {
+            out.push(LoweredCommand::SaveLayer { opacity: *opacity, filter: None })
+        }
+        DisplayCommand::Restore => out.push(LoweredCommand::Restore),
+        DisplayCommand::Translate(offset) => {
+            out.push(LoweredCommand::Transform(Transform::create_translation(offset.x, offset.y)))
+        }
+        DisplayCommand::Scale(scale) => {
+            out.push(LoweredCommand::Transform(Transform::create_scale(scale.x, scale.y)))
+        }
+        DisplayCommand::Rotate(angle) => {
+            out.push(LoweredCommand::Transform(Transform::create_rotation(*angle)))
+        }
+        DisplayCommand::Transform(transform) => out.push(LoweredCommand::Transform(*transform)),
+        DisplayCommand::Clear(color) => out.push(LoweredCommand::Clear(*color)),
+    }
+    Ok(())
+}
+
+/// Lowers a display list.
+///
+/// Consecutive transformations are combined into one.
+pub fn lower_display_list(
+    display_list: &[DisplayCommand],
+) -> Result<Vec<LoweredCommand>, error::FontError> {
+    let mut out = Vec::with_capacity(display_list.len());
+    for command in display_list {
+        let start = out.len();
+        lower_command(command, &mut out)?;
+
+        if let [.., LoweredCommand::Transform(previous), LoweredCommand::Transform(next)] = &out[..]
+        {
+            if start == out.len() - 1 {
+                let combined = next.post_transform(previous);
+                out.pop();
+                out.pop();
+                out.push(LoweredCommand::Transform(combined));
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_lower_shapes() {
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(40.0, 20.0));
+        let fill = GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into());
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, fill.clone(), None);
+        builder.push_round_rectangle(rect, [100.0; 4], fill.clone(), Some(Filter::Invert));
+        builder.push_ellipse(rect.center(), Vector::new(20.0, 10.0), fill, None);
+        let lowered = lower_display_list(&builder.build()).unwrap();
+
+        assert_eq!(lowered.len(), 5);
+        match lowered[0] {
+            LoweredCommand::Path { ref path, is_closed, .. } => {
+                assert!(is_closed);
+                assert_eq!(path.len(), 5);
+                assert_eq!(vector_path_bounds(path), rect);
+            }
+            _ => panic!("rectangle wasn't lowered to a path"),
+        }
+        // the filter moves to a layer.
+        assert!(matches!(
+            lowered[1],
+            LoweredCommand::SaveLayer { filter: Some(Filter::Invert), .. }
+        ));
+        assert!(matches!(lowered[3], LoweredCommand::Restore));
+        // radii are limited to the rectangle, and the control points of the curves are the corners.
+        for shape in &[&lowered[2], &lowered[4]] {
+            match shape {
+                LoweredCommand::Path { path, .. } => assert_eq!(vector_path_bounds(path), rect),
+                _ => panic!("shape wasn't lowered to a path"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lower_dashes() {
+        let stroke = GraphicsDisplayStroke {
+            dash: Some(StrokeDash { intervals: vec![10.0, 10.0], phase: 0.0 }),
+            trim: Some((0.0, 0.5)),
+            ..Default::default()
+        };
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(40.0, 40.0)),
+            GraphicsDisplayPaint::Stroke(stroke),
+            None,
+        );
+        let lowered = lower_display_list(&builder.build()).unwrap();
+
+        match lowered[0] {
+            LoweredCommand::Path {
+                ref path,
+                is_closed,
+                paint: GraphicsDisplayPaint::Stroke(ref stroke),
+            } => {
+                // half of the outline (the top and right), in four dashes.
+                assert!(!is_closed);
+                assert!(stroke.dash.is_none() && stroke.trim.is_none());
+                let dashes = path
+                    .iter()
+                    .filter(|event| matches!(event, VectorPathEvent::MoveTo { .. }))
+                    .count();
+                assert_eq!(dashes, 4);
+                assert_eq!(
+                    vector_path_bounds(path),
+                    Rect::new(Point::zero(), Size::new(40.0, 30.0))
+                );
+            }
+            _ => panic!("stroke wasn't lowered to a path"),
+        }
+    }
+
+    #[test]
+    fn test_lower_transforms() {
+        let list = vec![
+            DisplayCommand::Translate(Vector::new(10.0, 0.0)),
+            DisplayCommand::Scale(Vector::new(2.0, 2.0)),
+            DisplayCommand::Save,
+            DisplayCommand::Rotate(Angle::radians(0.0)),
+        ];
+        let lowered = lower_display_list(&list).unwrap();
+
+        assert_eq!(lowered.len(), 3);
+        match lowered[0] {
+            LoweredCommand::Transform(transform) => {
+                assert_eq!(transform.transform_point(Point::new(1.0, 1.0)), Point::new(12.0, 2.0))
+            }
+            _ => panic!("transformations weren't combined"),
+        }
+    }
+
+    #[test]
+    fn test_glyph_run() {
+        let font_info = FontInfo::from_data(
+            Arc::new(include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec()),
+            0,
+        )
+        .unwrap();
+        let item = TextDisplayItem {
+            text: "ab".to_string().into(),
+            font: ResourceReference::Font(0),
+            font_info,
+            size: 16.0,
+            bottom_left: Point::new(5.0, 20.0),
+            color: StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+        };
+
+        let run = GlyphRun::from_text(&item).unwrap();
+        assert_eq!(run.origin, item.bottom_left);
+        assert_eq!(run.glyphs.len(), 2);
+        assert_eq!(run.glyphs[0].position, Point::zero());
+        // the second glyph starts where the text bounds of the first end.
+        let first = item.limited_bounds(1).unwrap();
+        assert!((run.glyphs[1].position.x - first.size.width).abs() < 1e-3);
+
+        let shaped = DisplayText::Shaped(vec![
+            ShapedGlyph { codepoint: 3, advance: Vector::new(8.0, 0.0), offset: Vector::zero() },
+            ShapedGlyph {
+                codepoint: 4,
+                advance: Vector::new(8.0, 0.0),
+                offset: Vector::new(1.0, 2.0),
+            },
+        ]);
+        let run = GlyphRun::from_text(&TextDisplayItem { text: shaped, ..item }).unwrap();
+        assert_eq!(
+            run.glyphs,
+            vec![
+                PositionedGlyph { id: 3, position: Point::zero() },
+                PositionedGlyph { id: 4, position: Point::new(9.0, -2.0) },
+            ]
+        );
+    }
+}
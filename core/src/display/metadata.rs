@@ -0,0 +1,380 @@
+//! Metadata of encoded images; their size, EXIF orientation and color profile.
+//!
+//! Cameras store photos as they were captured and record how they should be turned in the EXIF orientation,
+//! so the orientation has to be applied for photos to appear upright. Backends which decode images apply it as the image is decoded
+//! (such that the size of the resource is the size as displayed), and expose the metadata through
+//! [`image_metadata`](../trait.GraphicsDisplay.html#method.image_metadata):
+//!
+//! ```ignore
+//! let photo = display.new_resource(ResourceDescriptor::Image(ImageData::Encoded(data)))?;
+//! if let Some(metadata) = display.image_metadata(photo) {
+//!     let (width, height) = metadata.size();
+//!     self.aspect_ratio = width as f32 / height as f32;
+//! }
+//! ```
+//!
+//! Likewise, images with an embedded ICC profile are converted from it to the color space of the display (sRGB) as they're decoded,
+//! such that their colors match those shown by other viewers, rather than being interpreted as sRGB.
+//!
+//! JPEG (EXIF and ICC profiles in `APP1`/`APP2` segments) and PNG (`eXIf`, `iCCP` and `sRGB` chunks) are understood.
+
+use super::{ImageOrientation, ImageRotation};
+
+/// The color profile an image is encoded in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ColorProfile {
+    /// Standard RGB, which is what images are assumed to be in without a profile.
+    Srgb,
+    /// An embedded ICC profile.
+    Icc(Vec<u8>),
+    /// An embedded ICC profile, compressed with zlib (as PNGs store it), along with the name of the profile.
+    CompressedIcc { name: String, data: Vec<u8> },
+}
+
+impl ColorProfile {
+    /// Returns `true` if the profile is embedded in the image, such that its colors need converting to be displayed.
+    #[inline]
+    pub fn is_embedded(&self) -> bool {
+        !matches!(self, ColorProfile::Srgb)
+    }
+}
+
+/// Metadata of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageMetadata {
+    /// Width and height of the image in pixels, as stored (i.e. before the orientation is applied).
+    pub stored_size: (u32, u32),
+    /// How the stored image has to be turned to appear upright.
+    pub orientation: ImageOrientation,
+    /// The profile the colors of the stored image are in, if specified (which displays convert from as the image is decoded).
+    pub color_profile: Option<ColorProfile>,
+}
+
+impl ImageMetadata {
+    /// Metadata of an image without any orientation or color profile.
+    pub fn new(size: (u32, u32)) -> Self {
+        ImageMetadata { stored_size: size, orientation: Default::default(), color_profile: None }
+    }
+
+    /// Reads the metadata of an encoded image, returning `None` if the format isn't understood or the data is malformed.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&[0xFF, 0xD8]) {
+            read_jpeg(data)
+        } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            read_png(data)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the width and height of the image once the orientation is applied.
+    pub fn size(&self) -> (u32, u32) {
+        let (width, height) = self.stored_size;
+        if self.orientation.rotation.is_sideways() {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+}
+
+/// Converts the value of the EXIF orientation tag (from `1` to `8`) to an orientation, or `None` if the value is invalid.
+pub fn exif_orientation(value: u16) -> Option<ImageOrientation> {
+    let (rotation, flip_horizontal, flip_vertical) = match value {
+        1 => (ImageRotation::None, false, false),
+        2 => (ImageRotation::None, true, false),
+        3 => (ImageRotation::Rotate180, false, false),
+        4 => (ImageRotation::None, false, true),
+        // transposed.
+        5 => (ImageRotation::Rotate270, true, false),
+        6 => (ImageRotation::Rotate90, false, false),
+        // transversed.
+        7 => (ImageRotation::Rotate90, true, false),
+        8 => (ImageRotation::Rotate270, false, false),
+        _ => return None,
+    };
+    Some(ImageOrientation { rotation, flip_horizontal, flip_vertical })
+}
+
+fn read_jpeg(data: &[u8]) -> Option<ImageMetadata> {
+    let mut size = None;
+    let mut exif = None;
+    let mut icc_chunks = Vec::new();
+
+    let mut pos = 2;
+    while let (Some(&0xFF), Some(&marker)) = (data.get(pos), data.get(pos + 1)) {
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        // the metadata is all in the header, which ends at the first scan.
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+
+        // a truncated segment ends the header early.
+        let payload = match data.get(pos + 2..pos + 4) {
+            Some(length) => {
+                data.get(pos + 4..pos + 2 + u16::from_be_bytes([length[0], length[1]]) as usize)
+            }
+            None => None,
+        };
+        let payload = match payload {
+            Some(payload) => payload,
+            None => break,
+        };
+
+        match marker {
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                let height = u16::from_be_bytes([*payload.get(1)?, *payload.get(2)?]);
+                let width = u16::from_be_bytes([*payload.get(3)?, *payload.get(4)?]);
+                size = Some((width as u32, height as u32));
+            }
+            0xE1 if payload.starts_with(b"Exif\0\0") => exif = Some(read_exif(&payload[6..])),
+            0xE2 if payload.starts_with(b"ICC_PROFILE\0") && payload.len() > 14 => {
+                icc_chunks.push((payload[12], &payload[14..]));
+            }
+            _ => {}
+        }
+
+        pos += 4 + payload.len();
+    }
+
+    // profiles larger than a segment are split across several, which are numbered.
+    icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+    let icc = if icc_chunks.is_empty() {
+        None
+    } else {
+        Some(ColorProfile::Icc(
+            icc_chunks.into_iter().flat_map(|(_, chunk)| chunk.to_vec()).collect(),
+        ))
+    };
+
+    let exif = exif.flatten().unwrap_or_default();
+    Some(ImageMetadata {
+        stored_size: size?,
+        orientation: exif.orientation.unwrap_or_default(),
+        color_profile: icc.or(exif.color_profile),
+    })
+}
+
+fn read_png(data: &[u8]) -> Option<ImageMetadata> {
+    let mut metadata = None;
+    let mut pos = 8;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let payload = match data.get(pos + 8..pos + 8 + length) {
+            Some(payload) => payload,
+            None => break,
+        };
+
+        match &header[4..] {
+            b"IHDR" if length >= 8 => {
+                let width = u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                let height = u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]);
+                metadata = Some(ImageMetadata::new((width, height)));
+            }
+            b"eXIf" => {
+                if let (Some(metadata), Some(exif)) = (&mut metadata, read_exif(payload)) {
+                    metadata.orientation = exif.orientation.unwrap_or_default();
+                    metadata.color_profile = metadata.color_profile.take().or(exif.color_profile);
+                }
+            }
+            b"iCCP" => {
+                // the name is null-terminated and followed by the compression method.
+                let name_end = payload.iter().position(|&byte| byte == 0)?;
+                if let Some(metadata) = &mut metadata {
+                    metadata.color_profile = Some(ColorProfile::CompressedIcc {
+                        name: String::from_utf8_lossy(&payload[..name_end]).into_owned(),
+                        data: payload.get(name_end + 2..)?.to_vec(),
+                    });
+                }
+            }
+            b"sRGB" => {
+                if let Some(metadata) = &mut metadata {
+                    metadata.color_profile = Some(ColorProfile::Srgb);
+                }
+            }
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+
+        pos += 8 + length + 4;
+    }
+    metadata
+}
+
+#[derive(Debug, Default)]
+struct Exif {
+    orientation: Option<ImageOrientation>,
+    color_profile: Option<ColorProfile>,
+}
+
+const ORIENTATION_TAG: u16 = 0x0112;
+const EXIF_IFD_TAG: u16 = 0x8769;
+const COLOR_SPACE_TAG: u16 = 0xA001;
+
+// reads the orientation (from the first IFD) and color space (from the EXIF IFD) of TIFF-structured EXIF data.
+fn read_exif(tiff: &[u8]) -> Option<Exif> {
+    let big_endian = match tiff.get(..4)? {
+        b"MM\0*" => true,
+        b"II*\0" => false,
+        _ => return None,
+    };
+    let u16_at = |offset: usize| -> Option<u16> {
+        let bytes = [*tiff.get(offset)?, *tiff.get(offset + 1)?];
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let u32_at = |offset: usize| -> Option<u32> {
+        let bytes = [
+            *tiff.get(offset)?,
+            *tiff.get(offset + 1)?,
+            *tiff.get(offset + 2)?,
+            *tiff.get(offset + 3)?,
+        ];
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+    // returns the value field of each entry of the IFD at `offset`, by tag.
+    let entries = |offset: usize| -> Vec<(u16, usize)> {
+        let count = u16_at(offset).unwrap_or(0) as usize;
+        (0..count)
+            .map(|i| offset + 2 + i * 12)
+            .filter_map(|entry| Some((u16_at(entry)?, entry + 8)))
+            .collect()
+    };
+
+    let mut exif = Exif::default();
+    for (tag, value) in entries(u32_at(4)? as usize) {
+        match tag {
+            ORIENTATION_TAG => exif.orientation = u16_at(value).and_then(exif_orientation),
+            EXIF_IFD_TAG => {
+                let is_srgb = u32_at(value)
+                    .map(|offset| entries(offset as usize))
+                    .into_iter()
+                    .flatten()
+                    .any(|(tag, value)| tag == COLOR_SPACE_TAG && u16_at(value) == Some(1));
+                if is_srgb {
+                    exif.color_profile = Some(ColorProfile::Srgb);
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(exif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // little-endian EXIF with the orientation, and an EXIF IFD with the color space.
+    fn exif(orientation: u16) -> Vec<u8> {
+        let mut tiff = b"II*\0".to_vec();
+        tiff.extend_from_slice(&8u32.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&ORIENTATION_TAG.to_le_bytes());
+        tiff.extend_from_slice(&[3, 0, 1, 0, 0, 0]);
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+        tiff.extend_from_slice(&EXIF_IFD_TAG.to_le_bytes());
+        tiff.extend_from_slice(&[4, 0, 1, 0, 0, 0]);
+        tiff.extend_from_slice(&38u32.to_le_bytes());
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&COLOR_SPACE_TAG.to_le_bytes());
+        tiff.extend_from_slice(&[3, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        tiff
+    }
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn chunk(kind: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = (payload.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(payload);
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    #[test]
+    fn test_jpeg_metadata() {
+        let mut jpeg = vec![0xFF, 0xD8];
+        jpeg.extend(segment(0xE1, &[b"Exif\0\0".to_vec(), exif(6)].concat()));
+        jpeg.extend(segment(0xE2, b"ICC_PROFILE\0\x02\x02world"));
+        jpeg.extend(segment(0xE2, b"ICC_PROFILE\0\x01\x02hello "));
+        jpeg.extend(segment(0xC0, &[8, 0, 30, 0, 40, 3]));
+        jpeg.extend(segment(0xDA, &[0; 4]));
+        jpeg.extend_from_slice(&[0x12, 0x34, 0xFF, 0xD9]);
+
+        let metadata = ImageMetadata::read(&jpeg).unwrap();
+        assert_eq!(metadata.stored_size, (40, 30));
+        assert_eq!(metadata.size(), (30, 40));
+        assert_eq!(metadata.orientation.rotation, ImageRotation::Rotate90);
+        // an embedded profile takes precedence over the color space tag.
+        assert_eq!(metadata.color_profile, Some(ColorProfile::Icc(b"hello world".to_vec())));
+
+        // truncated before the frame, so the size is unknown.
+        assert_eq!(ImageMetadata::read(&jpeg[..20]), None);
+    }
+
+    #[test]
+    fn test_png_metadata() {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &[0, 0, 1, 0, 0, 0, 0, 128, 8, 6, 0, 0, 0]));
+        png.extend(chunk(b"eXIf", &exif(3)));
+        png.extend(chunk(b"IDAT", &[0; 10]));
+
+        let metadata = ImageMetadata::read(&png).unwrap();
+        assert_eq!(metadata.size(), (256, 128));
+        assert_eq!(metadata.orientation.rotation, ImageRotation::Rotate180);
+        assert_eq!(metadata.color_profile, Some(ColorProfile::Srgb));
+        assert!(!ColorProfile::Srgb.is_embedded());
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(chunk(b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 6, 0, 0, 0]));
+        png.extend(chunk(b"iCCP", b"Display P3\0\0\x78\x9c"));
+        let metadata = ImageMetadata::read(&png).unwrap();
+        assert!(metadata.orientation.is_identity());
+        assert_eq!(
+            metadata.color_profile,
+            Some(ColorProfile::CompressedIcc { name: "Display P3".into(), data: vec![0x78, 0x9c] })
+        );
+        assert!(metadata.color_profile.unwrap().is_embedded());
+
+        assert_eq!(ImageMetadata::read(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        // each orientation maps the stored corners onto the displayed corners as specified by EXIF.
+        use crate::display::{Point, Rect, Size};
+
+        let dst = Rect::new(Point::new(0.0, 0.0), Size::new(2.0, 1.0));
+        // where the stored top-left corner ends up once displayed (in a 2x1 destination).
+        let expected = [
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(0.0, 1.0),
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(0.0, 1.0),
+        ];
+
+        for (value, expected) in (1..=8).zip(expected.iter()) {
+            let orientation = exif_orientation(value).unwrap();
+            let top_left = orientation.image_rect(dst).origin;
+            let displayed = orientation.transform(dst).transform_point(top_left);
+            assert!((displayed - *expected).length() < 1e-4, "orientation {}", value);
+        }
+
+        assert_eq!(exif_orientation(0), None);
+        assert_eq!(exif_orientation(9), None);
+    }
+}
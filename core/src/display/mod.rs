@@ -3,7 +3,35 @@
 #[cfg(feature = "skia")]
 pub mod skia;
 
-use {crate::error, palette::Srgba, std::sync::Arc};
+pub mod atlas;
+pub mod backend;
+pub mod chrome;
+pub mod geometry;
+pub mod lower;
+pub mod metadata;
+pub mod progressive;
+#[cfg(feature = "serialize")]
+pub mod record;
+pub mod software;
+pub mod tessellation;
+pub mod text;
+#[cfg(feature = "wgpu")]
+pub mod wgpu;
+
+use {
+    crate::{
+        draw::{FrameScheduler, RedrawRequester},
+        error,
+    },
+    palette::Srgba,
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        ops::{Deref, DerefMut},
+        rc::Rc,
+        sync::Arc,
+    },
+};
 
 /// Two-dimensional floating-point absolute point.
 pub type Point = euclid::Point2D<f32, euclid::UnknownUnit>;
@@ -15,6 +43,8 @@ pub type Size = euclid::Size2D<f32, euclid::UnknownUnit>;
 pub type Rect = euclid::Rect<f32, euclid::UnknownUnit>;
 /// An angle in radians.
 pub type Angle = euclid::Angle<f32>;
+/// Two-dimensional affine transformation.
+pub type Transform = euclid::Transform2D<f32, euclid::UnknownUnit, euclid::UnknownUnit>;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ZOrder(pub i32);
@@ -31,17 +61,52 @@ pub struct ZOrder(pub i32);
 /// however this means implementing `GraphicsDisplay` yourself.
 pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
     /// Resizes the underlying surface.
-    fn resize(&mut self, size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>>;
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError>;
 
     /// Creates a new resource for use in rendering.
+    ///
+    /// Failures can be handled independently of the backend through [`ResourceError::kind`](../error/enum.ResourceError.html#method.kind).
     fn new_resource(
         &mut self,
         descriptor: ResourceDescriptor,
     ) -> Result<ResourceReference, error::ResourceError>;
 
+    /// Creates a new resource like [`new_resource`](trait.GraphicsDisplay.html#method.new_resource), except that an image which can't be
+    /// read or decoded is replaced by a [placeholder](enum.ImageData.html#method.placeholder), such that a corrupt file doesn't need fallback drawing code.
+    ///
+    /// The failure is [reported](trait.GraphicsDisplay.html#method.report_error) as a [`BrokenImage`](../error/enum.DisplayError.html#variant.BrokenImage) instead.
+    /// Any other failure (e.g. of a font) is still returned.
+    fn new_resource_or_placeholder(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let image_file = match descriptor {
+            ResourceDescriptor::Image(_) => Some(descriptor.source_file().map(ToOwned::to_owned)),
+            ResourceDescriptor::Font(_) => None,
+        };
+
+        match (self.new_resource(descriptor), image_file) {
+            (Err(error), Some(source_file))
+                if error.kind() == error::DisplayErrorKind::ResourceDecode =>
+            {
+                self.report_error(error::DisplayError::BrokenImage { source_file, error });
+                self.new_resource(ResourceDescriptor::Image(ImageData::placeholder()))
+            }
+            (result, _) => result,
+        }
+    }
+
     /// Removes an existing resource.
     fn remove_resource(&mut self, reference: ResourceReference);
 
+    /// Returns the metadata of an image resource; its size, [orientation](metadata/struct.ImageMetadata.html#structfield.orientation) and color profile.
+    ///
+    /// Displays which decode images apply the orientation as they're decoded, such that photos appear upright.
+    /// Returns `None` if the resource isn't an existing image, or if the display doesn't keep metadata, which is the default.
+    fn image_metadata(&self, _reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        None
+    }
+
     /// Pushes a new command group to the scene, returning the handle which can be used to manipulate it later.
     ///
     /// Normally [`Save`](enum.DisplayCommand.html#variant.Save) and [`Restore`](enum.DisplayCommand.html#variant.Restore) (more specifically an internal `RestoreToCount`) is invoked between command group execution to prevent any leaking
@@ -75,20 +140,521 @@ pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
     /// Keeps a command group alive, additionally possibly moving it to the front (depending on implementation).
     fn maintain_command_group(&mut self, handle: CommandGroupHandle);
 
+    /// Sets a clip which is applied to all the commands of a command group, regardless of the commands themselves.
+    ///
+    /// The clip persists when the command group is modified; passing `None` removes it.
+    /// By default clips are unsupported and ignored.
+    fn set_command_group_clip(&mut self, _handle: CommandGroupHandle, _clip: Option<DisplayClip>) {}
+
+    /// Sets a transformation which is applied to all the commands (and the clip) of a command group,
+    /// mapping the coordinates of the commands to the coordinates of the display.
+    ///
+    /// Like the clip, the transformation persists when the command group is modified.
+    /// Through a [`TransformedDisplay`](struct.TransformedDisplay.html), the transformation is composed with that of the wrapper,
+    /// which replaces it whenever the command group is pushed, modified or maintained through the wrapper;
+    /// [`CommandGroup::set_transform`](struct.CommandGroup.html#method.set_transform) composes it again each time.
+    /// By default transformations are unsupported and ignored.
+    fn set_command_group_transform(&mut self, _handle: CommandGroupHandle, _transform: Transform) {}
+
+    /// Moves a command group to a different layer (by default, command groups are in [`Content`](enum.DisplayLayer.html#variant.Content)).
+    ///
+    /// By default layers are unsupported, and every command group is drawn as if it were in the content layer.
+    fn set_command_group_layer(&mut self, _handle: CommandGroupHandle, _layer: DisplayLayer) {}
+
+    /// Shows or hides all the command groups of a layer. By default this is ignored.
+    fn set_layer_visible(&mut self, _layer: DisplayLayer, _visible: bool) {}
+
+    /// Sets the opacity which a layer is composited with, from `0.0` to `1.0`. By default this is ignored.
+    fn set_layer_opacity(&mut self, _layer: DisplayLayer, _opacity: f32) {}
+
+    /// Clips a command group to a rectangle in display coordinates (a scissor), regardless of its transformation and clip, or removes the scissor.
+    ///
+    /// Unlike [`Clip`](enum.DisplayCommand.html#variant.Clip) commands, which only apply to the rest of their command group,
+    /// this is how a container clips all the drawing of its subtree (e.g. the viewport of a scrolling container);
+    /// see [`TransformedDisplay::with_scissor`](struct.TransformedDisplay.html#method.with_scissor).
+    /// The scissor persists when the command group is modified. By default scissors are unsupported and ignored.
+    fn set_command_group_scissor(&mut self, _handle: CommandGroupHandle, _scissor: Option<Rect>) {}
+
+    /// Renders a command group at `scale` times its resolution and downsamples it when composited (supersampling),
+    /// which improves the quality of fine detail, and of content which is zoomed in through its transform.
+    ///
+    /// The rendered command group may be cached (in the coordinates of its commands) until it's modified, so that
+    /// transforming it doesn't require it to be rendered again. A scale of `1.0` renders the command group directly.
+    /// By default render scales are unsupported and ignored.
+    fn set_command_group_render_scale(&mut self, _handle: CommandGroupHandle, _scale: f32) {}
+
+    /// Names a command group for debugging (e.g. `"titlebar"`), or removes its name.
+    ///
+    /// Labels appear wherever a display exposes its command groups, such as in recorded frames and (with [debug markers](trait.GraphicsDisplay.html#method.set_debug_markers) enabled) in GPU debuggers.
+    /// By default labels are ignored.
+    fn set_command_group_label(&mut self, _handle: CommandGroupHandle, _label: Option<&str>) {}
+
+    /// Returns the label of a command group, if it has one and the display keeps labels.
+    fn command_group_label(&self, _handle: CommandGroupHandle) -> Option<&str> {
+        None
+    }
+
+    /// Sets how gradients and blurs are dithered, except for gradients which set their own [`dithering`](struct.Gradient.html#structfield.dithering).
+    ///
+    /// Nothing is dithered by default, and displays which can't dither ignore this.
+    fn set_dithering(&mut self, _dithering: Dithering) {}
+
+    /// Sets color filters which are applied (in order) to the entire display as it's presented, after everything has been drawn
+    /// (e.g. to offer grayscale or inverted colors for accessibility, without changing any paints). An empty slice removes them.
+    ///
+    /// Returns `false` if the display doesn't support display filters, which is the default.
+    fn set_display_filters(&mut self, _filters: &[DisplayFilter]) -> bool {
+        false
+    }
+
+    /// Sets whether every command group and layer is enclosed in a debug marker (e.g. a `KHR_debug` group) as it's presented,
+    /// such that frame captures of GPU debuggers (e.g. RenderDoc) map onto the widget tree.
+    /// Command groups are named by their [label](trait.GraphicsDisplay.html#method.set_command_group_label) or otherwise by their handle.
+    ///
+    /// Markers are meant for debugging, as they may split up batched drawing.
+    /// Returns `false` if the display doesn't support debug markers, which is the default.
+    fn set_debug_markers(&mut self, _enabled: bool) -> bool {
+        false
+    }
+
+    /// Returns the ratio of physical pixels to logical pixels of the surface, which is `1.0` by default.
+    fn scale_factor(&self) -> f64 {
+        1.0
+    }
+
+    /// Sets the ratio of physical pixels to logical pixels of the surface, e.g. when the window is moved to a monitor with a different DPI.
+    ///
+    /// Anything rasterized at the previous scale factor (glyphs, supersampled command groups, etc) is discarded and everything is drawn again,
+    /// such that text stays crisp. See [`change_scale_factor`](../draw/fn.change_scale_factor.html) to notify widgets as well.
+    /// By default the scale factor is ignored.
+    fn set_scale_factor(&mut self, _scale_factor: f64) {}
+
+    /// Same as [`modify_command_group`](trait.GraphicsDisplay.html#tymethod.modify_command_group), however the command group keeps its position in the draw order.
+    ///
+    /// By default this is identical to `modify_command_group`.
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.modify_command_group(handle, commands, z_order, protected, always_alive)
+    }
+
+    /// Same as [`maintain_command_group`](trait.GraphicsDisplay.html#tymethod.maintain_command_group), however the command group is never moved to the front.
+    ///
+    /// By default this is identical to `maintain_command_group`.
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.maintain_command_group(handle)
+    }
+
+    /// Applies maintenance without drawing anything; command groups which weren't maintained since the last present
+    /// (or call) are removed, as they would be by [`present`](trait.GraphicsDisplay.html#tymethod.present).
+    ///
+    /// By default command groups are only maintained as the display is presented.
+    fn expire_command_groups(&mut self) {}
+
+    /// Returns the number of times maintenance was applied (by [`present`](trait.GraphicsDisplay.html#tymethod.present) or
+    /// [`expire_command_groups`](trait.GraphicsDisplay.html#method.expire_command_groups)), if the display counts it.
+    ///
+    /// A transient command group which wasn't maintained since before the last two maintenances has been removed,
+    /// which is how a [`CommandGroup`](struct.CommandGroup.html) knows to push its commands anew.
+    /// By default maintenance isn't counted.
+    fn maintenance_count(&self) -> Option<u64> {
+        None
+    }
+
     /// Executes pre-exit routines.
     ///
     /// In a GPU implementation, for example, this may wait for the device to finish any remaining draw calls.
     fn before_exit(&mut self);
 
+    /// Tears down the display, returning the resources and command groups which were still alive.
+    ///
+    /// Long-running applications can use this to verify that they aren't leaking resources.
+    /// The display shouldn't be used after this has been called.
+    ///
+    /// By default this only invokes [`before_exit`](trait.GraphicsDisplay.html#tymethod.before_exit) and reports nothing.
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+        LeakReport::default()
+    }
+
+    /// Starts (or with `None`, stops) capturing every presented frame, returning `false` if the display doesn't support capturing.
+    ///
+    /// This is useful for recording demos and visual regression tests. By default capturing is unsupported.
+    fn set_frame_capture(&mut self, _capture: Option<FrameCapture>) -> bool {
+        false
+    }
+
+    /// Reports an error which occurred while drawing (e.g. failing to create a resource).
+    ///
+    /// Since [`Widget::draw`](../widget/trait.Widget.html#method.draw) can't fail, this is how widgets surface errors to the application,
+    /// which collects them through [`take_errors`](trait.GraphicsDisplay.html#method.take_errors) (typically after drawing the frame).
+    /// By default, reported errors are discarded.
+    fn report_error(&mut self, _error: error::DisplayError) {}
+
+    /// Returns (and forgets) the errors reported since the last call.
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        Vec::new()
+    }
+
+    /// Returns (and resets) the region of the display which changed since the last call,
+    /// as tracked through changes to command groups and layers (e.g. when a [`CommandGroup`](struct.CommandGroup.html) is repainted).
+    ///
+    /// By default damage isn't tracked, so everything is reported as damaged.
+    fn take_damage(&mut self) -> Damage {
+        Damage::Full
+    }
+
     /// Displays the entire scene, optionally with a cull.
+    ///
+    /// If a cull is given, only command groups intersecting it are drawn and the pixels outside of it
+    /// are left untouched, meaning that only the culled region of the surface needs to be swapped.
     fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError>;
+
+    /// Presents only the region which changed since the last present (see [`take_damage`](trait.GraphicsDisplay.html#method.take_damage)),
+    /// skipping the present entirely if nothing changed, and returns the presented damage.
+    ///
+    /// Command groups which weren't maintained count as damage, since presenting removes them. Should nothing have changed,
+    /// the command groups are still [maintained](trait.GraphicsDisplay.html#method.expire_command_groups), such that they have to be maintained again.
+    /// This relies on the surface keeping its pixels between presents, so that only the damage needs to be redrawn.
+    fn present_damage(&mut self) -> Result<Damage, error::DisplayError> {
+        let damage = self.take_damage();
+        match damage {
+            Damage::Unchanged => self.expire_command_groups(),
+            Damage::Region(region) => self.present(Some(region))?,
+            Damage::Full => self.present(None)?,
+        }
+        Ok(damage)
+    }
+}
+
+impl dyn GraphicsDisplay + '_ {
+    /// Returns the commands of an existing command group, scaled down to fit within `dst`
+    /// (see [`preview_display_list`](fn.preview_display_list.html)), or `None` if the command group doesn't exist.
+    ///
+    /// The preview doesn't include the clip or transformation set on the command group itself.
+    ///
+    /// ```ignore
+    /// if let Some(preview) = display.command_group_preview(canvas_handle, minimap_bounds) {
+    ///     self.minimap.repaint();
+    ///     self.minimap.push(display, &preview, ZOrder::default(), None, None);
+    /// }
+    /// ```
+    pub fn command_group_preview(
+        &self,
+        handle: CommandGroupHandle,
+        dst: Rect,
+    ) -> Option<Vec<DisplayCommand>> {
+        let commands = self.get_command_group(handle)?;
+        let source = display_list_bounds(commands).unwrap_or_default();
+        Some(preview_display_list(commands, source, dst))
+    }
+}
+
+impl<D: Sized> dyn GraphicsDisplay<D> + '_ {
+    /// Unwraps `result`, [reporting](trait.GraphicsDisplay.html#method.report_error) the error (if any) instead.
+    ///
+    /// ```ignore
+    /// let image = display.new_resource(descriptor);
+    /// self.image = display.report(image);
+    /// ```
+    pub fn report<T, E: Into<error::DisplayError>>(&mut self, result: Result<T, E>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.report_error(error.into());
+                None
+            }
+        }
+    }
+}
+
+/// A resource which was still alive when a display was shut down.
+#[derive(Debug, Clone)]
+pub struct LeakedResource {
+    pub reference: ResourceReference,
+    /// Where the resource was created.
+    /// This is only captured in debug builds, and only if backtraces are enabled (i.e. through `RUST_BACKTRACE`).
+    pub backtrace: Option<String>,
 }
 
-/// Resource data, either as a file or an in-memory buffer.
+/// A single captured frame.
 #[derive(Debug, Clone)]
+pub struct CapturedFrame {
+    /// Number of the frame since capturing started, starting at zero.
+    pub index: u64,
+    pub size: (u32, u32),
+    /// Pixels of the entire surface, row by row from the top, as unpremultiplied RGBA (see [`RasterImageFormat::Rgba8`](enum.RasterImageFormat.html#variant.Rgba8)).
+    pub pixels: Vec<u8>,
+}
+
+/// Region of a display which changed and has to be redrawn, accumulated between presents.
+///
+/// See [`take_damage`](trait.GraphicsDisplay.html#method.take_damage).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Damage {
+    /// Nothing changed.
+    #[default]
+    Unchanged,
+    /// Only the region within the rectangle (in display coordinates) changed.
+    Region(Rect),
+    /// The entire display changed (e.g. it was resized).
+    Full,
+}
+
+impl Damage {
+    /// Adds a changed region. Empty rectangles are ignored.
+    pub fn add(&mut self, rect: Rect) {
+        if rect.is_empty_or_negative() {
+            return;
+        }
+
+        *self = match *self {
+            Damage::Unchanged => Damage::Region(rect),
+            Damage::Region(region) => Damage::Region(region.union(&rect)),
+            Damage::Full => Damage::Full,
+        };
+    }
+
+    /// Adds the region covered by a command group, given its bounds (as from [`display_list_bounds`](fn.display_list_bounds.html)), clip and transformation.
+    ///
+    /// Command groups which [`Clear`](enum.DisplayCommand.html#variant.Clear) cover their entire clip, or the entire display if unclipped.
+    pub fn add_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        bounds: Rect,
+        clip: Option<&DisplayClip>,
+        transform: &Transform,
+    ) {
+        let clears = commands.iter().any(|command| matches!(command, DisplayCommand::Clear(_)));
+        let bounds = match (clears, clip) {
+            (true, None) => return self.invalidate_all(),
+            (true, Some(clip)) => clip.bounds(),
+            (false, Some(clip)) => bounds.intersection(&clip.bounds()).unwrap_or_default(),
+            (false, None) => bounds,
+        };
+        self.add(transform.transform_rect(&bounds));
+    }
+
+    /// Marks the entire display as changed.
+    #[inline]
+    pub fn invalidate_all(&mut self) {
+        *self = Damage::Full;
+    }
+
+    /// Adds all the changes of `other`.
+    pub fn union(&mut self, other: Damage) {
+        match other {
+            Damage::Unchanged => {}
+            Damage::Region(region) => self.add(region),
+            Damage::Full => self.invalidate_all(),
+        }
+    }
+
+    #[inline]
+    pub fn is_unchanged(&self) -> bool {
+        *self == Damage::Unchanged
+    }
+}
+
+/// Where presented frames are captured to, as set by [`set_frame_capture`](trait.GraphicsDisplay.html#method.set_frame_capture).
+pub enum FrameCapture {
+    /// Every frame is passed to a closure (e.g. to collect frames into a buffer or feed a video encoder).
+    Sink(Box<dyn FnMut(CapturedFrame)>),
+    /// Every frame is written to `directory` as a numbered PNG file (`frame-00000.png`, `frame-00001.png`, ...).
+    PngSequence(std::path::PathBuf),
+}
+
+impl FrameCapture {
+    /// Returns the path of the PNG of the `index`-th frame within `directory`.
+    pub fn png_path(directory: &std::path::Path, index: u64) -> std::path::PathBuf {
+        directory.join(format!("frame-{:05}.png", index))
+    }
+}
+
+impl std::fmt::Debug for FrameCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameCapture::Sink(_) => f.write_str("Sink"),
+            FrameCapture::PngSequence(directory) => {
+                f.debug_tuple("PngSequence").field(directory).finish()
+            }
+        }
+    }
+}
+
+/// Everything which was still alive when a display was shut down.
+///
+/// See [`shutdown`](trait.GraphicsDisplay.html#method.shutdown).
+#[derive(Debug, Clone, Default)]
+pub struct LeakReport {
+    pub resources: Vec<LeakedResource>,
+    pub command_groups: Vec<CommandGroupHandle>,
+}
+
+impl LeakReport {
+    /// Returns `true` if nothing was leaked.
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty() && self.command_groups.is_empty()
+    }
+}
+
+/// Resource data, either as a file, an in-memory buffer or a stream.
+///
+/// More variants may be added (and some only exist with certain features, such as `MappedFile` with `mmap`), so matches need a wildcard arm.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum ResourceData {
     File(std::path::PathBuf),
+    /// A file which is memory-mapped rather than read into a buffer, so that large files aren't duplicated in memory before decoding.
+    ///
+    /// The file is only mapped while the resource is loaded; backends copy whatever they keep of it.
+    #[cfg(feature = "mmap")]
+    MappedFile(std::path::PathBuf),
     Data(SharedData),
+    /// Data read from a stream (e.g. a socket or an entry of an archive), such that it needn't be written to a file first.
+    Stream(ResourceStream),
+}
+
+impl ResourceData {
+    /// Loads the data and invokes `f` with the bytes.
+    ///
+    /// Backends should load resource data through this, so that each variant is treated the same.
+    pub fn with_bytes<R>(&self, f: impl FnOnce(&[u8]) -> R) -> Result<R, error::ResourceError> {
+        let check_file = |path: &std::path::Path| {
+            if path.is_file() {
+                Ok(())
+            } else {
+                Err(error::ResourceError::InvalidPath(path.to_string_lossy().to_string()))
+            }
+        };
+
+        Ok(match self {
+            ResourceData::File(path) => {
+                check_file(path)?;
+                f(&std::fs::read(path)?)
+            }
+            #[cfg(feature = "mmap")]
+            ResourceData::MappedFile(path) => {
+                check_file(path)?;
+                match map_file(path)? {
+                    Some(map) => f(&map),
+                    None => f(&[]),
+                }
+            }
+            ResourceData::Data(data) => f(data),
+            ResourceData::Stream(stream) => f(&stream.load()?),
+        })
+    }
+
+    /// Creates resource data which is read from `reader`.
+    pub fn from_reader(reader: impl std::io::Read + Send + 'static) -> Self {
+        ResourceData::Stream(ResourceStream::new(reader))
+    }
+
+    /// Reads files and streams into memory, such that creating a resource from the data doesn't block on I/O.
+    ///
+    /// This blocks, so that it can be run in the background (e.g. through a [`TaskSpawner`](../task/struct.TaskSpawner.html)) before the resource is created on the UI thread:
+    /// ```ignore
+    /// aux.tasks.spawn(async move { data.into_loaded().ok() }, &self.image_loaded);
+    /// ```
+    pub fn into_loaded(self) -> Result<ResourceData, error::ResourceError> {
+        match self {
+            ResourceData::Data(_) => Ok(self),
+            ResourceData::Stream(stream) => {
+                Ok(ResourceData::Data(SharedData::RefCount(stream.load()?)))
+            }
+            _ => Ok(ResourceData::Data(self.with_bytes(|bytes| bytes.to_vec())?.into())),
+        }
+    }
+}
+
+// maps a file into memory, or returns `None` if it's empty (since empty files can't be mapped).
+// the mapping must not outlive `with_bytes`, since the file may be edited (e.g. when hot-reloaded) and faults when read if truncated.
+#[cfg(feature = "mmap")]
+fn map_file(path: &std::path::Path) -> Result<Option<memmap::Mmap>, error::ResourceError> {
+    let file = std::fs::File::open(path)?;
+    if file.metadata()?.len() == 0 {
+        return Ok(None);
+    }
+    // safety: the file is assumed not to be modified while it's mapped.
+    Ok(Some(unsafe { memmap::Mmap::map(&file)? }))
+}
+
+impl From<ResourceStream> for ResourceData {
+    fn from(stream: ResourceStream) -> Self {
+        ResourceData::Stream(stream)
+    }
+}
+
+/// A stream which is read to the end the first time the data of a resource is needed.
+///
+/// The stream is only read once; clones share the data which was read (or the error which occurred while reading).
+#[derive(Clone)]
+pub struct ResourceStream(Arc<std::sync::Mutex<StreamState>>);
+
+enum StreamState {
+    Pending(Box<dyn std::io::Read + Send>),
+    Loaded(Arc<Vec<u8>>),
+    Failed(std::io::ErrorKind, String),
+}
+
+impl ResourceStream {
+    pub fn new(reader: impl std::io::Read + Send + 'static) -> Self {
+        ResourceStream(Arc::new(std::sync::Mutex::new(StreamState::Pending(Box::new(reader)))))
+    }
+
+    /// Returns `true` if the stream has been read to the end.
+    pub fn is_loaded(&self) -> bool {
+        matches!(*self.state(), StreamState::Loaded(_))
+    }
+
+    /// Reads the stream to the end, unless it's already been read, and returns the data.
+    pub fn load(&self) -> Result<Arc<Vec<u8>>, error::ResourceError> {
+        let mut state = self.state();
+        if let StreamState::Pending(reader) = &mut *state {
+            let mut data = Vec::new();
+            *state = match reader.read_to_end(&mut data) {
+                Ok(_) => StreamState::Loaded(Arc::new(data)),
+                Err(err) => StreamState::Failed(err.kind(), err.to_string()),
+            };
+        }
+
+        match &*state {
+            StreamState::Loaded(data) => Ok(data.clone()),
+            StreamState::Failed(kind, message) => {
+                Err(std::io::Error::new(*kind, message.clone()).into())
+            }
+            StreamState::Pending(_) => unreachable!(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, StreamState> {
+        // a panicking reader leaves the stream pending, which is still consistent.
+        self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl std::fmt::Debug for ResourceStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceStream").field("loaded", &self.is_loaded()).finish()
+    }
+}
+
+impl From<&'static [u8]> for ResourceData {
+    fn from(data: &'static [u8]) -> Self {
+        ResourceData::Data(data.into())
+    }
+}
+
+impl From<Vec<u8>> for ResourceData {
+    fn from(data: Vec<u8>) -> Self {
+        ResourceData::Data(data.into())
+    }
 }
 
 /// Whether the given image data is encoded.
@@ -99,8 +665,38 @@ pub enum ImageData {
     Raw(ResourceData, RasterImageInfo),
 }
 
+impl ImageData {
+    /// Reads the metadata of the image, returning `None` if the format of an encoded image isn't understood.
+    ///
+    /// Raw images have neither an orientation nor a color profile.
+    pub fn metadata(&self) -> Result<Option<metadata::ImageMetadata>, error::ResourceError> {
+        match self {
+            ImageData::Encoded(data) => data.with_bytes(metadata::ImageMetadata::read),
+            ImageData::Raw(_, info) => Ok(Some(metadata::ImageMetadata::new(info.size))),
+        }
+    }
+
+    /// Size of the [placeholder](enum.ImageData.html#method.placeholder) image.
+    pub const PLACEHOLDER_SIZE: (u32, u32) = (16, 16);
+
+    /// A raw image which stands in for one that failed to load; a magenta and black checkerboard, which is hard to mistake for content.
+    pub fn placeholder() -> Self {
+        let (width, height) = ImageData::PLACEHOLDER_SIZE;
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x / 8 + y / 8) % 2 == 0))
+            .flat_map(|magenta| if magenta { [255, 0, 255, 255] } else { [0, 0, 0, 255] })
+            .collect::<Vec<u8>>();
+
+        ImageData::Raw(
+            pixels.into(),
+            RasterImageInfo { size: ImageData::PLACEHOLDER_SIZE, format: RasterImageFormat::Rgba8 },
+        )
+    }
+}
+
 /// How pixels are stored in memory.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum RasterImageFormat {
     /// 4x8-bit components, in order of; red, green, blue and alpha.
     Rgba8,
@@ -110,6 +706,7 @@ pub enum RasterImageFormat {
 
 /// Information about a raster image.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct RasterImageInfo {
     pub size: (u32, u32),
     pub format: RasterImageFormat,
@@ -122,10 +719,29 @@ pub enum ResourceDescriptor {
     Font(ResourceData),
 }
 
+impl ResourceDescriptor {
+    /// Returns the file the resource is loaded from, if any.
+    pub fn source_file(&self) -> Option<&std::path::Path> {
+        let data = match self {
+            ResourceDescriptor::Image(ImageData::Encoded(data))
+            | ResourceDescriptor::Image(ImageData::Raw(data, _))
+            | ResourceDescriptor::Font(data) => data,
+        };
+
+        match data {
+            ResourceData::File(path) => Some(path),
+            #[cfg(feature = "mmap")]
+            ResourceData::MappedFile(path) => Some(path),
+            ResourceData::Data(_) | ResourceData::Stream(_) => None,
+        }
+    }
+}
+
 /// Contains a tagged ID to an existing resource, created through [`new_resource`](trait.GraphicsDisplay.html#method.new_resource).
 ///
 /// This is used to references resources in draw commands and to remove resources through [`remove_resource`](trait.GraphicsDisplay.html#method.remove_resource).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum ResourceReference {
     Image(u64),
     Font(u64),
@@ -138,15 +754,134 @@ impl ResourceReference {
             ResourceReference::Image(id) | ResourceReference::Font(id) => *id,
         }
     }
+
+    /// Converts this into a [`SharedResource`](struct.SharedResource.html), whose resource is removed once all its clones are dropped.
+    pub fn shared(self, reaper: &ResourceReaper) -> SharedResource {
+        SharedResource(Rc::new(ResourceOwner { reference: Some(self), reaper: reaper.clone() }))
+    }
+}
+
+/// Collects the references of dropped [`SharedResource`](struct.SharedResource.html)s so that their resources can be removed from a display.
+///
+/// As with [`CommandGroupReaper`](struct.CommandGroupReaper.html), dropping a resource doesn't give access to the display,
+/// hence removal is deferred until [`reap`](struct.ResourceReaper.html#method.reap) is called (typically once per frame, before presenting).
+#[derive(Debug, Clone, Default)]
+pub struct ResourceReaper(Rc<RefCell<Vec<ResourceReference>>>);
+
+impl ResourceReaper {
+    /// Creates a new reaper with no pending removals.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a new resource which is removed once the returned [`SharedResource`](struct.SharedResource.html) and all its clones are dropped.
+    pub fn new_resource<D: Sized>(
+        &self,
+        display: &mut dyn GraphicsDisplay<D>,
+        descriptor: ResourceDescriptor,
+    ) -> Result<SharedResource, error::ResourceError> {
+        Ok(display.new_resource(descriptor)?.shared(self))
+    }
+
+    /// Removes all the resources which have been released since the last call.
+    pub fn reap<D: Sized>(&self, display: &mut dyn GraphicsDisplay<D>) {
+        for reference in self.0.borrow_mut().drain(..) {
+            display.remove_resource(reference);
+        }
+    }
+
+    /// Returns the number of resources waiting to be removed.
+    pub fn pending(&self) -> usize {
+        self.0.borrow().len()
+    }
+}
+
+#[derive(Debug)]
+struct ResourceOwner {
+    // `None` once released from the reaper.
+    reference: Option<ResourceReference>,
+    reaper: ResourceReaper,
+}
+
+impl Drop for ResourceOwner {
+    fn drop(&mut self) {
+        if let Some(reference) = self.reference.take() {
+            self.reaper.0.borrow_mut().push(reference);
+        }
+    }
+}
+
+/// A reference-counted [`ResourceReference`](enum.ResourceReference.html), whose resource is removed once the last clone is dropped.
+///
+/// Removal goes through a [`ResourceReaper`](struct.ResourceReaper.html), such that images and fonts which are no longer used
+/// don't stay loaded for as long as the display lives.
+#[derive(Debug, Clone)]
+pub struct SharedResource(Rc<ResourceOwner>);
+
+impl SharedResource {
+    /// Returns the reference to use in display commands, which stays valid as long as this (or a clone) is alive.
+    #[inline]
+    pub fn reference(&self) -> ResourceReference {
+        self.0.reference.expect("shared resource was released")
+    }
+
+    /// Returns the number of clones sharing the resource (including this one).
+    #[inline]
+    pub fn count(&self) -> usize {
+        Rc::strong_count(&self.0)
+    }
+
+    /// Releases the resource, such that it's no longer removed on drop, or returns this back if it has other clones.
+    pub fn into_inner(self) -> Result<ResourceReference, Self> {
+        let mut owner = Rc::try_unwrap(self.0).map_err(SharedResource)?;
+        Ok(owner.reference.take().expect("shared resource was released"))
+    }
+}
+
+impl PartialEq for SharedResource {
+    fn eq(&self, other: &Self) -> bool {
+        self.reference() == other.reference()
+    }
+}
+
+impl From<&SharedResource> for ResourceReference {
+    #[inline]
+    fn from(resource: &SharedResource) -> Self {
+        resource.reference()
+    }
 }
 
 /// Data stored as bytes, either in a atomically reference counted `Vec` or a static reference.
 #[derive(Debug, Clone)]
 pub enum SharedData {
     RefCount(Arc<Vec<u8>>),
+    /// Borrowed data which is never copied by Reclutch itself, such as that of `include_bytes!`.
     Static(&'static [u8]),
 }
 
+impl Deref for SharedData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            SharedData::RefCount(data) => data,
+            SharedData::Static(data) => data,
+        }
+    }
+}
+
+impl From<&'static [u8]> for SharedData {
+    fn from(data: &'static [u8]) -> Self {
+        SharedData::Static(data)
+    }
+}
+
+impl From<Vec<u8>> for SharedData {
+    fn from(data: Vec<u8>) -> Self {
+        SharedData::RefCount(Arc::new(data))
+    }
+}
+
 /// Pushes or modifies a command group, depending on whether `handle` contains a value or not.
 /// This means that if `handle` did not contain a value, [`push_command_group`](trait.GraphicsDisplay.html#method.push_command_group) will be called and `handle` will be assigned to the returned handle.
 pub fn ok_or_push<D: Sized>(
@@ -173,27 +908,363 @@ pub fn ok_or_push<D: Sized>(
                 .ok();
         }
     }
-}
+}
+
+/// Handle to a command group within a [`GraphicsDisplay`](trait.GraphicsDisplay.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandGroupHandle(u64);
+
+impl CommandGroupHandle {
+    /// Creates a new `CommandGroupHandle`, with the inner ID set to `id`.
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Returns the inner ID.
+    pub fn id(self) -> u64 {
+        self.0
+    }
+}
+
+/// How a [`CommandGroup`](struct.CommandGroup.html) is placed in the draw order when it is repainted or maintained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RepaintMode {
+    /// The command group may be moved to the front of its z-order (depending on implementation).
+    #[default]
+    Front,
+    /// The command group keeps the position it was first pushed at within its z-order.
+    InPlace,
+}
+
+/// Wraps a display such that all the command groups pushed through it are transformed by `transform`.
+///
+/// This is how a widget with a [local coordinate space](../widget/trait.Widget.html#method.transform) is drawn;
+/// see [`draw_child`](../widget/fn.draw_child.html). Wrappers can be nested, in which case the transformations are composed
+/// and the scissors are intersected.
+pub struct TransformedDisplay<'a, D: Sized = DisplayCommand> {
+    display: &'a mut dyn GraphicsDisplay<D>,
+    transform: Transform,
+    render_scale: Option<f32>,
+    scissor: Option<Rect>,
+}
+
+impl<'a, D: Sized> TransformedDisplay<'a, D> {
+    /// Wraps `display`, transforming everything pushed through it by `transform`.
+    pub fn new(display: &'a mut dyn GraphicsDisplay<D>, transform: Transform) -> Self {
+        TransformedDisplay { display, transform, render_scale: None, scissor: None }
+    }
+
+    /// Additionally clips all the command groups pushed through the wrapper to `scissor`, in display coordinates,
+    /// such that a container can clip the drawing of its whole subtree (see [`set_command_group_scissor`](trait.GraphicsDisplay.html#method.set_command_group_scissor)).
+    ///
+    /// The scissor is given to command groups whenever they're pushed or maintained, so it follows a scrolling viewport without repaints.
+    pub fn with_scissor(mut self, scissor: Rect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    /// Additionally renders all the command groups pushed through the wrapper at `scale` times their resolution,
+    /// such that a whole widget subtree is supersampled.
+    ///
+    /// The scale is given to command groups when they're pushed, and command groups which request a higher scale themselves keep it.
+    /// See [`set_command_group_render_scale`](trait.GraphicsDisplay.html#method.set_command_group_render_scale).
+    pub fn with_render_scale(mut self, scale: f32) -> Self {
+        self.render_scale = Some(scale);
+        self
+    }
+
+    /// Returns the transformation applied to command groups.
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Returns the render scale applied to command groups, if any.
+    #[inline]
+    pub fn render_scale(&self) -> Option<f32> {
+        self.render_scale
+    }
+
+    /// Returns the scissor applied to command groups, if any.
+    #[inline]
+    pub fn scissor(&self) -> Option<Rect> {
+        self.scissor
+    }
+}
+
+impl<'a, D: Sized> GraphicsDisplay<D> for TransformedDisplay<'a, D> {
+    #[inline]
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.display.resize(size)
+    }
+
+    #[inline]
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        self.display.new_resource(descriptor)
+    }
+
+    #[inline]
+    fn new_resource_or_placeholder(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        self.display.new_resource_or_placeholder(descriptor)
+    }
+
+    #[inline]
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.display.remove_resource(reference)
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+        let handle = self.display.push_command_group(commands, z_order, protected, always_alive)?;
+        self.display.set_command_group_transform(handle, self.transform);
+        if let Some(scale) = self.render_scale {
+            self.display.set_command_group_render_scale(handle, scale);
+        }
+        if self.scissor.is_some() {
+            self.display.set_command_group_scissor(handle, self.scissor);
+        }
+        Ok(handle)
+    }
+
+    #[inline]
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[D]> {
+        self.display.get_command_group(handle)
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.display.modify_command_group(handle, commands, z_order, protected, always_alive);
+        self.display.set_command_group_transform(handle, self.transform);
+        if self.scissor.is_some() {
+            self.display.set_command_group_scissor(handle, self.scissor);
+        }
+    }
+
+    #[inline]
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.display.remove_command_group(handle)
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.display.maintain_command_group(handle);
+        // the transform (and scissor) may have changed without the command group being repainted.
+        self.display.set_command_group_transform(handle, self.transform);
+        if self.scissor.is_some() {
+            self.display.set_command_group_scissor(handle, self.scissor);
+        }
+    }
+
+    #[inline]
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.display.set_command_group_clip(handle, clip)
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.display.set_command_group_transform(handle, transform.post_transform(&self.transform))
+    }
+
+    #[inline]
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.display.set_command_group_layer(handle, layer)
+    }
+
+    #[inline]
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        self.display.set_layer_visible(layer, visible)
+    }
+
+    #[inline]
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        self.display.set_layer_opacity(layer, opacity)
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        let scissor = match (self.scissor, scissor) {
+            (Some(own), Some(scissor)) => Some(own.intersection(&scissor).unwrap_or_default()),
+            (own, scissor) => own.or(scissor),
+        };
+        self.display.set_command_group_scissor(handle, scissor)
+    }
+
+    fn set_command_group_render_scale(&mut self, handle: CommandGroupHandle, scale: f32) {
+        let scale = self.render_scale.map_or(scale, |own| own.max(scale));
+        self.display.set_command_group_render_scale(handle, scale)
+    }
+
+    #[inline]
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        self.display.set_command_group_label(handle, label)
+    }
+
+    #[inline]
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.display.command_group_label(handle)
+    }
+
+    #[inline]
+    fn image_metadata(&self, reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        self.display.image_metadata(reference)
+    }
+
+    #[inline]
+    fn set_dithering(&mut self, dithering: Dithering) {
+        self.display.set_dithering(dithering)
+    }
+
+    #[inline]
+    fn set_display_filters(&mut self, filters: &[DisplayFilter]) -> bool {
+        self.display.set_display_filters(filters)
+    }
+
+    #[inline]
+    fn set_debug_markers(&mut self, enabled: bool) -> bool {
+        self.display.set_debug_markers(enabled)
+    }
+
+    #[inline]
+    fn scale_factor(&self) -> f64 {
+        self.display.scale_factor()
+    }
+
+    #[inline]
+    fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.display.set_scale_factor(scale_factor)
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.display.modify_command_group_in_place(
+            handle,
+            commands,
+            z_order,
+            protected,
+            always_alive,
+        );
+        self.display.set_command_group_transform(handle, self.transform);
+    }
 
-/// Handle to a command group within a [`GraphicsDisplay`](trait.GraphicsDisplay.html).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct CommandGroupHandle(u64);
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.display.maintain_command_group_in_place(handle);
+        self.display.set_command_group_transform(handle, self.transform);
+    }
 
-impl CommandGroupHandle {
-    /// Creates a new `CommandGroupHandle`, with the inner ID set to `id`.
-    pub fn new(id: u64) -> Self {
-        Self(id)
+    #[inline]
+    fn expire_command_groups(&mut self) {
+        self.display.expire_command_groups()
     }
 
-    /// Returns the inner ID.
-    pub fn id(self) -> u64 {
-        self.0
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        self.display.maintenance_count()
+    }
+
+    #[inline]
+    fn before_exit(&mut self) {
+        self.display.before_exit()
+    }
+
+    #[inline]
+    fn shutdown(&mut self) -> LeakReport {
+        self.display.shutdown()
+    }
+
+    #[inline]
+    fn set_frame_capture(&mut self, capture: Option<FrameCapture>) -> bool {
+        self.display.set_frame_capture(capture)
+    }
+
+    #[inline]
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.display.report_error(error)
+    }
+
+    #[inline]
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        self.display.take_errors()
+    }
+
+    #[inline]
+    fn take_damage(&mut self) -> Damage {
+        self.display.take_damage()
+    }
+
+    #[inline]
+    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+        self.display.present(cull)
     }
 }
 
+/// Layers which command groups are assigned to.
+///
+/// Layers are rendered in the order declared here, so the z-order of a command group is only relative to the other command groups in its layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum DisplayLayer {
+    Background,
+    #[default]
+    Content,
+    /// Popups, tooltips, menus, etc.
+    Overlay,
+    Debug,
+}
+
 /// Helper wrapper around [`CommandGroupHandle`](struct.CommandGroupHandle.html).
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct CommandGroup(Option<CommandGroupHandle>, bool);
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandGroup {
+    handle: Option<CommandGroupHandle>,
+    repaint: bool,
+    mode: RepaintMode,
+    clip: Option<DisplayClip>,
+    clip_changed: bool,
+    transform: Transform,
+    transform_changed: bool,
+    layer: DisplayLayer,
+    layer_changed: bool,
+    render_scale: f32,
+    render_scale_changed: bool,
+    label: Option<String>,
+    label_changed: bool,
+    scheduler: Option<RedrawRequester>,
+    // the maintenance count of the display when the command group was last pushed or maintained, unless it's always alive.
+    maintained_at: Option<u64>,
+}
+
+// the clip, transformation and render scale are compared as floats, which are never NaN in a meaningful command group.
+impl Eq for CommandGroup {}
+
+impl std::hash::Hash for CommandGroup {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        // the properties which don't involve floats are enough to tell command groups apart.
+        self.handle.hash(state);
+        self.repaint.hash(state);
+        self.mode.hash(state);
+        self.layer.hash(state);
+        self.label.hash(state);
+    }
+}
 
 impl Default for CommandGroup {
     fn default() -> Self {
@@ -204,11 +1275,45 @@ impl Default for CommandGroup {
 impl CommandGroup {
     /// Creates a new, empty command group.
     pub fn new() -> Self {
-        CommandGroup(None, true)
+        Self::with_mode(RepaintMode::Front)
+    }
+
+    /// Creates a new, empty command group which is repainted and maintained according to `mode`.
+    pub fn with_mode(mode: RepaintMode) -> Self {
+        CommandGroup {
+            handle: None,
+            repaint: true,
+            mode,
+            clip: None,
+            clip_changed: false,
+            transform: Transform::identity(),
+            transform_changed: false,
+            layer: DisplayLayer::default(),
+            layer_changed: false,
+            render_scale: 1.0,
+            render_scale_changed: false,
+            label: None,
+            label_changed: false,
+            scheduler: None,
+            maintained_at: None,
+        }
+    }
+
+    /// Creates a new, empty command group named `label` for debugging.
+    ///
+    /// See [`set_label`](struct.CommandGroup.html#method.set_label).
+    pub fn with_label(label: impl Into<String>) -> Self {
+        let mut command_group = Self::new();
+        command_group.set_label(label);
+        command_group
     }
 
     /// Pushes a list of commands if the repaint flag is set, and resets repaint flag if so.
     ///
+    /// Should the display have removed the command group (as it does with command groups which weren't maintained),
+    /// it's pushed anew regardless of the repaint flag. This is only known of displays which
+    /// [count maintenance](trait.GraphicsDisplay.html#method.maintenance_count).
+    ///
     /// See [`push_command_group`](trait.GraphicsDisplay.html#method.push_command_group).
     /// Also see [`push_with`](struct.CommandGroup.html#method.push_with), which is more efficient.
     pub fn push<D: Sized>(
@@ -219,12 +1324,17 @@ impl CommandGroup {
         protected: impl Into<Option<bool>>,
         always_alive: impl Into<Option<bool>>,
     ) {
-        if self.1 {
-            self.1 = false;
-            ok_or_push(&mut self.0, display, commands, z_order, protected, always_alive);
+        let always_alive = always_alive.into();
+        self.reclaim(display);
+
+        if self.repaint {
+            self.repaint = false;
+            self.ok_or_push(display, commands, z_order, protected.into(), always_alive);
         } else {
-            display.maintain_command_group(self.0.unwrap());
+            self.maintain(display);
         }
+
+        self.record_maintenance(display, always_alive);
     }
 
     /// Almost identical to [`push`](struct.CommandGroup.html#method.push), however
@@ -242,31 +1352,345 @@ impl CommandGroup {
     ) where
         F: FnOnce() -> Vec<D>,
     {
-        if self.1 {
-            self.1 = false;
-            ok_or_push(&mut self.0, display, &f(), z_order, protected, always_alive);
+        let always_alive = always_alive.into();
+        self.reclaim(display);
+
+        if self.repaint {
+            self.repaint = false;
+            self.ok_or_push(display, &f(), z_order, protected.into(), always_alive);
         } else {
-            display.maintain_command_group(self.0.unwrap());
+            self.maintain(display);
+        }
+
+        self.record_maintenance(display, always_alive);
+    }
+
+    // a command group which went unmaintained through an entire frame (e.g. while its widget was hidden) has been removed by the display,
+    // so it's pushed anew.
+    fn reclaim<D: Sized>(&mut self, display: &dyn GraphicsDisplay<D>) {
+        let expired = match (self.maintained_at, display.maintenance_count()) {
+            (Some(maintained_at), Some(count)) => count > maintained_at + 1,
+            _ => false,
+        };
+
+        if self.handle.is_some() && expired {
+            self.handle = None;
+            self.repaint = true;
+        }
+    }
+
+    fn record_maintenance<D: Sized>(
+        &mut self,
+        display: &dyn GraphicsDisplay<D>,
+        always_alive: Option<bool>,
+    ) {
+        self.maintained_at = match always_alive {
+            Some(true) => None,
+            _ => display.maintenance_count(),
+        };
+    }
+
+    fn ok_or_push<D: Sized>(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<D>,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        match (self.handle, self.mode) {
+            (Some(handle), RepaintMode::InPlace) => display.modify_command_group_in_place(
+                handle,
+                commands,
+                z_order,
+                protected,
+                always_alive,
+            ),
+            (Some(_), RepaintMode::Front) => {
+                ok_or_push(&mut self.handle, display, commands, z_order, protected, always_alive)
+            }
+            (None, _) => {
+                ok_or_push(&mut self.handle, display, commands, z_order, protected, always_alive);
+                // a freshly pushed command group has no clip or transformation yet, is in the default layer and isn't supersampled.
+                self.clip_changed = self.clip.is_some();
+                self.transform_changed = self.transform != Transform::identity();
+                self.layer_changed = self.layer != DisplayLayer::default();
+                self.render_scale_changed = self.render_scale != 1.0;
+                self.label_changed = self.label.is_some();
+            }
+        }
+
+        self.apply_properties(display);
+    }
+
+    fn maintain<D: Sized>(&mut self, display: &mut dyn GraphicsDisplay<D>) {
+        if let Some(handle) = self.handle {
+            match self.mode {
+                RepaintMode::Front => display.maintain_command_group(handle),
+                RepaintMode::InPlace => display.maintain_command_group_in_place(handle),
+            }
+        }
+
+        self.apply_properties(display);
+    }
+
+    fn apply_properties<D: Sized>(&mut self, display: &mut dyn GraphicsDisplay<D>) {
+        if let Some(handle) = self.handle {
+            if self.clip_changed {
+                self.clip_changed = false;
+                display.set_command_group_clip(handle, self.clip.clone());
+            }
+
+            // a display wrapper replaces the transformation whenever the command group goes through it, so a transformation of its own is composed again.
+            if self.transform_changed || self.transform != Transform::identity() {
+                self.transform_changed = false;
+                display.set_command_group_transform(handle, self.transform);
+            }
+
+            if self.layer_changed {
+                self.layer_changed = false;
+                display.set_command_group_layer(handle, self.layer);
+            }
+
+            if self.render_scale_changed {
+                self.render_scale_changed = false;
+                display.set_command_group_render_scale(handle, self.render_scale);
+            }
+
+            if self.label_changed {
+                self.label_changed = false;
+                display.set_command_group_label(handle, self.label.as_deref());
+            }
+        }
+    }
+
+    /// Sets a clip which is applied to all the commands of this command group, without the commands needing to clip themselves.
+    ///
+    /// The clip is given to the display the next time [`push`](struct.CommandGroup.html#method.push) is called (regardless of the repaint flag)
+    /// and persists through repaints until changed.
+    ///
+    /// See [`set_command_group_clip`](trait.GraphicsDisplay.html#method.set_command_group_clip).
+    pub fn set_clip(&mut self, clip: impl Into<Option<DisplayClip>>) {
+        self.clip = clip.into();
+        self.clip_changed = true;
+    }
+
+    /// Returns the clip set by [`set_clip`](struct.CommandGroup.html#method.set_clip), if any.
+    #[inline]
+    pub fn clip(&self) -> Option<&DisplayClip> {
+        self.clip.as_ref()
+    }
+
+    /// Sets a transformation which is applied to all the commands (and the clip) of this command group,
+    /// and persists through repaints until changed.
+    ///
+    /// Unlike a transformation set on the display directly, this one is composed with that of a
+    /// [`TransformedDisplay`](struct.TransformedDisplay.html) whenever the command group is pushed or maintained through one.
+    /// See [`set_command_group_transform`](trait.GraphicsDisplay.html#method.set_command_group_transform).
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+        self.transform_changed = true;
+    }
+
+    /// Returns the transformation set by [`set_transform`](struct.CommandGroup.html#method.set_transform).
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        self.transform
+    }
+
+    /// Assigns this command group to a layer.
+    ///
+    /// Like [`set_clip`](struct.CommandGroup.html#method.set_clip), the layer is given to the display the next time [`push`](struct.CommandGroup.html#method.push) is called.
+    pub fn set_layer(&mut self, layer: DisplayLayer) {
+        if self.layer != layer {
+            self.layer = layer;
+            self.layer_changed = true;
+        }
+    }
+
+    /// Returns the layer this command group is assigned to.
+    #[inline]
+    pub fn layer(&self) -> DisplayLayer {
+        self.layer
+    }
+
+    /// Renders this command group at `scale` times its resolution (e.g. `2.0` for content which can be zoomed in).
+    ///
+    /// Like [`set_clip`](struct.CommandGroup.html#method.set_clip), the scale is given to the display the next time [`push`](struct.CommandGroup.html#method.push) is called.
+    /// See [`set_command_group_render_scale`](trait.GraphicsDisplay.html#method.set_command_group_render_scale).
+    pub fn set_render_scale(&mut self, scale: f32) {
+        if self.render_scale != scale {
+            self.render_scale = scale;
+            self.render_scale_changed = true;
+        }
+    }
+
+    /// Returns the scale this command group is rendered at.
+    #[inline]
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Names this command group for debugging, such that it can be told apart in recorded frames and GPU debuggers.
+    ///
+    /// Like [`set_clip`](struct.CommandGroup.html#method.set_clip), the label is given to the display the next time [`push`](struct.CommandGroup.html#method.push) is called.
+    /// See [`set_command_group_label`](trait.GraphicsDisplay.html#method.set_command_group_label).
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        let label = label.into();
+        if self.label.as_ref() != Some(&label) {
+            self.label = Some(label);
+            self.label_changed = true;
         }
     }
 
+    /// Returns the label set by [`set_label`](struct.CommandGroup.html#method.set_label), if any.
+    #[inline]
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Changes how the command group is placed in the draw order when repainted or maintained.
+    #[inline]
+    pub fn set_repaint_mode(&mut self, mode: RepaintMode) {
+        self.mode = mode;
+    }
+
+    /// Returns how the command group is placed in the draw order when repainted or maintained.
+    #[inline]
+    pub fn repaint_mode(&self) -> RepaintMode {
+        self.mode
+    }
+
     /// Sets the repaint flag so that next time [`push`](struct.CommandGroup.html#method.push) is called the commands will be pushed.
+    ///
+    /// Displays which track [damage](trait.GraphicsDisplay.html#method.take_damage) then damage the regions covered by both the old and new commands.
+    /// If the command group is [attached to a scheduler](struct.CommandGroup.html#method.set_scheduler), a redraw is requested from it.
     #[inline(always)]
     pub fn repaint(&mut self) {
-        self.1 = true;
+        self.repaint = true;
+        if let Some(ref scheduler) = self.scheduler {
+            scheduler.request_repaint(self.handle);
+        }
+    }
+
+    /// Attaches the command group to a [`FrameScheduler`](../draw/struct.FrameScheduler.html), such that it requests a redraw whenever it's marked for repaint
+    /// (including now, if it's yet to be pushed).
+    pub fn set_scheduler(&mut self, scheduler: &FrameScheduler) {
+        let requester = scheduler.requester();
+        if self.repaint {
+            requester.request_repaint(self.handle);
+        }
+        self.scheduler = Some(requester);
     }
 
     /// Returns flag indicating whether next [`push`](struct.CommandGroup.html#method.push) will skip or not.
     #[inline(always)]
     pub fn will_repaint(&self) -> bool {
-        self.1
+        self.repaint
+    }
+
+    /// Removes the command group from the display (if it was ever pushed) and sets the repaint flag,
+    /// so that the next [`push`](struct.CommandGroup.html#method.push) will push the commands anew.
+    pub fn remove<D: Sized>(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<D>,
+    ) -> Option<Vec<DisplayCommand>> {
+        self.repaint = true;
+        let handle = self.handle.take()?;
+        if let Some(ref scheduler) = self.scheduler {
+            scheduler.request_redraw();
+        }
+        display.remove_command_group(handle)
+    }
+
+    /// Returns the handle to the underlying command group, if it has been pushed.
+    #[inline]
+    pub fn handle(&self) -> Option<CommandGroupHandle> {
+        self.handle
+    }
+
+    /// Converts this into a [`ScopedCommandGroup`](struct.ScopedCommandGroup.html), which is removed once dropped.
+    pub fn scoped(self, reaper: &CommandGroupReaper) -> ScopedCommandGroup {
+        ScopedCommandGroup { group: self, reaper: reaper.clone() }
+    }
+}
+
+/// Collects the handles of dropped [`ScopedCommandGroup`](struct.ScopedCommandGroup.html)s so that they can be removed from a display.
+///
+/// Dropping a command group doesn't give access to the display, hence removal is deferred until [`reap`](struct.CommandGroupReaper.html#method.reap) is called
+/// (typically once per frame, before presenting).
+#[derive(Debug, Clone, Default)]
+pub struct CommandGroupReaper(Rc<RefCell<Vec<CommandGroupHandle>>>);
+
+impl CommandGroupReaper {
+    /// Creates a new reaper with no pending removals.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Removes all the command groups which have been dropped since the last call.
+    pub fn reap<D: Sized>(&self, display: &mut dyn GraphicsDisplay<D>) {
+        for handle in self.0.borrow_mut().drain(..) {
+            display.remove_command_group(handle);
+        }
+    }
+
+    /// Returns the number of command groups waiting to be removed.
+    pub fn pending(&self) -> usize {
+        self.0.borrow().len()
+    }
+}
+
+/// A [`CommandGroup`](struct.CommandGroup.html) which removes itself from the display once dropped.
+///
+/// Removal goes through a [`CommandGroupReaper`](struct.CommandGroupReaper.html), so that a widget which is destroyed
+/// doesn't leave its last commands rendering forever.
+#[derive(Debug)]
+pub struct ScopedCommandGroup {
+    group: CommandGroup,
+    reaper: CommandGroupReaper,
+}
+
+impl ScopedCommandGroup {
+    /// Creates a new, empty scoped command group.
+    pub fn new(reaper: &CommandGroupReaper) -> Self {
+        CommandGroup::new().scoped(reaper)
+    }
+
+    /// Releases the inner command group, such that it is no longer removed on drop.
+    pub fn into_inner(mut self) -> CommandGroup {
+        std::mem::take(&mut self.group)
+    }
+}
+
+impl Deref for ScopedCommandGroup {
+    type Target = CommandGroup;
+
+    fn deref(&self) -> &CommandGroup {
+        &self.group
+    }
+}
+
+impl DerefMut for ScopedCommandGroup {
+    fn deref_mut(&mut self) -> &mut CommandGroup {
+        &mut self.group
+    }
+}
+
+impl Drop for ScopedCommandGroup {
+    fn drop(&mut self) {
+        if let Some(handle) = self.group.handle.take() {
+            self.reaper.0.borrow_mut().push(handle);
+        }
     }
 }
 
 /// Stroke cap (stroke start/end) appearance.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineCap {
     /// The cap of the stroke will appear as expected.
+    #[default]
     Flat,
     /// The cap of the stroke will extend tangentially with dimensions square to the stroke width.
     Square,
@@ -274,16 +1698,40 @@ pub enum LineCap {
     Round,
 }
 
-impl Default for LineCap {
-    fn default() -> Self {
-        LineCap::Flat
-    }
+/// Where a stroke is drawn relative to the outline of a shape.
+///
+/// An odd-width stroke (such as a 1px border) along the center of a pixel-aligned outline straddles pixels,
+/// so it's blurred when antialiased; drawing it inside or outside of the outline keeps it on whole pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrokeAlignment {
+    /// Half of the stroke is inside the outline, and half outside.
+    #[default]
+    Center,
+    /// The stroke is entirely within the outline.
+    Inside,
+    /// The stroke is entirely outside the outline.
+    Outside,
+}
+
+/// Dashes along a stroke, measured along the length of the path.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrokeDash {
+    /// Alternating lengths of dashes and the gaps between them, starting with a dash.
+    ///
+    /// An odd number of intervals is repeated to make an even number (as in SVG).
+    pub intervals: Vec<f32>,
+    /// Distance into the intervals at which the path starts; animating it moves the dashes along the path.
+    pub phase: f32,
 }
 
 /// Path corner appearance.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineJoin {
     /// The corner will appear as expected.
+    #[default]
     Miter,
     /// The corner will be rounded off.
     Round,
@@ -291,14 +1739,9 @@ pub enum LineJoin {
     Bevel,
 }
 
-impl Default for LineJoin {
-    fn default() -> Self {
-        LineJoin::Miter
-    }
-}
-
 /// An "event"/segment within a vector path.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum VectorPathEvent {
     MoveTo { to: Point },
     LineTo { to: Point },
@@ -416,6 +1859,7 @@ pub fn vector_path_bounds(path: &VectorPath) -> Rect {
 
 /// Stroke/outline appearance.
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GraphicsDisplayStroke {
     /// The color of the stroke.
     pub color: StyleColor,
@@ -429,6 +1873,16 @@ pub struct GraphicsDisplayStroke {
     pub miter_limit: f32,
     /// Whether this stroke should be antialiased or not. This can be used to achieve sharp, thin outlines.
     pub antialias: bool,
+    /// Where the stroke is drawn relative to the outline.
+    ///
+    /// Only rectangles, rounded rectangles and ellipses can be stroked off-center; other items are always stroked along their center.
+    pub alignment: StrokeAlignment,
+    /// Dashes the stroke, rather than drawing it solid.
+    pub dash: Option<StrokeDash>,
+    /// Only strokes the part of the path between two fractions of its length (from `0.0` to `1.0`), e.g. to "draw in" an icon.
+    ///
+    /// Dashes stay in place along the path as it's trimmed, and nothing is stroked if the start isn't before the end.
+    pub trim: Option<(f32, f32)>,
 }
 
 impl Default for GraphicsDisplayStroke {
@@ -440,12 +1894,16 @@ impl Default for GraphicsDisplayStroke {
             join: LineJoin::default(),
             miter_limit: 4.0,
             antialias: true,
+            alignment: StrokeAlignment::default(),
+            dash: None,
+            trim: None,
         }
     }
 }
 
 /// Appearance of a display item.
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum GraphicsDisplayPaint {
     /// The item will simply be a color, image, or gradient.
     Fill(StyleColor),
@@ -453,8 +1911,193 @@ pub enum GraphicsDisplayPaint {
     Stroke(GraphicsDisplayStroke),
 }
 
+/// Clockwise rotation of an image about the center of its destination rectangle.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImageRotation {
+    #[default]
+    None,
+    /// A quarter turn. The image is fitted such that it fills the destination once rotated (i.e. its width spans the height of the destination).
+    Rotate90,
+    Rotate180,
+    /// Three quarter turns, fitted like [`Rotate90`](enum.ImageRotation.html#variant.Rotate90).
+    Rotate270,
+    /// An arbitrary rotation. The image fills the destination before it's rotated, hence its corners may extend beyond it.
+    Angle(Angle),
+}
+
+impl ImageRotation {
+    /// Returns the angle of the rotation.
+    pub fn angle(self) -> Angle {
+        match self {
+            ImageRotation::None => Angle::zero(),
+            ImageRotation::Rotate90 => Angle::frac_pi_2(),
+            ImageRotation::Rotate180 => Angle::pi(),
+            ImageRotation::Rotate270 => Angle::pi() + Angle::frac_pi_2(),
+            ImageRotation::Angle(angle) => angle,
+        }
+    }
+
+    /// Returns `true` if the width and height of the image are swapped by this rotation.
+    pub fn is_sideways(self) -> bool {
+        matches!(self, ImageRotation::Rotate90 | ImageRotation::Rotate270)
+    }
+
+    /// Returns the rotation by `turns` quarter turns.
+    pub fn from_quarter_turns(turns: u32) -> Self {
+        match turns % 4 {
+            0 => ImageRotation::None,
+            1 => ImageRotation::Rotate90,
+            2 => ImageRotation::Rotate180,
+            _ => ImageRotation::Rotate270,
+        }
+    }
+
+    /// Returns the number of quarter turns of the rotation (for arbitrary angles, fractional), between 0 and 4.
+    fn quarter_turns(self) -> f32 {
+        match self {
+            ImageRotation::None => 0.0,
+            ImageRotation::Rotate90 => 1.0,
+            ImageRotation::Rotate180 => 2.0,
+            ImageRotation::Rotate270 => 3.0,
+            ImageRotation::Angle(angle) => {
+                let turns = angle.positive().get() / Angle::frac_pi_2().get();
+                // absorb rounding errors of angles which are (nearly) quarter turns
+                if (turns - turns.round()).abs() < 1e-4 {
+                    turns.round()
+                } else {
+                    turns
+                }
+            }
+        }
+    }
+
+    /// Rotates a further quarter turn clockwise. Arbitrary angles are snapped to the next quarter turn.
+    pub fn rotate_cw(self) -> Self {
+        ImageRotation::from_quarter_turns(self.quarter_turns().floor() as u32 + 1)
+    }
+
+    /// Rotates a further quarter turn counter-clockwise. Arbitrary angles are snapped to the previous quarter turn.
+    pub fn rotate_ccw(self) -> Self {
+        ImageRotation::from_quarter_turns(self.quarter_turns().ceil() as u32 + 3)
+    }
+}
+
+/// Rotation and flip of an image within its destination rectangle.
+///
+/// The image is flipped (about its center) before it's rotated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageOrientation {
+    pub rotation: ImageRotation,
+    /// Mirrors the image horizontally (left becomes right).
+    pub flip_horizontal: bool,
+    /// Mirrors the image vertically (top becomes bottom).
+    pub flip_vertical: bool,
+}
+
+impl ImageOrientation {
+    /// Returns `true` if the image is drawn as is.
+    pub fn is_identity(&self) -> bool {
+        self.rotation.angle().radians == 0.0 && !self.flip_horizontal && !self.flip_vertical
+    }
+
+    /// Returns the rectangle the image is drawn into before it's rotated (about the center of `dst`).
+    pub fn image_rect(&self, dst: Rect) -> Rect {
+        if self.rotation.is_sideways() {
+            let center = dst.center();
+            let size = Size::new(dst.size.height, dst.size.width);
+            Rect::new(center - size.to_vector() / 2.0, size)
+        } else {
+            dst
+        }
+    }
+
+    /// Returns the transformation from the [image rectangle](struct.ImageOrientation.html#method.image_rect) to the display, given the destination.
+    pub fn transform(&self, dst: Rect) -> Transform {
+        let center = dst.center();
+        Transform::create_translation(-center.x, -center.y)
+            .post_scale(
+                if self.flip_horizontal { -1.0 } else { 1.0 },
+                if self.flip_vertical { -1.0 } else { 1.0 },
+            )
+            // euclid rotates counter-clockwise on screen (where y points down).
+            .post_rotate(-self.rotation.angle())
+            .post_translate(center.to_vector())
+    }
+
+    /// Returns the bounds of the image as drawn into `dst`.
+    pub fn bounds(&self, dst: Rect) -> Rect {
+        match self.rotation {
+            ImageRotation::Angle(angle) => rotated_rectangle_bounds(&dst, angle),
+            _ => dst,
+        }
+    }
+}
+
+/// Color adjustments of an image, applied by the backend as the image is drawn (the image resource itself is unchanged).
+///
+/// The adjustments are applied in the order of the fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImageAdjustments {
+    /// Multiplies the alpha of the image (e.g. for dimming on hover), from 0 to 1.
+    pub opacity: f32,
+    /// Multiplies the color of the image by this color, to the extent of its alpha.
+    pub tint: Option<Color>,
+    /// Exposure change in stops; each stop doubles (or halves, if negative) the brightness.
+    pub exposure: f32,
+    /// Contrast factor about mid-gray; 1 leaves the image unchanged, and 0 makes it entirely gray.
+    pub contrast: f32,
+}
+
+impl Default for ImageAdjustments {
+    fn default() -> Self {
+        ImageAdjustments { opacity: 1.0, tint: None, exposure: 0.0, contrast: 1.0 }
+    }
+}
+
+impl ImageAdjustments {
+    /// Returns `true` if the image is drawn as is.
+    pub fn is_identity(&self) -> bool {
+        *self == Default::default()
+    }
+
+    /// Returns the adjustments as a row-major 4x5 color matrix, operating on (unpremultiplied) RGBA colors within 0 and 1.
+    pub fn color_matrix(&self) -> [f32; 20] {
+        let tint = self.tint.map_or([1.0; 3], |tint| {
+            let mix = |channel: f32| 1.0 + (channel - 1.0) * tint.alpha;
+            [mix(tint.red), mix(tint.green), mix(tint.blue)]
+        });
+        let gain = 2f32.powf(self.exposure) * self.contrast;
+        let offset = 0.5 - 0.5 * self.contrast;
+
+        #[rustfmt::skip]
+        let matrix = [
+            gain * tint[0], 0.0, 0.0, 0.0, offset,
+            0.0, gain * tint[1], 0.0, 0.0, offset,
+            0.0, 0.0, gain * tint[2], 0.0, offset,
+            0.0, 0.0, 0.0, self.opacity, 0.0,
+        ];
+        matrix
+    }
+
+    /// Applies the adjustments to a single color, as the backend does for each pixel (before clamping).
+    pub fn apply(&self, color: Color) -> Color {
+        apply_color_matrix(&self.color_matrix(), color)
+    }
+}
+
+// multiplies a color by a row-major 4x5 color matrix.
+pub(crate) fn apply_color_matrix(m: &[f32; 20], color: Color) -> Color {
+    let (r, g, b, a) = (color.red, color.green, color.blue, color.alpha);
+    let row = |i: usize| m[i] * r + m[i + 1] * g + m[i + 2] * b + m[i + 3] * a + m[i + 4];
+    Color::new(row(0), row(5), row(10), row(15))
+}
+
 /// Describes all the possible graphical items (excluding text, see [`TextDisplayItem`](struct.TextDisplayItem.html)).
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum GraphicsDisplayItem {
     Line {
         /// First point of line.
@@ -486,6 +2129,20 @@ pub enum GraphicsDisplayItem {
         /// Paint style of ellipse.
         paint: GraphicsDisplayPaint,
     },
+    Arc {
+        /// Center point of the ellipse the arc is part of.
+        center: Point,
+        /// Horizontal/vertical radii of the ellipse.
+        radii: Vector,
+        /// Angle at which the arc starts, clockwise from the positive x axis.
+        start_angle: Angle,
+        /// Angle the arc spans (clockwise if positive), limited to a full turn.
+        sweep_angle: Angle,
+        /// Whether the arc is connected to the center, drawing a wedge rather than a segment.
+        use_center: bool,
+        /// Paint style of arc.
+        paint: GraphicsDisplayPaint,
+    },
     Image {
         /// Optional source sample rectangle.
         src: Option<Rect>,
@@ -493,6 +2150,10 @@ pub enum GraphicsDisplayItem {
         dst: Rect,
         /// Reference to the image resource.
         resource: ResourceReference,
+        /// Rotation and flip of the image within `dst`.
+        orientation: ImageOrientation,
+        /// Color adjustments applied to the image as it's drawn.
+        adjustments: ImageAdjustments,
     },
     Path {
         /// Vector path.
@@ -504,9 +2165,75 @@ pub enum GraphicsDisplayItem {
     },
 }
 
-impl GraphicsDisplayItem {
+impl GraphicsDisplayItem {
+    /// Returns the item with its stroke centered on its outline, moving the outline so that an [aligned](enum.StrokeAlignment.html) stroke covers the same area.
+    ///
+    /// Backends draw this rather than the item itself; items which are already centered are borrowed.
+    pub fn center_aligned(&self) -> std::borrow::Cow<'_, GraphicsDisplayItem> {
+        use std::borrow::Cow;
+
+        let (paint, offset) = match self {
+            GraphicsDisplayItem::Rectangle { paint, .. }
+            | GraphicsDisplayItem::RoundRectangle { paint, .. }
+            | GraphicsDisplayItem::Ellipse { paint, .. } => match paint {
+                GraphicsDisplayPaint::Stroke(stroke) => match stroke.alignment {
+                    StrokeAlignment::Center => return Cow::Borrowed(self),
+                    StrokeAlignment::Inside => (paint, -stroke.thickness / 2.0),
+                    StrokeAlignment::Outside => (paint, stroke.thickness / 2.0),
+                },
+                GraphicsDisplayPaint::Fill(_) => return Cow::Borrowed(self),
+            },
+            _ => return Cow::Borrowed(self),
+        };
+
+        let paint = match paint {
+            GraphicsDisplayPaint::Stroke(stroke) => {
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    alignment: StrokeAlignment::Center,
+                    ..stroke.clone()
+                })
+            }
+            GraphicsDisplayPaint::Fill(_) => unreachable!(),
+        };
+        // strokes too thick to fit inside collapse the outline onto its center.
+        let offset_rect = |rect: &Rect| {
+            let (min, max) = (
+                rect.min() - Vector::new(offset, offset),
+                rect.max() + Vector::new(offset, offset),
+            );
+            let center = rect.center();
+            Rect::from_points([
+                Point::new(min.x.min(center.x), min.y.min(center.y)),
+                Point::new(max.x.max(center.x), max.y.max(center.y)),
+            ])
+        };
+
+        Cow::Owned(match self {
+            GraphicsDisplayItem::Rectangle { rect, .. } => {
+                GraphicsDisplayItem::Rectangle { rect: offset_rect(rect), paint }
+            }
+            GraphicsDisplayItem::RoundRectangle { rect, radii, .. } => {
+                GraphicsDisplayItem::RoundRectangle {
+                    rect: offset_rect(rect),
+                    radii: radii.map(|radius| (radius + offset).max(0.0)),
+                    paint,
+                }
+            }
+            GraphicsDisplayItem::Ellipse { center, radii, .. } => GraphicsDisplayItem::Ellipse {
+                center: *center,
+                radii: Vector::new((radii.x + offset).max(0.0), (radii.y + offset).max(0.0)),
+                paint,
+            },
+            _ => unreachable!(),
+        })
+    }
+
     /// Returns the exact maximum boundaries for the item.
     pub fn bounds(&self) -> Rect {
+        if let std::borrow::Cow::Owned(item) = self.center_aligned() {
+            return item.bounds();
+        }
+
         match self {
             GraphicsDisplayItem::Line { a, b, stroke } => {
                 let size = Size::new(1.0, (*a - *b).length());
@@ -544,7 +2271,29 @@ impl GraphicsDisplayItem {
                     }
                 }
             }
-            GraphicsDisplayItem::Image { dst, .. } => *dst,
+            GraphicsDisplayItem::Arc {
+                center,
+                radii,
+                start_angle,
+                sweep_angle,
+                use_center,
+                paint,
+            } => {
+                let rect = arc_bounds(*center, *radii, *start_angle, *sweep_angle, *use_center);
+                match paint {
+                    GraphicsDisplayPaint::Fill(_) => rect,
+                    GraphicsDisplayPaint::Stroke(stroke) => {
+                        // the corners of a wedge can be mitered.
+                        let inflation = if *use_center && stroke.join == LineJoin::Miter {
+                            stroke.thickness / 2.0 * stroke.miter_limit.max(1.0)
+                        } else {
+                            stroke.thickness / 2.0
+                        };
+                        rect.inflate(inflation, inflation)
+                    }
+                }
+            }
+            GraphicsDisplayItem::Image { dst, orientation, .. } => orientation.bounds(*dst),
             GraphicsDisplayItem::Path { path, paint, .. } => {
                 let inflation = if let GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                     thickness,
@@ -562,9 +2311,41 @@ impl GraphicsDisplayItem {
     }
 }
 
+/// Returns the bounds of an arc of an ellipse (see [`GraphicsDisplayItem::Arc`](enum.GraphicsDisplayItem.html#variant.Arc)).
+fn arc_bounds(
+    center: Point,
+    radii: Vector,
+    start_angle: Angle,
+    sweep_angle: Angle,
+    use_center: bool,
+) -> Rect {
+    let full_turn = std::f32::consts::PI * 2.0;
+    let sweep = sweep_angle.radians.clamp(-full_turn, full_turn);
+    let (start, end) = if sweep < 0.0 {
+        (start_angle.radians + sweep, start_angle.radians)
+    } else {
+        (start_angle.radians, start_angle.radians + sweep)
+    };
+    let point = |angle: f32| center + Vector::new(radii.x * angle.cos(), radii.y * angle.sin());
+
+    let mut points = vec![point(start), point(end)];
+    if use_center {
+        points.push(center);
+    }
+    // the extremes of the ellipse which the arc passes through.
+    let quarter = std::f32::consts::FRAC_PI_2;
+    let first = (start / quarter).ceil() * quarter;
+    points.extend(
+        (0..5).map(|i| first + quarter * i as f32).take_while(|&extreme| extreme <= end).map(point),
+    );
+
+    Rect::from_points(points)
+}
+
 /// A single shaped glyph.
 /// This should be generated from the output of a shaping engine.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShapedGlyph {
     pub codepoint: u32,
     pub advance: Vector,
@@ -582,6 +2363,7 @@ pub enum DisplayCharacter {
 
 /// Render-able text, either as a simple string or pre-shaped glyphs (via a library such as HarfBuzz).
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayText {
     Simple(String),
     Shaped(Vec<ShapedGlyph>),
@@ -649,7 +2431,11 @@ impl From<Vec<ShapedGlyph>> for DisplayText {
 }
 
 /// Describes a text render item.
+///
+/// A text item is a single line; paragraphs are laid out by a [`TextLayout`](text/struct.TextLayout.html),
+/// either from an item (see [`layout`](struct.TextDisplayItem.html#method.layout)) or into items (see [`TextLayout::items`](text/struct.TextLayout.html#method.items)).
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextDisplayItem {
     pub text: DisplayText,
     pub font: ResourceReference,
@@ -716,77 +2502,201 @@ impl TextDisplayItem {
         Ok(Rect::new(Point::new(self.bottom_left.x, y), Size::new(width, height)))
     }
 
-    /// Breaks the text based on a bounding box using the standard Unicode line
+    /// Breaks the text into lines which fit within the width of `rect` (from the left of the text), using the standard Unicode line
     /// breaking algorithm.
+    ///
+    /// The text is broken by a [`TextLayout`](text/struct.TextLayout.html), and each line is `line_height` below the one before it.
+    /// Lines without any text are skipped (though they're still counted).
     pub fn linebreak(
-        mut self,
+        self,
         rect: Rect,
         line_height: f32,
         remove_newlines: bool,
     ) -> Result<Vec<TextDisplayItem>, error::FontError> {
-        let text = match &self.text {
-            DisplayText::Simple(text) => text.clone(),
-            DisplayText::Shaped(glyphs) => glyphs.iter().fold(String::new(), |mut text, glyph| {
-                // FIXME(jazzfool): yeah... I don't think this is the best way to convert Unicode code-points
-                text.push(glyph.codepoint as u8 as char);
-                text
-            }),
-        };
-
-        let mut next = None;
-
-        for (offset, hard) in xi_unicode::LineBreakIterator::new(&text) {
-            if hard || self.limited_bounds(offset)?.max_x() > rect.max_x() {
-                let next_text = TextDisplayItem {
-                    text: self.text.subtext(offset..self.text.len()),
-                    font: self.font.clone(),
-                    font_info: self.font_info.clone(),
-                    size: self.size,
-                    bottom_left: self.bottom_left + Size::new(0.0, line_height),
-                    color: self.color.clone(),
-                };
+        let layout = self.layout(Some(rect.max_x() - self.bottom_left.x))?;
+        let newline = self.font_info.font.glyph_for_char('\n');
+
+        Ok(layout
+            .lines()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let mut text = self.text.subtext(line.range.clone());
+
+                if remove_newlines {
+                    text.filter(|character| match character {
+                        DisplayCharacter::Character(c) => c != '\n',
+                        DisplayCharacter::Glyph(glyph) => Some(glyph.codepoint) != newline,
+                    });
+                }
 
-                if next_text.text.len() == 0 {
-                    continue;
+                if text.len() == 0 {
+                    return None;
                 }
 
-                next = Some((next_text, offset));
+                Some(TextDisplayItem {
+                    text,
+                    bottom_left: self.bottom_left + Size::new(0.0, i as f32 * line_height),
+                    ..self.clone()
+                })
+            })
+            .collect())
+    }
+
+    /// Lays the text out as a paragraph no wider than `max_width` (if any).
+    ///
+    /// Equivalent to [`TextLayout::from_item`](text/struct.TextLayout.html#method.from_item);
+    /// the [`items`](text/struct.TextLayout.html#method.items) of the layout are text items again.
+    pub fn layout(&self, max_width: Option<f32>) -> Result<text::TextLayout, error::FontError> {
+        text::TextLayout::from_item(self, max_width)
+    }
+
+    /// Sets the top-left position of this text item, using the font baseline as an anchor.
+    pub fn set_top_left(&mut self, top_left: Point) {
+        let metrics = self.font_info.font.metrics();
+
+        self.bottom_left.x = top_left.x;
+        self.bottom_left.y =
+            top_left.y + (metrics.ascent / metrics.units_per_em as f32 * self.size);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ParagraphKey {
+    text: DisplayText,
+    font: ResourceReference,
+    size: f32,
+    width: f32,
+    line_height: f32,
+    remove_newlines: bool,
+}
 
-                break;
+impl Eq for ParagraphKey {}
+
+impl std::hash::Hash for ParagraphKey {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match &self.text {
+            DisplayText::Simple(text) => text.hash(state),
+            DisplayText::Shaped(glyphs) => {
+                for glyph in glyphs {
+                    glyph.codepoint.hash(state);
+                    glyph.advance.x.to_bits().hash(state);
+                }
             }
         }
+        self.font.hash(state);
+        self.size.to_bits().hash(state);
+        self.width.to_bits().hash(state);
+        self.line_height.to_bits().hash(state);
+        self.remove_newlines.hash(state);
+    }
+}
 
-        let mut out = Vec::new();
+/// Least-recently-used cache of paragraph layouts (i.e. the output of [`linebreak`](struct.TextDisplayItem.html#method.linebreak)).
+///
+/// Layouts are keyed by text, font, size, line height and the width available,
+/// so a label which is moved or recolored still hits the cache.
+/// Entries are never stale for a given font resource; if the resource behind a `ResourceReference` is replaced,
+/// invalidate it through [`invalidate_font`](struct.ParagraphCache.html#method.invalidate_font).
+#[derive(Debug)]
+pub struct ParagraphCache {
+    capacity: usize,
+    tick: u64,
+    // lines are stored relative to the bottom-left of the original text.
+    entries: HashMap<ParagraphKey, (Vec<(DisplayText, Vector)>, u64)>,
+}
 
-        if let Some((next, offset)) = next {
-            self.text = self.text.subtext(0..offset);
+impl ParagraphCache {
+    /// Creates an empty cache which holds at most `capacity` paragraphs.
+    pub fn new(capacity: usize) -> Self {
+        ParagraphCache { capacity, tick: 0, entries: HashMap::new() }
+    }
 
-            if remove_newlines {
-                self.text.filter(|character| match character {
-                    DisplayCharacter::Character(c) => c != '\n',
-                    DisplayCharacter::Glyph(glyph) => glyph.codepoint as u8 as char != '\n',
-                });
-            }
+    /// Equivalent to `text.linebreak(rect, line_height, remove_newlines)`, reusing a cached layout where possible.
+    ///
+    /// Only the width of `rect` (from the left of the text) is considered.
+    pub fn linebreak(
+        &mut self,
+        text: &TextDisplayItem,
+        rect: Rect,
+        line_height: f32,
+        remove_newlines: bool,
+    ) -> Result<Vec<TextDisplayItem>, error::FontError> {
+        self.tick += 1;
+
+        let key = ParagraphKey {
+            text: text.text.clone(),
+            font: text.font,
+            size: text.size,
+            width: rect.max_x() - text.bottom_left.x,
+            line_height,
+            remove_newlines,
+        };
 
-            if self.text.len() > 0 {
-                out.push(self);
+        if let Some((lines, last_used)) = self.entries.get_mut(&key) {
+            *last_used = self.tick;
+            return Ok(lines
+                .iter()
+                .map(|(line, offset)| TextDisplayItem {
+                    text: line.clone(),
+                    bottom_left: text.bottom_left + *offset,
+                    ..text.clone()
+                })
+                .collect());
+        }
+
+        let items = text.clone().linebreak(rect, line_height, remove_newlines)?;
+
+        if self.capacity > 0 {
+            if self.entries.len() >= self.capacity {
+                self.evict();
             }
 
-            out.extend(next.linebreak(rect, line_height, remove_newlines)?.into_iter());
-        } else {
-            out.push(self);
+            let lines = items
+                .iter()
+                .map(|item| (item.text.clone(), item.bottom_left - text.bottom_left))
+                .collect();
+            self.entries.insert(key, (lines, self.tick));
         }
 
-        Ok(out)
+        Ok(items)
     }
 
-    /// Sets the top-left position of this text item, using the font baseline as an anchor.
-    pub fn set_top_left(&mut self, top_left: Point) {
-        let metrics = self.font_info.font.metrics();
+    fn evict(&mut self) {
+        if let Some(key) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(key, _)| key.clone())
+        {
+            self.entries.remove(&key);
+        }
+    }
 
-        self.bottom_left.x = top_left.x;
-        self.bottom_left.y =
-            top_left.y + (metrics.ascent / metrics.units_per_em as f32 * self.size);
+    /// Removes all the layouts which use `font`.
+    pub fn invalidate_font(&mut self, font: ResourceReference) {
+        self.entries.retain(|key, _| key.font != font);
+    }
+
+    /// Removes all the layouts of `text`.
+    pub fn invalidate_text(&mut self, text: &DisplayText) {
+        self.entries.retain(|key, _| key.text != *text);
+    }
+
+    /// Removes all the layouts.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the number of cached layouts.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 }
 
@@ -808,6 +2718,92 @@ pub fn center_horizontally(inner: Rect, outer: Rect) -> Point {
     Point::new(outer.origin.x + ((outer.size.width - inner.size.width) / 2.0), inner.origin.y)
 }
 
+/// An edge or corner of a rectangle, as grabbed when resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeEdge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ResizeEdge {
+    /// Returns `true` if this edge moves the left side of the rectangle.
+    pub fn is_left(self) -> bool {
+        matches!(self, ResizeEdge::Left | ResizeEdge::TopLeft | ResizeEdge::BottomLeft)
+    }
+
+    /// Returns `true` if this edge moves the top side of the rectangle.
+    pub fn is_top(self) -> bool {
+        matches!(self, ResizeEdge::Top | ResizeEdge::TopLeft | ResizeEdge::TopRight)
+    }
+
+    /// Returns `true` if this edge moves the right side of the rectangle.
+    pub fn is_right(self) -> bool {
+        matches!(self, ResizeEdge::Right | ResizeEdge::TopRight | ResizeEdge::BottomRight)
+    }
+
+    /// Returns `true` if this edge moves the bottom side of the rectangle.
+    pub fn is_bottom(self) -> bool {
+        matches!(self, ResizeEdge::Bottom | ResizeEdge::BottomLeft | ResizeEdge::BottomRight)
+    }
+}
+
+/// Constraint utilities for `Rect`, typically for moving and resizing windows within another window.
+pub trait RectExt {
+    /// Moves the rectangle (without resizing) such that it lies within `outer`.
+    ///
+    /// If the rectangle is larger than `outer`, it's aligned to the top/left of `outer`.
+    fn clamp_within(&self, outer: &Rect) -> Rect;
+
+    /// Resizes the rectangle by dragging `edge` by `delta`, keeping the opposite edge in place.
+    ///
+    /// The size won't shrink below `min_size`.
+    fn resize_edge(&self, edge: ResizeEdge, delta: Vector, min_size: Size) -> Rect;
+}
+
+impl RectExt for Rect {
+    fn clamp_within(&self, outer: &Rect) -> Rect {
+        let clamp = |min: f32, max: f32, outer_min: f32, outer_max: f32| {
+            if min < outer_min {
+                outer_min - min
+            } else if max > outer_max {
+                (outer_max - max).max(outer_min - min)
+            } else {
+                0.0
+            }
+        };
+
+        self.translate(Vector::new(
+            clamp(self.min_x(), self.max_x(), outer.min_x(), outer.max_x()),
+            clamp(self.min_y(), self.max_y(), outer.min_y(), outer.max_y()),
+        ))
+    }
+
+    fn resize_edge(&self, edge: ResizeEdge, delta: Vector, min_size: Size) -> Rect {
+        let (mut min_x, mut min_y, mut max_x, mut max_y) =
+            (self.min_x(), self.min_y(), self.max_x(), self.max_y());
+
+        if edge.is_left() {
+            min_x = (min_x + delta.x).min(max_x - min_size.width);
+        } else if edge.is_right() {
+            max_x = (max_x + delta.x).max(min_x + min_size.width);
+        }
+
+        if edge.is_top() {
+            min_y = (min_y + delta.y).min(max_y - min_size.height);
+        } else if edge.is_bottom() {
+            max_y = (max_y + delta.y).max(min_y + min_size.height);
+        }
+
+        Rect::new(Point::new(min_x, min_y), Size::new(max_x - min_x, max_y - min_y))
+    }
+}
+
 /// Various properties of a font (italics, boldness, etc).
 pub type FontProperties = font_kit::properties::Properties;
 /// "Style" of the font; upright, italics or oblique.
@@ -902,8 +2898,37 @@ impl FontInfo {
     }
 }
 
+/// Fonts are serialized by name rather than by data, and are matched against the system fonts when deserialized.
+#[cfg(feature = "serialize")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedFontInfo {
+    name: String,
+    postscript_name: Option<String>,
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for FontInfo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedFontInfo { name: self.name.clone(), postscript_name: self.font.postscript_name() }
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for FontInfo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let font = SerializedFontInfo::deserialize(deserializer)?;
+        font.postscript_name
+            .as_ref()
+            .and_then(|name| FontInfo::from_postscript_name(name, &[]).ok())
+            .map_or_else(|| FontInfo::from_name(&font.name, &[], None), Ok)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// An item that can be displayed.
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayItem {
     /// Graphical item; anything that isn't text.
     Graphics(GraphicsDisplayItem),
@@ -922,7 +2947,8 @@ impl DisplayItem {
 }
 
 /// Clipping shapes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayClip {
     /// Rectangle clip.
     Rectangle {
@@ -959,6 +2985,7 @@ impl DisplayClip {
 
 /// Describes all possible display commands.
 #[derive(Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum DisplayCommand {
     /// Display an item with an optional filter.
     Item(DisplayItem, Option<Filter>),
@@ -980,6 +3007,10 @@ pub enum DisplayCommand {
     Scale(Vector),
     /// Adds rotation to the transformation matrix.
     Rotate(Angle),
+    /// Adds an arbitrary transformation (e.g. a skew, or translation, scaling and rotation at once) to the transformation matrix,
+    /// such that the following commands are drawn in its coordinate space.
+    /// Like any other transformation, it's undone by a [`Restore`](enum.DisplayCommand.html#variant.Restore) of an earlier [`Save`](enum.DisplayCommand.html#variant.Save).
+    Transform(Transform),
     /// Fills the clipped region with a solid color.
     Clear(Color),
 }
@@ -995,6 +3026,276 @@ impl DisplayCommand {
             _ => None,
         })
     }
+
+    /// Returns the resource used by the command (the image of an image item or the font of a text item), if any.
+    pub fn resource(&self) -> Option<ResourceReference> {
+        match self {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Image { resource, .. }),
+                _,
+            ) => Some(*resource),
+            DisplayCommand::Item(DisplayItem::Text(item), _) => Some(item.font),
+            _ => None,
+        }
+    }
+
+    /// Mutable version of [`resource`](enum.DisplayCommand.html#method.resource), for example to map the references of a recorded display list onto another display.
+    pub fn resource_mut(&mut self) -> Option<&mut ResourceReference> {
+        match self {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Image { resource, .. }),
+                _,
+            ) => Some(resource),
+            DisplayCommand::Item(DisplayItem::Text(item), _) => Some(&mut item.font),
+            _ => None,
+        }
+    }
+
+    /// Applies `filters` in order to the colors of the command, e.g. to gray out a disabled widget.
+    ///
+    /// Unlike [display filters](trait.GraphicsDisplay.html#method.set_display_filters), only the colors given by the command are filtered,
+    /// so images are left as is.
+    pub fn filter_colors(&mut self, filters: &[DisplayFilter]) {
+        if filters.is_empty() {
+            return;
+        }
+
+        let filter = |color: &mut Color| *color = DisplayFilter::apply_all(filters, *color);
+        let filter_paint = |paint: &mut GraphicsDisplayPaint| match paint {
+            GraphicsDisplayPaint::Fill(color) => color.map_colors(filter),
+            GraphicsDisplayPaint::Stroke(stroke) => stroke.color.map_colors(filter),
+        };
+
+        match self {
+            DisplayCommand::Item(DisplayItem::Graphics(item), _) => match item {
+                GraphicsDisplayItem::Line { stroke, .. } => stroke.color.map_colors(filter),
+                GraphicsDisplayItem::Rectangle { paint, .. }
+                | GraphicsDisplayItem::RoundRectangle { paint, .. }
+                | GraphicsDisplayItem::Ellipse { paint, .. }
+                | GraphicsDisplayItem::Arc { paint, .. }
+                | GraphicsDisplayItem::Path { paint, .. } => filter_paint(paint),
+                GraphicsDisplayItem::Image { .. } => {}
+            },
+            DisplayCommand::Item(DisplayItem::Text(item), _) => item.color.map_colors(filter),
+            DisplayCommand::Clear(color) => filter(color),
+            _ => {}
+        }
+    }
+}
+
+/// Tracks the clip (and transformations) through a display list, such that items which are entirely clipped out can be skipped.
+///
+/// The clip is approximated by its bounds, hence items are only skipped if they're definitely invisible.
+#[derive(Debug, Clone)]
+pub struct ClipCuller {
+    transform: Transform,
+    clip: Option<Rect>,
+    stack: Vec<(Transform, Option<Rect>)>,
+}
+
+impl ClipCuller {
+    /// Creates a new culler, starting with an optional clip which is already active.
+    pub fn new(clip: Option<Rect>) -> Self {
+        ClipCuller { transform: Transform::identity(), clip, stack: Vec::new() }
+    }
+
+    /// Processes the next command in the display list, returning `false` if it can be skipped.
+    pub fn visit(&mut self, command: &DisplayCommand) -> bool {
+        match command {
+            DisplayCommand::Item(..) | DisplayCommand::BackdropFilter(..) => {
+                match (self.clip, command.bounds()) {
+                    (Some(clip), Ok(Some(bounds))) => {
+                        // a filtered item may spread beyond its bounds (e.g. blurred shadows).
+                        let bounds = match command {
+                            DisplayCommand::Item(_, Some(filter)) => {
+                                let extent = filter.extent();
+                                bounds.inflate(extent.x, extent.y)
+                            }
+                            _ => bounds,
+                        };
+                        clip.intersects(&self.transform.transform_rect(&bounds))
+                    }
+                    // unknown bounds (e.g. a font failed to load) are never culled.
+                    _ => true,
+                }
+            }
+            DisplayCommand::Clip(clip) => {
+                let bounds = self.transform.transform_rect(&clip.bounds());
+                self.clip = Some(match self.clip {
+                    // an empty clip intersects with nothing.
+                    Some(current) => current.intersection(&bounds).unwrap_or_default(),
+                    None => bounds,
+                });
+                true
+            }
+            DisplayCommand::Save | DisplayCommand::SaveLayer(_) => {
+                self.stack.push((self.transform, self.clip));
+                true
+            }
+            DisplayCommand::Restore => {
+                if let Some((transform, clip)) = self.stack.pop() {
+                    self.transform = transform;
+                    self.clip = clip;
+                }
+                true
+            }
+            DisplayCommand::Translate(offset) => {
+                self.transform = self.transform.pre_translate(*offset);
+                true
+            }
+            DisplayCommand::Scale(scale) => {
+                self.transform = self.transform.pre_scale(scale.x, scale.y);
+                true
+            }
+            DisplayCommand::Rotate(angle) => {
+                self.transform = self.transform.pre_rotate(*angle);
+                true
+            }
+            DisplayCommand::Transform(transform) => {
+                self.transform = transform.post_transform(&self.transform);
+                true
+            }
+            DisplayCommand::Clear(_) => true,
+        }
+    }
+}
+
+/// Returns the commands of a display list which aren't entirely clipped out, given an initial clip.
+pub fn cull_display_list(
+    display_list: &[DisplayCommand],
+    clip: Option<Rect>,
+) -> impl Iterator<Item = &DisplayCommand> {
+    let mut culler = ClipCuller::new(clip);
+    display_list.iter().filter(move |command| culler.visit(command))
+}
+
+/// A run of display commands, as grouped by [`batch_display_list`](fn.batch_display_list.html).
+#[derive(Clone)]
+pub enum DisplayBatch<'a> {
+    /// A command which couldn't be batched with its neighbours.
+    Single(&'a DisplayCommand),
+    /// Consecutive rectangles filled with the same solid color.
+    Rectangles(Color, Vec<Rect>),
+    /// Consecutive lines with the same solid-colored stroke.
+    Lines(&'a GraphicsDisplayStroke, Vec<(Point, Point)>),
+}
+
+enum BatchRun<'a> {
+    Rectangles(Color, Vec<Rect>),
+    Lines(&'a GraphicsDisplayStroke, Vec<(Point, Point)>),
+}
+
+/// Returns a single-item run for a command which may be batched, along with its bounds and whether it's opaque.
+fn batch_candidate(command: &DisplayCommand) -> Option<(BatchRun<'_>, Rect, bool)> {
+    match command {
+        DisplayCommand::Item(DisplayItem::Graphics(item), None) => match item {
+            GraphicsDisplayItem::Rectangle {
+                rect,
+                paint: GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+            } => Some((BatchRun::Rectangles(*color, vec![*rect]), *rect, color.alpha >= 1.0)),
+            // dashed and trimmed lines are drawn along their own length, so can't be drawn as part of a batch.
+            GraphicsDisplayItem::Line { a, b, stroke }
+                if stroke.dash.is_none() && stroke.trim.is_none() =>
+            {
+                match stroke.color {
+                    StyleColor::Color(color) => Some((
+                        BatchRun::Lines(stroke, vec![(*a, *b)]),
+                        item.bounds(),
+                        color.alpha >= 1.0,
+                    )),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn stroke_compatible(a: &GraphicsDisplayStroke, b: &GraphicsDisplayStroke) -> bool {
+    match (&a.color, &b.color) {
+        (StyleColor::Color(a_color), StyleColor::Color(b_color)) => {
+            a_color == b_color
+                && a.thickness == b.thickness
+                && a.cap == b.cap
+                && a.join == b.join
+                && a.miter_limit == b.miter_limit
+                && a.antialias == b.antialias
+                && a.alignment == b.alignment
+                && a.dash == b.dash
+                && a.trim == b.trim
+        }
+        _ => false,
+    }
+}
+
+/// Groups consecutive simple items (unfiltered, solid-colored rectangle fills and lines) with identical paints,
+/// so that a backend can draw each group with a single draw call.
+///
+/// Translucent items are only grouped if they don't overlap the rest of the group, since overlapping areas would otherwise only be blended once.
+pub fn batch_display_list<'a>(
+    commands: impl IntoIterator<Item = &'a DisplayCommand>,
+) -> Vec<DisplayBatch<'a>> {
+    let mut batches = Vec::new();
+    // the current run, along with the first command (for runs of one) and the union of the bounds.
+    let mut run: Option<(BatchRun<'a>, &'a DisplayCommand, Rect)> = None;
+
+    fn finish<'a>(
+        batches: &mut Vec<DisplayBatch<'a>>,
+        run: Option<(BatchRun<'a>, &'a DisplayCommand, Rect)>,
+    ) {
+        batches.push(match run {
+            Some((BatchRun::Rectangles(color, rects), _, _)) if rects.len() > 1 => {
+                DisplayBatch::Rectangles(color, rects)
+            }
+            Some((BatchRun::Lines(stroke, lines), _, _)) if lines.len() > 1 => {
+                DisplayBatch::Lines(stroke, lines)
+            }
+            Some((_, command, _)) => DisplayBatch::Single(command),
+            None => return,
+        });
+    }
+
+    for command in commands {
+        let (candidate, bounds, opaque) = match batch_candidate(command) {
+            Some(candidate) => candidate,
+            None => {
+                finish(&mut batches, run.take());
+                batches.push(DisplayBatch::Single(command));
+                continue;
+            }
+        };
+
+        let joined = match (&mut run, &candidate) {
+            (
+                Some((BatchRun::Rectangles(color, rects), _, union)),
+                BatchRun::Rectangles(new_color, new),
+            ) if color == new_color && (opaque || !union.intersects(&bounds)) => {
+                rects.extend(new);
+                *union = union.union(&bounds);
+                true
+            }
+            (
+                Some((BatchRun::Lines(stroke, lines), _, union)),
+                BatchRun::Lines(new_stroke, new),
+            ) if stroke_compatible(stroke, new_stroke)
+                && (opaque || !union.intersects(&bounds)) =>
+            {
+                lines.extend(new);
+                *union = union.union(&bounds);
+                true
+            }
+            _ => false,
+        };
+
+        if !joined {
+            finish(&mut batches, run.take());
+            run = Some((candidate, command, bounds));
+        }
+    }
+
+    finish(&mut batches, run);
+    batches
 }
 
 /// Returns the total maximum for a list of display commands.
@@ -1015,18 +3316,219 @@ pub fn display_list_bounds(display_list: &[DisplayCommand]) -> Result<Rect, erro
         .unwrap_or_default())
 }
 
-/// Interpolation between multiple colors.
-#[derive(Debug, Clone)]
-pub struct Gradient {
-    pub start: Point,
-    pub end: Point,
-    pub stops: Vec<(f32, Color)>,
+/// Returns the scale at which `size` fits within `dst`, and where it's placed when centered.
+fn fit(size: Size, dst: Rect) -> (f32, Point) {
+    let scale = (dst.size.width / size.width).min(dst.size.height / size.height);
+    (scale, center(size * scale, dst))
+}
+
+/// Returns the transform which scales `source` to fit within `dst`, centered and preserving its aspect ratio.
+pub fn fit_transform(source: Rect, dst: Rect) -> Transform {
+    let (scale, origin) = fit(source.size, dst);
+    Transform::create_translation(-source.origin.x, -source.origin.y)
+        .post_scale(scale, scale)
+        .post_translate(origin.to_vector())
+}
+
+/// Returns the region of `display_list` within `source` scaled to fit within `dst` (see [`fit_transform`](fn.fit_transform.html)),
+/// such as for a navigation minimap or the preview of a panel.
+///
+/// Typically `source` is the [bounds](fn.display_list_bounds.html) of the display list.
+/// The returned commands are self-contained (saving and restoring the draw state), so they can be pushed as a command group on their own.
+pub fn preview_display_list(
+    display_list: &[DisplayCommand],
+    source: Rect,
+    dst: Rect,
+) -> Vec<DisplayCommand> {
+    if source.is_empty_or_negative() || dst.is_empty_or_negative() {
+        return Vec::new();
+    }
+
+    let (scale, origin) = fit(source.size, dst);
+
+    let mut commands = Vec::with_capacity(display_list.len() + 6);
+    commands.push(DisplayCommand::Save);
+    commands.push(DisplayCommand::Clip(DisplayClip::Rectangle {
+        rect: Rect::new(origin, source.size * scale),
+        antialias: false,
+    }));
+    commands.push(DisplayCommand::Translate(origin.to_vector()));
+    commands.push(DisplayCommand::Scale(Vector::new(scale, scale)));
+    commands.push(DisplayCommand::Translate(-source.origin.to_vector()));
+    commands.extend(display_list.iter().cloned());
+    commands.push(DisplayCommand::Restore);
+    commands
+}
+
+/// Interpolation between multiple colors.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gradient {
+    pub start: Point,
+    pub end: Point,
+    pub stops: Vec<(f32, Color)>,
+    /// Overrides the dithering set on the display (see [`set_dithering`](trait.GraphicsDisplay.html#method.set_dithering)).
+    pub dithering: Option<Dithering>,
+}
+
+/// Noise added to smooth color transitions before they're stored with 8 bits per channel, which breaks up banding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dithering {
+    #[default]
+    None,
+    /// A repeating 8x8 Bayer matrix, which is cheap and spreads evenly but has a faint regular pattern.
+    Ordered,
+    /// Interleaved gradient noise, which (like blue noise) has no visible pattern or low-frequency clumps.
+    Noise,
+}
+
+impl Dithering {
+    /// Returns the offset (from `-0.5` to `0.5`, in steps of the output precision) which is added to a pixel before it's quantized.
+    pub fn threshold(self, x: i32, y: i32) -> f32 {
+        match self {
+            Dithering::None => 0.0,
+            Dithering::Ordered => {
+                // the index of each cell is built by interleaving the bits of x ^ y and y, reversed.
+                let (x, y) = ((x & 7) as u32, (y & 7) as u32);
+                let xy = x ^ y;
+                let index = (xy & 1) << 5
+                    | (y & 1) << 4
+                    | (xy & 2) << 2
+                    | (y & 2) << 1
+                    | (xy & 4) >> 1
+                    | (y & 4) >> 2;
+                (index as f32 + 0.5) / 64.0 - 0.5
+            }
+            Dithering::Noise => {
+                let noise = 0.067_110_56 * x as f32 + 0.005_837_15 * y as f32;
+                (52.982_918 * noise.fract()).fract() - 0.5
+            }
+        }
+    }
+}
+
+/// A color filter applied to everything on the display as it's presented (see [`set_display_filters`](trait.GraphicsDisplay.html#method.set_display_filters)).
+///
+/// These are intended for accessibility settings, like those offered by operating systems, which apply regardless of how each widget is painted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayFilter {
+    /// Removes all color, keeping the (Rec. 709) luminance.
+    Grayscale,
+    /// Inverts each color channel, leaving the alpha unchanged.
+    Invert,
+    /// Contrast factor about mid-gray; values above 1 increase the contrast, and 0 makes everything gray.
+    Contrast(f32),
+}
+
+impl DisplayFilter {
+    /// Returns the filter as a row-major 4x5 color matrix, operating on (unpremultiplied) RGBA colors within 0 and 1.
+    pub fn color_matrix(&self) -> [f32; 20] {
+        #[rustfmt::skip]
+        let matrix = match *self {
+            DisplayFilter::Grayscale => [
+                0.2126, 0.7152, 0.0722, 0.0, 0.0,
+                0.2126, 0.7152, 0.0722, 0.0, 0.0,
+                0.2126, 0.7152, 0.0722, 0.0, 0.0,
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            DisplayFilter::Invert => [
+                -1.0, 0.0, 0.0, 0.0, 1.0,
+                0.0, -1.0, 0.0, 0.0, 1.0,
+                0.0, 0.0, -1.0, 0.0, 1.0,
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            DisplayFilter::Contrast(contrast) => {
+                let offset = 0.5 - 0.5 * contrast;
+                [
+                    contrast, 0.0, 0.0, 0.0, offset,
+                    0.0, contrast, 0.0, 0.0, offset,
+                    0.0, 0.0, contrast, 0.0, offset,
+                    0.0, 0.0, 0.0, 1.0, 0.0,
+                ]
+            }
+        };
+        matrix
+    }
+
+    /// Returns the color matrix of `filters` applied in order (the identity if there are none).
+    ///
+    /// Note that clamping only happens once at the end, whereas applying the filters one by one would clamp after each.
+    pub fn combined_matrix(filters: &[DisplayFilter]) -> [f32; 20] {
+        #[rustfmt::skip]
+        let identity = [
+            1.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ];
+
+        filters.iter().fold(identity, |a, filter| {
+            let b = filter.color_matrix();
+            let mut combined = [0.0; 20];
+            for row in 0..4 {
+                for column in 0..5 {
+                    let product = (0..4).map(|k| b[row * 5 + k] * a[k * 5 + column]).sum::<f32>();
+                    combined[row * 5 + column] =
+                        product + if column == 4 { b[row * 5 + 4] } else { 0.0 };
+                }
+            }
+            combined
+        })
+    }
+
+    /// Applies `filters` in order to a single color, as the display does for each pixel.
+    pub fn apply_all(filters: &[DisplayFilter], color: Color) -> Color {
+        let color = apply_color_matrix(&DisplayFilter::combined_matrix(filters), color);
+        Color::new(
+            color.red.clamp(0.0, 1.0),
+            color.green.clamp(0.0, 1.0),
+            color.blue.clamp(0.0, 1.0),
+            color.alpha.clamp(0.0, 1.0),
+        )
+    }
+}
+
+/// Alternating squares of two colors, typically painted behind transparent images.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkerboard {
+    /// Corner of a light square, such that the pattern can be aligned with (and moved along with) the content above it.
+    pub origin: Point,
+    /// Side length of each square.
+    pub size: f32,
+    pub light: Color,
+    pub dark: Color,
+}
+
+impl Default for Checkerboard {
+    fn default() -> Self {
+        Checkerboard {
+            origin: Point::zero(),
+            size: 8.0,
+            light: Color::new(1.0, 1.0, 1.0, 1.0),
+            dark: Color::new(0.8, 0.8, 0.8, 1.0),
+        }
+    }
+}
+
+impl Checkerboard {
+    /// Returns the color of the square containing `point`.
+    pub fn color_at(&self, point: Point) -> Color {
+        let cell = ((point - self.origin) / self.size).floor();
+        if (cell.x + cell.y) as i64 % 2 == 0 {
+            self.light
+        } else {
+            self.dark
+        }
+    }
 }
 
 pub type Color = Srgba;
 
 /// Possible ways to paint a stroke/fill.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum StyleColor {
     /// Solid color.
     Color(Color),
@@ -1034,9 +3536,40 @@ pub enum StyleColor {
     LinearGradient(Gradient),
     /// Radial gradient (center being point A and point B being the edge of the circle).
     RadialGradient(Gradient),
+    /// Checkerboard pattern, generated by the backend.
+    Checkerboard(Checkerboard),
 }
 
 impl StyleColor {
+    /// Creates a linear gradient from `start` to `end`, with `stops` given as offsets between `0.0` (`start`) and `1.0` (`end`).
+    pub fn linear_gradient(start: Point, end: Point, stops: Vec<(f32, Color)>) -> Self {
+        StyleColor::LinearGradient(Gradient { start, end, stops, dithering: None })
+    }
+
+    /// Creates a radial gradient, with `stops` given as offsets between `0.0` (`center`) and `1.0` (`radius` away from `center`).
+    pub fn radial_gradient(center: Point, radius: f32, stops: Vec<(f32, Color)>) -> Self {
+        StyleColor::RadialGradient(Gradient {
+            start: center,
+            end: center + Vector::new(radius, 0.0),
+            stops,
+            dithering: None,
+        })
+    }
+
+    // calls `f` with every color making up the style.
+    fn map_colors(&mut self, f: impl Fn(&mut Color)) {
+        match self {
+            StyleColor::Color(color) => f(color),
+            StyleColor::LinearGradient(gradient) | StyleColor::RadialGradient(gradient) => {
+                gradient.stops.iter_mut().for_each(|(_, color)| f(color))
+            }
+            StyleColor::Checkerboard(checkerboard) => {
+                f(&mut checkerboard.light);
+                f(&mut checkerboard.dark);
+            }
+        }
+    }
+
     /// Returns solid color if possible, otherwise black.
     pub fn color_or_black(&self) -> Color {
         match self {
@@ -1052,13 +3585,32 @@ impl From<Color> for StyleColor {
     }
 }
 
+impl From<Checkerboard> for StyleColor {
+    fn from(checkerboard: Checkerboard) -> Self {
+        StyleColor::Checkerboard(checkerboard)
+    }
+}
+
 /// Graphical filter.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Filter {
     Blur(f32, f32),
     Invert,
 }
 
+impl Filter {
+    /// Returns how far the filter spreads what it's applied to beyond its bounds, horizontally and vertically.
+    ///
+    /// A blur is considered to spread three standard deviations, beyond which it's invisible.
+    pub fn extent(&self) -> Vector {
+        match *self {
+            Filter::Blur(x, y) => Vector::new(x * 3.0, y * 3.0),
+            Filter::Invert => Vector::zero(),
+        }
+    }
+}
+
 /// Interface to simplify creating a list of display commands.
 #[derive(Clone, Default)]
 pub struct DisplayListBuilder {
@@ -1131,6 +3683,29 @@ impl DisplayListBuilder {
         ));
     }
 
+    /// Pushes a filled/stroked arc of an ellipse, optionally connected to its center (see [`GraphicsDisplayItem::Arc`](enum.GraphicsDisplayItem.html#variant.Arc)).
+    pub fn push_arc(
+        &mut self,
+        center: Point,
+        radii: Vector,
+        (start_angle, sweep_angle): (Angle, Angle),
+        use_center: bool,
+        paint: GraphicsDisplayPaint,
+        filter: Option<Filter>,
+    ) {
+        self.display_list.push(DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Arc {
+                center,
+                radii,
+                start_angle,
+                sweep_angle,
+                use_center,
+                paint,
+            }),
+            filter,
+        ));
+    }
+
     /// Pushes an image.
     pub fn push_image(
         &mut self,
@@ -1138,12 +3713,39 @@ impl DisplayListBuilder {
         dst: Rect,
         image: ResourceReference,
         filter: Option<Filter>,
+    ) {
+        self.push_oriented_image(src, dst, image, Default::default(), filter);
+    }
+
+    /// Pushes a rotated and/or flipped image.
+    pub fn push_oriented_image(
+        &mut self,
+        src: impl Into<Option<Rect>>,
+        dst: Rect,
+        image: ResourceReference,
+        orientation: ImageOrientation,
+        filter: Option<Filter>,
+    ) {
+        self.push_adjusted_image(src, dst, image, orientation, Default::default(), filter);
+    }
+
+    /// Pushes an image with color adjustments (and optionally rotated and/or flipped).
+    pub fn push_adjusted_image(
+        &mut self,
+        src: impl Into<Option<Rect>>,
+        dst: Rect,
+        image: ResourceReference,
+        orientation: ImageOrientation,
+        adjustments: ImageAdjustments,
+        filter: Option<Filter>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Image {
                 src: src.into(),
                 dst,
                 resource: image,
+                orientation,
+                adjustments,
             }),
             filter,
         ));
@@ -1168,6 +3770,54 @@ impl DisplayListBuilder {
         self.display_list.push(DisplayCommand::Item(DisplayItem::Text(text), filter));
     }
 
+    /// Pushes the lines of a text layout, with its top-left at `top_left`.
+    pub fn push_text_layout(
+        &mut self,
+        layout: &text::TextLayout,
+        top_left: Point,
+        color: StyleColor,
+        filter: Option<Filter>,
+    ) {
+        for item in layout.items(top_left, color) {
+            self.push_text(item, filter);
+        }
+    }
+
+    /// Pushes the lines of a text layout with the characters within `selection` (bytes of the text) highlighted, with its top-left at `top_left`.
+    ///
+    /// The highlight is pushed before the text, such that the text is drawn over it.
+    /// If the selected glyphs have a color of their own, they're then drawn again in it, clipped to each rectangle of the highlight.
+    pub fn push_selected_text_layout(
+        &mut self,
+        layout: &text::TextLayout,
+        top_left: Point,
+        color: StyleColor,
+        selection: std::ops::Range<usize>,
+        style: &text::SelectionStyle,
+        filter: Option<Filter>,
+    ) {
+        let rects: Vec<_> = layout
+            .selection_rects(selection)
+            .into_iter()
+            .map(|rect| rect.translate(top_left.to_vector()))
+            .collect();
+
+        for rect in &rects {
+            self.push_rectangle(*rect, GraphicsDisplayPaint::Fill(style.highlight.clone()), filter);
+        }
+
+        self.push_text_layout(layout, top_left, color, filter);
+
+        if let Some(ref selected) = style.color {
+            for rect in rects {
+                self.save();
+                self.push_rectangle_clip(rect, false);
+                self.push_text_layout(layout, top_left, selected.clone(), filter);
+                self.restore();
+            }
+        }
+    }
+
     /// Pushes a rectangle which applies a filter on everything behind it.
     pub fn push_rectangle_backdrop(&mut self, rect: Rect, antialias: bool, filter: Filter) {
         self.display_list.push(DisplayCommand::BackdropFilter(
@@ -1235,6 +3885,14 @@ impl DisplayListBuilder {
         self.display_list.push(DisplayCommand::Rotate(rotation));
     }
 
+    /// Pushes an arbitrary transformation to the transformation matrix.
+    ///
+    /// Typically enclosed by [`save`](struct.DisplayListBuilder.html#method.save) and [`restore`](struct.DisplayListBuilder.html#method.restore),
+    /// e.g. to draw a child in its own coordinate space.
+    pub fn push_transform(&mut self, transform: Transform) {
+        self.display_list.push(DisplayCommand::Transform(transform));
+    }
+
     /// Fills the screen/clip with a solid color.
     pub fn push_clear(&mut self, color: Color) {
         self.display_list.push(DisplayCommand::Clear(color));
@@ -1382,4 +4040,720 @@ mod tests {
             &Rect::new(Point::new(-34.0, -72.0), Size::new(94.0, 32.0)),
         );
     }
+
+    #[test]
+    fn test_gradient_constructors() {
+        let stops =
+            vec![(0.0, Color::new(1.0, 0.0, 0.0, 1.0)), (1.0, Color::new(0.0, 0.0, 1.0, 1.0))];
+
+        match StyleColor::radial_gradient(Point::new(10.0, 20.0), 5.0, stops.clone()) {
+            StyleColor::RadialGradient(gradient) => {
+                assert_eq!(gradient.start, Point::new(10.0, 20.0));
+                assert_eq!((gradient.end - gradient.start).length(), 5.0);
+                assert_eq!(gradient.stops, stops);
+            }
+            _ => panic!("expected a radial gradient"),
+        }
+        assert!(matches!(
+            StyleColor::linear_gradient(Point::zero(), Point::new(0.0, 1.0), stops),
+            StyleColor::LinearGradient(Gradient { end, .. }) if end == Point::new(0.0, 1.0)
+        ));
+    }
+
+    #[test]
+    fn test_stroke_alignment() {
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 10.0));
+        let stroke = |alignment| {
+            GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                thickness: 2.0,
+                alignment,
+                ..Default::default()
+            })
+        };
+
+        let centered =
+            GraphicsDisplayItem::Rectangle { rect, paint: stroke(StrokeAlignment::Center) };
+        assert!(matches!(centered.center_aligned(), std::borrow::Cow::Borrowed(_)));
+        epsilon_rect(&centered.bounds(), &rect.inflate(1.0, 1.0));
+
+        let inside =
+            GraphicsDisplayItem::Rectangle { rect, paint: stroke(StrokeAlignment::Inside) };
+        match *inside.center_aligned() {
+            GraphicsDisplayItem::Rectangle { rect: aligned, ref paint } => {
+                epsilon_rect(&aligned, &rect.inflate(-1.0, -1.0));
+                assert!(matches!(
+                    paint,
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        alignment: StrokeAlignment::Center,
+                        ..
+                    })
+                ));
+            }
+            _ => panic!("rectangle changed shape"),
+        }
+        epsilon_rect(&inside.bounds(), &rect);
+
+        let outside = GraphicsDisplayItem::RoundRectangle {
+            rect,
+            radii: [0.0, 1.0, 2.0, 3.0],
+            paint: stroke(StrokeAlignment::Outside),
+        };
+        epsilon_rect(&outside.bounds(), &rect.inflate(2.0, 2.0));
+        match *outside.center_aligned() {
+            GraphicsDisplayItem::RoundRectangle { radii, .. } => {
+                assert_eq!(radii, [1.0, 2.0, 3.0, 4.0])
+            }
+            _ => panic!("rounded rectangle changed shape"),
+        }
+
+        // too thick to fit inside.
+        let ellipse = GraphicsDisplayItem::Ellipse {
+            center: Point::new(5.0, 5.0),
+            radii: Vector::new(4.0, 0.5),
+            paint: stroke(StrokeAlignment::Inside),
+        };
+        match *ellipse.center_aligned() {
+            GraphicsDisplayItem::Ellipse { radii, .. } => assert_eq!(radii, Vector::new(3.0, 0.0)),
+            _ => panic!("ellipse changed shape"),
+        }
+    }
+
+    #[test]
+    fn test_dithering_thresholds() {
+        assert_eq!(Dithering::None.threshold(3, 5), 0.0);
+
+        // each threshold of the Bayer matrix occurs exactly once per tile.
+        let mut thresholds = (0..64)
+            .map(|i| Dithering::Ordered.threshold(i % 8, i / 8))
+            .map(|threshold| ((threshold + 0.5) * 64.0) as usize)
+            .collect::<Vec<_>>();
+        thresholds.sort_unstable();
+        assert_eq!(thresholds, (0..64).collect::<Vec<_>>());
+        assert_eq!(Dithering::Ordered.threshold(1, 2), Dithering::Ordered.threshold(9, -6));
+
+        let mean = (0..64 * 64)
+            .map(|i| Dithering::Noise.threshold(i % 64, i / 64))
+            .inspect(|threshold| assert!((-0.5..0.5).contains(threshold)))
+            .sum::<f32>()
+            / (64.0 * 64.0);
+        assert!(mean.abs() < 0.05);
+    }
+
+    #[test]
+    fn test_display_filters() {
+        let color = Color::new(1.0, 0.5, 0.0, 0.8);
+        let close = |a: Color, b: Color| {
+            [(a.red, b.red), (a.green, b.green), (a.blue, b.blue), (a.alpha, b.alpha)]
+                .iter()
+                .all(|(a, b)| (a - b).abs() < 1e-5)
+        };
+
+        assert_eq!(DisplayFilter::apply_all(&[], color), color);
+        assert!(close(
+            DisplayFilter::apply_all(&[DisplayFilter::Invert], color),
+            Color::new(0.0, 0.5, 1.0, 0.8)
+        ));
+        assert!(close(
+            DisplayFilter::apply_all(&[DisplayFilter::Invert, DisplayFilter::Invert], color),
+            color
+        ));
+
+        let gray = DisplayFilter::apply_all(&[DisplayFilter::Grayscale], color);
+        assert!((gray.red - (0.2126 + 0.7152 * 0.5)).abs() < 1e-5);
+        assert!(gray.red == gray.green && gray.green == gray.blue);
+
+        // the filters are applied in order, and clamped once at the end.
+        assert!(close(
+            DisplayFilter::apply_all(&[DisplayFilter::Grayscale, DisplayFilter::Invert], color),
+            Color::new(1.0 - gray.red, 1.0 - gray.red, 1.0 - gray.red, 0.8)
+        ));
+        assert!(close(
+            DisplayFilter::apply_all(&[DisplayFilter::Contrast(2.0)], color),
+            Color::new(1.0, 0.5, 0.0, 0.8)
+        ));
+        assert!(close(
+            DisplayFilter::apply_all(&[DisplayFilter::Contrast(0.0)], color),
+            Color::new(0.5, 0.5, 0.5, 0.8)
+        ));
+    }
+
+    #[test]
+    fn test_arc_bounds() {
+        let arc = |start_angle: f32, sweep_angle: f32, use_center: bool| {
+            GraphicsDisplayItem::Arc {
+                center: Point::new(10.0, 10.0),
+                radii: Vector::new(20.0, 10.0),
+                start_angle: Angle::degrees(start_angle),
+                sweep_angle: Angle::degrees(sweep_angle),
+                use_center,
+                paint: GraphicsDisplayPaint::Fill(StyleColor::Color(Color::default())),
+            }
+            .bounds()
+        };
+
+        // the bottom right quarter.
+        epsilon_rect(
+            &arc(0.0, 90.0, true),
+            &Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 10.0)),
+        );
+        epsilon_rect(
+            &arc(90.0, -90.0, false),
+            &Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 10.0)),
+        );
+        // the top half, through the top extreme.
+        epsilon_rect(
+            &arc(180.0, 180.0, false),
+            &Rect::new(Point::new(-10.0, 0.0), Size::new(40.0, 10.0)),
+        );
+        epsilon_rect(
+            &arc(45.0, 720.0, false),
+            &Rect::new(Point::new(-10.0, 0.0), Size::new(40.0, 20.0)),
+        );
+    }
+
+    #[test]
+    fn test_rect_clamp_within() {
+        let outer = Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0));
+
+        let rect = Rect::new(Point::new(-10.0, 90.0), Size::new(20.0, 20.0));
+        assert_eq!(rect.clamp_within(&outer), Rect::new(Point::new(0.0, 80.0), rect.size));
+
+        let rect = Rect::new(Point::new(40.0, 40.0), Size::new(20.0, 20.0));
+        assert_eq!(rect.clamp_within(&outer), rect);
+
+        // too large; aligned to the top-left.
+        let rect = Rect::new(Point::new(-50.0, 10.0), Size::new(150.0, 120.0));
+        assert_eq!(rect.clamp_within(&outer).origin, Point::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_rect_resize_edge() {
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(50.0, 50.0));
+        let min_size = Size::new(20.0, 20.0);
+
+        assert_eq!(
+            rect.resize_edge(ResizeEdge::Right, Vector::new(10.0, 99.0), min_size),
+            Rect::new(Point::new(10.0, 10.0), Size::new(60.0, 50.0))
+        );
+        assert_eq!(
+            rect.resize_edge(ResizeEdge::TopLeft, Vector::new(-5.0, 10.0), min_size),
+            Rect::new(Point::new(5.0, 20.0), Size::new(55.0, 40.0))
+        );
+
+        // the opposite edge stays in place once the minimum size is reached.
+        assert_eq!(
+            rect.resize_edge(ResizeEdge::Left, Vector::new(100.0, 0.0), min_size),
+            Rect::new(Point::new(40.0, 10.0), Size::new(20.0, 50.0))
+        );
+        assert_eq!(
+            rect.resize_edge(ResizeEdge::Bottom, Vector::new(0.0, -100.0), min_size),
+            Rect::new(Point::new(10.0, 10.0), Size::new(50.0, 20.0))
+        );
+    }
+
+    #[test]
+    fn test_clip_culling() {
+        let item = |x: f32, y: f32| {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                    rect: Rect::new(Point::new(x, y), Size::new(10.0, 10.0)),
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::Color(Color::new(
+                        0.0, 0.0, 0.0, 1.0,
+                    ))),
+                }),
+                None,
+            )
+        };
+
+        let list = vec![
+            item(500.0, 500.0),
+            DisplayCommand::Save,
+            DisplayCommand::Clip(DisplayClip::Rectangle {
+                rect: Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0)),
+                antialias: false,
+            }),
+            item(50.0, 50.0),
+            item(150.0, 50.0),
+            // scrolled into view.
+            DisplayCommand::Translate(Vector::new(-100.0, 0.0)),
+            item(150.0, 50.0),
+            DisplayCommand::Restore,
+            item(150.0, 50.0),
+        ];
+
+        let culled: Vec<_> = cull_display_list(&list, None).collect();
+        assert_eq!(culled.len(), list.len() - 1);
+        assert!(!culled.iter().any(|command| std::ptr::eq(*command, &list[4])));
+
+        // with an initial clip, the first item is culled too.
+        let initial = Rect::new(Point::new(0.0, 0.0), Size::new(200.0, 200.0));
+        assert_eq!(cull_display_list(&list, Some(initial)).count(), list.len() - 2);
+
+        // a blur spreads beyond the bounds of the item, into the clip.
+        let blurred = |x: f32, sigma: f32| match item(x, 50.0) {
+            DisplayCommand::Item(item, _) => {
+                DisplayCommand::Item(item, Some(Filter::Blur(sigma, sigma)))
+            }
+            _ => unreachable!(),
+        };
+        let list = vec![blurred(105.0, 2.0), blurred(105.0, 1.0)];
+        let clip = Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0));
+        let culled: Vec<_> = cull_display_list(&list, Some(clip)).collect();
+        assert_eq!(culled.len(), 1);
+        assert!(std::ptr::eq(culled[0], &list[0]));
+    }
+
+    #[test]
+    fn test_paragraph_cache() {
+        let font_info = FontInfo::from_data(
+            Arc::new(include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec()),
+            0,
+        )
+        .unwrap();
+
+        let text = |text: &str, x: f32| TextDisplayItem {
+            text: text.to_string().into(),
+            font: ResourceReference::Font(0),
+            font_info: font_info.clone(),
+            size: 16.0,
+            bottom_left: Point::new(x, 20.0),
+            color: StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+        };
+        let rect = |x: f32| Rect::new(Point::new(x, 0.0), Size::new(60.0, 100.0));
+
+        let mut cache = ParagraphCache::new(1);
+        let item = text("lorem ipsum dolor sit amet", 0.0);
+        let lines = cache.linebreak(&item, rect(0.0), 18.0, true).unwrap();
+        assert!(lines.len() > 1);
+        assert_eq!(cache.len(), 1);
+
+        // moved; same layout, different position.
+        let moved =
+            cache.linebreak(&text("lorem ipsum dolor sit amet", 50.0), rect(50.0), 18.0, true);
+        let moved = moved.unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(moved.len(), lines.len());
+        for (a, b) in lines.iter().zip(moved.iter()) {
+            assert_eq!(a.text, b.text);
+            assert_eq!(a.bottom_left + Vector::new(50.0, 0.0), b.bottom_left);
+        }
+
+        // evicts the least recently used.
+        cache.linebreak(&text("consectetur", 0.0), rect(0.0), 18.0, true).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        cache.invalidate_font(ResourceReference::Font(0));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_linebreak() {
+        let font_info = FontInfo::from_data(
+            Arc::new(include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec()),
+            0,
+        )
+        .unwrap();
+        let item = TextDisplayItem {
+            text: "lorem ipsum\ndolor sit amet".to_string().into(),
+            font: ResourceReference::Font(0),
+            font_info: font_info.clone(),
+            size: 16.0,
+            bottom_left: Point::new(10.0, 20.0),
+            color: StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+        };
+        let rect = Rect::new(Point::new(10.0, 0.0), Size::new(60.0, 100.0));
+
+        // the lines are those of the layout.
+        let layout = item.layout(Some(60.0)).unwrap();
+        let lines = item.clone().linebreak(rect, 18.0, true).unwrap();
+        assert_eq!(lines.len(), layout.lines().len());
+        for (i, (line, laid_out)) in lines.iter().zip(layout.lines()).enumerate() {
+            let DisplayText::Simple(text) = &item.text else { unreachable!() };
+            assert_eq!(
+                line.text,
+                DisplayText::Simple(text[laid_out.range.clone()].replace('\n', ""))
+            );
+            assert_eq!(line.bottom_left, Point::new(10.0, 20.0 + i as f32 * 18.0));
+        }
+
+        // shaped text is broken at the same glyphs.
+        let simple =
+            TextDisplayItem { text: "lorem ipsum dolor sit amet".to_string().into(), ..item };
+        let glyphs = simple.layout(None).unwrap().lines()[0].glyphs.clone();
+        let shaped = TextDisplayItem { text: glyphs.into(), ..simple.clone() };
+        let simple_lines = simple.linebreak(rect, 18.0, true).unwrap();
+        let shaped_lines = shaped.linebreak(rect, 18.0, true).unwrap();
+        assert!(shaped_lines.len() > 1);
+        assert_eq!(shaped_lines.len(), simple_lines.len());
+        for (shaped_line, simple_line) in shaped_lines.iter().zip(&simple_lines) {
+            assert_eq!(shaped_line.text.len(), simple_line.text.len());
+            assert_eq!(shaped_line.bottom_left, simple_line.bottom_left);
+        }
+    }
+
+    #[test]
+    fn test_batching() {
+        let rect = |x: f32, alpha: f32| {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                    rect: Rect::new(Point::new(x, 0.0), Size::new(10.0, 10.0)),
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::Color(Color::new(
+                        1.0, 0.0, 0.0, alpha,
+                    ))),
+                }),
+                None,
+            )
+        };
+        let stroked_line = |y: f32, stroke: GraphicsDisplayStroke| {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Line {
+                    a: Point::new(0.0, y),
+                    b: Point::new(100.0, y),
+                    stroke,
+                }),
+                None,
+            )
+        };
+        let line = |y: f32| {
+            stroked_line(
+                y,
+                GraphicsDisplayStroke {
+                    color: StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+                    thickness: 1.0,
+                    ..Default::default()
+                },
+            )
+        };
+        let dashed = GraphicsDisplayStroke {
+            dash: Some(StrokeDash { intervals: vec![4.0, 2.0], phase: 0.0 }),
+            ..Default::default()
+        };
+        let trimmed = GraphicsDisplayStroke { trim: Some((0.0, 0.5)), ..Default::default() };
+        let inside =
+            GraphicsDisplayStroke { alignment: StrokeAlignment::Inside, ..Default::default() };
+
+        let list = vec![
+            rect(0.0, 1.0),
+            rect(20.0, 1.0),
+            rect(25.0, 1.0),
+            // translucent and overlapping; must be drawn separately.
+            rect(0.0, 0.5),
+            rect(5.0, 0.5),
+            line(50.0),
+            line(60.0),
+            DisplayCommand::Save,
+            line(70.0),
+            // dashed and trimmed lines are never batched, and lines are only batched with identical strokes.
+            stroked_line(80.0, dashed.clone()),
+            stroked_line(90.0, dashed),
+            stroked_line(100.0, trimmed.clone()),
+            stroked_line(110.0, trimmed),
+            stroked_line(120.0, inside),
+            line(130.0),
+        ];
+
+        let batches = batch_display_list(&list);
+        let kinds: Vec<_> = batches
+            .iter()
+            .map(|batch| match batch {
+                DisplayBatch::Single(_) => "single".to_string(),
+                DisplayBatch::Rectangles(_, rects) => format!("rects({})", rects.len()),
+                DisplayBatch::Lines(_, lines) => format!("lines({})", lines.len()),
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "rects(3)", "single", "single", "lines(2)", "single", "single", "single", "single",
+                "single", "single", "single", "single"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resource_data_bytes() {
+        static DATA: &[u8] = b"reclutch";
+
+        let data = ResourceData::from(DATA);
+        assert_eq!(data.with_bytes(|bytes| bytes.as_ptr()).unwrap(), DATA.as_ptr());
+
+        let path = std::env::temp_dir().join(format!("reclutch-resource-{}", std::process::id()));
+        std::fs::write(&path, DATA).unwrap();
+        assert_eq!(
+            ResourceData::File(path.clone()).with_bytes(|bytes| bytes.to_vec()).unwrap(),
+            DATA
+        );
+        #[cfg(feature = "mmap")]
+        assert_eq!(
+            ResourceData::MappedFile(path.clone()).with_bytes(|bytes| bytes.to_vec()).unwrap(),
+            DATA
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(ResourceData::File(path).with_bytes(|_| ()).is_err());
+    }
+
+    #[test]
+    fn test_resource_data_stream() {
+        struct Broken;
+
+        impl std::io::Read for Broken {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+
+        let data = ResourceData::from_reader(std::io::Cursor::new(b"reclutch".to_vec()));
+        let clone = data.clone();
+        assert_eq!(data.with_bytes(|bytes| bytes.to_vec()).unwrap(), b"reclutch");
+        // the stream was consumed, however the clone shares what was read.
+        assert_eq!(clone.with_bytes(|bytes| bytes.len()).unwrap(), 8);
+        assert!(
+            matches!(clone.into_loaded().unwrap(), ResourceData::Data(ref data) if &**data == b"reclutch")
+        );
+
+        let broken = ResourceData::from_reader(Broken);
+        for _ in 0..2 {
+            match broken.with_bytes(|_| ()) {
+                Err(error::ResourceError::IoError(err)) => {
+                    assert_eq!(err.kind(), std::io::ErrorKind::ConnectionReset)
+                }
+                result => panic!("expected an I/O error, got {:?}", result),
+            }
+        }
+        assert_eq!(
+            broken.into_loaded().unwrap_err().kind(),
+            error::DisplayErrorKind::ResourceDecode
+        );
+    }
+
+    #[test]
+    fn test_command_group_hash() {
+        let mut clipped =
+            CommandGroup { handle: Some(CommandGroupHandle::new(1)), ..CommandGroup::new() };
+        clipped.set_clip(DisplayClip::Rectangle {
+            rect: Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0)),
+            antialias: false,
+        });
+
+        let hash = |group: &CommandGroup| {
+            use std::hash::{Hash, Hasher};
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            group.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // the clip takes part in equality, though not in the hash.
+        let unclipped =
+            CommandGroup { handle: Some(CommandGroupHandle::new(1)), ..CommandGroup::new() };
+        assert_eq!(clipped, clipped.clone());
+        assert_eq!(hash(&clipped), hash(&clipped.clone()));
+        assert_ne!(clipped, unclipped);
+        assert_eq!(hash(&clipped), hash(&unclipped));
+        assert_ne!(hash(&unclipped), hash(&CommandGroup::new()));
+    }
+
+    #[test]
+    fn test_scoped_command_group() {
+        let reaper = CommandGroupReaper::new();
+
+        let pushed =
+            CommandGroup { handle: Some(CommandGroupHandle::new(3)), ..CommandGroup::new() }
+                .scoped(&reaper);
+        let never_pushed = ScopedCommandGroup::new(&reaper);
+        let released =
+            CommandGroup { handle: Some(CommandGroupHandle::new(4)), ..CommandGroup::new() }
+                .scoped(&reaper);
+
+        drop(pushed);
+        drop(never_pushed);
+        assert_eq!(released.into_inner().handle(), Some(CommandGroupHandle::new(4)));
+
+        assert_eq!(reaper.pending(), 1);
+    }
+
+    #[test]
+    fn test_shared_resource() {
+        let mut display = backend::RecordingDisplay::new((4, 4));
+        let reaper = ResourceReaper::new();
+        let descriptor = ResourceDescriptor::Image(ImageData::placeholder());
+
+        let image = reaper.new_resource(&mut display, descriptor.clone()).unwrap();
+        let clone = image.clone();
+        assert_eq!(image.count(), 2);
+        assert_eq!(
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Image {
+                    src: None,
+                    dst: Rect::default(),
+                    resource: (&image).into(),
+                    orientation: Default::default(),
+                    adjustments: Default::default(),
+                }),
+                None,
+            )
+            .resource(),
+            Some(image.reference())
+        );
+
+        drop(image);
+        assert_eq!(reaper.pending(), 0);
+        let reference = clone.reference();
+        drop(clone);
+        assert_eq!(reaper.pending(), 1);
+        reaper.reap(&mut display);
+        assert_eq!(reaper.pending(), 0);
+        assert!(display.resource(reference).is_none());
+
+        let released = reaper.new_resource(&mut display, descriptor).unwrap();
+        let reference = released.reference();
+        assert_eq!(released.into_inner(), Ok(reference));
+        assert_eq!(reaper.pending(), 0);
+        assert!(display.resource(reference).is_some());
+    }
+
+    #[test]
+    fn test_image_orientation() {
+        let dst = Rect::new(Point::new(0.0, 0.0), Size::new(200.0, 100.0));
+
+        let sideways = ImageOrientation { rotation: ImageRotation::Rotate90, ..Default::default() };
+        let image_rect = sideways.image_rect(dst);
+        assert_eq!(image_rect, Rect::new(Point::new(50.0, -50.0), Size::new(100.0, 200.0)));
+        // once rotated, the image rectangle fills the destination.
+        epsilon_rect(&sideways.transform(dst).transform_rect(&image_rect), &dst);
+        assert_eq!(sideways.bounds(dst), dst);
+
+        let flipped = ImageOrientation { flip_horizontal: true, ..Default::default() };
+        assert!(!flipped.is_identity());
+        assert!(ImageOrientation::default().is_identity());
+        let top_left = flipped.transform(dst).transform_point(Point::new(0.0, 0.0));
+        assert!(approx_eq!(f32, top_left.x, 200.0, epsilon = TOLERANCE));
+
+        let tilted = ImageOrientation {
+            rotation: ImageRotation::Angle(Angle::degrees(45.0)),
+            ..Default::default()
+        };
+        assert!(tilted.bounds(dst).contains_rect(&dst));
+
+        assert_eq!(ImageRotation::Rotate270.rotate_cw(), ImageRotation::None);
+        assert_eq!(ImageRotation::None.rotate_ccw(), ImageRotation::Rotate270);
+        assert_eq!(
+            ImageRotation::Angle(Angle::degrees(100.0)).rotate_cw(),
+            ImageRotation::Rotate180
+        );
+        assert_eq!(
+            ImageRotation::Angle(Angle::degrees(100.0)).rotate_ccw(),
+            ImageRotation::Rotate90
+        );
+        assert_eq!(
+            ImageRotation::Angle(Angle::degrees(90.0)).rotate_cw(),
+            ImageRotation::Rotate180
+        );
+    }
+
+    #[test]
+    fn test_image_adjustments() {
+        let gray = Color::new(0.5, 0.5, 0.5, 1.0);
+        assert!(ImageAdjustments::default().is_identity());
+        assert_eq!(ImageAdjustments::default().apply(gray), gray);
+
+        let dimmed = ImageAdjustments { opacity: 0.5, ..Default::default() };
+        assert_eq!(dimmed.apply(gray), Color::new(0.5, 0.5, 0.5, 0.5));
+
+        let brighter = ImageAdjustments { exposure: 1.0, ..Default::default() };
+        assert_eq!(brighter.apply(Color::new(0.25, 0.1, 0.0, 1.0)), Color::new(0.5, 0.2, 0.0, 1.0));
+
+        // mid-gray is unaffected by contrast.
+        let flat = ImageAdjustments { contrast: 0.0, ..Default::default() };
+        assert_eq!(flat.apply(Color::new(1.0, 0.0, 0.5, 1.0)), gray);
+
+        let tinted =
+            ImageAdjustments { tint: Some(Color::new(1.0, 0.0, 0.0, 0.5)), ..Default::default() };
+        assert_eq!(tinted.apply(Color::new(1.0, 1.0, 1.0, 1.0)), Color::new(1.0, 0.5, 0.5, 1.0));
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let checkerboard = Checkerboard { origin: Point::new(4.0, 0.0), ..Default::default() };
+
+        assert_eq!(checkerboard.color_at(Point::new(4.0, 0.0)), checkerboard.light);
+        assert_eq!(checkerboard.color_at(Point::new(11.9, 7.9)), checkerboard.light);
+        assert_eq!(checkerboard.color_at(Point::new(12.0, 0.0)), checkerboard.dark);
+        assert_eq!(checkerboard.color_at(Point::new(3.0, 0.0)), checkerboard.dark);
+        assert_eq!(checkerboard.color_at(Point::new(3.0, -1.0)), checkerboard.light);
+    }
+
+    #[test]
+    fn test_preview_display_list() {
+        let source = Rect::new(Point::new(100.0, 0.0), Size::new(400.0, 200.0));
+        let dst = Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0));
+
+        let transform = fit_transform(source, dst);
+        assert_eq!(transform.transform_point(source.origin), Point::new(0.0, 25.0));
+        assert_eq!(transform.transform_point(source.max()), Point::new(100.0, 75.0));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        let preview = preview_display_list(&builder.build(), source, dst);
+
+        assert_eq!(preview.len(), 7);
+        match preview[1] {
+            DisplayCommand::Clip(DisplayClip::Rectangle { rect, .. }) => {
+                assert_eq!(rect, Rect::new(Point::new(0.0, 25.0), Size::new(100.0, 50.0)))
+            }
+            _ => panic!("expected a clip"),
+        }
+        match preview[3] {
+            DisplayCommand::Scale(scale) => assert_eq!(scale, Vector::new(0.25, 0.25)),
+            _ => panic!("expected a scale"),
+        }
+        assert!(matches!(preview[6], DisplayCommand::Restore));
+
+        assert!(preview_display_list(&[], Rect::default(), dst).is_empty());
+    }
+
+    #[test]
+    fn test_damage() {
+        let rect = |x: f32, y: f32| Rect::new(Point::new(x, y), Size::new(10.0, 10.0));
+        let mut damage = Damage::default();
+
+        damage.add(Rect::default());
+        assert!(damage.is_unchanged());
+        damage.add(rect(0.0, 0.0));
+        damage.add(rect(20.0, 0.0));
+        assert_eq!(damage, Damage::Region(Rect::new(Point::zero(), Size::new(30.0, 10.0))));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            rect(0.0, 0.0),
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        let commands = builder.build();
+        let bounds = display_list_bounds(&commands).unwrap();
+        let clip = DisplayClip::Rectangle { rect: rect(5.0, 5.0), antialias: false };
+
+        // clipped and transformed.
+        let mut damage = Damage::default();
+        damage.add_command_group(
+            &commands,
+            bounds,
+            Some(&clip),
+            &Transform::create_translation(100.0, 0.0),
+        );
+        assert_eq!(damage, Damage::Region(Rect::new(Point::new(105.0, 5.0), Size::new(5.0, 5.0))));
+
+        // clearing covers the entire clip, or everything if unclipped.
+        builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        let clear = builder.build();
+        let mut damage = Damage::default();
+        damage.add_command_group(&clear, Rect::default(), Some(&clip), &Transform::identity());
+        assert_eq!(damage, Damage::Region(rect(5.0, 5.0)));
+        damage.add_command_group(&clear, Rect::default(), None, &Transform::identity());
+        assert_eq!(damage, Damage::Full);
+        damage.add(rect(0.0, 0.0));
+        assert_eq!(damage, Damage::Full);
+    }
 }
@@ -0,0 +1,454 @@
+//! Progressive loading of encoded images, for data which arrives slowly (e.g. over a network or from slow storage).
+//!
+//! A [`ProgressiveImage`](struct.ProgressiveImage.html) accumulates encoded bytes as they arrive, and creates the image resource as soon as
+//! a low quality version of it can be decoded; that is, once the first scan of a progressive JPEG or the first passes of an interlaced (Adam7) PNG are loaded.
+//! The resource is then replaced whenever more of the image has been loaded, each time emitting an [`ImageRefined`](struct.ImageRefined.html) event:
+//!
+//! ```ignore
+//! // in update
+//! self.image.read_from(&mut self.file, 16 * 1024)?;
+//!
+//! // in draw
+//! display.report(self.image.update(display));
+//! if let Some(image) = self.image.resource() {
+//!     builder.push_image(None, bounds, image, None);
+//! }
+//! ```
+//!
+//! Images which are neither progressive nor interlaced are only created once they're complete.
+//! Intermediate resources are created from truncated data, which the display must be able to decode (the Skia display can);
+//! if it can't, the image simply appears once it's complete.
+
+use {
+    super::{
+        GraphicsDisplay, ImageData, ResourceData, ResourceDescriptor, ResourceReference, SharedData,
+    },
+    crate::error,
+    reclutch_event::{prelude::*, RcEventQueue},
+    std::{io::Read, sync::Arc},
+};
+
+/// How an encoded image can be refined as more of it is loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProgressiveFormat {
+    /// A JPEG consisting of several scans, each adding detail to the entire image.
+    ProgressiveJpeg,
+    /// A PNG interlaced with Adam7, where each pass doubles the number of pixels known.
+    InterlacedPng,
+    /// Any other image, which can only be shown once it's complete.
+    Sequential,
+}
+
+impl ProgressiveFormat {
+    /// Detects the format from the start of the encoded data, returning `None` if more data is needed to tell.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        scan(data).map(|progress| progress.format)
+    }
+}
+
+/// Emitted by a [`ProgressiveImage`](struct.ProgressiveImage.html) whenever its image resource is replaced by a more detailed one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageRefined {
+    /// The new image resource. The previous one has already been removed from the display.
+    pub resource: ResourceReference,
+    /// How many times the image was refined, starting at `1` for the first version of the image.
+    pub pass: u32,
+    /// Whether the image is fully loaded, in which case this is the last refinement.
+    pub complete: bool,
+}
+
+/// An image resource which is created from partially loaded data and refined as the rest arrives.
+///
+/// See the [module documentation](index.html).
+#[derive(Debug)]
+pub struct ProgressiveImage {
+    data: Vec<u8>,
+    finished: bool,
+    complete: bool,
+    // complete scans (JPEG) or image data bytes (PNG) at the last refinement.
+    refined: usize,
+    pass: u32,
+    resource: Option<ResourceReference>,
+    pub event: RcEventQueue<ImageRefined>,
+}
+
+impl Default for ProgressiveImage {
+    fn default() -> Self {
+        ProgressiveImage {
+            data: Vec::new(),
+            finished: false,
+            complete: false,
+            refined: 0,
+            pass: 0,
+            resource: None,
+            event: RcEventQueue::new(),
+        }
+    }
+}
+
+impl ProgressiveImage {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Appends newly arrived encoded data.
+    pub fn push(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    /// Reads at most `limit` bytes from `reader` (such that loading can be spread over several updates),
+    /// returning how many bytes were read. Reaching the end of `reader` [finishes](struct.ProgressiveImage.html#method.finish) the image.
+    pub fn read_from(&mut self, reader: &mut impl Read, limit: usize) -> std::io::Result<usize> {
+        let read = reader.take(limit as u64).read_to_end(&mut self.data)?;
+        if read == 0 && limit > 0 {
+            self.finish();
+        }
+        Ok(read)
+    }
+
+    /// Marks the data as complete, even if the end of the image hasn't been recognized.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Returns the format of the image, if enough data has been loaded to tell.
+    pub fn format(&self) -> Option<ProgressiveFormat> {
+        ProgressiveFormat::detect(&self.data)
+    }
+
+    /// Returns the number of encoded bytes loaded so far.
+    #[inline]
+    pub fn loaded_bytes(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns the latest version of the image resource, if one has been created yet.
+    #[inline]
+    pub fn resource(&self) -> Option<ResourceReference> {
+        self.resource
+    }
+
+    /// Returns `true` if the image resource was created from the complete data, or failed to be.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Replaces the image resource if enough data has been loaded since the last refinement, returning the new resource.
+    ///
+    /// Failing to decode an incomplete image isn't an error (the display may not be able to decode truncated data);
+    /// only failing to decode the complete image is returned, after which the image isn't updated anymore.
+    pub fn update<D: Sized>(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<D>,
+    ) -> Result<Option<ResourceReference>, error::ResourceError> {
+        if self.complete {
+            return Ok(None);
+        }
+
+        let progress = match scan(&self.data) {
+            Some(progress) => progress,
+            None if self.finished => Progress::default(),
+            None => return Ok(None),
+        };
+        let complete = self.finished || progress.end;
+
+        let refine = complete
+            || match progress.format {
+                ProgressiveFormat::ProgressiveJpeg => progress.refinable > self.refined,
+                // each Adam7 pass holds (roughly) as much image data as all the passes before it.
+                ProgressiveFormat::InterlacedPng => progress.refinable >= (self.refined * 2).max(1),
+                ProgressiveFormat::Sequential => false,
+            };
+        if !refine {
+            return Ok(None);
+        }
+
+        let descriptor = ResourceDescriptor::Image(ImageData::Encoded(ResourceData::Data(
+            SharedData::RefCount(Arc::new(self.data.clone())),
+        )));
+        let resource = match display.new_resource(descriptor) {
+            Ok(resource) => resource,
+            Err(err) if !complete && err.kind() == error::DisplayErrorKind::ResourceDecode => {
+                return Ok(None)
+            }
+            Err(err) => {
+                self.complete = true;
+                return Err(err);
+            }
+        };
+
+        if let Some(previous) = self.resource.replace(resource) {
+            display.remove_resource(previous);
+        }
+        self.refined = progress.refinable;
+        self.complete = complete;
+        self.pass += 1;
+        self.event.emit_owned(ImageRefined { resource, pass: self.pass, complete });
+
+        Ok(Some(resource))
+    }
+
+    /// Removes the image resource from the display.
+    pub fn remove<D: Sized>(self, display: &mut dyn GraphicsDisplay<D>) {
+        if let Some(resource) = self.resource {
+            display.remove_resource(resource);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Progress {
+    format: ProgressiveFormat,
+    // complete scans for JPEG, complete image data bytes for PNG.
+    refinable: usize,
+    end: bool,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress { format: ProgressiveFormat::Sequential, refinable: 0, end: false }
+    }
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn scan(data: &[u8]) -> Option<Progress> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        scan_jpeg(data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        scan_png(data)
+    } else if data.len() >= PNG_SIGNATURE.len() {
+        Some(Progress::default())
+    } else {
+        None
+    }
+}
+
+fn scan_jpeg(data: &[u8]) -> Option<Progress> {
+    let mut progressive = None;
+    let mut scans = 0;
+    let mut end = false;
+
+    let mut pos = 2;
+    while let Some(&marker) = data.get(pos + 1) {
+        if data[pos] != 0xFF {
+            break;
+        }
+
+        match marker {
+            // fill bytes.
+            0xFF => {
+                pos += 1;
+                continue;
+            }
+            0xD9 => {
+                end = true;
+                break;
+            }
+            0x01 | 0xD0..=0xD8 => {
+                pos += 2;
+                continue;
+            }
+            _ => {}
+        }
+
+        let length = match data.get(pos + 2..pos + 4) {
+            Some(length) => u16::from_be_bytes([length[0], length[1]]) as usize,
+            None => break,
+        };
+        let segment_end = pos + 2 + length;
+
+        match marker {
+            // start of frame; SOF2, SOF6, SOF10 and SOF14 are progressive.
+            0xC0..=0xCF if marker != 0xC4 && marker != 0xC8 && marker != 0xCC => {
+                progressive = Some(marker & 0x03 == 0x02);
+            }
+            // start of scan; the entropy-coded data continues until the next marker other than a restart marker.
+            0xDA => {
+                let next_marker = data
+                    .get(segment_end..)
+                    .and_then(|entropy| {
+                        entropy.windows(2).position(|pair| {
+                            pair[0] == 0xFF && pair[1] != 0x00 && !(0xD0..=0xD7).contains(&pair[1])
+                        })
+                    })
+                    .map(|offset| segment_end + offset);
+
+                match next_marker {
+                    Some(next_marker) => {
+                        scans += 1;
+                        pos = next_marker;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            _ => {}
+        }
+
+        pos = segment_end;
+    }
+
+    let format = match progressive? {
+        true => ProgressiveFormat::ProgressiveJpeg,
+        false => ProgressiveFormat::Sequential,
+    };
+    Some(Progress { format, refinable: scans, end })
+}
+
+fn scan_png(data: &[u8]) -> Option<Progress> {
+    // the interlace method is the last byte of IHDR, which is always the first chunk.
+    let interlaced = *data.get(PNG_SIGNATURE.len() + 8 + 12)? == 1;
+    let mut image_data = 0;
+    let mut end = false;
+
+    let mut pos = PNG_SIGNATURE.len();
+    while let Some(header) = data.get(pos..pos + 8) {
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let chunk_end = pos + 8 + length + 4;
+        if data.len() < chunk_end {
+            break;
+        }
+
+        match &header[4..] {
+            b"IDAT" => image_data += length,
+            b"IEND" => {
+                end = true;
+                break;
+            }
+            _ => {}
+        }
+
+        pos = chunk_end;
+    }
+
+    let format = match interlaced {
+        true => ProgressiveFormat::InterlacedPng,
+        false => ProgressiveFormat::Sequential,
+    };
+    Some(Progress { format, refinable: image_data, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::backend::RecordingDisplay, *};
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xFF, marker];
+        segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    fn progressive_jpeg(scans: usize) -> Vec<u8> {
+        let mut data = vec![0xFF, 0xD8];
+        data.extend(segment(0xE0, b"JFIF\0"));
+        data.extend(segment(0xC2, &[8, 0, 16, 0, 16, 1, 1, 0x11, 0]));
+        for _ in 0..scans {
+            data.extend(segment(0xDA, &[1, 1, 0, 0, 63, 0]));
+            // entropy-coded data, including a stuffed 0xFF and a restart marker.
+            data.extend_from_slice(&[0x12, 0xFF, 0x00, 0x34, 0xFF, 0xD0, 0x56]);
+        }
+        data.extend_from_slice(&[0xFF, 0xD9]);
+        data
+    }
+
+    fn chunk(kind: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut chunk = (payload.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(payload);
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    fn interlaced_png(idat: &[usize]) -> Vec<u8> {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend(chunk(b"IHDR", &[0, 0, 0, 16, 0, 0, 0, 16, 8, 6, 0, 0, 1]));
+        for &length in idat {
+            data.extend(chunk(b"IDAT", &vec![0; length]));
+        }
+        data.extend(chunk(b"IEND", &[]));
+        data
+    }
+
+    #[test]
+    fn test_format_detection() {
+        let jpeg = progressive_jpeg(3);
+        assert_eq!(ProgressiveFormat::detect(&jpeg[..4]), None);
+        assert_eq!(ProgressiveFormat::detect(&jpeg), Some(ProgressiveFormat::ProgressiveJpeg));
+
+        let mut baseline = jpeg.clone();
+        let sof = baseline.windows(2).position(|pair| pair == [0xFF, 0xC2]).unwrap();
+        baseline[sof + 1] = 0xC0;
+        assert_eq!(ProgressiveFormat::detect(&baseline), Some(ProgressiveFormat::Sequential));
+
+        let png = interlaced_png(&[10]);
+        assert_eq!(ProgressiveFormat::detect(&png[..20]), None);
+        assert_eq!(ProgressiveFormat::detect(&png), Some(ProgressiveFormat::InterlacedPng));
+
+        assert_eq!(ProgressiveFormat::detect(b"GIF89a\0\0"), Some(ProgressiveFormat::Sequential));
+    }
+
+    #[test]
+    fn test_jpeg_scans() {
+        let jpeg = progressive_jpeg(3);
+        let progress = scan(&jpeg).unwrap();
+        assert_eq!((progress.refinable, progress.end), (3, true));
+
+        // a scan is only complete once the marker after it has arrived.
+        let progress = scan(&jpeg[..jpeg.len() - 2]).unwrap();
+        assert_eq!((progress.refinable, progress.end), (2, false));
+    }
+
+    #[test]
+    fn test_progressive_refinement() {
+        let jpeg = progressive_jpeg(3);
+        let mut display = RecordingDisplay::new((16, 16));
+        let mut image = ProgressiveImage::new();
+        let listener = image.event.listen();
+
+        // the header alone isn't enough for an image.
+        image.push(&jpeg[..30]);
+        assert_eq!(image.update(&mut display).unwrap(), None);
+
+        let mut reader = &jpeg[30..];
+        let mut resources = Vec::new();
+        while !image.is_complete() {
+            image.read_from(&mut reader, 8).unwrap();
+            resources.extend(image.update(&mut display).unwrap());
+        }
+
+        // one refinement per scan, the last of which is complete.
+        let events = listener.peek();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events.iter().map(|event| event.pass).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(events.iter().filter(|event| event.complete).count(), 1);
+        assert!(events[2].complete);
+        assert_eq!(events.iter().map(|event| event.resource).collect::<Vec<_>>(), resources);
+        assert_eq!(image.resource(), Some(resources[2]));
+        assert_eq!(image.loaded_bytes(), jpeg.len());
+
+        // previous versions were removed.
+        assert_eq!(display.shutdown().resources.len(), 1);
+    }
+
+    #[test]
+    fn test_interlaced_refinement() {
+        let png = interlaced_png(&[100, 100, 100, 100, 100, 100, 100, 100]);
+        let mut display = RecordingDisplay::new((16, 16));
+        let mut image = ProgressiveImage::new();
+        let listener = image.event.listen();
+
+        for byte in png.chunks(1) {
+            image.push(byte);
+            image.update(&mut display).unwrap();
+        }
+
+        // refined at 1, 2, 4 and 8 chunks of image data, the last of which is followed by the end.
+        let events = listener.peek();
+        assert_eq!(events.len(), 5);
+        assert!(events[4].complete);
+        assert!(image.is_complete());
+    }
+}
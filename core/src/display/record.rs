@@ -0,0 +1,888 @@
+//! Recording display lists to files, for snapshot testing and replay (behind the `serialize` feature).
+//!
+//! A [`DisplayListRecorder`](struct.DisplayListRecorder.html) is a display which keeps every frame it presents as a [`DisplayList`](struct.DisplayList.html);
+//! the command groups which were drawn, in order, along with their transformation, clip and layer.
+//! Display lists can be saved as JSON (readable, and diffable in snapshot tests) or as CBOR (compact),
+//! and later loaded and replayed onto any other display through a [`ReplayDisplay`](struct.ReplayDisplay.html).
+//! Frames can also be [streamed](struct.DisplayListRecorder.html#method.stream_to) as they're presented,
+//! for example to render the UI of one process in another.
+//!
+//! ```ignore
+//! let mut recorder = DisplayListRecorder::new((640, 480));
+//! widget.draw(&mut recorder, &mut aux);
+//! recorder.present(None)?;
+//! recorder.save("snapshot.json")?;
+//! ```
+//!
+//! Resources are recorded as they're created and removed, along with their data (such that recordings are self-contained,
+//! even when streamed to a process which can't read the files of the application).
+//! When replayed, they're created on the target display and the references to them are mapped onto the new resources.
+
+use {
+    super::{backend::*, *},
+    crate::error::ResourceError,
+    serde::{Deserialize, Serialize},
+    std::{collections::HashMap, fs, io, path::Path},
+};
+
+/// File formats display lists can be saved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayListFormat {
+    Json,
+    /// Binary, in the [CBOR](https://cbor.io) format.
+    Cbor,
+}
+
+impl DisplayListFormat {
+    /// Returns the format matching the extension of `path` (`json` or `cbor`), if any.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        match path.as_ref().extension()?.to_str()? {
+            "json" => Some(DisplayListFormat::Json),
+            "cbor" => Some(DisplayListFormat::Cbor),
+            _ => None,
+        }
+    }
+}
+
+/// A command group as drawn in a recorded frame.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecordedCommandGroup {
+    pub commands: Vec<DisplayCommand>,
+    pub protected: bool,
+    pub clip: Option<DisplayClip>,
+    pub transform: Transform,
+    pub layer: DisplayLayer,
+    /// Opacity of the layer at the time of the frame.
+    pub opacity: f32,
+    pub render_scale: f32,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub scissor: Option<Rect>,
+}
+
+/// The data of a recorded resource, loaded into memory.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum RecordedResource {
+    EncodedImage(Vec<u8>),
+    RawImage(Vec<u8>, RasterImageInfo),
+    Font(Vec<u8>),
+}
+
+impl RecordedResource {
+    /// Loads the data of `descriptor` (reading files and streams).
+    pub fn load(descriptor: &ResourceDescriptor) -> Result<Self, ResourceError> {
+        let load = |data: &ResourceData| data.with_bytes(<[u8]>::to_vec);
+        Ok(match descriptor {
+            ResourceDescriptor::Image(ImageData::Encoded(data)) => {
+                RecordedResource::EncodedImage(load(data)?)
+            }
+            ResourceDescriptor::Image(ImageData::Raw(data, info)) => {
+                RecordedResource::RawImage(load(data)?, *info)
+            }
+            ResourceDescriptor::Font(data) => RecordedResource::Font(load(data)?),
+        })
+    }
+
+    /// Returns a descriptor to create the resource from.
+    pub fn descriptor(&self) -> ResourceDescriptor {
+        match self {
+            RecordedResource::EncodedImage(data) => {
+                ResourceDescriptor::Image(ImageData::Encoded(data.clone().into()))
+            }
+            RecordedResource::RawImage(data, info) => {
+                ResourceDescriptor::Image(ImageData::Raw(data.clone().into(), *info))
+            }
+            RecordedResource::Font(data) => ResourceDescriptor::Font(data.clone().into()),
+        }
+    }
+}
+
+/// A resource created or removed before a frame was presented, by the reference it was recorded under.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum ResourceEvent {
+    Created(ResourceReference, RecordedResource),
+    Removed(ResourceReference),
+}
+
+/// Resources created on a display by replaying frames, by the references they were recorded under.
+#[derive(Debug, Clone, Default)]
+pub struct ReplayedResources {
+    references: HashMap<ResourceReference, ResourceReference>,
+}
+
+impl ReplayedResources {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates and removes resources on `display` as recorded by `events`.
+    pub fn apply(
+        &mut self,
+        events: &[ResourceEvent],
+        display: &mut dyn GraphicsDisplay,
+    ) -> Result<(), ResourceError> {
+        for event in events {
+            match event {
+                ResourceEvent::Created(recorded, resource) => {
+                    let reference = display.new_resource(resource.descriptor())?;
+                    if let Some(replaced) = self.references.insert(*recorded, reference) {
+                        display.remove_resource(replaced);
+                    }
+                }
+                ResourceEvent::Removed(recorded) => {
+                    if let Some(reference) = self.references.remove(recorded) {
+                        display.remove_resource(reference);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the reference on the display of the resource recorded as `recorded`.
+    ///
+    /// Resources which weren't recorded (e.g. in recordings made before resources were) keep their reference,
+    /// such that they can still be replayed by creating the same resources on the display first, in the same order.
+    pub fn get(&self, recorded: ResourceReference) -> ResourceReference {
+        self.references.get(&recorded).copied().unwrap_or(recorded)
+    }
+
+    /// Maps the resource references of `commands` onto the display.
+    pub fn map_commands(&self, commands: &[DisplayCommand]) -> Vec<DisplayCommand> {
+        let mut commands = commands.to_vec();
+        for reference in commands.iter_mut().filter_map(DisplayCommand::resource_mut) {
+            *reference = self.get(*reference);
+        }
+        commands
+    }
+
+    /// Removes every replayed resource from `display`.
+    pub fn clear(&mut self, display: &mut dyn GraphicsDisplay) {
+        for (_, reference) in self.references.drain() {
+            display.remove_resource(reference);
+        }
+    }
+}
+
+/// The command groups drawn by a single present, in draw order.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub size: (u32, u32),
+    pub cull: Option<Rect>,
+    pub command_groups: Vec<RecordedCommandGroup>,
+    /// Resources created or removed since the previous frame.
+    #[serde(default)]
+    pub resources: Vec<ResourceEvent>,
+}
+
+impl RecordedFrame {
+    /// Creates and removes the resources as recorded, then pushes the command groups of the frame into `display`,
+    /// returning their handles in draw order.
+    ///
+    /// The command groups are pushed as `always_alive` so that they can be presented any number of times,
+    /// and should be removed once the replay is no longer needed. The same `resources` should be passed for each frame of a display list
+    /// (since the resources created by a frame may be used by the later ones), and [cleared](struct.ReplayedResources.html#method.clear) once done.
+    pub fn replay(
+        &self,
+        display: &mut dyn GraphicsDisplay,
+        resources: &mut ReplayedResources,
+    ) -> Result<Vec<CommandGroupHandle>, Box<dyn std::error::Error>> {
+        resources.apply(&self.resources, display)?;
+
+        let mut handles = Vec::with_capacity(self.command_groups.len());
+        for group in &self.command_groups {
+            // all the command groups share a z-order, so they're drawn in the order they're pushed.
+            let handle = display.push_command_group(
+                &resources.map_commands(&group.commands),
+                ZOrder::default(),
+                Some(group.protected),
+                Some(true),
+            )?;
+            display.set_command_group_clip(handle, group.clip.clone());
+            display.set_command_group_transform(handle, group.transform);
+            display.set_command_group_layer(handle, group.layer);
+            display.set_layer_opacity(group.layer, group.opacity);
+            display.set_command_group_render_scale(handle, group.render_scale);
+            display.set_command_group_label(handle, group.label.as_deref());
+            if group.scissor.is_some() {
+                display.set_command_group_scissor(handle, group.scissor);
+            }
+            handles.push(handle);
+        }
+        Ok(handles)
+    }
+
+    /// Writes the frame to `writer` in `format`, such that frames can be sent one at a time (e.g. to another process).
+    ///
+    /// See [`read_frames`](fn.read_frames.html).
+    pub fn write<W: io::Write>(
+        &self,
+        mut writer: W,
+        format: DisplayListFormat,
+    ) -> Result<(), ResourceError> {
+        match format {
+            DisplayListFormat::Json => {
+                serde_json::to_writer(&mut writer, self).map_err(json_error)?;
+                writer.write_all(b"\n")?;
+            }
+            DisplayListFormat::Cbor => {
+                serde_cbor::to_writer(&mut writer, self).map_err(cbor_error)?
+            }
+        }
+        Ok(writer.flush()?)
+    }
+}
+
+/// A sequence of recorded frames.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DisplayList {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Writes the display list to `writer` in `format`.
+    pub fn write<W: io::Write>(
+        &self,
+        writer: W,
+        format: DisplayListFormat,
+    ) -> Result<(), ResourceError> {
+        match format {
+            DisplayListFormat::Json => {
+                serde_json::to_writer_pretty(writer, self).map_err(json_error)
+            }
+            DisplayListFormat::Cbor => serde_cbor::to_writer(writer, self).map_err(cbor_error),
+        }
+    }
+
+    /// Reads a display list in `format` from `reader`.
+    pub fn read<R: io::Read>(reader: R, format: DisplayListFormat) -> Result<Self, ResourceError> {
+        match format {
+            DisplayListFormat::Json => serde_json::from_reader(reader).map_err(json_error),
+            DisplayListFormat::Cbor => serde_cbor::from_reader(reader).map_err(cbor_error),
+        }
+    }
+
+    /// Saves the display list to the file at `path`, in the format matching its extension.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ResourceError> {
+        let format = format_of(path.as_ref())?;
+        self.write(io::BufWriter::new(fs::File::create(path)?), format)
+    }
+
+    /// Loads a display list from the file at `path`, in the format matching its extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ResourceError> {
+        let format = format_of(path.as_ref())?;
+        Self::read(io::BufReader::new(fs::File::open(path)?), format)
+    }
+}
+
+/// Reads consecutive frames written by [`RecordedFrame::write`](struct.RecordedFrame.html#method.write) (or streamed by a
+/// [`DisplayListRecorder`](struct.DisplayListRecorder.html#method.stream_to)) from `reader`, until the end of the stream or the first error.
+pub fn read_frames<'a, R: io::Read + 'a>(
+    reader: R,
+    format: DisplayListFormat,
+) -> Box<dyn Iterator<Item = Result<RecordedFrame, ResourceError>> + 'a> {
+    match format {
+        DisplayListFormat::Json => Box::new(
+            serde_json::Deserializer::from_reader(reader)
+                .into_iter()
+                .map(|frame| frame.map_err(json_error)),
+        ),
+        DisplayListFormat::Cbor => Box::new(
+            serde_cbor::Deserializer::from_reader(reader)
+                .into_iter()
+                .map(|frame| frame.map_err(cbor_error)),
+        ),
+    }
+}
+
+fn json_error(error: serde_json::Error) -> ResourceError {
+    match error.classify() {
+        serde_json::error::Category::Io => ResourceError::IoError(error.into()),
+        _ => ResourceError::InvalidData,
+    }
+}
+
+fn cbor_error(error: serde_cbor::Error) -> ResourceError {
+    if error.is_io() {
+        ResourceError::IoError(io::Error::other(error))
+    } else {
+        ResourceError::InvalidData
+    }
+}
+
+fn format_of(path: &Path) -> Result<DisplayListFormat, ResourceError> {
+    DisplayListFormat::from_path(path).ok_or_else(|| {
+        ResourceError::Unsupported(format!("display list file {}", path.to_string_lossy()))
+    })
+}
+
+/// A display which records the command groups drawn by each present into a [`DisplayList`](struct.DisplayList.html), rather than drawing them.
+///
+/// Command groups are ordered and maintained the same way as in the other backends.
+#[derive(Default)]
+pub struct DisplayListRecorder {
+    size: (u32, u32),
+    resources: ResourceRegistry<()>,
+    groups: CommandGroupStore,
+    list: DisplayList,
+    // created and removed since the last present.
+    resource_events: Vec<ResourceEvent>,
+    stream: Option<(Box<dyn io::Write>, DisplayListFormat)>,
+    errors: Vec<error::DisplayError>,
+}
+
+impl DisplayListRecorder {
+    pub fn new(size: (u32, u32)) -> Self {
+        DisplayListRecorder { size, ..Default::default() }
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the frames recorded so far.
+    #[inline]
+    pub fn display_list(&self) -> &DisplayList {
+        &self.list
+    }
+
+    /// Returns the frames recorded so far, leaving the recording empty.
+    pub fn take_display_list(&mut self) -> DisplayList {
+        std::mem::take(&mut self.list)
+    }
+
+    /// Saves the frames recorded so far to the file at `path`, in the format matching its extension.
+    #[inline]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ResourceError> {
+        self.list.save(path)
+    }
+
+    /// Writes each frame to `writer` as it's presented, rather than keeping it in the display list,
+    /// such that the frames can be [read](fn.read_frames.html) and replayed elsewhere (e.g. in another process) while the application runs.
+    ///
+    /// `None` stops streaming, after which frames are kept in the display list again.
+    pub fn stream_to(&mut self, stream: Option<(Box<dyn io::Write>, DisplayListFormat)>) {
+        self.stream = stream;
+    }
+}
+
+impl GraphicsDisplay for DisplayListRecorder {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = size;
+        self.groups.invalidate_all();
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let resource = RecordedResource::load(&descriptor)?;
+        let reference = self.resources.insert(&descriptor, ());
+        self.resource_events.push(ResourceEvent::Created(reference, resource));
+        Ok(reference)
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        if self.resources.remove(reference).is_some() {
+            self.resource_events.push(ResourceEvent::Removed(reference));
+        }
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+        Ok(self.groups.push(
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+        )?)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.groups.get(handle).map(|group| &group.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            false,
+        );
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.groups.remove(handle).map(|group| group.commands)
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, false);
+    }
+
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.groups.set_clip(handle, clip);
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.groups.set_transform(handle, transform);
+    }
+
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.groups.set_layer(handle, layer);
+    }
+
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        self.groups.set_layer_visible(layer, visible);
+    }
+
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        self.groups.set_layer_opacity(layer, opacity);
+    }
+
+    fn set_command_group_render_scale(&mut self, handle: CommandGroupHandle, scale: f32) {
+        self.groups.set_render_scale(handle, scale);
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        self.groups.set_scissor(handle, scissor);
+    }
+
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        self.groups.set_label(handle, label);
+    }
+
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.groups.get(handle)?.label.as_deref()
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            true,
+        );
+    }
+
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, true);
+    }
+
+    fn expire_command_groups(&mut self) {
+        self.groups.expire();
+    }
+
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        Some(self.groups.maintenance_count())
+    }
+
+    fn before_exit(&mut self) {}
+
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+        LeakReport {
+            resources: self.resources.take_leaks(),
+            command_groups: self.groups.take_handles(),
+        }
+    }
+
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn take_damage(&mut self) -> Damage {
+        self.groups.take_damage()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+        let mut command_groups = Vec::new();
+        for handle in self.groups.present(cull) {
+            let group = match self.groups.get(handle) {
+                Some(group) => group,
+                None => continue,
+            };
+
+            // the resources are recorded as they're created, so they're only checked here.
+            for resolved in resolve_commands(&group.commands, &self.resources) {
+                resolved?;
+            }
+
+            command_groups.push(RecordedCommandGroup {
+                commands: group.commands.clone(),
+                protected: group.protected,
+                clip: group.clip.clone(),
+                transform: group.transform,
+                layer: group.layer,
+                opacity: self.groups.layer_state(group.layer).1,
+                render_scale: group.render_scale,
+                label: group.label.clone(),
+                scissor: group.scissor,
+            });
+        }
+
+        let frame = RecordedFrame {
+            size: self.size,
+            cull,
+            command_groups,
+            resources: std::mem::take(&mut self.resource_events),
+        };
+        match self.stream {
+            Some((ref mut writer, format)) => frame.write(writer, format)?,
+            None => self.list.frames.push(frame),
+        }
+        Ok(())
+    }
+}
+
+/// Replays recorded frames onto another display, e.g. to compare a recording against the output of a backend in regression tests,
+/// or to draw the frames streamed from another process.
+///
+/// ```ignore
+/// let mut replay = ReplayDisplay::new(SoftwareGraphicsDisplay::new((640, 480)));
+/// replay.play_file("snapshot.json", |display, index| {
+///     assert_eq!(display.to_rgba8(), expected[index]);
+/// })?;
+/// ```
+pub struct ReplayDisplay<G: GraphicsDisplay> {
+    display: G,
+    size: Option<(u32, u32)>,
+    handles: Vec<CommandGroupHandle>,
+    resources: ReplayedResources,
+    frames: u64,
+}
+
+impl<G: GraphicsDisplay> ReplayDisplay<G> {
+    pub fn new(display: G) -> Self {
+        ReplayDisplay {
+            display,
+            size: None,
+            handles: Vec::new(),
+            resources: ReplayedResources::new(),
+            frames: 0,
+        }
+    }
+
+    /// Replaces the command groups of the previously replayed frame with those of `frame`, and presents it.
+    ///
+    /// The resources created and removed before the frame was recorded are created and removed on the display too.
+    ///
+    /// The display is resized to the size of the frame, if it differs from that of the previous frame.
+    pub fn present_frame(&mut self, frame: &RecordedFrame) -> Result<(), error::DisplayError> {
+        for handle in self.handles.drain(..) {
+            self.display.remove_command_group(handle);
+        }
+
+        if self.size != Some(frame.size) {
+            self.size = Some(frame.size);
+            self.display.resize(frame.size)?;
+        }
+
+        self.handles = frame.replay(&mut self.display, &mut self.resources)?;
+        self.display.present(frame.cull)?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Presents each frame of `list` in turn, invoking `f` with the display and the index of the frame after each present.
+    pub fn play<F>(&mut self, list: &DisplayList, mut f: F) -> Result<(), error::DisplayError>
+    where
+        F: FnMut(&mut G, usize),
+    {
+        for (index, frame) in list.frames.iter().enumerate() {
+            self.present_frame(frame)?;
+            f(&mut self.display, index);
+        }
+        Ok(())
+    }
+
+    /// Loads the display list saved at `path` and [plays](struct.ReplayDisplay.html#method.play) it.
+    pub fn play_file<P, F>(&mut self, path: P, f: F) -> Result<(), error::DisplayError>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&mut G, usize),
+    {
+        self.play(&DisplayList::load(path)?, f)
+    }
+
+    /// Presents each frame read from `reader` as it arrives, until the end of the stream.
+    ///
+    /// See [`read_frames`](fn.read_frames.html).
+    pub fn play_stream<R, F>(
+        &mut self,
+        reader: R,
+        format: DisplayListFormat,
+        mut f: F,
+    ) -> Result<(), error::DisplayError>
+    where
+        R: io::Read,
+        F: FnMut(&mut G, usize),
+    {
+        for (index, frame) in read_frames(reader, format).enumerate() {
+            self.present_frame(&frame?)?;
+            f(&mut self.display, index);
+        }
+        Ok(())
+    }
+
+    /// Number of frames presented.
+    #[inline]
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    #[inline]
+    pub fn display(&self) -> &G {
+        &self.display
+    }
+
+    #[inline]
+    pub fn display_mut(&mut self) -> &mut G {
+        &mut self.display
+    }
+
+    /// Returns the resources created on the display so far.
+    #[inline]
+    pub fn resources(&self) -> &ReplayedResources {
+        &self.resources
+    }
+
+    /// Returns the display, without the command groups of the last replayed frame or the replayed resources.
+    pub fn into_inner(mut self) -> G {
+        for handle in self.handles.drain(..) {
+            self.display.remove_command_group(handle);
+        }
+        self.resources.clear(&mut self.display);
+        self.display
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(color: Color) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::new(2.0, 2.0), Size::new(4.0, 4.0)),
+            GraphicsDisplayPaint::Fill(color.into()),
+            None,
+        );
+        builder.build()
+    }
+
+    fn record() -> DisplayList {
+        let mut recorder = DisplayListRecorder::new((8, 8));
+        let back = recorder
+            .push_command_group(&rectangle(Color::new(1.0, 0.0, 0.0, 1.0)), ZOrder(1), None, None)
+            .unwrap();
+        let front = recorder
+            .push_command_group(&rectangle(Color::new(0.0, 0.0, 1.0, 1.0)), ZOrder(2), None, None)
+            .unwrap();
+        recorder.set_command_group_transform(front, Transform::create_translation(1.0, 0.0));
+        recorder.present(None).unwrap();
+
+        // the front command group isn't maintained, so it's gone from the second frame.
+        recorder.maintain_command_group(back);
+        recorder.present(None).unwrap();
+        recorder.present(None).unwrap();
+
+        recorder.take_display_list()
+    }
+
+    fn round_trip(list: &DisplayList, format: DisplayListFormat) -> DisplayList {
+        let mut buffer = Vec::new();
+        list.write(&mut buffer, format).unwrap();
+        DisplayList::read(&buffer[..], format).unwrap()
+    }
+
+    #[test]
+    fn test_display_list_recorder() {
+        let list = record();
+        let sizes: Vec<_> = list.frames.iter().map(|frame| frame.command_groups.len()).collect();
+        assert_eq!(sizes, vec![2, 1, 0]);
+        assert_eq!(
+            list.frames[0].command_groups[1].transform,
+            Transform::create_translation(1.0, 0.0)
+        );
+
+        // replaying the first frame draws the same as drawing it directly.
+        let mut direct = software::SoftwareGraphicsDisplay::new((8, 8));
+        direct
+            .push_command_group(&rectangle(Color::new(1.0, 0.0, 0.0, 1.0)), ZOrder(1), None, None)
+            .unwrap();
+        let front = direct
+            .push_command_group(&rectangle(Color::new(0.0, 0.0, 1.0, 1.0)), ZOrder(2), None, None)
+            .unwrap();
+        direct.set_command_group_transform(front, Transform::create_translation(1.0, 0.0));
+        direct.present(None).unwrap();
+
+        let mut replayed = software::SoftwareGraphicsDisplay::new((8, 8));
+        let handles = list.frames[0].replay(&mut replayed, &mut ReplayedResources::new()).unwrap();
+        assert_eq!(handles.len(), 2);
+        replayed.present(None).unwrap();
+        assert_eq!(replayed.to_rgba8(), direct.to_rgba8());
+    }
+
+    #[test]
+    fn test_display_list_formats() {
+        let list = record();
+        let json = serde_json::to_string(&list).unwrap();
+        for &format in &[DisplayListFormat::Json, DisplayListFormat::Cbor] {
+            assert_eq!(serde_json::to_string(&round_trip(&list, format)).unwrap(), json);
+        }
+
+        assert!(matches!(
+            DisplayList::read(&b"{\"frames\": 1}"[..], DisplayListFormat::Json),
+            Err(ResourceError::InvalidData)
+        ));
+
+        let path =
+            std::env::temp_dir().join(format!("reclutch-display-list-{}.cbor", std::process::id()));
+        list.save(&path).unwrap();
+        let loaded = DisplayList::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+
+        assert_eq!(DisplayListFormat::from_path("frames.json"), Some(DisplayListFormat::Json));
+        assert!(matches!(list.save("frames.ron"), Err(ResourceError::Unsupported(_))));
+    }
+
+    // a writer which can be inspected while the recorder owns it.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl io::Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_replay_display() {
+        let list = record();
+        let mut replay = ReplayDisplay::new(RecordingDisplay::new((1, 1)));
+        let mut drawn = Vec::new();
+        replay.play(&list, |display, _| drawn.push(display.last_frame().len())).unwrap();
+        assert_eq!(replay.frames(), 3);
+        assert_eq!(replay.display().size(), (8, 8));
+        // the command groups of each frame replace those of the previous frame.
+        assert_eq!(drawn, vec![2, 1, 0]);
+
+        let mut direct = software::SoftwareGraphicsDisplay::new((8, 8));
+        direct
+            .push_command_group(&rectangle(Color::new(1.0, 0.0, 0.0, 1.0)), ZOrder(0), None, None)
+            .unwrap();
+        direct.present(None).unwrap();
+        let expected = direct.to_rgba8();
+
+        for &format in &[DisplayListFormat::Json, DisplayListFormat::Cbor] {
+            let buffer = SharedBuffer::default();
+            let mut recorder = DisplayListRecorder::new((8, 8));
+            recorder.stream_to(Some((Box::new(buffer.clone()), format)));
+            recorder
+                .push_command_group(
+                    &rectangle(Color::new(1.0, 0.0, 0.0, 1.0)),
+                    ZOrder(0),
+                    None,
+                    None,
+                )
+                .unwrap();
+            recorder.present(None).unwrap();
+            recorder.present(None).unwrap();
+            assert!(recorder.display_list().frames.is_empty());
+
+            let mut streamed = Vec::new();
+            let mut replay = ReplayDisplay::new(software::SoftwareGraphicsDisplay::new((8, 8)));
+            replay
+                .play_stream(&buffer.0.borrow()[..], format, |display, _| {
+                    streamed.push(display.to_rgba8())
+                })
+                .unwrap();
+            assert_eq!(streamed, vec![expected.clone(), expected.clone()]);
+            assert!(replay.into_inner().shutdown().command_groups.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_replayed_resources() {
+        let image = |color: [u8; 4]| {
+            ResourceDescriptor::Image(ImageData::Raw(
+                color.to_vec().into(),
+                RasterImageInfo { size: (1, 1), format: RasterImageFormat::Rgba8 },
+            ))
+        };
+        let draw_image = |resource| {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_image(None, Rect::new(Point::zero(), Size::new(8.0, 8.0)), resource, None);
+            builder.build()
+        };
+
+        let mut recorder = DisplayListRecorder::new((8, 8));
+        let red = recorder.new_resource(image([255, 0, 0, 255])).unwrap();
+        recorder.push_command_group(&draw_image(red), ZOrder(0), None, None).unwrap();
+        recorder.present(None).unwrap();
+        recorder.remove_resource(red);
+        // removing it again isn't recorded.
+        recorder.remove_resource(red);
+        let blue = recorder.new_resource(image([0, 0, 255, 255])).unwrap();
+        recorder.push_command_group(&draw_image(blue), ZOrder(0), None, None).unwrap();
+        recorder.present(None).unwrap();
+
+        let list = round_trip(&recorder.take_display_list(), DisplayListFormat::Cbor);
+        assert_eq!(list.frames[0].resources.len(), 1);
+        assert_eq!(list.frames[1].resources.len(), 2);
+
+        // a resource which already exists on the display takes the references the recorded resources were created under.
+        let mut display = software::SoftwareGraphicsDisplay::new((8, 8));
+        let existing = display.new_resource(image([0, 255, 0, 255])).unwrap();
+        assert_eq!(existing, red);
+
+        let mut replay = ReplayDisplay::new(display);
+        let mut colors = Vec::new();
+        replay.play(&list, |display, _| colors.push(display.pixel(4, 4).unwrap())).unwrap();
+        assert_eq!(colors, vec![Color::new(1.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0)]);
+        assert_ne!(replay.resources().get(blue), blue);
+
+        let report = replay.into_inner().shutdown();
+        assert_eq!(report.resources.len(), 1);
+        assert_eq!(report.resources[0].reference, existing);
+    }
+}
@@ -2,11 +2,22 @@
 
 use super::*;
 use {
+    super::backend::{EffectCache, EffectKey},
     crate::error,
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
     skia_safe as sk,
-    std::collections::{BTreeMap, HashMap},
+    std::{
+        borrow::Cow,
+        cell::RefCell,
+        collections::{BTreeMap, HashMap, HashSet},
+        rc::Rc,
+        time::{Duration, Instant, SystemTime},
+    },
 };
 
+/// How often the source files of resources are checked for changes (in debug builds).
+const RESOURCE_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
 /// Contains information about an existing OpenGL framebuffer.
 #[derive(Debug, Clone, Copy)]
 pub struct SkiaOpenGlFramebuffer {
@@ -22,29 +33,397 @@ pub struct SkiaOpenGlTexture {
     pub texture_id: u32,
 }
 
+/// A region of the surface in pixels, with the origin at the bottom-left corner.
+///
+/// This is the form expected by partial buffer swapping (e.g. `EGL_KHR_swap_buffers_with_damage`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SkiaSwapRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 enum SurfaceType {
     OpenGlFramebuffer(SkiaOpenGlFramebuffer),
     OpenGlTexture(SkiaOpenGlTexture),
+    Raster((i32, i32)),
 }
 
 enum Resource {
-    Image(sk::Image),
+    Image(sk::Image, metadata::ImageMetadata),
     Font(sk::Typeface),
 }
 
+#[derive(Default)]
+struct ResourceStore {
+    resources: HashMap<u64, Resource>,
+    next_resource_id: u64,
+    // only populated in debug builds.
+    backtraces: HashMap<u64, std::backtrace::Backtrace>,
+    // file-based resources and the modification time of the file when loaded; only populated in debug builds.
+    watched: HashMap<u64, (ResourceDescriptor, Option<SystemTime>)>,
+    last_watch: Option<Instant>,
+    updated: RcEventQueue<ResourceReference>,
+    // only present while an image budget is set.
+    cache: Option<ImageCache>,
+    // incremented whenever images are loaded again (as they're reloaded, or decoded again after being evicted), since their pixels
+    // may have changed; displays compare it against the last value they saw to drop what they cached from the old pixels.
+    generation: u64,
+}
+
+// images created while a budget is set, which are evicted (least recently drawn first) to keep their decoded size within the budget,
+// and decoded again from their descriptor when next drawn.
+struct ImageCache {
+    budget: usize,
+    // decoded size of each image in memory, least recently drawn first.
+    loaded: linked_hash_map::LinkedHashMap<u64, usize>,
+    size: usize,
+    descriptors: HashMap<u64, ResourceDescriptor>,
+    // the metadata is still reported while the image is evicted.
+    evicted: HashMap<u64, metadata::ImageMetadata>,
+}
+
+impl ImageCache {
+    fn new(budget: usize) -> Self {
+        ImageCache {
+            budget,
+            loaded: Default::default(),
+            size: 0,
+            descriptors: HashMap::new(),
+            evicted: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, id: u64, descriptor: ResourceDescriptor, image: &sk::Image) {
+        let size = image_size(image);
+        self.size += size;
+        self.loaded.insert(id, size);
+        self.descriptors.insert(id, descriptor);
+    }
+
+    fn remove(&mut self, id: u64) {
+        if let Some(size) = self.loaded.remove(&id) {
+            self.size -= size;
+        }
+        self.descriptors.remove(&id);
+        self.evicted.remove(&id);
+    }
+}
+
+// bytes taken by the decoded image.
+fn image_size(image: &sk::Image) -> usize {
+    image.width() as usize * image.height() as usize * 4
+}
+
+impl ResourceStore {
+    /// Re-uploads resources whose source file has changed, under the same ID.
+    fn reload_changed(&mut self) {
+        // nothing is watched in release builds.
+        if self.watched.is_empty() {
+            return;
+        }
+
+        if let Some(last) = self.last_watch {
+            if last.elapsed() < RESOURCE_WATCH_INTERVAL {
+                return;
+            }
+        }
+        self.last_watch = Some(Instant::now());
+
+        let resources = &mut self.resources;
+        let updated = &self.updated;
+        let cache = &mut self.cache;
+        let generation = &mut self.generation;
+        for (id, (descriptor, stamp)) in &mut self.watched {
+            let current = descriptor.source_file().and_then(file_modified);
+            if current == *stamp {
+                continue;
+            }
+            *stamp = current;
+
+            // evicted images are decoded from the file anew once drawn.
+            if cache.as_ref().is_some_and(|cache| cache.evicted.contains_key(id)) {
+                continue;
+            }
+
+            // the file may be mid-write; the old resource is kept until it loads.
+            if let Ok(resource) = load_resource(descriptor) {
+                resources.insert(*id, resource);
+                *generation += 1;
+                updated.emit_owned(match descriptor {
+                    ResourceDescriptor::Image(_) => ResourceReference::Image(*id),
+                    ResourceDescriptor::Font(_) => ResourceReference::Font(*id),
+                });
+            }
+        }
+    }
+}
+
+impl ResourceStore {
+    // decodes the evicted images among those about to be drawn and marks them as most recently drawn,
+    // then evicts the least recently drawn images until they fit the budget again.
+    fn prepare_images(&mut self, drawn: &HashSet<u64>) {
+        let cache = match self.cache {
+            Some(ref mut cache) => cache,
+            None => return,
+        };
+
+        for id in drawn {
+            if cache.loaded.get_refresh(id).is_some() {
+                continue;
+            }
+
+            // the image stays evicted (and fails to draw) if its data is no longer available.
+            if let Some(Ok(resource)) = cache.descriptors.get(id).map(load_resource) {
+                if let Resource::Image(ref image, ..) = resource {
+                    let size = image_size(image);
+                    cache.size += size;
+                    cache.loaded.insert(*id, size);
+                }
+                cache.evicted.remove(id);
+                self.resources.insert(*id, resource);
+                // the file of an evicted image isn't watched, so it may have changed since.
+                self.generation += 1;
+            }
+        }
+
+        let mut excess = cache.size.saturating_sub(cache.budget);
+        let evicted: Vec<_> = cache
+            .loaded
+            .iter()
+            .filter(|(id, _)| !drawn.contains(id))
+            .take_while(|(_, size)| {
+                let evict = excess > 0;
+                excess = excess.saturating_sub(**size);
+                evict
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in evicted {
+            if let Some(size) = cache.loaded.remove(&id) {
+                cache.size -= size;
+            }
+            if let Some(Resource::Image(_, metadata)) = self.resources.remove(&id) {
+                cache.evicted.insert(id, metadata);
+            }
+        }
+    }
+
+    // decodes all the evicted images again.
+    fn restore_evicted(&mut self) {
+        if let Some(cache) = self.cache.take() {
+            for id in cache.evicted.keys() {
+                if let Some(Ok(resource)) = cache.descriptors.get(id).map(load_resource) {
+                    self.resources.insert(*id, resource);
+                    self.generation += 1;
+                }
+            }
+        }
+    }
+}
+
+fn file_modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn load_resource(descriptor: &ResourceDescriptor) -> Result<Resource, error::ResourceError> {
+    // static bytes are borrowed rather than copied into Skia.
+    // mapped files are copied regardless, since Skia may keep (or upload) the data for longer than the resource,
+    // and the file may be edited while mapped (such as when it's hot-reloaded), which would fault within Skia.
+    let load_data = |data: &ResourceData| -> Result<sk::Data, error::ResourceError> {
+        Ok(match data {
+            // safety: the bytes are never freed.
+            ResourceData::Data(SharedData::Static(bytes)) => unsafe { sk::Data::new_bytes(bytes) },
+            _ => data.with_bytes(sk::Data::new_copy)?,
+        })
+    };
+
+    Ok(match descriptor {
+        ResourceDescriptor::Image(ImageData::Encoded(data)) => {
+            let encoded = load_data(data)?;
+            // read from the loaded bytes, such that files and streams aren't read twice.
+            let metadata = metadata::ImageMetadata::read(encoded.as_bytes());
+            let image =
+                sk::Image::from_encoded(encoded, None).ok_or(error::ResourceError::InvalidData)?;
+            let metadata = metadata.unwrap_or_else(|| {
+                metadata::ImageMetadata::new((image.width() as _, image.height() as _))
+            });
+            // Skia tags decoded images with their embedded profile; converting them once here spares converting them on every draw.
+            // A profile Skia can't convert from is drawn as is rather than failing the whole image.
+            let image =
+                if metadata.color_profile.as_ref().is_some_and(|profile| profile.is_embedded()) {
+                    image.new_color_space(display_color_space()).unwrap_or(image)
+                } else {
+                    image
+                };
+            let image = if metadata.orientation.is_identity() {
+                image
+            } else {
+                orient_image(&image, &metadata.orientation)?
+            };
+            Resource::Image(image, metadata)
+        }
+        ResourceDescriptor::Image(ImageData::Raw(data, info)) => {
+            let pixels = load_data(data)?;
+            let image = sk::Image::from_raster_data(
+                &sk::ImageInfo::new(
+                    sk::ISize::new(info.size.0 as _, info.size.1 as _),
+                    match info.format {
+                        RasterImageFormat::Rgba8 => sk::ColorType::RGBA8888,
+                        RasterImageFormat::Bgra8 => sk::ColorType::BGRA8888,
+                    },
+                    sk::AlphaType::Unpremul,
+                    None,
+                ),
+                pixels,
+                info.size.0 as usize * 4, // width * 4 bytes -> 4 x 8-bit components
+            )
+            .ok_or(error::ResourceError::InvalidData)?;
+            Resource::Image(image, metadata::ImageMetadata::new(info.size))
+        }
+        ResourceDescriptor::Font(data) => {
+            let data = load_data(data)?;
+            Resource::Font(
+                sk::Typeface::from_data(data, None).ok_or(error::ResourceError::InvalidData)?,
+            )
+        }
+    })
+}
+
+// the color space of every surface, which images are converted to when decoded.
+fn display_color_space() -> sk::ColorSpace {
+    sk::ColorSpace::new_srgb()
+}
+
+// redraws a decoded image upright, such that its orientation needn't be applied each time it's drawn.
+fn orient_image(
+    image: &sk::Image,
+    orientation: &ImageOrientation,
+) -> Result<sk::Image, error::ResourceError> {
+    let size = if orientation.rotation.is_sideways() {
+        Size::new(image.height() as _, image.width() as _)
+    } else {
+        Size::new(image.width() as _, image.height() as _)
+    };
+    let dst = Rect::new(Point::zero(), size);
+
+    let mut surface = sk::Surface::new_raster(
+        &sk::ImageInfo::new_n32_premul(
+            (size.width as i32, size.height as i32),
+            display_color_space(),
+        ),
+        None,
+        None,
+    )
+    .ok_or(error::ResourceError::InvalidData)?;
+    surface.canvas().concat(&convert_transform(&orientation.transform(dst)));
+    surface.canvas().draw_image_rect(
+        image.clone(),
+        None,
+        &convert_rect(&orientation.image_rect(dst)),
+        &sk::Paint::default(),
+    );
+    Ok(surface.image_snapshot())
+}
+
+// commands, bounds, protected, maintained, clip, layer and transform.
+type StoredCommandGroup =
+    (Vec<DisplayCommand>, Rect, bool, Option<bool>, Option<DisplayClip>, DisplayLayer, Transform);
+
+fn add_damage(damage: &mut Damage, cmd_group: &StoredCommandGroup) {
+    damage.add_command_group(&cmd_group.0, cmd_group.1, cmd_group.4.as_ref(), &cmd_group.6);
+}
+
+// damage since the last `take_damage` (for the application), and since the last present (for the cached backdrops).
+struct DamageTracker {
+    pending: Damage,
+    redraw: Damage,
+}
+
+impl DamageTracker {
+    fn add(&mut self, cmd_group: &StoredCommandGroup) {
+        add_damage(&mut self.pending, cmd_group);
+        add_damage(&mut self.redraw, cmd_group);
+    }
+
+    fn invalidate_all(&mut self) {
+        self.pending.invalidate_all();
+        self.redraw.invalidate_all();
+    }
+}
+
+// blurred backdrops, so that they're only blurred again when what's behind them was redrawn.
+struct BackdropCache {
+    // `None` if the blur couldn't be drawn.
+    effects: EffectCache<Option<sk::Image>>,
+    // reading back the surface to hash it would cost about as much as blurring it, so each blur is given a new content value instead;
+    // damaged entries are therefore always blurred again.
+    next_content: u64,
+}
+
+// Skia defers drawing until it's flushed, so the surface is flushed on both ends of a debug group to have it enclose the right draw calls.
+fn push_debug_group(surface: &mut sk::Surface, label: &str) {
+    surface.flush();
+    unsafe {
+        gl::PushDebugGroup(
+            gl::DEBUG_SOURCE_APPLICATION,
+            0,
+            label.len() as _,
+            label.as_ptr() as *const _,
+        );
+    }
+}
+
+fn pop_debug_group(surface: &mut sk::Surface) {
+    surface.flush();
+    unsafe {
+        gl::PopDebugGroup();
+    }
+}
+
 /// Converts [`DisplayCommand`](../enum.DisplayCommand.html) to immediate-mode Skia commands.
+///
+/// Multiple displays (e.g. one per window) can share a single GPU context and resource store
+/// through [`new_gl_framebuffer_shared`](struct.SkiaGraphicsDisplay.html#method.new_gl_framebuffer_shared) and
+/// [`new_gl_texture_shared`](struct.SkiaGraphicsDisplay.html#method.new_gl_texture_shared), so that fonts and images are only uploaded once.
+///
+/// With [debug markers](../trait.GraphicsDisplay.html#method.set_debug_markers) enabled, command groups and layers are drawn within
+/// OpenGL debug groups, such that they're named in frame captures of GPU debuggers (e.g. RenderDoc), once the application has loaded
+/// the OpenGL functions of the `gl` crate (through `gl::load_with`). Command groups are named by their
+/// [label](../trait.GraphicsDisplay.html#method.set_command_group_label) or otherwise by their handle.
 pub struct SkiaGraphicsDisplay {
     surface: sk::Surface,
     surface_type: SurfaceType,
-    context: sk::gpu::Context,
-    command_groups: BTreeMap<
-        ZOrder,
-        linked_hash_map::LinkedHashMap<u64, (Vec<DisplayCommand>, Rect, bool, Option<bool>)>,
-    >,
+    // `None` for raster surfaces.
+    context: Option<sk::gpu::Context>,
+    command_groups: BTreeMap<ZOrder, linked_hash_map::LinkedHashMap<u64, StoredCommandGroup>>,
     z_lookup: HashMap<CommandGroupHandle, ZOrder>,
     next_command_group_id: u64,
-    resources: HashMap<u64, Resource>,
-    next_resource_id: u64,
+    maintenance_count: u64,
+    resources: Rc<RefCell<ResourceStore>>,
+    present_region: Option<Rect>,
+    // (visible, opacity); layers which aren't present are visible and opaque.
+    layers: HashMap<DisplayLayer, (bool, f32)>,
+    // the capture and index of the next frame.
+    capture: Option<(FrameCapture, u64)>,
+    // reported by widgets while drawing.
+    errors: Vec<error::DisplayError>,
+    damage: DamageTracker,
+    backdrops: BackdropCache,
+    // supersampled command groups; the scale and the cached rendering (with the bounds it covers).
+    render_scales: HashMap<CommandGroupHandle, (f32, Option<(Rect, sk::Image)>)>,
+    // the generation of the resource store when the caches were last checked against it.
+    resource_generation: u64,
+    dithering: Dithering,
+    display_filters: Vec<DisplayFilter>,
+    scale_factor: f64,
+    labels: HashMap<CommandGroupHandle, String>,
+    // display-space rectangles which command groups are clipped to.
+    scissors: HashMap<CommandGroupHandle, Rect>,
+    // whether unlabeled command groups and layers are enclosed in debug groups too.
+    debug_markers: bool,
 }
 
 impl SkiaGraphicsDisplay {
@@ -53,16 +432,12 @@ impl SkiaGraphicsDisplay {
     /// This also assumes that the color format is RGBA with 8-bit components.
     pub fn new_gl_framebuffer(target: &SkiaOpenGlFramebuffer) -> Result<Self, error::SkiaError> {
         let (surface, context) = Self::new_gl_framebuffer_surface(target)?;
-        Ok(Self {
+        Ok(Self::from_parts(
             surface,
-            surface_type: SurfaceType::OpenGlFramebuffer(*target),
-            context,
-            command_groups: Default::default(),
-            z_lookup: HashMap::new(),
-            next_command_group_id: 0,
-            resources: HashMap::new(),
-            next_resource_id: 0,
-        })
+            SurfaceType::OpenGlFramebuffer(*target),
+            Some(context),
+            Default::default(),
+        ))
     }
 
     /// Creates a new [`SkiaGraphicsDisplay`](struct.SkiaGraphicsDisplay.html) with the Skia OpenGL backend, drawing into an existing texture.
@@ -70,26 +445,191 @@ impl SkiaGraphicsDisplay {
     /// This also assumes that the color format is RGBA with 8-bit components
     pub fn new_gl_texture(target: &SkiaOpenGlTexture) -> Result<Self, error::SkiaError> {
         let (surface, context) = Self::new_gl_texture_surface(target)?;
-        Ok(Self {
+        Ok(Self::from_parts(
             surface,
-            surface_type: SurfaceType::OpenGlTexture(*target),
+            SurfaceType::OpenGlTexture(*target),
+            Some(context),
+            Default::default(),
+        ))
+    }
+
+    /// Creates a new [`SkiaGraphicsDisplay`](struct.SkiaGraphicsDisplay.html) drawing into an existing framebuffer,
+    /// sharing the GPU context and resources of `share`.
+    ///
+    /// Resources created through either display are valid in both.
+    /// This assumes that the OpenGL context of `target` shares objects with the OpenGL context of `share`
+    /// (e.g. through `with_shared_lists` in glutin).
+    ///
+    /// # Context switching
+    /// The shared GPU context caches OpenGL state, so before using a display after another display sharing its GPU
+    /// context has been used, its OpenGL context must be made current and [`context_switched`](struct.SkiaGraphicsDisplay.html#method.context_switched)
+    /// must be called.
+    pub fn new_gl_framebuffer_shared(
+        target: &SkiaOpenGlFramebuffer,
+        share: &SkiaGraphicsDisplay,
+    ) -> Result<Self, error::SkiaError> {
+        let mut context = share.context.clone().ok_or(error::SkiaError::InvalidContext)?;
+        context.reset(None);
+        let surface = Self::new_gl_framebuffer_from_context(target, &mut context)?;
+        Ok(Self::from_parts(
+            surface,
+            SurfaceType::OpenGlFramebuffer(*target),
+            Some(context),
+            share.resources.clone(),
+        ))
+    }
+
+    /// Creates a new [`SkiaGraphicsDisplay`](struct.SkiaGraphicsDisplay.html) drawing into an existing texture,
+    /// sharing the GPU context and resources of `share`.
+    ///
+    /// See [`new_gl_framebuffer_shared`](struct.SkiaGraphicsDisplay.html#method.new_gl_framebuffer_shared) for the requirements regarding context switching.
+    pub fn new_gl_texture_shared(
+        target: &SkiaOpenGlTexture,
+        share: &SkiaGraphicsDisplay,
+    ) -> Result<Self, error::SkiaError> {
+        let mut context = share.context.clone().ok_or(error::SkiaError::InvalidContext)?;
+        context.reset(None);
+        let surface = Self::new_gl_texture_from_context(target, &mut context)?;
+        Ok(Self::from_parts(
+            surface,
+            SurfaceType::OpenGlTexture(*target),
+            Some(context),
+            share.resources.clone(),
+        ))
+    }
+
+    /// Creates a new [`SkiaGraphicsDisplay`](struct.SkiaGraphicsDisplay.html) with the Skia raster (CPU) backend, drawing into an off-screen buffer.
+    ///
+    /// This doesn't require an OpenGL context, which makes it suitable for rendering screenshots and tests.
+    /// See [`render_to_png`](fn.render_to_png.html).
+    pub fn new_raster(size: (u32, u32)) -> Result<Self, error::SkiaError> {
+        let size = (size.0 as i32, size.1 as i32);
+        Ok(Self::from_parts(
+            Self::new_raster_surface(size)?,
+            SurfaceType::Raster(size),
+            None,
+            Default::default(),
+        ))
+    }
+
+    /// Notifies the display that its OpenGL context has just been made current (again).
+    ///
+    /// This must be invoked when switching between displays which share a GPU context, since the GPU context
+    /// otherwise assumes that the OpenGL state it last saw is still bound.
+    pub fn context_switched(&mut self) {
+        if let Some(ref mut context) = self.context {
+            context.reset(None);
+        }
+    }
+
+    fn invalidate_render_cache(&mut self, handle: CommandGroupHandle) {
+        if let Some((_, cache)) = self.render_scales.get_mut(&handle) {
+            *cache = None;
+        }
+    }
+
+    fn damage_layer(&mut self, layer: DisplayLayer) {
+        for cmd_group in self.command_groups.values().flat_map(|z_list| z_list.values()) {
+            if cmd_group.5 == layer {
+                self.damage.add(cmd_group);
+            }
+        }
+    }
+
+    fn from_parts(
+        surface: sk::Surface,
+        surface_type: SurfaceType,
+        context: Option<sk::gpu::Context>,
+        resources: Rc<RefCell<ResourceStore>>,
+    ) -> Self {
+        let resource_generation = resources.borrow().generation;
+        SkiaGraphicsDisplay {
+            surface,
+            surface_type,
             context,
             command_groups: Default::default(),
             z_lookup: HashMap::new(),
             next_command_group_id: 0,
-            resources: HashMap::new(),
-            next_resource_id: 0,
-        })
+            maintenance_count: 0,
+            resources,
+            present_region: None,
+            layers: HashMap::new(),
+            capture: None,
+            errors: Vec::new(),
+            // the surface hasn't been drawn to yet.
+            damage: DamageTracker { pending: Damage::Full, redraw: Damage::Full },
+            backdrops: BackdropCache { effects: EffectCache::new(), next_content: 0 },
+            render_scales: HashMap::new(),
+            resource_generation,
+            dithering: Dithering::None,
+            display_filters: Vec::new(),
+            scale_factor: 1.0,
+            labels: HashMap::new(),
+            scissors: HashMap::new(),
+            debug_markers: false,
+        }
+    }
+
+    /// Returns a listener for resources which were reloaded because their source file changed.
+    ///
+    /// In debug builds, the source files of file-based resources are watched and re-uploaded (under the same reference) when changed.
+    /// Since the reference remains valid, this is only of interest to widgets which, for instance, lay out around the size of an image.
+    /// The resource store is shared, so resources are updated for all displays sharing it.
+    pub fn resource_updates(&self) -> RcEventListener<ResourceReference> {
+        self.resources.borrow().updated.listen()
+    }
+
+    /// Limits the memory taken by decoded images to `budget` bytes, or lifts the limit if `None`.
+    ///
+    /// Past the budget, the images which were drawn least recently are evicted from memory (their references stay valid),
+    /// and decoded again from their descriptor when next drawn, such that long-running applications cycling through
+    /// many images (e.g. an image viewer) don't exhaust GPU memory.
+    /// Only images created while a budget is set are evicted; their descriptors are kept (along with the encoded data) for as long as they exist.
+    /// The budget is shared by displays sharing resources.
+    pub fn set_image_budget(&mut self, budget: Option<usize>) {
+        let mut store = self.resources.borrow_mut();
+        match (budget, &mut store.cache) {
+            (Some(budget), Some(cache)) => cache.budget = budget,
+            (Some(budget), None) => store.cache = Some(ImageCache::new(budget)),
+            (None, _) => store.restore_evicted(),
+        }
+    }
+
+    /// Returns the bytes taken by the decoded images created while a [budget](struct.SkiaGraphicsDisplay.html#method.set_image_budget) is set.
+    pub fn image_cache_size(&self) -> usize {
+        self.resources.borrow().cache.as_ref().map_or(0, |cache| cache.size)
     }
 
     /// Returns the size of the underlying surface.
     pub fn size(&self) -> (i32, i32) {
         match self.surface_type {
             SurfaceType::OpenGlFramebuffer(SkiaOpenGlFramebuffer { size, .. })
-            | SurfaceType::OpenGlTexture(SkiaOpenGlTexture { size, .. }) => size,
+            | SurfaceType::OpenGlTexture(SkiaOpenGlTexture { size, .. })
+            | SurfaceType::Raster(size) => size,
         }
     }
 
+    /// Returns the region of the surface which was touched by the last call to [`present`](../trait.GraphicsDisplay.html#tymethod.present).
+    ///
+    /// If the last present was given a cull, then only the pixels within the cull were redrawn,
+    /// meaning that only this region needs to be swapped (where the windowing system supports
+    /// partial swapping, such as `swap_buffers_with_damage` in glutin).
+    /// Returns `None` if the entire surface was redrawn.
+    pub fn swap_region(&self) -> Option<SkiaSwapRegion> {
+        let size = self.size();
+        let region = self
+            .present_region?
+            .intersection(&Rect::new(Point::default(), Size::new(size.0 as _, size.1 as _)))
+            .unwrap_or_default();
+
+        Some(SkiaSwapRegion {
+            x: region.origin.x as _,
+            y: (size.1 as f32 - region.max_y()).max(0.0) as _,
+            width: region.size.width as _,
+            height: region.size.height as _,
+        })
+    }
+
     fn new_gl_framebuffer_surface(
         target: &SkiaOpenGlFramebuffer,
     ) -> Result<(sk::Surface, sk::gpu::Context), error::SkiaError> {
@@ -114,7 +654,7 @@ impl SkiaGraphicsDisplay {
             &info,
             sk::gpu::SurfaceOrigin::BottomLeft,
             sk::ColorType::RGBA8888,
-            sk::ColorSpace::new_srgb(),
+            display_color_space(),
             None,
         )
         .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("framebuffer")))?)
@@ -150,12 +690,123 @@ impl SkiaGraphicsDisplay {
             sk::gpu::SurfaceOrigin::BottomLeft,
             None,
             sk::ColorType::RGBA8888,
-            sk::ColorSpace::new_srgb(),
+            display_color_space(),
             None,
         )
         .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("texture")))?)
     }
 
+    fn new_raster_surface(size: (i32, i32)) -> Result<sk::Surface, error::SkiaError> {
+        sk::Surface::new_raster(
+            &sk::ImageInfo::new_n32_premul(size, display_color_space()),
+            None,
+            None,
+        )
+        .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("raster")))
+    }
+
+    // moves a command group to the front of another z-order, if it isn't already at that z-order.
+    fn move_to_z_order(&mut self, handle: CommandGroupHandle, z_order: ZOrder) {
+        let z = match self.z_lookup.get_mut(&handle) {
+            Some(z) if *z != z_order => std::mem::replace(z, z_order),
+            _ => return,
+        };
+        if let Some(cmd_group) =
+            self.command_groups.get_mut(&z).and_then(|z_list| z_list.remove(&handle.id()))
+        {
+            self.command_groups.entry(z_order).or_default().insert(handle.id(), cmd_group);
+        }
+    }
+
+    /// Encodes the current contents of the surface as a PNG image.
+    pub fn encode_png(&mut self) -> Option<Vec<u8>> {
+        self.surface.flush();
+        let data = self.surface.image_snapshot().encode_to_data(sk::EncodedImageFormat::PNG)?;
+        Some(data.as_bytes().to_vec())
+    }
+
+    // removes the command groups which weren't maintained since they were last aged, and ages the others.
+    fn expire_within(&mut self, cull: Option<Rect>) {
+        self.maintenance_count += 1;
+        let mut expired = Vec::new();
+        for z_list in self.command_groups.values_mut() {
+            for (&id, cmd_group) in z_list.iter_mut() {
+                match cmd_group.3 {
+                    Some(true) => cmd_group.3 = Some(false),
+                    Some(false) => {
+                        let bounds = match cmd_group.4 {
+                            Some(ref clip) => {
+                                cmd_group.1.intersection(&clip.bounds()).unwrap_or_default()
+                            }
+                            None => cmd_group.1,
+                        };
+                        let redrawn = cull.map_or(true, |cull| {
+                            cull.contains_rect(&cmd_group.6.transform_rect(&bounds))
+                        });
+                        expired.push((CommandGroupHandle(id), redrawn));
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        // the expired command groups are still on the surface, so they're damaged for the next present,
+        // unless they're within the cull and thereby about to be redrawn without them.
+        for (handle, redrawn) in expired {
+            let damage = self.damage.pending;
+            self.remove_command_group(handle);
+            if redrawn {
+                self.damage.pending = damage;
+            }
+        }
+    }
+
+    /// Mirrors the presented frame into the frame capture, if any.
+    fn capture_frame(&mut self) -> Result<(), error::DisplayError> {
+        let size = self.size();
+        let (capture, index) = match self.capture {
+            Some((ref mut capture, ref mut index)) => {
+                *index += 1;
+                (capture, *index - 1)
+            }
+            None => return Ok(()),
+        };
+
+        match capture {
+            FrameCapture::Sink(sink) => {
+                let info = sk::ImageInfo::new(
+                    size,
+                    sk::ColorType::RGBA8888,
+                    sk::AlphaType::Unpremul,
+                    None,
+                );
+                let mut pixels = vec![0; size.0 as usize * size.1 as usize * 4];
+
+                if !self.surface.read_pixels(&info, &mut pixels, size.0 as usize * 4, (0, 0)) {
+                    return Err(error::DisplayError::InternalError(
+                        "failed to read surface pixels".into(),
+                    ));
+                }
+
+                sink(CapturedFrame { index, size: (size.0 as _, size.1 as _), pixels });
+            }
+            FrameCapture::PngSequence(directory) => {
+                let png = self
+                    .surface
+                    .image_snapshot()
+                    .encode_to_data(sk::EncodedImageFormat::PNG)
+                    .ok_or_else(|| {
+                        error::DisplayError::InternalError("failed to encode PNG".into())
+                    })?;
+
+                std::fs::write(FrameCapture::png_path(directory, index), png.as_bytes())
+                    .map_err(error::ResourceError::from)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn new_gl_context() -> Result<sk::gpu::Context, error::SkiaError> {
         sk::gpu::Context::new_gl(sk::gpu::gl::Interface::new_native())
             .ok_or(error::SkiaError::InvalidContext)
@@ -163,17 +814,29 @@ impl SkiaGraphicsDisplay {
 }
 
 impl GraphicsDisplay for SkiaGraphicsDisplay {
-    fn resize(&mut self, size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
         self.surface = match self.surface_type {
             SurfaceType::OpenGlFramebuffer(ref mut target) => {
                 target.size = (size.0 as i32, size.1 as i32);
-                Self::new_gl_framebuffer_from_context(target, &mut self.context)
+                Self::new_gl_framebuffer_from_context(
+                    target,
+                    self.context.as_mut().ok_or(error::SkiaError::InvalidContext)?,
+                )
             }
             SurfaceType::OpenGlTexture(ref mut target) => {
                 target.size = (size.0 as i32, size.1 as i32);
-                Self::new_gl_texture_from_context(target, &mut self.context)
+                Self::new_gl_texture_from_context(
+                    target,
+                    self.context.as_mut().ok_or(error::SkiaError::InvalidContext)?,
+                )
+            }
+            SurfaceType::Raster(ref mut target) => {
+                *target = (size.0 as i32, size.1 as i32);
+                Self::new_raster_surface(*target)
             }
         }?;
+        self.damage.invalidate_all();
+        self.backdrops.effects.clear();
 
         Ok(())
     }
@@ -182,66 +845,52 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         &mut self,
         descriptor: ResourceDescriptor,
     ) -> Result<ResourceReference, error::ResourceError> {
-        let load_data = |data: ResourceData| -> Result<sk::Data, error::ResourceError> {
-            Ok(match data {
-                ResourceData::File(path) => {
-                    if !path.is_file() {
-                        return Err(error::ResourceError::InvalidPath(
-                            path.to_string_lossy().to_string(),
-                        ));
-                    }
+        let res = load_resource(&descriptor)?;
 
-                    sk::Data::new_copy(&std::fs::read(path)?)
-                }
-                ResourceData::Data(data) => sk::Data::new_copy(match data {
-                    SharedData::RefCount(ref data) => &(*data),
-                    SharedData::Static(data) => data,
-                }),
-            })
+        let mut store = self.resources.borrow_mut();
+        let id = store.next_resource_id;
+        let rid = match descriptor {
+            ResourceDescriptor::Image(_) => ResourceReference::Image(id),
+            ResourceDescriptor::Font(_) => ResourceReference::Font(id),
         };
 
-        let id = self.next_resource_id;
-        let (rid, res) = match &descriptor {
-            ResourceDescriptor::Image(data) => (
-                ResourceReference::Image(id),
-                Resource::Image(match data {
-                    ImageData::Encoded(data) => {
-                        sk::Image::from_encoded(load_data(data.clone())?, None)
-                            .ok_or(error::ResourceError::InvalidData)?
-                    }
-                    ImageData::Raw(data, info) => sk::Image::from_raster_data(
-                        &sk::ImageInfo::new(
-                            sk::ISize::new(info.size.0 as _, info.size.1 as _),
-                            match info.format {
-                                RasterImageFormat::Rgba8 => sk::ColorType::RGBA8888,
-                                RasterImageFormat::Bgra8 => sk::ColorType::BGRA8888,
-                            },
-                            sk::AlphaType::Unpremul,
-                            None,
-                        ),
-                        load_data(data.clone())?,
-                        info.size.0 as usize * 4, // width * 4 bytes -> 4 x 8-bit components
-                    )
-                    .ok_or(error::ResourceError::InvalidData)?,
-                }),
-            ),
-            ResourceDescriptor::Font(data) => (
-                ResourceReference::Font(id),
-                Resource::Font(
-                    sk::Typeface::from_data(load_data(data.clone())?, None)
-                        .ok_or(error::ResourceError::InvalidData)?,
-                ),
-            ),
-        };
+        if let (Some(cache), Resource::Image(ref image, ..)) = (&mut store.cache, &res) {
+            cache.insert(id, descriptor.clone(), image);
+        }
+
+        store.resources.insert(id, res);
+        store.next_resource_id += 1;
+
+        #[cfg(debug_assertions)]
+        {
+            store.backtraces.insert(id, std::backtrace::Backtrace::capture());
 
-        self.resources.insert(id, res);
-        self.next_resource_id += 1;
+            if let Some(path) = descriptor.source_file() {
+                let stamp = file_modified(path);
+                store.watched.insert(id, (descriptor, stamp));
+            }
+        }
 
         Ok(rid)
     }
 
     fn remove_resource(&mut self, reference: ResourceReference) {
-        self.resources.remove(&reference.id());
+        let mut store = self.resources.borrow_mut();
+        store.resources.remove(&reference.id());
+        store.backtraces.remove(&reference.id());
+        store.watched.remove(&reference.id());
+        if let Some(ref mut cache) = store.cache {
+            cache.remove(reference.id());
+        }
+    }
+
+    fn image_metadata(&self, reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        let store = self.resources.borrow();
+        match store.resources.get(&reference.id()) {
+            Some(Resource::Image(_, metadata)) => Some(metadata.clone()),
+            Some(Resource::Font(..)) => None,
+            None => store.cache.as_ref()?.evicted.get(&reference.id()).cloned(),
+        }
     }
 
     fn push_command_group(
@@ -253,16 +902,19 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
     ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
         let id = self.next_command_group_id;
 
-        self.command_groups.entry(z_order).or_default().insert(
-            id,
-            (
-                commands.to_owned(),
-                display_list_bounds(commands)?,
-                protected.unwrap_or(true),
-                if always_alive.unwrap_or(true) { Some(true) } else { None },
-            ),
+        let cmd_group = (
+            commands.to_owned(),
+            display_list_bounds(commands)?,
+            protected.unwrap_or(true),
+            if always_alive.unwrap_or(true) { Some(true) } else { None },
+            None,
+            DisplayLayer::default(),
+            Transform::identity(),
         );
+        self.damage.add(&cmd_group);
+        self.command_groups.entry(z_order).or_default().insert(id, cmd_group);
 
+        self.z_lookup.insert(CommandGroupHandle::new(id), z_order);
         self.next_command_group_id += 1;
 
         Ok(CommandGroupHandle::new(id))
@@ -284,18 +936,29 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         protected: Option<bool>,
         always_alive: Option<bool>,
     ) {
+        self.move_to_z_order(handle, z_order);
         if let Some(z_list) = self.command_groups.get_mut(&z_order) {
-            if z_list.contains_key(&handle.id()) {
+            if let Some((clip, layer, transform)) = z_list
+                .get(&handle.id())
+                .map(|cmd_group| (cmd_group.4.clone(), cmd_group.5, cmd_group.6))
+            {
                 if let Ok(bounds) = display_list_bounds(commands) {
-                    z_list.insert(
-                        handle.id(),
-                        (
-                            commands.to_owned(),
-                            bounds,
-                            protected.unwrap_or(true),
-                            if always_alive.unwrap_or(true) { Some(true) } else { None },
-                        ),
+                    if let Some(old) = z_list.get(&handle.id()) {
+                        self.damage.add(old);
+                    }
+
+                    let cmd_group = (
+                        commands.to_owned(),
+                        bounds,
+                        protected.unwrap_or(true),
+                        if always_alive.unwrap_or(true) { Some(true) } else { None },
+                        clip,
+                        layer,
+                        transform,
                     );
+                    self.damage.add(&cmd_group);
+                    z_list.insert(handle.id(), cmd_group);
+                    self.invalidate_render_cache(handle);
                 }
             }
         }
@@ -312,7 +975,208 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
     }
 
     fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
-        Some(self.command_groups.get_mut(self.z_lookup.get(&handle)?)?.remove(&handle.id())?.0)
+        let z = self.z_lookup.remove(&handle)?;
+        let cmd_group = self.command_groups.get_mut(&z)?.remove(&handle.id())?;
+        self.damage.add(&cmd_group);
+        self.render_scales.remove(&handle);
+        self.labels.remove(&handle);
+        self.scissors.remove(&handle);
+        Some(cmd_group.0)
+    }
+
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(cmd_group) =
+                self.command_groups.get_mut(z).and_then(|z_list| z_list.get_mut(&handle.id()))
+            {
+                self.damage.add(cmd_group);
+                cmd_group.4 = clip;
+                self.damage.add(cmd_group);
+                // only the clipped part of the command group is cached.
+                self.invalidate_render_cache(handle);
+            }
+        }
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(cmd_group) =
+                self.command_groups.get_mut(z).and_then(|z_list| z_list.get_mut(&handle.id()))
+            {
+                self.damage.add(cmd_group);
+                cmd_group.6 = transform;
+                self.damage.add(cmd_group);
+            }
+        }
+    }
+
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(cmd_group) =
+                self.command_groups.get_mut(z).and_then(|z_list| z_list.get_mut(&handle.id()))
+            {
+                self.damage.add(cmd_group);
+                cmd_group.5 = layer;
+                self.damage.add(cmd_group);
+            }
+        }
+    }
+
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        let state = self.layers.entry(layer).or_insert((true, 1.0));
+        if state.0 != visible {
+            state.0 = visible;
+            self.damage_layer(layer);
+        }
+    }
+
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        let opacity = opacity.max(0.0).min(1.0);
+        let state = self.layers.entry(layer).or_insert((true, 1.0));
+        if state.1 != opacity {
+            state.1 = opacity;
+            self.damage_layer(layer);
+        }
+    }
+
+    fn set_command_group_render_scale(&mut self, handle: CommandGroupHandle, scale: f32) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(cmd_group) =
+                self.command_groups.get(z).and_then(|z_list| z_list.get(&handle.id()))
+            {
+                let current = self.render_scales.get(&handle).map_or(1.0, |(scale, _)| *scale);
+                if current != scale {
+                    self.damage.add(cmd_group);
+                    if scale == 1.0 {
+                        self.render_scales.remove(&handle);
+                    } else {
+                        self.render_scales.insert(handle, (scale, None));
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(cmd_group) =
+                self.command_groups.get(z).and_then(|z_list| z_list.get(&handle.id()))
+            {
+                if self.scissors.get(&handle).copied() != scissor {
+                    // the damage of a command group doesn't account for its scissor, so this covers both.
+                    self.damage.add(cmd_group);
+                    match scissor {
+                        Some(scissor) => self.scissors.insert(handle, scissor),
+                        None => self.scissors.remove(&handle),
+                    };
+                }
+            }
+        }
+    }
+
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        match label {
+            Some(label) if self.z_lookup.contains_key(&handle) => {
+                self.labels.insert(handle, label.to_owned());
+            }
+            _ => {
+                self.labels.remove(&handle);
+            }
+        }
+    }
+
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.labels.get(&handle).map(String::as_str)
+    }
+
+    fn set_dithering(&mut self, dithering: Dithering) {
+        if dithering != self.dithering {
+            self.dithering = dithering;
+            for (_, cache) in self.render_scales.values_mut() {
+                *cache = None;
+            }
+            self.damage.invalidate_all();
+        }
+    }
+
+    fn set_debug_markers(&mut self, enabled: bool) -> bool {
+        self.debug_markers = enabled;
+        true
+    }
+
+    fn set_display_filters(&mut self, filters: &[DisplayFilter]) -> bool {
+        if filters != &self.display_filters[..] {
+            self.display_filters = filters.to_vec();
+            // the filters are applied as the scene is drawn, so everything has to be drawn again.
+            self.damage.invalidate_all();
+        }
+        true
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn set_scale_factor(&mut self, scale_factor: f64) {
+        if scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            // glyphs rasterized for the old scale factor are never drawn again, and neither are supersampled renderings.
+            sk::graphics::purge_font_cache();
+            for (_, cache) in self.render_scales.values_mut() {
+                *cache = None;
+            }
+            self.damage.invalidate_all();
+        }
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        // the command group can only keep its position if it stays at the same z-order.
+        self.move_to_z_order(handle, z_order);
+        // unlike `insert`, writing through `get_mut` doesn't refresh the entry's position.
+        if let Some(cmd_group) =
+            self.command_groups.get_mut(&z_order).and_then(|z_list| z_list.get_mut(&handle.id()))
+        {
+            if let Ok(bounds) = display_list_bounds(commands) {
+                self.damage.add(cmd_group);
+                *cmd_group = (
+                    commands.to_owned(),
+                    bounds,
+                    protected.unwrap_or(true),
+                    if always_alive.unwrap_or(true) { Some(true) } else { None },
+                    cmd_group.4.take(),
+                    cmd_group.5,
+                    cmd_group.6,
+                );
+                self.damage.add(cmd_group);
+                self.invalidate_render_cache(handle);
+            }
+        }
+    }
+
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        if let Some(z) = self.z_lookup.get(&handle) {
+            if let Some(z_list) = self.command_groups.get_mut(z) {
+                if let Some(cmd_group) = z_list.get_mut(&handle.id()) {
+                    cmd_group.3 = cmd_group.3.map(|_| true);
+                }
+            }
+        }
+    }
+
+    fn expire_command_groups(&mut self) {
+        self.expire_within(Some(Rect::zero()));
+    }
+
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        Some(self.maintenance_count)
     }
 
     #[inline]
@@ -320,8 +1184,79 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         self.surface.flush()
     }
 
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+
+        let command_groups = std::mem::take(&mut self.command_groups)
+            .into_iter()
+            .flat_map(|(_, z_list)| z_list.into_iter().map(|(id, _)| CommandGroupHandle::new(id)))
+            .collect();
+        self.z_lookup.clear();
+
+        // resources shared with another display are still legitimately in use.
+        let resources = if Rc::strong_count(&self.resources) == 1 {
+            let store = std::mem::take(&mut *self.resources.borrow_mut());
+            let mut backtraces = store.backtraces;
+            let evicted = store.cache.map(|cache| cache.evicted).unwrap_or_default();
+            store
+                .resources
+                .into_iter()
+                .map(|(id, res)| match res {
+                    Resource::Image(..) => ResourceReference::Image(id),
+                    Resource::Font(..) => ResourceReference::Font(id),
+                })
+                .chain(evicted.into_iter().map(|(id, _)| ResourceReference::Image(id)))
+                .map(|reference| LeakedResource {
+                    reference,
+                    backtrace: backtraces.remove(&reference.id()).and_then(|backtrace| {
+                        if backtrace.status() == std::backtrace::BacktraceStatus::Captured {
+                            Some(backtrace.to_string())
+                        } else {
+                            None
+                        }
+                    }),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        LeakReport { resources, command_groups }
+    }
+
+    fn set_frame_capture(&mut self, capture: Option<FrameCapture>) -> bool {
+        self.capture = capture.map(|capture| (capture, 0));
+        true
+    }
+
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn take_damage(&mut self) -> Damage {
+        let mut damage = std::mem::take(&mut self.damage.pending);
+        // command groups which weren't maintained are removed by the next present.
+        for cmd_group in self.command_groups.values().flat_map(|z_list| z_list.values()) {
+            if cmd_group.3 == Some(false) {
+                add_damage(&mut damage, cmd_group);
+            }
+        }
+        damage
+    }
+
     fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
-        let mut processed = Vec::new();
+        self.resources.borrow_mut().reload_changed();
+
+        // pixel-align the cull so that the scissor doesn't leave half-drawn edges.
+        let cull = cull.map(|cull| cull.round_out());
+        self.present_region = cull;
+        self.expire_within(cull);
+        // expired command groups are damaged by `expire_within`, so the damage is taken afterwards.
+        self.backdrops.effects.invalidate(std::mem::take(&mut self.damage.redraw));
 
         {
             let cmds = self
@@ -332,52 +1267,225 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
                     list
                 })
                 .into_iter()
-                .map(|(id, cmds)| (&cmds.0, &cmds.1, &cmds.2, &cmds.3, *id))
-                .filter_map(|(cmd_group, bounds, protected, maintained, id)| {
-                    if cull.map(|cull| cull.intersects(bounds)).unwrap_or(true) {
-                        if let Some(maintained) = *maintained {
-                            if maintained {
-                                processed.push((true, id));
-                            } else {
-                                processed.push((false, id));
-                                return None;
-                            }
-                        }
-
-                        Some((cmd_group, protected))
+                .map(|(id, cmds)| (&cmds.0, &cmds.1, &cmds.2, &cmds.4, cmds.5, cmds.6, *id))
+                .filter_map(|(cmd_group, bounds, protected, clip, layer, transform, id)| {
+                    if cull
+                        .map(|cull| {
+                            cull.intersects(&transform.transform_rect(bounds))
+                                && clip.as_ref().map_or(true, |clip| {
+                                    cull.intersects(&transform.transform_rect(&clip.bounds()))
+                                })
+                        })
+                        .unwrap_or(true)
+                    {
+                        Some((cmd_group, protected, clip, layer, transform, id))
                     } else {
                         None
                     }
                 });
-            let resources = &self.resources;
+            let layers = &self.layers;
+            let layer_state =
+                |layer: DisplayLayer| layers.get(&layer).copied().unwrap_or((true, 1.0));
+
+            // command groups are ordered by z-order; a stable sort keeps that order within each layer.
+            let mut cmds: Vec<_> = cmds.filter(|cmd_group| layer_state(cmd_group.3).0).collect();
+            cmds.sort_by_key(|cmd_group| cmd_group.3);
+
+            let drawn = cmds
+                .iter()
+                .flat_map(|cmd_group| cmd_group.0.iter())
+                .filter_map(|cmd| match cmd.resource() {
+                    Some(ResourceReference::Image(id)) => Some(id),
+                    _ => None,
+                })
+                .collect();
+            self.resources.borrow_mut().prepare_images(&drawn);
+
+            // the renderings of supersampled command groups and the blurred backdrops may contain the old pixels of images loaded again since.
+            let generation = self.resources.borrow().generation;
+            if generation != self.resource_generation {
+                self.resource_generation = generation;
+                for (_, cache) in self.render_scales.values_mut() {
+                    *cache = None;
+                }
+                self.backdrops.effects.clear();
+            }
+
+            let resources = self.resources.borrow();
             let size = self.size();
             let surface = &mut self.surface;
+            let render_scales = &mut self.render_scales;
+            let backdrops = &mut self.backdrops;
+            let dithering = self.dithering;
+            let labels = &self.labels;
+            let scissors = &self.scissors;
+            // debug groups are only visible to GPU debuggers, and only if the application loaded the OpenGL functions.
+            let debug_markers =
+                self.debug_markers && self.context.is_some() && gl::PushDebugGroup::is_loaded();
+
+            // anything outside the cull is left untouched, so that only the cull needs to be swapped.
+            let scissor_count = cull.map(|cull| {
+                let count = surface.canvas().save();
+                surface.canvas().clip_rect(convert_rect(&cull), None, false);
+                count
+            });
+
+            // the whole scene is drawn into a layer which is filtered as it's composited onto the surface.
+            let filter_count = if self.display_filters.is_empty() {
+                None
+            } else {
+                let mut paint = sk::Paint::default();
+                paint.set_color_filter(sk::ColorFilters::matrix_row_major(
+                    &DisplayFilter::combined_matrix(&self.display_filters),
+                ));
+                Some(surface.canvas().save_layer(&sk::SaveLayerRec::default().paint(&paint)))
+            };
+
+            let mut current_layer = None;
+            let mut layer_count = None;
+
             for cmd_group in cmds {
-                let count = if *cmd_group.1 { Some(surface.canvas().save()) } else { None };
+                if current_layer != Some(cmd_group.3) {
+                    if let Some(count) = layer_count.take() {
+                        surface.canvas().restore_to_count(count);
+                    }
 
-                draw_command_group(cmd_group.0, surface, resources, size)?;
+                    // the debug group of a layer encloses its compositing too.
+                    if debug_markers {
+                        if current_layer.is_some() {
+                            pop_debug_group(surface);
+                        }
+                        push_debug_group(surface, &format!("{:?} layer", cmd_group.3));
+                    }
 
-                if let Some(count) = count {
-                    surface.canvas().restore_to_count(count);
+                    current_layer = Some(cmd_group.3);
+
+                    let opacity = layer_state(cmd_group.3).1;
+                    if opacity < 1.0 {
+                        let mut paint = sk::Paint::default();
+                        paint.set_alpha_f(opacity);
+
+                        layer_count = Some(
+                            surface.canvas().save_layer(&sk::SaveLayerRec::default().paint(&paint)),
+                        );
+                    }
                 }
-            }
 
-            surface.flush();
-        }
+                let label: Option<Cow<'_, str>> = if debug_markers {
+                    Some(match labels.get(&CommandGroupHandle(cmd_group.5)) {
+                        Some(label) => label.as_str().into(),
+                        None => format!("command group {}", cmd_group.5).into(),
+                    })
+                } else {
+                    None
+                };
+                if let Some(ref label) = label {
+                    push_debug_group(surface, label);
+                }
+
+                let transformed = cmd_group.4 != Transform::identity();
+                let scissor = scissors.get(&CommandGroupHandle(cmd_group.5)).copied();
 
-        for (ok, id) in processed {
-            if let Some(z) = self.z_lookup.get(&CommandGroupHandle(id)) {
-                if let Some(z_list) = self.command_groups.get_mut(z) {
-                    if ok {
-                        z_list.get_mut(&id).unwrap().3 = Some(false);
+                // a group clip, scissor or transform must not leak, so it implies protection.
+                let count =
+                    if *cmd_group.1 || cmd_group.2.is_some() || scissor.is_some() || transformed {
+                        Some(surface.canvas().save())
                     } else {
-                        z_list.remove(&id);
+                        None
+                    };
+
+                // the scissor is in display coordinates, so it's applied before the transformation.
+                if let Some(scissor) = scissor {
+                    surface.canvas().clip_rect(convert_rect(&scissor), None, false);
+                }
+
+                if transformed {
+                    surface.canvas().concat(&convert_transform(&cmd_group.4));
+                }
+
+                if let Some(ref clip) = cmd_group.2 {
+                    apply_clip(surface.canvas(), clip);
+                }
+
+                // the cull, scissor and group clip, in the coordinate space of the display list.
+                let display_visible = match (cull, scissor) {
+                    (Some(cull), Some(scissor)) => {
+                        Some(cull.intersection(&scissor).unwrap_or_default())
                     }
+                    (cull, scissor) => cull.or(scissor),
+                };
+                let visible = display_visible
+                    .and_then(|visible| {
+                        cmd_group.4.inverse().map(|inverse| inverse.transform_rect(&visible))
+                    })
+                    .into_iter()
+                    .chain(cmd_group.2.as_ref().map(|clip| clip.bounds()))
+                    .fold(None, |visible: Option<Rect>, bounds| {
+                        Some(visible.map_or(bounds, |visible| {
+                            visible.intersection(&bounds).unwrap_or_default()
+                        }))
+                    });
+
+                let supersampled = match render_scales.get_mut(&CommandGroupHandle(cmd_group.5)) {
+                    Some((scale, cache)) => draw_supersampled(
+                        cmd_group.0,
+                        cmd_group.2.as_ref(),
+                        *scale,
+                        cache,
+                        surface,
+                        &resources.resources,
+                        dithering,
+                    )?,
+                    None => false,
+                };
+                if !supersampled {
+                    // backdrops within a layer are read from the layer, which can't be snapshotted.
+                    let backdrops = if filter_count.is_none() && layer_count.is_none() {
+                        Some(&mut *backdrops)
+                    } else {
+                        None
+                    };
+                    draw_command_group(
+                        cmd_group.0,
+                        visible,
+                        surface,
+                        &resources.resources,
+                        size,
+                        dithering,
+                        backdrops,
+                    )?;
                 }
+
+                if let Some(count) = count {
+                    surface.canvas().restore_to_count(count);
+                }
+
+                if label.is_some() {
+                    pop_debug_group(surface);
+                }
+            }
+
+            if let Some(count) = layer_count {
+                surface.canvas().restore_to_count(count);
+            }
+
+            if debug_markers && current_layer.is_some() {
+                pop_debug_group(surface);
             }
+
+            if let Some(count) = filter_count {
+                surface.canvas().restore_to_count(count);
+            }
+
+            if let Some(count) = scissor_count {
+                surface.canvas().restore_to_count(count);
+            }
+
+            surface.flush();
         }
 
-        Ok(())
+        self.backdrops.effects.trim();
+        self.capture_frame()
     }
 }
 
@@ -385,17 +1493,37 @@ fn convert_color(color: Color) -> sk::Color4f {
     sk::Color4f::new(color.red, color.green, color.blue, color.alpha)
 }
 
+fn convert_transform(transform: &Transform) -> sk::Matrix {
+    sk::Matrix::new_all(
+        transform.m11,
+        transform.m21,
+        transform.m31,
+        transform.m12,
+        transform.m22,
+        transform.m32,
+        0.0,
+        0.0,
+        1.0,
+    )
+}
+
 fn convert_point(point: Point) -> sk::Point {
     sk::Point::new(point.x, point.y)
 }
 
-fn apply_color(color: &StyleColor, paint: &mut sk::Paint) -> Result<(), error::SkiaError> {
+fn apply_color(
+    color: &StyleColor,
+    paint: &mut sk::Paint,
+    dithering: Dithering,
+) -> Result<(), error::SkiaError> {
     match color {
         StyleColor::Color(ref color) => {
             // we can afford to "make" the SRGB color space every time; it's actually a singleton in the C++ Skia code.
             paint.set_color4f(convert_color(*color), &sk::ColorSpace::new_srgb());
         }
         StyleColor::LinearGradient(ref gradient) => {
+            // Skia only has its own ordered dithering.
+            paint.set_dither(gradient.dithering.unwrap_or(dithering) != Dithering::None);
             let (colors, stops): (Vec<_>, Vec<_>) = gradient
                 .stops
                 .iter()
@@ -415,6 +1543,7 @@ fn apply_color(color: &StyleColor, paint: &mut sk::Paint) -> Result<(), error::S
             );
         }
         StyleColor::RadialGradient(ref gradient) => {
+            paint.set_dither(gradient.dithering.unwrap_or(dithering) != Dithering::None);
             let (colors, stops): (Vec<_>, Vec<_>) = gradient
                 .stops
                 .iter()
@@ -431,6 +1560,32 @@ fn apply_color(color: &StyleColor, paint: &mut sk::Paint) -> Result<(), error::S
                 None,
             ));
         }
+        StyleColor::Checkerboard(ref checkerboard) => {
+            // a 2x2 pixel tile, scaled up and repeated; the paint has no filter quality, so the squares stay sharp.
+            let (light, dark) = (
+                convert_color(checkerboard.light).to_color(),
+                convert_color(checkerboard.dark).to_color(),
+            );
+            let pixels: Vec<u8> = [light, dark, dark, light]
+                .iter()
+                .flat_map(|color| vec![color.r(), color.g(), color.b(), color.a()])
+                .collect();
+            let tile = sk::Image::from_raster_data(
+                &sk::ImageInfo::new(
+                    sk::ISize::new(2, 2),
+                    sk::ColorType::RGBA8888,
+                    sk::AlphaType::Unpremul,
+                    None,
+                ),
+                sk::Data::new_copy(&pixels),
+                2 * 4,
+            )
+            .ok_or(error::SkiaError::UnknownError)?;
+
+            let mut matrix = sk::Matrix::new_trans(convert_point(checkerboard.origin));
+            matrix.pre_scale((checkerboard.size, checkerboard.size), None);
+            paint.set_shader(tile.to_shader((sk::TileMode::Repeat, sk::TileMode::Repeat), &matrix));
+        }
     };
 
     Ok(())
@@ -479,6 +1634,7 @@ fn apply_filter_to_paint(paint: &mut sk::Paint, filter: Option<Filter>) {
 fn convert_paint(
     gdpaint: &GraphicsDisplayPaint,
     filter: Option<Filter>,
+    dithering: Dithering,
 ) -> Result<sk::Paint, error::SkiaError> {
     let mut paint = sk::Paint::default();
 
@@ -486,13 +1642,13 @@ fn convert_paint(
         GraphicsDisplayPaint::Fill(ref color) => {
             paint.set_anti_alias(true);
 
-            apply_color(color, &mut paint)?;
+            apply_color(color, &mut paint, dithering)?;
         }
         GraphicsDisplayPaint::Stroke(ref stroke) => {
             paint.set_anti_alias(stroke.antialias);
             paint.set_style(sk::PaintStyle::Stroke);
 
-            apply_color(&stroke.color, &mut paint)?;
+            apply_color(&stroke.color, &mut paint, dithering)?;
 
             paint.set_stroke_width(stroke.thickness);
             paint.set_stroke_cap(convert_line_cap(stroke.cap));
@@ -502,6 +1658,9 @@ fn convert_paint(
     }
 
     apply_filter_to_paint(&mut paint, filter);
+    if let Some(Filter::Blur(..)) = filter {
+        paint.set_dither(paint.is_dither() || dithering != Dithering::None);
+    }
 
     Ok(paint)
 }
@@ -608,33 +1767,239 @@ fn apply_clip(canvas: &mut sk::Canvas, clip: &DisplayClip) {
     };
 }
 
+/// Renders a widget (tree) off-screen at the given size and writes the result to a PNG file at `path`.
+///
+/// This is intended for generating screenshots, e.g. of a widget gallery for documentation.
+/// The widget is drawn once onto a transparent background; it's up to the widget to draw its children.
+pub fn render_to_png<W>(
+    widget: &mut W,
+    aux: &mut W::GraphicalAux,
+    size: (u32, u32),
+    path: impl AsRef<std::path::Path>,
+) -> Result<(), error::DisplayError>
+where
+    W: crate::widget::Widget<DisplayObject = DisplayCommand> + ?Sized,
+{
+    let mut display = SkiaGraphicsDisplay::new_raster(size)
+        .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+
+    widget.draw(&mut display, aux);
+    display.present(None)?;
+
+    let png = display
+        .encode_png()
+        .ok_or_else(|| error::DisplayError::InternalError("failed to encode PNG".into()))?;
+    std::fs::write(path, png).map_err(error::ResourceError::from)?;
+
+    display.shutdown();
+
+    Ok(())
+}
+
+// Draws a command group into an offscreen surface at `scale` times its resolution (in the coordinate space of the commands),
+// which is then downsampled onto `surface`. The rendering is kept in `cache` until the command group changes,
+// so zooming in through the group transform doesn't render it again.
+// Returns false if the offscreen surface couldn't be created (e.g. it's too large), in which case nothing is drawn,
+// and the caller draws the command group directly instead.
+fn draw_supersampled(
+    cmds: &[DisplayCommand],
+    clip: Option<&DisplayClip>,
+    scale: f32,
+    cache: &mut Option<(Rect, sk::Image)>,
+    surface: &mut sk::Surface,
+    resources: &HashMap<u64, Resource>,
+    dithering: Dithering,
+) -> Result<bool, error::DisplayError> {
+    if cache.is_none() {
+        let bounds =
+            display_list_bounds(cmds).map_err(|e| error::DisplayError::InternalError(e.into()))?;
+        let bounds = match clip {
+            Some(clip) => bounds.intersection(&clip.bounds()).unwrap_or_default(),
+            None => bounds,
+        }
+        .round_out();
+        if bounds.is_empty_or_negative() {
+            return Ok(true);
+        }
+
+        let pixels =
+            ((bounds.size.width * scale).ceil() as i32, (bounds.size.height * scale).ceil() as i32);
+        let mut offscreen = match surface.new_surface(&sk::ImageInfo::new_n32_premul(pixels, None))
+        {
+            Some(offscreen) => offscreen,
+            None => return Ok(false),
+        };
+        offscreen.canvas().clear(sk::Color::TRANSPARENT);
+        offscreen.canvas().scale((scale, scale));
+        offscreen.canvas().translate(sk::Vector::new(-bounds.origin.x, -bounds.origin.y));
+        // the backdrops of the offscreen surface are only the command group itself, so they aren't cached.
+        draw_command_group(cmds, Some(bounds), &mut offscreen, resources, pixels, dithering, None)?;
+
+        *cache = Some((bounds, offscreen.image_snapshot()));
+    }
+
+    if let Some((bounds, ref image)) = *cache {
+        let mut paint = sk::Paint::default();
+        paint.set_filter_quality(sk::FilterQuality::High);
+        surface.canvas().draw_image_rect(image, None, convert_rect(&bounds), &paint);
+    }
+
+    Ok(true)
+}
+
+// Draws the backdrop within the current clip blurred, from the cache unless what's behind it was redrawn since it was cached.
+fn draw_cached_blur(
+    surface: &mut sk::Surface,
+    filter: Filter,
+    cache: &mut BackdropCache,
+) -> Result<(), error::DisplayError> {
+    let clip = surface.canvas().device_clip_bounds();
+    // the blur reads beyond the clip.
+    let extent = filter.extent();
+    let source = match Rect::new(
+        Point::new(clip.left as _, clip.top as _),
+        Size::new(clip.width() as _, clip.height() as _),
+    )
+    .inflate(extent.x, extent.y)
+    .round_out()
+    .intersection(&Rect::new(Point::zero(), Size::new(surface.width() as _, surface.height() as _)))
+    {
+        Some(source) if !clip.is_empty() && !source.is_empty() => source,
+        _ => return Ok(()),
+    };
+
+    let next_content = &mut cache.next_content;
+    let blurred = cache.effects.get_or_insert_with(
+        EffectKey::new(filter, source),
+        || {
+            *next_content += 1;
+            *next_content
+        },
+        || blur_region(surface, source, filter),
+    );
+    let blurred = blurred.as_ref().ok_or_else(|| {
+        error::DisplayError::InternalError(Box::new(error::SkiaError::UnknownError))
+    })?;
+
+    // the blur is in display space, whereas the canvas may be translated.
+    let count = surface.canvas().save();
+    surface.canvas().reset_matrix();
+    surface.canvas().draw_image(blurred, convert_point(source.origin), None);
+    surface.canvas().restore_to_count(count);
+
+    Ok(())
+}
+
+// blurs a region of the surface (in display space) into a new image, or returns `None` if the offscreen surface couldn't be created.
+fn blur_region(surface: &mut sk::Surface, source: Rect, filter: Filter) -> Option<sk::Image> {
+    let sigma = match filter {
+        Filter::Blur(sigma_x, sigma_y) => (sigma_x, sigma_y),
+        Filter::Invert => return None,
+    };
+    let region = sk::IRect::new(
+        source.min_x() as _,
+        source.min_y() as _,
+        source.max_x() as _,
+        source.max_y() as _,
+    );
+    let snapshot = surface.image_snapshot_with_bounds(region)?;
+    let mut offscreen = surface
+        .new_surface(&sk::ImageInfo::new_n32_premul((region.width(), region.height()), None))?;
+
+    let mut paint = sk::Paint::default();
+    paint.set_image_filter(sk::image_filters::blur(
+        sigma,
+        sk::TileMode::Clamp,
+        None,
+        &sk::IRect::new(0, 0, region.width(), region.height()),
+    )?);
+    offscreen.canvas().draw_image(&snapshot, (0, 0), Some(&paint));
+
+    Some(offscreen.image_snapshot())
+}
+
 // The meat of this module.
 // If there are any drawing bugs, they probably happen here.
 fn draw_command_group(
     cmds: &[DisplayCommand],
+    visible: Option<Rect>,
     surface: &mut sk::Surface,
     resources: &HashMap<u64, Resource>,
     size: (i32, i32),
+    dithering: Dithering,
+    mut backdrops: Option<&mut BackdropCache>,
 ) -> Result<(), error::DisplayError> {
-    for cmd in cmds {
+    // whether each save within the command group is a layer, since backdrops within layers aren't cached.
+    let mut saves = Vec::new();
+
+    // items which are entirely clipped out aren't worth the draw call,
+    // and runs of tiny rectangles/lines are drawn in one go.
+    for batch in batch_display_list(cull_display_list(cmds, visible)) {
+        let cmd = match batch {
+            DisplayBatch::Single(cmd) => cmd,
+            DisplayBatch::Rectangles(color, rects) => {
+                let paint = convert_paint(
+                    &GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+                    None,
+                    dithering,
+                )
+                .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+                let mut path = sk::Path::new();
+                for rect in &rects {
+                    path.add_rect(convert_rect(rect), None);
+                }
+                surface.canvas().draw_path(&path, &paint);
+                continue;
+            }
+            DisplayBatch::Lines(stroke, lines) => {
+                let paint =
+                    convert_paint(&GraphicsDisplayPaint::Stroke(stroke.clone()), None, dithering)
+                        .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+                let mut points = Vec::with_capacity(lines.len() * 2);
+                for (a, b) in &lines {
+                    points.push(convert_point(*a));
+                    points.push(convert_point(*b));
+                }
+                surface.canvas().draw_points(sk::canvas::PointMode::Lines, &points, &paint);
+                continue;
+            }
+        };
+
         match cmd {
             DisplayCommand::Item(item, filter) => match item {
-                DisplayItem::Graphics(ref item) => match item {
+                DisplayItem::Graphics(ref graphics) if lower::has_dashed_stroke(graphics) => {
+                    // dashes and trims are measured along the same paths as in the software display, rather than through a path effect,
+                    // so that dashes line up across backends (and stay in place as the path is trimmed).
+                    let mut lowered = Vec::new();
+                    lower::lower_command(&DisplayCommand::Item(item.clone(), None), &mut lowered)
+                        .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+                    for command in lowered {
+                        if let lower::LoweredCommand::Path { path, is_closed, paint } = command {
+                            surface.canvas().draw_path(
+                                &convert_path(&path, is_closed),
+                                &convert_paint(&paint, *filter, dithering)
+                                    .map_err(|e| error::DisplayError::InternalError(e.into()))?,
+                            );
+                        }
+                    }
+                }
+                DisplayItem::Graphics(ref item) => match &*item.center_aligned() {
                     GraphicsDisplayItem::Line { a, b, stroke } => {
                         let paint = convert_paint(
                             &GraphicsDisplayPaint::Stroke((*stroke).clone()),
                             *filter,
+                            dithering,
                         )
                         .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                         surface.canvas().draw_line(convert_point(*a), convert_point(*b), &paint);
                     }
                     GraphicsDisplayItem::Rectangle { rect, paint } => {
-                        let paint = convert_paint(paint, *filter)
+                        let paint = convert_paint(paint, *filter, dithering)
                             .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                         surface.canvas().draw_rect(&convert_rect(rect), &paint);
                     }
                     GraphicsDisplayItem::RoundRectangle { rect, radii, paint } => {
-                        let paint = convert_paint(paint, *filter)
+                        let paint = convert_paint(paint, *filter, dithering)
                             .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                         surface.canvas().draw_rrect(
                             sk::RRect::new_rect_radii(
@@ -649,16 +2014,34 @@ fn draw_command_group(
                             &paint,
                         );
                     }
-                    GraphicsDisplayItem::Ellipse { paint, .. } => {
+                    GraphicsDisplayItem::Ellipse { center, radii, paint } => {
+                        // not the bounds, which include the stroke.
                         surface.canvas().draw_oval(
-                            convert_rect(&item.bounds()),
-                            &convert_paint(paint, *filter)
+                            convert_rect(&Rect::new(*center - *radii, (*radii * 2.0).to_size())),
+                            &convert_paint(paint, *filter, dithering)
+                                .map_err(|e| error::DisplayError::InternalError(e.into()))?,
+                        );
+                    }
+                    GraphicsDisplayItem::Arc {
+                        center,
+                        radii,
+                        start_angle,
+                        sweep_angle,
+                        use_center,
+                        paint,
+                    } => {
+                        surface.canvas().draw_arc(
+                            convert_rect(&Rect::new(*center - *radii, (*radii * 2.0).to_size())),
+                            start_angle.to_degrees(),
+                            sweep_angle.to_degrees().clamp(-360.0, 360.0),
+                            *use_center,
+                            &convert_paint(paint, *filter, dithering)
                                 .map_err(|e| error::DisplayError::InternalError(e.into()))?,
                         );
                     }
-                    GraphicsDisplayItem::Image { src, dst, resource } => {
+                    GraphicsDisplayItem::Image { src, dst, resource, orientation, adjustments } => {
                         if let ResourceReference::Image(ref id) = resource {
-                            if let Resource::Image(ref img) = resources
+                            if let Resource::Image(ref img, ..) = resources
                                 .get(id)
                                 .ok_or(error::DisplayError::InvalidResource(*id))?
                             {
@@ -669,9 +2052,37 @@ fn draw_command_group(
 
                                 apply_filter_to_paint(&mut paint, *filter);
 
+                                if !adjustments.is_identity() {
+                                    let adjust = sk::ColorFilters::matrix_row_major(
+                                        &adjustments.color_matrix(),
+                                    );
+                                    paint.set_color_filter(match paint.color_filter() {
+                                        Some(outer) => {
+                                            sk::ColorFilters::compose(outer, adjust.clone())
+                                                .unwrap_or(adjust)
+                                        }
+                                        None => adjust,
+                                    });
+                                }
+
+                                if !orientation.is_identity() {
+                                    let center = dst.center();
+                                    surface
+                                        .canvas()
+                                        .translate(sk::Vector::new(center.x, center.y))
+                                        .rotate(orientation.rotation.angle().to_degrees(), None)
+                                        .scale((
+                                            if orientation.flip_horizontal { -1.0 } else { 1.0 },
+                                            if orientation.flip_vertical { -1.0 } else { 1.0 },
+                                        ))
+                                        .translate(sk::Vector::new(-center.x, -center.y));
+                                }
+
+                                let dst = orientation.image_rect(*dst);
+
                                 apply_clip(
                                     surface.canvas(),
-                                    &DisplayClip::Rectangle { rect: *dst, antialias: true },
+                                    &DisplayClip::Rectangle { rect: dst, antialias: true },
                                 );
 
                                 let o_src = src.map(|src_rect| convert_rect(&src_rect));
@@ -680,7 +2091,7 @@ fn draw_command_group(
                                     o_src
                                         .as_ref()
                                         .map(|src_rect| (src_rect, sk::SrcRectConstraint::Fast)),
-                                    &convert_rect(dst),
+                                    &convert_rect(&dst),
                                     &paint,
                                 );
 
@@ -693,7 +2104,7 @@ fn draw_command_group(
                     GraphicsDisplayItem::Path { path, is_closed, paint } => {
                         surface.canvas().draw_path(
                             &convert_path(path, *is_closed),
-                            &convert_paint(paint, *filter)
+                            &convert_paint(paint, *filter, dithering)
                                 .map_err(|e| error::DisplayError::InternalError(e.into()))?,
                         );
                     }
@@ -711,6 +2122,7 @@ fn draw_command_group(
                             let paint = convert_paint(
                                 &GraphicsDisplayPaint::Fill(item.color.clone()),
                                 *filter,
+                                dithering,
                             )
                             .map_err(|e| error::DisplayError::InternalError(e.into()))?;
 
@@ -736,9 +2148,16 @@ fn draw_command_group(
 
                 let bounds = clip.bounds();
 
-                match filter {
-                    Filter::Blur(sigma_x, sigma_y) => {
-                        // TODO(jazzfool): cache blur filter (figure out a way to cache by floats)
+                // the cached blurs are taken in display space, so they can't be scaled or rotated.
+                let cache = backdrops.as_deref_mut().filter(|_| {
+                    !saves.contains(&true) && surface.canvas().total_matrix().is_translate()
+                });
+
+                match (filter, cache) {
+                    (Filter::Blur(..), Some(cache)) => {
+                        draw_cached_blur(surface, *filter, cache)?;
+                    }
+                    (Filter::Blur(sigma_x, sigma_y), None) => {
                         if let Some(ref _snapshot_rect) = bounds.round_out().intersection(
                             &Rect::new(Point::default(), Size::new(size.0 as _, size.1 as _)),
                         ) {
@@ -759,7 +2178,7 @@ fn draw_command_group(
                                 .save_layer(&sk::SaveLayerRec::default().backdrop(&blur));
                         }
                     }
-                    Filter::Invert => {
+                    (Filter::Invert, _) => {
                         let mut paint = sk::Paint::default();
 
                         let mut color_matrix = sk::ColorMatrix::default();
@@ -780,15 +2199,18 @@ fn draw_command_group(
                 apply_clip(surface.canvas(), clip);
             }
             DisplayCommand::Save => {
+                saves.push(false);
                 surface.canvas().save();
             }
             DisplayCommand::SaveLayer(opacity) => {
+                saves.push(true);
                 let mut paint = sk::Paint::default();
                 paint.set_alpha_f(*opacity);
 
                 surface.canvas().save_layer(&sk::SaveLayerRec::default().paint(&paint));
             }
             DisplayCommand::Restore => {
+                saves.pop();
                 surface.canvas().restore();
             }
             DisplayCommand::Translate(ref offset) => {
@@ -800,6 +2222,9 @@ fn draw_command_group(
             DisplayCommand::Rotate(ref angle) => {
                 surface.canvas().rotate(angle.to_degrees(), None);
             }
+            DisplayCommand::Transform(ref transform) => {
+                surface.canvas().concat(&convert_transform(transform));
+            }
             DisplayCommand::Clear(ref color) => {
                 surface.canvas().clear(convert_color(*color).to_color());
             }
@@ -808,3 +2233,44 @@ fn draw_command_group(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "image-codecs")]
+    fn test_dashes_match_software() {
+        let stroke = GraphicsDisplayStroke {
+            thickness: 2.0,
+            antialias: false,
+            dash: Some(StrokeDash { intervals: vec![6.0, 4.0], phase: 0.0 }),
+            trim: Some((0.25, 1.0)),
+            ..Default::default()
+        };
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(1.0, 1.0, 1.0, 1.0));
+        builder.push_line(Point::new(0.0, 5.0), Point::new(64.0, 5.0), stroke, None);
+        let commands = builder.build();
+
+        let mut skia = SkiaGraphicsDisplay::new_raster((64, 10)).unwrap();
+        skia.push_command_group(&commands, ZOrder::default(), None, Some(false)).unwrap();
+        skia.present(None).unwrap();
+        let skia_pixels =
+            image::load_from_memory(&skia.encode_png().unwrap()).unwrap().into_rgba8();
+
+        let mut software = software::SoftwareGraphicsDisplay::new((64, 10));
+        software.push_command_group(&commands, ZOrder::default(), None, Some(false)).unwrap();
+        software.present(None).unwrap();
+
+        // the trim starts at 16, so the dashes at 0 and 10 are trimmed away and the dashes from 20 onwards stay in place.
+        for (x, dashed) in
+            [(3, false), (13, false), (18, false), (23, true), (28, false), (33, true), (63, true)]
+        {
+            let skia_covered = skia_pixels.get_pixel(x, 5).0[0] < 128;
+            let software_covered = software.pixel(x, 5).unwrap().red < 0.5;
+            assert_eq!(skia_covered, dashed, "skia at {}", x);
+            assert_eq!(software_covered, dashed, "software at {}", x);
+        }
+    }
+}
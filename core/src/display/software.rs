@@ -0,0 +1,1710 @@
+//! A display which renders entirely on the CPU, for running headless or on systems without OpenGL.
+//!
+//! [`SoftwareGraphicsDisplay`](struct.SoftwareGraphicsDisplay.html) rasterizes [lowered](../lower/index.html) display commands
+//! into an in-memory buffer, which can be copied into a window (e.g. through softbuffer), read back in tests or saved as a PNG:
+//!
+//! ```ignore
+//! let mut display = SoftwareGraphicsDisplay::new((640, 480));
+//! widget.draw(&mut display, &mut aux);
+//! display.present(None)?;
+//! window_buffer.copy_from_slice(&display.to_xrgb8888());
+//! ```
+//!
+//! With the `image-codecs` feature, PNG and JPEG images can be decoded, the pixels can be encoded as a PNG (and frames
+//! [captured](../enum.FrameCapture.html#variant.PngSequence) as PNGs), and they can be taken as an
+//! [`image::RgbaImage`](https://docs.rs/image/0.24/image/type.RgbaImage.html) (through [`to_image_buffer`](struct.SoftwareGraphicsDisplay.html#method.to_image_buffer)).
+//! Without it, images can only be created from raw pixels, and [`to_rgba8`](struct.SoftwareGraphicsDisplay.html#method.to_rgba8) returns the same pixels as bytes.
+//!
+//! Every [`DisplayCommand`](../enum.DisplayCommand.html) is supported, although text is drawn from unhinted outlines.
+
+use {
+    super::{backend::*, lower::*, tessellation::*, *},
+    std::rc::Rc,
+};
+
+/// Maximum distance (in pixels) between curves and the line segments they're drawn as.
+const TOLERANCE: f32 = 0.2;
+
+// premultiplied RGBA, within 0 and 1.
+type Pixel = [f32; 4];
+
+const TRANSPARENT: Pixel = [0.0; 4];
+
+/// A display which draws into a buffer in memory, without a GPU.
+///
+/// The buffer persists between presents (like a window surface), so a present with a cull only changes the pixels within it.
+pub struct SoftwareGraphicsDisplay {
+    size: (u32, u32),
+    pixels: Vec<Pixel>,
+    resources: ResourceRegistry<SoftwareResource>,
+    groups: CommandGroupStore,
+    // blurred backdrops, so that they're only blurred again when what's behind them changes.
+    effects: EffectCache<Vec<Pixel>>,
+    dithering: Dithering,
+    // the combined color matrix of the display filters, applied as pixels are read (so that `pixels` stays unfiltered for backdrops).
+    filter: Option<[f32; 20]>,
+    scale_factor: f64,
+    // the capture and index of the next frame.
+    capture: Option<(FrameCapture, u64)>,
+    errors: Vec<error::DisplayError>,
+}
+
+impl SoftwareGraphicsDisplay {
+    /// Creates a display with a transparent buffer of `size`.
+    pub fn new(size: (u32, u32)) -> Self {
+        SoftwareGraphicsDisplay {
+            size,
+            pixels: vec![TRANSPARENT; size.0 as usize * size.1 as usize],
+            resources: ResourceRegistry::new(),
+            groups: CommandGroupStore::default(),
+            effects: EffectCache::new(),
+            dithering: Dithering::None,
+            filter: None,
+            scale_factor: 1.0,
+            capture: None,
+            errors: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the (unpremultiplied) color of a pixel, or `None` if it's outside the display.
+    ///
+    /// This, and the other methods which read pixels, include the [display filters](../trait.GraphicsDisplay.html#method.set_display_filters).
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+        if x >= self.size.0 || y >= self.size.1 {
+            return None;
+        }
+        Some(self.filtered(self.pixels[y as usize * self.size.0 as usize + x as usize]))
+    }
+
+    fn filtered(&self, pixel: Pixel) -> Color {
+        let color = unpremultiply(pixel);
+        match self.filter {
+            // premultiplying clamps the filtered color.
+            Some(ref matrix) => unpremultiply(premultiply(apply_color_matrix(matrix, color))),
+            None => color,
+        }
+    }
+
+    /// Returns the pixels row by row from the top, as unpremultiplied RGBA (see [`RasterImageFormat::Rgba8`](../enum.RasterImageFormat.html#variant.Rgba8)).
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut rgba = Vec::with_capacity(self.pixels.len() * 4);
+        for &pixel in &self.pixels {
+            let color = self.filtered(pixel);
+            rgba.extend_from_slice(&[
+                to_u8(color.red),
+                to_u8(color.green),
+                to_u8(color.blue),
+                to_u8(color.alpha),
+            ]);
+        }
+        rgba
+    }
+
+    /// Returns the pixels as an image buffer, which can be saved in any format supported by the `image` crate.
+    #[cfg(feature = "image-codecs")]
+    pub fn to_image_buffer(&self) -> image::RgbaImage {
+        image::RgbaImage::from_raw(self.size.0, self.size.1, self.to_rgba8())
+            .expect("buffer matches the size of the display")
+    }
+
+    /// Returns the pixels row by row from the top, as `0RGB` words (the format of most window system buffers, such as softbuffer).
+    ///
+    /// Since there's no alpha channel, the pixels are composited over black.
+    pub fn to_xrgb8888(&self) -> Vec<u32> {
+        self.pixels
+            .iter()
+            .map(|&pixel| match self.filter {
+                Some(_) => premultiply(self.filtered(pixel)),
+                None => pixel,
+            })
+            .map(|pixel| {
+                (to_u8(pixel[0]) as u32) << 16
+                    | (to_u8(pixel[1]) as u32) << 8
+                    | to_u8(pixel[2]) as u32
+            })
+            .collect()
+    }
+
+    /// Encodes the pixels as a PNG.
+    #[cfg(feature = "image-codecs")]
+    pub fn encode_png(&self) -> Result<Vec<u8>, error::ResourceError> {
+        encode_png(self.size, &self.to_rgba8())
+    }
+
+    #[inline]
+    pub fn command_groups(&self) -> &CommandGroupStore {
+        &self.groups
+    }
+
+    fn capture_frame(&mut self) -> Result<(), error::DisplayError> {
+        let index = match self.capture {
+            Some((_, ref mut index)) => {
+                *index += 1;
+                *index - 1
+            }
+            None => return Ok(()),
+        };
+
+        let pixels = self.to_rgba8();
+        match self.capture {
+            Some((FrameCapture::Sink(ref mut sink), _)) => {
+                sink(CapturedFrame { index, size: self.size, pixels })
+            }
+            #[cfg(feature = "image-codecs")]
+            Some((FrameCapture::PngSequence(ref directory), _)) => {
+                std::fs::write(
+                    FrameCapture::png_path(directory, index),
+                    encode_png(self.size, &pixels)?,
+                )
+                .map_err(error::ResourceError::from)?;
+            }
+            // PNG sequences aren't captured without the `image-codecs` feature.
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn font_error(error: error::FontError) -> error::DisplayError {
+    error::DisplayError::InternalError(error.into())
+}
+
+impl GraphicsDisplay for SoftwareGraphicsDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = size;
+        self.pixels = vec![TRANSPARENT; size.0 as usize * size.1 as usize];
+        self.effects.clear();
+        self.groups.invalidate_all();
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let resource = match descriptor {
+            ResourceDescriptor::Image(ImageData::Raw(ref data, info)) => {
+                SoftwareResource::Image(data.with_bytes(|bytes| RasterImage::new(bytes, info))??)
+            }
+            ResourceDescriptor::Image(ImageData::Encoded(ref data)) => {
+                SoftwareResource::Image(data.with_bytes(RasterImage::decode)??)
+            }
+            ResourceDescriptor::Font(ref data) => {
+                // fonts are drawn through the font info of text items, so they're only checked.
+                let bytes = data.with_bytes(|bytes| bytes.to_vec())?;
+                font_kit::font::Font::from_bytes(Arc::new(bytes), 0)
+                    .map_err(|_| error::ResourceError::InvalidData)?;
+                SoftwareResource::Font
+            }
+        };
+        Ok(self.resources.insert(&descriptor, resource))
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.resources.remove(reference);
+    }
+
+    fn image_metadata(&self, reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        match self.resources.get(reference) {
+            Ok(SoftwareResource::Image(image)) => {
+                Some(metadata::ImageMetadata::new((image.width as u32, image.height as u32)))
+            }
+            _ => None,
+        }
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+        Ok(self.groups.push(
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+        )?)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.groups.get(handle).map(|group| &group.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            false,
+        );
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.groups.remove(handle).map(|group| group.commands)
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, false);
+    }
+
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.groups.set_clip(handle, clip);
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.groups.set_transform(handle, transform);
+    }
+
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.groups.set_layer(handle, layer);
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        self.groups.set_scissor(handle, scissor);
+    }
+
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        self.groups.set_label(handle, label);
+    }
+
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.groups.get(handle)?.label.as_deref()
+    }
+
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        self.groups.set_layer_visible(layer, visible);
+    }
+
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        self.groups.set_layer_opacity(layer, opacity);
+    }
+
+    fn set_dithering(&mut self, dithering: Dithering) {
+        if dithering != self.dithering {
+            self.dithering = dithering;
+            self.groups.invalidate_all();
+        }
+    }
+
+    fn set_display_filters(&mut self, filters: &[DisplayFilter]) -> bool {
+        self.filter =
+            if filters.is_empty() { None } else { Some(DisplayFilter::combined_matrix(filters)) };
+        true
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn set_scale_factor(&mut self, scale_factor: f64) {
+        if scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            // glyphs are filled from their outlines every time, so only the blurred backdrops are rasterized ahead.
+            self.effects.clear();
+            self.groups.invalidate_all();
+        }
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            true,
+        );
+    }
+
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, true);
+    }
+
+    fn expire_command_groups(&mut self) {
+        self.groups.expire();
+    }
+
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        Some(self.groups.maintenance_count())
+    }
+
+    fn before_exit(&mut self) {}
+
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+        LeakReport {
+            resources: self.resources.take_leaks(),
+            command_groups: self.groups.take_handles(),
+        }
+    }
+
+    fn set_frame_capture(&mut self, capture: Option<FrameCapture>) -> bool {
+        if cfg!(not(feature = "image-codecs"))
+            && matches!(capture, Some(FrameCapture::PngSequence(_)))
+        {
+            return false;
+        }
+
+        self.capture = capture.map(|capture| (capture, 0));
+        true
+    }
+
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn take_damage(&mut self) -> Damage {
+        self.groups.take_damage()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+        let (width, height) = (self.size.0 as usize, self.size.1 as usize);
+        // anything outside the cull is left untouched.
+        let mut region = Region::new(0, 0, width as i32, height as i32);
+        if let Some(cull) = cull {
+            region = region.intersection(Region::round_out(cull));
+        }
+
+        let handles = self.groups.present(cull);
+        // expired command groups are damaged by the store's present, so the damage is taken afterwards.
+        self.effects.invalidate(self.groups.take_redraw_damage());
+        let mut canvas =
+            Canvas::new(&mut self.pixels, width, region, &mut self.effects, self.dithering);
+        let mut lowered = Vec::new();
+        let mut current_layer = None;
+        let mut layer_depth = None;
+
+        for handle in handles {
+            let group = match self.groups.get(handle) {
+                Some(group) => group,
+                None => continue,
+            };
+
+            // command groups are ordered by layer, so each layer is composited as a whole.
+            if current_layer != Some(group.layer) {
+                current_layer = Some(group.layer);
+                if let Some(depth) = layer_depth.take() {
+                    canvas.restore_to(depth);
+                }
+
+                let opacity = self.groups.layer_state(group.layer).1;
+                if opacity < 1.0 {
+                    layer_depth = Some(canvas.depth());
+                    canvas.save_layer(opacity, None);
+                }
+            }
+
+            // unprotected command groups leak their state into the following command groups.
+            let depth = canvas.depth();
+            let isolated = group.protected
+                || group.clip.is_some()
+                || group.scissor.is_some()
+                || group.transform != Transform::identity();
+            if isolated {
+                canvas.save();
+            }
+            // the scissor is in display coordinates, so it's applied before the transformation.
+            if let Some(scissor) = group.scissor {
+                let clip = DisplayClip::Rectangle { rect: scissor, antialias: false };
+                canvas.draw(
+                    &LoweredCommand::Clip { path: clip_path(&clip), antialias: false },
+                    None,
+                )?;
+            }
+            canvas.concat(&group.transform);
+            if let Some(ref clip) = group.clip {
+                canvas
+                    .draw(&LoweredCommand::Clip { path: clip_path(clip), antialias: true }, None)?;
+            }
+
+            lowered.clear();
+            for command in &group.commands {
+                lower_command(command, &mut lowered).map_err(font_error)?;
+            }
+            for command in &lowered {
+                canvas.draw(command, Some(&self.resources))?;
+            }
+
+            if isolated {
+                canvas.restore_to(depth);
+            }
+        }
+
+        canvas.restore_to(0);
+        self.effects.trim();
+        self.capture_frame()
+    }
+}
+
+enum SoftwareResource {
+    Image(RasterImage),
+    Font,
+}
+
+/// Pixels of an image resource.
+struct RasterImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl RasterImage {
+    fn new(bytes: &[u8], info: RasterImageInfo) -> Result<Self, error::ResourceError> {
+        let (width, height) = (info.size.0 as usize, info.size.1 as usize);
+        if bytes.len() != width * height * 4 {
+            return Err(error::ResourceError::InvalidData);
+        }
+
+        let pixels = bytes
+            .chunks(4)
+            .map(|pixel| {
+                let [red, green, blue, alpha] = match info.format {
+                    RasterImageFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+                    RasterImageFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+                };
+                let channel = |value: u8| value as f32 / 255.0;
+                premultiply(Color::new(channel(red), channel(green), channel(blue), channel(alpha)))
+            })
+            .collect();
+
+        Ok(RasterImage { width, height, pixels })
+    }
+
+    #[cfg(feature = "image-codecs")]
+    fn decode(bytes: &[u8]) -> Result<Self, error::ResourceError> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|_| error::ResourceError::InvalidData)?
+            .into_rgba8();
+        RasterImage::new(
+            image.as_raw(),
+            RasterImageInfo { size: image.dimensions(), format: RasterImageFormat::Rgba8 },
+        )
+    }
+
+    #[cfg(not(feature = "image-codecs"))]
+    fn decode(_bytes: &[u8]) -> Result<Self, error::ResourceError> {
+        Err(error::ResourceError::Unsupported(
+            "encoded images are only decoded by the software display with the `image-codecs` feature"
+                .to_string(),
+        ))
+    }
+
+    // Samples the image at a position in pixels, interpolating between the four nearest pixels within `source`.
+    fn sample(&self, position: Point, source: Rect) -> Pixel {
+        let x = position
+            .x
+            .clamp(source.min_x() + 0.5, (source.max_x() - 0.5).max(source.min_x() + 0.5))
+            - 0.5;
+        let y = position
+            .y
+            .clamp(source.min_y() + 0.5, (source.max_y() - 0.5).max(source.min_y() + 0.5))
+            - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let pixel = |x: f32, y: f32| {
+            let x = (x.max(0.0) as usize).min(self.width.saturating_sub(1));
+            let y = (y.max(0.0) as usize).min(self.height.saturating_sub(1));
+            self.pixels.get(y * self.width + x).copied().unwrap_or(TRANSPARENT)
+        };
+
+        let top = lerp(pixel(x0, y0), pixel(x0 + 1.0, y0), fx);
+        let bottom = lerp(pixel(x0, y0 + 1.0), pixel(x0 + 1.0, y0 + 1.0), fx);
+        lerp(top, bottom, fy)
+    }
+}
+
+fn hash_pixels(pixels: impl Iterator<Item = Pixel>) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for pixel in pixels {
+        pixel.map(f32::to_bits).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn premultiply(color: Color) -> Pixel {
+    let alpha = color.alpha.clamp(0.0, 1.0);
+    [
+        color.red.clamp(0.0, 1.0) * alpha,
+        color.green.clamp(0.0, 1.0) * alpha,
+        color.blue.clamp(0.0, 1.0) * alpha,
+        alpha,
+    ]
+}
+
+fn unpremultiply(pixel: Pixel) -> Color {
+    if pixel[3] <= 0.0 {
+        Color::new(0.0, 0.0, 0.0, 0.0)
+    } else {
+        Color::new(pixel[0] / pixel[3], pixel[1] / pixel[3], pixel[2] / pixel[3], pixel[3])
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn lerp(a: Pixel, b: Pixel, t: f32) -> Pixel {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+// Draws `source` over `destination` (both premultiplied), with `source` scaled by `coverage`.
+fn blend(destination: &mut Pixel, source: Pixel, coverage: f32) {
+    let inverse = 1.0 - source[3] * coverage;
+    for (destination, source) in destination.iter_mut().zip(&source) {
+        *destination = source * coverage + *destination * inverse;
+    }
+}
+
+/// A pixel-aligned region, from `(x0, y0)` inclusive to `(x1, y1)` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Region {
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+}
+
+impl Region {
+    fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Region { x0, y0, x1, y1 }
+    }
+
+    /// The smallest region containing `rect`.
+    fn round_out(rect: Rect) -> Self {
+        Region::new(
+            rect.min_x().floor() as i32,
+            rect.min_y().floor() as i32,
+            rect.max_x().ceil() as i32,
+            rect.max_y().ceil() as i32,
+        )
+    }
+
+    fn intersection(self, other: Region) -> Self {
+        Region::new(
+            self.x0.max(other.x0),
+            self.y0.max(other.y0),
+            self.x1.min(other.x1),
+            self.y1.min(other.y1),
+        )
+    }
+
+    fn inflate(self, x: i32, y: i32) -> Self {
+        Region::new(self.x0 - x, self.y0 - y, self.x1 + x, self.y1 + y)
+    }
+
+    fn is_empty(self) -> bool {
+        self.x0 >= self.x1 || self.y0 >= self.y1
+    }
+
+    fn width(self) -> usize {
+        (self.x1 - self.x0).max(0) as usize
+    }
+
+    fn height(self) -> usize {
+        (self.y1 - self.y0).max(0) as usize
+    }
+
+    fn contains(self, x: i32, y: i32) -> bool {
+        x >= self.x0 && x < self.x1 && y >= self.y0 && y < self.y1
+    }
+
+    /// Index of a pixel in a buffer covering this region, row by row.
+    fn index(self, x: i32, y: i32) -> usize {
+        (y - self.y0) as usize * self.width() + (x - self.x0) as usize
+    }
+
+    fn to_rect(self) -> Rect {
+        Rect::new(
+            Point::new(self.x0 as f32, self.y0 as f32),
+            Size::new(self.width() as f32, self.height() as f32),
+        )
+    }
+
+    fn pixels(self) -> impl Iterator<Item = (i32, i32)> {
+        (self.y0..self.y1.max(self.y0)).flat_map(move |y| (self.x0..self.x1).map(move |x| (x, y)))
+    }
+}
+
+/// Returns the region touched by polygons (ignoring points which aren't finite).
+fn polygon_region(polygons: &[Vec<Point>]) -> Region {
+    let points = polygons
+        .iter()
+        .flatten()
+        .filter(|point| point.x.is_finite() && point.y.is_finite())
+        .copied();
+    let (min, max) = points.fold(
+        (Point::new(f32::MAX, f32::MAX), Point::new(f32::MIN, f32::MIN)),
+        |(min, max), point| (min.min(point), max.max(point)),
+    );
+    if min.x > max.x {
+        return Region::new(0, 0, 0, 0);
+    }
+    Region::round_out(Rect::from_points([min, max]))
+}
+
+/// Coverage (from 0 to 1) of each pixel within a region; pixels outside of the region aren't covered.
+struct Mask {
+    region: Region,
+    coverage: Vec<f32>,
+}
+
+impl Mask {
+    fn get(&self, x: i32, y: i32) -> f32 {
+        if self.region.contains(x, y) {
+            self.coverage[self.region.index(x, y)]
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Rasterizes polygons (which are implicitly closed) with the non-zero rule, into a coverage mask of `region`.
+///
+/// The area each edge covers in each pixel is accumulated (as in font-rs), which is exact for any edge
+/// and only approximates the non-zero rule where polygons of opposite winding overlap.
+fn rasterize(polygons: &[Vec<Point>], region: Region, antialias: bool) -> Mask {
+    let (width, height) = (region.width(), region.height());
+    // the accumulation of a row spills one pixel past its end, and edges at its end land another pixel further.
+    let stride = width + 2;
+    let mut accumulation = vec![0.0f32; stride * height];
+    let offset = Vector::new(region.x0 as f32, region.y0 as f32);
+    for polygon in polygons {
+        for (i, &a) in polygon.iter().enumerate() {
+            let b = polygon[(i + 1) % polygon.len()];
+            add_edge(&mut accumulation, stride, width, height, a - offset, b - offset);
+        }
+    }
+
+    let mut coverage = vec![0.0; width * height];
+    for y in 0..height {
+        let mut sum = 0.0;
+        for x in 0..width {
+            sum += accumulation[y * stride + x];
+            let value = sum.abs().min(1.0);
+            coverage[y * width + x] = if antialias || value == 0.0 {
+                value
+            } else if value >= 0.5 {
+                1.0
+            } else {
+                0.0
+            };
+        }
+    }
+
+    Mask { region, coverage }
+}
+
+// Adds an edge to the accumulation of a `width` by `height` region.
+fn add_edge(
+    accumulation: &mut [f32],
+    stride: usize,
+    width: usize,
+    height: usize,
+    a: Point,
+    b: Point,
+) {
+    if a.y == b.y || !(a.x.is_finite() && a.y.is_finite() && b.x.is_finite() && b.y.is_finite()) {
+        return;
+    }
+
+    // the edge is split where it crosses the sides of the region, and moved onto the sides outside of them;
+    // an edge to the left of the region covers all of its row, and one to the right of it covers nothing.
+    let width = width as f32;
+    let mut cuts = [0.0, 0.0, 0.0, 1.0];
+    let mut count = 1;
+    for &side in &[0.0, width] {
+        if (a.x - side) * (b.x - side) < 0.0 {
+            cuts[count] = (side - a.x) / (b.x - a.x);
+            count += 1;
+        }
+    }
+    cuts[count] = 1.0;
+    count += 1;
+    cuts[..count].sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let clamp = |point: Point| Point::new(point.x.clamp(0.0, width), point.y);
+    for pair in cuts[..count].windows(2) {
+        accumulate_line(
+            accumulation,
+            stride,
+            height,
+            clamp(a.lerp(b, pair[0])),
+            clamp(a.lerp(b, pair[1])),
+        );
+    }
+}
+
+// Accumulates the signed area covered by a line within the region, to the right of it.
+fn accumulate_line(accumulation: &mut [f32], stride: usize, height: usize, a: Point, b: Point) {
+    if a.y == b.y {
+        return;
+    }
+
+    let (direction, a, b) = if a.y < b.y { (1.0, a, b) } else { (-1.0, b, a) };
+    let dxdy = (b.x - a.x) / (b.y - a.y);
+    let mut x = a.x;
+    if a.y < 0.0 {
+        x -= a.y * dxdy;
+    }
+
+    for y in a.y.max(0.0) as usize..(b.y.max(0.0).ceil() as usize).min(height) {
+        let row = y * stride;
+        let dy = ((y + 1) as f32).min(b.y) - (y as f32).max(a.y);
+        let x_next = x + dxdy * dy;
+        let d = dy * direction;
+        let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+        let x0_floor = x0.floor();
+        let x0i = x0_floor.max(0.0) as usize;
+        let x1_ceil = x1.ceil();
+        let x1i = x1_ceil.max(0.0) as usize;
+
+        if x1i <= x0i + 1 {
+            // within a single pixel.
+            let xmf = 0.5 * (x + x_next) - x0_floor;
+            accumulation[row + x0i] += d - d * xmf;
+            accumulation[row + x0i + 1] += d * xmf;
+        } else {
+            let s = (x1 - x0).recip();
+            let x0f = x0 - x0_floor;
+            let a0 = 0.5 * s * (1.0 - x0f) * (1.0 - x0f);
+            let x1f = x1 - x1_ceil + 1.0;
+            let am = 0.5 * s * x1f * x1f;
+            accumulation[row + x0i] += d * a0;
+            if x1i == x0i + 2 {
+                accumulation[row + x0i + 1] += d * (1.0 - a0 - am);
+            } else {
+                let a1 = s * (1.5 - x0f);
+                accumulation[row + x0i + 1] += d * (a1 - a0);
+                for xi in x0i + 2..x1i - 1 {
+                    accumulation[row + xi] += d * s;
+                }
+                let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                accumulation[row + x1i - 1] += d * (1.0 - a2 - am);
+            }
+            accumulation[row + x1i] += d * am;
+        }
+
+        x = x_next;
+    }
+}
+
+/// Source of the color of each pixel of a fill.
+enum Shader<'a> {
+    Solid(Pixel),
+    /// Gradients and patterns are evaluated in the coordinate space they were drawn in (`inverse` maps pixels back into it).
+    Linear(Transform, &'a Gradient),
+    Radial(Transform, &'a Gradient),
+    Checkerboard(Transform, Checkerboard),
+    Image {
+        inverse: Transform,
+        image: &'a RasterImage,
+        /// Where the image is drawn, before the inverse is applied.
+        rect: Rect,
+        /// Part of the image which is drawn, in pixels.
+        source: Rect,
+        adjustments: Option<ImageAdjustments>,
+    },
+}
+
+impl<'a> Shader<'a> {
+    fn new(color: &'a StyleColor, transform: &Transform) -> Self {
+        let inverse = transform.inverse().unwrap_or_else(Transform::identity);
+        match color {
+            StyleColor::Color(color) => Shader::Solid(premultiply(*color)),
+            StyleColor::LinearGradient(gradient) => Shader::Linear(inverse, gradient),
+            StyleColor::RadialGradient(gradient) => Shader::Radial(inverse, gradient),
+            StyleColor::Checkerboard(checkerboard) => Shader::Checkerboard(inverse, *checkerboard),
+        }
+    }
+
+    /// Returns how the shader is dithered, given the dithering of the display.
+    fn dithering(&self, display: Dithering) -> Dithering {
+        match self {
+            Shader::Linear(_, gradient) | Shader::Radial(_, gradient) => {
+                gradient.dithering.unwrap_or(display)
+            }
+            _ => Dithering::None,
+        }
+    }
+
+    /// Returns the color at the center of a pixel.
+    fn shade(&self, x: i32, y: i32) -> Pixel {
+        let center = Point::new(x as f32 + 0.5, y as f32 + 0.5);
+        match self {
+            Shader::Solid(pixel) => *pixel,
+            Shader::Linear(inverse, gradient) => {
+                let point = inverse.transform_point(center);
+                let axis = gradient.end - gradient.start;
+                let t = (point - gradient.start).dot(axis) / axis.square_length().max(1e-6);
+                gradient_color(&gradient.stops, t)
+            }
+            Shader::Radial(inverse, gradient) => {
+                let point = inverse.transform_point(center);
+                let radius = (gradient.end - gradient.start).length().max(1e-6);
+                gradient_color(&gradient.stops, (point - gradient.start).length() / radius)
+            }
+            Shader::Checkerboard(inverse, checkerboard) => {
+                premultiply(checkerboard.color_at(inverse.transform_point(center)))
+            }
+            Shader::Image { inverse, image, rect, source, adjustments } => {
+                let point = inverse.transform_point(center);
+                let position = Point::new(
+                    source.min_x() + (point.x - rect.min_x()) / rect.size.width * source.size.width,
+                    source.min_y()
+                        + (point.y - rect.min_y()) / rect.size.height * source.size.height,
+                );
+                let pixel = image.sample(position, *source);
+                match adjustments {
+                    Some(adjustments) => premultiply(adjustments.apply(unpremultiply(pixel))),
+                    None => pixel,
+                }
+            }
+        }
+    }
+}
+
+fn gradient_color(stops: &[(f32, Color)], t: f32) -> Pixel {
+    let t = t.clamp(0.0, 1.0);
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return TRANSPARENT,
+    };
+    if t <= first.0 {
+        return premultiply(first.1);
+    }
+
+    for pair in stops.windows(2) {
+        let ((start, from), (end, to)) = (pair[0], pair[1]);
+        if t <= end {
+            // colors are interpolated unpremultiplied, as in Skia.
+            let mix = if end > start { (t - start) / (end - start) } else { 1.0 };
+            return premultiply(Color::new(
+                from.red + (to.red - from.red) * mix,
+                from.green + (to.green - from.green) * mix,
+                from.blue + (to.blue - from.blue) * mix,
+                from.alpha + (to.alpha - from.alpha) * mix,
+            ));
+        }
+    }
+    premultiply(last.1)
+}
+
+/// Offsets a pixel by the threshold of `dithering` at its position, so that it's rounded up or down when it's quantized.
+fn dither(pixel: Pixel, dithering: Dithering, x: i32, y: i32) -> Pixel {
+    if dithering == Dithering::None {
+        return pixel;
+    }
+
+    let offset = dithering.threshold(x, y) / 255.0;
+    let alpha = pixel[3];
+    [
+        (pixel[0] + offset).clamp(0.0, alpha),
+        (pixel[1] + offset).clamp(0.0, alpha),
+        (pixel[2] + offset).clamp(0.0, alpha),
+        alpha,
+    ]
+}
+
+/// Applies a filter to a buffer covering `region`.
+fn apply_filter(pixels: &mut [Pixel], region: Region, filter: Filter) {
+    match filter {
+        Filter::Invert => {
+            for pixel in pixels {
+                *pixel = [pixel[3] - pixel[0], pixel[3] - pixel[1], pixel[3] - pixel[2], pixel[3]];
+            }
+        }
+        Filter::Blur(sigma_x, sigma_y) => {
+            // three box blurs approximate a gaussian blur.
+            let radius =
+                |sigma: f32| (((4.0 * sigma * sigma + 1.0).sqrt() - 1.0) / 2.0).round() as usize;
+            let (width, height) = (region.width(), region.height());
+            for _ in 0..3 {
+                box_blur(pixels, height, width, 1, width, radius(sigma_x));
+                box_blur(pixels, width, height, width, 1, radius(sigma_y));
+            }
+        }
+    }
+}
+
+// Blurs `lines` lines of `length` pixels (`step` apart within a line, and `line_step` apart between lines),
+// treating pixels outside of the buffer as transparent.
+fn box_blur(
+    pixels: &mut [Pixel],
+    lines: usize,
+    length: usize,
+    step: usize,
+    line_step: usize,
+    radius: usize,
+) {
+    if radius == 0 || length == 0 {
+        return;
+    }
+
+    let scale = 1.0 / (radius * 2 + 1) as f32;
+    let mut line = vec![TRANSPARENT; length];
+    for l in 0..lines {
+        let start = l * line_step;
+        for (i, pixel) in line.iter_mut().enumerate() {
+            *pixel = pixels[start + i * step];
+        }
+
+        let mut sum = TRANSPARENT;
+        for pixel in line.iter().take(radius) {
+            accumulate(&mut sum, pixel, 1.0);
+        }
+        for i in 0..length {
+            if let Some(entering) = line.get(i + radius) {
+                accumulate(&mut sum, entering, 1.0);
+            }
+            let out = &mut pixels[start + i * step];
+            for (out, sum) in out.iter_mut().zip(&sum) {
+                *out = (sum * scale).max(0.0);
+            }
+            if i >= radius {
+                accumulate(&mut sum, &line[i - radius], -1.0);
+            }
+        }
+    }
+}
+
+fn accumulate(sum: &mut Pixel, pixel: &Pixel, sign: f32) {
+    for (sum, value) in sum.iter_mut().zip(pixel) {
+        *sum += value * sign;
+    }
+}
+
+#[derive(Clone)]
+struct DrawState {
+    transform: Transform,
+    /// Everything outside of this is clipped.
+    clip: Region,
+    /// Coverage of the clip within its region, unless it's only the region.
+    mask: Option<Rc<Mask>>,
+}
+
+impl DrawState {
+    fn coverage(&self, x: i32, y: i32) -> f32 {
+        self.mask.as_ref().map_or(1.0, |mask| mask.get(x, y))
+    }
+}
+
+struct Layer {
+    region: Region,
+    pixels: Vec<Pixel>,
+    opacity: f32,
+    filter: Option<Filter>,
+    /// The state the layer was begun in, which it's composited through.
+    state: DrawState,
+}
+
+/// Draws lowered commands into a buffer.
+struct Canvas<'a> {
+    target: &'a mut [Pixel],
+    // the region of the whole target.
+    bounds: Region,
+    layers: Vec<Layer>,
+    state: DrawState,
+    // saved states, and whether each began a layer.
+    saved: Vec<(DrawState, bool)>,
+    effects: &'a mut EffectCache<Vec<Pixel>>,
+    // applied to gradients (which don't override it) and blurs.
+    dithering: Dithering,
+}
+
+impl<'a> Canvas<'a> {
+    fn new(
+        target: &'a mut [Pixel],
+        width: usize,
+        clip: Region,
+        effects: &'a mut EffectCache<Vec<Pixel>>,
+        dithering: Dithering,
+    ) -> Self {
+        let height = target.len().checked_div(width).unwrap_or(0);
+        Canvas {
+            target,
+            bounds: Region::new(0, 0, width as i32, height as i32),
+            layers: Vec::new(),
+            state: DrawState { transform: Transform::identity(), clip, mask: None },
+            saved: Vec::new(),
+            effects,
+            dithering,
+        }
+    }
+
+    /// Returns the buffer which is drawn into, and the region it covers.
+    fn surface(&mut self) -> (&mut [Pixel], Region) {
+        match self.layers.last_mut() {
+            Some(layer) => (&mut layer.pixels, layer.region),
+            None => (&mut *self.target, self.bounds),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.saved.len()
+    }
+
+    fn save(&mut self) {
+        self.saved.push((self.state.clone(), false));
+    }
+
+    fn save_layer(&mut self, opacity: f32, filter: Option<Filter>) {
+        self.saved.push((self.state.clone(), true));
+        let region = self.state.clip;
+        self.layers.push(Layer {
+            region,
+            pixels: vec![TRANSPARENT; region.width() * region.height()],
+            opacity,
+            filter,
+            state: self.state.clone(),
+        });
+    }
+
+    fn restore(&mut self) {
+        if let Some((state, layer)) = self.saved.pop() {
+            self.state = state;
+            if layer {
+                if let Some(layer) = self.layers.pop() {
+                    self.composite(layer);
+                }
+            }
+        }
+    }
+
+    fn restore_to(&mut self, depth: usize) {
+        while self.saved.len() > depth {
+            self.restore();
+        }
+    }
+
+    fn concat(&mut self, transform: &Transform) {
+        self.state.transform = transform.post_transform(&self.state.transform);
+    }
+
+    fn tolerance(&self) -> f32 {
+        TOLERANCE / transform_scale(&self.state.transform)
+    }
+
+    fn to_device(&self, polygons: impl IntoIterator<Item = Vec<Point>>) -> Vec<Vec<Point>> {
+        polygons
+            .into_iter()
+            .map(|polygon| {
+                polygon
+                    .into_iter()
+                    .map(|point| self.state.transform.transform_point(point))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn fill_polygons(&self, path: &VectorPath) -> Vec<Vec<Point>> {
+        self.to_device(flatten(path, true, self.tolerance()).into_iter().map(|(points, _)| points))
+    }
+
+    fn draw(
+        &mut self,
+        command: &LoweredCommand,
+        resources: Option<&ResourceRegistry<SoftwareResource>>,
+    ) -> Result<(), error::DisplayError> {
+        let resources = || {
+            resources.ok_or_else(|| {
+                error::DisplayError::InternalError("resources used outside of a command".into())
+            })
+        };
+
+        match command {
+            LoweredCommand::Path { path, is_closed, paint } => match paint {
+                GraphicsDisplayPaint::Fill(color) => {
+                    let polygons = self.fill_polygons(path);
+                    self.fill(&polygons, true, &Shader::new(color, &self.state.transform));
+                }
+                GraphicsDisplayPaint::Stroke(stroke) => {
+                    let tolerance = self.tolerance();
+                    // hairlines are a pixel wide, regardless of the transformation.
+                    let width = if stroke.thickness > 0.0 {
+                        stroke.thickness
+                    } else {
+                        1.0 / transform_scale(&self.state.transform)
+                    };
+                    let contours = flatten(path, *is_closed, tolerance);
+                    let polygons =
+                        self.to_device(stroke_polygons(&contours, stroke, width, tolerance));
+                    self.fill(
+                        &polygons,
+                        stroke.antialias,
+                        &Shader::new(&stroke.color, &self.state.transform),
+                    );
+                }
+            },
+            LoweredCommand::Image { src, dst, resource, orientation, adjustments } => {
+                let image = match resources()?.get(*resource)? {
+                    SoftwareResource::Image(image) => image,
+                    SoftwareResource::Font => {
+                        return Err(error::DisplayError::MismatchedResource(resource.id()))
+                    }
+                };
+                let rect = orientation.image_rect(*dst);
+                let transform = orientation.transform(*dst).post_transform(&self.state.transform);
+                let (min, max) = (rect.min(), rect.max());
+                let polygon = [min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)]
+                    .iter()
+                    .map(|&point| transform.transform_point(point))
+                    .collect();
+                let shader = Shader::Image {
+                    inverse: transform.inverse().unwrap_or_else(Transform::identity),
+                    image,
+                    rect,
+                    source: src.unwrap_or_else(|| {
+                        Rect::new(Point::zero(), Size::new(image.width as f32, image.height as f32))
+                    }),
+                    adjustments: if adjustments.is_identity() { None } else { Some(*adjustments) },
+                };
+                self.fill(&[polygon], true, &shader);
+            }
+            LoweredCommand::Glyphs(run) => {
+                resources()?.get(run.font)?;
+                let polygons = self.fill_polygons(&run.outline().map_err(font_error)?);
+                self.fill(&polygons, true, &Shader::new(&run.color, &self.state.transform));
+            }
+            LoweredCommand::Clip { path, antialias } => {
+                let polygons = self.fill_polygons(path);
+                self.clip(&polygons, *antialias);
+            }
+            LoweredCommand::BackdropFilter { path, filter } => {
+                let polygons = self.fill_polygons(path);
+                self.backdrop_filter(&polygons, *filter);
+            }
+            LoweredCommand::Save => self.save(),
+            LoweredCommand::SaveLayer { opacity, filter } => self.save_layer(*opacity, *filter),
+            LoweredCommand::Restore => self.restore(),
+            LoweredCommand::Transform(transform) => self.concat(transform),
+            LoweredCommand::Clear(color) => self.clear(*color),
+        }
+
+        Ok(())
+    }
+
+    fn fill(&mut self, polygons: &[Vec<Point>], antialias: bool, shader: &Shader) {
+        let region = polygon_region(polygons).intersection(self.state.clip);
+        if region.is_empty() {
+            return;
+        }
+
+        let mask = rasterize(polygons, region, antialias);
+        let state = self.state.clone();
+        let dithering = shader.dithering(self.dithering);
+        let (pixels, surface) = self.surface();
+        for (x, y) in region.pixels() {
+            let coverage = mask.get(x, y) * state.coverage(x, y);
+            if coverage > 0.0 {
+                let color = dither(shader.shade(x, y), dithering, x, y);
+                blend(&mut pixels[surface.index(x, y)], color, coverage);
+            }
+        }
+    }
+
+    fn clip(&mut self, polygons: &[Vec<Point>], antialias: bool) {
+        let region = polygon_region(polygons).intersection(self.state.clip);
+        let region = if region.is_empty() { Region::new(0, 0, 0, 0) } else { region };
+
+        let mut mask = rasterize(polygons, region, antialias);
+        if let Some(ref previous) = self.state.mask {
+            for (x, y) in region.pixels() {
+                mask.coverage[region.index(x, y)] *= previous.get(x, y);
+            }
+        }
+        self.state.clip = region;
+        self.state.mask = Some(Rc::new(mask));
+    }
+
+    fn clear(&mut self, color: Color) {
+        let color = premultiply(color);
+        let state = self.state.clone();
+        let (pixels, surface) = self.surface();
+        for (x, y) in state.clip.pixels() {
+            let pixel = &mut pixels[surface.index(x, y)];
+            *pixel = lerp(*pixel, color, state.coverage(x, y));
+        }
+    }
+
+    fn backdrop_filter(&mut self, polygons: &[Vec<Point>], filter: Filter) {
+        let region = polygon_region(polygons).intersection(self.state.clip);
+        if region.is_empty() {
+            return;
+        }
+
+        let mask = rasterize(polygons, region, true);
+        let (pixels, surface) = match self.layers.last_mut() {
+            Some(layer) => (&mut layer.pixels[..], layer.region),
+            None => (&mut *self.target, self.bounds),
+        };
+
+        // the filter reads beyond the region (e.g. to blur into it).
+        let extent = filter.extent();
+        let source =
+            region.inflate(extent.x.ceil() as i32, extent.y.ceil() as i32).intersection(surface);
+        let filter_source = |pixels: &[Pixel]| {
+            let mut filtered: Vec<Pixel> =
+                source.pixels().map(|(x, y)| pixels[surface.index(x, y)]).collect();
+            apply_filter(&mut filtered, source, filter);
+            filtered
+        };
+
+        // only blurs are worth caching; inverting costs about as much as hashing the input.
+        let uncached;
+        let filtered = match filter {
+            Filter::Blur(..) => self.effects.get_or_insert_with(
+                EffectKey::new(filter, source.to_rect()),
+                || hash_pixels(source.pixels().map(|(x, y)| pixels[surface.index(x, y)])),
+                || filter_source(pixels),
+            ),
+            Filter::Invert => {
+                uncached = filter_source(pixels);
+                &uncached
+            }
+        };
+
+        // the cached blur isn't dithered, so it can be reused if the dithering changes.
+        let dithering = match filter {
+            Filter::Blur(..) => self.dithering,
+            Filter::Invert => Dithering::None,
+        };
+        for (x, y) in region.pixels() {
+            let coverage = mask.get(x, y) * self.state.coverage(x, y);
+            if coverage > 0.0 {
+                let pixel = &mut pixels[surface.index(x, y)];
+                let color = dither(filtered[source.index(x, y)], dithering, x, y);
+                *pixel = lerp(*pixel, color, coverage);
+            }
+        }
+    }
+
+    fn composite(&mut self, mut layer: Layer) {
+        if let Some(filter) = layer.filter {
+            apply_filter(&mut layer.pixels, layer.region, filter);
+            if let Filter::Blur(..) = filter {
+                for (x, y) in layer.region.pixels() {
+                    let pixel = &mut layer.pixels[layer.region.index(x, y)];
+                    *pixel = dither(*pixel, self.dithering, x, y);
+                }
+            }
+        }
+
+        let (pixels, surface) = self.surface();
+        for (x, y) in layer.region.pixels() {
+            let coverage = layer.state.coverage(x, y) * layer.opacity;
+            if coverage > 0.0 {
+                blend(
+                    &mut pixels[surface.index(x, y)],
+                    layer.pixels[layer.region.index(x, y)],
+                    coverage,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(feature = "image-codecs")]
+fn encode_png(size: (u32, u32), rgba: &[u8]) -> Result<Vec<u8>, error::ResourceError> {
+    use image::ImageEncoder;
+
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(rgba, size.0, size.1, image::ColorType::Rgba8)
+        .map_err(|error| error::ResourceError::InternalError(error.into()))?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(color: Color) -> GraphicsDisplayPaint {
+        GraphicsDisplayPaint::Fill(color.into())
+    }
+
+    fn draw(display: &mut SoftwareGraphicsDisplay, commands: &[DisplayCommand]) {
+        display.push_command_group(commands, ZOrder::default(), None, Some(false)).unwrap();
+        display.present(None).unwrap();
+    }
+
+    fn close(a: Color, b: Color) -> bool {
+        (a.red - b.red).abs() < 0.02
+            && (a.green - b.green).abs() < 0.02
+            && (a.blue - b.blue).abs() < 0.02
+            && (a.alpha - b.alpha).abs() < 0.02
+    }
+
+    #[test]
+    fn test_software_display_conformance() {
+        backend::conformance::check_display(|| SoftwareGraphicsDisplay::new((32, 32)));
+    }
+
+    #[test]
+    fn test_fill_coverage() {
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let mut display = SoftwareGraphicsDisplay::new((16, 16));
+        let mut builder = DisplayListBuilder::new();
+        // the right edge runs through the middle of a column of pixels.
+        builder.push_rectangle(
+            Rect::new(Point::new(2.0, 2.0), Size::new(4.5, 4.0)),
+            fill(red),
+            None,
+        );
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(3, 3).unwrap(), red));
+        assert!(close(display.pixel(6, 3).unwrap(), Color::new(1.0, 0.0, 0.0, 0.5)));
+        assert_eq!(display.pixel(7, 3).unwrap().alpha, 0.0);
+        assert_eq!(display.pixel(3, 6).unwrap().alpha, 0.0);
+        assert_eq!(display.pixel(16, 0), None);
+    }
+
+    #[test]
+    fn test_stroke_and_clip() {
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let mut display = SoftwareGraphicsDisplay::new((16, 16));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle_clip(Rect::new(Point::zero(), Size::new(8.0, 16.0)), false);
+        builder.push_line(
+            Point::new(0.0, 8.0),
+            Point::new(16.0, 8.0),
+            GraphicsDisplayStroke { thickness: 2.0, ..Default::default() },
+            None,
+        );
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(4, 7).unwrap(), black));
+        assert!(close(display.pixel(4, 8).unwrap(), black));
+        assert_eq!(display.pixel(4, 6).unwrap().alpha, 0.0);
+        // clipped.
+        assert_eq!(display.pixel(12, 8).unwrap().alpha, 0.0);
+    }
+
+    #[test]
+    fn test_layers_and_filters() {
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let rect = Rect::new(Point::zero(), Size::new(8.0, 8.0));
+        let mut display = SoftwareGraphicsDisplay::new((8, 8));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(white);
+        builder.save_layer(0.5);
+        builder.push_rectangle(rect, fill(Color::new(0.0, 0.0, 1.0, 1.0)), None);
+        builder.restore();
+        builder.push_rectangle_backdrop(
+            Rect::new(Point::zero(), Size::new(4.0, 8.0)),
+            false,
+            Filter::Invert,
+        );
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(6, 0).unwrap(), Color::new(0.5, 0.5, 1.0, 1.0)));
+        assert!(close(display.pixel(1, 0).unwrap(), Color::new(0.5, 0.5, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_cached_backdrop_blur() {
+        let mut display = SoftwareGraphicsDisplay::new((32, 32));
+        let background = |color| {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_clear(Color::new(1.0, 1.0, 1.0, 1.0));
+            builder.push_rectangle(
+                Rect::new(Point::new(8.0, 0.0), Size::new(8.0, 32.0)),
+                fill(color),
+                None,
+            );
+            builder.build()
+        };
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle_backdrop(
+            Rect::new(Point::new(4.0, 4.0), Size::new(24.0, 24.0)),
+            true,
+            Filter::Blur(2.0, 2.0),
+        );
+        let panel = builder.build();
+
+        let group = display
+            .push_command_group(
+                &background(Color::new(0.0, 0.0, 0.0, 1.0)),
+                ZOrder(0),
+                None,
+                Some(false),
+            )
+            .unwrap();
+        display.push_command_group(&panel, ZOrder(1), None, Some(false)).unwrap();
+        display.present(None).unwrap();
+        let blurred = display.pixel(16, 16).unwrap();
+        assert!(blurred.red > 0.1 && blurred.red < 0.9);
+        assert_eq!(display.effects.len(), 1);
+
+        // idle frames reuse the blur.
+        display.present(None).unwrap();
+        assert!(close(display.pixel(16, 16).unwrap(), blurred));
+        assert_eq!(display.effects.len(), 1);
+
+        // changing what's behind the panel blurs it again.
+        display.modify_command_group(
+            group,
+            &background(Color::new(1.0, 0.0, 0.0, 1.0)),
+            ZOrder(0),
+            None,
+            Some(false),
+        );
+        display.present(None).unwrap();
+        let pixel = display.pixel(16, 16).unwrap();
+        assert!(close(Color::new(pixel.red, 1.0, 1.0, 1.0), Color::new(1.0, 1.0, 1.0, 1.0)));
+        assert!(pixel.green < 0.9);
+        assert_eq!(display.effects.len(), 1);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let gradient = Gradient {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(16.0, 0.0),
+            stops: vec![
+                (0.0, Color::new(0.0, 0.0, 0.0, 1.0)),
+                (1.0, Color::new(1.0, 1.0, 1.0, 1.0)),
+            ],
+            dithering: None,
+        };
+        let mut display = SoftwareGraphicsDisplay::new((16, 1));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(16.0, 1.0)),
+            GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(gradient)),
+            None,
+        );
+        draw(&mut display, &builder.build());
+
+        let (left, right) = (display.pixel(0, 0).unwrap(), display.pixel(15, 0).unwrap());
+        assert!(left.red < 0.1 && right.red > 0.9);
+        assert!(close(display.pixel(8, 0).unwrap(), Color::new(0.53, 0.53, 0.53, 1.0)));
+    }
+
+    #[test]
+    fn test_dithering() {
+        // a gradient so shallow that it's a single band when quantized.
+        let gradient = |dithering| Gradient {
+            start: Point::new(0.0, 0.0),
+            end: Point::new(16.0, 0.0),
+            stops: vec![
+                (0.0, Color::new(0.4, 0.4, 0.4, 1.0)),
+                (1.0, Color::new(0.401, 0.401, 0.401, 1.0)),
+            ],
+            dithering,
+        };
+        let reds = |display: &mut SoftwareGraphicsDisplay, dithering| {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_rectangle(
+                Rect::new(Point::zero(), Size::new(16.0, 4.0)),
+                GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(gradient(dithering))),
+                None,
+            );
+            draw(display, &builder.build());
+            display.to_rgba8().chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>()
+        };
+
+        let mut display = SoftwareGraphicsDisplay::new((16, 4));
+        let banded = reds(&mut display, None);
+        assert!(banded.iter().all(|&red| red == banded[0]));
+
+        display.set_dithering(Dithering::Ordered);
+        let dithered = reds(&mut display, None);
+        assert!(dithered.iter().any(|&red| red != dithered[0]));
+        assert!(dithered.iter().all(|&red| (red as i32 - banded[0] as i32).abs() <= 1));
+
+        // the gradient's own dithering takes precedence over the display's.
+        assert_eq!(reds(&mut display, Some(Dithering::None)), banded);
+    }
+
+    #[test]
+    fn test_display_filters() {
+        let mut display = SoftwareGraphicsDisplay::new((2, 2));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(2.0, 2.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            None,
+        );
+        draw(&mut display, &builder.build());
+
+        assert!(display.set_display_filters(&[DisplayFilter::Invert]));
+        assert_eq!(display.pixel(1, 1), Some(Color::new(0.0, 1.0, 1.0, 1.0)));
+        assert_eq!(display.to_rgba8()[..4], [0, 255, 255, 255]);
+        assert_eq!(display.to_xrgb8888()[0], 0x0000_ffff);
+
+        // the filters aren't baked into the buffer, so they can be removed without drawing again.
+        assert!(display.set_display_filters(&[]));
+        assert_eq!(display.pixel(1, 1), Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_image() {
+        let mut display = SoftwareGraphicsDisplay::new((4, 4));
+        // 2x1, a blue and a red pixel.
+        let image = display
+            .new_resource(ResourceDescriptor::Image(ImageData::Raw(
+                vec![255, 0, 0, 255, 0, 0, 255, 255].into(),
+                RasterImageInfo { size: (2, 1), format: RasterImageFormat::Bgra8 },
+            )))
+            .unwrap();
+        assert!(matches!(
+            display.new_resource(ResourceDescriptor::Image(ImageData::Raw(
+                vec![0; 3].into(),
+                RasterImageInfo { size: (2, 1), format: RasterImageFormat::Rgba8 },
+            ))),
+            Err(error::ResourceError::InvalidData)
+        ));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_image(None, Rect::new(Point::zero(), Size::new(4.0, 4.0)), image, None);
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(0, 0).unwrap(), Color::new(0.0, 0.0, 1.0, 1.0)));
+        assert!(close(display.pixel(3, 3).unwrap(), Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_placeholder_image() {
+        let mut display = SoftwareGraphicsDisplay::new((16, 16));
+        let image = display
+            .new_resource_or_placeholder(ResourceDescriptor::Image(ImageData::Raw(
+                vec![0; 3].into(),
+                RasterImageInfo { size: (2, 1), format: RasterImageFormat::Rgba8 },
+            )))
+            .unwrap();
+
+        let errors = display.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            error::DisplayError::BrokenImage {
+                source_file: None,
+                error: error::ResourceError::InvalidData
+            }
+        ));
+        assert_eq!(errors[0].kind(), error::DisplayErrorKind::ResourceDecode);
+
+        // fonts aren't replaced.
+        assert!(display
+            .new_resource_or_placeholder(ResourceDescriptor::Font(vec![0; 3].into()))
+            .is_err());
+        assert!(display.take_errors().is_empty());
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_image(None, Rect::new(Point::zero(), Size::new(16.0, 16.0)), image, None);
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(0, 0).unwrap(), Color::new(1.0, 0.0, 1.0, 1.0)));
+        assert!(close(display.pixel(12, 4).unwrap(), Color::new(0.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_text() {
+        let data = include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec();
+        let mut display = SoftwareGraphicsDisplay::new((64, 32));
+        let font = display.new_resource(ResourceDescriptor::Font(data.clone().into())).unwrap();
+        let mut builder = DisplayListBuilder::new();
+        builder.push_text(
+            TextDisplayItem {
+                text: "ab".to_string().into(),
+                font,
+                font_info: FontInfo::from_data(Arc::new(data), 0).unwrap(),
+                size: 20.0,
+                bottom_left: Point::new(4.0, 24.0),
+                color: StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)),
+            },
+            None,
+        );
+        draw(&mut display, &builder.build());
+
+        let covered = display.to_rgba8().chunks(4).filter(|pixel| pixel[3] > 128).count();
+        assert!(covered > 20);
+        // nothing is drawn above the glyphs.
+        assert!((0..64).all(|x| display.pixel(x, 0).unwrap().alpha == 0.0));
+    }
+
+    #[test]
+    #[cfg(feature = "image-codecs")]
+    fn test_encode_png() {
+        let mut display = SoftwareGraphicsDisplay::new((3, 2));
+        draw(&mut display, &[DisplayCommand::Clear(Color::new(1.0, 0.0, 0.0, 1.0))]);
+        let png = display.encode_png().unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let decoded = image::load_from_memory(&png).unwrap().into_rgba8();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.into_raw(), display.to_rgba8());
+        assert_eq!(display.to_xrgb8888(), vec![0x00ff_0000; 6]);
+    }
+
+    #[test]
+    #[cfg(feature = "image-codecs")]
+    fn test_encoded_image() {
+        let mut source = SoftwareGraphicsDisplay::new((2, 1));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(1.0, 0.0, 0.0, 1.0));
+        builder.push_rectangle(
+            Rect::new(Point::new(1.0, 0.0), Size::new(1.0, 1.0)),
+            fill(Color::new(0.0, 0.0, 1.0, 1.0)),
+            None,
+        );
+        draw(&mut source, &builder.build());
+        assert_eq!(source.to_image_buffer().get_pixel(1, 0).0, [0, 0, 255, 255]);
+
+        let mut display = SoftwareGraphicsDisplay::new((4, 4));
+        let image = display
+            .new_resource(ResourceDescriptor::Image(ImageData::Encoded(
+                source.encode_png().unwrap().into(),
+            )))
+            .unwrap();
+        assert_eq!(display.image_metadata(image).unwrap().size(), (2, 1));
+        assert!(matches!(
+            display.new_resource(ResourceDescriptor::Image(ImageData::Encoded(vec![0; 8].into()))),
+            Err(error::ResourceError::InvalidData)
+        ));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_image(None, Rect::new(Point::zero(), Size::new(4.0, 4.0)), image, None);
+        draw(&mut display, &builder.build());
+
+        assert!(close(display.pixel(0, 0).unwrap(), Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert!(close(display.pixel(3, 3).unwrap(), Color::new(0.0, 0.0, 1.0, 1.0)));
+    }
+}
@@ -0,0 +1,594 @@
+//! Geometry for drawing paths as polygons or triangles, independently of the graphics API.
+//!
+//! Paths are [flattened](fn.flatten.html) into polylines, and strokes are [outlined](fn.stroke_polygons.html) as polygons;
+//! the [software display](../software/index.html) rasterizes these directly, while a GPU backend uploads them as a [`Mesh`](struct.Mesh.html).
+//!
+//! Paths convert to and from [`lyon_path`](https://docs.rs/lyon_path/0.14) (see [`to_lyon_path`](fn.to_lyon_path.html)),
+//! so a GPU backend can instead take exact triangulations from lyon through its own [`Tessellator`](trait.Tessellator.html).
+
+use super::*;
+
+/// Triangles as a vertex buffer and an index buffer (three indices per triangle).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    pub vertices: Vec<Point>,
+    pub indices: Vec<u32>,
+    /// Whether the triangles overlap, so that they have to be drawn through a stencil (see [`from_polygons`](struct.Mesh.html#method.from_polygons)).
+    pub stencil: bool,
+}
+
+impl Mesh {
+    /// Triangulates polygons (which are implicitly closed) as triangle fans.
+    ///
+    /// The fans of convex polygons cover them exactly. Concave polygons (and overlapping polygons) are only covered correctly
+    /// by stencil-then-cover; the triangles are drawn into a stencil buffer (incrementing it for clockwise triangles
+    /// and decrementing it for counter-clockwise ones), then the bounds are covered where the stencil isn't zero.
+    pub fn from_polygons(polygons: &[Vec<Point>]) -> Self {
+        let mut mesh = Mesh { stencil: true, ..Default::default() };
+        for polygon in polygons.iter().filter(|polygon| polygon.len() >= 3) {
+            let first = mesh.vertices.len() as u32;
+            mesh.vertices.extend_from_slice(polygon);
+            for i in 1..polygon.len() as u32 - 1 {
+                mesh.indices.extend_from_slice(&[first, first + i, first + i + 1]);
+            }
+        }
+        mesh
+    }
+
+    /// Creates a mesh from triangles which don't overlap (such as the output of lyon's tessellators), so can be drawn directly.
+    pub fn from_triangles<I: Into<u32>>(
+        vertices: impl IntoIterator<Item = Point>,
+        indices: impl IntoIterator<Item = I>,
+    ) -> Self {
+        Mesh {
+            vertices: vertices.into_iter().collect(),
+            indices: indices.into_iter().map(Into::into).collect(),
+            stencil: false,
+        }
+    }
+
+    /// Returns the number of triangles.
+    #[inline]
+    pub fn triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Returns the mesh with every vertex transformed.
+    pub fn transformed(mut self, transform: &Transform) -> Self {
+        for vertex in &mut self.vertices {
+            *vertex = transform.transform_point(*vertex);
+        }
+        self
+    }
+}
+
+/// Triangulates the fill of a path (see [`Mesh::from_polygons`](struct.Mesh.html#method.from_polygons) for how it has to be drawn).
+pub fn fill_mesh(path: &VectorPath, tolerance: f32) -> Mesh {
+    let contours = flatten(path, true, tolerance);
+    Mesh::from_polygons(&contours.into_iter().map(|(points, _)| points).collect::<Vec<_>>())
+}
+
+/// Triangulates a stroke of a path, `width` wide (see [`stroke_polygons`](fn.stroke_polygons.html)).
+///
+/// The polygons of the stroke are convex, but they overlap, so translucent strokes still have to use a stencil.
+pub fn stroke_mesh(
+    path: &VectorPath,
+    is_closed: bool,
+    stroke: &GraphicsDisplayStroke,
+    width: f32,
+    tolerance: f32,
+) -> Mesh {
+    let contours = flatten(path, is_closed, tolerance);
+    Mesh::from_polygons(&stroke_polygons(&contours, stroke, width, tolerance))
+}
+
+/// Triangulates paths for a GPU backend.
+///
+/// [`FanTessellator`](struct.FanTessellator.html) is used unless a backend is given another, such as one which converts paths
+/// with [`to_lyon_path`](fn.to_lyon_path.html) and triangulates them with `lyon_tessellation`.
+pub trait Tessellator {
+    /// Triangulates the fill of a path, with curves approximated to within `tolerance`.
+    fn fill(&mut self, path: &VectorPath, tolerance: f32) -> Mesh;
+
+    /// Triangulates a stroke of a path, `width` wide (which is used rather than the thickness of the stroke, as in [`stroke_mesh`](fn.stroke_mesh.html)).
+    fn stroke(
+        &mut self,
+        path: &VectorPath,
+        is_closed: bool,
+        stroke: &GraphicsDisplayStroke,
+        width: f32,
+        tolerance: f32,
+    ) -> Mesh;
+}
+
+/// Tessellator of [`fill_mesh`](fn.fill_mesh.html) and [`stroke_mesh`](fn.stroke_mesh.html); cheap, but its meshes need a stencil.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FanTessellator;
+
+impl Tessellator for FanTessellator {
+    fn fill(&mut self, path: &VectorPath, tolerance: f32) -> Mesh {
+        fill_mesh(path, tolerance)
+    }
+
+    fn stroke(
+        &mut self,
+        path: &VectorPath,
+        is_closed: bool,
+        stroke: &GraphicsDisplayStroke,
+        width: f32,
+        tolerance: f32,
+    ) -> Mesh {
+        stroke_mesh(path, is_closed, stroke, width, tolerance)
+    }
+}
+
+/// Converts a path to a lyon path.
+///
+/// Lyon has no conics or elliptical arcs by angle, so they're converted to cubic curves (which are exact for weights of 1, and within
+/// a fraction of a percent of the radius for circular arcs). As with [`flatten`](fn.flatten.html), only the last contour is closed (if `is_closed`).
+pub fn to_lyon_path(path: &VectorPath, is_closed: bool) -> lyon_path::Path {
+    let mut builder = lyon_path::Path::builder();
+    let mut current = None;
+
+    for event in path {
+        // a contour without a starting point starts at the origin.
+        let start = match (current, event) {
+            (Some(start), _) => start,
+            (None, VectorPathEvent::MoveTo { .. }) => Point::zero(),
+            (None, _) => {
+                builder.move_to(Point::zero());
+                Point::zero()
+            }
+        };
+
+        match *event {
+            VectorPathEvent::MoveTo { to } => builder.move_to(to),
+            VectorPathEvent::LineTo { to } => builder.line_to(to),
+            VectorPathEvent::QuadTo { control, to } => builder.quadratic_bezier_to(control, to),
+            VectorPathEvent::ConicTo { control, to, weight } => {
+                let k = 4.0 * weight / (3.0 * (1.0 + weight));
+                builder.cubic_bezier_to(start.lerp(control, k), to.lerp(control, k), to);
+            }
+            VectorPathEvent::CubicTo { c1, c2, to } => builder.cubic_bezier_to(c1, c2, to),
+            VectorPathEvent::ArcTo { center, radii, start_angle, sweep_angle } => {
+                // angles are in degrees, and a line leads to the start of the arc; each cubic spans at most a quarter turn.
+                let (start_angle, sweep_angle) =
+                    (start_angle.to_radians(), sweep_angle.to_radians());
+                let point =
+                    |angle: f32| center + Vector::new(radii.x * angle.cos(), radii.y * angle.sin());
+                let tangent =
+                    |angle: f32| Vector::new(-radii.x * angle.sin(), radii.y * angle.cos());
+                builder.line_to(point(start_angle));
+
+                let count = ((sweep_angle.abs() / std::f32::consts::FRAC_PI_2).ceil() as usize)
+                    .clamp(1, 16);
+                let step = sweep_angle / count as f32;
+                let k = 4.0 / 3.0 * (step / 4.0).tan();
+                for i in 0..count {
+                    let (from, to) =
+                        (start_angle + step * i as f32, start_angle + step * (i + 1) as f32);
+                    builder.cubic_bezier_to(
+                        point(from) + tangent(from) * k,
+                        point(to) - tangent(to) * k,
+                        point(to),
+                    );
+                }
+            }
+        }
+
+        current = Some(match *event {
+            VectorPathEvent::MoveTo { to }
+            | VectorPathEvent::LineTo { to }
+            | VectorPathEvent::QuadTo { to, .. }
+            | VectorPathEvent::ConicTo { to, .. }
+            | VectorPathEvent::CubicTo { to, .. } => to,
+            VectorPathEvent::ArcTo { center, radii, start_angle, sweep_angle } => {
+                let angle = (start_angle + sweep_angle).to_radians();
+                center + Vector::new(radii.x * angle.cos(), radii.y * angle.sin())
+            }
+        });
+    }
+
+    if is_closed && current.is_some() {
+        builder.close();
+    }
+    builder.build()
+}
+
+/// Converts a lyon path to a path, and whether its last contour is closed.
+///
+/// Contours other than the last which are closed are closed with a line back to their start.
+pub fn from_lyon_path(path: &lyon_path::Path) -> (VectorPath, bool) {
+    let mut builder = VectorPathBuilder::new();
+    let mut is_closed = false;
+
+    let mut events = path.iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            lyon_path::PathEvent::MoveTo(to) => builder.move_to(to),
+            lyon_path::PathEvent::Line(segment) => builder.line_to(segment.to),
+            lyon_path::PathEvent::Quadratic(segment) => builder.quad_to(segment.ctrl, segment.to),
+            lyon_path::PathEvent::Cubic(segment) => {
+                builder.cubic_to(segment.ctrl1, segment.ctrl2, segment.to)
+            }
+            lyon_path::PathEvent::Close(segment) => {
+                is_closed = events.peek().is_none();
+                if !is_closed && segment.from != segment.to {
+                    builder.line_to(segment.to);
+                }
+            }
+        }
+    }
+
+    (builder.build(), is_closed)
+}
+
+/// Largest factor by which a transformation scales lengths.
+pub fn transform_scale(transform: &Transform) -> f32 {
+    let x = (transform.m11 * transform.m11 + transform.m12 * transform.m12).sqrt();
+    let y = (transform.m21 * transform.m21 + transform.m22 * transform.m22).sqrt();
+    x.max(y).max(1e-6)
+}
+
+/// Number of line segments needed for a curve with the given deviation from a straight line.
+fn segment_count(deviation: f32, tolerance: f32) -> usize {
+    ((deviation / tolerance).sqrt().ceil() as usize).clamp(1, 100)
+}
+
+/// Flattens a path into polylines, each tagged with whether it's closed.
+///
+/// As in the Skia display, only the last contour is closed (if `is_closed`).
+/// Curves are approximated to within `tolerance`, in the units of the path.
+pub fn flatten(path: &VectorPath, is_closed: bool, tolerance: f32) -> Vec<(Vec<Point>, bool)> {
+    let mut contours = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+
+    for event in path {
+        // a contour without a starting point starts at the origin.
+        if current.is_empty() {
+            current.push(Point::zero());
+        }
+        let start = current[current.len() - 1].to_vector();
+
+        match *event {
+            VectorPathEvent::MoveTo { to } => {
+                if current.len() > 1 {
+                    contours.push((std::mem::take(&mut current), false));
+                } else {
+                    current.clear();
+                }
+                current.push(to);
+            }
+            VectorPathEvent::LineTo { to } => current.push(to),
+            VectorPathEvent::QuadTo { control, to } => {
+                let (control, end) = (control.to_vector(), to.to_vector());
+                let count = segment_count((start - control * 2.0 + end).length() / 8.0, tolerance);
+                for i in 1..=count {
+                    let t = i as f32 / count as f32;
+                    let u = 1.0 - t;
+                    current.push((start * u * u + control * 2.0 * u * t + end * t * t).to_point());
+                }
+            }
+            VectorPathEvent::ConicTo { control, to, weight } => {
+                let (control, end) = (control.to_vector(), to.to_vector());
+                let count = segment_count((start - control * 2.0 + end).length() / 8.0, tolerance);
+                for i in 1..=count {
+                    let t = i as f32 / count as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c) = (u * u, 2.0 * weight * u * t, t * t);
+                    current.push(((start * a + control * b + end * c) / (a + b + c)).to_point());
+                }
+            }
+            VectorPathEvent::CubicTo { c1, c2, to } => {
+                let (c1, c2, end) = (c1.to_vector(), c2.to_vector(), to.to_vector());
+                let deviation =
+                    (start - c1 * 2.0 + c2).length().max((c1 - c2 * 2.0 + end).length());
+                let count = segment_count(deviation * 0.75, tolerance);
+                for i in 1..=count {
+                    let t = i as f32 / count as f32;
+                    let u = 1.0 - t;
+                    current.push(
+                        (start * (u * u * u)
+                            + c1 * (3.0 * u * u * t)
+                            + c2 * (3.0 * u * t * t)
+                            + end * (t * t * t))
+                            .to_point(),
+                    );
+                }
+            }
+            VectorPathEvent::ArcTo { center, radii, start_angle, sweep_angle } => {
+                // angles are in degrees, and a line leads to the start of the arc.
+                let (start_angle, sweep_angle) =
+                    (start_angle.to_radians(), sweep_angle.to_radians());
+                let radius = radii.x.max(radii.y).max(tolerance);
+                let step = 2.0 * (1.0 - tolerance / radius).max(-1.0).acos();
+                let count = ((sweep_angle.abs() / step).ceil() as usize).clamp(1, 100);
+                for i in 0..=count {
+                    let angle = start_angle + sweep_angle * i as f32 / count as f32;
+                    current
+                        .push(center + Vector::new(radii.x * angle.cos(), radii.y * angle.sin()));
+                }
+            }
+        }
+    }
+
+    if current.len() > 1 {
+        contours.push((current, is_closed));
+    }
+    contours
+}
+
+fn perpendicular(vector: Vector) -> Vector {
+    Vector::new(-vector.y, vector.x)
+}
+
+fn circle(center: Point, radius: f32, tolerance: f32) -> Vec<Point> {
+    let step = 2.0 * (1.0 - tolerance / radius.max(tolerance)).max(-1.0).acos();
+    let count = ((std::f32::consts::PI * 2.0 / step).ceil() as usize).clamp(8, 256);
+    (0..count)
+        .map(|i| {
+            let angle = i as f32 / count as f32 * std::f32::consts::PI * 2.0;
+            center + Vector::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+fn signed_area(polygon: &[Point]) -> f32 {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        / 2.0
+}
+
+/// Outlines a stroke of flattened contours as convex polygons (segments, joins and caps),
+/// which are wound the same way so that they fill as one with the non-zero rule.
+///
+/// `width` is used rather than the thickness of the stroke, so that hairlines can be given a width first.
+pub fn stroke_polygons(
+    contours: &[(Vec<Point>, bool)],
+    stroke: &GraphicsDisplayStroke,
+    width: f32,
+    tolerance: f32,
+) -> Vec<Vec<Point>> {
+    let half = width / 2.0;
+    let mut polygons = Vec::new();
+
+    for (points, closed) in contours {
+        let mut points = points.clone();
+        points.dedup_by(|a, b| (*a - *b).length() < 1e-4);
+        if *closed && points.len() > 1 && (points[0] - points[points.len() - 1]).length() < 1e-4 {
+            points.pop();
+        }
+
+        if points.len() == 1 {
+            // a contour without length is only visible through its caps.
+            let point = points[0];
+            match stroke.cap {
+                LineCap::Flat => {}
+                LineCap::Square => polygons.push(vec![
+                    point + Vector::new(-half, -half),
+                    point + Vector::new(half, -half),
+                    point + Vector::new(half, half),
+                    point + Vector::new(-half, half),
+                ]),
+                LineCap::Round => polygons.push(circle(point, half, tolerance)),
+            }
+            continue;
+        }
+
+        let count = points.len();
+        let direction = |i: usize| (points[(i + 1) % count] - points[i]).normalize();
+        let segments = if *closed { count } else { count - 1 };
+        for i in 0..segments {
+            let (a, b) = (points[i], points[(i + 1) % count]);
+            let normal = perpendicular(direction(i)) * half;
+            polygons.push(vec![a + normal, b + normal, b - normal, a - normal]);
+        }
+
+        let joins = if *closed { 0..count } else { 1..count - 1 };
+        for i in joins {
+            let (incoming, outgoing) = (direction((i + count - 1) % count), direction(i));
+            add_join(&mut polygons, points[i], incoming, outgoing, stroke, half, tolerance);
+        }
+
+        if !*closed {
+            add_cap(&mut polygons, points[0], -direction(0), stroke.cap, half, tolerance);
+            add_cap(
+                &mut polygons,
+                points[count - 1],
+                direction(count - 2),
+                stroke.cap,
+                half,
+                tolerance,
+            );
+        }
+    }
+
+    for polygon in &mut polygons {
+        if signed_area(polygon) < 0.0 {
+            polygon.reverse();
+        }
+    }
+    polygons
+}
+
+fn add_join(
+    polygons: &mut Vec<Vec<Point>>,
+    point: Point,
+    incoming: Vector,
+    outgoing: Vector,
+    stroke: &GraphicsDisplayStroke,
+    half: f32,
+    tolerance: f32,
+) {
+    let (cross, dot) = (incoming.cross(outgoing), incoming.dot(outgoing));
+    if cross.abs() < 1e-6 && dot > 0.0 {
+        return;
+    }
+
+    // the outside of the corner is opposite to the direction the contour turns in.
+    let side = if cross > 0.0 { -half } else { half };
+    let (a, b) = (point + perpendicular(incoming) * side, point + perpendicular(outgoing) * side);
+    match stroke.join {
+        LineJoin::Round => polygons.push(circle(point, half, tolerance)),
+        LineJoin::Miter => {
+            // ratio of the miter length to the stroke width; infinite if the contour turns back on itself.
+            let ratio = 1.0 / ((1.0 + dot) / 2.0).sqrt();
+            if ratio <= stroke.miter_limit {
+                let miter = point + ((a - point) + (b - point)).normalize() * half * ratio;
+                polygons.push(vec![point, a, miter, b]);
+            } else {
+                polygons.push(vec![point, a, b]);
+            }
+        }
+        LineJoin::Bevel => polygons.push(vec![point, a, b]),
+    }
+}
+
+fn add_cap(
+    polygons: &mut Vec<Vec<Point>>,
+    point: Point,
+    outwards: Vector,
+    cap: LineCap,
+    half: f32,
+    tolerance: f32,
+) {
+    match cap {
+        LineCap::Flat => {}
+        LineCap::Square => {
+            let (normal, extension) = (perpendicular(outwards) * half, outwards * half);
+            polygons.push(vec![
+                point + normal,
+                point + normal + extension,
+                point - normal + extension,
+                point - normal,
+            ]);
+        }
+        LineCap::Round => polygons.push(circle(point, half, tolerance)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(mesh: &Mesh) -> f32 {
+        mesh.indices
+            .chunks(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+                (b - a).cross(c - a).abs() / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_fill_mesh() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(10.0, 0.0));
+        builder.line_to(Point::new(10.0, 5.0));
+        builder.line_to(Point::new(0.0, 5.0));
+        let mesh = fill_mesh(&builder.build(), 0.1);
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.triangles(), 2);
+        assert!((area(&mesh) - 50.0).abs() < 1e-3);
+
+        let scaled = mesh.transformed(&Transform::create_scale(2.0, 2.0));
+        assert!((area(&scaled) - 200.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_lyon_path() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(10.0, 0.0));
+        builder.quad_to(Point::new(15.0, 5.0), Point::new(10.0, 10.0));
+        builder.cubic_to(Point::new(8.0, 12.0), Point::new(2.0, 12.0), Point::new(0.0, 10.0));
+        let path = builder.build();
+
+        let lyon = to_lyon_path(&path, true);
+        assert_eq!(from_lyon_path(&lyon), (path.clone(), true));
+        assert_eq!(from_lyon_path(&to_lyon_path(&path, false)), (path, false));
+
+        // a conic with a weight of 1 is a quadratic curve.
+        let mut builder = VectorPathBuilder::new();
+        builder.conic_to(Point::new(5.0, 10.0), Point::new(10.0, 0.0), 1.0);
+        let (conic, _) = from_lyon_path(&to_lyon_path(&builder.build(), false));
+        match conic[..] {
+            [VectorPathEvent::MoveTo { .. }, VectorPathEvent::CubicTo { c1, c2, to }] => {
+                assert!((c1 - Point::new(10.0 / 3.0, 20.0 / 3.0)).length() < 1e-4);
+                assert!((c2 - Point::new(20.0 / 3.0, 20.0 / 3.0)).length() < 1e-4);
+                assert_eq!(to, Point::new(10.0, 0.0));
+            }
+            _ => panic!("expected a cubic curve"),
+        }
+
+        // arcs are approximated by cubics, which stay on the circle.
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(10.0, 0.0));
+        builder.arc_to(Point::zero(), Vector::new(10.0, 10.0), 0.0, 270.0);
+        let (arc, _) = from_lyon_path(&to_lyon_path(&builder.build(), false));
+        assert_eq!(arc.len(), 5);
+        for (points, _) in flatten(&arc, false, 0.01) {
+            assert!(points.iter().all(|point| (point.to_vector().length() - 10.0).abs() < 0.01));
+        }
+        assert!(
+            matches!(arc[4], VectorPathEvent::CubicTo { to, .. } if (to - Point::new(0.0, -10.0)).length() < 1e-3)
+        );
+    }
+
+    #[test]
+    fn test_tessellator() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(10.0, 0.0));
+        builder.line_to(Point::new(0.0, 10.0));
+        let path = builder.build();
+
+        let mesh = FanTessellator.fill(&path, 0.1);
+        assert_eq!(mesh, fill_mesh(&path, 0.1));
+        assert!(mesh.stencil);
+
+        let triangles = Mesh::from_triangles(mesh.vertices.clone(), vec![0u16, 1, 2]);
+        assert_eq!(triangles.indices, mesh.indices);
+        assert!(!triangles.stencil);
+    }
+
+    #[test]
+    fn test_stroke_mesh() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.line_to(Point::new(10.0, 0.0));
+        let path = builder.build();
+
+        let flat = GraphicsDisplayStroke { cap: LineCap::Flat, ..Default::default() };
+        let mesh = stroke_mesh(&path, false, &flat, 2.0, 0.1);
+        assert_eq!(mesh.triangles(), 2);
+        assert!((area(&mesh) - 20.0).abs() < 1e-3);
+
+        // square caps extend the stroke by half its width at each end.
+        let square = GraphicsDisplayStroke { cap: LineCap::Square, ..Default::default() };
+        assert!((area(&stroke_mesh(&path, false, &square, 2.0, 0.1)) - 24.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_flatten_curves() {
+        let mut builder = VectorPathBuilder::new();
+        builder.move_to(Point::new(0.0, 0.0));
+        builder.quad_to(Point::new(50.0, 100.0), Point::new(100.0, 0.0));
+        let contours = flatten(&builder.build(), false, 0.1);
+
+        assert_eq!(contours.len(), 1);
+        let (ref points, closed) = contours[0];
+        assert!(!closed);
+        assert!(points.len() > 10);
+        assert_eq!(points[points.len() - 1], Point::new(100.0, 0.0));
+        // the apex of the curve is half way to the control point.
+        let top = points.iter().map(|point| point.y).fold(0.0, f32::max);
+        assert!((top - 50.0).abs() < 0.2);
+    }
+}
@@ -0,0 +1,630 @@
+//! Layout of paragraphs; line breaking and alignment.
+//!
+//! A [`TextLayout`](struct.TextLayout.html) is measured once and can be drawn any number of times (at any position and in any color)
+//! through [`items`](struct.TextLayout.html#method.items) or [`DisplayListBuilder::push_text_layout`](../struct.DisplayListBuilder.html#method.push_text_layout).
+//!
+//! Selected text is highlighted by the rectangles of [`selection_rects`](struct.TextLayout.html#method.selection_rects), or drawn along with
+//! its highlight by [`DisplayListBuilder::push_selected_text_layout`](../struct.DisplayListBuilder.html#method.push_selected_text_layout).
+
+use super::*;
+
+/// Horizontal alignment of the lines of a paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces of every line (except the last of each paragraph) such that it fills the width.
+    Justify,
+}
+
+/// A single line of a [`TextLayout`](struct.TextLayout.html).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// Glyphs of the line (by their IDs within the font), with the spacing of the alignment applied.
+    pub glyphs: Vec<ShapedGlyph>,
+    /// Byte offset in the text of the character each glyph was laid out from, in the (visual) order of the glyphs.
+    pub clusters: Vec<usize>,
+    /// Bytes of the text which the line covers, including trailing whitespace and line breaks.
+    pub range: std::ops::Range<usize>,
+    /// Start of the baseline, relative to the top-left of the layout.
+    pub origin: Point,
+    /// Width of the line, excluding trailing whitespace.
+    pub width: f32,
+    // the extra advance given to each glyph by justification, so that it can be undone.
+    justification: Vec<f32>,
+}
+
+impl TextLine {
+    /// Returns the horizontal extents (relative to the layout) of the glyphs laid out from the characters within `range` (bytes of the text),
+    /// merged into runs of adjacent glyphs, from left to right.
+    ///
+    /// Glyphs are matched by the [character](struct.TextLine.html#structfield.clusters) they were laid out from rather than assumed to be in the order of the text,
+    /// since a range of mixed-direction (bidi) text may be split into several runs once laid out.
+    pub fn selection_runs(&self, range: std::ops::Range<usize>) -> Vec<std::ops::Range<f32>> {
+        let mut runs: Vec<std::ops::Range<f32>> = Vec::new();
+        let mut x = self.origin.x;
+        let mut previous_selected = false;
+        for (glyph, cluster) in self.glyphs.iter().zip(&self.clusters) {
+            let selected = range.contains(cluster);
+            if selected {
+                match runs.last_mut() {
+                    Some(run) if previous_selected => run.end = x + glyph.advance.x,
+                    _ => runs.push(x..x + glyph.advance.x),
+                }
+            }
+            previous_selected = selected;
+            x += glyph.advance.x;
+        }
+        runs
+    }
+
+    // puts the glyphs (laid out in the order of the text) in visual order, reversing the right-to-left runs.
+    fn reorder(&mut self, bidi: &unicode_bidi::BidiInfo<'_>) {
+        let paragraph = match bidi.paragraphs.iter().find(|paragraph| {
+            paragraph.range.start <= self.range.start && self.range.end <= paragraph.range.end
+        }) {
+            Some(paragraph) if !self.range.is_empty() => paragraph,
+            _ => return,
+        };
+
+        let (levels, runs) = bidi.visual_runs(paragraph, self.range.clone());
+        let mut order = Vec::with_capacity(self.glyphs.len());
+        for run in runs {
+            // the glyphs are still in the order of the text, so each run is a contiguous slice of them.
+            let start = self.clusters.partition_point(|&cluster| cluster < run.start);
+            let end = self.clusters.partition_point(|&cluster| cluster < run.end);
+            if levels[run.start].is_rtl() {
+                order.extend((start..end).rev());
+            } else {
+                order.extend(start..end);
+            }
+        }
+
+        self.glyphs = order.iter().map(|&i| self.glyphs[i]).collect();
+        self.clusters = order.iter().map(|&i| self.clusters[i]).collect();
+    }
+}
+
+fn is_bidi_control(character: char) -> bool {
+    matches!(character, '\u{61c}' | '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Appearance of selected text, drawn by [`DisplayListBuilder::push_selected_text_layout`](../struct.DisplayListBuilder.html#method.push_selected_text_layout).
+#[derive(Debug, Clone)]
+pub struct SelectionStyle {
+    /// Fill behind the selected glyphs.
+    pub highlight: StyleColor,
+    /// Color of the selected glyphs, if it differs from the color of the rest of the text.
+    pub color: Option<StyleColor>,
+}
+
+/// Text broken into lines, within an optional maximum width.
+///
+/// Glyphs are positioned by the advances of the font (without kerning or shaping), as with [`DisplayText::Simple`](../enum.DisplayText.html#variant.Simple).
+/// Words which are wider than the maximum width are left to overflow it.
+///
+/// Mixed-direction text is reordered within each line by the Unicode bidirectional algorithm, such that right-to-left runs (e.g. of Hebrew) read correctly.
+/// Paragraphs are always laid out left to right though (as if their base direction were left-to-right), and mirrored characters (such as brackets) aren't mirrored.
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    font: ResourceReference,
+    font_info: FontInfo,
+    size: f32,
+    max_width: Option<f32>,
+    align: TextAlign,
+    line_height: f32,
+    ascent: f32,
+    lines: Vec<TextLine>,
+    // whether each line ends a paragraph (with a hard break or the end of the text), so isn't justified.
+    paragraph_ends: Vec<bool>,
+}
+
+impl TextLayout {
+    /// Breaks `text` into lines no wider than `max_width` (if any), with the Unicode line breaking algorithm.
+    ///
+    /// The lines are left-aligned; see [`with_align`](struct.TextLayout.html#method.with_align).
+    pub fn new(
+        text: &str,
+        font: ResourceReference,
+        font_info: FontInfo,
+        size: f32,
+        max_width: Option<f32>,
+    ) -> Result<Self, error::FontError> {
+        let scale = size / font_info.font.metrics().units_per_em as f32;
+        let glyph_font = font_info.font.clone();
+        Self::lay_out(text, font, font_info, size, max_width, |_, character| {
+            // explicit directional formatting characters only affect the order of the others.
+            if character == '\n' || character == '\r' || is_bidi_control(character) {
+                return Ok(None);
+            }
+            let id =
+                glyph_font.glyph_for_char(character).ok_or(error::FontError::CodepointError)?;
+            Ok(Some(ShapedGlyph {
+                codepoint: id,
+                advance: Vector::new(glyph_font.advance(id)?.x * scale, 0.0),
+                offset: Vector::zero(),
+            }))
+        })
+    }
+
+    /// Breaks the text of `item` into lines no wider than `max_width` (if any), in the font and size of the item.
+    ///
+    /// Shaped text keeps the advances of its glyphs, and is broken after the glyphs of spaces and line breaks in the font;
+    /// the [`range`](struct.TextLine.html#structfield.range) and [`clusters`](struct.TextLine.html#structfield.clusters) of its lines are indices of glyphs rather than bytes.
+    pub fn from_item(
+        item: &TextDisplayItem,
+        max_width: Option<f32>,
+    ) -> Result<Self, error::FontError> {
+        match &item.text {
+            DisplayText::Simple(text) => {
+                Self::new(text, item.font, item.font_info.clone(), item.size, max_width)
+            }
+            DisplayText::Shaped(glyphs) => {
+                // a stand-in character for each glyph, such that byte offsets in the text are glyph indices.
+                let font = &item.font_info.font;
+                let (space, newline) = (font.glyph_for_char(' '), font.glyph_for_char('\n'));
+                let text: String = glyphs
+                    .iter()
+                    .map(|glyph| match Some(glyph.codepoint) {
+                        id if id == newline => '\n',
+                        id if id == space => ' ',
+                        _ => 'x',
+                    })
+                    .collect();
+                Self::lay_out(
+                    &text,
+                    item.font,
+                    item.font_info.clone(),
+                    item.size,
+                    max_width,
+                    |index, character| {
+                        Ok(if character == '\n' { None } else { Some(glyphs[index]) })
+                    },
+                )
+            }
+        }
+    }
+
+    // lays out the glyphs given by `glyph` for each character of `text` (and its byte offset), which returns `None` for characters without a glyph.
+    fn lay_out(
+        text: &str,
+        font: ResourceReference,
+        font_info: FontInfo,
+        size: f32,
+        max_width: Option<f32>,
+        mut glyph: impl FnMut(usize, char) -> Result<Option<ShapedGlyph>, error::FontError>,
+    ) -> Result<Self, error::FontError> {
+        let metrics = font_info.font.metrics();
+        let scale = size / metrics.units_per_em as f32;
+        // the same line height as the bounds of a text item.
+        let font_height = metrics.ascent - metrics.descent;
+        let line_height = if font_height > metrics.units_per_em as f32 {
+            font_height
+        } else {
+            font_height + metrics.line_gap
+        } * scale;
+
+        let mut layout = TextLayout {
+            font,
+            font_info: font_info.clone(),
+            size,
+            max_width,
+            align: TextAlign::Left,
+            line_height,
+            ascent: metrics.ascent * scale,
+            lines: Vec::new(),
+            paragraph_ends: Vec::new(),
+        };
+
+        // the glyphs of the current line, the start of it, and its width up to the last break opportunity (without trailing whitespace).
+        let mut line = TextLine {
+            glyphs: Vec::new(),
+            clusters: Vec::new(),
+            range: 0..0,
+            origin: Point::zero(),
+            width: 0.0,
+            justification: Vec::new(),
+        };
+        let mut pen = 0.0;
+        let mut start = 0;
+        for (end, hard) in xi_unicode::LineBreakIterator::new(text) {
+            let segment = &text[start..end];
+            let mut glyphs = Vec::with_capacity(segment.len());
+            let mut clusters = Vec::with_capacity(segment.len());
+            let (mut advance, mut width) = (0.0, 0.0);
+            for (index, character) in segment.char_indices() {
+                let laid_out = match glyph(start + index, character)? {
+                    Some(laid_out) => laid_out,
+                    None => continue,
+                };
+                glyphs.push(laid_out);
+                clusters.push(start + index);
+                advance += laid_out.advance.x;
+                if !character.is_whitespace() {
+                    width = advance;
+                }
+            }
+
+            // the segment moves to the next line if it doesn't fit (and isn't the only one on the line).
+            let overflows = max_width.is_some_and(|max_width| pen + width > max_width);
+            if overflows && !line.glyphs.is_empty() {
+                line.range.end = start;
+                layout.lines.push(std::mem::replace(
+                    &mut line,
+                    TextLine {
+                        glyphs: Vec::new(),
+                        clusters: Vec::new(),
+                        range: start..start,
+                        origin: Point::zero(),
+                        width: 0.0,
+                        justification: Vec::new(),
+                    },
+                ));
+                layout.paragraph_ends.push(false);
+                pen = 0.0;
+            }
+
+            line.glyphs.extend(glyphs);
+            line.clusters.extend(clusters);
+            if width > 0.0 {
+                line.width = pen + width;
+            }
+            pen += advance;
+            start = end;
+
+            if hard {
+                line.range.end = end;
+                layout.lines.push(std::mem::replace(
+                    &mut line,
+                    TextLine {
+                        glyphs: Vec::new(),
+                        clusters: Vec::new(),
+                        range: end..end,
+                        origin: Point::zero(),
+                        width: 0.0,
+                        justification: Vec::new(),
+                    },
+                ));
+                layout.paragraph_ends.push(true);
+                pen = 0.0;
+            }
+        }
+
+        // the iterator ends with a hard break at the end of the text, unless the text ends with a line break.
+        if layout.lines.is_empty() || text.ends_with('\n') {
+            line.range = text.len()..text.len();
+            layout.lines.push(line);
+            layout.paragraph_ends.push(true);
+        }
+
+        let bidi = unicode_bidi::BidiInfo::new(text, Some(unicode_bidi::Level::ltr()));
+        if bidi.has_rtl() {
+            for line in &mut layout.lines {
+                line.reorder(&bidi);
+            }
+        }
+
+        layout.position_lines();
+        Ok(layout)
+    }
+
+    /// Returns the layout with its lines aligned by `align`.
+    ///
+    /// Without a maximum width, lines are aligned within the widest line.
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self.position_lines();
+        self
+    }
+
+    fn position_lines(&mut self) {
+        let width = self
+            .max_width
+            .unwrap_or_else(|| self.lines.iter().map(|line| line.width).fold(0.0, f32::max));
+        let font = &self.font_info.font;
+        let space = font.glyph_for_char(' ');
+
+        for (i, (line, &paragraph_end)) in
+            self.lines.iter_mut().zip(&self.paragraph_ends).enumerate()
+        {
+            // any previous justification is undone first.
+            for (glyph, extra) in line.glyphs.iter_mut().zip(line.justification.drain(..)) {
+                glyph.advance.x -= extra;
+                line.width -= extra;
+            }
+
+            let remaining = (width - line.width).max(0.0);
+            let x = match self.align {
+                TextAlign::Left | TextAlign::Justify => 0.0,
+                TextAlign::Center => remaining / 2.0,
+                TextAlign::Right => remaining,
+            };
+            line.origin = Point::new(x, self.ascent + i as f32 * self.line_height);
+
+            if self.align == TextAlign::Justify && !paragraph_end {
+                // trailing spaces aren't stretched, as they're beyond the width of the line.
+                let mut advance = 0.0;
+                let inner = line
+                    .glyphs
+                    .iter()
+                    .take_while(|glyph| {
+                        advance += glyph.advance.x;
+                        advance <= line.width + 1e-3
+                    })
+                    .count();
+                let spaces = line.glyphs[..inner]
+                    .iter()
+                    .filter(|glyph| Some(glyph.codepoint) == space)
+                    .count();
+                if spaces > 0 {
+                    let extra = remaining / spaces as f32;
+                    line.justification = line
+                        .glyphs
+                        .iter_mut()
+                        .enumerate()
+                        .map(|(i, glyph)| {
+                            if i < inner && Some(glyph.codepoint) == space {
+                                glyph.advance.x += extra;
+                                extra
+                            } else {
+                                0.0
+                            }
+                        })
+                        .collect();
+                    line.width += remaining;
+                }
+            }
+        }
+    }
+
+    /// Returns the lines, from the top.
+    #[inline]
+    pub fn lines(&self) -> &[TextLine] {
+        &self.lines
+    }
+
+    /// Returns the distance between the baselines of consecutive lines.
+    #[inline]
+    pub fn line_height(&self) -> f32 {
+        self.line_height
+    }
+
+    #[inline]
+    pub fn align(&self) -> TextAlign {
+        self.align
+    }
+
+    #[inline]
+    pub fn max_width(&self) -> Option<f32> {
+        self.max_width
+    }
+
+    /// Returns the bounds of the lines, relative to the top-left of the layout.
+    ///
+    /// As with [`TextDisplayItem::bounds`](../struct.TextDisplayItem.html#method.bounds), each line is as tall as the tallest glyph of the font.
+    pub fn bounds(&self) -> Rect {
+        let (min_x, max_x) = self
+            .lines
+            .iter()
+            .filter(|line| line.width > 0.0)
+            .fold(None, |bounds: Option<(f32, f32)>, line| {
+                let (min, max) = (line.origin.x, line.origin.x + line.width);
+                Some(bounds.map_or((min, max), |(a, b)| (a.min(min), b.max(max))))
+            })
+            .unwrap_or((0.0, 0.0));
+        Rect::new(
+            Point::new(min_x, 0.0),
+            Size::new(max_x - min_x, self.lines.len() as f32 * self.line_height),
+        )
+    }
+
+    /// Returns the rectangles covering the glyphs laid out from the characters within `range` (bytes of the text), relative to the top-left of the layout.
+    ///
+    /// Each rectangle spans the height of its line (such that the selection of consecutive lines is contiguous),
+    /// and the rectangles are ordered from the top line down, and from left to right within a line.
+    /// A line may have several rectangles, as described in [`TextLine::selection_runs`](struct.TextLine.html#method.selection_runs).
+    pub fn selection_rects(&self, range: std::ops::Range<usize>) -> Vec<Rect> {
+        self.lines
+            .iter()
+            .filter(|line| line.range.start < range.end && range.start < line.range.end)
+            .flat_map(|line| {
+                let top = line.origin.y - self.ascent;
+                line.selection_runs(range.clone()).into_iter().map(move |run| {
+                    Rect::new(
+                        Point::new(run.start, top),
+                        Size::new(run.end - run.start, self.line_height),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a text item for each (non-empty) line, with the top-left of the layout at `top_left`.
+    pub fn items(&self, top_left: Point, color: StyleColor) -> Vec<TextDisplayItem> {
+        self.lines
+            .iter()
+            .filter(|line| !line.glyphs.is_empty())
+            .map(|line| TextDisplayItem {
+                text: DisplayText::Shaped(line.glyphs.clone()),
+                font: self.font,
+                font_info: self.font_info.clone(),
+                size: self.size,
+                bottom_left: top_left + line.origin.to_vector(),
+                color: color.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(text: &str, max_width: Option<f32>) -> TextLayout {
+        let font_info = FontInfo::from_data(
+            Arc::new(include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec()),
+            0,
+        )
+        .unwrap();
+        TextLayout::new(text, ResourceReference::Font(0), font_info, 16.0, max_width).unwrap()
+    }
+
+    #[test]
+    fn test_line_breaking() {
+        let single = layout("lorem ipsum dolor sit amet", None);
+        assert_eq!(single.lines().len(), 1);
+        assert_eq!(single.lines()[0].range, 0..26);
+
+        let wrapped = layout("lorem ipsum dolor sit amet", Some(single.bounds().size.width / 2.0));
+        assert!(wrapped.lines().len() > 1);
+        assert!(wrapped.lines().iter().all(|line| line.width <= single.bounds().size.width / 2.0));
+        // every byte is in exactly one line.
+        assert_eq!(wrapped.lines()[0].range.start, 0);
+        for pair in wrapped.lines().windows(2) {
+            assert_eq!(pair[0].range.end, pair[1].range.start);
+            assert_eq!(pair[1].origin.y - pair[0].origin.y, wrapped.line_height());
+        }
+        assert_eq!(wrapped.lines().last().unwrap().range.end, 26);
+
+        // hard breaks (and a trailing one) start new lines; a word which doesn't fit overflows.
+        let paragraphs = layout("lorem\nipsum\n", None);
+        assert_eq!(paragraphs.lines().len(), 3);
+        assert_eq!(
+            paragraphs
+                .items(Point::zero(), StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)))
+                .len(),
+            2
+        );
+        assert_eq!(paragraphs.bounds().size.height, paragraphs.line_height() * 3.0);
+        assert_eq!(layout("lorem", Some(1.0)).lines().len(), 1);
+    }
+
+    #[test]
+    fn test_alignment() {
+        let text = "lorem ipsum dolor sit amet";
+        let width = layout(text, None).bounds().size.width * 0.75;
+        let left = layout(text, Some(width));
+        let lines = left.lines().len();
+        assert!(lines > 1);
+
+        let right = layout(text, Some(width)).with_align(TextAlign::Right);
+        let center = layout(text, Some(width)).with_align(TextAlign::Center);
+        for ((left, right), center) in left.lines().iter().zip(right.lines()).zip(center.lines()) {
+            assert!((right.origin.x + right.width - width).abs() < 1e-3);
+            assert!((center.origin.x * 2.0 + center.width - width).abs() < 1e-3);
+            assert_eq!(left.origin.x, 0.0);
+        }
+
+        // every line but the last fills the width.
+        let justified = layout(text, Some(width)).with_align(TextAlign::Justify);
+        for line in &justified.lines()[..lines - 1] {
+            assert!((line.width - width).abs() < 1e-3);
+        }
+        assert_eq!(justified.lines()[lines - 1], left.lines()[lines - 1]);
+
+        // realigning undoes the justification.
+        assert_eq!(justified.with_align(TextAlign::Left).lines(), left.lines());
+
+        let items =
+            center.items(Point::new(10.0, 20.0), StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0)));
+        assert_eq!(items.len(), lines);
+        assert_eq!(
+            items[0].bottom_left,
+            Point::new(10.0, 20.0) + center.lines()[0].origin.to_vector()
+        );
+    }
+
+    #[test]
+    fn test_selection_rects() {
+        let text = "lorem ipsum dolor sit amet";
+        let single = layout(text, None);
+        assert_eq!(single.lines()[0].clusters.len(), single.lines()[0].glyphs.len());
+        assert_eq!(single.lines()[0].clusters[6], 6);
+
+        // "ipsum", within a single line.
+        let rects = single.selection_rects(6..11);
+        assert_eq!(rects.len(), 1);
+        let ipsum = single.lines()[0].glyphs[..6].iter().map(|glyph| glyph.advance.x).sum::<f32>();
+        assert!((rects[0].origin.x - ipsum).abs() < 1e-3);
+        assert_eq!(rects[0].origin.y, 0.0);
+        assert_eq!(rects[0].size.height, single.line_height());
+        assert!(single.selection_rects(26..26).is_empty());
+
+        // a selection across wrapped lines has a rectangle per line, stacked without gaps.
+        let wrapped = layout(text, Some(single.bounds().size.width / 2.0));
+        let rects = wrapped.selection_rects(2..24);
+        assert_eq!(rects.len(), wrapped.lines().len());
+        for pair in rects.windows(2) {
+            assert_eq!(pair[0].max_y(), pair[1].min_y());
+        }
+
+        // glyphs laid out in a different order than the text (as with right-to-left runs) split the selection.
+        let mut line = single.lines()[0].clone();
+        line.clusters[2..5].reverse();
+        line.origin.x = 10.0;
+        let runs = line.selection_runs(0..3);
+        assert_eq!(runs.len(), 2);
+        let advance = |i: usize| line.glyphs[i].advance.x;
+        assert!((runs[0].end - (10.0 + advance(0) + advance(1))).abs() < 1e-3);
+        assert!((runs[1].start - runs[0].end - advance(2) - advance(3)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_selected_text_layout() {
+        let text = layout("lorem ipsum", None);
+        let black = StyleColor::Color(Color::new(0.0, 0.0, 0.0, 1.0));
+        let style = SelectionStyle {
+            highlight: StyleColor::Color(Color::new(0.2, 0.4, 0.9, 1.0)),
+            color: Some(StyleColor::Color(Color::new(1.0, 1.0, 1.0, 1.0))),
+        };
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_selected_text_layout(&text, Point::new(5.0, 5.0), black, 0..5, &style, None);
+        let commands = builder.build();
+
+        // the highlight, then the text, then the selected glyphs clipped to the highlight.
+        assert_eq!(commands.len(), 6);
+        match commands[0] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { rect, .. }),
+                _,
+            ) => {
+                assert_eq!(rect.origin, Point::new(5.0, 5.0));
+            }
+            _ => panic!("expected the highlight"),
+        }
+        assert!(matches!(commands[1], DisplayCommand::Item(DisplayItem::Text(_), _)));
+        assert!(matches!(commands[2], DisplayCommand::Save));
+        assert!(matches!(commands[3], DisplayCommand::Clip(_)));
+        assert!(matches!(commands[4], DisplayCommand::Item(DisplayItem::Text(_), _)));
+        assert!(matches!(commands[5], DisplayCommand::Restore));
+    }
+
+    #[test]
+    fn test_bidi_reordering() {
+        // "cde" is overridden to read right to left.
+        let text = "ab \u{202e}cde\u{202c} fg";
+        let mixed = layout(text, None);
+        let line = &mixed.lines()[0];
+        let order: String =
+            line.clusters.iter().map(|&cluster| &text[cluster..cluster + 1]).collect();
+        assert_eq!(order, "ab edc fg");
+        assert_eq!(line.width, layout("ab cde fg", None).lines()[0].width);
+
+        // the selection of "cd" is a single run at the right end of the reversed run, and "bc" is split around the space.
+        let advance = |i: usize| line.glyphs[i].advance.x;
+        let start: f32 = (0..3).map(advance).sum();
+        let runs = line.selection_runs(6..8);
+        assert_eq!(runs.len(), 1);
+        assert!((runs[0].start - (start + advance(3))).abs() < 1e-3);
+        assert!((runs[0].end - (start + advance(3) + advance(4) + advance(5))).abs() < 1e-3);
+        assert_eq!(mixed.selection_rects(1..7).len(), 2);
+
+        // text without right-to-left runs keeps its order.
+        let plain = layout("ab cde", None);
+        let clusters = &plain.lines()[0].clusters;
+        assert!(clusters.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,1822 @@
+//! A display which renders through [wgpu](https://wgpu.rs), and so on Vulkan, Metal, DX12, OpenGL and WebGPU.
+//!
+//! [`WgpuGraphicsDisplay`](struct.WgpuGraphicsDisplay.html) draws [lowered](../lower/index.html) display commands into a texture of its own,
+//! which persists between presents (like the buffer of the [software display](../software/index.html)), so a present with a cull only redraws within it.
+//! The application draws the texture onto its surface, or reads it back:
+//!
+//! ```ignore
+//! let mut display = WgpuGraphicsDisplay::new(device.clone(), queue.clone(), (640, 480));
+//! widget.draw(&mut display, &mut aux);
+//! display.present(None)?;
+//! // e.g. bind `display.texture_view()` to a pipeline which draws a quad covering the window.
+//! ```
+//!
+//! Paths (including the outlines of glyphs) are triangulated by a [`LyonTessellator`](struct.LyonTessellator.html) and antialiased by multisampling.
+//! Clips are drawn into a stencil buffer, and layers and backdrop filters into textures of their own.
+//! Render scales, display filters, debug markers and frame capturing aren't supported, and blurs aren't cached between frames.
+
+use {
+    super::{backend::*, lower::*, tessellation::*, *},
+    ::wgpu::util::DeviceExt,
+    lyon_tessellation as lyon,
+    std::{num::NonZeroU64, ops::Range},
+};
+
+/// Format of the texture drawn into, with premultiplied alpha.
+pub const TEXTURE_FORMAT: ::wgpu::TextureFormat = ::wgpu::TextureFormat::Rgba8Unorm;
+
+/// Maximum distance (in pixels) between curves and the triangles they're drawn as.
+const TOLERANCE: f32 = 0.2;
+/// Samples per pixel, which antialias the edges of meshes.
+const SAMPLE_COUNT: u32 = 4;
+const STENCIL_FORMAT: ::wgpu::TextureFormat = ::wgpu::TextureFormat::Depth24PlusStencil8;
+/// Number of gradient stops the shader takes; gradients with more stops are resampled.
+const MAX_STOPS: usize = 16;
+/// Size of the `Paint` uniform of the shader.
+const PAINT_SIZE: u64 = 496;
+
+// kinds of paints, and their flags (as in the shader).
+const SOLID: u32 = 0;
+const LINEAR_GRADIENT: u32 = 1;
+const RADIAL_GRADIENT: u32 = 2;
+const CHECKERBOARD: u32 = 3;
+const IMAGE: u32 = 4;
+const SURFACE: u32 = 5;
+const ADJUSTED: u32 = 1;
+const INVERTED: u32 = 2;
+
+/// Tessellator which triangulates paths exactly through `lyon_tessellation`, such that meshes don't need a stencil.
+///
+/// lyon 0.14 only fills by the even-odd rule, which differs from the non-zero rule where a path winds around an area more than once
+/// (such as where the contours of a glyph overlap). Unlike [`FanTessellator`](../tessellation/struct.FanTessellator.html), strokes may overlap themselves
+/// where a path crosses itself (or turns sharply), so translucent strokes can be darker there.
+pub struct LyonTessellator {
+    fill: lyon::FillTessellator,
+    stroke: lyon::StrokeTessellator,
+}
+
+impl Default for LyonTessellator {
+    fn default() -> Self {
+        LyonTessellator {
+            fill: lyon::FillTessellator::new(),
+            stroke: lyon::StrokeTessellator::new(),
+        }
+    }
+}
+
+impl LyonTessellator {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Tessellator for LyonTessellator {
+    fn fill(&mut self, path: &VectorPath, tolerance: f32) -> Mesh {
+        let mut buffers: lyon::VertexBuffers<Point, u32> = lyon::VertexBuffers::new();
+        let result = self.fill.tessellate_path(
+            &to_lyon_path(path, true),
+            &lyon::FillOptions::even_odd().with_tolerance(tolerance).with_normals(false),
+            &mut lyon::BuffersBuilder::new(&mut buffers, |vertex: lyon::FillVertex| {
+                vertex.position
+            }),
+        );
+
+        match result {
+            Ok(_) => Mesh::from_triangles(buffers.vertices, buffers.indices),
+            Err(_) => Mesh::default(),
+        }
+    }
+
+    fn stroke(
+        &mut self,
+        path: &VectorPath,
+        is_closed: bool,
+        stroke: &GraphicsDisplayStroke,
+        width: f32,
+        tolerance: f32,
+    ) -> Mesh {
+        let options = lyon::StrokeOptions::tolerance(tolerance)
+            .with_line_width(width)
+            .with_line_cap(match stroke.cap {
+                LineCap::Flat => lyon::LineCap::Butt,
+                LineCap::Square => lyon::LineCap::Square,
+                LineCap::Round => lyon::LineCap::Round,
+            })
+            .with_line_join(match stroke.join {
+                LineJoin::Miter => lyon::LineJoin::Miter,
+                LineJoin::Round => lyon::LineJoin::Round,
+                LineJoin::Bevel => lyon::LineJoin::Bevel,
+            })
+            .with_miter_limit(stroke.miter_limit.max(lyon::StrokeOptions::MINIMUM_MITER_LIMIT));
+
+        let mut buffers: lyon::VertexBuffers<Point, u32> = lyon::VertexBuffers::new();
+        let result = self.stroke.tessellate_path(
+            &to_lyon_path(path, is_closed),
+            &options,
+            &mut lyon::BuffersBuilder::new(&mut buffers, |vertex: lyon::StrokeVertex| {
+                vertex.position
+            }),
+        );
+
+        match result {
+            Ok(_) => Mesh::from_triangles(buffers.vertices, buffers.indices),
+            Err(_) => Mesh::default(),
+        }
+    }
+}
+
+/// A display which draws into a texture through wgpu.
+///
+/// The texture ([`TEXTURE_FORMAT`](constant.TEXTURE_FORMAT.html), with premultiplied alpha) persists between presents,
+/// and starts out transparent (as it does again after resizing).
+pub struct WgpuGraphicsDisplay {
+    device: Arc<::wgpu::Device>,
+    queue: Arc<::wgpu::Queue>,
+    size: (u32, u32),
+    pipelines: Pipelines,
+    // the target, followed by the textures of layers and backdrops (which are reused between presents).
+    surfaces: Vec<Surface>,
+    tessellator: LyonTessellator,
+    resources: ResourceRegistry<WgpuResource>,
+    groups: CommandGroupStore,
+    dithering: Dithering,
+    scale_factor: f64,
+    errors: Vec<error::DisplayError>,
+}
+
+impl WgpuGraphicsDisplay {
+    /// Creates a display drawing into a transparent texture of `size`, through a device and its queue.
+    pub fn new(device: Arc<::wgpu::Device>, queue: Arc<::wgpu::Queue>, size: (u32, u32)) -> Self {
+        let pipelines = Pipelines::new(&device, &queue);
+        let target = Surface::new(&device, &pipelines, size);
+        WgpuGraphicsDisplay {
+            device,
+            queue,
+            size,
+            pipelines,
+            surfaces: vec![target],
+            tessellator: LyonTessellator::new(),
+            resources: ResourceRegistry::new(),
+            groups: CommandGroupStore::default(),
+            dithering: Dithering::None,
+            scale_factor: 1.0,
+            errors: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the texture drawn into, which is replaced when the display is resized.
+    #[inline]
+    pub fn texture(&self) -> &::wgpu::Texture {
+        &self.surfaces[0].resolved
+    }
+
+    /// Returns a view of the whole [texture](struct.WgpuGraphicsDisplay.html#method.texture).
+    #[inline]
+    pub fn texture_view(&self) -> &::wgpu::TextureView {
+        &self.surfaces[0].resolved_view
+    }
+
+    #[inline]
+    pub fn command_groups(&self) -> &CommandGroupStore {
+        &self.groups
+    }
+
+    /// Reads the texture back, returning the pixels row by row from the top as unpremultiplied RGBA
+    /// (see [`RasterImageFormat::Rgba8`](../enum.RasterImageFormat.html#variant.Rgba8)).
+    ///
+    /// This waits for the GPU to finish drawing, so it's meant for tests and screenshots rather than every frame.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let (width, height) = (self.size.0.max(1), self.size.1.max(1));
+        // rows of a copy into a buffer are aligned.
+        let row = width * 4;
+        let padded_row = row.div_ceil(::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * ::wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let buffer = self.device.create_buffer(&::wgpu::BufferDescriptor {
+            label: Some("reclutch readback"),
+            size: padded_row as u64 * height as u64,
+            usage: ::wgpu::BufferUsages::COPY_DST | ::wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            self.texture().as_image_copy(),
+            ::wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: ::wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            extent((width, height)),
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(::wgpu::MapMode::Read, |_| {});
+        self.device.poll(::wgpu::Maintain::Wait);
+
+        let data = slice.get_mapped_range();
+        let mut rgba = Vec::with_capacity(self.size.0 as usize * self.size.1 as usize * 4);
+        for y in 0..self.size.1 as usize {
+            let start = y * padded_row as usize;
+            for pixel in data[start..start + self.size.0 as usize * 4].chunks(4) {
+                let unpremultiply = |channel: u8| match pixel[3] {
+                    0 => 0,
+                    alpha => {
+                        ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8
+                    }
+                };
+                rgba.extend_from_slice(&[
+                    unpremultiply(pixel[0]),
+                    unpremultiply(pixel[1]),
+                    unpremultiply(pixel[2]),
+                    pixel[3],
+                ]);
+            }
+        }
+        rgba
+    }
+
+    fn upload_image(
+        &self,
+        pixels: &[u8],
+        size: (u32, u32),
+    ) -> Result<GpuImage, error::ResourceError> {
+        let max = self.device.limits().max_texture_dimension_2d;
+        if size.0 > max || size.1 > max {
+            return Err(error::ResourceError::Unsupported(format!(
+                "images larger than {} pixels across can't be drawn by this device",
+                max
+            )));
+        }
+
+        // textures can't be empty, so an empty image is a transparent pixel.
+        let (texture_size, pixels) =
+            if size.0 == 0 || size.1 == 0 { ((1, 1), &[0; 4][..]) } else { (size, pixels) };
+        let texture = self.device.create_texture_with_data(
+            &self.queue,
+            &::wgpu::TextureDescriptor {
+                label: Some("reclutch image"),
+                size: extent(texture_size),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: ::wgpu::TextureDimension::D2,
+                format: TEXTURE_FORMAT,
+                usage: ::wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            Default::default(),
+            pixels,
+        );
+        let bind_group = self
+            .pipelines
+            .texture_bind_group(&self.device, &texture.create_view(&Default::default()));
+
+        Ok(GpuImage { size, bind_group, _texture: texture })
+    }
+
+    fn render(&mut self, frame: Frame, scissor: [u32; 4]) {
+        while self.surfaces.len() < frame.surfaces {
+            self.surfaces.push(Surface::new(&self.device, &self.pipelines, self.size));
+        }
+
+        let stride = {
+            let alignment = self.device.limits().min_uniform_buffer_offset_alignment as u64;
+            PAINT_SIZE.div_ceil(alignment) * alignment
+        };
+        let viewport = (self.size.0.max(1) as f32, self.size.1.max(1) as f32);
+        let mut paints = Vec::with_capacity(frame.paints.len() * stride as usize);
+        for paint in &frame.paints {
+            paint.encode(viewport, &mut paints);
+            paints.resize(paints.len() + (stride - PAINT_SIZE) as usize, 0);
+        }
+
+        let mut vertices = Vec::with_capacity(frame.vertices.len() * 8);
+        for vertex in &frame.vertices {
+            vertices.extend_from_slice(&vertex.x.to_ne_bytes());
+            vertices.extend_from_slice(&vertex.y.to_ne_bytes());
+        }
+        let indices: Vec<u8> = frame.indices.iter().flat_map(|index| index.to_ne_bytes()).collect();
+
+        // buffers can't be empty.
+        let buffer = |contents: &[u8], usage| {
+            self.device.create_buffer_init(&::wgpu::util::BufferInitDescriptor {
+                label: Some("reclutch frame"),
+                contents: if contents.is_empty() { &[0; 8] } else { contents },
+                usage,
+            })
+        };
+        let vertex_buffer = buffer(&vertices, ::wgpu::BufferUsages::VERTEX);
+        let index_buffer = buffer(&indices, ::wgpu::BufferUsages::INDEX);
+        let paint_buffer = buffer(&paints, ::wgpu::BufferUsages::UNIFORM);
+        let paint_bind_group = self.device.create_bind_group(&::wgpu::BindGroupDescriptor {
+            label: Some("reclutch paints"),
+            layout: &self.pipelines.paint_layout,
+            entries: &[::wgpu::BindGroupEntry {
+                binding: 0,
+                resource: ::wgpu::BindingResource::Buffer(::wgpu::BufferBinding {
+                    buffer: &paint_buffer,
+                    offset: 0,
+                    size: NonZeroU64::new(PAINT_SIZE),
+                }),
+            }],
+        });
+        let paint_offset = |paint: u32| [(paint as u64 * stride) as u32];
+
+        let mut encoder = self.device.create_command_encoder(&::wgpu::CommandEncoderDescriptor {
+            label: Some("reclutch present"),
+        });
+        for step in &frame.steps {
+            match step {
+                Step::Pass { surface, clear_color, clear_stencil, draws } => {
+                    let surface = &self.surfaces[*surface];
+                    let mut pass = encoder.begin_render_pass(&::wgpu::RenderPassDescriptor {
+                        label: Some("reclutch draws"),
+                        color_attachments: &[Some(::wgpu::RenderPassColorAttachment {
+                            view: &surface.color,
+                            resolve_target: Some(&surface.resolved_view),
+                            ops: ::wgpu::Operations {
+                                load: if *clear_color {
+                                    ::wgpu::LoadOp::Clear(::wgpu::Color::TRANSPARENT)
+                                } else {
+                                    ::wgpu::LoadOp::Load
+                                },
+                                store: ::wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: Some(::wgpu::RenderPassDepthStencilAttachment {
+                            view: &surface.stencil,
+                            depth_ops: None,
+                            stencil_ops: Some(::wgpu::Operations {
+                                load: if *clear_stencil {
+                                    ::wgpu::LoadOp::Clear(0)
+                                } else {
+                                    ::wgpu::LoadOp::Load
+                                },
+                                store: ::wgpu::StoreOp::Store,
+                            }),
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+                    pass.set_scissor_rect(scissor[0], scissor[1], scissor[2], scissor[3]);
+                    pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+                    pass.set_index_buffer(index_buffer.slice(..), ::wgpu::IndexFormat::Uint32);
+                    for draw in draws {
+                        pass.set_pipeline(self.pipelines.get(draw.pipeline));
+                        pass.set_bind_group(0, &paint_bind_group, &paint_offset(draw.paint));
+                        let texture = match draw.binding {
+                            Binding::Blank => &self.pipelines.blank,
+                            Binding::Image(reference) => match self.resources.get(reference) {
+                                Ok(WgpuResource::Image(image)) => &image.bind_group,
+                                _ => &self.pipelines.blank,
+                            },
+                            Binding::Surface(surface) => &self.surfaces[surface].bind_group,
+                        };
+                        pass.set_bind_group(1, texture, &[]);
+                        pass.set_stencil_reference(draw.stencil);
+                        pass.draw_indexed(draw.indices.clone(), 0, 0..1);
+                    }
+                }
+                Step::Copy { from, to } => encoder.copy_texture_to_texture(
+                    self.surfaces[*from].resolved.as_image_copy(),
+                    self.surfaces[*to].resolved.as_image_copy(),
+                    extent(self.size),
+                ),
+                Step::Blur { surface, scratch, paints } => {
+                    for (source, target, paint) in
+                        [(*surface, *scratch, paints[0]), (*scratch, *surface, paints[1])]
+                    {
+                        let mut pass = encoder.begin_render_pass(&::wgpu::RenderPassDescriptor {
+                            label: Some("reclutch blur"),
+                            color_attachments: &[Some(::wgpu::RenderPassColorAttachment {
+                                view: &self.surfaces[target].resolved_view,
+                                resolve_target: None,
+                                ops: ::wgpu::Operations {
+                                    load: ::wgpu::LoadOp::Clear(::wgpu::Color::TRANSPARENT),
+                                    store: ::wgpu::StoreOp::Store,
+                                },
+                            })],
+                            depth_stencil_attachment: None,
+                            timestamp_writes: None,
+                            occlusion_query_set: None,
+                        });
+                        pass.set_pipeline(&self.pipelines.blur);
+                        pass.set_bind_group(0, &paint_bind_group, &paint_offset(paint));
+                        pass.set_bind_group(1, &self.surfaces[source].bind_group, &[]);
+                        pass.draw(0..3, 0..1);
+                    }
+                }
+            }
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+    }
+}
+
+fn font_error(error: error::FontError) -> error::DisplayError {
+    error::DisplayError::InternalError(error.into())
+}
+
+fn extent(size: (u32, u32)) -> ::wgpu::Extent3d {
+    ::wgpu::Extent3d { width: size.0.max(1), height: size.1.max(1), depth_or_array_layers: 1 }
+}
+
+impl GraphicsDisplay for WgpuGraphicsDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = size;
+        self.surfaces = vec![Surface::new(&self.device, &self.pipelines, size)];
+        self.groups.invalidate_all();
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let resource = match descriptor {
+            ResourceDescriptor::Image(ImageData::Raw(ref data, info)) => {
+                let pixels = data.with_bytes(|bytes| raw_pixels(bytes, info))??;
+                WgpuResource::Image(self.upload_image(&pixels, info.size)?)
+            }
+            ResourceDescriptor::Image(ImageData::Encoded(ref data)) => {
+                let (pixels, size) = data.with_bytes(decode_pixels)??;
+                WgpuResource::Image(self.upload_image(&pixels, size)?)
+            }
+            ResourceDescriptor::Font(ref data) => {
+                // glyphs are drawn from the outlines of the font info of text items, so fonts are only checked.
+                let bytes = data.with_bytes(|bytes| bytes.to_vec())?;
+                font_kit::font::Font::from_bytes(Arc::new(bytes), 0)
+                    .map_err(|_| error::ResourceError::InvalidData)?;
+                WgpuResource::Font
+            }
+        };
+        Ok(self.resources.insert(&descriptor, resource))
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.resources.remove(reference);
+    }
+
+    fn image_metadata(&self, reference: ResourceReference) -> Option<metadata::ImageMetadata> {
+        match self.resources.get(reference) {
+            Ok(WgpuResource::Image(image)) => Some(metadata::ImageMetadata::new(image.size)),
+            _ => None,
+        }
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+        Ok(self.groups.push(
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+        )?)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.groups.get(handle).map(|group| &group.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            false,
+        );
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.groups.remove(handle).map(|group| group.commands)
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, false);
+    }
+
+    fn set_command_group_clip(&mut self, handle: CommandGroupHandle, clip: Option<DisplayClip>) {
+        self.groups.set_clip(handle, clip);
+    }
+
+    fn set_command_group_transform(&mut self, handle: CommandGroupHandle, transform: Transform) {
+        self.groups.set_transform(handle, transform);
+    }
+
+    fn set_command_group_layer(&mut self, handle: CommandGroupHandle, layer: DisplayLayer) {
+        self.groups.set_layer(handle, layer);
+    }
+
+    fn set_command_group_scissor(&mut self, handle: CommandGroupHandle, scissor: Option<Rect>) {
+        self.groups.set_scissor(handle, scissor);
+    }
+
+    fn set_command_group_label(&mut self, handle: CommandGroupHandle, label: Option<&str>) {
+        self.groups.set_label(handle, label);
+    }
+
+    fn command_group_label(&self, handle: CommandGroupHandle) -> Option<&str> {
+        self.groups.get(handle)?.label.as_deref()
+    }
+
+    fn set_layer_visible(&mut self, layer: DisplayLayer, visible: bool) {
+        self.groups.set_layer_visible(layer, visible);
+    }
+
+    fn set_layer_opacity(&mut self, layer: DisplayLayer, opacity: f32) {
+        self.groups.set_layer_opacity(layer, opacity);
+    }
+
+    fn set_dithering(&mut self, dithering: Dithering) {
+        if dithering != self.dithering {
+            self.dithering = dithering;
+            self.groups.invalidate_all();
+        }
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    fn set_scale_factor(&mut self, scale_factor: f64) {
+        if scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            self.groups.invalidate_all();
+        }
+    }
+
+    fn modify_command_group_in_place(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        always_alive: Option<bool>,
+    ) {
+        self.groups.modify(
+            handle,
+            commands,
+            z_order,
+            protected.unwrap_or(true),
+            is_transient(always_alive),
+            true,
+        );
+    }
+
+    fn maintain_command_group_in_place(&mut self, handle: CommandGroupHandle) {
+        self.groups.maintain(handle, true);
+    }
+
+    fn expire_command_groups(&mut self) {
+        self.groups.expire();
+    }
+
+    #[inline]
+    fn maintenance_count(&self) -> Option<u64> {
+        Some(self.groups.maintenance_count())
+    }
+
+    fn before_exit(&mut self) {
+        self.device.poll(::wgpu::Maintain::Wait);
+    }
+
+    fn shutdown(&mut self) -> LeakReport {
+        self.before_exit();
+        LeakReport {
+            resources: self.resources.take_leaks(),
+            command_groups: self.groups.take_handles(),
+        }
+    }
+
+    fn report_error(&mut self, error: error::DisplayError) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<error::DisplayError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn take_damage(&mut self) -> Damage {
+        self.groups.take_damage()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+        let handles = self.groups.present(cull);
+
+        // anything outside the cull is left untouched.
+        let (width, height) = (self.size.0.max(1), self.size.1.max(1));
+        let scissor = match cull {
+            Some(cull) => {
+                let x0 = cull.min_x().floor().clamp(0.0, width as f32) as u32;
+                let y0 = cull.min_y().floor().clamp(0.0, height as f32) as u32;
+                let x1 = cull.max_x().ceil().clamp(0.0, width as f32) as u32;
+                let y1 = cull.max_y().ceil().clamp(0.0, height as f32) as u32;
+                [x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0)]
+            }
+            None => [0, 0, width, height],
+        };
+        if scissor[2] == 0 || scissor[3] == 0 {
+            return Ok(());
+        }
+
+        let mut canvas = Canvas::new(
+            &mut self.tessellator,
+            &self.resources,
+            (width as f32, height as f32),
+            self.dithering,
+        );
+        let mut lowered = Vec::new();
+        let mut current_layer = None;
+        let mut layer_depth = None;
+
+        for handle in handles {
+            let group = match self.groups.get(handle) {
+                Some(group) => group,
+                None => continue,
+            };
+
+            // command groups are ordered by layer, so each layer is composited as a whole.
+            if current_layer != Some(group.layer) {
+                current_layer = Some(group.layer);
+                if let Some(depth) = layer_depth.take() {
+                    canvas.restore_to(depth);
+                }
+
+                let opacity = self.groups.layer_state(group.layer).1;
+                if opacity < 1.0 {
+                    layer_depth = Some(canvas.depth());
+                    canvas.save_layer(opacity, None);
+                }
+            }
+
+            // unprotected command groups leak their state into the following command groups.
+            let depth = canvas.depth();
+            let isolated = group.protected
+                || group.clip.is_some()
+                || group.scissor.is_some()
+                || group.transform != Transform::identity();
+            if isolated {
+                canvas.save();
+            }
+            // the scissor is in display coordinates, so it's applied before the transformation.
+            if let Some(scissor) = group.scissor {
+                canvas
+                    .clip(&clip_path(&DisplayClip::Rectangle { rect: scissor, antialias: false }));
+            }
+            canvas.concat(&group.transform);
+            if let Some(ref clip) = group.clip {
+                canvas.clip(&clip_path(clip));
+            }
+
+            lowered.clear();
+            for command in &group.commands {
+                lower_command(command, &mut lowered).map_err(font_error)?;
+            }
+            for command in &lowered {
+                canvas.draw(command)?;
+            }
+
+            if isolated {
+                canvas.restore_to(depth);
+            }
+        }
+
+        canvas.restore_to(0);
+        let frame = canvas.finish();
+        self.render(frame, scissor);
+        Ok(())
+    }
+}
+
+enum WgpuResource {
+    Image(GpuImage),
+    Font,
+}
+
+struct GpuImage {
+    size: (u32, u32),
+    bind_group: ::wgpu::BindGroup,
+    _texture: ::wgpu::Texture,
+}
+
+/// Converts raw pixels to premultiplied RGBA.
+fn raw_pixels(bytes: &[u8], info: RasterImageInfo) -> Result<Vec<u8>, error::ResourceError> {
+    if bytes.len() != info.size.0 as usize * info.size.1 as usize * 4 {
+        return Err(error::ResourceError::InvalidData);
+    }
+
+    let premultiply = |channel: u8, alpha: u8| ((channel as u32 * alpha as u32 + 127) / 255) as u8;
+    Ok(bytes
+        .chunks(4)
+        .flat_map(|pixel| {
+            let [red, green, blue, alpha] = match info.format {
+                RasterImageFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+                RasterImageFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+            };
+            [premultiply(red, alpha), premultiply(green, alpha), premultiply(blue, alpha), alpha]
+        })
+        .collect())
+}
+
+#[cfg(feature = "image-codecs")]
+fn decode_pixels(bytes: &[u8]) -> Result<(Vec<u8>, (u32, u32)), error::ResourceError> {
+    let image =
+        image::load_from_memory(bytes).map_err(|_| error::ResourceError::InvalidData)?.into_rgba8();
+    let size = image.dimensions();
+    let info = RasterImageInfo { size, format: RasterImageFormat::Rgba8 };
+    Ok((raw_pixels(image.as_raw(), info)?, size))
+}
+
+#[cfg(not(feature = "image-codecs"))]
+fn decode_pixels(_bytes: &[u8]) -> Result<(Vec<u8>, (u32, u32)), error::ResourceError> {
+    Err(error::ResourceError::Unsupported(
+        "encoded images are only decoded by the wgpu display with the `image-codecs` feature"
+            .to_string(),
+    ))
+}
+
+/// Render pipelines, and what they bind.
+struct Pipelines {
+    paint_layout: ::wgpu::BindGroupLayout,
+    texture_layout: ::wgpu::BindGroupLayout,
+    sampler: ::wgpu::Sampler,
+    // bound in place of a texture by draws which don't sample one.
+    blank: ::wgpu::BindGroup,
+    fill: ::wgpu::RenderPipeline,
+    replace: ::wgpu::RenderPipeline,
+    clip_in: ::wgpu::RenderPipeline,
+    clip_out: ::wgpu::RenderPipeline,
+    blur: ::wgpu::RenderPipeline,
+}
+
+/// How a draw affects a surface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pipeline {
+    /// Blends over the surface, within the clip.
+    Fill,
+    /// Replaces the surface, within the clip.
+    Replace,
+    /// Intersects the clip with the mesh, incrementing the stencil where it equals the reference.
+    ClipIn,
+    /// Undoes a `ClipIn`, decrementing the stencil where it equals the reference.
+    ClipOut,
+}
+
+impl Pipelines {
+    fn new(device: &::wgpu::Device, queue: &::wgpu::Queue) -> Self {
+        let shader = device.create_shader_module(::wgpu::ShaderModuleDescriptor {
+            label: Some("reclutch shaders"),
+            source: ::wgpu::ShaderSource::Wgsl(include_str!("wgpu.wgsl").into()),
+        });
+
+        let paint_layout = device.create_bind_group_layout(&::wgpu::BindGroupLayoutDescriptor {
+            label: Some("reclutch paint"),
+            entries: &[::wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ::wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: ::wgpu::BindingType::Buffer {
+                    ty: ::wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: NonZeroU64::new(PAINT_SIZE),
+                },
+                count: None,
+            }],
+        });
+        let texture_layout = device.create_bind_group_layout(&::wgpu::BindGroupLayoutDescriptor {
+            label: Some("reclutch texture"),
+            entries: &[
+                ::wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ::wgpu::ShaderStages::FRAGMENT,
+                    ty: ::wgpu::BindingType::Texture {
+                        sample_type: ::wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: ::wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                ::wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ::wgpu::ShaderStages::FRAGMENT,
+                    ty: ::wgpu::BindingType::Sampler(::wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&::wgpu::PipelineLayoutDescriptor {
+            label: Some("reclutch pipeline"),
+            bind_group_layouts: &[&paint_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = |label, fragment, blend, write_mask, pass_op| {
+            let face = ::wgpu::StencilFaceState {
+                compare: ::wgpu::CompareFunction::Equal,
+                fail_op: ::wgpu::StencilOperation::Keep,
+                depth_fail_op: ::wgpu::StencilOperation::Keep,
+                pass_op,
+            };
+            device.create_render_pipeline(&::wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: ::wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    compilation_options: Default::default(),
+                    buffers: &[::wgpu::VertexBufferLayout {
+                        array_stride: 8,
+                        step_mode: ::wgpu::VertexStepMode::Vertex,
+                        attributes: &::wgpu::vertex_attr_array![0 => Float32x2],
+                    }],
+                },
+                primitive: Default::default(),
+                depth_stencil: Some(::wgpu::DepthStencilState {
+                    format: STENCIL_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: ::wgpu::CompareFunction::Always,
+                    stencil: ::wgpu::StencilState {
+                        front: face,
+                        back: face,
+                        read_mask: !0,
+                        write_mask: !0,
+                    },
+                    bias: Default::default(),
+                }),
+                multisample: ::wgpu::MultisampleState { count: SAMPLE_COUNT, ..Default::default() },
+                fragment: Some(::wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: fragment,
+                    compilation_options: Default::default(),
+                    targets: &[Some(::wgpu::ColorTargetState {
+                        format: TEXTURE_FORMAT,
+                        blend,
+                        write_mask,
+                    })],
+                }),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let (keep, increment, decrement) = (
+            ::wgpu::StencilOperation::Keep,
+            ::wgpu::StencilOperation::IncrementClamp,
+            ::wgpu::StencilOperation::DecrementClamp,
+        );
+        let (all, none) = (::wgpu::ColorWrites::ALL, ::wgpu::ColorWrites::empty());
+        let fill = pipeline(
+            "reclutch fill",
+            "fs_main",
+            Some(::wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            all,
+            keep,
+        );
+        let replace = pipeline("reclutch replace", "fs_main", None, all, keep);
+        let clip_in = pipeline("reclutch clip", "fs_clip", None, none, increment);
+        let clip_out = pipeline("reclutch unclip", "fs_clip", None, none, decrement);
+
+        let blur = device.create_render_pipeline(&::wgpu::RenderPipelineDescriptor {
+            label: Some("reclutch blur"),
+            layout: Some(&layout),
+            vertex: ::wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_fullscreen",
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(::wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_blur",
+                compilation_options: Default::default(),
+                targets: &[Some(TEXTURE_FORMAT.into())],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        let sampler = device.create_sampler(&::wgpu::SamplerDescriptor {
+            label: Some("reclutch sampler"),
+            mag_filter: ::wgpu::FilterMode::Linear,
+            min_filter: ::wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let blank_texture = device.create_texture_with_data(
+            queue,
+            &::wgpu::TextureDescriptor {
+                label: Some("reclutch blank"),
+                size: extent((1, 1)),
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: ::wgpu::TextureDimension::D2,
+                format: TEXTURE_FORMAT,
+                usage: ::wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            Default::default(),
+            &[255; 4],
+        );
+
+        let blank = texture_bind_group(
+            device,
+            &texture_layout,
+            &sampler,
+            &blank_texture.create_view(&Default::default()),
+        );
+
+        Pipelines {
+            paint_layout,
+            texture_layout,
+            sampler,
+            blank,
+            fill,
+            replace,
+            clip_in,
+            clip_out,
+            blur,
+        }
+    }
+
+    fn get(&self, pipeline: Pipeline) -> &::wgpu::RenderPipeline {
+        match pipeline {
+            Pipeline::Fill => &self.fill,
+            Pipeline::Replace => &self.replace,
+            Pipeline::ClipIn => &self.clip_in,
+            Pipeline::ClipOut => &self.clip_out,
+        }
+    }
+
+    fn texture_bind_group(
+        &self,
+        device: &::wgpu::Device,
+        view: &::wgpu::TextureView,
+    ) -> ::wgpu::BindGroup {
+        texture_bind_group(device, &self.texture_layout, &self.sampler, view)
+    }
+}
+
+fn texture_bind_group(
+    device: &::wgpu::Device,
+    layout: &::wgpu::BindGroupLayout,
+    sampler: &::wgpu::Sampler,
+    view: &::wgpu::TextureView,
+) -> ::wgpu::BindGroup {
+    device.create_bind_group(&::wgpu::BindGroupDescriptor {
+        label: None,
+        layout,
+        entries: &[
+            ::wgpu::BindGroupEntry {
+                binding: 0,
+                resource: ::wgpu::BindingResource::TextureView(view),
+            },
+            ::wgpu::BindGroupEntry {
+                binding: 1,
+                resource: ::wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+/// Textures which are drawn into; the target of the display, or a layer (or a copy of what's behind a backdrop filter).
+struct Surface {
+    // multisampled, and resolved into `resolved` at the end of every pass.
+    color: ::wgpu::TextureView,
+    stencil: ::wgpu::TextureView,
+    resolved: ::wgpu::Texture,
+    resolved_view: ::wgpu::TextureView,
+    // samples `resolved`.
+    bind_group: ::wgpu::BindGroup,
+}
+
+impl Surface {
+    fn new(device: &::wgpu::Device, pipelines: &Pipelines, size: (u32, u32)) -> Self {
+        let texture = |label, sample_count, format, usage| {
+            device.create_texture(&::wgpu::TextureDescriptor {
+                label: Some(label),
+                size: extent(size),
+                mip_level_count: 1,
+                sample_count,
+                dimension: ::wgpu::TextureDimension::D2,
+                format,
+                usage,
+                view_formats: &[],
+            })
+        };
+
+        let color = texture(
+            "reclutch surface",
+            SAMPLE_COUNT,
+            TEXTURE_FORMAT,
+            ::wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let stencil = texture(
+            "reclutch stencil",
+            SAMPLE_COUNT,
+            STENCIL_FORMAT,
+            ::wgpu::TextureUsages::RENDER_ATTACHMENT,
+        );
+        let resolved = texture(
+            "reclutch resolved surface",
+            1,
+            TEXTURE_FORMAT,
+            ::wgpu::TextureUsages::RENDER_ATTACHMENT
+                | ::wgpu::TextureUsages::TEXTURE_BINDING
+                | ::wgpu::TextureUsages::COPY_SRC
+                | ::wgpu::TextureUsages::COPY_DST,
+        );
+        let resolved_view = resolved.create_view(&Default::default());
+
+        Surface {
+            color: color.create_view(&Default::default()),
+            stencil: stencil.create_view(&Default::default()),
+            bind_group: pipelines.texture_bind_group(device, &resolved_view),
+            resolved,
+            resolved_view,
+        }
+    }
+}
+
+/// Uniforms of a draw (the `Paint` of the shader).
+#[derive(Clone)]
+struct Paint {
+    kind: u32,
+    dithering: u32,
+    flags: u32,
+    /// Maps display coordinates to the coordinates the paint was drawn in.
+    inverse: Transform,
+    geometry: [f32; 4],
+    source: [f32; 4],
+    /// Row-major 4x5 color matrix.
+    matrix: [f32; 20],
+    /// Unpremultiplied colors (of solid paints and checkerboards, too).
+    stops: Vec<(f32, Color)>,
+}
+
+impl Paint {
+    fn new(kind: u32) -> Self {
+        Paint {
+            kind,
+            dithering: 0,
+            flags: 0,
+            inverse: Transform::identity(),
+            geometry: [0.0; 4],
+            source: [0.0; 4],
+            matrix: [0.0; 20],
+            stops: Vec::new(),
+        }
+    }
+
+    fn solid(color: Color) -> Self {
+        Paint { stops: vec![(0.0, color)], ..Paint::new(SOLID) }
+    }
+
+    fn style(color: &StyleColor, transform: &Transform, dithering: Dithering) -> Self {
+        let inverse = transform.inverse().unwrap_or_else(Transform::identity);
+        let gradient = |kind, gradient: &Gradient| Paint {
+            inverse,
+            dithering: match gradient.dithering.unwrap_or(dithering) {
+                Dithering::None => 0,
+                Dithering::Ordered => 1,
+                Dithering::Noise => 2,
+            },
+            geometry: [gradient.start.x, gradient.start.y, gradient.end.x, gradient.end.y],
+            stops: gradient_stops(&gradient.stops),
+            ..Paint::new(kind)
+        };
+
+        match color {
+            StyleColor::Color(color) => Paint::solid(*color),
+            StyleColor::LinearGradient(linear) => gradient(LINEAR_GRADIENT, linear),
+            StyleColor::RadialGradient(radial) => gradient(RADIAL_GRADIENT, radial),
+            StyleColor::Checkerboard(checkerboard) => Paint {
+                inverse,
+                geometry: [checkerboard.origin.x, checkerboard.origin.y, checkerboard.size, 0.0],
+                stops: vec![(0.0, checkerboard.light), (0.0, checkerboard.dark)],
+                ..Paint::new(CHECKERBOARD)
+            },
+        }
+    }
+
+    /// A paint of a surface (composited with `opacity`), inverted by `filter` if it's [`Invert`](../enum.Filter.html#variant.Invert).
+    fn surface(opacity: f32, filter: Option<Filter>) -> Self {
+        Paint {
+            flags: if filter == Some(Filter::Invert) { INVERTED } else { 0 },
+            geometry: [opacity, 0.0, 0.0, 0.0],
+            ..Paint::new(SURFACE)
+        }
+    }
+
+    fn blur(direction: Vector, sigma: f32) -> Self {
+        Paint { geometry: [direction.x, direction.y, sigma, 0.0], ..Paint::new(SURFACE) }
+    }
+
+    fn encode(&self, viewport: (f32, f32), out: &mut Vec<u8>) {
+        let start = out.len();
+        let mut floats = |values: &[f32]| {
+            for value in values {
+                out.extend_from_slice(&value.to_ne_bytes());
+            }
+        };
+
+        let header = [self.kind, self.stops.len() as u32, self.dithering, self.flags];
+        floats(&header.map(f32::from_bits));
+        floats(&[viewport.0, viewport.1, 0.0, 0.0]);
+        let m = &self.inverse;
+        floats(&[m.m11, m.m21, m.m31, 0.0, m.m12, m.m22, m.m32, 0.0]);
+        floats(&self.geometry);
+        floats(&self.source);
+        for column in 0..5 {
+            floats(&[0, 1, 2, 3].map(|row| self.matrix[row * 5 + column]));
+        }
+
+        let mut colors = [[0.0; 4]; MAX_STOPS];
+        let mut offsets = [0.0; MAX_STOPS];
+        for (i, (offset, color)) in self.stops.iter().take(MAX_STOPS).enumerate() {
+            colors[i] = [color.red, color.green, color.blue, color.alpha];
+            offsets[i] = *offset;
+        }
+        for color in &colors {
+            floats(color);
+        }
+        floats(&offsets);
+
+        debug_assert_eq!(out.len() - start, PAINT_SIZE as usize);
+    }
+}
+
+/// Returns the stops of a gradient, resampled if there are more than the shader takes.
+fn gradient_stops(stops: &[(f32, Color)]) -> Vec<(f32, Color)> {
+    if stops.len() <= MAX_STOPS {
+        return stops.to_vec();
+    }
+
+    (0..MAX_STOPS)
+        .map(|i| {
+            let t = i as f32 / (MAX_STOPS - 1) as f32;
+            let color = match stops.iter().position(|&(offset, _)| t <= offset) {
+                Some(0) => stops[0].1,
+                Some(end) => {
+                    let ((start, from), (end, to)) = (stops[end - 1], stops[end]);
+                    let mix = if end > start { (t - start) / (end - start) } else { 1.0 };
+                    Color::new(
+                        from.red + (to.red - from.red) * mix,
+                        from.green + (to.green - from.green) * mix,
+                        from.blue + (to.blue - from.blue) * mix,
+                        from.alpha + (to.alpha - from.alpha) * mix,
+                    )
+                }
+                None => stops[stops.len() - 1].1,
+            };
+            (t, color)
+        })
+        .collect()
+}
+
+/// What a draw samples.
+#[derive(Debug, Clone, Copy)]
+enum Binding {
+    Blank,
+    Image(ResourceReference),
+    Surface(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Draw {
+    pipeline: Pipeline,
+    indices: Range<u32>,
+    paint: u32,
+    binding: Binding,
+    /// Stencil reference, which is the depth of the clip the draw is within.
+    stencil: u32,
+}
+
+/// A step of drawing a frame, in order.
+enum Step {
+    Pass {
+        surface: usize,
+        clear_color: bool,
+        clear_stencil: bool,
+        draws: Vec<Draw>,
+    },
+    /// Copies one surface into another.
+    Copy {
+        from: usize,
+        to: usize,
+    },
+    /// Blurs a surface horizontally into a scratch surface, then vertically back, with a paint for each.
+    Blur {
+        surface: usize,
+        scratch: usize,
+        paints: [u32; 2],
+    },
+}
+
+/// Everything needed to draw a frame.
+struct Frame {
+    vertices: Vec<Point>,
+    indices: Vec<u32>,
+    paints: Vec<Paint>,
+    steps: Vec<Step>,
+    /// Number of surfaces drawn into, including the target.
+    surfaces: usize,
+}
+
+#[derive(Clone)]
+struct DrawState {
+    transform: Transform,
+    /// Meshes of the clips drawn into the stencil of the current surface, in order.
+    clips: Vec<Range<u32>>,
+}
+
+/// A layer, which is drawn into a surface of its own.
+struct Layer {
+    surface: usize,
+    parent: usize,
+    opacity: f32,
+    filter: Option<Filter>,
+}
+
+/// Turns lowered commands into the steps of a frame.
+struct Canvas<'a> {
+    tessellator: &'a mut LyonTessellator,
+    resources: &'a ResourceRegistry<WgpuResource>,
+    viewport: (f32, f32),
+    dithering: Dithering,
+    frame: Frame,
+    // the surface being drawn into.
+    surface: usize,
+    state: DrawState,
+    // saved states, and the layer each began.
+    saved: Vec<(DrawState, Option<Layer>)>,
+}
+
+impl<'a> Canvas<'a> {
+    fn new(
+        tessellator: &'a mut LyonTessellator,
+        resources: &'a ResourceRegistry<WgpuResource>,
+        viewport: (f32, f32),
+        dithering: Dithering,
+    ) -> Self {
+        Canvas {
+            tessellator,
+            resources,
+            viewport,
+            dithering,
+            frame: Frame {
+                vertices: Vec::new(),
+                indices: Vec::new(),
+                // clips are drawn with the first paint, since they only need the viewport.
+                paints: vec![Paint::new(SOLID)],
+                // the target keeps its pixels, but its stencil starts out clear.
+                steps: vec![Step::Pass {
+                    surface: 0,
+                    clear_color: false,
+                    clear_stencil: true,
+                    draws: Vec::new(),
+                }],
+                surfaces: 1,
+            },
+            surface: 0,
+            state: DrawState { transform: Transform::identity(), clips: Vec::new() },
+            saved: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> Frame {
+        self.frame
+    }
+
+    fn depth(&self) -> usize {
+        self.saved.len()
+    }
+
+    fn tolerance(&self) -> f32 {
+        TOLERANCE / transform_scale(&self.state.transform)
+    }
+
+    fn new_surface(&mut self) -> usize {
+        self.frame.surfaces += 1;
+        self.frame.surfaces - 1
+    }
+
+    fn add_paint(&mut self, paint: Paint) -> u32 {
+        self.frame.paints.push(paint);
+        self.frame.paints.len() as u32 - 1
+    }
+
+    /// Adds a mesh, transformed into display coordinates, returning the range of its indices.
+    fn add_mesh(&mut self, mesh: &Mesh, transform: &Transform) -> Range<u32> {
+        let first = self.frame.vertices.len() as u32;
+        let start = self.frame.indices.len() as u32;
+        self.frame
+            .vertices
+            .extend(mesh.vertices.iter().map(|&point| transform.transform_point(point)));
+        self.frame.indices.extend(mesh.indices.iter().map(|index| first + index));
+        start..self.frame.indices.len() as u32
+    }
+
+    /// Adds a rectangle covering every surface.
+    fn add_viewport(&mut self) -> Range<u32> {
+        let size = Size::new(self.viewport.0, self.viewport.1);
+        let (min, max) = (Point::zero(), size.to_vector().to_point());
+        let mesh = Mesh::from_triangles(
+            vec![min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)],
+            vec![0u32, 1, 2, 0, 2, 3],
+        );
+        self.add_mesh(&mesh, &Transform::identity())
+    }
+
+    fn push(&mut self, draw: Draw) {
+        if draw.indices.start == draw.indices.end {
+            return;
+        }
+
+        match self.frame.steps.last_mut() {
+            Some(Step::Pass { surface, draws, .. }) if *surface == self.surface => draws.push(draw),
+            _ => self.frame.steps.push(Step::Pass {
+                surface: self.surface,
+                clear_color: false,
+                clear_stencil: false,
+                draws: vec![draw],
+            }),
+        }
+    }
+
+    /// Draws a mesh (in the current coordinate space) within the clip.
+    fn fill(&mut self, mesh: &Mesh, paint: Paint, binding: Binding) {
+        if mesh.indices.is_empty() {
+            return;
+        }
+
+        let transform = self.state.transform;
+        let indices = self.add_mesh(mesh, &transform);
+        let paint = self.add_paint(paint);
+        self.push(Draw {
+            pipeline: Pipeline::Fill,
+            indices,
+            paint,
+            binding,
+            stencil: self.state.clips.len() as u32,
+        });
+    }
+
+    fn draw(&mut self, command: &LoweredCommand) -> Result<(), error::DisplayError> {
+        match command {
+            LoweredCommand::Path { path, is_closed, paint } => {
+                let tolerance = self.tolerance();
+                let (mesh, color) = match paint {
+                    GraphicsDisplayPaint::Fill(color) => {
+                        (self.tessellator.fill(path, tolerance), color)
+                    }
+                    GraphicsDisplayPaint::Stroke(stroke) => {
+                        // hairlines are a pixel wide, regardless of the transformation.
+                        let width = if stroke.thickness > 0.0 {
+                            stroke.thickness
+                        } else {
+                            1.0 / transform_scale(&self.state.transform)
+                        };
+                        let mesh =
+                            self.tessellator.stroke(path, *is_closed, stroke, width, tolerance);
+                        (mesh, &stroke.color)
+                    }
+                };
+                let paint = Paint::style(color, &self.state.transform, self.dithering);
+                self.fill(&mesh, paint, Binding::Blank);
+            }
+            LoweredCommand::Image { src, dst, resource, orientation, adjustments } => {
+                let image = match self.resources.get(*resource)? {
+                    WgpuResource::Image(image) => image,
+                    WgpuResource::Font => {
+                        return Err(error::DisplayError::MismatchedResource(resource.id()))
+                    }
+                };
+                let rect = orientation.image_rect(*dst);
+                let transform = orientation.transform(*dst).post_transform(&self.state.transform);
+                let source = src.unwrap_or_else(|| {
+                    Rect::new(Point::zero(), Size::new(image.size.0 as f32, image.size.1 as f32))
+                });
+
+                let (min, max) = (rect.min(), rect.max());
+                let mesh = Mesh::from_triangles(
+                    vec![min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)],
+                    vec![0u32, 1, 2, 0, 2, 3],
+                );
+                let paint = Paint {
+                    flags: if adjustments.is_identity() { 0 } else { ADJUSTED },
+                    inverse: transform.inverse().unwrap_or_else(Transform::identity),
+                    geometry: [rect.min_x(), rect.min_y(), rect.size.width, rect.size.height],
+                    source: [source.min_x(), source.min_y(), source.size.width, source.size.height],
+                    matrix: adjustments.color_matrix(),
+                    ..Paint::new(IMAGE)
+                };
+
+                let indices = self.add_mesh(&mesh, &transform);
+                let paint = self.add_paint(paint);
+                self.push(Draw {
+                    pipeline: Pipeline::Fill,
+                    indices,
+                    paint,
+                    binding: Binding::Image(*resource),
+                    stencil: self.state.clips.len() as u32,
+                });
+            }
+            LoweredCommand::Glyphs(run) => {
+                self.resources.get(run.font)?;
+                let outline = run.outline().map_err(font_error)?;
+                let mesh = self.tessellator.fill(&outline, self.tolerance());
+                let paint = Paint::style(&run.color, &self.state.transform, self.dithering);
+                self.fill(&mesh, paint, Binding::Blank);
+            }
+            LoweredCommand::Clip { path, .. } => self.clip(path),
+            LoweredCommand::BackdropFilter { path, filter } => {
+                // the filter reads a copy of what's behind it, so that it can replace it.
+                let backdrop = self.new_surface();
+                self.frame.steps.push(Step::Copy { from: self.surface, to: backdrop });
+                if let Filter::Blur(x, y) = *filter {
+                    self.blur(backdrop, x, y);
+                }
+
+                let mesh = self.tessellator.fill(path, self.tolerance());
+                let transform = self.state.transform;
+                let indices = self.add_mesh(&mesh, &transform);
+                let paint = self.add_paint(Paint::surface(1.0, Some(*filter)));
+                self.push(Draw {
+                    pipeline: Pipeline::Replace,
+                    indices,
+                    paint,
+                    binding: Binding::Surface(backdrop),
+                    stencil: self.state.clips.len() as u32,
+                });
+            }
+            LoweredCommand::Save => self.save(),
+            LoweredCommand::SaveLayer { opacity, filter } => self.save_layer(*opacity, *filter),
+            LoweredCommand::Restore => self.restore(),
+            LoweredCommand::Transform(transform) => self.concat(transform),
+            LoweredCommand::Clear(color) => {
+                let indices = self.add_viewport();
+                let paint = self.add_paint(Paint::solid(*color));
+                self.push(Draw {
+                    pipeline: Pipeline::Replace,
+                    indices,
+                    paint,
+                    binding: Binding::Blank,
+                    stencil: self.state.clips.len() as u32,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Intersects the clip with a path (in the current coordinate space).
+    fn clip(&mut self, path: &VectorPath) {
+        let mesh = self.tessellator.fill(path, self.tolerance());
+        let transform = self.state.transform;
+        let indices = self.add_mesh(&mesh, &transform);
+        self.push(Draw {
+            pipeline: Pipeline::ClipIn,
+            indices: indices.clone(),
+            paint: 0,
+            binding: Binding::Blank,
+            stencil: self.state.clips.len() as u32,
+        });
+        // an empty clip still counts, such that nothing is drawn within it.
+        self.state.clips.push(indices);
+    }
+
+    fn blur(&mut self, surface: usize, sigma_x: f32, sigma_y: f32) {
+        let scratch = self.new_surface();
+        let horizontal = self.add_paint(Paint::blur(Vector::new(1.0, 0.0), sigma_x));
+        let vertical = self.add_paint(Paint::blur(Vector::new(0.0, 1.0), sigma_y));
+        self.frame.steps.push(Step::Blur { surface, scratch, paints: [horizontal, vertical] });
+    }
+
+    fn save(&mut self) {
+        self.saved.push((self.state.clone(), None));
+    }
+
+    fn save_layer(&mut self, opacity: f32, filter: Option<Filter>) {
+        let surface = self.new_surface();
+        self.saved.push((
+            self.state.clone(),
+            Some(Layer { surface, parent: self.surface, opacity, filter }),
+        ));
+
+        // the layer is clipped like the surface it's composited into.
+        let draws = self
+            .state
+            .clips
+            .iter()
+            .enumerate()
+            .map(|(depth, indices)| Draw {
+                pipeline: Pipeline::ClipIn,
+                indices: indices.clone(),
+                paint: 0,
+                binding: Binding::Blank,
+                stencil: depth as u32,
+            })
+            .collect();
+        self.frame.steps.push(Step::Pass {
+            surface,
+            clear_color: true,
+            clear_stencil: true,
+            draws,
+        });
+        self.surface = surface;
+    }
+
+    fn restore(&mut self) {
+        let (state, layer) = match self.saved.pop() {
+            Some(saved) => saved,
+            None => return,
+        };
+
+        match layer {
+            None => {
+                // clips since the save were drawn into the stencil of this surface, so they're undone.
+                let clips: Vec<_> =
+                    self.state.clips.iter().cloned().enumerate().skip(state.clips.len()).collect();
+                for (depth, indices) in clips.into_iter().rev() {
+                    self.push(Draw {
+                        pipeline: Pipeline::ClipOut,
+                        indices,
+                        paint: 0,
+                        binding: Binding::Blank,
+                        stencil: depth as u32 + 1,
+                    });
+                }
+                self.state = state;
+            }
+            Some(layer) => {
+                if let Some(Filter::Blur(x, y)) = layer.filter {
+                    self.blur(layer.surface, x, y);
+                }
+
+                self.state = state;
+                self.surface = layer.parent;
+                let indices = self.add_viewport();
+                let paint = self.add_paint(Paint::surface(layer.opacity, layer.filter));
+                self.push(Draw {
+                    pipeline: Pipeline::Fill,
+                    indices,
+                    paint,
+                    binding: Binding::Surface(layer.surface),
+                    stencil: self.state.clips.len() as u32,
+                });
+            }
+        }
+    }
+
+    fn restore_to(&mut self, depth: usize) {
+        while self.saved.len() > depth {
+            self.restore();
+        }
+    }
+
+    fn concat(&mut self, transform: &Transform) {
+        self.state.transform = transform.post_transform(&self.state.transform);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(color: Color) -> GraphicsDisplayPaint {
+        GraphicsDisplayPaint::Fill(color.into())
+    }
+
+    // returns `None` (skipping the test) where there's no adapter, not even a software one.
+    fn device() -> Option<(Arc<::wgpu::Device>, Arc<::wgpu::Queue>)> {
+        let instance = ::wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&Default::default()))?;
+        let (device, queue) =
+            pollster::block_on(adapter.request_device(&Default::default(), None)).ok()?;
+        Some((Arc::new(device), Arc::new(queue)))
+    }
+
+    fn display(size: (u32, u32)) -> Option<WgpuGraphicsDisplay> {
+        device().map(|(device, queue)| WgpuGraphicsDisplay::new(device, queue, size))
+    }
+
+    fn draw(display: &mut WgpuGraphicsDisplay, commands: &[DisplayCommand]) -> Vec<u8> {
+        display.push_command_group(commands, ZOrder::default(), None, Some(false)).unwrap();
+        display.present(None).unwrap();
+        display.to_rgba8()
+    }
+
+    fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> Color {
+        let i = (y * width + x) as usize * 4;
+        let channel = |i: usize| rgba[i] as f32 / 255.0;
+        Color::new(channel(i), channel(i + 1), channel(i + 2), channel(i + 3))
+    }
+
+    fn close(a: Color, b: Color) -> bool {
+        (a.red - b.red).abs() < 0.02
+            && (a.green - b.green).abs() < 0.02
+            && (a.blue - b.blue).abs() < 0.02
+            && (a.alpha - b.alpha).abs() < 0.02
+    }
+
+    #[test]
+    fn test_lyon_tessellator() {
+        let mut tessellator = LyonTessellator::new();
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::new(1.0, 1.0), Size::new(4.0, 3.0)),
+            fill(Color::new(0.0, 0.0, 0.0, 1.0)),
+            None,
+        );
+        let mut lowered = Vec::new();
+        for command in &builder.build() {
+            lower_command(command, &mut lowered).unwrap();
+        }
+        let path = lowered
+            .iter()
+            .find_map(|command| match command {
+                LoweredCommand::Path { path, .. } => Some(path),
+                _ => None,
+            })
+            .unwrap();
+
+        let area = |mesh: &Mesh| {
+            mesh.indices
+                .chunks(3)
+                .map(|triangle| {
+                    let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize]);
+                    (b - a).cross(c - a).abs() / 2.0
+                })
+                .sum::<f32>()
+        };
+        assert!((area(&tessellator.fill(path, TOLERANCE)) - 12.0).abs() < 0.01);
+
+        let stroke = GraphicsDisplayStroke { cap: LineCap::Flat, ..Default::default() };
+        // a closed outline of width 2 covers 6x5 less 2x1.
+        let mesh = tessellator.stroke(path, true, &stroke, 2.0, TOLERANCE);
+        assert!((area(&mesh) - 28.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_wgpu_display_conformance() {
+        let (device, queue) = match device() {
+            Some(device) => device,
+            None => return,
+        };
+        backend::conformance::check_display(|| {
+            WgpuGraphicsDisplay::new(device.clone(), queue.clone(), (32, 32))
+        });
+    }
+
+    #[test]
+    fn test_fill_and_clip() {
+        let mut display = match display((16, 16)) {
+            Some(display) => display,
+            None => return,
+        };
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let mut builder = DisplayListBuilder::new();
+        builder.save();
+        builder.push_rectangle_clip(Rect::new(Point::zero(), Size::new(8.0, 16.0)), false);
+        builder.push_rectangle(
+            Rect::new(Point::new(2.0, 2.0), Size::new(12.0, 4.0)),
+            fill(red),
+            None,
+        );
+        builder.restore();
+        builder.push_rectangle(
+            Rect::new(Point::new(2.0, 10.0), Size::new(12.0, 4.0)),
+            fill(Color::new(0.0, 0.0, 1.0, 0.5)),
+            None,
+        );
+        let rgba = draw(&mut display, &builder.build());
+
+        assert!(close(pixel(&rgba, 16, 4, 4), red));
+        // clipped.
+        assert_eq!(pixel(&rgba, 16, 12, 4).alpha, 0.0);
+        assert_eq!(pixel(&rgba, 16, 1, 4).alpha, 0.0);
+        // the clip was restored.
+        assert!(close(pixel(&rgba, 16, 12, 12), Color::new(0.0, 0.0, 1.0, 0.5)));
+    }
+
+    #[test]
+    fn test_layers_and_filters() {
+        let mut display = match display((8, 8)) {
+            Some(display) => display,
+            None => return,
+        };
+        let rect = Rect::new(Point::zero(), Size::new(8.0, 8.0));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(1.0, 1.0, 1.0, 1.0));
+        builder.save_layer(0.5);
+        builder.push_rectangle(rect, fill(Color::new(0.0, 0.0, 1.0, 1.0)), None);
+        builder.restore();
+        builder.push_rectangle_backdrop(
+            Rect::new(Point::zero(), Size::new(4.0, 8.0)),
+            false,
+            Filter::Invert,
+        );
+        let rgba = draw(&mut display, &builder.build());
+
+        assert!(close(pixel(&rgba, 8, 6, 0), Color::new(0.5, 0.5, 1.0, 1.0)));
+        assert!(close(pixel(&rgba, 8, 1, 0), Color::new(0.5, 0.5, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_backdrop_blur() {
+        let mut display = match display((16, 4)) {
+            Some(display) => display,
+            None => return,
+        };
+        let mut builder = DisplayListBuilder::new();
+        builder.push_clear(Color::new(1.0, 1.0, 1.0, 1.0));
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(8.0, 4.0)),
+            fill(Color::new(0.0, 0.0, 0.0, 1.0)),
+            None,
+        );
+        builder.push_rectangle_backdrop(
+            Rect::new(Point::new(4.0, 0.0), Size::new(8.0, 4.0)),
+            false,
+            Filter::Blur(2.0, 2.0),
+        );
+        let rgba = draw(&mut display, &builder.build());
+
+        // the edge is blurred into grey within the backdrop.
+        let edge = pixel(&rgba, 16, 7, 2);
+        assert!(edge.red > 0.2 && edge.red < 0.5);
+        assert!(close(pixel(&rgba, 16, 2, 2), Color::new(0.0, 0.0, 0.0, 1.0)));
+        assert!(close(pixel(&rgba, 16, 14, 2), Color::new(1.0, 1.0, 1.0, 1.0)));
+    }
+
+    #[test]
+    fn test_retained_cull() {
+        let mut display = match display((8, 8)) {
+            Some(display) => display,
+            None => return,
+        };
+        let rect = Rect::new(Point::zero(), Size::new(8.0, 8.0));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, fill(Color::new(1.0, 0.0, 0.0, 1.0)), None);
+        let handle =
+            display.push_command_group(&builder.build(), ZOrder::default(), None, None).unwrap();
+        display.present(None).unwrap();
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(rect, fill(Color::new(0.0, 1.0, 0.0, 1.0)), None);
+        display.modify_command_group(handle, &builder.build(), ZOrder::default(), None, None);
+        display.present(Some(Rect::new(Point::zero(), Size::new(4.0, 8.0)))).unwrap();
+        let rgba = display.to_rgba8();
+
+        assert!(close(pixel(&rgba, 8, 1, 1), Color::new(0.0, 1.0, 0.0, 1.0)));
+        // outside the cull, the previous frame remains.
+        assert!(close(pixel(&rgba, 8, 6, 1), Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn test_image() {
+        let mut display = match display((4, 4)) {
+            Some(display) => display,
+            None => return,
+        };
+        // 2x1, a blue and a red pixel.
+        let image = display
+            .new_resource(ResourceDescriptor::Image(ImageData::Raw(
+                vec![255, 0, 0, 255, 0, 0, 255, 255].into(),
+                RasterImageInfo { size: (2, 1), format: RasterImageFormat::Bgra8 },
+            )))
+            .unwrap();
+        assert_eq!(display.image_metadata(image).map(|metadata| metadata.size()), Some((2, 1)));
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_image(None, Rect::new(Point::zero(), Size::new(4.0, 4.0)), image, None);
+        let rgba = draw(&mut display, &builder.build());
+
+        assert!(close(pixel(&rgba, 4, 0, 0), Color::new(0.0, 0.0, 1.0, 1.0)));
+        assert!(close(pixel(&rgba, 4, 3, 3), Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+}
@@ -0,0 +1,224 @@
+// shaders of the wgpu display (see `wgpu.rs`).
+//
+// every draw is a mesh in display coordinates, colored by a paint. colors are premultiplied,
+// except for those in the paint itself.
+
+const SOLID: u32 = 0u;
+const LINEAR_GRADIENT: u32 = 1u;
+const RADIAL_GRADIENT: u32 = 2u;
+const CHECKERBOARD: u32 = 3u;
+const IMAGE: u32 = 4u;
+const SURFACE: u32 = 5u;
+
+const ADJUSTED: u32 = 1u;
+const INVERTED: u32 = 2u;
+
+struct Paint {
+    kind: u32,
+    stop_count: u32,
+    dithering: u32,
+    flags: u32,
+    // size of the surface drawn into, in pixels.
+    viewport: vec4<f32>,
+    // rows of the transformation from display coordinates back into the coordinates the paint was drawn in.
+    inverse_x: vec4<f32>,
+    inverse_y: vec4<f32>,
+    // gradients: start and end. checkerboards: origin and square size. images: where the image is drawn (origin and size).
+    // surfaces: opacity. blurs: direction and standard deviation.
+    geometry: vec4<f32>,
+    // images: the part of the image which is drawn, in texels (origin and size).
+    source: vec4<f32>,
+    // columns of a 4x5 color matrix, applied to unpremultiplied colors.
+    matrix: array<vec4<f32>, 5>,
+    colors: array<vec4<f32>, 16>,
+    offsets: array<vec4<f32>, 4>,
+}
+
+@group(0) @binding(0) var<uniform> paint: Paint;
+@group(1) @binding(0) var source_texture: texture_2d<f32>;
+@group(1) @binding(1) var source_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> VertexOutput {
+    var out: VertexOutput;
+    let ndc = position / paint.viewport.xy * 2.0 - 1.0;
+    out.position = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+    return out;
+}
+
+// a triangle covering the whole surface.
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    out.position = vec4<f32>(corner.x * 2.0 - 1.0, 1.0 - corner.y * 2.0, 0.0, 1.0);
+    return out;
+}
+
+fn premultiply(color: vec4<f32>) -> vec4<f32> {
+    let clamped = clamp(color, vec4<f32>(0.0), vec4<f32>(1.0));
+    return vec4<f32>(clamped.rgb * clamped.a, clamped.a);
+}
+
+fn unpremultiply(color: vec4<f32>) -> vec4<f32> {
+    if color.a <= 0.0 {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(color.rgb / color.a, color.a);
+}
+
+fn local_point(position: vec2<f32>) -> vec2<f32> {
+    let point = vec3<f32>(position, 1.0);
+    return vec2<f32>(dot(paint.inverse_x.xyz, point), dot(paint.inverse_y.xyz, point));
+}
+
+fn stop_offset(i: u32) -> f32 {
+    return paint.offsets[i / 4u][i % 4u];
+}
+
+// colors are interpolated unpremultiplied, as in Skia.
+fn gradient_color(position: f32) -> vec4<f32> {
+    let count = paint.stop_count;
+    if count == 0u {
+        return vec4<f32>(0.0);
+    }
+    let t = clamp(position, 0.0, 1.0);
+    if t <= stop_offset(0u) {
+        return premultiply(paint.colors[0]);
+    }
+
+    for (var i = 1u; i < count; i++) {
+        let start = stop_offset(i - 1u);
+        let end = stop_offset(i);
+        if t <= end {
+            var amount = 1.0;
+            if end > start {
+                amount = (t - start) / (end - start);
+            }
+            return premultiply(mix(paint.colors[i - 1u], paint.colors[i], amount));
+        }
+    }
+    return premultiply(paint.colors[count - 1u]);
+}
+
+// same as `Dithering::threshold`.
+fn dither_threshold(position: vec2<f32>) -> f32 {
+    let x = i32(floor(position.x));
+    let y = i32(floor(position.y));
+    if paint.dithering == 1u {
+        let xu = u32(x & 7);
+        let yu = u32(y & 7);
+        let xy = xu ^ yu;
+        let index = ((xy & 1u) << 5u) | ((yu & 1u) << 4u) | ((xy & 2u) << 2u) | ((yu & 2u) << 1u)
+            | ((xy & 4u) >> 1u) | ((yu & 4u) >> 2u);
+        return (f32(index) + 0.5) / 64.0 - 0.5;
+    } else if paint.dithering == 2u {
+        let noise = 0.06711056 * f32(x) + 0.00583715 * f32(y);
+        return fract(52.982918 * fract(noise)) - 0.5;
+    }
+    return 0.0;
+}
+
+fn sample_image(position: vec2<f32>) -> vec4<f32> {
+    let point = local_point(position);
+    let rect = paint.geometry;
+    let source = paint.source;
+    let texel = clamp(
+        source.xy + (point - rect.xy) / rect.zw * source.zw,
+        source.xy + 0.5,
+        max(source.xy + source.zw - 0.5, source.xy + 0.5),
+    );
+    let size = vec2<f32>(textureDimensions(source_texture));
+    let color = textureSampleLevel(source_texture, source_sampler, texel / size, 0.0);
+    if (paint.flags & ADJUSTED) == 0u {
+        return color;
+    }
+
+    let unpremultiplied = unpremultiply(color);
+    let m = paint.matrix;
+    return premultiply(
+        m[0] * unpremultiplied.r + m[1] * unpremultiplied.g + m[2] * unpremultiplied.b
+            + m[3] * unpremultiplied.a + m[4],
+    );
+}
+
+fn sample_surface(position: vec2<f32>) -> vec4<f32> {
+    var color = textureSampleLevel(source_texture, source_sampler, position / paint.viewport.xy, 0.0);
+    if (paint.flags & INVERTED) != 0u {
+        color = vec4<f32>(color.a - color.rgb, color.a);
+    }
+    return color * paint.geometry.x;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let position = in.position.xy;
+    switch paint.kind {
+        case LINEAR_GRADIENT: {
+            let point = local_point(position);
+            let start = paint.geometry.xy;
+            let axis = paint.geometry.zw - start;
+            let color = gradient_color(dot(point - start, axis) / max(dot(axis, axis), 1e-6));
+            let offset = dither_threshold(position) / 255.0;
+            return vec4<f32>(clamp(color.rgb + offset, vec3<f32>(0.0), vec3<f32>(color.a)), color.a);
+        }
+        case RADIAL_GRADIENT: {
+            let point = local_point(position);
+            let start = paint.geometry.xy;
+            let radius = max(length(paint.geometry.zw - start), 1e-6);
+            let color = gradient_color(length(point - start) / radius);
+            let offset = dither_threshold(position) / 255.0;
+            return vec4<f32>(clamp(color.rgb + offset, vec3<f32>(0.0), vec3<f32>(color.a)), color.a);
+        }
+        case CHECKERBOARD: {
+            let cell = floor((local_point(position) - paint.geometry.xy) / paint.geometry.z);
+            if (i32(cell.x) + i32(cell.y)) % 2 == 0 {
+                return premultiply(paint.colors[0]);
+            }
+            return premultiply(paint.colors[1]);
+        }
+        case IMAGE: {
+            return sample_image(position);
+        }
+        case SURFACE: {
+            return sample_surface(position);
+        }
+        default: {
+            return premultiply(paint.colors[0]);
+        }
+    }
+}
+
+// clips only write to the stencil.
+@fragment
+fn fs_clip() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0);
+}
+
+// one direction of a gaussian blur, treating everything outside the surface as transparent.
+@fragment
+fn fs_blur(in: VertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(source_texture));
+    let direction = paint.geometry.xy;
+    // a blur of nothing (or less) leaves the surface as it is.
+    let sigma = max(paint.geometry.z, 1e-3);
+    let extent = ceil(sigma * 3.0);
+    // wide blurs skip texels (which the linear sampler blends between).
+    let step = max(1.0, ceil(extent / 64.0));
+
+    var sum = vec4<f32>(0.0);
+    var weight = 0.0;
+    for (var offset = -extent; offset <= extent; offset += step) {
+        let w = exp(-offset * offset / (2.0 * sigma * sigma));
+        let position = in.position.xy + direction * offset;
+        weight += w;
+        if all(position >= vec2<f32>(0.0)) && all(position < size) {
+            sum += w * textureSampleLevel(source_texture, source_sampler, position / size, 0.0);
+        }
+    }
+    return sum / weight;
+}
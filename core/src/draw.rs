@@ -0,0 +1,719 @@
+//! Context passed down the widget tree while drawing.
+//!
+//! A [`DrawContext`](struct.DrawContext.html) bundles the display with the state of the subtree being drawn
+//! (its transform and clip) and the parameters of the frame (scale factor, theme, timing and accessibility preferences),
+//! such that widgets don't have to smuggle this information through the graphical auxiliary.
+//!
+//! ```ignore
+//! let mut cx = DrawContext::new(&mut display)
+//!     .with_scale_factor(window.scale_factor())
+//!     .with_theme(&theme)
+//!     .with_frame(clock.tick())
+//!     .with_accessibility(accessibility.preferences());
+//!
+//! root.draw_in_context(&mut cx, &mut ());
+//! ```
+//!
+//! The same [`FrameTiming`](struct.FrameTiming.html) is given to updates through the update auxiliary
+//! (see [`HasFrameTiming`](trait.HasFrameTiming.html)), such that animations read a single clock per frame.
+//!
+//! Rather than redrawing after every event, a [`FrameScheduler`](struct.FrameScheduler.html) decides whether anything changed
+//! since the last frame, such that the application idles while nothing does:
+//!
+//! ```ignore
+//! // after updating:
+//! if scheduler.needs_redraw() {
+//!     window.request_redraw();
+//! }
+//!
+//! // when the window asks for a redraw (at display refresh, if presenting waits for vsync):
+//! let frame = scheduler.begin_frame();
+//! root.draw_in_context(&mut DrawContext::new(&mut display).with_frame(frame), &mut ());
+//! ```
+
+use {
+    crate::{
+        accessibility::AccessibilityPreferences,
+        display::{
+            CommandGroupHandle, DisplayCommand, DisplayFilter, GraphicsDisplay, Rect, Transform,
+            TransformedDisplay,
+        },
+        simulation::Clock,
+        widget::{WidgetChildren, WidgetChildrenExt},
+    },
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+    std::{
+        any::Any,
+        cell::RefCell,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Timing of the frame being drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FrameTiming {
+    /// Number of frames drawn before this one.
+    pub index: u64,
+    /// Time since the first frame.
+    pub time: Duration,
+    /// Time since the previous frame.
+    pub dt: Duration,
+}
+
+/// Produces the [`FrameTiming`](struct.FrameTiming.html) of consecutive frames.
+#[derive(Debug, Clone, Default)]
+pub struct FrameClock {
+    start: Option<Instant>,
+    last: Option<FrameTiming>,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Advances to the next frame, as of now.
+    pub fn tick(&mut self) -> FrameTiming {
+        self.tick_at(Instant::now())
+    }
+
+    /// Advances to the next frame, as of the time of `clock` (e.g. a [`VirtualClock`](../simulation/struct.VirtualClock.html)).
+    pub fn tick_from(&mut self, clock: &dyn Clock) -> FrameTiming {
+        self.tick_at(clock.now())
+    }
+
+    /// Advances to the next frame, as of `now`.
+    pub fn tick_at(&mut self, now: Instant) -> FrameTiming {
+        let start = *self.start.get_or_insert(now);
+        let time = now.saturating_duration_since(start);
+        let frame = match self.last {
+            Some(last) => FrameTiming { index: last.index + 1, time, dt: time - last.time },
+            None => FrameTiming { index: 0, time, dt: Duration::default() },
+        };
+        self.last = Some(frame);
+        frame
+    }
+
+    /// Returns the timing of the last frame, if any.
+    #[inline]
+    pub fn last(&self) -> Option<FrameTiming> {
+        self.last
+    }
+}
+
+/// Access to the timing of the current frame from an update auxiliary.
+///
+/// The runner ticks a [`FrameClock`](struct.FrameClock.html) once per frame (before updating), and widgets animate by the
+/// resulting `dt` instead of each measuring time on their own:
+///
+/// ```ignore
+/// // in the event loop:
+/// globals.frame = clock.tick();
+/// root.update(&mut globals);
+///
+/// // in a widget:
+/// impl<A: HasFrameTiming> Widget for Spinner<A> {
+///     type UpdateAux = A;
+///
+///     fn update(&mut self, aux: &mut A) {
+///         self.angle += self.speed * aux.frame().dt.as_secs_f32();
+///     }
+/// }
+/// ```
+pub trait HasFrameTiming {
+    fn frame(&self) -> FrameTiming;
+}
+
+impl HasFrameTiming for FrameTiming {
+    #[inline]
+    fn frame(&self) -> FrameTiming {
+        *self
+    }
+}
+
+impl HasFrameTiming for FrameClock {
+    /// Returns the timing of the last frame, or that of the first frame if the clock hasn't been ticked.
+    #[inline]
+    fn frame(&self) -> FrameTiming {
+        self.last.unwrap_or_default()
+    }
+}
+
+/// Upper bound of fixed ticks emitted for a single frame, past which the remaining time is dropped
+/// (such that a slow frame doesn't cause ever more ticks to catch up on).
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// A step of fixed length emitted by a [`FrameScheduler`](struct.FrameScheduler.html) with a [tick rate](struct.FrameScheduler.html#method.with_tick_rate).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTick {
+    /// Number of ticks emitted before this one.
+    pub index: u64,
+    /// Length of the step, which is the same for every tick.
+    pub dt: Duration,
+}
+
+#[derive(Debug, Default)]
+struct RedrawRequests {
+    requested: bool,
+    repainted: Vec<CommandGroupHandle>,
+}
+
+/// Shared handle through which redraws are requested from a [`FrameScheduler`](struct.FrameScheduler.html).
+///
+/// Command groups hold one once [attached](../display/struct.CommandGroup.html#method.set_scheduler) to a scheduler,
+/// such that marking them for [repaint](../display/struct.CommandGroup.html#method.repaint) requests a redraw.
+#[derive(Debug, Clone, Default)]
+pub struct RedrawRequester(Rc<RefCell<RedrawRequests>>);
+
+impl RedrawRequester {
+    /// Requests that the next frame is drawn.
+    pub fn request_redraw(&self) {
+        self.0.borrow_mut().requested = true;
+    }
+
+    /// Requests that the next frame is drawn to repaint a command group (`None` if it hasn't been pushed yet).
+    pub fn request_repaint(&self, handle: Option<CommandGroupHandle>) {
+        let mut requests = self.0.borrow_mut();
+        requests.requested = true;
+        if let Some(handle) = handle {
+            if !requests.repainted.contains(&handle) {
+                requests.repainted.push(handle);
+            }
+        }
+    }
+}
+
+impl PartialEq for RedrawRequester {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Decides when frames are drawn, such that the application idles while nothing changes and animates at display refresh otherwise.
+///
+/// A frame is needed once anything requests a redraw (typically a command group marked for repaint),
+/// or if the previous frame requested the next one (see [`request_animation_frame`](struct.FrameScheduler.html#method.request_animation_frame)).
+/// Frames begin with [`begin_frame`](struct.FrameScheduler.html#method.begin_frame), which ticks the frame clock and clears the requests.
+///
+/// Optionally, fixed ticks are emitted to [`tick`](struct.FrameScheduler.html#structfield.tick) for each step of time elapsed between frames,
+/// for simulations which must advance at a constant rate regardless of the refresh rate.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    requests: RedrawRequester,
+    clock: FrameClock,
+    frame: FrameTiming,
+    animating: bool,
+    step: Option<Duration>,
+    accumulated: Duration,
+    ticks: u64,
+    pub tick: RcEventQueue<FixedTick>,
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameScheduler {
+    /// Creates a scheduler which needs a first frame, without fixed ticks.
+    pub fn new() -> Self {
+        let requests = RedrawRequester::default();
+        requests.request_redraw();
+        FrameScheduler {
+            requests,
+            clock: FrameClock::new(),
+            frame: FrameTiming::default(),
+            animating: false,
+            step: None,
+            accumulated: Duration::default(),
+            ticks: 0,
+            tick: RcEventQueue::new(),
+        }
+    }
+
+    /// Creates a scheduler which emits a fixed tick every `step` of time elapsed between frames.
+    pub fn with_tick_rate(step: Duration) -> Self {
+        let mut scheduler = Self::new();
+        scheduler.set_tick_rate(step);
+        scheduler
+    }
+
+    /// Changes the length of fixed ticks, or stops emitting them if `step` is zero.
+    pub fn set_tick_rate(&mut self, step: Duration) {
+        self.step = if step > Duration::default() { Some(step) } else { None };
+        self.accumulated = Duration::default();
+    }
+
+    /// Returns the length of fixed ticks, if they're emitted.
+    #[inline]
+    pub fn tick_rate(&self) -> Option<Duration> {
+        self.step
+    }
+
+    /// Returns a handle to request redraws from this scheduler.
+    #[inline]
+    pub fn requester(&self) -> RedrawRequester {
+        self.requests.clone()
+    }
+
+    /// Requests that the next frame is drawn.
+    #[inline]
+    pub fn request_redraw(&self) {
+        self.requests.request_redraw();
+    }
+
+    /// Requests that the frame after the current one is drawn too, such that frames are drawn continuously (e.g. while an animation is running).
+    ///
+    /// This should be invoked on every frame which should be followed by another, and has no effect past the next frame.
+    #[inline]
+    pub fn request_animation_frame(&mut self) {
+        self.animating = true;
+    }
+
+    /// Returns `true` if a frame should be drawn.
+    pub fn needs_redraw(&self) -> bool {
+        self.animating || self.requests.0.borrow().requested
+    }
+
+    /// Returns the (pushed) command groups marked for repaint since the last frame began, in the order they were marked.
+    pub fn repainted(&self) -> Vec<CommandGroupHandle> {
+        self.requests.0.borrow().repainted.clone()
+    }
+
+    /// Begins a frame as of now. See [`begin_frame_at`](struct.FrameScheduler.html#method.begin_frame_at).
+    #[inline]
+    pub fn begin_frame(&mut self) -> FrameTiming {
+        self.begin_frame_at(Instant::now())
+    }
+
+    /// Begins a frame as of the time of `clock` (e.g. a [`VirtualClock`](../simulation/struct.VirtualClock.html)).
+    #[inline]
+    pub fn begin_frame_from(&mut self, clock: &dyn Clock) -> FrameTiming {
+        self.begin_frame_at(clock.now())
+    }
+
+    /// Begins a frame as of `now`, clearing the requests for it and returning its timing, after emitting the fixed ticks elapsed since the previous frame.
+    ///
+    /// Time spent idle isn't elapsed; a frame which wasn't [requested by the previous frame](struct.FrameScheduler.html#method.request_animation_frame)
+    /// has a zero `dt`, such that animations starting in it don't skip ahead by the time the application idled for.
+    pub fn begin_frame_at(&mut self, now: Instant) -> FrameTiming {
+        let mut frame = self.clock.tick_at(now);
+        if !std::mem::replace(&mut self.animating, false) {
+            frame.dt = Duration::default();
+        }
+        self.frame = frame;
+
+        {
+            let mut requests = self.requests.0.borrow_mut();
+            requests.requested = false;
+            requests.repainted.clear();
+        }
+
+        if let Some(step) = self.step {
+            self.accumulated += frame.dt;
+            let mut emitted = 0;
+            while self.accumulated >= step {
+                if emitted == MAX_TICKS_PER_FRAME {
+                    self.accumulated = Duration::default();
+                    break;
+                }
+
+                self.accumulated -= step;
+                self.tick.emit_owned(FixedTick { index: self.ticks, dt: step });
+                self.ticks += 1;
+                emitted += 1;
+            }
+        }
+
+        frame
+    }
+
+    /// Returns a listener to the fixed ticks.
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<FixedTick> {
+        self.tick.listen()
+    }
+}
+
+impl HasFrameTiming for FrameScheduler {
+    /// Returns the timing of the frame which began last.
+    #[inline]
+    fn frame(&self) -> FrameTiming {
+        self.frame
+    }
+}
+
+/// Emitted to widgets through [`on_scale_factor_changed`](../widget/trait.Widget.html#method.on_scale_factor_changed)
+/// when the ratio of physical pixels to logical pixels changes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleFactorChanged {
+    pub old: f64,
+    pub new: f64,
+}
+
+impl ScaleFactorChanged {
+    /// Returns the factor to multiply physical sizes by to keep them the same logical size.
+    #[inline]
+    pub fn ratio(&self) -> f64 {
+        self.new / self.old
+    }
+}
+
+/// Changes the scale factor of `display` (discarding anything it rasterized at the previous scale factor)
+/// and notifies `root` and all its descendants, returning the change, or `None` if the scale factor is unchanged.
+///
+/// This should be invoked when the window reports a new scale factor (e.g. [`InputEvent::ScaleFactorChanged`](../input/enum.InputEvent.html#variant.ScaleFactorChanged)),
+/// followed by a redraw with the new scale factor in the [`DrawContext`](struct.DrawContext.html).
+/// Caches kept outside of the display, such as a [`ParagraphCache`](../display/struct.ParagraphCache.html) of text in physical pixels, should be cleared too.
+///
+/// ```ignore
+/// if let InputEvent::ScaleFactorChanged(scale_factor) = event {
+///     draw::change_scale_factor(&mut root, &mut display, scale_factor, &mut globals);
+///     globals.paragraphs.clear();
+/// }
+/// ```
+pub fn change_scale_factor<W: WidgetChildren + ?Sized>(
+    root: &mut W,
+    display: &mut dyn GraphicsDisplay<W::DisplayObject>,
+    scale_factor: f64,
+    aux: &mut W::UpdateAux,
+) -> Option<ScaleFactorChanged> {
+    let old = display.scale_factor();
+    if old == scale_factor {
+        return None;
+    }
+
+    display.set_scale_factor(scale_factor);
+    let event = ScaleFactorChanged { old, new: scale_factor };
+    root.on_scale_factor_changed(&event, aux);
+    root.propagate_scale_factor_changed(&event, aux);
+    Some(event)
+}
+
+/// The display and state of the subtree being drawn.
+pub struct DrawContext<'a, D: Sized = DisplayCommand> {
+    display: &'a mut dyn GraphicsDisplay<D>,
+    theme: Option<&'a dyn Any>,
+    disabled_filters: &'a [DisplayFilter],
+    /// Maps the local coordinate space of the subtree to that of the display.
+    pub transform: Transform,
+    /// The visible region in the local coordinate space of the subtree, if it's clipped.
+    pub clip: Option<Rect>,
+    /// Ratio of physical pixels to logical pixels.
+    pub scale_factor: f64,
+    pub frame: FrameTiming,
+    /// The [accessibility preferences](../accessibility/index.html), which the theme may adapt to (e.g. with high contrast).
+    pub accessibility: AccessibilityPreferences,
+    /// Whether the subtree is [disabled](../widget/trait.Widget.html#method.is_enabled), so that it can look disabled.
+    pub disabled: bool,
+}
+
+impl<D: Sized> std::fmt::Debug for DrawContext<'_, D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DrawContext")
+            .field("transform", &self.transform)
+            .field("clip", &self.clip)
+            .field("scale_factor", &self.scale_factor)
+            .field("frame", &self.frame)
+            .field("accessibility", &self.accessibility)
+            .field("disabled", &self.disabled)
+            .field("disabled_filters", &self.disabled_filters)
+            .finish()
+    }
+}
+
+impl<'a, D: Sized> DrawContext<'a, D> {
+    /// Creates a context for the root of the widget tree, with an identity transform, no clip and the scale factor of the display.
+    pub fn new(display: &'a mut dyn GraphicsDisplay<D>) -> Self {
+        DrawContext {
+            scale_factor: display.scale_factor(),
+            display,
+            theme: None,
+            disabled_filters: &[],
+            transform: Transform::identity(),
+            clip: None,
+            frame: Default::default(),
+            accessibility: Default::default(),
+            disabled: false,
+        }
+    }
+
+    pub fn with_scale_factor(mut self, scale_factor: f64) -> Self {
+        self.scale_factor = scale_factor;
+        self
+    }
+
+    /// Sets the theme, which widgets retrieve through [`theme`](struct.DrawContext.html#method.theme).
+    pub fn with_theme<T: Any>(mut self, theme: &'a T) -> Self {
+        self.theme = Some(theme);
+        self
+    }
+
+    pub fn with_frame(mut self, frame: FrameTiming) -> Self {
+        self.frame = frame;
+        self
+    }
+
+    pub fn with_accessibility(mut self, accessibility: AccessibilityPreferences) -> Self {
+        self.accessibility = accessibility;
+        self
+    }
+
+    pub fn with_clip(mut self, clip: Option<Rect>) -> Self {
+        self.clip = clip;
+        self
+    }
+
+    /// Sets the filters which disabled subtrees are drawn with (e.g. [`Grayscale`](../display/enum.DisplayFilter.html#variant.Grayscale)),
+    /// as applied by [`filter_disabled`](struct.DrawContext.html#method.filter_disabled). There are none by default.
+    pub fn with_disabled_filters(mut self, filters: &'a [DisplayFilter]) -> Self {
+        self.disabled_filters = filters;
+        self
+    }
+
+    /// Returns the theme, if one of type `T` was set.
+    #[inline]
+    pub fn theme<T: Any>(&self) -> Option<&T> {
+        self.theme.and_then(|theme| theme.downcast_ref())
+    }
+
+    /// Returns the display, such that command groups pushed through it are in the local coordinate space of the subtree,
+    /// and are scissored to the clip of the subtree, if any.
+    pub fn display(&mut self) -> TransformedDisplay<'_, D> {
+        let display = TransformedDisplay::new(&mut *self.display, self.transform);
+        match self.clip {
+            Some(clip) => display.with_scissor(self.transform.transform_rect(&clip)),
+            None => display,
+        }
+    }
+
+    /// Returns the underlying display, ignoring the transform of the subtree.
+    #[inline]
+    pub fn raw_display(&mut self) -> &mut dyn GraphicsDisplay<D> {
+        &mut *self.display
+    }
+
+    /// Creates the context of a subtree, whose local coordinate space is mapped to this one by `transform`.
+    ///
+    /// The clip of the subtree is the clip of this context (mapped into the subtree), intersected with `clip` (in the subtree).
+    pub fn child(&mut self, transform: Transform, clip: Option<Rect>) -> DrawContext<'_, D> {
+        let inherited = self
+            .clip
+            .and_then(|parent_clip| Some(transform.inverse()?.transform_rect(&parent_clip)));
+
+        let clip = match (inherited, clip) {
+            (Some(inherited), Some(clip)) => {
+                Some(inherited.intersection(&clip).unwrap_or_default())
+            }
+            (inherited, clip) => inherited.or(clip),
+        };
+
+        DrawContext {
+            display: &mut *self.display,
+            theme: self.theme,
+            disabled_filters: self.disabled_filters,
+            transform: transform.post_transform(&self.transform),
+            clip,
+            scale_factor: self.scale_factor,
+            frame: self.frame,
+            accessibility: self.accessibility,
+            disabled: self.disabled,
+        }
+    }
+}
+
+impl DrawContext<'_, DisplayCommand> {
+    /// Applies the [disabled filters](struct.DrawContext.html#method.with_disabled_filters) to the colors of `commands`
+    /// if the subtree is [disabled](struct.DrawContext.html#structfield.disabled), and otherwise leaves them as is.
+    ///
+    /// Widgets pass their display list through this before pushing it, so that they look disabled without styling it themselves.
+    pub fn filter_disabled(&self, commands: &mut [DisplayCommand]) {
+        if self.disabled {
+            for command in commands {
+                command.filter_colors(self.disabled_filters);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_clock() {
+        let start = Instant::now();
+        let mut clock = FrameClock::new();
+        assert_eq!(clock.last(), None);
+
+        assert_eq!(clock.tick_at(start), FrameTiming::default());
+        assert_eq!(clock.tick_at(start + Duration::from_millis(16)).dt, Duration::from_millis(16));
+        clock.tick_at(start + Duration::from_millis(40));
+        assert_eq!(
+            clock.last(),
+            Some(FrameTiming {
+                index: 2,
+                time: Duration::from_millis(40),
+                dt: Duration::from_millis(24)
+            })
+        );
+    }
+
+    #[test]
+    fn test_has_frame_timing() {
+        struct Globals {
+            clock: FrameClock,
+        }
+
+        impl HasFrameTiming for Globals {
+            fn frame(&self) -> FrameTiming {
+                self.clock.frame()
+            }
+        }
+
+        fn animate<A: HasFrameTiming>(aux: &A, elapsed: &mut Duration) {
+            *elapsed += aux.frame().dt;
+        }
+
+        let start = Instant::now();
+        let mut globals = Globals { clock: FrameClock::new() };
+        let mut elapsed = Duration::default();
+        assert_eq!(globals.frame(), FrameTiming::default());
+
+        for i in 0..4 {
+            globals.clock.tick_at(start + Duration::from_millis(i * 10));
+            animate(&globals, &mut elapsed);
+        }
+        assert_eq!(elapsed, Duration::from_millis(30));
+        assert_eq!(globals.frame().index, 3);
+    }
+
+    #[test]
+    fn test_frame_scheduler() {
+        use crate::display::{software::SoftwareGraphicsDisplay, CommandGroup};
+
+        let start = Instant::now();
+        let mut display = SoftwareGraphicsDisplay::new((4, 4));
+        let mut scheduler = FrameScheduler::new();
+        assert!(scheduler.needs_redraw());
+        scheduler.begin_frame_at(start);
+        assert!(!scheduler.needs_redraw());
+
+        let mut command_group = CommandGroup::new();
+        command_group.set_scheduler(&scheduler);
+        assert!(scheduler.needs_redraw());
+        assert!(scheduler.repainted().is_empty());
+        scheduler.begin_frame_at(start + Duration::from_millis(10));
+        command_group.push(&mut display, &[], Default::default(), None, None);
+
+        command_group.repaint();
+        command_group.repaint();
+        assert!(scheduler.needs_redraw());
+        assert_eq!(scheduler.repainted(), vec![command_group.handle().unwrap()]);
+
+        // the application idled since the last frame.
+        let frame = scheduler.begin_frame_at(start + Duration::from_secs(5));
+        assert_eq!(frame.dt, Duration::default());
+        assert!(scheduler.repainted().is_empty());
+
+        scheduler.request_animation_frame();
+        assert!(scheduler.needs_redraw());
+        let frame = scheduler.begin_frame_at(start + Duration::from_millis(5016));
+        assert_eq!(frame.dt, Duration::from_millis(16));
+        assert_eq!(scheduler.frame(), frame);
+        assert!(!scheduler.needs_redraw());
+    }
+
+    #[test]
+    fn test_fixed_ticks() {
+        let start = Instant::now();
+        let mut scheduler = FrameScheduler::with_tick_rate(Duration::from_millis(10));
+        let listener = scheduler.listen();
+
+        scheduler.begin_frame_at(start);
+        scheduler.request_animation_frame();
+        scheduler.begin_frame_at(start + Duration::from_millis(25));
+        scheduler.request_animation_frame();
+        scheduler.begin_frame_at(start + Duration::from_millis(30));
+        assert_eq!(
+            listener.peek(),
+            (0..3)
+                .map(|index| FixedTick { index, dt: Duration::from_millis(10) })
+                .collect::<Vec<_>>()
+        );
+
+        // a long frame emits a bounded number of ticks.
+        scheduler.request_animation_frame();
+        scheduler.begin_frame_at(start + Duration::from_secs(1));
+        assert_eq!(listener.peek().len(), MAX_TICKS_PER_FRAME as usize);
+
+        scheduler.set_tick_rate(Duration::default());
+        assert_eq!(scheduler.tick_rate(), None);
+        scheduler.request_animation_frame();
+        scheduler.begin_frame_at(start + Duration::from_secs(2));
+        assert!(listener.peek().is_empty());
+    }
+
+    // keeps its font size in physical pixels.
+    struct Label(f64, Vec<Label>);
+
+    impl crate::widget::Widget for Label {
+        type UpdateAux = Vec<f64>;
+        type GraphicalAux = ();
+        type DisplayObject = DisplayCommand;
+
+        fn on_scale_factor_changed(&mut self, event: &ScaleFactorChanged, aux: &mut Vec<f64>) {
+            self.0 *= event.ratio();
+            aux.push(self.0);
+        }
+    }
+
+    impl WidgetChildren for Label {
+        fn children(
+            &self,
+        ) -> Vec<
+            &dyn WidgetChildren<
+                UpdateAux = Vec<f64>,
+                GraphicalAux = (),
+                DisplayObject = DisplayCommand,
+            >,
+        > {
+            self.1.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<
+            &mut dyn WidgetChildren<
+                UpdateAux = Vec<f64>,
+                GraphicalAux = (),
+                DisplayObject = DisplayCommand,
+            >,
+        > {
+            self.1.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_change_scale_factor() {
+        let mut display = crate::display::software::SoftwareGraphicsDisplay::new((4, 4));
+        let mut root = Label(12.0, vec![Label(10.0, vec![Label(8.0, vec![])])]);
+        let mut sizes = Vec::new();
+
+        assert_eq!(change_scale_factor(&mut root, &mut display, 1.0, &mut sizes), None);
+        assert!(sizes.is_empty());
+
+        assert_eq!(
+            change_scale_factor(&mut root, &mut display, 2.0, &mut sizes),
+            Some(ScaleFactorChanged { old: 1.0, new: 2.0 })
+        );
+        assert_eq!(sizes, vec![24.0, 20.0, 16.0]);
+        assert_eq!(DrawContext::new(&mut display).scale_factor, 2.0);
+
+        sizes.clear();
+        change_scale_factor(&mut root, &mut display, 1.5, &mut sizes);
+        assert_eq!(sizes, vec![18.0, 15.0, 12.0]);
+    }
+}
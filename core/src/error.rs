@@ -25,6 +25,26 @@ pub enum SkiaError {
     UnknownError,
 }
 
+/// Backend-agnostic classification of display and resource errors.
+///
+/// Widgets handling failures (e.g. of [`new_resource`](../display/trait.GraphicsDisplay.html#tymethod.new_resource))
+/// should match on this rather than on individual error variants, which may be backend-specific.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayErrorKind {
+    /// Resource data couldn't be read or decoded.
+    ResourceDecode,
+    /// A resource reference doesn't exist or is of the wrong type.
+    InvalidResource,
+    /// The backend ran out of (CPU or GPU) memory.
+    OutOfMemory,
+    /// The backend doesn't support the requested operation or format.
+    Unsupported,
+    /// The underlying graphics context was lost (e.g. GPU reset) or is unavailable.
+    ContextLost,
+    /// Anything else.
+    Other,
+}
+
 /// An error associated with loading graphical resources.
 #[derive(Error, Debug)]
 pub enum ResourceError {
@@ -34,10 +54,28 @@ pub enum ResourceError {
     IoError(#[from] std::io::Error),
     #[error("given resource data is invalid and cannot be read/decoded")]
     InvalidData,
+    #[error("out of memory")]
+    OutOfMemory,
+    #[error("unsupported resource: {0}")]
+    Unsupported(String),
     #[error("{0}")]
     InternalError(#[from] Box<dyn std::error::Error>),
 }
 
+impl ResourceError {
+    /// Returns the backend-agnostic kind of this error.
+    pub fn kind(&self) -> DisplayErrorKind {
+        match self {
+            ResourceError::InvalidPath(_)
+            | ResourceError::IoError(_)
+            | ResourceError::InvalidData => DisplayErrorKind::ResourceDecode,
+            ResourceError::OutOfMemory => DisplayErrorKind::OutOfMemory,
+            ResourceError::Unsupported(_) => DisplayErrorKind::Unsupported,
+            ResourceError::InternalError(_) => DisplayErrorKind::Other,
+        }
+    }
+}
+
 /// An error related to [`GraphicsDisplay`](../display/trait.GraphicsDisplay.html).
 #[derive(Error, Debug)]
 pub enum DisplayError {
@@ -47,6 +85,82 @@ pub enum DisplayError {
     InvalidResource(u64),
     #[error("mismatched resource reference type (id: {0})")]
     MismatchedResource(u64),
+    #[error("out of memory")]
+    OutOfMemory,
+    #[error("unsupported operation: {0}")]
+    Unsupported(String),
+    #[error("graphics context lost")]
+    ContextLost,
+    /// An image failed to load and was replaced by a placeholder
+    /// (see [`new_resource_or_placeholder`](../display/trait.GraphicsDisplay.html#method.new_resource_or_placeholder)).
+    #[error("image replaced by a placeholder: {error}")]
+    BrokenImage {
+        /// The file the image was loaded from, if any.
+        source_file: Option<std::path::PathBuf>,
+        #[source]
+        error: ResourceError,
+    },
     #[error("{0}")]
     InternalError(#[from] Box<dyn std::error::Error>),
 }
+
+impl DisplayError {
+    /// Returns the backend-agnostic kind of this error.
+    pub fn kind(&self) -> DisplayErrorKind {
+        match self {
+            DisplayError::ResourceError(err) | DisplayError::BrokenImage { error: err, .. } => {
+                err.kind()
+            }
+            DisplayError::InvalidResource(_) | DisplayError::MismatchedResource(_) => {
+                DisplayErrorKind::InvalidResource
+            }
+            DisplayError::OutOfMemory => DisplayErrorKind::OutOfMemory,
+            DisplayError::Unsupported(_) => DisplayErrorKind::Unsupported,
+            DisplayError::ContextLost => DisplayErrorKind::ContextLost,
+            DisplayError::InternalError(_) => DisplayErrorKind::Other,
+        }
+    }
+}
+
+/// An error from a [clipboard backend](../clipboard/trait.ClipboardBackend.html).
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[error("the clipboard is unavailable")]
+    Unavailable,
+    #[error("unsupported clipboard content: {0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    InternalError(#[from] Box<dyn std::error::Error>),
+}
+
+#[cfg(feature = "skia")]
+impl From<SkiaError> for DisplayError {
+    fn from(err: SkiaError) -> Self {
+        match err {
+            SkiaError::InvalidContext => DisplayError::ContextLost,
+            SkiaError::InvalidTarget(target) => {
+                DisplayError::Unsupported(format!("OpenGL target {}", target))
+            }
+            err => DisplayError::InternalError(Box::new(err)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(
+            DisplayError::from(ResourceError::InvalidData).kind(),
+            DisplayErrorKind::ResourceDecode
+        );
+        assert_eq!(DisplayError::InvalidResource(3).kind(), DisplayErrorKind::InvalidResource);
+        assert_eq!(DisplayError::ContextLost.kind(), DisplayErrorKind::ContextLost);
+        assert_eq!(
+            DisplayError::from(Box::<dyn std::error::Error>::from("oops")).kind(),
+            DisplayErrorKind::Other
+        );
+    }
+}
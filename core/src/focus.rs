@@ -0,0 +1,660 @@
+//! Keyboard focus.
+//!
+//! A [`FocusTracker`](struct.FocusTracker.html) (typically kept in the update auxiliary) decides which widget has keyboard focus;
+//! widgets opt in through [`accepts_focus`](../widget/trait.Widget.html#method.accepts_focus) and are notified through
+//! [`on_focus_changed`](../widget/trait.Widget.html#method.on_focus_changed). Keyboard and text events are then dispatched to the focused widget,
+//! Tab and Shift+Tab move focus, and pressing a pointer button focuses the widget beneath it:
+//!
+//! ```ignore
+//! for event in self.input_listener.peek() {
+//!     if !event.is_keyboard() {
+//!         input::dispatch_pointer(&mut root, &event, &mut aux);
+//!     }
+//!     // dispatches keyboard events, and handles tab navigation and clicks.
+//!     focus.handle(&mut root, &event, &mut aux);
+//! }
+//! ```
+//!
+//! Focus rings are drawn through [`push_focus_ring`](fn.push_focus_ring.html) with a [`FocusRingTheme`](struct.FocusRingTheme.html),
+//! such that focus looks the same across widget libraries sharing the theme, and only while [`FocusVisibility`](struct.FocusVisibility.html)
+//! says the keyboard is being used to navigate:
+//!
+//! ```ignore
+//! // in update:
+//! aux.focus_visibility.handle(&event);
+//!
+//! // in draw:
+//! if self.focused && aux.focus_visibility.is_visible() {
+//!     let theme = aux.focus_ring.adapted(&cx.accessibility);
+//!     focus::push_focus_ring(&mut builder, self.bounds(), [4.0; 4], &theme);
+//! }
+//! ```
+
+use crate::{
+    accessibility::AccessibilityPreferences,
+    display::{
+        Color, DisplayListBuilder, GraphicsDisplayPaint, GraphicsDisplayStroke, Rect,
+        StrokeAlignment, VectorPath,
+    },
+    input::{self, InputEvent, Key, WidgetPath},
+    widget::WidgetChildren,
+};
+
+/// Colors and metrics of focus rings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FocusRingTheme {
+    pub color: Color,
+    /// Color used instead of [`color`](struct.FocusRingTheme.html#structfield.color) when high contrast is preferred.
+    pub high_contrast_color: Color,
+    pub thickness: f32,
+    /// Gap between the outline of the widget and the ring.
+    pub offset: f32,
+    /// Minimum corner radius of the ring, such that rings around square widgets are still rounded.
+    pub corner_radius: f32,
+}
+
+impl Default for FocusRingTheme {
+    fn default() -> Self {
+        FocusRingTheme {
+            color: Color::new(0.1, 0.45, 0.95, 0.8),
+            high_contrast_color: Color::new(0.0, 0.0, 0.0, 1.0),
+            thickness: 2.0,
+            offset: 1.0,
+            corner_radius: 3.0,
+        }
+    }
+}
+
+impl FocusRingTheme {
+    /// Returns the theme adjusted for accessibility preferences; the ring is scaled, and made opaque and thicker for high contrast.
+    pub fn adapted(&self, preferences: &AccessibilityPreferences) -> Self {
+        let mut theme = *self;
+        if preferences.high_contrast {
+            theme.color = theme.high_contrast_color;
+            theme.thickness += 1.0;
+        }
+        theme.thickness *= preferences.scale;
+        theme.offset *= preferences.scale;
+        theme.corner_radius *= preferences.scale;
+        theme
+    }
+
+    fn stroke(&self) -> GraphicsDisplayPaint {
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            color: self.color.into(),
+            thickness: self.thickness,
+            alignment: StrokeAlignment::Outside,
+            ..Default::default()
+        })
+    }
+}
+
+/// Returns the outline the ring is drawn outside of (the rectangle and its corner radii), given the bounds and corner radii of the widget.
+///
+/// The ring itself extends a further [`thickness`](struct.FocusRingTheme.html#structfield.thickness) beyond this.
+pub fn focus_ring_outline(
+    bounds: Rect,
+    radii: [f32; 4],
+    theme: &FocusRingTheme,
+) -> (Rect, [f32; 4]) {
+    let outline = bounds.inflate(theme.offset, theme.offset);
+    let mut ring_radii = radii;
+    for radius in &mut ring_radii {
+        *radius = (*radius + theme.offset).max(theme.corner_radius);
+    }
+    (outline, ring_radii)
+}
+
+/// Pushes a ring around a widget, given its bounds and corner radii (from top-left, top-right, bottom-left, bottom-right).
+pub fn push_focus_ring(
+    builder: &mut DisplayListBuilder,
+    bounds: Rect,
+    radii: [f32; 4],
+    theme: &FocusRingTheme,
+) {
+    if theme.thickness <= 0.0 {
+        return;
+    }
+
+    let (outline, radii) = focus_ring_outline(bounds, radii, theme);
+    builder.push_round_rectangle(outline, radii, theme.stroke(), None);
+}
+
+/// Pushes a ring along the outside of a closed path, for widgets which aren't (rounded) rectangles.
+///
+/// Paths can't be offset, so the ring touches the path; the path should already enclose the widget with a gap if desired.
+pub fn push_focus_ring_path(
+    builder: &mut DisplayListBuilder,
+    path: VectorPath,
+    theme: &FocusRingTheme,
+) {
+    if theme.thickness <= 0.0 {
+        return;
+    }
+
+    builder.push_path(path, true, theme.stroke(), None);
+}
+
+/// Keeps track of which widget has keyboard focus, identified by its [path](../input/type.WidgetPath.html).
+///
+/// See the [module documentation](index.html).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusTracker {
+    focused: Option<WidgetPath>,
+}
+
+impl FocusTracker {
+    /// Creates a new tracker with nothing focused.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the path of the focused widget.
+    #[inline]
+    pub fn focused(&self) -> Option<&[usize]> {
+        self.focused.as_deref()
+    }
+
+    /// Returns `true` if the widget at `path` is focused.
+    #[inline]
+    pub fn is_focused(&self, path: &[usize]) -> bool {
+        self.focused.as_deref() == Some(path)
+    }
+
+    /// Returns `true` if the widget at `path` or one of its descendants is focused (like `:focus-within` on the web).
+    pub fn contains_focus(&self, path: &[usize]) -> bool {
+        self.focused.iter().any(|focused| focused.starts_with(path))
+    }
+
+    /// Moves focus to the widget at `path` (or removes focus, given `None`), notifying the widget which lost focus and the one which gained it.
+    ///
+    /// Returns `false` (leaving focus unchanged) if the widget doesn't exist or can't be [focused](fn.can_focus.html).
+    pub fn focus<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &mut W,
+        path: Option<WidgetPath>,
+        aux: &mut W::UpdateAux,
+    ) -> bool {
+        if path.as_ref().is_some_and(|path| !can_focus(root, path)) {
+            return false;
+        }
+
+        if path != self.focused {
+            if let Some(old) = std::mem::replace(&mut self.focused, path) {
+                notify(root, &old, false, aux);
+            }
+            if let Some(new) = &self.focused {
+                notify(root, new, true, aux);
+            }
+        }
+
+        true
+    }
+
+    /// Moves focus to the next widget in [focus order](fn.focus_order.html) (wrapping around), returning `false` if nothing can be focused.
+    pub fn focus_next<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &mut W,
+        aux: &mut W::UpdateAux,
+    ) -> bool {
+        self.step(root, aux, false)
+    }
+
+    /// Moves focus to the previous widget in [focus order](fn.focus_order.html) (wrapping around), returning `false` if nothing can be focused.
+    pub fn focus_previous<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &mut W,
+        aux: &mut W::UpdateAux,
+    ) -> bool {
+        self.step(root, aux, true)
+    }
+
+    fn step<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &mut W,
+        aux: &mut W::UpdateAux,
+        backwards: bool,
+    ) -> bool {
+        let order = focus_order(root);
+        if order.is_empty() {
+            return false;
+        }
+
+        let current =
+            self.focused.as_ref().and_then(|focused| order.iter().position(|path| path == focused));
+        let next = match (current, backwards) {
+            (Some(i), false) => (i + 1) % order.len(),
+            (Some(i), true) => (i + order.len() - 1) % order.len(),
+            (None, false) => 0,
+            (None, true) => order.len() - 1,
+        };
+
+        self.focus(root, Some(order[next].clone()), aux)
+    }
+
+    /// Consumes an input event, returning `true` if it was handled.
+    ///
+    /// Keyboard events ([`is_keyboard`](../input/enum.InputEvent.html#method.is_keyboard)) are [dispatched](../input/fn.dispatch.html) to the focused widget
+    /// (or to `root` if nothing is focused), such that its ancestors can intercept shortcuts in the capture phase.
+    /// Unless propagation was stopped (e.g. by a widget which inserts tabs), Tab then moves focus forwards, and Shift+Tab backwards.
+    ///
+    /// Pressing a pointer button focuses the closest focusable widget at or above the one beneath the pointer, or removes focus if there isn't one.
+    /// Note that the pointer event itself isn't dispatched here.
+    pub fn handle<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &mut W,
+        event: &InputEvent,
+        aux: &mut W::UpdateAux,
+    ) -> bool {
+        match event {
+            InputEvent::PointerDown { position, .. } => {
+                let target = input::hit_test(root, *position).and_then(|mut path| loop {
+                    if can_focus(root, &path) {
+                        break Some(path);
+                    }
+                    path.pop()?;
+                });
+                self.focus(root, target, aux);
+                false
+            }
+            event if event.is_keyboard() => {
+                self.validate(root, aux);
+                let target = self.focused.clone().unwrap_or_default();
+                if input::dispatch(root, &target, event, aux) {
+                    return true;
+                }
+
+                match event {
+                    InputEvent::KeyDown { key: Key::Tab, modifiers, .. }
+                        if !(modifiers.ctrl || modifiers.alt || modifiers.logo) =>
+                    {
+                        self.step(root, aux, modifiers.shift)
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes focus if the focused widget no longer exists or can no longer be focused (e.g. it was hidden or disabled).
+    ///
+    /// This should be invoked when the widget tree changes; [`handle`](struct.FocusTracker.html#method.handle) does so before dispatching keyboard events.
+    /// Note that if the widget itself was removed, it isn't notified, and if another widget took its path, that one is focused instead.
+    pub fn validate<W: WidgetChildren + ?Sized>(&mut self, root: &mut W, aux: &mut W::UpdateAux) {
+        if self.focused.as_ref().is_some_and(|focused| !can_focus(root, focused)) {
+            if let Some(old) = self.focused.take() {
+                notify(root, &old, false, aux);
+            }
+        }
+    }
+}
+
+/// Returns `true` if the widget at `path` exists, [accepts focus](../widget/trait.Widget.html#method.accepts_focus),
+/// and neither it nor any of its ancestors are invisible or disabled.
+pub fn can_focus<W: WidgetChildren + ?Sized>(root: &W, path: &[usize]) -> bool {
+    if !root.is_visible() || !root.is_enabled() {
+        return false;
+    }
+
+    match path.split_first() {
+        Some((&i, rest)) => {
+            root.children().into_iter().nth(i).is_some_and(|child| can_focus(child, rest))
+        }
+        None => root.accepts_focus(),
+    }
+}
+
+/// Returns the paths of all the widgets which [can be focused](fn.can_focus.html), in the order tab navigation visits them;
+/// the order of [`children`](../widget/trait.WidgetChildren.html#method.children), parents before their children.
+pub fn focus_order<W: WidgetChildren + ?Sized>(root: &W) -> Vec<WidgetPath> {
+    let mut order = Vec::new();
+    collect_focusable(root, &mut Vec::new(), &mut order);
+    order
+}
+
+fn collect_focusable<W: WidgetChildren + ?Sized>(
+    widget: &W,
+    path: &mut WidgetPath,
+    order: &mut Vec<WidgetPath>,
+) {
+    if !widget.is_visible() || !widget.is_enabled() {
+        return;
+    }
+
+    if widget.accepts_focus() {
+        order.push(path.clone());
+    }
+
+    for (i, child) in widget.children().into_iter().enumerate() {
+        path.push(i);
+        collect_focusable(child, path, order);
+        path.pop();
+    }
+}
+
+fn notify<W: WidgetChildren + ?Sized>(
+    widget: &mut W,
+    path: &[usize],
+    focused: bool,
+    aux: &mut W::UpdateAux,
+) {
+    match path.split_first() {
+        Some((&i, rest)) => {
+            if let Some(child) = widget.children_mut().into_iter().nth(i) {
+                notify(child, rest, focused, aux);
+            }
+        }
+        None => widget.on_focus_changed(focused, aux),
+    }
+}
+
+/// Decides whether focus rings are shown, which is only while the keyboard is used to navigate (like `:focus-visible` on the web).
+///
+/// Focus rings appear once a key is pressed, and disappear again once a pointer button is pressed,
+/// since pointer users can already see what they clicked.
+/// Keyboard shortcuts (with Ctrl, Alt or the logo key held) and modifier keys on their own don't show focus rings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FocusVisibility {
+    visible: bool,
+}
+
+impl FocusVisibility {
+    /// Creates a new visibility with focus rings hidden.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Consumes an input event, returning `true` if the visibility changed (in which case focused widgets should be repainted).
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        let visible = match *event {
+            InputEvent::KeyDown { key, modifiers, .. } => {
+                let is_modifier = matches!(
+                    key,
+                    Key::LShift
+                        | Key::RShift
+                        | Key::LControl
+                        | Key::RControl
+                        | Key::LAlt
+                        | Key::RAlt
+                        | Key::LLogo
+                        | Key::RLogo
+                );
+                if is_modifier || modifiers.ctrl || modifiers.alt || modifiers.logo {
+                    return false;
+                }
+                true
+            }
+            InputEvent::PointerDown { .. } => false,
+            _ => return false,
+        };
+
+        self.set_visible(visible)
+    }
+
+    /// Returns `true` if focus rings should be drawn.
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides focus rings (e.g. showing them when focus is moved programmatically in response to a key), returning `true` if this changed.
+    pub fn set_visible(&mut self, visible: bool) -> bool {
+        std::mem::replace(&mut self.visible, visible) != visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::{DisplayCommand, DisplayItem, GraphicsDisplayItem, Point, Size},
+            input::{Modifiers, MouseButton, PropagatingEvent},
+            widget::Widget,
+        },
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Log {
+        Focus(&'static str, bool),
+        Key(&'static str, InputEvent),
+    }
+
+    struct Field {
+        name: &'static str,
+        bounds: Rect,
+        focusable: bool,
+        enabled: bool,
+        // swallows tabs, like a code editor would.
+        takes_tab: bool,
+        children: Vec<Field>,
+    }
+
+    fn field(name: &'static str, x: f32, focusable: bool, children: Vec<Field>) -> Field {
+        Field {
+            name,
+            bounds: Rect::new(Point::new(x, 0.0), Size::new(10.0, 10.0)),
+            focusable,
+            enabled: true,
+            takes_tab: false,
+            children,
+        }
+    }
+
+    impl Widget for Field {
+        type UpdateAux = Vec<Log>;
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.bounds
+        }
+
+        fn is_enabled(&self) -> bool {
+            self.enabled
+        }
+
+        fn accepts_focus(&self) -> bool {
+            self.focusable
+        }
+
+        fn on_focus_changed(&mut self, focused: bool, aux: &mut Vec<Log>) {
+            aux.push(Log::Focus(self.name, focused));
+        }
+
+        fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, aux: &mut Vec<Log>) {
+            if event.target() == event.current() {
+                aux.push(Log::Key(self.name, event.event().clone()));
+                if self.takes_tab {
+                    event.stop_propagation();
+                }
+            }
+        }
+    }
+
+    impl WidgetChildren for Field {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = Vec<Log>, GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.children.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<&mut dyn WidgetChildren<UpdateAux = Vec<Log>, GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.children.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    fn form() -> Field {
+        let mut root = field(
+            "root",
+            0.0,
+            false,
+            vec![
+                field("name", 0.0, true, vec![]),
+                field("group", 20.0, true, vec![field("inner", 20.0, false, vec![])]),
+                field("disabled", 40.0, true, vec![]),
+                field("email", 60.0, true, vec![]),
+            ],
+        );
+        root.bounds.size = Size::new(100.0, 10.0);
+        root.children[2].enabled = false;
+        root
+    }
+
+    fn key_down(key: Key, shift: bool) -> InputEvent {
+        InputEvent::KeyDown {
+            key,
+            scancode: 0,
+            modifiers: Modifiers { shift, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_focus_order() {
+        let root = form();
+        assert_eq!(focus_order(&root), vec![vec![0], vec![1], vec![3]]);
+        assert!(can_focus(&root, &[1]));
+        assert!(!can_focus(&root, &[1, 0]));
+        assert!(!can_focus(&root, &[2]));
+        assert!(!can_focus(&root, &[7]));
+    }
+
+    #[test]
+    fn test_focus_tracking() {
+        let mut root = form();
+        let mut log = Vec::new();
+        let mut focus = FocusTracker::new();
+
+        assert!(focus.handle(&mut root, &key_down(Key::Tab, false), &mut log));
+        assert_eq!(focus.focused(), Some(&[0][..]));
+        assert!(focus.handle(&mut root, &key_down(Key::Tab, false), &mut log));
+        assert!(focus.contains_focus(&[]));
+        assert!(focus.is_focused(&[1]));
+        log.clear();
+
+        // disabled widgets are skipped, and navigation wraps around.
+        focus.handle(&mut root, &key_down(Key::Tab, false), &mut log);
+        focus.handle(&mut root, &key_down(Key::Tab, false), &mut log);
+        assert_eq!(focus.focused(), Some(&[0][..]));
+        focus.handle(&mut root, &key_down(Key::Tab, true), &mut log);
+        assert_eq!(focus.focused(), Some(&[3][..]));
+        assert_eq!(
+            log.iter().filter(|entry| matches!(entry, Log::Focus(..))).cloned().collect::<Vec<_>>(),
+            vec![
+                Log::Focus("group", false),
+                Log::Focus("email", true),
+                Log::Focus("email", false),
+                Log::Focus("name", true),
+                Log::Focus("name", false),
+                Log::Focus("email", true),
+            ]
+        );
+
+        // text goes to the focused widget.
+        log.clear();
+        assert!(!focus.handle(&mut root, &InputEvent::TextInput('a'), &mut log));
+        assert_eq!(log, vec![Log::Key("email", InputEvent::TextInput('a'))]);
+
+        // widgets which stop propagation keep focus on tab.
+        root.children[3].takes_tab = true;
+        assert!(focus.handle(&mut root, &key_down(Key::Tab, false), &mut log));
+        assert_eq!(focus.focused(), Some(&[3][..]));
+
+        // clicking focuses the closest focusable ancestor, or nothing.
+        log.clear();
+        let click = |x| InputEvent::PointerDown {
+            position: Point::new(x, 5.0),
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        };
+        focus.handle(&mut root, &click(25.0), &mut log);
+        assert_eq!(focus.focused(), Some(&[1][..]));
+        focus.handle(&mut root, &click(95.0), &mut log);
+        assert_eq!(focus.focused(), None);
+        assert_eq!(
+            log,
+            vec![Log::Focus("email", false), Log::Focus("group", true), Log::Focus("group", false)]
+        );
+
+        // focus is lost once the widget can't be focused anymore.
+        assert!(!focus.focus(&mut root, Some(vec![2]), &mut log));
+        assert!(focus.focus(&mut root, Some(vec![0]), &mut log));
+        root.children[0].enabled = false;
+        log.clear();
+        focus.validate(&mut root, &mut log);
+        assert_eq!(focus.focused(), None);
+        assert_eq!(log, vec![Log::Focus("name", false)]);
+    }
+
+    #[test]
+    fn test_focus_ring() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 20.0));
+        let theme = FocusRingTheme::default();
+
+        let mut builder = DisplayListBuilder::new();
+        push_focus_ring(&mut builder, bounds, [0.0, 0.0, 6.0, 6.0], &theme);
+        match &builder.build()[..] {
+            [DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::RoundRectangle { rect, radii, paint }),
+                None,
+            )] => {
+                assert_eq!(*rect, Rect::new(Point::new(9.0, 9.0), Size::new(102.0, 22.0)));
+                // square corners still get the minimum radius, and round corners stay concentric.
+                assert_eq!(*radii, [3.0, 3.0, 7.0, 7.0]);
+                assert!(matches!(
+                    paint,
+                    GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                        alignment: StrokeAlignment::Outside,
+                        ..
+                    })
+                ));
+            }
+            _ => panic!("expected a single rounded rectangle"),
+        }
+
+        let adapted = theme.adapted(&AccessibilityPreferences {
+            high_contrast: true,
+            scale: 2.0,
+            ..Default::default()
+        });
+        assert_eq!(adapted.color, theme.high_contrast_color);
+        assert_eq!((adapted.thickness, adapted.offset), (6.0, 2.0));
+
+        let mut builder = DisplayListBuilder::new();
+        push_focus_ring(
+            &mut builder,
+            bounds,
+            [0.0; 4],
+            &FocusRingTheme { thickness: 0.0, ..theme },
+        );
+        assert!(builder.build().is_empty());
+    }
+
+    #[test]
+    fn test_focus_visibility() {
+        let key = |key, modifiers| InputEvent::KeyDown { key, scancode: 0, modifiers };
+        let mut visibility = FocusVisibility::new();
+        assert!(!visibility.is_visible());
+
+        let ctrl = Modifiers { ctrl: true, ..Default::default() };
+        assert!(!visibility.handle(&key(Key::C, ctrl)));
+        assert!(!visibility.handle(&key(Key::LShift, Modifiers::default())));
+        assert!(!visibility.is_visible());
+
+        assert!(visibility.handle(&key(Key::Tab, Modifiers::default())));
+        assert!(!visibility.handle(&key(Key::Down, Modifiers::default())));
+        assert!(visibility.is_visible());
+
+        assert!(visibility.handle(&InputEvent::PointerDown {
+            position: Point::new(0.0, 0.0),
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        }));
+        assert!(!visibility.is_visible());
+    }
+}
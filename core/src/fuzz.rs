@@ -0,0 +1,540 @@
+//! Generation of arbitrary display commands and input events from raw bytes, for fuzzing.
+//!
+//! Values are derived deterministically from a byte string (as supplied by fuzzers such as `cargo fuzz`)
+//! through the [`arbitrary`](https://docs.rs/arbitrary) crate, which makes every failure reproducible from its input:
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| {
+//!     let mut u = Unstructured::new(data);
+//!     let commands = DisplayListGenerator::default().generate(&mut u).unwrap();
+//!
+//!     display.push_command_group(&commands, ZOrder::default(), None, None).unwrap();
+//!     display.present(None).unwrap();
+//! });
+//! ```
+//!
+//! Once the bytes run out, the smallest values are produced, so generation always terminates (with increasingly trivial values).
+//! Coordinates are finite and within a few thousand pixels of the origin, and sizes are non-negative.
+//!
+//! Text items aren't generated since they require font resources.
+
+use {
+    crate::{
+        display::{
+            Angle, Checkerboard, Color, DisplayClip, DisplayCommand, DisplayItem, Dithering,
+            Filter, Gradient, GraphicsDisplayItem, GraphicsDisplayPaint, GraphicsDisplayStroke,
+            ImageAdjustments, ImageOrientation, ImageRotation, LineCap, LineJoin, Point, Rect,
+            ResourceReference, Size, StrokeAlignment, StrokeDash, StyleColor, Transform, Vector,
+            VectorPathEvent,
+        },
+        input::{Composition, InputEvent, Key, Modifiers, MouseButton, ScrollDelta},
+    },
+    arbitrary::Result,
+    std::path::PathBuf,
+};
+
+pub use arbitrary::{Arbitrary, Unstructured};
+
+// a float between `min` and `max` (inclusive).
+fn float(u: &mut Unstructured<'_>, min: f32, max: f32) -> Result<f32> {
+    Ok(min + (max - min) * (u.int_in_range(0..=u16::MAX)? as f32 / u16::MAX as f32))
+}
+
+// an index less than `len` (which must be non-zero).
+fn choose(u: &mut Unstructured<'_>, len: usize) -> Result<usize> {
+    u.int_in_range(0..=len - 1)
+}
+
+fn option<'a, T>(
+    u: &mut Unstructured<'a>,
+    f: impl FnOnce(&mut Unstructured<'a>) -> Result<T>,
+) -> Result<Option<T>> {
+    Ok(if u.arbitrary()? { Some(f(u)?) } else { None })
+}
+
+// up to `max_len` arbitrary values.
+fn vec<'a, T: Arbitrary<'a>>(u: &mut Unstructured<'a>, max_len: usize) -> Result<Vec<T>> {
+    (0..choose(u, max_len + 1)?).map(|_| u.arbitrary()).collect()
+}
+
+// the geometry and colors are foreign types, so they're generated by functions rather than `Arbitrary` implementations.
+
+/// Returns an arbitrary point.
+pub fn point(u: &mut Unstructured<'_>) -> Result<Point> {
+    Ok(Point::new(float(u, -2000.0, 2000.0)?, float(u, -2000.0, 2000.0)?))
+}
+
+/// Returns an arbitrary vector.
+pub fn vector(u: &mut Unstructured<'_>) -> Result<Vector> {
+    Ok(Vector::new(float(u, -500.0, 500.0)?, float(u, -500.0, 500.0)?))
+}
+
+/// Returns an arbitrary (non-negative) size.
+pub fn size(u: &mut Unstructured<'_>) -> Result<Size> {
+    Ok(Size::new(float(u, 0.0, 1000.0)?, float(u, 0.0, 1000.0)?))
+}
+
+/// Returns an arbitrary rectangle.
+pub fn rect(u: &mut Unstructured<'_>) -> Result<Rect> {
+    Ok(Rect::new(point(u)?, size(u)?))
+}
+
+/// Returns an arbitrary angle, within two full turns either way.
+pub fn angle(u: &mut Unstructured<'_>) -> Result<Angle> {
+    Ok(Angle::radians(float(u, -std::f32::consts::PI * 2.0, std::f32::consts::PI * 2.0)?))
+}
+
+/// Returns an arbitrary affine transformation.
+pub fn transform(u: &mut Unstructured<'_>) -> Result<Transform> {
+    let (m11, m12) = (float(u, -4.0, 4.0)?, float(u, -4.0, 4.0)?);
+    let (m21, m22) = (float(u, -4.0, 4.0)?, float(u, -4.0, 4.0)?);
+    let translation = vector(u)?;
+    Ok(Transform::row_major(m11, m12, m21, m22, translation.x, translation.y))
+}
+
+/// Returns an arbitrary color.
+pub fn color(u: &mut Unstructured<'_>) -> Result<Color> {
+    let mut channel = || Ok(u8::arbitrary(u)? as f32 / 255.0);
+    Ok(Color::new(channel()?, channel()?, channel()?, channel()?))
+}
+
+impl<'a> Arbitrary<'a> for Gradient {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut stops = (0..choose(u, 4)? + 1)
+            .map(|_| Ok((float(u, 0.0, 1.0)?, color(u)?)))
+            .collect::<Result<Vec<(f32, Color)>>>()?;
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let dithering =
+            [None, Some(Dithering::None), Some(Dithering::Ordered), Some(Dithering::Noise)]
+                [choose(u, 4)?];
+        Ok(Gradient { start: point(u)?, end: point(u)?, stops, dithering })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Checkerboard {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Checkerboard {
+            origin: point(u)?,
+            size: float(u, 1.0, 64.0)?,
+            light: color(u)?,
+            dark: color(u)?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for StyleColor {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match choose(u, 4)? {
+            0 => StyleColor::Color(color(u)?),
+            1 => StyleColor::LinearGradient(u.arbitrary()?),
+            2 => StyleColor::RadialGradient(u.arbitrary()?),
+            _ => StyleColor::Checkerboard(u.arbitrary()?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for Filter {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            Filter::Blur(float(u, 0.0, 32.0)?, float(u, 0.0, 32.0)?)
+        } else {
+            Filter::Invert
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GraphicsDisplayStroke {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(GraphicsDisplayStroke {
+            color: u.arbitrary()?,
+            thickness: float(u, 0.0, 32.0)?,
+            cap: [LineCap::Flat, LineCap::Square, LineCap::Round][choose(u, 3)?],
+            join: [LineJoin::Miter, LineJoin::Round, LineJoin::Bevel][choose(u, 3)?],
+            miter_limit: float(u, 0.0, 16.0)?,
+            antialias: u.arbitrary()?,
+            alignment: [StrokeAlignment::Center, StrokeAlignment::Inside, StrokeAlignment::Outside]
+                [choose(u, 3)?],
+            dash: option(u, |u| {
+                Ok(StrokeDash {
+                    intervals: (0..choose(u, 5)?)
+                        .map(|_| float(u, 0.0, 32.0))
+                        .collect::<Result<_>>()?,
+                    phase: float(u, -64.0, 64.0)?,
+                })
+            })?,
+            trim: option(u, |u| Ok((float(u, -0.5, 1.5)?, float(u, -0.5, 1.5)?)))?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GraphicsDisplayPaint {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            GraphicsDisplayPaint::Fill(u.arbitrary()?)
+        } else {
+            GraphicsDisplayPaint::Stroke(u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for VectorPathEvent {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match choose(u, 6)? {
+            0 => VectorPathEvent::MoveTo { to: point(u)? },
+            1 => VectorPathEvent::LineTo { to: point(u)? },
+            2 => VectorPathEvent::QuadTo { control: point(u)?, to: point(u)? },
+            3 => VectorPathEvent::ConicTo {
+                control: point(u)?,
+                to: point(u)?,
+                weight: float(u, 0.0, 4.0)?,
+            },
+            4 => VectorPathEvent::CubicTo { c1: point(u)?, c2: point(u)?, to: point(u)? },
+            _ => VectorPathEvent::ArcTo {
+                center: point(u)?,
+                radii: vector(u)?,
+                start_angle: float(u, -360.0, 360.0)?,
+                sweep_angle: float(u, -360.0, 360.0)?,
+            },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisplayClip {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match choose(u, 4)? {
+            0 => DisplayClip::Rectangle { rect: rect(u)?, antialias: u.arbitrary()? },
+            1 => DisplayClip::RoundRectangle { rect: rect(u)?, radii: radii(u)? },
+            2 => DisplayClip::Ellipse { center: point(u)?, radii: vector(u)? },
+            _ => DisplayClip::Path { path: vec(u, 8)?, is_closed: u.arbitrary()? },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ImageOrientation {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let rotation = match choose(u, 5)? {
+            4 => ImageRotation::Angle(angle(u)?),
+            quarter_turns => ImageRotation::from_quarter_turns(quarter_turns as u32),
+        };
+        Ok(ImageOrientation {
+            rotation,
+            flip_horizontal: u.arbitrary()?,
+            flip_vertical: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ImageAdjustments {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(ImageAdjustments {
+            opacity: float(u, 0.0, 1.0)?,
+            tint: option(u, color)?,
+            exposure: float(u, -4.0, 4.0)?,
+            contrast: float(u, 0.0, 4.0)?,
+        })
+    }
+}
+
+fn radii(u: &mut Unstructured<'_>) -> Result<[f32; 4]> {
+    Ok([float(u, 0.0, 64.0)?, float(u, 0.0, 64.0)?, float(u, 0.0, 64.0)?, float(u, 0.0, 64.0)?])
+}
+
+/// Produces display lists of arbitrary commands, with balanced saves and restores.
+#[derive(Debug, Clone)]
+pub struct DisplayListGenerator {
+    /// Maximum number of commands (excluding those which restore the saves).
+    pub max_len: usize,
+    /// Maximum number of nested saves.
+    pub max_depth: usize,
+    /// Image resources which image items may reference. If empty, no image items are generated.
+    pub images: Vec<ResourceReference>,
+}
+
+impl Default for DisplayListGenerator {
+    fn default() -> Self {
+        DisplayListGenerator { max_len: 64, max_depth: 8, images: Vec::new() }
+    }
+}
+
+impl DisplayListGenerator {
+    pub fn generate(&self, u: &mut Unstructured<'_>) -> Result<Vec<DisplayCommand>> {
+        let len = choose(u, self.max_len + 1)?;
+        let mut commands = Vec::with_capacity(len);
+        let mut depth = 0;
+
+        for _ in 0..len {
+            let command = match choose(u, 10)? {
+                0 if depth < self.max_depth => {
+                    depth += 1;
+                    if u.arbitrary()? {
+                        DisplayCommand::Save
+                    } else {
+                        DisplayCommand::SaveLayer(float(u, 0.0, 1.0)?)
+                    }
+                }
+                1 if depth > 0 => {
+                    depth -= 1;
+                    DisplayCommand::Restore
+                }
+                2 => DisplayCommand::Clip(u.arbitrary()?),
+                3 => match choose(u, 4)? {
+                    0 => DisplayCommand::Translate(vector(u)?),
+                    1 => DisplayCommand::Scale(Vector::new(
+                        float(u, -4.0, 4.0)?,
+                        float(u, -4.0, 4.0)?,
+                    )),
+                    2 => DisplayCommand::Rotate(angle(u)?),
+                    _ => DisplayCommand::Transform(transform(u)?),
+                },
+                4 => DisplayCommand::BackdropFilter(u.arbitrary()?, u.arbitrary()?),
+                5 => DisplayCommand::Clear(color(u)?),
+                _ => DisplayCommand::Item(
+                    DisplayItem::Graphics(self.item(u)?),
+                    if u8::arbitrary(u)? < 32 { Some(u.arbitrary()?) } else { None },
+                ),
+            };
+            commands.push(command);
+        }
+
+        commands.extend((0..depth).map(|_| DisplayCommand::Restore));
+        Ok(commands)
+    }
+
+    fn item(&self, u: &mut Unstructured<'_>) -> Result<GraphicsDisplayItem> {
+        Ok(match choose(u, 7)? {
+            0 => GraphicsDisplayItem::Line { a: point(u)?, b: point(u)?, stroke: u.arbitrary()? },
+            1 => GraphicsDisplayItem::RoundRectangle {
+                rect: rect(u)?,
+                radii: radii(u)?,
+                paint: u.arbitrary()?,
+            },
+            2 => GraphicsDisplayItem::Ellipse {
+                center: point(u)?,
+                radii: vector(u)?,
+                paint: u.arbitrary()?,
+            },
+            3 => GraphicsDisplayItem::Path {
+                path: vec(u, 8)?,
+                is_closed: u.arbitrary()?,
+                paint: u.arbitrary()?,
+            },
+            5 => GraphicsDisplayItem::Arc {
+                center: point(u)?,
+                radii: vector(u)?,
+                start_angle: Angle::degrees(float(u, -360.0, 360.0)?),
+                sweep_angle: Angle::degrees(float(u, -720.0, 720.0)?),
+                use_center: u.arbitrary()?,
+                paint: u.arbitrary()?,
+            },
+            4 if !self.images.is_empty() => GraphicsDisplayItem::Image {
+                src: option(u, rect)?,
+                dst: rect(u)?,
+                resource: self.images[choose(u, self.images.len())?],
+                orientation: u.arbitrary()?,
+                adjustments: u.arbitrary()?,
+            },
+            _ => GraphicsDisplayItem::Rectangle { rect: rect(u)?, paint: u.arbitrary()? },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisplayCommand {
+    /// Produces a single command, which may be an unbalanced save or restore;
+    /// use [`DisplayListGenerator`](struct.DisplayListGenerator.html) for well-formed display lists.
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let generator = DisplayListGenerator { max_len: 1, ..Default::default() };
+        Ok(match choose(u, 12)? {
+            0 => DisplayCommand::Save,
+            1 => DisplayCommand::Restore,
+            _ => generator.generate(u)?.pop().unwrap_or(DisplayCommand::Save),
+        })
+    }
+}
+
+const KEYS: &[Key] = &[
+    Key::A,
+    Key::C,
+    Key::V,
+    Key::X,
+    Key::Z,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Down,
+    Key::Home,
+    Key::End,
+    Key::Backspace,
+    Key::Delete,
+    Key::Enter,
+    Key::Tab,
+    Key::Space,
+    Key::Escape,
+    Key::LShift,
+    Key::Unknown,
+];
+
+impl<'a> Arbitrary<'a> for Modifiers {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let bits = u8::arbitrary(u)?;
+        Ok(Modifiers {
+            shift: bits & 1 != 0,
+            ctrl: bits & 2 != 0,
+            alt: bits & 4 != 0,
+            logo: bits & 8 != 0,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for MouseButton {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match choose(u, 4)? {
+            0 => MouseButton::Left,
+            1 => MouseButton::Right,
+            2 => MouseButton::Middle,
+            _ => MouseButton::Other(u.arbitrary()?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for InputEvent {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match choose(u, 13)? {
+            0 => InputEvent::PointerDown {
+                position: point(u)?,
+                button: u.arbitrary()?,
+                modifiers: u.arbitrary()?,
+            },
+            1 => InputEvent::PointerUp {
+                position: point(u)?,
+                button: u.arbitrary()?,
+                modifiers: u.arbitrary()?,
+            },
+            2 => InputEvent::Click {
+                position: point(u)?,
+                button: u.arbitrary()?,
+                count: choose(u, 4)? as u32 + 1,
+                modifiers: u.arbitrary()?,
+            },
+            3 => InputEvent::Wheel {
+                position: point(u)?,
+                delta: if u.arbitrary()? {
+                    ScrollDelta::Lines(Vector::new(float(u, -3.0, 3.0)?, float(u, -3.0, 3.0)?))
+                } else {
+                    ScrollDelta::Pixels(vector(u)?)
+                },
+                modifiers: u.arbitrary()?,
+            },
+            4 => InputEvent::KeyDown {
+                key: *u.choose(KEYS)?,
+                scancode: u8::arbitrary(u)? as u32,
+                modifiers: u.arbitrary()?,
+            },
+            5 => InputEvent::KeyUp {
+                key: *u.choose(KEYS)?,
+                scancode: u8::arbitrary(u)? as u32,
+                modifiers: u.arbitrary()?,
+            },
+            6 => InputEvent::TextInput(
+                std::char::from_u32(u32::arbitrary(u)? % 0x11_0000).unwrap_or('\u{fffd}'),
+            ),
+            7 => InputEvent::FilesDropped {
+                paths: (0..choose(u, 3)?).map(|i| PathBuf::from(format!("file{}", i))).collect(),
+                position: point(u)?,
+            },
+            8 => InputEvent::WindowResized(size(u)?),
+            9 => InputEvent::ScaleFactorChanged(float(u, 0.5, 4.0)? as f64),
+            10 => {
+                let text = "\u{3042}\u{3044}\u{3046}"[..choose(u, 4)? * 3].to_string();
+                let cursor = if u.arbitrary()? { Some(0..text.len()) } else { None };
+                InputEvent::ImeComposition(Composition { text, cursor })
+            }
+            _ => InputEvent::PointerMove { position: point(u)?, modifiers: u.arbitrary()? },
+        })
+    }
+}
+
+/// Returns `true` if every restore in `display_list` matches an earlier save (or save layer), and every save is restored.
+pub fn is_balanced(display_list: &[DisplayCommand]) -> bool {
+    let mut depth = 0usize;
+    for command in display_list {
+        match command {
+            DisplayCommand::Save | DisplayCommand::SaveLayer(_) => depth += 1,
+            DisplayCommand::Restore => match depth.checked_sub(1) {
+                Some(new_depth) => depth = new_depth,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::display};
+
+    /// Deterministic pseudo-random bytes (xorshift), standing in for a fuzzer.
+    fn bytes(seed: u32, len: usize) -> Vec<u8> {
+        let mut state = seed.max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generated_display_lists() {
+        let generator = DisplayListGenerator {
+            images: vec![ResourceReference::Image(0)],
+            ..Default::default()
+        };
+
+        for seed in 0..256 {
+            let data = bytes(seed, 4096);
+            let commands = generator.generate(&mut Unstructured::new(&data)).unwrap();
+            assert_eq!(
+                commands.len(),
+                generator.generate(&mut Unstructured::new(&data)).unwrap().len()
+            );
+            assert!(is_balanced(&commands));
+
+            let bounds = display::display_list_bounds(&commands).unwrap();
+            assert!(bounds.origin.x.is_finite() && bounds.size.width.is_finite());
+
+            // batching neither loses nor duplicates commands.
+            let batched: usize = display::batch_display_list(&commands)
+                .iter()
+                .map(|batch| match batch {
+                    display::DisplayBatch::Single(_) => 1,
+                    display::DisplayBatch::Rectangles(_, rects) => rects.len(),
+                    display::DisplayBatch::Lines(_, lines) => lines.len(),
+                })
+                .sum();
+            assert_eq!(batched, commands.len());
+
+            assert!(display::cull_display_list(&commands, None).count() <= commands.len());
+        }
+
+        assert!(generator.generate(&mut Unstructured::new(&[])).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_generated_input_events() {
+        for seed in 0..256 {
+            let data = bytes(seed, 512);
+            let mut u = Unstructured::new(&data);
+            let events: Vec<InputEvent> = vec(&mut u, 32).unwrap();
+
+            for event in &events {
+                let transform = crate::display::Transform::create_translation(10.0, 10.0);
+                assert_eq!(event.transformed(&transform).modifiers(), event.modifiers());
+            }
+        }
+
+        assert!(!is_balanced(&[DisplayCommand::Restore, DisplayCommand::Save]));
+    }
+}
@@ -0,0 +1,1740 @@
+//! Backend-agnostic input events.
+//!
+//! These cover what most applications need from their windowing library (pointer, wheel, keyboard, text and window events),
+//! so that widgets can be written against a single event type rather than one defined per application.
+//!
+//! With the `winit` feature enabled, [`WinitInputConverter`](struct.WinitInputConverter.html) converts winit events (and by extension glutin events, which are re-exported winit events).
+
+use {
+    crate::{
+        display::{Point, ResizeEdge, Size, Transform, Vector},
+        simulation::{Clock, SystemClock},
+        widget::WidgetChildren,
+    },
+    std::{
+        collections::HashSet,
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+};
+
+/// A button on a pointing device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u8),
+}
+
+/// State of the keyboard modifiers at the time of an event.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    /// The "Windows" key on PC and "Command" key on Mac.
+    pub logo: bool,
+}
+
+impl Modifiers {
+    /// Returns `true` if no modifiers are held.
+    pub fn is_empty(&self) -> bool {
+        !(self.shift || self.ctrl || self.alt || self.logo)
+    }
+}
+
+/// Symbolic name of a keyboard key, independent of keyboard layout.
+///
+/// Keys which don't have a variant are reported as [`Unknown`](enum.Key.html#variant.Unknown); use the scancode to distinguish them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Insert,
+    Home,
+    Delete,
+    End,
+    PageDown,
+    PageUp,
+    Left,
+    Up,
+    Right,
+    Down,
+    Backspace,
+    Enter,
+    Space,
+    Tab,
+    Minus,
+    Equals,
+    LBracket,
+    RBracket,
+    Backslash,
+    Semicolon,
+    Apostrophe,
+    Comma,
+    Period,
+    Slash,
+    Grave,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    LLogo,
+    RLogo,
+    Unknown,
+}
+
+/// Amount scrolled by a mouse wheel or touchpad.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollDelta {
+    /// Scrolled by lines (typically a mouse wheel).
+    Lines(Vector),
+    /// Scrolled by (physical) pixels (typically a touchpad).
+    Pixels(Vector),
+}
+
+/// The appearance of the pointer, as requested by widgets (e.g. when hovering over a resizable edge).
+///
+/// With the `winit` feature enabled, this converts into `winit::window::CursorIcon`, to be passed to `Window::set_cursor_icon`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorIcon {
+    #[default]
+    Default,
+    /// Indicates a link or button.
+    Pointer,
+    Text,
+    Move,
+    Grab,
+    Grabbing,
+    NotAllowed,
+    /// Resizing horizontally (the left or right edge).
+    EwResize,
+    /// Resizing vertically (the top or bottom edge).
+    NsResize,
+    /// Resizing diagonally (the top-left or bottom-right corner).
+    NwseResize,
+    /// Resizing diagonally (the top-right or bottom-left corner).
+    NeswResize,
+}
+
+impl From<ResizeEdge> for CursorIcon {
+    fn from(edge: ResizeEdge) -> Self {
+        match edge {
+            ResizeEdge::Left | ResizeEdge::Right => CursorIcon::EwResize,
+            ResizeEdge::Top | ResizeEdge::Bottom => CursorIcon::NsResize,
+            ResizeEdge::TopLeft | ResizeEdge::BottomRight => CursorIcon::NwseResize,
+            ResizeEdge::TopRight | ResizeEdge::BottomLeft => CursorIcon::NeswResize,
+        }
+    }
+}
+
+/// Text which is being composed through an input method editor (IME), but hasn't been committed yet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Composition {
+    /// The composed text; empty once the composition ended (either committed or cancelled).
+    pub text: String,
+    /// The byte range of `text` which the IME is currently converting (or the cursor, if empty), if any.
+    pub cursor: Option<std::ops::Range<usize>>,
+}
+
+impl Composition {
+    /// Returns `true` if nothing is being composed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// An input event.
+///
+/// All positions and sizes are in physical pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    PointerDown {
+        position: Point,
+        button: MouseButton,
+        modifiers: Modifiers,
+    },
+    PointerUp {
+        position: Point,
+        button: MouseButton,
+        modifiers: Modifiers,
+    },
+    PointerMove {
+        position: Point,
+        modifiers: Modifiers,
+    },
+    /// A button was pressed `count` times in quick succession (e.g. `count` is 2 for a double click).
+    ///
+    /// This isn't produced by backends; see [`ClickSynthesizer`](struct.ClickSynthesizer.html).
+    Click {
+        position: Point,
+        button: MouseButton,
+        count: u32,
+        modifiers: Modifiers,
+    },
+    Wheel {
+        position: Point,
+        delta: ScrollDelta,
+        modifiers: Modifiers,
+    },
+    KeyDown {
+        key: Key,
+        scancode: u32,
+        modifiers: Modifiers,
+    },
+    KeyUp {
+        key: Key,
+        scancode: u32,
+        modifiers: Modifiers,
+    },
+    /// A character was typed. This is separate from [`KeyDown`](enum.InputEvent.html#variant.KeyDown) since it accounts for keyboard layout, IMEs, dead keys, etc.
+    TextInput(char),
+    /// The text being composed through an IME changed. The composed text is only shown (usually underlined) by the focused widget;
+    /// once committed, it arrives as [`TextInput`](enum.InputEvent.html#variant.TextInput).
+    ImeComposition(Composition),
+    /// Files were dropped onto the window. Use [`hit_test`](fn.hit_test.html) to find the widget they were dropped onto.
+    FilesDropped {
+        paths: Vec<PathBuf>,
+        position: Point,
+    },
+    WindowResized(Size),
+    ScaleFactorChanged(f64),
+}
+
+impl InputEvent {
+    /// Returns the position of the pointer, if this is a pointer event.
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            InputEvent::PointerDown { position, .. }
+            | InputEvent::PointerUp { position, .. }
+            | InputEvent::PointerMove { position, .. }
+            | InputEvent::Click { position, .. }
+            | InputEvent::Wheel { position, .. }
+            | InputEvent::FilesDropped { position, .. } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Maps all the positions (and pixel scroll deltas) of this event through `transform`.
+    ///
+    /// Typically `transform` is the inverse of a widget's [`transform`](../widget/trait.Widget.html#method.transform),
+    /// to map the event into the local coordinate space of the widget.
+    pub fn transformed(&self, transform: &Transform) -> InputEvent {
+        let mut event = self.clone();
+        match event {
+            InputEvent::PointerDown { ref mut position, .. }
+            | InputEvent::PointerUp { ref mut position, .. }
+            | InputEvent::PointerMove { ref mut position, .. }
+            | InputEvent::Click { ref mut position, .. }
+            | InputEvent::FilesDropped { ref mut position, .. } => {
+                *position = transform.transform_point(*position)
+            }
+            InputEvent::Wheel { ref mut position, ref mut delta, .. } => {
+                *position = transform.transform_point(*position);
+                if let ScrollDelta::Pixels(ref mut pixels) = delta {
+                    *pixels = transform.transform_vector(*pixels);
+                }
+            }
+            _ => {}
+        }
+        event
+    }
+
+    /// Returns `true` if this is a keyboard (or text) event, which is meant for the [focused](../focus/struct.FocusTracker.html) widget.
+    pub fn is_keyboard(&self) -> bool {
+        matches!(
+            self,
+            InputEvent::KeyDown { .. }
+                | InputEvent::KeyUp { .. }
+                | InputEvent::TextInput(_)
+                | InputEvent::ImeComposition(_)
+        )
+    }
+
+    /// Returns the modifiers held at the time of the event, if applicable.
+    pub fn modifiers(&self) -> Option<Modifiers> {
+        match self {
+            InputEvent::PointerDown { modifiers, .. }
+            | InputEvent::PointerUp { modifiers, .. }
+            | InputEvent::PointerMove { modifiers, .. }
+            | InputEvent::Click { modifiers, .. }
+            | InputEvent::Wheel { modifiers, .. }
+            | InputEvent::KeyDown { modifiers, .. }
+            | InputEvent::KeyUp { modifiers, .. } => Some(*modifiers),
+            _ => None,
+        }
+    }
+}
+
+/// Keeps track of the pointer, held buttons/keys and modifiers by consuming [`InputEvent`](enum.InputEvent.html)s.
+///
+/// Typically this is kept in the `UpdateAux` so that widgets can query it rather than tracking the cursor themselves:
+/// ```ignore
+/// for event in self.input_listener.peek() {
+///     aux.input.handle(&event);
+/// }
+///
+/// if aux.input.is_button_down(MouseButton::Left) && aux.input.modifiers().shift {
+///     // --snip--
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    cursor: Option<Point>,
+    buttons: HashSet<MouseButton>,
+    keys: HashSet<Key>,
+    modifiers: Modifiers,
+    window_size: Size,
+    scale_factor: Option<f64>,
+}
+
+impl InputState {
+    /// Creates a new state with nothing held and the cursor position unknown.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Updates the state from an input event.
+    pub fn handle(&mut self, event: &InputEvent) {
+        if let Some(position) = event.position() {
+            self.cursor = Some(position);
+        }
+
+        if let Some(modifiers) = event.modifiers() {
+            self.modifiers = modifiers;
+        }
+
+        match event {
+            InputEvent::PointerDown { button, .. } => {
+                self.buttons.insert(*button);
+            }
+            InputEvent::PointerUp { button, .. } => {
+                self.buttons.remove(button);
+            }
+            InputEvent::KeyDown { key, .. } if *key != Key::Unknown => {
+                self.keys.insert(*key);
+            }
+            InputEvent::KeyUp { key, .. } => {
+                self.keys.remove(key);
+            }
+            InputEvent::WindowResized(size) => self.window_size = *size,
+            InputEvent::ScaleFactorChanged(scale_factor) => self.scale_factor = Some(*scale_factor),
+            _ => {}
+        }
+    }
+
+    /// Forgets all held buttons and keys.
+    ///
+    /// Release events aren't received while the window is unfocused, so this should be called when focus is lost.
+    pub fn release_all(&mut self) {
+        self.buttons.clear();
+        self.keys.clear();
+        self.modifiers = Modifiers::default();
+    }
+
+    /// Returns the last known position of the cursor, or `None` if the cursor hasn't moved yet.
+    #[inline]
+    pub fn cursor(&self) -> Option<Point> {
+        self.cursor
+    }
+
+    /// Returns `true` if `button` is currently held.
+    #[inline]
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        self.buttons.contains(&button)
+    }
+
+    /// Returns all the buttons which are currently held.
+    #[inline]
+    pub fn buttons(&self) -> impl Iterator<Item = &MouseButton> {
+        self.buttons.iter()
+    }
+
+    /// Returns `true` if `key` is currently held.
+    #[inline]
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+
+    /// Returns the modifiers which are currently held.
+    #[inline]
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the last known window size, in physical pixels.
+    #[inline]
+    pub fn window_size(&self) -> Size {
+        self.window_size
+    }
+
+    /// Returns the last known scale factor, if one has been received.
+    #[inline]
+    pub fn scale_factor(&self) -> Option<f64> {
+        self.scale_factor
+    }
+}
+
+/// Produces [`Click`](enum.InputEvent.html#variant.Click) events with a click count from pointer presses.
+///
+/// A press continues the current click sequence if it's the same button, within `interval` of the previous press and within `distance` of the first press.
+#[derive(Debug, Clone)]
+pub struct ClickSynthesizer {
+    /// Maximum time between two presses of a sequence.
+    pub interval: Duration,
+    /// Maximum distance (in physical pixels) the pointer may drift during a sequence.
+    pub distance: f32,
+    last: Option<(Instant, Point, MouseButton, u32)>,
+}
+
+impl Default for ClickSynthesizer {
+    fn default() -> Self {
+        ClickSynthesizer::new(Duration::from_millis(500), 4.0)
+    }
+}
+
+impl ClickSynthesizer {
+    /// Creates a new click synthesizer with the given thresholds.
+    pub fn new(interval: Duration, distance: f32) -> Self {
+        ClickSynthesizer { interval, distance, last: None }
+    }
+
+    /// Consumes an input event, returning a `Click` if the event was a pointer press.
+    ///
+    /// The event is timed by the system clock; see [`handle_with`](struct.ClickSynthesizer.html#method.handle_with) to time it otherwise.
+    #[inline]
+    pub fn handle(&mut self, event: &InputEvent) -> Option<InputEvent> {
+        self.handle_with(event, &SystemClock)
+    }
+
+    /// Same as [`handle`](struct.ClickSynthesizer.html#method.handle), however the event is timed by `clock`
+    /// (e.g. the [virtual clock](../simulation/struct.VirtualClock.html) of a simulation).
+    #[inline]
+    pub fn handle_with(&mut self, event: &InputEvent, clock: &dyn Clock) -> Option<InputEvent> {
+        self.handle_at(event, clock.now())
+    }
+
+    /// Same as [`handle`](struct.ClickSynthesizer.html#method.handle), however with an explicit time of the event.
+    pub fn handle_at(&mut self, event: &InputEvent, time: Instant) -> Option<InputEvent> {
+        if let InputEvent::PointerDown { position, button, modifiers } = *event {
+            let count = match self.last {
+                Some((last_time, origin, last_button, count))
+                    if last_button == button
+                        && time.duration_since(last_time) <= self.interval
+                        && (position - origin).length() <= self.distance =>
+                {
+                    self.last = Some((time, origin, button, count + 1));
+                    count + 1
+                }
+                _ => {
+                    self.last = Some((time, position, button, 1));
+                    1
+                }
+            };
+
+            Some(InputEvent::Click { position, button, count, modifiers })
+        } else {
+            None
+        }
+    }
+
+    /// Ends the current click sequence, such that the next press has a count of 1.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Converts scroll events into a smoothly animated scroll offset.
+///
+/// Each scroll adds momentum which decays exponentially, such that the offset eventually travels exactly the scrolled distance.
+/// The offset grows as content is scrolled down/right, and is kept within `0` and the [maximum offset](struct.KineticScroll.html#method.set_max_offset).
+#[derive(Debug, Clone)]
+pub struct KineticScroll {
+    /// Number of pixels scrolled per line (for [`ScrollDelta::Lines`](enum.ScrollDelta.html#variant.Lines)).
+    pub line_height: f32,
+    /// Rate at which momentum decays, per second. Higher values stop sooner.
+    ///
+    /// Must be greater than zero; without friction, momentum would never decay.
+    pub friction: f32,
+    offset: Vector,
+    velocity: Vector,
+    max_offset: Vector,
+}
+
+impl Default for KineticScroll {
+    fn default() -> Self {
+        KineticScroll::new(40.0, 12.0)
+    }
+}
+
+impl KineticScroll {
+    /// Velocity (in pixels per second) below which scrolling comes to a stop.
+    const REST_VELOCITY: f32 = 1.0;
+
+    /// Creates a new kinetic scroller at rest with no room to scroll.
+    ///
+    /// # Panics
+    /// Panics if `friction` isn't greater than zero.
+    pub fn new(line_height: f32, friction: f32) -> Self {
+        assert!(friction > 0.0, "friction must be greater than zero");
+
+        KineticScroll {
+            line_height,
+            friction,
+            offset: Vector::zero(),
+            velocity: Vector::zero(),
+            max_offset: Vector::zero(),
+        }
+    }
+
+    /// Consumes an input event, returning `true` if it was a [`Wheel`](enum.InputEvent.html#variant.Wheel) event.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        if let InputEvent::Wheel { delta, .. } = event {
+            self.scroll(*delta);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Adds momentum from a scroll delta.
+    pub fn scroll(&mut self, delta: ScrollDelta) {
+        let distance = match delta {
+            ScrollDelta::Lines(lines) => lines * self.line_height,
+            ScrollDelta::Pixels(pixels) => pixels,
+        };
+
+        // positive deltas scroll towards the start of the content.
+        // with exponential decay, the total distance travelled is `velocity / friction`.
+        self.velocity -= distance * self.friction;
+    }
+
+    /// Advances the animation by `dt`, returning the new offset.
+    pub fn update(&mut self, dt: Duration) -> Vector {
+        if self.is_moving() {
+            let decay = (-self.friction * dt.as_secs_f32()).exp();
+
+            self.offset += self.velocity * ((1.0 - decay) / self.friction);
+            self.velocity *= decay;
+
+            if self.velocity.length() < Self::REST_VELOCITY {
+                self.velocity = Vector::zero();
+            }
+
+            self.clamp();
+        }
+
+        self.offset
+    }
+
+    /// Returns `true` if the offset is still animating.
+    #[inline]
+    pub fn is_moving(&self) -> bool {
+        self.velocity != Vector::zero()
+    }
+
+    /// Returns the current scroll offset.
+    #[inline]
+    pub fn offset(&self) -> Vector {
+        self.offset
+    }
+
+    /// Jumps to an offset, stopping any momentum.
+    pub fn set_offset(&mut self, offset: Vector) {
+        self.offset = offset;
+        self.velocity = Vector::zero();
+        self.clamp();
+    }
+
+    /// Sets how far the content can be scrolled, typically the content size minus the viewport size.
+    pub fn set_max_offset(&mut self, max_offset: Vector) {
+        self.max_offset = max_offset.max(Vector::zero());
+        self.clamp();
+    }
+
+    fn clamp(&mut self) {
+        let clamped = self.offset.max(Vector::zero()).min(self.max_offset);
+
+        // hitting an edge stops momentum along that axis.
+        if clamped.x != self.offset.x {
+            self.velocity.x = 0.0;
+        }
+        if clamped.y != self.offset.y {
+            self.velocity.y = 0.0;
+        }
+
+        self.offset = clamped;
+    }
+}
+
+/// Identifies a widget by the indices of the children followed from the root widget to reach it.
+///
+/// The root itself is an empty path.
+pub type WidgetPath = Vec<usize>;
+
+/// Finds the top-most widget under `point` (in the coordinate space of the parent of `root`), returning its path.
+///
+/// Children are searched from the highest [z-index](../widget/trait.Widget.html#method.z_index) down (and, among equal z-indices, in reverse order),
+/// since those are drawn over the others. The path still consists of indices into [`children`](../widget/trait.WidgetChildren.html#method.children).
+/// `point` is mapped into the [local coordinate space](../widget/trait.Widget.html#method.transform) of each widget before searching its children.
+/// Descendants are searched even if `point` is outside the bounds of their parent, so that containers which don't implement [`bounds`](../widget/trait.Widget.html#method.bounds) don't hide their children.
+///
+/// [Invisible](../widget/trait.Widget.html#method.is_visible) widgets are skipped entirely.
+/// [Disabled](../widget/trait.Widget.html#method.is_enabled) widgets still occlude what's beneath them, however the path is cut short
+/// to their closest enabled ancestor (or `None` if there isn't one), since disabled widgets don't receive input.
+pub fn hit_test<W: WidgetChildren + ?Sized>(root: &W, point: Point) -> Option<WidgetPath> {
+    enabled_prefix(root, &hit_test_visible(root, point)?)
+}
+
+fn hit_test_visible<W: WidgetChildren + ?Sized>(root: &W, point: Point) -> Option<WidgetPath> {
+    if !root.is_visible() {
+        return None;
+    }
+
+    if let Some(local) = root.transform().inverse().map(|inverse| inverse.transform_point(point)) {
+        let mut children = root.children().into_iter().enumerate().collect::<Vec<_>>();
+        children.sort_by_key(|(_, child)| child.z_index());
+        for (i, child) in children.into_iter().rev() {
+            if let Some(mut path) = hit_test_visible(child, local) {
+                path.insert(0, i);
+                return Some(path);
+            }
+        }
+    }
+
+    if root.bounds().contains(point) {
+        Some(Vec::new())
+    } else {
+        None
+    }
+}
+
+// cuts `path` short at the first disabled widget.
+fn enabled_prefix<W: WidgetChildren + ?Sized>(root: &W, path: &[usize]) -> Option<WidgetPath> {
+    if !root.is_enabled() {
+        return None;
+    }
+
+    Some(
+        path.split_first()
+            .and_then(|(&i, rest)| {
+                let mut prefix = enabled_prefix(root.children().into_iter().nth(i)?, rest)?;
+                prefix.insert(0, i);
+                Some(prefix)
+            })
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns the widget at `path` (as returned by [`hit_test`](fn.hit_test.html)), or `None` if the path no longer exists.
+pub fn resolve_path_mut<'a, U, G, D>(
+    root: &'a mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+    path: &[usize],
+) -> Option<&'a mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>> {
+    match path.split_first() {
+        Some((&i, rest)) => resolve_path_mut(root.children_mut().into_iter().nth(i)?, rest),
+        None => Some(root),
+    }
+}
+
+/// The stage of [dispatch](fn.dispatch.html) a [`PropagatingEvent`](struct.PropagatingEvent.html) is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropagationPhase {
+    /// The event is travelling from the root down to the target, and is being received by an ancestor of the target.
+    Capture,
+    /// The event is being received by the target itself.
+    Target,
+    /// The event is travelling from the target back up to the root, and is being received by an ancestor of the target.
+    Bubble,
+}
+
+/// An input event on its way to (or back from) a target widget, as received by [`Widget::handle_input`](../widget/trait.Widget.html#method.handle_input).
+#[derive(Debug)]
+pub struct PropagatingEvent<'a> {
+    event: InputEvent,
+    phase: PropagationPhase,
+    target: &'a [usize],
+    depth: usize,
+    stopped: bool,
+}
+
+impl<'a> PropagatingEvent<'a> {
+    /// Returns the event, mapped into the coordinate space of the parent of the receiving widget
+    /// (i.e. the same space as its [`bounds`](../widget/trait.Widget.html#method.bounds)).
+    #[inline]
+    pub fn event(&self) -> &InputEvent {
+        &self.event
+    }
+
+    /// Returns the current phase of propagation.
+    #[inline]
+    pub fn phase(&self) -> PropagationPhase {
+        self.phase
+    }
+
+    /// Returns the path of the target widget.
+    #[inline]
+    pub fn target(&self) -> &'a [usize] {
+        self.target
+    }
+
+    /// Returns the path of the widget currently receiving the event.
+    #[inline]
+    pub fn current(&self) -> &'a [usize] {
+        &self.target[..self.depth]
+    }
+
+    /// Stops the event from reaching any further widgets, in either phase.
+    #[inline]
+    pub fn stop_propagation(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Returns `true` if [`stop_propagation`](struct.PropagatingEvent.html#method.stop_propagation) has been called.
+    #[inline]
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+/// Dispatches `event` (in the coordinate space of the parent of `root`) to the widget at `target`, through [`Widget::handle_input`](../widget/trait.Widget.html#method.handle_input).
+///
+/// Each ancestor of the target receives the event in the [capture](enum.PropagationPhase.html#variant.Capture) phase, from the root down,
+/// then the target receives it, then each ancestor receives it again in the [bubble](enum.PropagationPhase.html#variant.Bubble) phase, from the target's parent up.
+/// This stops as soon as a widget calls [`stop_propagation`](struct.PropagatingEvent.html#method.stop_propagation).
+///
+/// The event is mapped into the [local coordinate space](../widget/trait.Widget.html#method.transform) of each widget before being passed to its children.
+/// If `target` no longer exists (or passes through a widget with a non-invertible transform) then nothing receives the event.
+///
+/// Returns `true` if propagation was stopped.
+pub fn dispatch<W: WidgetChildren + ?Sized>(
+    root: &mut W,
+    target: &[usize],
+    event: &InputEvent,
+    aux: &mut W::UpdateAux,
+) -> bool {
+    if !is_reachable(root, target) {
+        return false;
+    }
+
+    let mut propagating = PropagatingEvent {
+        event: event.clone(),
+        phase: PropagationPhase::Capture,
+        target,
+        depth: 0,
+        stopped: false,
+    };
+
+    dispatch_from(root, &mut propagating, aux);
+
+    propagating.stopped
+}
+
+/// Dispatches a pointer event to the [top-most](fn.hit_test.html) widget under the pointer, returning its path.
+///
+/// Events without a [position](enum.InputEvent.html#method.position) have no target here (keyboard events, for example, are better dispatched to a focused widget),
+/// so `None` is returned without dispatching them.
+pub fn dispatch_pointer<W: WidgetChildren + ?Sized>(
+    root: &mut W,
+    event: &InputEvent,
+    aux: &mut W::UpdateAux,
+) -> Option<WidgetPath> {
+    let target = hit_test(root, event.position()?)?;
+    dispatch(root, &target, event, aux);
+    Some(target)
+}
+
+fn is_reachable<W: WidgetChildren + ?Sized>(root: &W, path: &[usize]) -> bool {
+    match path.split_first() {
+        Some((&i, rest)) => {
+            root.transform().inverse().is_some()
+                && root.children().into_iter().nth(i).is_some_and(|child| is_reachable(child, rest))
+        }
+        None => true,
+    }
+}
+
+fn dispatch_from<W: WidgetChildren + ?Sized>(
+    widget: &mut W,
+    propagating: &mut PropagatingEvent<'_>,
+    aux: &mut W::UpdateAux,
+) {
+    let depth = propagating.depth;
+    let index = match propagating.target.get(depth) {
+        Some(&index) => index,
+        None => {
+            propagating.phase = PropagationPhase::Target;
+            widget.handle_input(propagating, aux);
+            return;
+        }
+    };
+
+    propagating.phase = PropagationPhase::Capture;
+    widget.handle_input(propagating, aux);
+    if propagating.stopped {
+        return;
+    }
+
+    // `is_reachable` has already checked the transform and index.
+    let inverse = widget.transform().inverse().unwrap_or_else(Transform::identity);
+    let event = propagating.event.clone();
+    propagating.event = event.transformed(&inverse);
+    propagating.depth += 1;
+
+    if let Some(child) = widget.children_mut().into_iter().nth(index) {
+        dispatch_from(child, propagating, aux);
+    }
+
+    propagating.depth = depth;
+    propagating.event = event;
+
+    if !propagating.stopped {
+        propagating.phase = PropagationPhase::Bubble;
+        widget.handle_input(propagating, aux);
+    }
+}
+
+/// Emitted by [`HoverTracker`](struct.HoverTracker.html) as the pointer moves across widgets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HoverEvent {
+    Enter(WidgetPath),
+    Leave(WidgetPath),
+}
+
+/// Tracks which widgets are under the pointer, emitting enter/leave events as that changes.
+///
+/// The hovered widget is the [top-most](fn.hit_test.html) one under the pointer; all its ancestors are considered hovered too.
+#[derive(Debug, Clone, Default)]
+pub struct HoverTracker {
+    hovered: Option<WidgetPath>,
+}
+
+impl HoverTracker {
+    /// Creates a new tracker with nothing hovered.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Consumes an input event, updating the hovered widgets if the pointer moved.
+    pub fn handle<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &W,
+        event: &InputEvent,
+    ) -> Vec<HoverEvent> {
+        match event {
+            InputEvent::PointerMove { position, .. } => self.update(root, Some(*position)),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Updates the hovered widgets from the pointer position (or `None` if the pointer left the window).
+    ///
+    /// This should also be invoked when widgets move or the tree changes, even if the pointer hasn't moved.
+    ///
+    /// Leave events are ordered from the deepest widget up, and enter events from the shallowest widget down.
+    pub fn update<W: WidgetChildren + ?Sized>(
+        &mut self,
+        root: &W,
+        position: Option<Point>,
+    ) -> Vec<HoverEvent> {
+        let new = position.and_then(|position| hit_test(root, position));
+        let old = std::mem::replace(&mut self.hovered, new.clone());
+
+        let common = match (&old, &new) {
+            (Some(old), Some(new)) => {
+                old.iter().zip(new.iter()).take_while(|(a, b)| a == b).count() + 1
+            }
+            _ => 0,
+        };
+
+        let mut events = Vec::new();
+
+        if let Some(old) = old {
+            events.extend(
+                (common..=old.len()).rev().map(|depth| HoverEvent::Leave(old[..depth].to_vec())),
+            );
+        }
+
+        if let Some(new) = new {
+            events
+                .extend((common..=new.len()).map(|depth| HoverEvent::Enter(new[..depth].to_vec())));
+        }
+
+        events
+    }
+
+    /// Returns the path of the top-most hovered widget.
+    #[inline]
+    pub fn hovered(&self) -> Option<&[usize]> {
+        self.hovered.as_deref()
+    }
+
+    /// Returns `true` if the widget at `path` (or one of its descendants) is hovered.
+    pub fn is_hovered(&self, path: &[usize]) -> bool {
+        self.hovered.iter().any(|hovered| hovered.starts_with(path))
+    }
+}
+
+#[cfg(feature = "winit")]
+mod winit_conv {
+    use super::*;
+    use winit::event::{
+        DeviceEvent, ElementState, Event, ModifiersState, MouseScrollDelta, VirtualKeyCode,
+        WindowEvent,
+    };
+
+    impl From<CursorIcon> for winit::window::CursorIcon {
+        fn from(icon: CursorIcon) -> Self {
+            match icon {
+                CursorIcon::Default => winit::window::CursorIcon::Default,
+                CursorIcon::Pointer => winit::window::CursorIcon::Hand,
+                CursorIcon::Text => winit::window::CursorIcon::Text,
+                CursorIcon::Move => winit::window::CursorIcon::Move,
+                CursorIcon::Grab => winit::window::CursorIcon::Grab,
+                CursorIcon::Grabbing => winit::window::CursorIcon::Grabbing,
+                CursorIcon::NotAllowed => winit::window::CursorIcon::NotAllowed,
+                CursorIcon::EwResize => winit::window::CursorIcon::EwResize,
+                CursorIcon::NsResize => winit::window::CursorIcon::NsResize,
+                CursorIcon::NwseResize => winit::window::CursorIcon::NwseResize,
+                CursorIcon::NeswResize => winit::window::CursorIcon::NeswResize,
+            }
+        }
+    }
+
+    impl From<winit::event::MouseButton> for MouseButton {
+        fn from(button: winit::event::MouseButton) -> Self {
+            match button {
+                winit::event::MouseButton::Left => MouseButton::Left,
+                winit::event::MouseButton::Right => MouseButton::Right,
+                winit::event::MouseButton::Middle => MouseButton::Middle,
+                winit::event::MouseButton::Other(button) => MouseButton::Other(button),
+            }
+        }
+    }
+
+    impl From<ModifiersState> for Modifiers {
+        fn from(modifiers: ModifiersState) -> Self {
+            Modifiers {
+                shift: modifiers.shift(),
+                ctrl: modifiers.ctrl(),
+                alt: modifiers.alt(),
+                logo: modifiers.logo(),
+            }
+        }
+    }
+
+    impl From<VirtualKeyCode> for Key {
+        fn from(key: VirtualKeyCode) -> Self {
+            match key {
+                VirtualKeyCode::Key1 => Key::Key1,
+                VirtualKeyCode::Key2 => Key::Key2,
+                VirtualKeyCode::Key3 => Key::Key3,
+                VirtualKeyCode::Key4 => Key::Key4,
+                VirtualKeyCode::Key5 => Key::Key5,
+                VirtualKeyCode::Key6 => Key::Key6,
+                VirtualKeyCode::Key7 => Key::Key7,
+                VirtualKeyCode::Key8 => Key::Key8,
+                VirtualKeyCode::Key9 => Key::Key9,
+                VirtualKeyCode::Key0 => Key::Key0,
+                VirtualKeyCode::A => Key::A,
+                VirtualKeyCode::B => Key::B,
+                VirtualKeyCode::C => Key::C,
+                VirtualKeyCode::D => Key::D,
+                VirtualKeyCode::E => Key::E,
+                VirtualKeyCode::F => Key::F,
+                VirtualKeyCode::G => Key::G,
+                VirtualKeyCode::H => Key::H,
+                VirtualKeyCode::I => Key::I,
+                VirtualKeyCode::J => Key::J,
+                VirtualKeyCode::K => Key::K,
+                VirtualKeyCode::L => Key::L,
+                VirtualKeyCode::M => Key::M,
+                VirtualKeyCode::N => Key::N,
+                VirtualKeyCode::O => Key::O,
+                VirtualKeyCode::P => Key::P,
+                VirtualKeyCode::Q => Key::Q,
+                VirtualKeyCode::R => Key::R,
+                VirtualKeyCode::S => Key::S,
+                VirtualKeyCode::T => Key::T,
+                VirtualKeyCode::U => Key::U,
+                VirtualKeyCode::V => Key::V,
+                VirtualKeyCode::W => Key::W,
+                VirtualKeyCode::X => Key::X,
+                VirtualKeyCode::Y => Key::Y,
+                VirtualKeyCode::Z => Key::Z,
+                VirtualKeyCode::Escape => Key::Escape,
+                VirtualKeyCode::F1 => Key::F1,
+                VirtualKeyCode::F2 => Key::F2,
+                VirtualKeyCode::F3 => Key::F3,
+                VirtualKeyCode::F4 => Key::F4,
+                VirtualKeyCode::F5 => Key::F5,
+                VirtualKeyCode::F6 => Key::F6,
+                VirtualKeyCode::F7 => Key::F7,
+                VirtualKeyCode::F8 => Key::F8,
+                VirtualKeyCode::F9 => Key::F9,
+                VirtualKeyCode::F10 => Key::F10,
+                VirtualKeyCode::F11 => Key::F11,
+                VirtualKeyCode::F12 => Key::F12,
+                VirtualKeyCode::Insert => Key::Insert,
+                VirtualKeyCode::Home => Key::Home,
+                VirtualKeyCode::Delete => Key::Delete,
+                VirtualKeyCode::End => Key::End,
+                VirtualKeyCode::PageDown => Key::PageDown,
+                VirtualKeyCode::PageUp => Key::PageUp,
+                VirtualKeyCode::Left => Key::Left,
+                VirtualKeyCode::Up => Key::Up,
+                VirtualKeyCode::Right => Key::Right,
+                VirtualKeyCode::Down => Key::Down,
+                VirtualKeyCode::Back => Key::Backspace,
+                VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => Key::Enter,
+                VirtualKeyCode::Space => Key::Space,
+                VirtualKeyCode::Tab => Key::Tab,
+                VirtualKeyCode::Minus => Key::Minus,
+                VirtualKeyCode::Equals => Key::Equals,
+                VirtualKeyCode::LBracket => Key::LBracket,
+                VirtualKeyCode::RBracket => Key::RBracket,
+                VirtualKeyCode::Backslash => Key::Backslash,
+                VirtualKeyCode::Semicolon => Key::Semicolon,
+                VirtualKeyCode::Apostrophe => Key::Apostrophe,
+                VirtualKeyCode::Comma => Key::Comma,
+                VirtualKeyCode::Period => Key::Period,
+                VirtualKeyCode::Slash => Key::Slash,
+                VirtualKeyCode::Grave => Key::Grave,
+                VirtualKeyCode::Numpad0 => Key::Numpad0,
+                VirtualKeyCode::Numpad1 => Key::Numpad1,
+                VirtualKeyCode::Numpad2 => Key::Numpad2,
+                VirtualKeyCode::Numpad3 => Key::Numpad3,
+                VirtualKeyCode::Numpad4 => Key::Numpad4,
+                VirtualKeyCode::Numpad5 => Key::Numpad5,
+                VirtualKeyCode::Numpad6 => Key::Numpad6,
+                VirtualKeyCode::Numpad7 => Key::Numpad7,
+                VirtualKeyCode::Numpad8 => Key::Numpad8,
+                VirtualKeyCode::Numpad9 => Key::Numpad9,
+                VirtualKeyCode::LShift => Key::LShift,
+                VirtualKeyCode::RShift => Key::RShift,
+                VirtualKeyCode::LControl => Key::LControl,
+                VirtualKeyCode::RControl => Key::RControl,
+                VirtualKeyCode::LAlt => Key::LAlt,
+                VirtualKeyCode::RAlt => Key::RAlt,
+                VirtualKeyCode::LWin => Key::LLogo,
+                VirtualKeyCode::RWin => Key::RLogo,
+                _ => Key::Unknown,
+            }
+        }
+    }
+
+    /// Converts winit events into [`InputEvent`](enum.InputEvent.html)s.
+    ///
+    /// winit doesn't attach the cursor position to button/wheel events, nor the modifiers to any event, hence they are tracked here.
+    #[derive(Debug, Clone)]
+    pub struct WinitInputConverter {
+        cursor: Point,
+        modifiers: Modifiers,
+        scale_factor: f64,
+        dropped_files: Vec<PathBuf>,
+    }
+
+    impl WinitInputConverter {
+        /// Creates a new converter for a window with the given scale factor.
+        pub fn new(scale_factor: f64) -> Self {
+            WinitInputConverter {
+                cursor: Point::default(),
+                modifiers: Modifiers::default(),
+                scale_factor,
+                dropped_files: Vec::new(),
+            }
+        }
+
+        /// Returns the last known position of the cursor, in physical pixels.
+        #[inline]
+        pub fn cursor(&self) -> Point {
+            self.cursor
+        }
+
+        /// Returns the modifiers which are currently held.
+        #[inline]
+        pub fn modifiers(&self) -> Modifiers {
+            self.modifiers
+        }
+
+        /// Returns the last known scale factor.
+        #[inline]
+        pub fn scale_factor(&self) -> f64 {
+            self.scale_factor
+        }
+
+        /// Converts a winit event, returning `None` if the event has no `InputEvent` equivalent.
+        ///
+        /// Every event should be passed through here (not only the ones which are wanted) so that the cursor and modifiers are kept up to date.
+        pub fn convert<T>(&mut self, event: &Event<'_, T>) -> Option<InputEvent> {
+            match event {
+                Event::WindowEvent { event, .. } => self.convert_window_event(event),
+                Event::DeviceEvent { event: DeviceEvent::ModifiersChanged(modifiers), .. } => {
+                    self.modifiers = (*modifiers).into();
+                    None
+                }
+                Event::MainEventsCleared => self.take_dropped_files(),
+                _ => None,
+            }
+        }
+
+        /// Returns the files dropped since the last call as a single [`FilesDropped`](enum.InputEvent.html#variant.FilesDropped) event.
+        ///
+        /// winit reports each dropped file as a separate event, hence they are collected until `MainEventsCleared`
+        /// (at which point [`convert`](struct.WinitInputConverter.html#method.convert) invokes this).
+        pub fn take_dropped_files(&mut self) -> Option<InputEvent> {
+            if self.dropped_files.is_empty() {
+                None
+            } else {
+                Some(InputEvent::FilesDropped {
+                    paths: std::mem::take(&mut self.dropped_files),
+                    position: self.cursor,
+                })
+            }
+        }
+
+        /// Converts a winit window event, returning `None` if the event has no `InputEvent` equivalent.
+        ///
+        /// Note that modifier changes are only received through device events, so prefer [`convert`](struct.WinitInputConverter.html#method.convert).
+        pub fn convert_window_event(&mut self, event: &WindowEvent<'_>) -> Option<InputEvent> {
+            let modifiers = self.modifiers;
+            Some(match event {
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.cursor = Point::new(position.x as _, position.y as _);
+                    InputEvent::PointerMove { position: self.cursor, modifiers }
+                }
+                WindowEvent::MouseInput { state, button, .. } => match state {
+                    ElementState::Pressed => InputEvent::PointerDown {
+                        position: self.cursor,
+                        button: (*button).into(),
+                        modifiers,
+                    },
+                    ElementState::Released => InputEvent::PointerUp {
+                        position: self.cursor,
+                        button: (*button).into(),
+                        modifiers,
+                    },
+                },
+                WindowEvent::MouseWheel { delta, .. } => InputEvent::Wheel {
+                    position: self.cursor,
+                    delta: match delta {
+                        MouseScrollDelta::LineDelta(x, y) => {
+                            ScrollDelta::Lines(Vector::new(*x, *y))
+                        }
+                        MouseScrollDelta::PixelDelta(delta) => ScrollDelta::Pixels(Vector::new(
+                            (delta.x * self.scale_factor) as _,
+                            (delta.y * self.scale_factor) as _,
+                        )),
+                    },
+                    modifiers,
+                },
+                WindowEvent::KeyboardInput { input, .. } => {
+                    let key = input.virtual_keycode.map(Key::from).unwrap_or(Key::Unknown);
+                    match input.state {
+                        ElementState::Pressed => {
+                            InputEvent::KeyDown { key, scancode: input.scancode, modifiers }
+                        }
+                        ElementState::Released => {
+                            InputEvent::KeyUp { key, scancode: input.scancode, modifiers }
+                        }
+                    }
+                }
+                WindowEvent::ReceivedCharacter(c) => InputEvent::TextInput(*c),
+                WindowEvent::Resized(size) => {
+                    InputEvent::WindowResized(Size::new(size.width as _, size.height as _))
+                }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    self.scale_factor = *scale_factor;
+                    InputEvent::ScaleFactorChanged(*scale_factor)
+                }
+                WindowEvent::DroppedFile(path) => {
+                    self.dropped_files.push(path.clone());
+                    return None;
+                }
+                _ => return None,
+            })
+        }
+    }
+}
+
+#[cfg(feature = "winit")]
+pub use winit_conv::WinitInputConverter;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{display::Rect, widget::Widget};
+
+    struct Area(Rect, Vec<Area>);
+
+    impl Widget for Area {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.0
+        }
+    }
+
+    impl WidgetChildren for Area {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.1.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<&mut dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.1.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_hover_tracking() {
+        let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+        let root = Area(
+            rect(0.0, 0.0, 100.0, 100.0),
+            vec![
+                Area(rect(0.0, 0.0, 50.0, 50.0), vec![Area(rect(10.0, 10.0, 10.0, 10.0), vec![])]),
+                // overlaps the first child and is on top of it.
+                Area(rect(40.0, 40.0, 50.0, 50.0), vec![]),
+            ],
+        );
+
+        let mut hover = HoverTracker::new();
+
+        assert_eq!(
+            hover.update(&root, Some(Point::new(15.0, 15.0))),
+            vec![
+                HoverEvent::Enter(vec![]),
+                HoverEvent::Enter(vec![0]),
+                HoverEvent::Enter(vec![0, 0])
+            ]
+        );
+        assert!(hover.is_hovered(&[0]));
+
+        assert_eq!(
+            hover.update(&root, Some(Point::new(45.0, 45.0))),
+            vec![
+                HoverEvent::Leave(vec![0, 0]),
+                HoverEvent::Leave(vec![0]),
+                HoverEvent::Enter(vec![1])
+            ]
+        );
+        assert!(!hover.is_hovered(&[0]));
+
+        assert_eq!(hover.update(&root, Some(Point::new(46.0, 46.0))), vec![]);
+
+        assert_eq!(
+            hover.update(&root, None),
+            vec![HoverEvent::Leave(vec![1]), HoverEvent::Leave(vec![])]
+        );
+        assert_eq!(hover.hovered(), None);
+    }
+
+    struct Offset(Vector, Area);
+
+    impl Widget for Offset {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.1.bounds().translate(self.0)
+        }
+
+        fn transform(&self) -> Transform {
+            Transform::create_translation(self.0.x, self.0.y)
+        }
+    }
+
+    impl WidgetChildren for Offset {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            vec![&self.1]
+        }
+    }
+
+    #[test]
+    fn test_local_coordinates() {
+        let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+        // the child is at (10, 10) in its own space, which is (60, 60) in the window.
+        let root = Offset(
+            Vector::new(50.0, 50.0),
+            Area(rect(0.0, 0.0, 40.0, 40.0), vec![Area(rect(10.0, 10.0, 5.0, 5.0), vec![])]),
+        );
+
+        assert_eq!(hit_test(&root, Point::new(62.0, 62.0)), Some(vec![0, 0]));
+        assert_eq!(hit_test(&root, Point::new(12.0, 12.0)), None);
+
+        let event = InputEvent::PointerMove {
+            position: Point::new(62.0, 62.0),
+            modifiers: Modifiers::default(),
+        };
+        assert_eq!(
+            event.transformed(&root.transform().inverse().unwrap()).position(),
+            Some(Point::new(12.0, 12.0))
+        );
+    }
+
+    struct Toggled(bool, bool, Area);
+
+    impl Widget for Toggled {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.2.bounds()
+        }
+
+        fn is_visible(&self) -> bool {
+            self.0
+        }
+
+        fn is_enabled(&self) -> bool {
+            self.1
+        }
+    }
+
+    impl WidgetChildren for Toggled {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            vec![&self.2]
+        }
+    }
+
+    struct Stack(Rect, Vec<Toggled>);
+
+    impl Widget for Stack {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.0
+        }
+    }
+
+    impl WidgetChildren for Stack {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.1.iter().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_hidden_and_disabled() {
+        let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+        let toggled =
+            |visible, enabled| Toggled(visible, enabled, Area(rect(0.0, 0.0, 10.0, 10.0), vec![]));
+        let point = Point::new(5.0, 5.0);
+
+        // the top-most child is invisible, so the one beneath is hit.
+        let root =
+            Stack(rect(0.0, 0.0, 100.0, 100.0), vec![toggled(true, true), toggled(false, true)]);
+        assert_eq!(hit_test(&root, point), Some(vec![0, 0]));
+
+        // the top-most child is disabled, so it occludes the one beneath yet resolves to the root.
+        let root =
+            Stack(rect(0.0, 0.0, 100.0, 100.0), vec![toggled(true, true), toggled(true, false)]);
+        assert_eq!(hit_test(&root, point), Some(vec![]));
+    }
+
+    struct Raised(i32, Rect);
+
+    impl Widget for Raised {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.1
+        }
+
+        fn z_index(&self) -> i32 {
+            self.0
+        }
+    }
+
+    impl WidgetChildren for Raised {}
+
+    struct Layers(Vec<Raised>);
+
+    impl Widget for Layers {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+    }
+
+    impl WidgetChildren for Layers {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.0.iter().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_z_index() {
+        let rect = Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0));
+        let point = Point::new(5.0, 5.0);
+
+        // the first child is raised above the later ones, yet the path is still its index.
+        let layers = Layers(vec![Raised(1, rect), Raised(0, rect), Raised(0, rect)]);
+        assert_eq!(hit_test(&layers, point), Some(vec![0]));
+
+        // among equal z-indices, the later child is on top.
+        let layers = Layers(vec![Raised(1, rect), Raised(1, rect), Raised(0, rect)]);
+        assert_eq!(hit_test(&layers, point), Some(vec![1]));
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+        let mut root = Area(
+            rect(0.0, 0.0, 100.0, 100.0),
+            vec![Area(
+                rect(0.0, 0.0, 50.0, 50.0),
+                vec![Area(rect(10.0, 10.0, 10.0, 10.0), vec![])],
+            )],
+        );
+
+        let path = hit_test(&root, Point::new(15.0, 15.0)).unwrap();
+        assert_eq!(path, vec![0, 0]);
+        assert_eq!(
+            resolve_path_mut(&mut root, &path).map(|widget| widget.bounds()),
+            Some(rect(10.0, 10.0, 10.0, 10.0))
+        );
+        assert!(resolve_path_mut(&mut root, &[1]).is_none());
+    }
+
+    type DispatchLog = Vec<(WidgetPath, PropagationPhase, Option<Point>)>;
+
+    // records the events it receives, stopping propagation in the `stop` phase.
+    struct Handler(Rect, Vector, Option<PropagationPhase>, Vec<Handler>);
+
+    impl Widget for Handler {
+        type UpdateAux = DispatchLog;
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            self.0
+        }
+
+        fn transform(&self) -> Transform {
+            Transform::create_translation(self.1.x, self.1.y)
+        }
+
+        fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, aux: &mut DispatchLog) {
+            aux.push((event.current().to_vec(), event.phase(), event.event().position()));
+            if self.2 == Some(event.phase()) {
+                event.stop_propagation();
+            }
+        }
+    }
+
+    impl WidgetChildren for Handler {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = DispatchLog, GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.3.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<
+            &mut dyn WidgetChildren<UpdateAux = DispatchLog, GraphicalAux = (), DisplayObject = ()>,
+        > {
+            self.3.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_dispatch() {
+        use PropagationPhase::*;
+
+        let rect = |x, y, w, h| Rect::new(Point::new(x, y), Size::new(w, h));
+        let tree = |stop_root, stop_child| {
+            Handler(
+                rect(0.0, 0.0, 100.0, 100.0),
+                Vector::new(10.0, 10.0),
+                stop_root,
+                vec![
+                    Handler(rect(0.0, 0.0, 50.0, 50.0), Vector::zero(), None, vec![]),
+                    Handler(
+                        rect(20.0, 20.0, 50.0, 50.0),
+                        Vector::zero(),
+                        stop_child,
+                        vec![Handler(rect(30.0, 30.0, 10.0, 10.0), Vector::zero(), None, vec![])],
+                    ),
+                ],
+            )
+        };
+        let event = InputEvent::PointerDown {
+            position: Point::new(45.0, 45.0),
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        };
+        let outer = Some(Point::new(45.0, 45.0));
+        let local = Some(Point::new(35.0, 35.0));
+
+        // the overlapping later child receives the event instead of the earlier one.
+        let mut root = tree(None, None);
+        let mut log = Vec::new();
+        assert_eq!(dispatch_pointer(&mut root, &event, &mut log), Some(vec![1, 0]));
+        assert_eq!(
+            log,
+            vec![
+                (vec![], Capture, outer),
+                (vec![1], Capture, local),
+                (vec![1, 0], Target, local),
+                (vec![1], Bubble, local),
+                (vec![], Bubble, outer),
+            ]
+        );
+
+        // stopped while capturing, so nothing beneath the root is reached.
+        let mut root = tree(Some(Capture), None);
+        let mut log = Vec::new();
+        assert!(dispatch(&mut root, &[1, 0], &event, &mut log));
+        assert_eq!(log, vec![(vec![], Capture, outer)]);
+
+        // stopped while bubbling, so the root doesn't receive it again.
+        let mut root = tree(None, Some(Bubble));
+        let mut log = Vec::new();
+        assert!(dispatch(&mut root, &[1, 0], &event, &mut log));
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.last(), Some(&(vec![1], Bubble, local)));
+
+        // the root as the target only receives it once.
+        let mut log = Vec::new();
+        assert!(!dispatch(&mut root, &[], &event, &mut log));
+        assert_eq!(log, vec![(vec![], Target, outer)]);
+
+        // paths which don't exist aren't dispatched at all.
+        let mut log = Vec::new();
+        assert!(!dispatch(&mut root, &[0, 0], &event, &mut log));
+        assert!(log.is_empty());
+
+        let key = InputEvent::TextInput('a');
+        assert_eq!(dispatch_pointer(&mut root, &key, &mut log), None);
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn test_input_state() {
+        let mut state = InputState::new();
+        let modifiers = Modifiers { shift: true, ..Default::default() };
+
+        state.handle(&InputEvent::PointerMove { position: Point::new(5.0, 5.0), modifiers });
+        state.handle(&InputEvent::PointerDown {
+            position: Point::new(5.0, 5.0),
+            button: MouseButton::Left,
+            modifiers,
+        });
+        state.handle(&InputEvent::KeyDown { key: Key::A, scancode: 0, modifiers });
+
+        assert_eq!(state.cursor(), Some(Point::new(5.0, 5.0)));
+        assert!(state.is_button_down(MouseButton::Left));
+        assert!(!state.is_button_down(MouseButton::Right));
+        assert!(state.is_key_down(Key::A));
+        assert!(state.modifiers().shift);
+
+        state.handle(&InputEvent::PointerUp {
+            position: Point::new(5.0, 5.0),
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        });
+
+        assert!(!state.is_button_down(MouseButton::Left));
+        assert!(state.modifiers().is_empty());
+    }
+
+    #[test]
+    fn test_click_synthesis() {
+        let mut clicks = ClickSynthesizer::default();
+        let start = Instant::now();
+        let press = |x| InputEvent::PointerDown {
+            position: Point::new(x, 0.0),
+            button: MouseButton::Left,
+            modifiers: Modifiers::default(),
+        };
+        let count = |event| match event {
+            Some(InputEvent::Click { count, .. }) => count,
+            _ => panic!("expected a click"),
+        };
+
+        assert_eq!(count(clicks.handle_at(&press(0.0), start)), 1);
+        assert_eq!(count(clicks.handle_at(&press(1.0), start + Duration::from_millis(200))), 2);
+        assert_eq!(count(clicks.handle_at(&press(2.0), start + Duration::from_millis(400))), 3);
+        // too far from the first press.
+        assert_eq!(count(clicks.handle_at(&press(10.0), start + Duration::from_millis(600))), 1);
+        // too long after the previous press.
+        assert_eq!(count(clicks.handle_at(&press(10.0), start + Duration::from_millis(1200))), 1);
+        assert!(clicks
+            .handle_at(
+                &InputEvent::PointerMove {
+                    position: Point::zero(),
+                    modifiers: Modifiers::default()
+                },
+                start
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_kinetic_scroll() {
+        let mut scroll = KineticScroll::new(40.0, 12.0);
+        scroll.set_max_offset(Vector::new(0.0, 500.0));
+
+        assert!(scroll.handle(&InputEvent::Wheel {
+            position: Point::zero(),
+            delta: ScrollDelta::Lines(Vector::new(0.0, -3.0)),
+            modifiers: Modifiers::default(),
+        }));
+        assert!(scroll.is_moving());
+
+        for _ in 0..120 {
+            scroll.update(Duration::from_millis(16));
+        }
+
+        assert!(!scroll.is_moving());
+        assert!((scroll.offset().y - 120.0).abs() < 1.0);
+        assert_eq!(scroll.offset().x, 0.0);
+
+        // scrolling past the start stops at the edge.
+        scroll.scroll(ScrollDelta::Pixels(Vector::new(0.0, 1000.0)));
+        assert_eq!(scroll.update(Duration::from_secs(1)), Vector::zero());
+        assert!(!scroll.is_moving());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_kinetic_scroll_without_friction() {
+        KineticScroll::new(40.0, 0.0);
+    }
+
+    #[cfg(feature = "winit")]
+    #[allow(deprecated)]
+    #[test]
+    fn test_winit_pointer_tracking() {
+        use winit::{
+            dpi::PhysicalPosition,
+            event::{DeviceId, ElementState, Event, WindowEvent},
+            window::WindowId,
+        };
+
+        let mut converter = WinitInputConverter::new(1.0);
+        let device_id = unsafe { DeviceId::dummy() };
+        let window_id = unsafe { WindowId::dummy() };
+
+        let moved: Event<()> = Event::WindowEvent {
+            window_id,
+            event: WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(10, 20),
+                modifiers: Default::default(),
+            },
+        };
+        let pressed: Event<()> = Event::WindowEvent {
+            window_id,
+            event: WindowEvent::MouseInput {
+                device_id,
+                state: ElementState::Pressed,
+                button: winit::event::MouseButton::Left,
+                modifiers: Default::default(),
+            },
+        };
+
+        assert_eq!(
+            converter.convert(&moved),
+            Some(InputEvent::PointerMove {
+                position: Point::new(10.0, 20.0),
+                modifiers: Modifiers::default()
+            })
+        );
+        assert_eq!(
+            converter.convert(&pressed),
+            Some(InputEvent::PointerDown {
+                position: Point::new(10.0, 20.0),
+                button: MouseButton::Left,
+                modifiers: Modifiers::default()
+            })
+        );
+    }
+}
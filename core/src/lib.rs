@@ -1,7 +1,27 @@
 //! Core components of Reclutch, such as the Widget types and the display module.
 
+pub mod accessibility;
+pub mod anchor;
+pub mod animation;
+pub mod asset;
+pub mod behavior;
+pub mod clipboard;
+pub mod declarative;
 pub mod display;
+pub mod draw;
 pub mod error;
+pub mod focus;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod input;
+pub mod observe;
+pub mod popup;
+pub mod simulation;
+pub mod state;
+#[cfg(feature = "tasks")]
+pub mod task;
+pub mod tiling;
+pub mod viewport;
 
 pub use euclid;
 pub use font_kit;
@@ -9,16 +29,32 @@ pub use palette;
 
 pub use reclutch_event as event;
 
+/// Commonly used traits (including extension traits) and types,
+/// such that most widget modules only need `use reclutch::prelude::*;`.
 pub mod prelude {
     pub use crate::{
-        display::GraphicsDisplay,
-        widget::{Widget, WidgetChildren},
+        display::{
+            Color, CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, GraphicsDisplayStroke, Point, Rect, RectExt, Size, Vector,
+        },
+        draw::{DrawContext, HasFrameTiming},
+        widget::{Widget, WidgetChildren, WidgetChildrenExt, WidgetExt},
     };
-    pub use reclutch_event::prelude::*;
+    pub use reclutch_event::{prelude::*, RcEventListener, RcEventQueue};
 }
 
 pub mod widget {
-    use crate::display::{GraphicsDisplay, Rect};
+    use {
+        crate::{
+            display::{
+                DisplayCommand, GraphicsDisplay, Point, Rect, Size, Transform, TransformedDisplay,
+            },
+            draw::{DrawContext, ScaleFactorChanged},
+            input::PropagatingEvent,
+        },
+        reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+        std::any::Any,
+    };
 
     /// Simple widget trait with a render boundary, event updating and rendering.
     pub trait Widget {
@@ -33,12 +69,77 @@ pub mod widget {
             Rect::default()
         }
 
+        /// The size this widget would ideally be given by its parent.
+        ///
+        /// Defaults to the size of the current [`bounds`](trait.Widget.html#method.bounds).
+        fn size_hint(&self) -> Size {
+            self.bounds().size
+        }
+
+        /// The smallest size this widget can sensibly be given. Defaults to zero.
+        fn min_size(&self) -> Size {
+            Size::zero()
+        }
+
+        /// The largest size this widget can sensibly be given. Defaults to unbounded.
+        fn max_size(&self) -> Size {
+            Size::new(f32::INFINITY, f32::INFINITY)
+        }
+
+        /// Whether this widget (and its children) are shown.
+        ///
+        /// Invisible widgets aren't drawn by [`draw_child`](fn.draw_child.html) and aren't found by [hit testing](../input/fn.hit_test.html).
+        fn is_visible(&self) -> bool {
+            true
+        }
+
+        /// Whether this widget (and its children) accept input.
+        ///
+        /// Disabled widgets are still drawn (it's up to the widget to look disabled, e.g. through
+        /// [`filter_disabled`](../draw/struct.DrawContext.html#method.filter_disabled)), however [hit testing](../input/fn.hit_test.html)
+        /// resolves to their closest enabled ancestor instead.
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        /// Whether this widget can receive keyboard focus (see [`FocusTracker`](../focus/struct.FocusTracker.html)), such as a text field or button.
+        ///
+        /// Focusable widgets are visited by tab navigation in the order of [`children`](trait.WidgetChildren.html#method.children), parents before their children.
+        /// Invisible and disabled widgets are never focused, regardless of this.
+        fn accepts_focus(&self) -> bool {
+            false
+        }
+
+        /// Invoked when this widget gains (`focused` is `true`) or loses keyboard focus, e.g. to repaint a [focus ring](../focus/fn.push_focus_ring.html)
+        /// or to show a text cursor.
+        fn on_focus_changed(&mut self, _focused: bool, _aux: &mut Self::UpdateAux) {}
+
+        /// Maps the local coordinate space of this widget (which its drawing and children are in) to that of its parent.
+        ///
+        /// This lets children be positioned relative to their parent, rather than every widget computing absolute positions.
+        /// Note that [`bounds`](trait.Widget.html#method.bounds) is still in the coordinate space of the parent.
+        ///
+        /// For this to take effect, the parent must draw the widget through [`draw_child`](fn.draw_child.html),
+        /// and pointer positions must be mapped into the local space (see [`InputEvent::transformed`](../input/enum.InputEvent.html#method.transformed)).
+        fn transform(&self) -> Transform {
+            Transform::identity()
+        }
+
+        /// Stacking order of this widget among its siblings; siblings with a higher z-index are drawn over (and [hit](../input/fn.hit_test.html) before) those with a lower one.
+        ///
+        /// Siblings with the same z-index are stacked in the order of [`children`](trait.WidgetChildren.html#method.children),
+        /// so containers can change stacking without reordering their children (see [`children_zsorted`](trait.WidgetChildrenExt.html#method.children_zsorted)).
+        fn z_index(&self) -> i32 {
+            0
+        }
+
         /// Perhaps the most important method, this method gives every widget an opportunity
         /// to process events, emit events and execute all the side effects attached to such.
         /// Event handling is performed through a focused event system (see the event module).
         ///
         /// This is also where the [`UpdateAux`](trait.Widget.html#associatedtype.UpdateAux) associated type comes in.
-        /// It allows you to pass mutable data around during updating.
+        /// It allows you to pass mutable data around during updating, such as the timing of the frame for animations
+        /// (see [`HasFrameTiming`](../draw/trait.HasFrameTiming.html)).
         ///
         /// Here's an example implementation of `update`:
         /// ```ignore
@@ -52,7 +153,7 @@ pub mod widget {
         ///
         ///     fn update(&mut self, aux: &mut GlobalData) {
         ///         // propagate to children
-        ///         propagate_update(self, aux);
+        ///         self.propagate_update(aux);
         ///
         ///         for event in self.count_up_listener.peek() {
         ///             self.count += 1;
@@ -70,6 +171,26 @@ pub mod widget {
         /// ```
         fn update(&mut self, _aux: &mut Self::UpdateAux) {}
 
+        /// Handles an input event [dispatched](../input/fn.dispatch.html) along the path to a target widget.
+        ///
+        /// The event first travels from the root down to the target (the capture phase), then back up to the root (the bubble phase).
+        /// Calling [`stop_propagation`](../input/struct.PropagatingEvent.html#method.stop_propagation) prevents any further widgets from
+        /// receiving it, which is how, for example, a panel stops the panels beneath it from reacting to a click.
+        ///
+        /// Handlers shouldn't propagate to their children themselves; the dispatcher already visits each widget along the path.
+        fn handle_input(&mut self, _event: &mut PropagatingEvent<'_>, _aux: &mut Self::UpdateAux) {}
+
+        /// Invoked when the scale factor of the display changes (see [`change_scale_factor`](../draw/fn.change_scale_factor.html)),
+        /// e.g. when the window is moved to a monitor with a different DPI.
+        ///
+        /// Widgets which keep anything in physical pixels (text layouts, images rasterized at a certain size, etc) should rebuild it here and repaint.
+        fn on_scale_factor_changed(
+            &mut self,
+            _event: &ScaleFactorChanged,
+            _aux: &mut Self::UpdateAux,
+        ) {
+        }
+
         /// Drawing is renderer-agnostic, however this doesn't mean the API is restrictive.
         /// Generally, drawing is performed through [`CommandGroup`](../display/struct.CommandGroup.html).
         /// This is also where [`GraphicalAux`](trait.Widget.html#associatedtype.GraphicalAux) and [`DisplayObject`](trait.Widget.html#associatedtype.DisplayObject) come in handy.
@@ -118,6 +239,132 @@ pub mod widget {
             _aux: &mut Self::GraphicalAux,
         ) {
         }
+
+        /// Draws through a [`DrawContext`](../draw/struct.DrawContext.html), which, besides the display,
+        /// carries the transform and clip of the subtree, the scale factor, the theme and the frame timing.
+        ///
+        /// Defaults to [`draw`](trait.Widget.html#method.draw) with the display of the context,
+        /// so widgets which don't need the context needn't implement this.
+        /// Widgets overriding this should draw their children through [`draw_child_in_context`](fn.draw_child_in_context.html).
+        fn draw_in_context(
+            &mut self,
+            cx: &mut DrawContext<'_, Self::DisplayObject>,
+            aux: &mut Self::GraphicalAux,
+        ) {
+            self.draw(&mut cx.display(), aux);
+        }
+    }
+
+    /// Draws `child` within its own coordinate space, as given by [`transform`](trait.Widget.html#method.transform).
+    ///
+    /// `display` is the display the parent is drawing to (i.e. in the coordinate space of the parent).
+    ///
+    /// Nothing is drawn if the child [isn't visible](trait.Widget.html#method.is_visible).
+    /// Since the command groups of the child then aren't maintained, they are removed (unless they were pushed as always alive),
+    /// and a [`CommandGroup`](../display/struct.CommandGroup.html) pushes its commands anew once the child is shown again.
+    pub fn draw_child<W: Widget + ?Sized>(
+        child: &mut W,
+        display: &mut dyn GraphicsDisplay<W::DisplayObject>,
+        aux: &mut W::GraphicalAux,
+    ) {
+        if !child.is_visible() {
+            return;
+        }
+
+        let transform = child.transform();
+        child.draw(&mut TransformedDisplay::new(display, transform), aux);
+    }
+
+    /// Equivalent of [`draw_child`](fn.draw_child.html) for [`draw_in_context`](trait.Widget.html#method.draw_in_context).
+    ///
+    /// `cx` is the context of the parent; the child is drawn in a [child context](../draw/struct.DrawContext.html#method.child)
+    /// with the [`transform`](trait.Widget.html#method.transform) of the child, which is [disabled](../draw/struct.DrawContext.html#structfield.disabled)
+    /// if the child (or any of its ancestors) [isn't enabled](trait.Widget.html#method.is_enabled).
+    pub fn draw_child_in_context<W: Widget + ?Sized>(
+        child: &mut W,
+        cx: &mut DrawContext<'_, W::DisplayObject>,
+        aux: &mut W::GraphicalAux,
+    ) {
+        if !child.is_visible() {
+            return;
+        }
+
+        let transform = child.transform();
+        let mut cx = cx.child(transform, None);
+        cx.disabled |= !child.is_enabled();
+        child.draw_in_context(&mut cx, aux);
+    }
+
+    /// Clamps `size` between the [minimum](trait.Widget.html#method.min_size) and [maximum](trait.Widget.html#method.max_size) sizes of `widget`.
+    ///
+    /// Should the minimum exceed the maximum, the minimum takes precedence.
+    pub fn constrain_size<W: Widget + ?Sized>(widget: &W, size: Size) -> Size {
+        size.min(widget.max_size()).max(widget.min_size())
+    }
+
+    /// Convenience methods for any widget.
+    pub trait WidgetExt: Widget {
+        /// See [`constrain_size`](fn.constrain_size.html).
+        #[inline]
+        fn constrain_size(&self, size: Size) -> Size {
+            constrain_size(self, size)
+        }
+
+        /// Returns `true` if `point` (in the coordinate space of the parent) is within the bounds.
+        #[inline]
+        fn contains_point(&self, point: Point) -> bool {
+            self.bounds().contains(point)
+        }
+    }
+
+    impl<W: Widget + ?Sized> WidgetExt for W {}
+
+    /// Emitted when the [`bounds`](trait.Widget.html#method.bounds) of a widget change.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct BoundsChanged {
+        pub old: Rect,
+        pub new: Rect,
+    }
+
+    /// Standard way for a widget to emit [`BoundsChanged`](struct.BoundsChanged.html).
+    ///
+    /// The widget keeps this as a field and passes its new bounds to [`update`](struct.BoundsNotifier.html#method.update)
+    /// whenever it moves or resizes. Parents and dependent siblings can then [`listen`](struct.BoundsNotifier.html#method.listen)
+    /// to stay in sync, rather than being updated manually.
+    #[derive(Debug)]
+    pub struct BoundsNotifier {
+        bounds: Rect,
+        pub event: RcEventQueue<BoundsChanged>,
+    }
+
+    impl BoundsNotifier {
+        /// Creates a new notifier with the initial bounds of the widget.
+        pub fn new(bounds: Rect) -> Self {
+            BoundsNotifier { bounds, event: RcEventQueue::new() }
+        }
+
+        /// Emits `BoundsChanged` if `bounds` differs from the previous bounds, returning whether it did.
+        pub fn update(&mut self, bounds: Rect) -> bool {
+            if bounds != self.bounds {
+                let old = std::mem::replace(&mut self.bounds, bounds);
+                self.event.emit_owned(BoundsChanged { old, new: bounds });
+                true
+            } else {
+                false
+            }
+        }
+
+        /// Returns the last bounds given to the notifier.
+        #[inline]
+        pub fn bounds(&self) -> Rect {
+            self.bounds
+        }
+
+        /// Creates a listener for `BoundsChanged` events.
+        #[inline]
+        pub fn listen(&self) -> RcEventListener<BoundsChanged> {
+            self.event.listen()
+        }
     }
 
     /// Interface to get children of a widget as an array of dynamic widgets.
@@ -148,4 +395,735 @@ pub mod widget {
             Vec::new()
         }
     }
+
+    type DynWidgetChildren<'a, W> = dyn WidgetChildren<
+            UpdateAux = <W as Widget>::UpdateAux,
+            GraphicalAux = <W as Widget>::GraphicalAux,
+            DisplayObject = <W as Widget>::DisplayObject,
+        > + 'a;
+
+    /// Convenience methods for propagating to and traversing children.
+    pub trait WidgetChildrenExt: WidgetChildren {
+        /// Updates each child.
+        fn propagate_update(&mut self, aux: &mut Self::UpdateAux) {
+            for child in self.children_mut() {
+                child.update(aux);
+            }
+        }
+
+        /// Notifies every descendant of a change of scale factor, parents before their children.
+        fn propagate_scale_factor_changed(
+            &mut self,
+            event: &ScaleFactorChanged,
+            aux: &mut Self::UpdateAux,
+        ) {
+            for child in self.children_mut() {
+                child.on_scale_factor_changed(event, aux);
+                child.propagate_scale_factor_changed(event, aux);
+            }
+        }
+
+        /// Draws each child through [`draw_child`](fn.draw_child.html), from the lowest [z-index](trait.Widget.html#method.z_index) up.
+        fn propagate_draw(
+            &mut self,
+            display: &mut dyn GraphicsDisplay<Self::DisplayObject>,
+            aux: &mut Self::GraphicalAux,
+        ) {
+            for child in self.children_zsorted_mut() {
+                draw_child(child, display, aux);
+            }
+        }
+
+        /// Draws each child through [`draw_child_in_context`](fn.draw_child_in_context.html), from the lowest [z-index](trait.Widget.html#method.z_index) up.
+        fn propagate_draw_in_context(
+            &mut self,
+            cx: &mut DrawContext<'_, Self::DisplayObject>,
+            aux: &mut Self::GraphicalAux,
+        ) {
+            for child in self.children_zsorted_mut() {
+                draw_child_in_context(child, cx, aux);
+            }
+        }
+
+        /// Returns the children in stacking order, from the lowest [z-index](trait.Widget.html#method.z_index) (drawn first) to the highest.
+        ///
+        /// The sort is stable, so children with the same z-index keep the order of [`children`](trait.WidgetChildren.html#method.children).
+        fn children_zsorted(&self) -> Vec<&DynWidgetChildren<'_, Self>> {
+            let mut children = self.children();
+            children.sort_by_key(|child| child.z_index());
+            children
+        }
+
+        /// Same as [`children_zsorted`](trait.WidgetChildrenExt.html#method.children_zsorted), but mutable.
+        fn children_zsorted_mut(&mut self) -> Vec<&mut DynWidgetChildren<'_, Self>> {
+            let mut children = self.children_mut();
+            children.sort_by_key(|child| child.z_index());
+            children
+        }
+
+        /// Invokes `f` on every descendant (depth-first, parents before their children), along with its depth (starting at 0 for children).
+        fn visit_descendants(&self, f: &mut dyn FnMut(&DynWidgetChildren<'_, Self>, usize)) {
+            fn visit<W: WidgetChildren + ?Sized>(
+                widget: &W,
+                depth: usize,
+                f: &mut dyn FnMut(&DynWidgetChildren<'_, W>, usize),
+            ) {
+                for child in widget.children() {
+                    f(child, depth);
+                    visit(child, depth + 1, f);
+                }
+            }
+
+            visit(self, 0, f)
+        }
+    }
+
+    impl<W: WidgetChildren + ?Sized> WidgetChildrenExt for W {}
+
+    impl<W: Widget + ?Sized> Widget for Box<W> {
+        type UpdateAux = W::UpdateAux;
+        type GraphicalAux = W::GraphicalAux;
+        type DisplayObject = W::DisplayObject;
+
+        #[inline]
+        fn bounds(&self) -> Rect {
+            (**self).bounds()
+        }
+
+        #[inline]
+        fn size_hint(&self) -> Size {
+            (**self).size_hint()
+        }
+
+        #[inline]
+        fn min_size(&self) -> Size {
+            (**self).min_size()
+        }
+
+        #[inline]
+        fn max_size(&self) -> Size {
+            (**self).max_size()
+        }
+
+        #[inline]
+        fn is_visible(&self) -> bool {
+            (**self).is_visible()
+        }
+
+        #[inline]
+        fn is_enabled(&self) -> bool {
+            (**self).is_enabled()
+        }
+
+        #[inline]
+        fn accepts_focus(&self) -> bool {
+            (**self).accepts_focus()
+        }
+
+        #[inline]
+        fn transform(&self) -> Transform {
+            (**self).transform()
+        }
+
+        #[inline]
+        fn z_index(&self) -> i32 {
+            (**self).z_index()
+        }
+
+        #[inline]
+        fn update(&mut self, aux: &mut Self::UpdateAux) {
+            (**self).update(aux)
+        }
+
+        #[inline]
+        fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, aux: &mut Self::UpdateAux) {
+            (**self).handle_input(event, aux)
+        }
+
+        #[inline]
+        fn on_focus_changed(&mut self, focused: bool, aux: &mut Self::UpdateAux) {
+            (**self).on_focus_changed(focused, aux)
+        }
+
+        #[inline]
+        fn on_scale_factor_changed(
+            &mut self,
+            event: &ScaleFactorChanged,
+            aux: &mut Self::UpdateAux,
+        ) {
+            (**self).on_scale_factor_changed(event, aux)
+        }
+
+        #[inline]
+        fn draw(
+            &mut self,
+            display: &mut dyn GraphicsDisplay<Self::DisplayObject>,
+            aux: &mut Self::GraphicalAux,
+        ) {
+            (**self).draw(display, aux)
+        }
+
+        #[inline]
+        fn draw_in_context(
+            &mut self,
+            cx: &mut DrawContext<'_, Self::DisplayObject>,
+            aux: &mut Self::GraphicalAux,
+        ) {
+            (**self).draw_in_context(cx, aux)
+        }
+    }
+
+    impl<W: WidgetChildren + ?Sized> WidgetChildren for Box<W> {
+        #[inline]
+        fn children(&self) -> Vec<&DynWidgetChildren<'_, Self>> {
+            (**self).children()
+        }
+
+        #[inline]
+        fn children_mut(&mut self) -> Vec<&mut DynWidgetChildren<'_, Self>> {
+            (**self).children_mut()
+        }
+    }
+
+    /// A widget which can be downcast to its concrete type.
+    ///
+    /// This is implemented for every `'static` widget, such that heterogeneous containers can store
+    /// [`BoxedWidget`](type.BoxedWidget.html)s and still retrieve the concrete type of a child:
+    /// ```ignore
+    /// let mut panels: Vec<BoxedWidget<Globals>> = vec![Box::new(ImagePanel::new(..)), Box::new(ToolPanel::new(..))];
+    ///
+    /// if let Some(image) = panels[0].downcast_mut::<ImagePanel>() {
+    ///     image.zoom_in();
+    /// }
+    /// ```
+    pub trait AnyWidget: WidgetChildren + Any {
+        fn as_any(&self) -> &dyn Any;
+        fn as_any_mut(&mut self) -> &mut dyn Any;
+        fn into_any(self: Box<Self>) -> Box<dyn Any>;
+    }
+
+    impl<W: WidgetChildren + Any> AnyWidget for W {
+        #[inline]
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        #[inline]
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        #[inline]
+        fn into_any(self: Box<Self>) -> Box<dyn Any> {
+            self
+        }
+    }
+
+    /// A boxed widget of any type, given its auxiliary types and display object.
+    pub type BoxedWidget<U, G = (), D = DisplayCommand> =
+        Box<dyn AnyWidget<UpdateAux = U, GraphicalAux = G, DisplayObject = D>>;
+
+    impl<U: 'static, G: 'static, D: 'static>
+        dyn AnyWidget<UpdateAux = U, GraphicalAux = G, DisplayObject = D>
+    {
+        /// Returns `true` if the widget is of type `T`.
+        #[inline]
+        pub fn is<T: Any>(&self) -> bool {
+            self.as_any().is::<T>()
+        }
+
+        /// Returns the widget as `T`, if it's of that type.
+        #[inline]
+        pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+            self.as_any().downcast_ref()
+        }
+
+        /// Returns the widget as `T`, if it's of that type.
+        #[inline]
+        pub fn downcast_mut<T: Any>(&mut self) -> Option<&mut T> {
+            self.as_any_mut().downcast_mut()
+        }
+
+        /// Converts the boxed widget into a box of `T`, or returns it unchanged if it's not of that type.
+        pub fn downcast<T: Any>(self: Box<Self>) -> Result<Box<T>, Box<Self>> {
+            if self.is::<T>() {
+                Ok(self.into_any().downcast().unwrap())
+            } else {
+                Err(self)
+            }
+        }
+    }
+
+    /// Projects the auxiliaries of a parent onto those of a child, for [`MapAux`](struct.MapAux.html).
+    ///
+    /// This is usually implemented on a unit struct, for instance, to let a widget which only needs a theme
+    /// live in a tree whose update auxiliary is the global state of the application:
+    /// ```ignore
+    /// struct ThemeOnly;
+    ///
+    /// impl AuxProjection for ThemeOnly {
+    ///     type ParentUpdateAux = Globals;
+    ///     type ParentGraphicalAux = ();
+    ///     type UpdateAux = Theme;
+    ///     type GraphicalAux = ();
+    ///
+    ///     fn update_aux(aux: &mut Globals) -> &mut Theme {
+    ///         &mut aux.theme
+    ///     }
+    ///
+    ///     fn graphical_aux(aux: &mut ()) -> &mut () {
+    ///         aux
+    ///     }
+    /// }
+    /// ```
+    pub trait AuxProjection {
+        type ParentUpdateAux;
+        type ParentGraphicalAux;
+        type UpdateAux;
+        type GraphicalAux;
+
+        fn update_aux(aux: &mut Self::ParentUpdateAux) -> &mut Self::UpdateAux;
+        fn graphical_aux(aux: &mut Self::ParentGraphicalAux) -> &mut Self::GraphicalAux;
+    }
+
+    /// Wraps a widget such that it takes the auxiliaries of its parent, projecting them through `F`.
+    ///
+    /// This allows widgets with different auxiliary types (e.g. those of a reusable widget library) to live in the same tree.
+    ///
+    /// Note that the children of the wrapped widget aren't exposed through [`WidgetChildren`](trait.WidgetChildren.html),
+    /// since they're of a different auxiliary type; the wrapped subtree is opaque to traversal (and thereby hit testing),
+    /// however the wrapped widget still propagates updates and drawing to its children as usual.
+    #[repr(transparent)]
+    pub struct MapAux<W, F> {
+        widget: W,
+        projection: std::marker::PhantomData<F>,
+    }
+
+    impl<W: Widget, F: AuxProjection<UpdateAux = W::UpdateAux, GraphicalAux = W::GraphicalAux>>
+        MapAux<W, F>
+    {
+        pub fn new(widget: W) -> Self {
+            MapAux { widget, projection: Default::default() }
+        }
+
+        /// Views a widget as wrapped, without moving it.
+        pub fn from_ref(widget: &W) -> &Self {
+            // SAFETY: `MapAux` is `repr(transparent)` over `W` (the other field being zero-sized).
+            unsafe { &*(widget as *const W as *const Self) }
+        }
+
+        /// Views a widget as wrapped, without moving it.
+        pub fn from_mut(widget: &mut W) -> &mut Self {
+            // SAFETY: See `from_ref`.
+            unsafe { &mut *(widget as *mut W as *mut Self) }
+        }
+
+        #[inline]
+        pub fn get(&self) -> &W {
+            &self.widget
+        }
+
+        #[inline]
+        pub fn get_mut(&mut self) -> &mut W {
+            &mut self.widget
+        }
+
+        #[inline]
+        pub fn into_inner(self) -> W {
+            self.widget
+        }
+    }
+
+    impl<W: std::fmt::Debug, F> std::fmt::Debug for MapAux<W, F> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_tuple("MapAux").field(&self.widget).finish()
+        }
+    }
+
+    impl<W, F> Widget for MapAux<W, F>
+    where
+        W: Widget,
+        F: AuxProjection<UpdateAux = W::UpdateAux, GraphicalAux = W::GraphicalAux>,
+    {
+        type UpdateAux = F::ParentUpdateAux;
+        type GraphicalAux = F::ParentGraphicalAux;
+        type DisplayObject = W::DisplayObject;
+
+        #[inline]
+        fn bounds(&self) -> Rect {
+            self.widget.bounds()
+        }
+
+        #[inline]
+        fn size_hint(&self) -> Size {
+            self.widget.size_hint()
+        }
+
+        #[inline]
+        fn min_size(&self) -> Size {
+            self.widget.min_size()
+        }
+
+        #[inline]
+        fn max_size(&self) -> Size {
+            self.widget.max_size()
+        }
+
+        #[inline]
+        fn is_visible(&self) -> bool {
+            self.widget.is_visible()
+        }
+
+        #[inline]
+        fn is_enabled(&self) -> bool {
+            self.widget.is_enabled()
+        }
+
+        #[inline]
+        fn accepts_focus(&self) -> bool {
+            self.widget.accepts_focus()
+        }
+
+        #[inline]
+        fn transform(&self) -> Transform {
+            self.widget.transform()
+        }
+
+        #[inline]
+        fn z_index(&self) -> i32 {
+            self.widget.z_index()
+        }
+
+        #[inline]
+        fn update(&mut self, aux: &mut F::ParentUpdateAux) {
+            self.widget.update(F::update_aux(aux))
+        }
+
+        #[inline]
+        fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, aux: &mut F::ParentUpdateAux) {
+            self.widget.handle_input(event, F::update_aux(aux))
+        }
+
+        #[inline]
+        fn on_focus_changed(&mut self, focused: bool, aux: &mut F::ParentUpdateAux) {
+            self.widget.on_focus_changed(focused, F::update_aux(aux))
+        }
+
+        #[inline]
+        fn on_scale_factor_changed(
+            &mut self,
+            event: &ScaleFactorChanged,
+            aux: &mut F::ParentUpdateAux,
+        ) {
+            self.widget.on_scale_factor_changed(event, F::update_aux(aux))
+        }
+
+        #[inline]
+        fn draw(
+            &mut self,
+            display: &mut dyn GraphicsDisplay<Self::DisplayObject>,
+            aux: &mut F::ParentGraphicalAux,
+        ) {
+            self.widget.draw(display, F::graphical_aux(aux))
+        }
+
+        #[inline]
+        fn draw_in_context(
+            &mut self,
+            cx: &mut DrawContext<'_, Self::DisplayObject>,
+            aux: &mut F::ParentGraphicalAux,
+        ) {
+            self.widget.draw_in_context(cx, F::graphical_aux(aux))
+        }
+    }
+
+    impl<W, F> WidgetChildren for MapAux<W, F>
+    where
+        W: Widget,
+        F: AuxProjection<UpdateAux = W::UpdateAux, GraphicalAux = W::GraphicalAux>,
+    {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::widget::*,
+        crate::{
+            display::{
+                backend::RecordingDisplay, Color, CommandGroup, DisplayCommand, DisplayFilter,
+                GraphicsDisplay, Point, Rect, Size, ZOrder,
+            },
+            draw::DrawContext,
+        },
+        reclutch_event::prelude::*,
+    };
+
+    struct Constrained;
+
+    impl Widget for Constrained {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn min_size(&self) -> Size {
+            Size::new(10.0, 20.0)
+        }
+
+        fn max_size(&self) -> Size {
+            Size::new(100.0, f32::INFINITY)
+        }
+    }
+
+    #[test]
+    fn test_constrain_size() {
+        assert_eq!(constrain_size(&Constrained, Size::new(5.0, 5.0)), Size::new(10.0, 20.0));
+        assert_eq!(constrain_size(&Constrained, Size::new(500.0, 500.0)), Size::new(100.0, 500.0));
+        assert_eq!(Constrained.size_hint(), Size::zero());
+    }
+
+    #[test]
+    fn test_bounds_notifier() {
+        let initial = Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0));
+        let moved = Rect::new(Point::new(5.0, 0.0), Size::new(10.0, 10.0));
+
+        let mut notifier = BoundsNotifier::new(initial);
+        let listener = notifier.listen();
+
+        assert!(!notifier.update(initial));
+        assert!(notifier.update(moved));
+
+        assert_eq!(listener.peek(), vec![BoundsChanged { old: initial, new: moved }]);
+    }
+
+    struct Node(Vec<Node>, u32);
+
+    impl Widget for Node {
+        type UpdateAux = u32;
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            Rect::new(Point::zero(), Size::new(10.0, 10.0))
+        }
+
+        fn update(&mut self, aux: &mut u32) {
+            *aux += 1;
+            self.1 = *aux;
+            self.propagate_update(aux);
+        }
+    }
+
+    impl WidgetChildren for Node {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = u32, GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.0.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<&mut dyn WidgetChildren<UpdateAux = u32, GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.0.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_widget_extensions() {
+        let mut root = Node(vec![Node(vec![Node(vec![], 0)], 0), Node(vec![], 0)], 0);
+
+        let mut count = 0;
+        root.update(&mut count);
+        assert_eq!(count, 4);
+
+        let mut visited = Vec::new();
+        root.visit_descendants(&mut |_, depth| visited.push(depth));
+        assert_eq!(visited, vec![0, 1, 0]);
+
+        assert!(root.contains_point(Point::new(5.0, 5.0)));
+        assert!(!root.contains_point(Point::new(15.0, 5.0)));
+    }
+
+    struct Raised(i32, Vec<Raised>);
+
+    impl Widget for Raised {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn z_index(&self) -> i32 {
+            self.0
+        }
+    }
+
+    impl WidgetChildren for Raised {
+        fn children(
+            &self,
+        ) -> Vec<&dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.1.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(
+            &mut self,
+        ) -> Vec<&mut dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>>
+        {
+            self.1.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_children_zsorted() {
+        let mut root = Raised(
+            0,
+            vec![
+                Raised(2, vec![]),
+                Raised(-1, vec![]),
+                Raised(0, vec![]),
+                Raised(-1, vec![Raised(5, vec![])]),
+            ],
+        );
+        let z_indices = |children: Vec<
+            &dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>,
+        >| {
+            children
+                .iter()
+                .map(|child| (child.z_index(), child.children().len()))
+                .collect::<Vec<_>>()
+        };
+
+        // stable, so the two children at -1 keep their order.
+        assert_eq!(z_indices(root.children_zsorted()), vec![(-1, 0), (-1, 1), (0, 0), (2, 0)]);
+        assert_eq!(root.children_zsorted_mut().last().map(|child| child.z_index()), Some(2));
+        assert_eq!(root.children()[0].z_index(), 2);
+        assert_eq!(Box::new(Raised(3, vec![])).z_index(), 3);
+    }
+
+    struct Counters {
+        frames: u32,
+        nodes: u32,
+    }
+
+    struct NodeCounter;
+
+    impl AuxProjection for NodeCounter {
+        type ParentUpdateAux = Counters;
+        type ParentGraphicalAux = ();
+        type UpdateAux = u32;
+        type GraphicalAux = ();
+
+        fn update_aux(aux: &mut Counters) -> &mut u32 {
+            &mut aux.nodes
+        }
+
+        fn graphical_aux(aux: &mut ()) -> &mut () {
+            aux
+        }
+    }
+
+    #[test]
+    fn test_map_aux() {
+        let mut root = Node(vec![Node(vec![], 0)], 0);
+        let mut counters = Counters { frames: 1, nodes: 0 };
+
+        MapAux::<_, NodeCounter>::from_mut(&mut root).update(&mut counters);
+        let mut wrapped: MapAux<_, NodeCounter> = MapAux::new(root);
+        wrapped.update(&mut counters);
+
+        assert_eq!((counters.frames, counters.nodes), (1, 4));
+        assert_eq!(wrapped.get().0[0].1, 4);
+        assert!(wrapped.children().is_empty());
+        assert_eq!(wrapped.bounds(), wrapped.into_inner().bounds());
+    }
+
+    #[test]
+    fn test_boxed_widgets() {
+        let mut widgets: Vec<BoxedWidget<u32, (), ()>> = vec![
+            Box::new(Node(vec![Node(vec![], 0)], 0)),
+            // the inner box is the concrete type here, so it isn't a `Node`.
+            Box::new(Box::new(Node(vec![], 0))),
+        ];
+
+        let mut count = 0;
+        for widget in &mut widgets {
+            widget.update(&mut count);
+        }
+
+        assert_eq!(count, 3);
+        assert_eq!(widgets[0].children().len(), 1);
+        assert_eq!(widgets[0].downcast_ref::<Node>().unwrap().1, 1);
+        assert!(widgets[1].downcast_mut::<Node>().is_none());
+        assert!(widgets[1].is::<Box<Node>>());
+        assert!(widgets.remove(1).downcast::<Node>().is_err());
+        assert_eq!(widgets.remove(0).downcast::<Node>().ok().unwrap().0[0].1, 2);
+    }
+
+    struct Toggled(bool, bool, CommandGroup);
+
+    impl Widget for Toggled {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = DisplayCommand;
+
+        fn is_visible(&self) -> bool {
+            self.0
+        }
+
+        fn is_enabled(&self) -> bool {
+            self.1
+        }
+
+        fn draw_in_context(&mut self, cx: &mut DrawContext<'_>, _aux: &mut ()) {
+            let mut commands = vec![DisplayCommand::Clear(Color::new(1.0, 0.0, 0.0, 1.0))];
+            cx.filter_disabled(&mut commands);
+            self.2.push(&mut cx.display(), &commands, ZOrder::default(), None, None);
+        }
+    }
+
+    #[test]
+    fn test_hidden_and_disabled_drawing() {
+        let mut display = RecordingDisplay::new((10, 10));
+        let mut child = Toggled(true, true, CommandGroup::new());
+        let frame = |child: &mut Toggled, display: &mut RecordingDisplay| {
+            draw_child_in_context(
+                child,
+                &mut DrawContext::new(display).with_disabled_filters(&[DisplayFilter::Grayscale]),
+                &mut (),
+            );
+            display.present(None).unwrap();
+            display.last_frame().iter().map(|recorded| recorded.command.clone()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(frame(&mut child, &mut display).len(), 1);
+
+        // hidden children aren't maintained, so their command groups are removed...
+        child.0 = false;
+        assert!(frame(&mut child, &mut display).is_empty());
+        assert!(display.get_command_group(child.2.handle().unwrap()).is_none());
+
+        // ...and pushed anew once they're shown again, though only the once.
+        let hidden = child.2.handle();
+        child.0 = true;
+        assert_eq!(frame(&mut child, &mut display).len(), 1);
+        let shown = child.2.handle();
+        assert_ne!(shown, hidden);
+        assert_eq!(frame(&mut child, &mut display).len(), 1);
+        assert_eq!(child.2.handle(), shown);
+
+        let mut disabled = Toggled(true, false, CommandGroup::new());
+        match &frame(&mut disabled, &mut display)[..] {
+            [DisplayCommand::Clear(color)] => {
+                assert_eq!(
+                    *color,
+                    DisplayFilter::apply_all(
+                        &[DisplayFilter::Grayscale],
+                        Color::new(1.0, 0.0, 0.0, 1.0)
+                    )
+                );
+            }
+            _ => panic!("the disabled child wasn't drawn on its own"),
+        }
+    }
 }
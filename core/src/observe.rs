@@ -0,0 +1,173 @@
+//! Observed values and bindings between them.
+//!
+//! An [`Observed`](struct.Observed.html) value emits an event whenever it changes.
+//! A [`Binding`](struct.Binding.html) keeps two observed values in sync (e.g. a model field and the value of a slider),
+//! converting between them in both directions.
+
+use {
+    reclutch_event::{prelude::*, RcEventListener, RcEventQueue},
+    std::ops::Deref,
+};
+
+/// A value which emits the new value whenever it's changed.
+#[derive(Debug)]
+pub struct Observed<T: Clone> {
+    value: T,
+    pub on_change: RcEventQueue<T>,
+}
+
+impl<T: Clone + Default> Default for Observed<T> {
+    fn default() -> Self {
+        Observed::new(T::default())
+    }
+}
+
+impl<T: Clone> Observed<T> {
+    /// Creates a new observed value. No event is emitted for the initial value.
+    pub fn new(value: T) -> Self {
+        Observed { value, on_change: RcEventQueue::new() }
+    }
+
+    /// Returns the current value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Changes the value, emitting the new value.
+    pub fn set(&mut self, value: T) {
+        self.value = value;
+        self.on_change.emit_borrowed(&self.value);
+    }
+
+    /// Changes the value through a closure, emitting the new value.
+    pub fn update(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.value);
+        self.on_change.emit_borrowed(&self.value);
+    }
+
+    /// Creates a listener for changes to the value.
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<T> {
+        self.on_change.listen()
+    }
+}
+
+impl<T: Clone + PartialEq> Observed<T> {
+    /// Changes the value only if it differs from the current value, returning whether it did.
+    pub fn set_if_changed(&mut self, value: T) -> bool {
+        if value != self.value {
+            self.set(value);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<T: Clone> Deref for Observed<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// Two-way binding between two observed values, typically a model field and a widget property.
+///
+/// Changes are carried across on [`sync`](struct.Binding.html#method.sync).
+/// The change caused by the binding itself is not carried back, so values bound in a loop don't bounce back and forth.
+pub struct Binding<A: Clone, B: Clone> {
+    a_listener: RcEventListener<A>,
+    b_listener: RcEventListener<B>,
+    a_to_b: Box<dyn Fn(&A) -> B>,
+    b_to_a: Box<dyn Fn(&B) -> A>,
+}
+
+impl<A: Clone, B: Clone> Binding<A, B> {
+    /// Binds `a` and `b`, converting between them with `a_to_b` and `b_to_a`.
+    ///
+    /// The values aren't synchronized initially; invoke [`push`](struct.Binding.html#method.push) for that.
+    pub fn new(
+        a: &Observed<A>,
+        b: &Observed<B>,
+        a_to_b: impl Fn(&A) -> B + 'static,
+        b_to_a: impl Fn(&B) -> A + 'static,
+    ) -> Self {
+        Binding {
+            a_listener: a.listen(),
+            b_listener: b.listen(),
+            a_to_b: Box::new(a_to_b),
+            b_to_a: Box::new(b_to_a),
+        }
+    }
+
+    /// Carries the latest change across, returning `true` if either value was changed.
+    ///
+    /// Should both values have changed since the last sync, `a` takes precedence.
+    pub fn sync(&mut self, a: &mut Observed<A>, b: &mut Observed<B>) -> bool {
+        let a_changed = self.a_listener.peek().pop();
+        let b_changed = self.b_listener.peek().pop();
+
+        if let Some(value) = a_changed {
+            b.set((self.a_to_b)(&value));
+            // discard the change which was just caused.
+            self.b_listener.peek();
+            true
+        } else if let Some(value) = b_changed {
+            a.set((self.b_to_a)(&value));
+            self.a_listener.peek();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Overwrites `b` with the (converted) current value of `a`.
+    pub fn push(&mut self, a: &Observed<A>, b: &mut Observed<B>) {
+        b.set((self.a_to_b)(a.get()));
+        self.b_listener.peek();
+    }
+}
+
+impl<A: Clone, B: Clone> Binding<A, B>
+where
+    A: Into<B>,
+    B: Into<A>,
+{
+    /// Binds `a` and `b`, converting between them with `Into`.
+    pub fn with_into(a: &Observed<A>, b: &Observed<B>) -> Self {
+        Binding::new(a, b, |a: &A| a.clone().into(), |b: &B| b.clone().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_way_binding() {
+        // e.g. a model storing a percentage, bound to a slider from 0 to 1.
+        let mut model = Observed::new(50u32);
+        let mut slider = Observed::new(0.0f32);
+        let model_listener = model.listen();
+
+        let mut binding =
+            Binding::new(&model, &slider, |x| *x as f32 / 100.0, |x| (*x * 100.0).round() as _);
+        binding.push(&model, &mut slider);
+        assert_eq!(*slider, 0.5);
+
+        slider.set(0.25);
+        assert!(binding.sync(&mut model, &mut slider));
+        assert_eq!(*model, 25);
+        assert_eq!(model_listener.peek(), vec![25]);
+
+        // the change to the model above doesn't echo back into the slider.
+        assert!(!binding.sync(&mut model, &mut slider));
+
+        model.set(80);
+        assert!(binding.sync(&mut model, &mut slider));
+        assert_eq!(*slider, 0.8);
+    }
+}
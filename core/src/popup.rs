@@ -0,0 +1,260 @@
+//! Placement of popups (tooltips, menus, dropdowns) next to the rectangle they belong to.
+//!
+//! A popup is placed on a side of its anchor (e.g. below a button), flipped to the opposite side if it doesn't fit there,
+//! and shifted along the side to stay within the screen:
+//!
+//! ```ignore
+//! let placed = PopupPlacement::new(Side::Bottom).gap(4.0).place(menu_size, button_bounds, window_bounds);
+//! menu.set_bounds(placed.rect);
+//! // if it doesn't fit entirely, the menu can scroll instead.
+//! menu.set_max_height(placed.available.height);
+//! ```
+
+use crate::display::{Point, Rect, Size};
+
+/// Side of the anchor which a popup is placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Returns the side on the other side of the anchor.
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Top => Side::Bottom,
+            Side::Bottom => Side::Top,
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    #[inline]
+    pub fn is_vertical(self) -> bool {
+        self == Side::Top || self == Side::Bottom
+    }
+}
+
+/// How a popup is aligned with its anchor, along the side it's placed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Align {
+    /// The left/top edges are aligned.
+    Start,
+    Center,
+    /// The right/bottom edges are aligned.
+    End,
+}
+
+/// Strategy for placing a popup of a given size next to an anchor rectangle, within the bounds of the screen (or window).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PopupPlacement {
+    pub side: Side,
+    pub align: Align,
+    /// Distance between the anchor and the popup.
+    pub gap: f32,
+    /// Minimum distance between the popup and the edges of the bounds.
+    pub padding: f32,
+    /// Whether the popup moves to the opposite side if it doesn't fit on its side.
+    pub flip: bool,
+    /// Whether the popup moves along its side to stay within the bounds.
+    pub shift: bool,
+}
+
+impl Default for PopupPlacement {
+    fn default() -> Self {
+        PopupPlacement::new(Side::Bottom)
+    }
+}
+
+/// The result of [`PopupPlacement::place`](struct.PopupPlacement.html#method.place).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlacedPopup {
+    pub rect: Rect,
+    /// The side the popup ended up on, which differs from the requested side if it was flipped.
+    pub side: Side,
+    /// The space available on that side; if the popup didn't fit, it can be shrunk to this (e.g. by scrolling).
+    pub available: Size,
+    /// Position of the center of the anchor along the side, relative to the start of the popup and limited to the popup;
+    /// this is where an arrow pointing at the anchor goes.
+    pub arrow: f32,
+}
+
+impl PopupPlacement {
+    /// Places popups on `side`, aligned with the start of the anchor, flipping and shifting as needed.
+    pub fn new(side: Side) -> Self {
+        PopupPlacement {
+            side,
+            align: Align::Start,
+            gap: 0.0,
+            padding: 0.0,
+            flip: true,
+            shift: true,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn gap(mut self, gap: f32) -> Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn flip(mut self, flip: bool) -> Self {
+        self.flip = flip;
+        self
+    }
+
+    pub fn shift(mut self, shift: bool) -> Self {
+        self.shift = shift;
+        self
+    }
+
+    /// Places a popup of `size` next to `anchor`, within `bounds`.
+    ///
+    /// If the popup fits on neither side, it's placed on the side with the most space.
+    pub fn place(&self, size: Size, anchor: Rect, bounds: Rect) -> PlacedPopup {
+        let bounds = bounds.inflate(-self.padding, -self.padding);
+
+        let mut side = self.side;
+        if self.flip {
+            let opposite = side.opposite();
+            let (space, opposite_space) =
+                (self.space(side, anchor, bounds), self.space(opposite, anchor, bounds));
+            let length = main(side, size);
+            if space < length && (opposite_space >= length || opposite_space > space) {
+                side = opposite;
+            }
+        }
+
+        // main axis; away from the anchor.
+        let length = main(side, size);
+        let start = match side {
+            Side::Top => anchor.min_y() - self.gap - length,
+            Side::Bottom => anchor.max_y() + self.gap,
+            Side::Left => anchor.min_x() - self.gap - length,
+            Side::Right => anchor.max_x() + self.gap,
+        };
+
+        // cross axis; along the anchor.
+        let (anchor_start, anchor_length, bounds_start, bounds_length, length) = if side
+            .is_vertical()
+        {
+            (anchor.min_x(), anchor.size.width, bounds.min_x(), bounds.size.width, size.width)
+        } else {
+            (anchor.min_y(), anchor.size.height, bounds.min_y(), bounds.size.height, size.height)
+        };
+        let mut cross = match self.align {
+            Align::Start => anchor_start,
+            Align::Center => anchor_start + (anchor_length - length) / 2.0,
+            Align::End => anchor_start + anchor_length - length,
+        };
+        if self.shift {
+            // popups larger than the bounds stick to the start.
+            cross = cross.min(bounds_start + bounds_length - length).max(bounds_start);
+        }
+        let arrow = (anchor_start + anchor_length / 2.0 - cross).max(0.0).min(length);
+
+        let (origin, available) = if side.is_vertical() {
+            (
+                Point::new(cross, start),
+                Size::new(bounds.size.width, self.space(side, anchor, bounds)),
+            )
+        } else {
+            (
+                Point::new(start, cross),
+                Size::new(self.space(side, anchor, bounds), bounds.size.height),
+            )
+        };
+
+        PlacedPopup { rect: Rect::new(origin, size), side, available, arrow }
+    }
+
+    /// Returns the length available on `side` of the anchor.
+    fn space(&self, side: Side, anchor: Rect, bounds: Rect) -> f32 {
+        let space = match side {
+            Side::Top => anchor.min_y() - bounds.min_y(),
+            Side::Bottom => bounds.max_y() - anchor.max_y(),
+            Side::Left => anchor.min_x() - bounds.min_x(),
+            Side::Right => bounds.max_x() - anchor.max_x(),
+        };
+        (space - self.gap).max(0.0)
+    }
+}
+
+/// Length of a popup away from its anchor.
+fn main(side: Side, size: Size) -> f32 {
+    if side.is_vertical() {
+        size.height
+    } else {
+        size.width
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen() -> Rect {
+        Rect::new(Point::zero(), Size::new(400.0, 300.0))
+    }
+
+    #[test]
+    fn test_popup_placement() {
+        let button = Rect::new(Point::new(50.0, 50.0), Size::new(80.0, 20.0));
+        let menu = Size::new(120.0, 100.0);
+
+        let placed = PopupPlacement::new(Side::Bottom).gap(2.0).place(menu, button, screen());
+        assert_eq!(placed.rect, Rect::new(Point::new(50.0, 72.0), menu));
+        assert_eq!(placed.side, Side::Bottom);
+        assert_eq!(placed.available, Size::new(400.0, 228.0));
+        assert_eq!(placed.arrow, 40.0);
+
+        // flipped above a button at the bottom of the screen.
+        let low = button.translate(euclid::vec2(0.0, 200.0));
+        let placed = PopupPlacement::new(Side::Bottom).gap(2.0).place(menu, low, screen());
+        assert_eq!(placed.side, Side::Top);
+        assert_eq!(placed.rect.origin, Point::new(50.0, 148.0));
+
+        // ...unless flipping is disabled.
+        let placed = PopupPlacement::new(Side::Bottom).flip(false).place(menu, low, screen());
+        assert_eq!(placed.side, Side::Bottom);
+
+        // shifted to stay on screen, with the arrow still pointing at the anchor.
+        let right = Rect::new(Point::new(360.0, 50.0), Size::new(30.0, 20.0));
+        let placed = PopupPlacement::new(Side::Bottom).align(Align::Center).padding(5.0).place(
+            menu,
+            right,
+            screen(),
+        );
+        assert_eq!(placed.rect.origin.x, 275.0);
+        assert_eq!(placed.arrow, 100.0);
+    }
+
+    #[test]
+    fn test_submenu_placement() {
+        let item = Rect::new(Point::new(300.0, 250.0), Size::new(80.0, 20.0));
+        let submenu = Size::new(100.0, 120.0);
+
+        // no space to the right, so it opens to the left, shifted up to stay on screen.
+        let placed = PopupPlacement::new(Side::Right).place(submenu, item, screen());
+        assert_eq!(placed.side, Side::Left);
+        assert_eq!(placed.rect, Rect::new(Point::new(200.0, 180.0), submenu));
+
+        // fits on neither side; the side with the most space wins.
+        let wide = Size::new(350.0, 50.0);
+        let placed = PopupPlacement::new(Side::Right).place(wide, item, screen());
+        assert_eq!(placed.side, Side::Left);
+        assert_eq!(placed.available, Size::new(300.0, 300.0));
+    }
+}
@@ -0,0 +1,300 @@
+//! Deterministic simulation, driven by a manually advanced virtual clock.
+//!
+//! Anything which reads the time through a [`Clock`](trait.Clock.html) (frame timing, [`Timers`](struct.Timers.html),
+//! and time-dependent input processing such as [click synthesis](../input/struct.ClickSynthesizer.html#method.handle_with))
+//! behaves identically when given a [`VirtualClock`](struct.VirtualClock.html),
+//! regardless of how fast the host runs. A [`Simulation`](struct.Simulation.html) combines a virtual clock with a script of events,
+//! which is how interaction tests and recorded replays are run:
+//!
+//! ```ignore
+//! let mut sim = Simulation::new(Duration::from_millis(16));
+//! sim.schedule(Duration::from_millis(100), InputEvent::PointerDown { .. });
+//! sim.schedule(Duration::from_millis(150), InputEvent::PointerUp { .. });
+//!
+//! while !sim.is_finished() {
+//!     let frame = sim.step();
+//!     globals.frame = frame.timing;
+//!     for event in frame.events {
+//!         if let Some(click) = clicks.handle_with(&event, sim.clock()) {
+//!             globals.input.emit_owned(click);
+//!         }
+//!         globals.input.emit_owned(event);
+//!     }
+//!     root.update(&mut globals);
+//! }
+//! ```
+
+use {
+    crate::{
+        draw::{FrameClock, FrameTiming},
+        input::InputEvent,
+    },
+    std::{
+        cell::Cell,
+        collections::VecDeque,
+        rc::Rc,
+        time::{Duration, Instant},
+    },
+};
+
+/// Source of the current time.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real time, as measured by [`Instant::now`](https://doc.rust-lang.org/std/time/struct.Instant.html#method.now).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock which only advances when told to.
+///
+/// Clones share the same time, so a single virtual clock can be handed out to everything which reads the time.
+#[derive(Debug, Clone)]
+pub struct VirtualClock {
+    start: Instant,
+    elapsed: Rc<Cell<Duration>>,
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        VirtualClock { start: Instant::now(), elapsed: Default::default() }
+    }
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Moves the time forward by `dt`.
+    pub fn advance(&self, dt: Duration) {
+        self.elapsed.set(self.elapsed.get() + dt);
+    }
+
+    /// Returns the time passed since the clock was created.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed.get()
+    }
+}
+
+impl Clock for VirtualClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        self.start + self.elapsed.get()
+    }
+}
+
+/// Values which become due at a deadline, such as the callbacks of timeouts or animation keyframes.
+///
+/// Values due at the same time are returned in the order they were scheduled, so the order of delivery is deterministic.
+#[derive(Debug, Clone)]
+pub struct Timers<T> {
+    pending: Vec<(Instant, u64, T)>,
+    next_id: u64,
+}
+
+impl<T> Default for Timers<T> {
+    fn default() -> Self {
+        Timers { pending: Vec::new(), next_id: 0 }
+    }
+}
+
+impl<T> Timers<T> {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Schedules `value` to be due once `after` has passed (as of `clock`).
+    pub fn schedule(&mut self, clock: &dyn Clock, after: Duration, value: T) {
+        self.schedule_at(clock.now() + after, value);
+    }
+
+    /// Schedules `value` to be due at `deadline`.
+    pub fn schedule_at(&mut self, deadline: Instant, value: T) {
+        self.pending.push((deadline, self.next_id, value));
+        self.next_id += 1;
+    }
+
+    /// Removes and returns the values which are due (as of `clock`), earliest first.
+    pub fn due(&mut self, clock: &dyn Clock) -> Vec<T> {
+        let now = clock.now();
+        let (mut due, pending) =
+            self.pending.drain(..).partition(|(deadline, ..)| *deadline <= now);
+        self.pending = pending;
+        due.sort_by_key(|&(deadline, id, _)| (deadline, id));
+        due.into_iter().map(|(.., value)| value).collect()
+    }
+
+    /// Returns the earliest deadline, if any values are scheduled.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|(deadline, ..)| *deadline).min()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// The result of a single [`step`](struct.Simulation.html#method.step) of a simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedFrame<E> {
+    pub timing: FrameTiming,
+    /// The scripted events which became due during the frame, in order.
+    pub events: Vec<E>,
+}
+
+/// Runs frames at a fixed interval on a [`VirtualClock`](struct.VirtualClock.html), delivering scripted events as they become due.
+#[derive(Debug)]
+pub struct Simulation<E = InputEvent> {
+    clock: VirtualClock,
+    frame_clock: FrameClock,
+    frame_interval: Duration,
+    script: VecDeque<(Duration, E)>,
+}
+
+impl<E> Simulation<E> {
+    /// Creates a simulation whose frames are `frame_interval` apart.
+    pub fn new(frame_interval: Duration) -> Self {
+        Simulation {
+            clock: VirtualClock::new(),
+            frame_clock: FrameClock::new(),
+            frame_interval,
+            script: VecDeque::new(),
+        }
+    }
+
+    /// Returns the virtual clock, to be shared with anything which reads the time.
+    #[inline]
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Schedules `event` to be delivered by the first frame at or after `at` (relative to the start of the simulation).
+    ///
+    /// Events scheduled for the same time are delivered in the order they were scheduled.
+    pub fn schedule(&mut self, at: Duration, event: E) {
+        let index =
+            self.script.iter().position(|(time, _)| *time > at).unwrap_or(self.script.len());
+        self.script.insert(index, (at, event));
+    }
+
+    /// Advances the clock to the next frame (the first frame is at the start of the simulation),
+    /// returning its timing and the events which became due.
+    pub fn step(&mut self) -> SimulatedFrame<E> {
+        if self.frame_clock.last().is_some() {
+            self.clock.advance(self.frame_interval);
+        }
+        let timing = self.frame_clock.tick_from(&self.clock);
+
+        let now = self.clock.elapsed();
+        let mut events = Vec::new();
+        while self.script.front().is_some_and(|(time, _)| *time <= now) {
+            events.extend(self.script.pop_front().map(|(_, event)| event));
+        }
+
+        SimulatedFrame { timing, events }
+    }
+
+    /// Returns `true` if all the scripted events have been delivered.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.script.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::input::{ClickSynthesizer, MouseButton},
+    };
+
+    #[test]
+    fn test_timers() {
+        let clock = VirtualClock::new();
+        let mut timers = Timers::new();
+
+        timers.schedule(&clock, Duration::from_millis(20), "b");
+        timers.schedule(&clock, Duration::from_millis(10), "a");
+        timers.schedule(&clock, Duration::from_millis(20), "c");
+        assert_eq!(timers.next_deadline(), Some(clock.now() + Duration::from_millis(10)));
+
+        clock.advance(Duration::from_millis(5));
+        assert!(timers.due(&clock).is_empty());
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(timers.due(&clock), vec!["a", "b", "c"]);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn test_simulation() {
+        let run = || {
+            let mut sim = Simulation::new(Duration::from_millis(10));
+            sim.schedule(Duration::from_millis(25), 2);
+            sim.schedule(Duration::from_millis(0), 0);
+            sim.schedule(Duration::from_millis(25), 3);
+            sim.schedule(Duration::from_millis(5), 1);
+
+            let mut frames = Vec::new();
+            while !sim.is_finished() {
+                frames.push(sim.step());
+            }
+            frames
+        };
+
+        let frames = run();
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[0].events, vec![0]);
+        assert_eq!(frames[1].events, vec![1]);
+        assert!(frames[2].events.is_empty());
+        assert_eq!(frames[3].events, vec![2, 3]);
+        assert_eq!(
+            frames[3].timing,
+            FrameTiming {
+                index: 3,
+                time: Duration::from_millis(30),
+                dt: Duration::from_millis(10)
+            }
+        );
+
+        // independent of how long the host takes.
+        assert_eq!(run(), frames);
+    }
+
+    #[test]
+    fn test_simulated_clicks() {
+        let press = InputEvent::PointerDown {
+            position: Default::default(),
+            button: MouseButton::Left,
+            modifiers: Default::default(),
+        };
+        let mut sim = Simulation::new(Duration::from_millis(100));
+        sim.schedule(Duration::from_millis(0), press.clone());
+        sim.schedule(Duration::from_millis(200), press.clone());
+        sim.schedule(Duration::from_millis(1000), press);
+
+        // the presses are timed by the virtual clock, so the first two form a double click.
+        let mut clicks = ClickSynthesizer::default();
+        let mut counts = Vec::new();
+        while !sim.is_finished() {
+            for event in sim.step().events {
+                if let Some(InputEvent::Click { count, .. }) =
+                    clicks.handle_with(&event, sim.clock())
+                {
+                    counts.push(count);
+                }
+            }
+        }
+        assert_eq!(counts, vec![1, 2, 1]);
+    }
+}
@@ -0,0 +1,155 @@
+//! Typed state machines for interaction logic.
+//!
+//! Interactions such as dragging tend to be spread across match arms and `Option` fields;
+//! a [`StateMachine`](struct.StateMachine.html) keeps the states, what moves between them and the resulting output in one place.
+
+use reclutch_event::{prelude::*, RcEventListener, RcEventQueue};
+
+/// What happens in response to an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition<S, O> {
+    /// Nothing; remain in the current state.
+    Stay,
+    /// Remain in the current state, emitting an output.
+    Emit(O),
+    /// Move to another state, invoking the exit and entry actions.
+    To(S),
+}
+
+type TransitionFn<S, E, O> = Box<dyn FnMut(&S, &E) -> Transition<S, O>>;
+type ActionFn<S, O> = Box<dyn FnMut(&S) -> Option<O>>;
+
+/// A state machine with states `S`, driven by events `E`, emitting output `O` to a queue.
+pub struct StateMachine<S, E, O: Clone> {
+    state: S,
+    transition: TransitionFn<S, E, O>,
+    on_enter: Option<ActionFn<S, O>>,
+    on_exit: Option<ActionFn<S, O>>,
+    pub output: RcEventQueue<O>,
+}
+
+impl<S, E, O: Clone> StateMachine<S, E, O> {
+    /// Creates a new state machine in the `initial` state, using `transition` to respond to events.
+    ///
+    /// The entry action isn't invoked for the initial state.
+    pub fn new(initial: S, transition: impl FnMut(&S, &E) -> Transition<S, O> + 'static) -> Self {
+        StateMachine {
+            state: initial,
+            transition: Box::new(transition),
+            on_enter: None,
+            on_exit: None,
+            output: RcEventQueue::new(),
+        }
+    }
+
+    /// Sets the action invoked when a state is entered, which may emit an output.
+    pub fn on_enter(mut self, action: impl FnMut(&S) -> Option<O> + 'static) -> Self {
+        self.on_enter = Some(Box::new(action));
+        self
+    }
+
+    /// Sets the action invoked when a state is exited, which may emit an output.
+    pub fn on_exit(mut self, action: impl FnMut(&S) -> Option<O> + 'static) -> Self {
+        self.on_exit = Some(Box::new(action));
+        self
+    }
+
+    /// Responds to an event, returning `true` if the state changed.
+    pub fn handle(&mut self, event: &E) -> bool {
+        match (self.transition)(&self.state, event) {
+            Transition::Stay => false,
+            Transition::Emit(output) => {
+                self.output.emit_borrowed(&output);
+                false
+            }
+            Transition::To(state) => {
+                self.set_state(state);
+                true
+            }
+        }
+    }
+
+    /// Moves to `state` directly, invoking the exit and entry actions.
+    pub fn set_state(&mut self, state: S) {
+        let StateMachine { state: current, on_enter, on_exit, output, .. } = self;
+
+        if let Some(event) = on_exit.as_mut().and_then(|action| action(current)) {
+            output.emit_borrowed(&event);
+        }
+
+        *current = state;
+
+        if let Some(event) = on_enter.as_mut().and_then(|action| action(current)) {
+            output.emit_borrowed(&event);
+        }
+    }
+
+    /// Returns the current state.
+    #[inline]
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+
+    /// Creates a listener for the output.
+    #[inline]
+    pub fn listen(&self) -> RcEventListener<O> {
+        self.output.listen()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::display::Point};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Drag {
+        Idle,
+        Dragging { anchor: Point },
+    }
+
+    enum Pointer {
+        Press(Point),
+        Move(Point),
+        Release,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Output {
+        Begin,
+        Move(Point),
+        End,
+    }
+
+    #[test]
+    fn test_drag_state_machine() {
+        let mut drag = StateMachine::new(Drag::Idle, |state, event| match (state, event) {
+            (Drag::Idle, Pointer::Press(pos)) => Transition::To(Drag::Dragging { anchor: *pos }),
+            (Drag::Dragging { anchor }, Pointer::Move(pos)) => {
+                Transition::Emit(Output::Move((*pos - *anchor).to_point()))
+            }
+            (Drag::Dragging { .. }, Pointer::Release) => Transition::To(Drag::Idle),
+            _ => Transition::Stay,
+        })
+        .on_enter(|state| match state {
+            Drag::Dragging { .. } => Some(Output::Begin),
+            Drag::Idle => None,
+        })
+        .on_exit(|state| match state {
+            Drag::Dragging { .. } => Some(Output::End),
+            Drag::Idle => None,
+        });
+
+        let listener = drag.listen();
+
+        assert!(!drag.handle(&Pointer::Move(Point::new(1.0, 1.0))));
+        assert!(drag.handle(&Pointer::Press(Point::new(10.0, 10.0))));
+        assert!(!drag.handle(&Pointer::Move(Point::new(15.0, 12.0))));
+        assert!(drag.handle(&Pointer::Release));
+        assert_eq!(*drag.state(), Drag::Idle);
+
+        assert_eq!(
+            listener.peek(),
+            vec![Output::Begin, Output::Move(Point::new(5.0, 2.0)), Output::End]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Background tasks whose results are delivered as events.
+//!
+//! A [`TaskSpawner`](struct.TaskSpawner.html) is meant to live within the update auxiliary (e.g. `aux.tasks`),
+//! such that any widget can spawn a future and receive its output on one of its own queues:
+//!
+//! ```ignore
+//! aux.tasks.spawn(async move { std::fs::read(path) }, &self.file_loaded);
+//! ```
+//!
+//! Futures are run on a thread pool, however event queues aren't thread-safe,
+//! so outputs are only emitted once [`deliver`](struct.TaskSpawner.html#method.deliver) is invoked on the UI thread (typically once per update).
+
+use {
+    futures_executor::ThreadPool,
+    reclutch_event::{prelude::*, RcEventQueue},
+    std::{
+        cell::RefCell,
+        future::Future,
+        rc::{Rc, Weak},
+        sync::mpsc,
+    },
+};
+
+pub use futures_executor::ThreadPoolBuilder;
+
+/// Spawns futures onto a thread pool and delivers their outputs to event queues.
+pub struct TaskSpawner {
+    pool: ThreadPool,
+    pending: Vec<Box<dyn FnMut() -> bool>>,
+}
+
+impl std::fmt::Debug for TaskSpawner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskSpawner").field("pending", &self.pending.len()).finish()
+    }
+}
+
+impl TaskSpawner {
+    /// Creates a task spawner with a default thread pool (one thread per CPU).
+    pub fn new() -> std::io::Result<Self> {
+        Ok(TaskSpawner::with_pool(ThreadPool::new()?))
+    }
+
+    /// Creates a task spawner which runs futures on an existing thread pool.
+    pub fn with_pool(pool: ThreadPool) -> Self {
+        TaskSpawner { pool, pending: Vec::new() }
+    }
+
+    /// Runs `future` in the background, emitting its output to `queue` upon completion.
+    ///
+    /// If the queue has been dropped by the time the output is delivered, the output is discarded.
+    pub fn spawn<T>(
+        &mut self,
+        future: impl Future<Output = T> + Send + 'static,
+        queue: &RcEventQueue<T>,
+    ) where
+        T: Clone + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.pool.spawn_ok(async move {
+            // the receiver is only gone if the spawner was dropped.
+            let _ = sender.send(future.await);
+        });
+
+        let queue: Weak<RefCell<_>> = Rc::downgrade(&queue.0);
+        self.pending.push(Box::new(move || match receiver.try_recv() {
+            Ok(output) => {
+                if let Some(queue) = queue.upgrade() {
+                    queue.emit_owned(output);
+                }
+                true
+            }
+            Err(mpsc::TryRecvError::Empty) => false,
+            // the future panicked; there's nothing to deliver.
+            Err(mpsc::TryRecvError::Disconnected) => true,
+        }));
+    }
+
+    /// Emits the outputs of all the tasks which have completed since the last delivery, returning how many were completed.
+    pub fn deliver(&mut self) -> usize {
+        let before = self.pending.len();
+        self.pending.retain_mut(|deliver| !deliver());
+        before - self.pending.len()
+    }
+
+    /// Returns the number of tasks which haven't been delivered yet.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, reclutch_event::RcEventQueue};
+
+    #[test]
+    fn test_task_delivery() {
+        let mut tasks = TaskSpawner::new().unwrap();
+        let queue = RcEventQueue::new();
+        let listener = queue.listen();
+
+        tasks.spawn(async { 6 * 7 }, &queue);
+        assert_eq!(tasks.pending(), 1);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while tasks.deliver() == 0 {
+            assert!(std::time::Instant::now() < deadline, "task wasn't delivered");
+            std::thread::yield_now();
+        }
+
+        assert_eq!(tasks.pending(), 0);
+        assert_eq!(listener.peek(), vec![42]);
+    }
+}
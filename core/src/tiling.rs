@@ -0,0 +1,195 @@
+//! Arrangement of floating panels.
+//!
+//! These are plain rectangle algorithms; a container of floating panels (such as the one in the `image_viewer` example)
+//! applies the results to the bounds of its panels, for instance when a panel is dropped after being dragged:
+//!
+//! ```ignore
+//! let others: Vec<Rect> = self.panels.iter().filter(|p| !p.dragging).map(|p| p.bounds()).collect();
+//! panel.set_bounds(tiling::snap(panel.bounds(), window_rect, &others, 8.0));
+//! ```
+
+use crate::display::{Point, Rect, Size, Vector};
+
+/// Places panels of the given sizes diagonally from the top-left of `area`, each offset from the last by `step`.
+///
+/// Panels are shrunk to fit within `area`, and the cascade restarts from the top-left whenever a panel would overflow `area`.
+pub fn cascade(area: Rect, sizes: &[Size], step: Vector) -> Vec<Rect> {
+    let mut offset = Vector::zero();
+    sizes
+        .iter()
+        .map(|size| {
+            let size = size.min(area.size);
+            if offset.x + size.width > area.size.width || offset.y + size.height > area.size.height
+            {
+                offset = Vector::zero();
+            }
+            let rect = Rect::new(area.origin + offset, size);
+            offset += step;
+            rect
+        })
+        .collect()
+}
+
+/// Divides `area` into a grid of `count` panels, with `spacing` between them.
+///
+/// The grid has as many rows as columns (or one less). Panels of the last row, if it's incomplete, are widened to fill it.
+pub fn tile(area: Rect, count: usize, spacing: f32) -> Vec<Rect> {
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let columns = (count as f32).sqrt().ceil() as usize;
+    let rows = count.div_ceil(columns);
+    let height = ((area.size.height - spacing * (rows - 1) as f32) / rows as f32).max(0.0);
+
+    (0..count)
+        .map(|i| {
+            let (row, column) = (i / columns, i % columns);
+            let row_columns = if row == rows - 1 { count - row * columns } else { columns };
+            let width = ((area.size.width - spacing * (row_columns - 1) as f32)
+                / row_columns as f32)
+                .max(0.0);
+            Rect::new(
+                Point::new(
+                    area.origin.x + (width + spacing) * column as f32,
+                    area.origin.y + (height + spacing) * row as f32,
+                ),
+                Size::new(width, height),
+            )
+        })
+        .collect()
+}
+
+/// Returns the smallest of `offsets` (by magnitude) within `distance`, or zero.
+fn nearest_offset(offsets: impl Iterator<Item = f32>, distance: f32) -> f32 {
+    offsets
+        .filter(|offset| offset.abs() <= distance)
+        .min_by(|a, b| a.abs().partial_cmp(&b.abs()).unwrap())
+        .unwrap_or(0.0)
+}
+
+fn edge_offsets(rect: Rect, area: Rect) -> [Vector; 2] {
+    [area.min() - rect.min(), area.max() - rect.max()]
+}
+
+fn sibling_offsets(rect: Rect, sibling: Rect, distance: f32) -> Option<[Vector; 4]> {
+    // only siblings which are (nearly) touching can be snapped to.
+    if !sibling.inflate(distance, distance).intersects(&rect) {
+        return None;
+    }
+
+    Some([
+        // adjacent
+        sibling.max() - rect.min(),
+        sibling.min() - rect.max(),
+        // aligned
+        sibling.min() - rect.min(),
+        sibling.max() - rect.max(),
+    ])
+}
+
+fn snap_with(rect: Rect, offsets: &[Vector], distance: f32) -> Rect {
+    let offset = Vector::new(
+        nearest_offset(offsets.iter().map(|offset| offset.x), distance),
+        nearest_offset(offsets.iter().map(|offset| offset.y), distance),
+    );
+    rect.translate(offset)
+}
+
+/// Moves `rect` such that each of its edges within `distance` of the corresponding edge of `area` lines up with it.
+pub fn snap_to_edges(rect: Rect, area: Rect, distance: f32) -> Rect {
+    snap_with(rect, &edge_offsets(rect, area), distance)
+}
+
+/// Moves `rect` such that its edges line up with the nearest edges of `siblings` within `distance`,
+/// either placing it next to a sibling or aligning it with one.
+pub fn snap_to_siblings(rect: Rect, siblings: &[Rect], distance: f32) -> Rect {
+    let offsets: Vec<_> = siblings
+        .iter()
+        .filter_map(|sibling| sibling_offsets(rect, *sibling, distance))
+        .flatten()
+        .collect();
+    snap_with(rect, &offsets, distance)
+}
+
+/// Combination of [`snap_to_edges`](fn.snap_to_edges.html) and [`snap_to_siblings`](fn.snap_to_siblings.html),
+/// snapping each axis to whichever edge is nearest.
+pub fn snap(rect: Rect, area: Rect, siblings: &[Rect], distance: f32) -> Rect {
+    let mut offsets = edge_offsets(rect, area).to_vec();
+    offsets.extend(
+        siblings.iter().filter_map(|sibling| sibling_offsets(rect, *sibling, distance)).flatten(),
+    );
+    snap_with(rect, &offsets, distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect::new(Point::new(x, y), Size::new(w, h))
+    }
+
+    #[test]
+    fn test_cascade() {
+        let area = rect(0.0, 0.0, 100.0, 100.0);
+        let sizes = [Size::new(60.0, 60.0), Size::new(60.0, 60.0), Size::new(200.0, 50.0)];
+
+        assert_eq!(
+            cascade(area, &sizes, Vector::new(30.0, 30.0)),
+            vec![
+                rect(0.0, 0.0, 60.0, 60.0),
+                rect(30.0, 30.0, 60.0, 60.0),
+                rect(0.0, 0.0, 100.0, 50.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tile() {
+        let tiles = tile(rect(0.0, 0.0, 210.0, 100.0), 5, 10.0);
+
+        assert_eq!(tiles.len(), 5);
+        assert_eq!(tiles[0], rect(0.0, 0.0, 190.0 / 3.0, 45.0));
+        assert_eq!(tiles[2].origin.x, tiles[0].size.width * 2.0 + 20.0);
+        assert_eq!(tiles[3], rect(0.0, 55.0, 100.0, 45.0));
+        assert_eq!(tiles[4], rect(110.0, 55.0, 100.0, 45.0));
+        assert!(tile(rect(0.0, 0.0, 10.0, 10.0), 0, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_snap() {
+        let area = rect(0.0, 0.0, 500.0, 500.0);
+
+        assert_eq!(
+            snap_to_edges(rect(5.0, 250.0, 100.0, 100.0), area, 8.0),
+            rect(0.0, 250.0, 100.0, 100.0)
+        );
+        assert_eq!(
+            snap_to_edges(rect(395.0, 397.0, 100.0, 100.0), area, 8.0),
+            rect(400.0, 400.0, 100.0, 100.0)
+        );
+        assert_eq!(
+            snap_to_edges(rect(20.0, 20.0, 100.0, 100.0), area, 8.0),
+            rect(20.0, 20.0, 100.0, 100.0)
+        );
+
+        let siblings = [rect(100.0, 100.0, 100.0, 100.0), rect(400.0, 400.0, 50.0, 50.0)];
+        // placed next to the right of the first sibling, aligned with its top.
+        assert_eq!(
+            snap_to_siblings(rect(204.0, 97.0, 50.0, 50.0), &siblings, 8.0),
+            rect(200.0, 100.0, 50.0, 50.0)
+        );
+        // too far from any sibling.
+        assert_eq!(
+            snap_to_siblings(rect(250.0, 250.0, 50.0, 50.0), &siblings, 8.0),
+            rect(250.0, 250.0, 50.0, 50.0)
+        );
+
+        // the nearest edge wins.
+        assert_eq!(
+            snap(rect(3.0, 203.0, 50.0, 50.0), area, &[rect(0.0, 0.0, 100.0, 200.0)], 8.0),
+            rect(0.0, 200.0, 50.0, 50.0)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Zooming and panning of content within a view.
+//!
+//! A [`Viewport2D`](struct.Viewport2D.html) only does the math; a viewer widget feeds it input and draws its content through
+//! the resulting transform:
+//!
+//! ```ignore
+//! // zoom about the cursor on scroll.
+//! viewport.zoom_at(1.1f32.powf(delta.y), cursor);
+//!
+//! // content outside of the view is scissored away.
+//! let mut display = TransformedDisplay::new(display, viewport.transform()).with_scissor(viewport.view());
+//! // ...and map the cursor back to content coordinates for hit-testing.
+//! let hovered = viewport.view_to_content(cursor);
+//! ```
+
+use crate::display::{Point, Rect, Size, Transform, Vector};
+
+/// Zoom level and pan offset of content (e.g. an image or canvas) of a given size shown within a view rectangle.
+///
+/// The offset is clamped such that content larger than the view always covers it, and content smaller than the view is centered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport2D {
+    view: Rect,
+    content: Size,
+    zoom: f32,
+    offset: Vector,
+    min_zoom: f32,
+    max_zoom: f32,
+}
+
+impl Viewport2D {
+    /// Creates a viewport showing `content` at its actual size, centered in `view`.
+    pub fn new(view: Rect, content: Size) -> Self {
+        let mut viewport = Viewport2D {
+            view,
+            content,
+            zoom: 1.0,
+            offset: Vector::zero(),
+            min_zoom: 0.01,
+            max_zoom: 100.0,
+        };
+        viewport.actual_size();
+        viewport
+    }
+
+    /// Sets the range of the zoom level, clamping the current zoom level to it.
+    ///
+    /// # Panics
+    /// Panics unless `0 < min_zoom <= max_zoom`.
+    pub fn with_zoom_limits(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        assert!(
+            0.0 < min_zoom && min_zoom <= max_zoom,
+            "zoom limits must satisfy 0 < min_zoom <= max_zoom"
+        );
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+        self.set_zoom(self.zoom);
+        self
+    }
+
+    #[inline]
+    pub fn view(&self) -> Rect {
+        self.view
+    }
+
+    /// Changes the view rectangle (e.g. when the window is resized), keeping the content point at the center of the view.
+    pub fn set_view(&mut self, view: Rect) {
+        let center = self.view_to_content(self.view.center());
+        self.view = view;
+        self.center_on(center);
+    }
+
+    #[inline]
+    pub fn content(&self) -> Size {
+        self.content
+    }
+
+    pub fn set_content(&mut self, content: Size) {
+        self.content = content;
+        self.clamp();
+    }
+
+    #[inline]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Sets the zoom level, about the center of the view.
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom_at(zoom / self.zoom, self.view.center());
+    }
+
+    /// Multiplies the zoom level by `factor`, keeping the content under `cursor` (in view coordinates) in place.
+    pub fn zoom_at(&mut self, factor: f32, cursor: Point) {
+        let anchor = self.view_to_content(cursor);
+        self.zoom = (self.zoom * factor).max(self.min_zoom).min(self.max_zoom);
+        self.offset = cursor - self.view.origin - anchor.to_vector() * self.zoom;
+        self.clamp();
+    }
+
+    /// Position of the content origin relative to the view origin, in view coordinates.
+    #[inline]
+    pub fn offset(&self) -> Vector {
+        self.offset
+    }
+
+    /// Moves the content by `delta` (in view coordinates), such as by the delta of a drag.
+    pub fn pan_by(&mut self, delta: Vector) {
+        self.offset += delta;
+        self.clamp();
+    }
+
+    /// Moves the content such that `point` (in content coordinates) is at the center of the view.
+    pub fn center_on(&mut self, point: Point) {
+        self.offset = self.view.size.to_vector() / 2.0 - point.to_vector() * self.zoom;
+        self.clamp();
+    }
+
+    /// Zooms such that the entire content fits within the view, centered.
+    pub fn fit(&mut self) {
+        if self.content.width > 0.0 && self.content.height > 0.0 {
+            self.zoom = (self.view.size.width / self.content.width)
+                .min(self.view.size.height / self.content.height)
+                .max(self.min_zoom)
+                .min(self.max_zoom);
+        }
+        self.center_on(self.content_rect().center());
+    }
+
+    /// Resets the zoom level to 1, centered.
+    pub fn actual_size(&mut self) {
+        self.zoom = 1.0_f32.max(self.min_zoom).min(self.max_zoom);
+        self.center_on(self.content_rect().center());
+    }
+
+    /// Maps content coordinates to view coordinates; used to draw the content.
+    pub fn transform(&self) -> Transform {
+        Transform::create_scale(self.zoom, self.zoom)
+            .post_translate(self.view.origin.to_vector() + self.offset)
+    }
+
+    pub fn content_to_view(&self, point: Point) -> Point {
+        self.view.origin + self.offset + point.to_vector() * self.zoom
+    }
+
+    /// Maps view coordinates (e.g. the cursor) to content coordinates.
+    pub fn view_to_content(&self, point: Point) -> Point {
+        ((point - self.view.origin - self.offset) / self.zoom).to_point()
+    }
+
+    /// Returns the part of the content which is visible, in content coordinates.
+    pub fn visible_content(&self) -> Rect {
+        let visible = Rect::new(self.view_to_content(self.view.origin), self.view.size / self.zoom);
+        visible.intersection(&self.content_rect()).unwrap_or_default()
+    }
+
+    fn content_rect(&self) -> Rect {
+        Rect::new(Point::zero(), self.content)
+    }
+
+    fn clamp(&mut self) {
+        let clamp_axis = |offset: f32, view: f32, content: f32| {
+            if content <= view {
+                (view - content) / 2.0
+            } else {
+                offset.max(view - content).min(0.0)
+            }
+        };
+
+        let scaled = self.content * self.zoom;
+        self.offset = Vector::new(
+            clamp_axis(self.offset.x, self.view.size.width, scaled.width),
+            clamp_axis(self.offset.y, self.view.size.height, scaled.height),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_viewport() {
+        let view = Rect::new(Point::new(10.0, 20.0), Size::new(200.0, 100.0));
+        let mut viewport = Viewport2D::new(view, Size::new(400.0, 400.0));
+
+        // centered at actual size.
+        assert_eq!(viewport.offset(), Vector::new(-100.0, -150.0));
+        assert_eq!(viewport.view_to_content(view.center()), Point::new(200.0, 200.0));
+
+        viewport.fit();
+        assert_eq!(viewport.zoom(), 0.25);
+        assert_eq!(viewport.offset(), Vector::new(50.0, 0.0));
+        assert_eq!(viewport.visible_content(), Rect::new(Point::zero(), Size::new(400.0, 400.0)));
+
+        // the content under the cursor stays in place.
+        let cursor = Point::new(110.0, 70.0);
+        assert_eq!(viewport.view_to_content(cursor), Point::new(200.0, 200.0));
+        viewport.zoom_at(4.0, cursor);
+        assert_eq!(viewport.zoom(), 1.0);
+        assert_eq!(viewport.view_to_content(cursor), Point::new(200.0, 200.0));
+        assert_eq!(
+            viewport.transform().transform_point(Point::new(100.0, 50.0)),
+            viewport.content_to_view(Point::new(100.0, 50.0))
+        );
+
+        // panning is clamped to the content.
+        viewport.pan_by(Vector::new(500.0, 500.0));
+        assert_eq!(viewport.offset(), Vector::zero());
+        viewport.pan_by(Vector::new(-1000.0, -120.0));
+        assert_eq!(viewport.offset(), Vector::new(-200.0, -120.0));
+        assert_eq!(
+            viewport.visible_content(),
+            Rect::new(Point::new(200.0, 120.0), Size::new(200.0, 100.0))
+        );
+
+        let viewport = viewport.with_zoom_limits(2.0, 8.0);
+        assert_eq!(viewport.zoom(), 2.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_inverted_zoom_limits() {
+        let view = Rect::new(Point::zero(), Size::new(100.0, 100.0));
+        let _ = Viewport2D::new(view, Size::new(100.0, 100.0)).with_zoom_limits(2.0, 1.0);
+    }
+}
@@ -6,7 +6,7 @@ use {proc_macro::TokenStream, quote::quote};
 
 #[proc_macro_derive(
     WidgetChildren,
-    attributes(widget_child, vec_widget_child, widget_children_trait)
+    attributes(widget_child, widget_children, vec_widget_child, widget_children_trait)
 )]
 pub fn widget_macro_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -16,8 +16,32 @@ pub fn widget_macro_derive(input: TokenStream) -> TokenStream {
 
 enum ChildAttr {
     None,
-    WidgetChild,
-    VecWidgetChild,
+    WidgetChild(Option<syn::Path>),
+    /// `widget_children`, or its older name `vec_widget_child`.
+    WidgetChildren(Option<syn::Path>),
+}
+
+/// How the children of a `widget_children` field are iterated.
+#[derive(Clone, Copy)]
+enum CollectionKind {
+    /// `Vec`, arrays, slices and anything else which iterates over its children by reference and has a `len`.
+    Sequence,
+    Option,
+    /// `HashMap` and `BTreeMap`, of which the values are the children.
+    Map,
+}
+
+fn collection_kind(ty: &syn::Type) -> CollectionKind {
+    match ty {
+        syn::Type::Path(path) => match path.path.segments.last() {
+            Some(segment) if segment.ident == "Option" => CollectionKind::Option,
+            Some(segment) if segment.ident == "HashMap" || segment.ident == "BTreeMap" => {
+                CollectionKind::Map
+            }
+            _ => CollectionKind::Sequence,
+        },
+        _ => CollectionKind::Sequence,
+    }
 }
 
 enum StringOrInt {
@@ -26,22 +50,58 @@ enum StringOrInt {
 }
 
 enum ChildReference {
-    Single(StringOrInt),
-    Vec(StringOrInt),
+    Single(StringOrInt, Option<syn::Path>),
+    Collection(StringOrInt, CollectionKind, Option<syn::Path>),
+}
+
+/// Parses the `aux_map = "path::to::Projection"` argument of a child attribute, if any.
+fn parse_aux_map(attr: &syn::Attribute) -> Option<syn::Path> {
+    if let Ok(syn::Meta::List(list)) = attr.parse_meta() {
+        for nested in list.nested {
+            if let syn::NestedMeta::Meta(syn::Meta::NameValue(nv)) = nested {
+                if nv.path.is_ident("aux_map") {
+                    if let syn::Lit::Str(lit) = nv.lit {
+                        return Some(lit.parse().expect("aux_map must be a path"));
+                    }
+                }
+            }
+        }
+    }
+    None
 }
 
 fn chk_attrs_is_child(attrs: &[syn::Attribute]) -> ChildAttr {
     for attr in attrs {
         if attr.path.segments.first().map(|i| i.ident == "widget_child").unwrap_or(false) {
-            return ChildAttr::WidgetChild;
-        } else if attr.path.segments.first().map(|i| i.ident == "vec_widget_child").unwrap_or(false)
+            return ChildAttr::WidgetChild(parse_aux_map(attr));
+        } else if attr
+            .path
+            .segments
+            .first()
+            .map(|i| i.ident == "widget_children" || i.ident == "vec_widget_child")
+            .unwrap_or(false)
         {
-            return ChildAttr::VecWidgetChild;
+            return ChildAttr::WidgetChildren(parse_aux_map(attr));
         }
     }
     ChildAttr::None
 }
 
+/// Wraps a reference to a child in `MapAux` if it has an `aux_map`.
+fn map_aux(
+    child: proc_macro2::TokenStream,
+    aux_map: &Option<syn::Path>,
+    mutable: bool,
+) -> proc_macro2::TokenStream {
+    match aux_map {
+        Some(aux_map) if mutable => {
+            quote! { reclutch::widget::MapAux::<_, #aux_map>::from_mut(#child) }
+        }
+        Some(aux_map) => quote! { reclutch::widget::MapAux::<_, #aux_map>::from_ref(#child) },
+        None => child,
+    }
+}
+
 fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
     let trait_type = if let Some(attr) = ast.attrs.iter().find(|attr| {
         attr.path.segments.first().map(|i| i.ident == "widget_children_trait").unwrap_or(false)
@@ -75,16 +135,19 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
                     if let Some(ref ident) = field.ident {
                         match chk_attrs_is_child(&field.attrs) {
                             ChildAttr::None => continue,
-                            ChildAttr::WidgetChild => {
+                            ChildAttr::WidgetChild(aux_map) => {
                                 capacity += 1;
-                                children.push(ChildReference::Single(StringOrInt::String(
-                                    ident.to_string(),
-                                )));
+                                children.push(ChildReference::Single(
+                                    StringOrInt::String(ident.to_string()),
+                                    aux_map,
+                                ));
                             }
-                            ChildAttr::VecWidgetChild => {
-                                children.push(ChildReference::Vec(StringOrInt::String(
-                                    ident.to_string(),
-                                )));
+                            ChildAttr::WidgetChildren(aux_map) => {
+                                children.push(ChildReference::Collection(
+                                    StringOrInt::String(ident.to_string()),
+                                    collection_kind(&field.ty),
+                                    aux_map,
+                                ));
                             }
                         }
                     }
@@ -94,12 +157,16 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
                 for (i, field) in fields.unnamed.iter().enumerate() {
                     match chk_attrs_is_child(&field.attrs) {
                         ChildAttr::None => continue,
-                        ChildAttr::WidgetChild => {
+                        ChildAttr::WidgetChild(aux_map) => {
                             capacity += 1;
-                            children.push(ChildReference::Single(StringOrInt::Int(i)));
+                            children.push(ChildReference::Single(StringOrInt::Int(i), aux_map));
                         }
-                        ChildAttr::VecWidgetChild => {
-                            children.push(ChildReference::Vec(StringOrInt::Int(i)));
+                        ChildAttr::WidgetChildren(aux_map) => {
+                            children.push(ChildReference::Collection(
+                                StringOrInt::Int(i),
+                                collection_kind(&field.ty),
+                                aux_map,
+                            ));
                         }
                     }
                 }
@@ -112,40 +179,48 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
     let mut push_children_mut = Vec::new();
     let mut capacities = Vec::new();
 
+    let member = |ident: StringOrInt| match ident {
+        StringOrInt::String(child) => {
+            let ident = quote::format_ident!("{}", child);
+            quote! { #ident }
+        }
+        StringOrInt::Int(child) => {
+            let ident = syn::Index::from(child);
+            quote! { #ident }
+        }
+    };
+
     for child in children {
         match child {
-            ChildReference::Single(ident) => match ident {
-                StringOrInt::String(child) => {
-                    let ident = quote::format_ident!("{}", child);
-                    push_children.push(quote! { children.push(&self.#ident as _); });
-                    push_children_mut.push(quote! { children.push(&mut self.#ident as _); });
-                }
-                StringOrInt::Int(child) => {
-                    let ident = syn::Index::from(child);
-                    push_children.push(quote! { children.push(&self.#ident as _); });
-                    push_children_mut.push(quote! { children.push(&mut self.#ident as _); });
-                }
-            },
-            ChildReference::Vec(ident) => match ident {
-                StringOrInt::String(child) => {
-                    let ident = quote::format_ident!("{}", child);
-                    push_children
-                        .push(quote! { for child in &self.#ident { children.push(child as _); } });
-                    push_children_mut.push(
-                        quote! { for child in &mut self.#ident { children.push(child as _); } },
-                    );
-                    capacities.push(quote! { + self.#ident.len() });
-                }
-                StringOrInt::Int(child) => {
-                    let ident = syn::Index::from(child);
-                    push_children
-                        .push(quote! { for child in &self.#ident { children.push(child as _); } });
-                    push_children_mut.push(
-                        quote! { for child in &mut self.#ident { children.push(child as _); } },
-                    );
-                    capacities.push(quote! { + self.#ident.len() });
-                }
-            },
+            ChildReference::Single(ident, aux_map) => {
+                let ident = member(ident);
+                let child = map_aux(quote! { &self.#ident }, &aux_map, false);
+                let child_mut = map_aux(quote! { &mut self.#ident }, &aux_map, true);
+                push_children.push(quote! { children.push(#child as _); });
+                push_children_mut.push(quote! { children.push(#child_mut as _); });
+            }
+            ChildReference::Collection(ident, kind, aux_map) => {
+                let ident = member(ident);
+                let (iter, iter_mut) = match kind {
+                    CollectionKind::Map => {
+                        (quote! { self.#ident.values() }, quote! { self.#ident.values_mut() })
+                    }
+                    // iterating an option by reference directly is linted against in the user's crate.
+                    CollectionKind::Option => {
+                        (quote! { self.#ident.iter() }, quote! { self.#ident.iter_mut() })
+                    }
+                    _ => (quote! { &self.#ident }, quote! { &mut self.#ident }),
+                };
+                let child = map_aux(quote! { child }, &aux_map, false);
+                push_children.push(quote! { for child in #iter { children.push(#child as _); } });
+                let child_mut = map_aux(quote! { child }, &aux_map, true);
+                push_children_mut
+                    .push(quote! { for child in #iter_mut { children.push(#child_mut as _); } });
+                capacities.push(match kind {
+                    CollectionKind::Option => quote! { + self.#ident.is_some() as usize },
+                    _ => quote! { + self.#ident.len() },
+                });
+            }
         }
     }
 
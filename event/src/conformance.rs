@@ -0,0 +1,189 @@
+//! Every check takes a fresh queue (or a function creating them) and panics
+//! with a description of the violated invariant, so third-party queue
+//! implementations can run them from their own tests.
+//! The checks only apply to broadcasting queues, where every listener receives every event
+//! emitted while it exists (unlike e.g. [`work`](crate::work) queues).
+//!
+//! ```ignore
+//! #[test]
+//! fn my_queue_conforms() {
+//!     reclutch_event::conformance::check_queue(MyQueue::<u32>::default);
+//! }
+//! ```
+
+use crate::traits::{Emitter, EmitterExt, Listen, QueueInterfaceListable};
+
+/// Runs every check against queues created by `new_queue`.
+pub fn check_queue<Q, F>(mut new_queue: F)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+    F: FnMut() -> Q,
+{
+    check_ordering(&new_queue());
+    check_no_loss(&new_queue());
+    check_multiple_listeners(&new_queue());
+    check_late_listener(&new_queue());
+    check_dropped_listener(&new_queue());
+    for seed in 1..=64 {
+        check_random_operations(&new_queue(), seed, 256);
+    }
+}
+
+/// Events are received in the order they were emitted.
+pub fn check_ordering<Q>(queue: &Q)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    let listener = queue.listen();
+    for i in 0..100 {
+        assert!(
+            queue.emit_owned(i).was_delivered(),
+            "emitting with a listener present should be reported as delivered"
+        );
+    }
+    assert_eq!(listener.peek(), (0..100).collect::<Vec<_>>(), "events were reordered");
+}
+
+/// Events emitted between (and across) reads are each received exactly once.
+pub fn check_no_loss<Q>(queue: &Q)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    let listener = queue.listen();
+    let mut received = Vec::new();
+    for i in 0..50 {
+        queue.emit_owned(i * 2);
+        queue.emit_owned(i * 2 + 1);
+        if i % 3 == 0 {
+            received.extend(listener.peek());
+        }
+    }
+    received.extend(listener.peek());
+
+    assert_eq!(received, (0..100).collect::<Vec<_>>(), "events were lost or duplicated");
+    assert!(listener.peek().is_empty(), "events were received more than once");
+}
+
+/// Every listener receives every event, independently of when the other listeners read.
+pub fn check_multiple_listeners<Q>(queue: &Q)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    let a = queue.listen();
+    let b = queue.listen();
+
+    queue.emit_owned(1);
+    assert_eq!(a.peek(), [1], "first listener didn't receive the event");
+    queue.emit_owned(2);
+
+    assert_eq!(b.peek(), [1, 2], "reading from one listener affected another");
+    assert_eq!(a.peek(), [2], "reading from one listener affected another");
+}
+
+/// Listeners don't receive events emitted before they were created.
+pub fn check_late_listener<Q>(queue: &Q)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    let early = queue.listen();
+    queue.emit_owned(1);
+    let late = queue.listen();
+    queue.emit_owned(2);
+
+    assert_eq!(late.peek(), [2], "a listener received an event emitted before it was created");
+    assert_eq!(early.peek(), [1, 2]);
+}
+
+/// Dropping a listener (even with unread events) doesn't affect the remaining listeners.
+pub fn check_dropped_listener<Q>(queue: &Q)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    let kept = queue.listen();
+    let dropped = queue.listen();
+    queue.emit_owned(1);
+    drop(dropped);
+    queue.emit_owned(2);
+
+    assert_eq!(kept.peek(), [1, 2], "dropping a listener affected another");
+}
+
+/// Performs `steps` random operations (emitting, creating, dropping and reading listeners),
+/// comparing what each listener receives against a model of the queue.
+///
+/// The operations are derived from `seed`, so a failure can be reproduced by running the same seed again.
+pub fn check_random_operations<Q>(queue: &Q, seed: u64, steps: usize)
+where
+    Q: Emitter<Item = u32> + QueueInterfaceListable<Item = u32>,
+{
+    // xorshift; deterministic and good enough to shuffle operations.
+    let mut state = seed.max(1);
+    let mut random = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % n as u64) as usize
+    };
+
+    let mut listeners: Vec<(Q::Listener, Vec<u32>)> = Vec::new();
+    let mut next = 0;
+
+    for step in 0..steps {
+        match random(8) {
+            0 if listeners.len() < 8 => listeners.push((queue.listen(), Vec::new())),
+            1 if !listeners.is_empty() => {
+                listeners.remove(random(listeners.len()));
+            }
+            2 | 3 if !listeners.is_empty() => {
+                let index = random(listeners.len());
+                let (listener, expected) = &mut listeners[index];
+                assert_eq!(
+                    listener.peek(),
+                    std::mem::take(expected),
+                    "listener diverged from the model at step {} (seed {})",
+                    step,
+                    seed
+                );
+            }
+            _ => {
+                let delivered = queue.emit_owned(next).was_delivered();
+                assert!(
+                    delivered || listeners.is_empty(),
+                    "emitting with a listener present should be reported as delivered (step {}, seed {})",
+                    step,
+                    seed
+                );
+                for (_, expected) in &mut listeners {
+                    expected.push(next);
+                }
+                next += 1;
+            }
+        }
+    }
+
+    for (listener, expected) in listeners {
+        assert_eq!(listener.peek(), expected, "listener diverged from the model (seed {})", seed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonts_conformance() {
+        // the queue itself derefs to the shared queue, which is what implements the traits.
+        check_queue(|| crate::RcEventQueue::<u32>::new().0);
+    }
+
+    #[test]
+    fn test_ts_conformance() {
+        check_queue(crate::ts::Queue::<u32>::default);
+    }
+
+    #[cfg(feature = "crossbeam-channel")]
+    #[test]
+    fn test_chans_conformance() {
+        check_queue(crate::chans::Queue::<u32>::default);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::traits::Emitter;
+use std::borrow::Cow;
+
+/// A guard which collects events and emits them onto a queue once dropped.
+///
+/// Emitting onto a queue while one of its listeners is being iterated
+/// (e.g. within [`with`](crate::traits::Listen::with)) would conflict with the borrow held by the listener;
+/// a `DeferredEmit` postpones the emission until after the iteration instead:
+///
+/// ```ignore
+/// let mut deferred = self.queue.defer();
+/// self.listener.with(|events| {
+///     for event in events {
+///         deferred.push(respond_to(event));
+///     }
+/// });
+/// // `deferred` is dropped here, emitting the responses.
+/// ```
+#[derive(Debug)]
+pub struct DeferredEmit<'a, Q: Emitter>
+where
+    Q::Item: Clone,
+{
+    queue: &'a Q,
+    events: Vec<Q::Item>,
+}
+
+impl<'a, Q: Emitter> DeferredEmit<'a, Q>
+where
+    Q::Item: Clone,
+{
+    #[inline]
+    pub fn new(queue: &'a Q) -> Self {
+        DeferredEmit { queue, events: Vec::new() }
+    }
+
+    /// Adds an event to be emitted later
+    #[inline]
+    pub fn push(&mut self, event: Q::Item) {
+        self.events.push(event);
+    }
+
+    /// Returns the number of events waiting to be emitted
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Emits the collected events now, instead of when dropped
+    pub fn flush(&mut self) {
+        for event in self.events.drain(..) {
+            self.queue.emit(Cow::Owned(event));
+        }
+    }
+
+    /// Drops the collected events without emitting them
+    #[inline]
+    pub fn discard(mut self) {
+        self.events.clear();
+    }
+}
+
+impl<Q: Emitter> Extend<Q::Item> for DeferredEmit<'_, Q>
+where
+    Q::Item: Clone,
+{
+    #[inline]
+    fn extend<I: IntoIterator<Item = Q::Item>>(&mut self, iter: I) {
+        self.events.extend(iter)
+    }
+}
+
+impl<Q: Emitter> Drop for DeferredEmit<'_, Q>
+where
+    Q::Item: Clone,
+{
+    #[inline]
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{nonts::Queue, prelude::*};
+
+    #[test]
+    fn test_deferred_emit() {
+        let event = Queue::default();
+        let listener = event.listen();
+        let observer = event.listen();
+
+        event.emit_owned(1i32).to_result().unwrap();
+        event.emit_owned(2i32).to_result().unwrap();
+
+        // emitting directly within `with` would fail to borrow the queue.
+        let mut deferred = event.defer();
+        listener.with(|events| deferred.extend(events.iter().map(|i| i * 10)));
+        assert_eq!(deferred.len(), 2);
+        drop(deferred);
+
+        assert_eq!(listener.peek(), &[10, 20]);
+        assert_eq!(observer.peek(), &[1, 2, 10, 20]);
+
+        let mut deferred = event.defer();
+        deferred.push(3);
+        deferred.discard();
+        assert!(listener.peek().is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::traits::QueueInterfaceFreezable;
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+
+/// Thread-safe queues emit while holding this for reading,
+/// and snapshots are taken while holding it for writing
+static SNAPSHOT_LOCK: RwLock<()> = RwLock::new(());
+
+/// Prevents snapshots from being taken until dropped
+#[inline]
+pub(crate) fn snapshot_guard() -> RwLockReadGuard<'static, ()> {
+    // the lock guards no data, so it's still usable if a thread panicked while holding it
+    SNAPSHOT_LOCK.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Captures the pending events of a set of queues at a frame boundary.
+///
+/// Until the next snapshot, listeners of these queues only receive the events
+/// which were emitted before this call. Events emitted during the frame (e.g. by widgets
+/// responding to other events) are held back for the next frame, instead of being processed
+/// by whichever widgets happen to update later in the same frame.
+///
+/// The snapshot is atomic across all the queues; thread-safe queues can't be emitted to
+/// while it's taken, so another thread can't emit in between capturing two queues.
+///
+/// ```ignore
+/// loop {
+///     reclutch_event::frame::snapshot(&[&window_events, &self.app_events]);
+///     root.update(&mut aux);
+///     root.draw(&mut display, &mut ());
+/// }
+/// ```
+pub fn snapshot(queues: &[&dyn QueueInterfaceFreezable]) {
+    let _lock = SNAPSHOT_LOCK.write().unwrap_or_else(PoisonError::into_inner);
+    for queue in queues {
+        queue.freeze();
+    }
+}
+
+/// Stops holding back events of a set of queues, such that events are delivered immediately again.
+pub fn thaw(queues: &[&dyn QueueInterfaceFreezable]) {
+    for queue in queues {
+        queue.thaw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nonrc, nonts, prelude::*, ts};
+
+    #[test]
+    fn test_frame_snapshot() {
+        let input = nonts::Queue::default();
+        let output = nonrc::Queue::new();
+        let remote = ts::Queue::default();
+
+        let input_listener = input.listen();
+        let output_listener = output.listen();
+        let remote_listener = remote.listen();
+
+        input.emit_owned(1i32).to_result().unwrap();
+        remote.emit_owned(1i32).to_result().unwrap();
+        snapshot(&[&input, &output, &remote]);
+
+        // emitted mid-frame in response to input
+        for event in input_listener.peek() {
+            output.emit_owned(event * 10).to_result().unwrap();
+            input.emit_owned(event + 1).to_result().unwrap();
+        }
+        assert!(output_listener.peek().is_empty());
+        assert!(input_listener.peek().is_empty());
+        assert_eq!(remote_listener.peek(), &[1]);
+
+        snapshot(&[&input, &output, &remote]);
+        assert_eq!(output_listener.peek(), &[10]);
+        assert_eq!(input_listener.peek(), &[2]);
+
+        thaw(&[&input, &output, &remote]);
+        input.emit_owned(3i32).to_result().unwrap();
+        assert_eq!(input_listener.peek(), &[3]);
+    }
+
+    #[test]
+    fn test_atomic_snapshot() {
+        let first = ts::Queue::default();
+        let second = ts::Queue::default();
+        let first_listener = first.listen();
+        let second_listener = second.listen();
+
+        // each event is emitted to the first queue, then to the second
+        let emitter = {
+            let (first, second) = (first.clone(), second.clone());
+            std::thread::spawn(move || {
+                for i in 0..10_000u32 {
+                    first.emit_owned(i).to_result().unwrap();
+                    second.emit_owned(i).to_result().unwrap();
+                }
+            })
+        };
+
+        // so no snapshot captures an event of the second queue without that of the first
+        let (mut first_seen, mut second_seen) = (0, 0);
+        while first_seen < 10_000 || second_seen < 10_000 {
+            snapshot(&[&first, &second]);
+            first_seen += first_listener.peek().len();
+            second_seen += second_listener.peek().len();
+            assert!(second_seen <= first_seen);
+        }
+
+        emitter.join().unwrap();
+    }
+}
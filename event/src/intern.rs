@@ -1,15 +1,65 @@
 pub(crate) type ListenerKey = slotmap::DefaultKey;
 
+/// Counts emitted events across all queues, such that events from
+/// different queues can be ordered by arrival
+static SEQUENCE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+#[inline]
+fn next_stamp() -> u64 {
+    SEQUENCE.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+}
+
+/// A subscription which buffers its own copies of the events matching a predicate,
+/// instead of reading the shared buffer
+struct Filtered<T> {
+    predicate: Box<dyn Fn(&T) -> bool + Send + Sync>,
+    /// Copies a matching event into `events`, such that only creating
+    /// a filtered subscription requires the events to be `Clone`
+    clone: fn(&T) -> T,
+    events: Vec<T>,
+    stamps: Vec<u64>,
+    /// Number of events visible while frozen
+    frozen: Option<usize>,
+}
+
+impl<T> Filtered<T> {
+    #[inline]
+    fn visible(&self) -> usize {
+        self.frozen.unwrap_or(self.events.len())
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Filtered<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Filtered")
+            .field("events", &self.events)
+            .field("stamps", &self.stamps)
+            .field("frozen", &self.frozen)
+            .finish()
+    }
+}
+
 /// Non-thread-safe, non-reference-counted API
 #[derive(Debug)]
 pub struct Queue<T> {
     pub(crate) listeners: slotmap::SlotMap<ListenerKey, usize>,
     pub(crate) events: Vec<T>,
+    /// Arrival stamp of each event in `events`
+    stamps: Vec<u64>,
+    /// Number of events visible to listeners while frozen
+    frozen: Option<usize>,
+    filtered: slotmap::DenseSlotMap<ListenerKey, Filtered<T>>,
 }
 
 impl<T> Default for Queue<T> {
     fn default() -> Self {
-        Self { listeners: Default::default(), events: Vec::new() }
+        Self {
+            listeners: Default::default(),
+            events: Vec::new(),
+            stamps: Vec::new(),
+            frozen: None,
+            filtered: Default::default(),
+        }
     }
 }
 
@@ -30,7 +80,56 @@ impl<T> Queue<T> {
             *idx -= min_idx;
         }
 
+        if let Some(frozen) = &mut self.frozen {
+            *frozen = frozen.saturating_sub(min_idx);
+        }
+
         self.events.drain(0..min_idx);
+        self.stamps.drain(0..min_idx);
+    }
+
+    /// Get the end index of events visible to listeners
+    #[inline]
+    fn visible(&self) -> usize {
+        self.frozen.unwrap_or(self.events.len())
+    }
+
+    /// Only deliver the events emitted up until now;
+    /// later events are held back until the queue is frozen again or thawed
+    #[inline]
+    pub fn freeze(&mut self) {
+        self.frozen = Some(self.events.len());
+        for filtered in self.filtered.values_mut() {
+            filtered.frozen = Some(filtered.events.len());
+        }
+    }
+
+    /// Deliver all events again, including those held back
+    #[inline]
+    pub fn thaw(&mut self) {
+        self.frozen = None;
+        for filtered in self.filtered.values_mut() {
+            filtered.frozen = None;
+        }
+    }
+
+    /// Checks if events are currently held back
+    #[inline]
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Appends an event to the shared buffer, regardless of whether there are listeners
+    #[cfg(feature = "crossbeam-channel")]
+    #[inline]
+    pub(crate) fn push(&mut self, event: T) {
+        self.push_stamped(event, next_stamp());
+    }
+
+    #[inline]
+    fn push_stamped(&mut self, event: T, stamp: u64) {
+        self.events.push(event);
+        self.stamps.push(stamp);
     }
 
     /// Creates a subscription
@@ -47,10 +146,80 @@ impl<T> Queue<T> {
         }
     }
 
-    /// Get the start index of new events since last `pull`
-    fn pull(&mut self, key: ListenerKey) -> usize {
-        let maxidx = self.events.len();
-        std::mem::replace(self.listeners.get_mut(key).unwrap(), maxidx)
+    /// Creates a subscription which only receives the events matching `predicate`
+    ///
+    /// The matching events are cloned into a buffer of the subscription as they're emitted,
+    /// so other events are never stored for it (and it never holds back the cleanup of the shared buffer).
+    /// Use the `*_filtered` methods with the returned key.
+    pub fn create_filtered_listener(
+        &mut self,
+        predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> ListenerKey
+    where
+        T: Clone,
+    {
+        let frozen = self.frozen.map(|_| 0);
+        self.filtered.insert(Filtered {
+            predicate: Box::new(predicate),
+            clone: T::clone,
+            events: Vec::new(),
+            stamps: Vec::new(),
+            frozen,
+        })
+    }
+
+    /// Removes a filtered subscription
+    #[inline]
+    pub fn remove_filtered_listener(&mut self, key: ListenerKey) {
+        self.filtered.remove(key);
+    }
+
+    /// Applies a function to the list of new matching events since last `pull`,
+    /// along with their arrival stamps
+    pub fn pull_filtered_stamped_with<F, R>(&mut self, key: ListenerKey, f: F) -> R
+    where
+        F: FnOnce(&[T], &[u64]) -> R,
+    {
+        let filtered = self.filtered.get_mut(key).unwrap();
+        let end = filtered.visible();
+        let ret = f(&filtered.events[..end], &filtered.stamps[..end]);
+        filtered.events.drain(..end);
+        filtered.stamps.drain(..end);
+        if let Some(frozen) = &mut filtered.frozen {
+            *frozen = 0;
+        }
+        ret
+    }
+
+    /// Get the number of matching events since last `pull`
+    #[inline]
+    pub fn pending_filtered(&self, key: ListenerKey) -> usize {
+        self.filtered.get(key).map(Filtered::visible).unwrap_or(0)
+    }
+
+    /// Clones an event into the buffers of the filtered subscriptions it matches
+    ///
+    /// Returns whether it matched any of them.
+    fn push_filtered(&mut self, event: &T, stamp: u64) -> bool {
+        let mut matched = false;
+        for filtered in self.filtered.values_mut() {
+            if (filtered.predicate)(event) {
+                filtered.events.push((filtered.clone)(event));
+                filtered.stamps.push(stamp);
+                matched = true;
+            }
+        }
+        matched
+    }
+
+    /// Get the start and end index of new events since last `pull`
+    fn pull(&mut self, key: ListenerKey) -> (usize, usize) {
+        let maxidx = self.visible();
+        let idx = self.listeners.get_mut(key).unwrap();
+        // listeners created while frozen may be ahead of the visible events
+        let start = (*idx).min(maxidx);
+        *idx = (*idx).max(maxidx);
+        (start, maxidx)
     }
 
     /// Applies a function to the list of new events since last `pull`
@@ -59,8 +228,18 @@ impl<T> Queue<T> {
     where
         F: FnOnce(&[T]) -> R,
     {
-        let idx = self.pull(key);
-        let ret = f(&self.events[idx..]);
+        self.pull_stamped_with(key, |events, _| f(events))
+    }
+
+    /// Applies a function to the list of new events since last `pull`,
+    /// along with their arrival stamps
+    #[inline]
+    pub fn pull_stamped_with<F, R>(&mut self, key: ListenerKey, f: F) -> R
+    where
+        F: FnOnce(&[T], &[u64]) -> R,
+    {
+        let (idx, end) = self.pull(key);
+        let ret = f(&self.events[idx..end], &self.stamps[idx..end]);
         if idx == 0 {
             // this was a blocker
             self.cleanup();
@@ -71,13 +250,24 @@ impl<T> Queue<T> {
     /// Get the next event since last `pull`
     #[inline]
     pub fn peek_get(&self, key: ListenerKey) -> Option<&T> {
-        self.events.get(*self.listeners.get(key)?)
+        let idx = *self.listeners.get(key)?;
+        if idx < self.visible() {
+            self.events.get(idx)
+        } else {
+            None
+        }
+    }
+
+    /// Get the number of events since last `pull`
+    #[inline]
+    pub fn pending(&self, key: ListenerKey) -> usize {
+        self.listeners.get(key).map(|idx| self.visible().saturating_sub(*idx)).unwrap_or(0)
     }
 
     /// Finish with this peek, go to next event
     #[inline]
     pub fn peek_finish(&mut self, key: ListenerKey) {
-        let maxidx = self.events.len();
+        let maxidx = self.visible();
         let was_blocker = self
             .listeners
             .get_mut(key)
@@ -104,20 +294,52 @@ impl<T> Queue<T> {
     }
 }
 
+impl<T> crate::traits::QueueInterfaceFreezable for std::cell::RefCell<Queue<T>> {
+    #[inline]
+    fn freeze(&self) {
+        self.borrow_mut().freeze();
+    }
+
+    #[inline]
+    fn thaw(&self) {
+        self.borrow_mut().thaw();
+    }
+}
+
+impl<T> crate::traits::QueueInterfaceFreezable for std::sync::RwLock<Queue<T>> {
+    #[inline]
+    fn freeze(&self) {
+        if let Ok(mut queue) = self.write() {
+            queue.freeze();
+        }
+    }
+
+    #[inline]
+    fn thaw(&self) {
+        if let Ok(mut queue) = self.write() {
+            queue.thaw();
+        }
+    }
+}
+
 impl<T> crate::traits::QueueInterfaceCommon for Queue<T> {
     type Item = T;
 
     #[inline]
     fn buffer_is_empty(&self) -> bool {
-        self.events.is_empty()
+        self.events.is_empty() && self.filtered.values().all(|filtered| filtered.events.is_empty())
     }
 }
 
 impl<T: Clone> crate::traits::EmitterMut for Queue<T> {
     #[inline]
     fn emit<'a>(&mut self, event: std::borrow::Cow<'a, T>) -> crate::traits::EmitResult<'a, T> {
+        let stamp = next_stamp();
+        let matched = !self.filtered.is_empty() && self.push_filtered(&event, stamp);
         if !self.listeners.is_empty() {
-            self.events.push(event.into_owned());
+            self.push_stamped(event.into_owned(), stamp);
+            crate::traits::EmitResult::Delivered
+        } else if matched {
             crate::traits::EmitResult::Delivered
         } else {
             crate::traits::EmitResult::Undelivered(event)
@@ -131,8 +353,16 @@ impl<A> std::iter::Extend<A> for Queue<A> {
     where
         T: IntoIterator<Item = A>,
     {
-        if !self.listeners.is_empty() {
-            self.events.extend(iter)
+        if !self.listeners.is_empty() || !self.filtered.is_empty() {
+            for event in iter {
+                let stamp = next_stamp();
+                if !self.filtered.is_empty() {
+                    self.push_filtered(&event, stamp);
+                }
+                if !self.listeners.is_empty() {
+                    self.push_stamped(event, stamp);
+                }
+            }
         }
     }
 }
@@ -190,4 +420,77 @@ mod tests {
 
         assert_eq!(event.events_len(), 0);
     }
+
+    #[test]
+    fn test_filtered_listener() {
+        let mut event = Queue::new();
+
+        let even = event.create_filtered_listener(|x: &i32| x % 2 == 0);
+        event.emit_owned(1).to_result().unwrap_err();
+        event.emit_owned(2).to_result().unwrap();
+        // only the matching events are stored, and only for the filtered listener.
+        assert_eq!(event.events_len(), 0);
+        assert_eq!(event.pending_filtered(even), 1);
+
+        let listener = event.create_listener();
+        event.freeze();
+        event.extend(vec![3, 4]);
+        assert_eq!(event.pending_filtered(even), 1);
+        event.thaw();
+        event.pull_filtered_stamped_with(even, |x, stamps| {
+            assert_eq!(x, &[2, 4]);
+            assert!(stamps[0] < stamps[1]);
+        });
+        event.pull_filtered_stamped_with(even, |x, _| assert_eq!(x, &[] as &[i32]));
+        event.pull_with(listener, |x| assert_eq!(x, &[3, 4]));
+
+        event.remove_filtered_listener(even);
+        event.remove_listener(listener);
+        event.emit_owned(6).to_result().unwrap_err();
+    }
+
+    #[test]
+    fn test_extend_without_clone() {
+        #[derive(Debug, PartialEq)]
+        struct Unique(i32);
+
+        // only filtered listeners need to clone events, so extending doesn't.
+        let mut event = Queue::new();
+        let listener = event.create_listener();
+        event.extend(vec![Unique(1), Unique(2)]);
+        event.pull_with(listener, |x| assert_eq!(x, &[Unique(1), Unique(2)]));
+    }
+
+    #[test]
+    fn test_event_freeze() {
+        let mut event = Queue::new();
+
+        let listener_1 = event.create_listener();
+
+        event.emit_owned(10).to_result().unwrap();
+        event.freeze();
+        event.emit_owned(20).to_result().unwrap();
+
+        let listener_2 = event.create_listener();
+        event.emit_owned(30).to_result().unwrap();
+
+        assert_eq!(event.pending(listener_1), 1);
+        assert_eq!(event.pending(listener_2), 0);
+        event.pull_with(listener_1, |x| assert_eq!(x, &[10]));
+        event.pull_with(listener_1, |x| assert_eq!(x, &[]));
+        event.pull_with(listener_2, |x| assert_eq!(x, &[]));
+
+        event.freeze();
+        event.pull_with(listener_1, |x| assert_eq!(x, &[20, 30]));
+        event.pull_with(listener_2, |x| assert_eq!(x, &[30]));
+
+        event.emit_owned(40).to_result().unwrap();
+        assert_eq!(event.peek_get(listener_1), None);
+        event.thaw();
+        assert_eq!(event.peek_get(listener_1), Some(&40));
+        event.pull_with(listener_1, |x| assert_eq!(x, &[40]));
+        event.pull_with(listener_2, |x| assert_eq!(x, &[40]));
+
+        assert_eq!(event.events_len(), 0);
+    }
 }
@@ -92,18 +92,43 @@ channels_api! {
 #[cfg_attr(feature = "docs", doc(cfg(futures)))]
 pub mod streaming;
 
+/// Contains a conformance test suite for event queue implementations
+#[cfg(feature = "test-util")]
+#[cfg_attr(feature = "docs", doc(cfg(test_util)))]
+pub mod conformance;
+
+/// Contains a guard which defers emitting events
+pub mod deferred;
+
+/// Contains frame boundary snapshots across event queues
+pub mod frame;
+
 /// Contains an Event queue merger
 pub mod merge;
 
 /// Contains the non-thread-safe, non-reference-counted API
 pub mod nonrc;
 
+/// Contains helpers which emit the outcome of fallible producers,
+/// including panics, as `Result` events
+pub mod outcome;
+
+/// Contains listener guards which catch unhandled events
+pub mod scoped;
+
+/// Contains a listener group which polls several listeners at once
+pub mod set;
+
 /// Contains the non-thread-safe, reference-counted API
 pub mod nonts;
 
 /// Contains the thread-safe, reference-counted API
 pub mod ts;
 
+/// Contains a thread-safe work queue,
+/// which delivers each event to only one of its listeners
+pub mod work;
+
 // implementation of traits for 3rd party types
 #[doc(hidden)]
 pub mod thirdparty;
@@ -118,15 +143,23 @@ use intern::ListenerKey;
 pub mod prelude {
     pub use crate::traits::{
         Emitter as EventEmitter, EmitterExt as EventEmitterExt, EmitterMut as EventEmitterMut,
-        EmitterMutExt as EventEmitterMutExt, Listen as EventListen, QueueInterfaceCommon,
-        QueueInterfaceListable,
+        EmitterMutExt as EventEmitterMutExt, Listen as EventListen, ListenExt as EventListenExt,
+        ListenPending as EventListenPending, ListenStamped as EventListenStamped,
+        QueueInterfaceCommon, QueueInterfaceFreezable, QueueInterfaceListable,
     };
 }
 
 pub use {
+    deferred::DeferredEmit,
     intern::Queue as RawEventQueue,
     nonrc::{Listener as NonRcEventListener, Queue as NonRcEventQueue},
-    nonts::{Listener as RcEventListener, Queue as RcEventQueue},
+    nonts::{
+        FilteredListener as RcFilteredEventListener, Listener as RcEventListener,
+        Queue as RcEventQueue,
+    },
     prelude::*,
+    scoped::ScopedListener,
+    set::ListenerSet,
     traits::EmitResult,
+    ts::{Listener as ArcEventListener, Queue as ArcEventQueue},
 };
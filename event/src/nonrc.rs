@@ -23,6 +23,18 @@ impl<T> Default for Queue<T> {
     }
 }
 
+impl<T> QueueInterfaceFreezable for Queue<T> {
+    #[inline]
+    fn freeze(&self) {
+        self.0.freeze()
+    }
+
+    #[inline]
+    fn thaw(&self) {
+        self.0.thaw()
+    }
+}
+
 impl<T> Deref for Queue<T> {
     type Target = RefCell<RawEventQueue<T>>;
 
@@ -47,6 +59,23 @@ impl<T> EventListen for Listener<'_, T> {
     }
 }
 
+impl<T> EventListenStamped for Listener<'_, T> {
+    #[inline]
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R,
+    {
+        self.1.borrow_mut().pull_stamped_with(self.0, f)
+    }
+}
+
+impl<T> EventListenPending for Listener<'_, T> {
+    #[inline]
+    fn pending(&self) -> usize {
+        self.1.borrow().pending(self.0)
+    }
+}
+
 impl<T> Drop for Listener<'_, T> {
     #[inline]
     fn drop(&mut self) {
@@ -13,6 +13,21 @@ impl<T> Queue<T> {
     }
 }
 
+impl<T: Clone> Queue<T> {
+    /// Creates a listener which only receives the events matching `predicate`
+    ///
+    /// Unlike filtering the events of a regular listener, the other events are never stored for it;
+    /// the matching events are cloned into its own buffer as they're emitted.
+    #[inline]
+    pub fn listen_filtered(
+        &self,
+        predicate: impl Fn(&T) -> bool + Send + Sync + 'static,
+    ) -> FilteredListener<T> {
+        let key = self.0.borrow_mut().create_filtered_listener(predicate);
+        FilteredListener(key, self.0.clone())
+    }
+}
+
 impl<T> Default for Queue<T> {
     #[inline]
     fn default() -> Self {
@@ -20,6 +35,18 @@ impl<T> Default for Queue<T> {
     }
 }
 
+impl<T> QueueInterfaceFreezable for Queue<T> {
+    #[inline]
+    fn freeze(&self) {
+        self.0.freeze()
+    }
+
+    #[inline]
+    fn thaw(&self) {
+        self.0.thaw()
+    }
+}
+
 impl<T> Deref for Queue<T> {
     type Target = Intern<T>;
 
@@ -53,6 +80,23 @@ impl<T> EventListen for Listener<T> {
     }
 }
 
+impl<T> EventListenStamped for Listener<T> {
+    #[inline]
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R,
+    {
+        self.1.borrow_mut().pull_stamped_with(self.0, f)
+    }
+}
+
+impl<T> EventListenPending for Listener<T> {
+    #[inline]
+    fn pending(&self) -> usize {
+        self.1.borrow().pending(self.0)
+    }
+}
+
 impl<T> Drop for Listener<T> {
     fn drop(&mut self) {
         self.1.borrow_mut().remove_listener(self.0)
@@ -66,6 +110,45 @@ impl<T> Listener<T> {
     }
 }
 
+/// A listener which only receives the events matching a predicate (see [`Queue::listen_filtered`])
+#[derive(Debug)]
+pub struct FilteredListener<T>(ListenerKey, Intern<T>);
+
+impl<T> EventListen for FilteredListener<T> {
+    type Item = T;
+
+    #[inline]
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item]) -> R,
+    {
+        self.1.borrow_mut().pull_filtered_stamped_with(self.0, |events, _| f(events))
+    }
+}
+
+impl<T> EventListenStamped for FilteredListener<T> {
+    #[inline]
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R,
+    {
+        self.1.borrow_mut().pull_filtered_stamped_with(self.0, f)
+    }
+}
+
+impl<T> EventListenPending for FilteredListener<T> {
+    #[inline]
+    fn pending(&self) -> usize {
+        self.1.borrow().pending_filtered(self.0)
+    }
+}
+
+impl<T> Drop for FilteredListener<T> {
+    fn drop(&mut self) {
+        self.1.borrow_mut().remove_filtered_listener(self.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,6 +171,56 @@ mod tests {
         drop(listener);
     }
 
+    #[test]
+    fn test_listener_extensions() {
+        let event = Queue::default();
+        let listener = event.listen();
+
+        event.emit_owned(1i32).to_result().unwrap();
+        event.emit_owned(2i32).to_result().unwrap();
+
+        let mut sum = 0;
+        listener.for_each(|i| sum += i);
+        assert_eq!(sum, 3);
+
+        event.emit_owned(3i32).to_result().unwrap();
+        event.emit_owned(4i32).to_result().unwrap();
+        assert_eq!(listener.latest(), Some(4));
+        assert_eq!(listener.latest(), None);
+    }
+
+    #[test]
+    fn test_filtered_listener() {
+        #[derive(Clone, Debug, PartialEq)]
+        enum Event {
+            Click(u32),
+            Hover,
+        }
+
+        let event = Queue::default();
+        let clicks = event.listen_filtered(|event| matches!(event, Event::Click(_)));
+
+        event.emit_owned(Event::Hover).to_result().unwrap_err();
+        event.emit_owned(Event::Click(1)).to_result().unwrap();
+        event.emit_owned(Event::Hover).to_result().unwrap_err();
+        event.emit_owned(Event::Click(2)).to_result().unwrap();
+
+        assert_eq!(clicks.pending(), 2);
+        assert_eq!(clicks.peek(), &[Event::Click(1), Event::Click(2)]);
+        assert_eq!(clicks.peek(), &[]);
+
+        // regular listeners still see every event.
+        let listener = event.listen();
+        event.emit_owned(Event::Hover).to_result().unwrap();
+        event.emit_owned(Event::Click(3)).to_result().unwrap();
+        assert_eq!(listener.peek(), &[Event::Hover, Event::Click(3)]);
+        assert_eq!(clicks.latest(), Some(Event::Click(3)));
+
+        drop(listener);
+        drop(clicks);
+        event.emit_owned(Event::Click(4)).to_result().unwrap_err();
+    }
+
     #[test]
     fn test_event_cleanup() {
         let event = Queue::default();
@@ -0,0 +1,111 @@
+use crate::traits::Emitter;
+use std::{
+    any::Any,
+    borrow::Cow,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// Why a producer didn't yield a value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Failure<E> {
+    /// The producer returned an error
+    Error(E),
+    /// The producer panicked, with the panic message (if it was a string)
+    Panic(String),
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for Failure<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::Error(err) => err.fmt(f),
+            Failure::Panic(msg) => write!(f, "panicked: {}", msg),
+        }
+    }
+}
+
+impl<E: std::error::Error + 'static> std::error::Error for Failure<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Failure::Error(err) => Some(err),
+            Failure::Panic(_) => None,
+        }
+    }
+}
+
+/// The event emitted for a fallible producer
+pub type Outcome<T, E> = Result<T, Failure<E>>;
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "unknown panic payload".to_string(),
+        },
+    }
+}
+
+/// Runs a fallible producer, catching any panic, and returns its outcome
+pub fn capture<T, E>(producer: impl FnOnce() -> Result<T, E>) -> Outcome<T, E> {
+    match panic::catch_unwind(AssertUnwindSafe(producer)) {
+        Ok(result) => result.map_err(Failure::Error),
+        Err(payload) => Err(Failure::Panic(panic_message(payload))),
+    }
+}
+
+/// Runs a fallible producer and emits its outcome onto `queue`, whether it succeeded, failed or panicked
+pub fn emit_outcome<Q, T, E>(queue: &Q, producer: impl FnOnce() -> Result<T, E>)
+where
+    Q: Emitter<Item = Outcome<T, E>> + ?Sized,
+    T: Clone,
+    E: Clone,
+{
+    queue.emit(Cow::Owned(capture(producer)));
+}
+
+/// Runs a fallible producer on a new thread and emits its outcome onto a thread-safe `queue`
+///
+/// ```ignore
+/// let loaded = reclutch_event::ts::Queue::default();
+/// spawn_outcome(loaded.clone(), move || std::fs::read(path));
+/// ```
+pub fn spawn_outcome<Q, T, E>(
+    queue: Q,
+    producer: impl FnOnce() -> Result<T, E> + Send + 'static,
+) -> std::thread::JoinHandle<()>
+where
+    Q: Emitter<Item = Outcome<T, E>> + Send + 'static,
+    T: Clone + Send + 'static,
+    E: Clone + Send + 'static,
+{
+    std::thread::spawn(move || emit_outcome(&queue, producer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, ts};
+
+    #[test]
+    fn test_outcome_events() {
+        let queue: ts::Queue<Outcome<u32, String>> = Default::default();
+        let listener = queue.listen();
+
+        emit_outcome(&queue, || Ok(1));
+        emit_outcome(&queue, || Err("not found".to_string()));
+        spawn_outcome(queue.clone(), || panic!("worker crashed")).join().unwrap();
+        spawn_outcome(queue.clone(), || if true { panic!("code {}", 42) } else { Ok(0) })
+            .join()
+            .unwrap();
+
+        assert_eq!(
+            listener.peek(),
+            vec![
+                Ok(1),
+                Err(Failure::Error("not found".to_string())),
+                Err(Failure::Panic("worker crashed".to_string())),
+                Err(Failure::Panic("code 42".to_string())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use crate::traits::{Listen, ListenPending, ListenStamped};
+use std::ops::Deref;
+
+/// A listener guard which detaches from its queue when dropped,
+/// asserting (in debug builds) that every event was consumed beforehand.
+///
+/// Events which are deliberately ignored should be consumed through
+/// [`discard`](ScopedListener::discard) before the guard goes out of scope.
+#[derive(Debug)]
+pub struct ScopedListener<L: ListenPending>(Option<L>);
+
+impl<L: ListenPending> ScopedListener<L> {
+    #[inline]
+    pub fn new(listener: L) -> Self {
+        ScopedListener(Some(listener))
+    }
+
+    /// Returns the wrapped listener without checking for unconsumed events
+    #[inline]
+    pub fn into_inner(mut self) -> L {
+        self.0.take().unwrap()
+    }
+
+    /// Marks all new events as seen, without looking at them
+    #[inline]
+    pub fn discard(&self) -> usize {
+        self.with(<[L::Item]>::len)
+    }
+}
+
+impl<L: ListenPending> From<L> for ScopedListener<L> {
+    #[inline]
+    fn from(listener: L) -> Self {
+        ScopedListener::new(listener)
+    }
+}
+
+impl<L: ListenPending> Deref for ScopedListener<L> {
+    type Target = L;
+
+    #[inline]
+    fn deref(&self) -> &L {
+        self.0.as_ref().unwrap()
+    }
+}
+
+impl<L: ListenPending> Listen for ScopedListener<L> {
+    type Item = L::Item;
+
+    #[inline]
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item]) -> R,
+    {
+        (**self).with(f)
+    }
+}
+
+impl<L: ListenPending> ListenPending for ScopedListener<L> {
+    #[inline]
+    fn pending(&self) -> usize {
+        (**self).pending()
+    }
+}
+
+impl<L: ListenPending + ListenStamped> ListenStamped for ScopedListener<L> {
+    #[inline]
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R,
+    {
+        (**self).with_stamped(f)
+    }
+}
+
+impl<L: ListenPending> Drop for ScopedListener<L> {
+    fn drop(&mut self) {
+        if let Some(listener) = self.0.take() {
+            // don't turn an unwinding panic into an abort
+            if cfg!(debug_assertions) && !std::thread::panicking() {
+                let pending = listener.pending();
+                assert!(pending == 0, "listener dropped with {} unconsumed event(s)", pending);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nonts::Queue, prelude::*};
+
+    #[test]
+    fn test_scoped_listener() {
+        let event = Queue::default();
+        let listener = ScopedListener::new(event.listen());
+
+        event.emit_owned(1i32).to_result().unwrap();
+        event.emit_owned(2i32).to_result().unwrap();
+        assert_eq!(listener.pending(), 2);
+        assert_eq!(listener.peek(), &[1, 2]);
+        assert_eq!(listener.pending(), 0);
+
+        event.emit_owned(3i32).to_result().unwrap();
+        assert_eq!(listener.discard(), 1);
+        drop(listener);
+
+        let listener = event.listen().scoped();
+        event.emit_owned(4i32).to_result().unwrap();
+        assert_eq!(listener.into_inner().peek(), &[4]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "1 unconsumed event(s)")]
+    fn test_scoped_listener_unconsumed() {
+        let event = Queue::default();
+        let _listener = ScopedListener::new(event.listen());
+        event.emit_owned(1i32).to_result().unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use crate::traits::ListenStamped;
+
+type Collect<'a, E> = Box<dyn Fn(&mut Vec<(u64, E)>) + 'a>;
+
+/// A group of listeners which are polled at once.
+///
+/// Each listener is registered along with a function which tags its events
+/// (usually an enum variant), such that all events can be handled in a single `match`:
+///
+/// ```ignore
+/// let mut listeners = ListenerSet::new();
+/// listeners.add(titlebar.move_event.listen(), PanelEvent::Titlebar);
+/// listeners.add(global.listen(), PanelEvent::Global);
+///
+/// listeners.poll(|event| match event { ... });
+/// ```
+pub struct ListenerSet<'a, E> {
+    listeners: Vec<Collect<'a, E>>,
+}
+
+impl<'a, E> Default for ListenerSet<'a, E> {
+    #[inline]
+    fn default() -> Self {
+        ListenerSet { listeners: Vec::new() }
+    }
+}
+
+impl<E> std::fmt::Debug for ListenerSet<'_, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ListenerSet").field("listeners", &self.listeners.len()).finish()
+    }
+}
+
+impl<'a, E> ListenerSet<'a, E> {
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers a listener, using `tag` to convert its events
+    pub fn add<L>(&mut self, listener: L, tag: impl Fn(L::Item) -> E + 'a) -> &mut Self
+    where
+        L: ListenStamped + 'a,
+        L::Item: Clone,
+    {
+        self.listeners.push(Box::new(move |events| {
+            listener.with_stamped(|items, stamps| {
+                events.extend(stamps.iter().copied().zip(items.iter().cloned().map(&tag)))
+            })
+        }));
+        self
+    }
+
+    /// Builder-style variant of [`add`](ListenerSet::add)
+    #[inline]
+    pub fn with<L>(mut self, listener: L, tag: impl Fn(L::Item) -> E + 'a) -> Self
+    where
+        L: ListenStamped + 'a,
+        L::Item: Clone,
+    {
+        self.add(listener, tag);
+        self
+    }
+
+    /// Returns the number of registered listeners
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.listeners.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.listeners.is_empty()
+    }
+
+    /// Returns the new events of all listeners, tagged and in arrival order
+    pub fn peek(&self) -> Vec<E> {
+        let mut events = Vec::new();
+        for collect in &self.listeners {
+            collect(&mut events);
+        }
+        events.sort_by_key(|(stamp, _)| *stamp);
+        events.into_iter().map(|(_, event)| event).collect()
+    }
+
+    /// Applies a function to each new event of all listeners, in arrival order
+    #[inline]
+    pub fn poll(&self, f: impl FnMut(E)) {
+        self.peek().into_iter().for_each(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nonrc, nonts, prelude::*};
+
+    #[derive(Debug, PartialEq)]
+    enum Tagged {
+        Number(i32),
+        Text(&'static str),
+    }
+
+    #[test]
+    fn test_listener_set() {
+        let numbers = nonts::Queue::default();
+        let text = nonrc::Queue::new();
+
+        let listeners = ListenerSet::new()
+            .with(numbers.listen(), Tagged::Number)
+            .with(text.listen(), Tagged::Text);
+        assert_eq!(listeners.len(), 2);
+
+        numbers.emit_owned(1).to_result().unwrap();
+        text.emit_owned("a").to_result().unwrap();
+        numbers.emit_owned(2).to_result().unwrap();
+        text.emit_owned("b").to_result().unwrap();
+
+        let mut events = Vec::new();
+        listeners.poll(|event| events.push(event));
+        assert_eq!(
+            events,
+            vec![Tagged::Number(1), Tagged::Text("a"), Tagged::Number(2), Tagged::Text("b")]
+        );
+
+        assert!(listeners.peek().is_empty());
+    }
+}
@@ -4,7 +4,10 @@
 
 use crate::{
     channels_api,
-    traits::{EmitResult, Emitter, EmitterMut, EmitterMutExt, QueueInterfaceCommon},
+    traits::{
+        EmitResult, Emitter, EmitterMut, EmitterMutExt, QueueInterfaceCommon,
+        QueueInterfaceFreezable,
+    },
 };
 use retain_mut::RetainMut;
 use std::{
@@ -121,6 +124,18 @@ where
     }
 }
 
+impl<Q: QueueInterfaceFreezable + ?Sized> QueueInterfaceFreezable for std::rc::Rc<Q> {
+    #[inline(always)]
+    fn freeze(&self) {
+        self.deref().freeze()
+    }
+
+    #[inline(always)]
+    fn thaw(&self) {
+        self.deref().thaw()
+    }
+}
+
 impl<Q: QueueInterfaceCommon> QueueInterfaceCommon for Arc<Q> {
     type Item = <Q as QueueInterfaceCommon>::Item;
 
@@ -141,6 +156,18 @@ where
     }
 }
 
+impl<Q: QueueInterfaceFreezable + ?Sized> QueueInterfaceFreezable for Arc<Q> {
+    #[inline(always)]
+    fn freeze(&self) {
+        self.deref().freeze()
+    }
+
+    #[inline(always)]
+    fn thaw(&self) {
+        self.deref().thaw()
+    }
+}
+
 impl<Q: QueueInterfaceCommon> QueueInterfaceCommon for RefCell<Q> {
     type Item = <Q as QueueInterfaceCommon>::Item;
 
@@ -177,6 +204,7 @@ where
 {
     #[inline]
     fn emit<'a>(&self, event: Cow<'a, Self::Item>) -> EmitResult<'a, Self::Item> {
+        let _snapshot = crate::frame::snapshot_guard();
         if let Ok(mut i) = self.write() {
             i.emit(event)
         } else {
@@ -44,9 +44,9 @@ impl<'a, T: Clone> From<Result<(), Cow<'a, T>>> for EmitResult<'a, T> {
     }
 }
 
-impl<'a, T: Clone> Into<Result<(), Cow<'a, T>>> for EmitResult<'a, T> {
-    fn into(self) -> Result<(), Cow<'a, T>> {
-        match self {
+impl<'a, T: Clone> From<EmitResult<'a, T>> for Result<(), Cow<'a, T>> {
+    fn from(result: EmitResult<'a, T>) -> Self {
+        match result {
             EmitResult::Delivered => Result::Ok(()),
             EmitResult::Undelivered(x) => Result::Err(x),
         }
@@ -97,6 +97,18 @@ where
     fn emit<'a>(&self, event: Cow<'a, Self::Item>) -> EmitResult<'a, Self::Item>;
 }
 
+/// Event queues which can hold back events at frame boundaries implement this trait
+///
+/// See [`frame::snapshot`](crate::frame::snapshot).
+pub trait QueueInterfaceFreezable {
+    /// Only delivers the events emitted up until now to listeners;
+    /// later events are held back until the queue is frozen again or thawed
+    fn freeze(&self);
+
+    /// Delivers all events again, including those held back
+    fn thaw(&self);
+}
+
 /// Event queues with the ability to create new listeners implement this trait
 pub trait QueueInterfaceListable: QueueInterfaceCommon
 where
@@ -150,6 +162,15 @@ where
     fn emit_borrowed<'a>(&self, event: &'a Self::Item) -> EmitResult<'a, Self::Item> {
         self.emit(Cow::Borrowed(event))
     }
+
+    /// Returns a guard which collects events and emits them once dropped
+    #[inline]
+    fn defer(&self) -> crate::deferred::DeferredEmit<'_, Self>
+    where
+        Self: Sized,
+    {
+        crate::deferred::DeferredEmit::new(self)
+    }
 }
 
 impl<Q: Emitter> EmitterExt for Q where Self::Item: Clone {}
@@ -177,11 +198,11 @@ pub trait Listen {
     /// It holds a lock on the event while called, which means that recursive
     /// calls to [`QueueInterfaceListable`] methods aren't allowed and will deadlock or panic.
     #[inline]
-    fn map<F, R>(&self, mut f: F) -> Vec<R>
+    fn map<F, R>(&self, f: F) -> Vec<R>
     where
         F: FnMut(&Self::Item) -> R,
     {
-        self.with(|slc| slc.iter().map(|i| f(i)).collect())
+        self.with(|slc| slc.iter().map(f).collect())
     }
 
     /// Returns a list of new events since last `peek`
@@ -193,3 +214,55 @@ pub trait Listen {
         self.with(<[Self::Item]>::to_vec)
     }
 }
+
+/// Convenience methods for every listener
+pub trait ListenExt: Listen {
+    /// Applies a function to each new event since last `with` or `peek`
+    /// without cloning T
+    #[inline]
+    fn for_each<F>(&self, f: F)
+    where
+        F: FnMut(&Self::Item),
+    {
+        self.with(|slc| slc.iter().for_each(f))
+    }
+
+    /// Returns the most recent of the new events since last `with` or `peek`,
+    /// discarding the other ones
+    #[inline]
+    fn latest(&self) -> Option<Self::Item>
+    where
+        Self::Item: Clone,
+    {
+        self.with(|slc| slc.last().cloned())
+    }
+
+    /// Wraps this listener into a [`ScopedListener`](crate::scoped::ScopedListener)
+    #[inline]
+    fn scoped(self) -> crate::scoped::ScopedListener<Self>
+    where
+        Self: ListenPending + Sized,
+    {
+        crate::scoped::ScopedListener::new(self)
+    }
+}
+
+impl<L: Listen + ?Sized> ListenExt for L {}
+
+/// Listeners which can tell how many events they haven't seen yet implement this trait
+pub trait ListenPending: Listen {
+    /// Returns the number of new events since last `with` or `peek`,
+    /// without marking them as seen
+    fn pending(&self) -> usize;
+}
+
+/// Listeners which can tell when their events arrived implement this trait
+pub trait ListenStamped: Listen {
+    /// Like [`with`](Listen::with), but additionally passes the arrival stamp of each event
+    ///
+    /// Stamps are increasing across all event queues,
+    /// and thus order events from different queues by the time they were emitted.
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R;
+}
@@ -8,7 +8,7 @@ impl<T: Clone> QueueInterfaceListable for Queue<T> {
 
     #[inline]
     fn listen(&self) -> Listener<T> {
-        Listener::new(Arc::clone(&self))
+        Listener::new(Arc::clone(self))
     }
 }
 
@@ -30,6 +30,23 @@ impl<T> EventListen for Listener<T> {
     }
 }
 
+impl<T> EventListenStamped for Listener<T> {
+    #[inline]
+    fn with_stamped<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item], &[u64]) -> R,
+    {
+        self.eq.write().ok().unwrap().pull_stamped_with(self.key, f)
+    }
+}
+
+impl<T> EventListenPending for Listener<T> {
+    #[inline]
+    fn pending(&self) -> usize {
+        self.eq.read().ok().unwrap().pending(self.key)
+    }
+}
+
 impl<T> Drop for Listener<T> {
     fn drop(&mut self) {
         if let Ok(mut eq) = self.eq.write() {
@@ -44,3 +61,69 @@ impl<T> Listener<T> {
         Listener { key, eq }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_event_listener() {
+        let event = Queue::default();
+
+        event.emit_owned(0i32).to_result().unwrap_err();
+
+        let listener = event.listen();
+
+        event.emit_owned(1i32).to_result().unwrap();
+        event.emit_owned(2i32).to_result().unwrap();
+
+        assert_eq!(listener.peek(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_worker_threads() {
+        let event: ArcEventQueue<u32> = Default::default();
+        let listener = event.listen();
+        // listening before any producer starts, so no event can be missed.
+        let remote = event.listen();
+
+        // several producers...
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                let event = Arc::clone(&event);
+                thread::spawn(move || {
+                    for i in 0..100 {
+                        event.emit_owned(worker * 100 + i);
+                    }
+                })
+            })
+            .collect();
+
+        // ...and a consumer on another thread.
+        let consumer = thread::spawn(move || {
+            let mut received = Vec::new();
+            while received.len() < 400 {
+                received.extend(remote.peek());
+                thread::yield_now();
+            }
+            received
+        });
+
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        let mut received = consumer.join().unwrap();
+        received.sort_unstable();
+        assert_eq!(received, (0..400).collect::<Vec<_>>());
+
+        // events of a single producer stay in order.
+        let local = listener.peek();
+        assert_eq!(local.len(), 400);
+        for worker in 0..4 {
+            let own: Vec<_> = local.iter().filter(|&&i| i / 100 == worker).copied().collect();
+            assert_eq!(own, (worker * 100..(worker + 1) * 100).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::*;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+/// How a work queue picks the listener which receives an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Events are assigned to the listeners in turn, as they're emitted
+    RoundRobin,
+    /// Events are kept in the queue until any listener asks for them
+    FirstReady,
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+    policy: Policy,
+    /// Events assigned to each listener (only used by `Policy::RoundRobin`), by listener id
+    listeners: Vec<(u64, VecDeque<T>)>,
+    shared: VecDeque<T>,
+    next: usize,
+    next_id: u64,
+}
+
+impl<T> Inner<T> {
+    /// Returns the events which are waiting for a listener
+    fn events(&mut self, id: u64) -> &mut VecDeque<T> {
+        match self.policy {
+            Policy::RoundRobin => {
+                &mut self.listeners.iter_mut().find(|(listener, _)| *listener == id).unwrap().1
+            }
+            Policy::FirstReady => &mut self.shared,
+        }
+    }
+
+    fn assign(&mut self, event: T) {
+        match self.policy {
+            Policy::RoundRobin => {
+                let idx = self.next % self.listeners.len();
+                self.next = idx + 1;
+                self.listeners[idx].1.push_back(event);
+            }
+            Policy::FirstReady => self.shared.push_back(event),
+        }
+    }
+}
+
+/// A thread-safe event queue which delivers each event to exactly one of its listeners.
+///
+/// This is useful to distribute work among identical consumers (e.g. several loader threads),
+/// as opposed to the other queues, which broadcast every event to every listener.
+/// Cloning the queue yields another handle to the same queue.
+#[derive(Debug)]
+pub struct Queue<T>(Arc<Mutex<Inner<T>>>);
+
+impl<T> Clone for Queue<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Queue(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Queue<T> {
+    pub fn new(policy: Policy) -> Self {
+        Queue(Arc::new(Mutex::new(Inner {
+            policy,
+            listeners: Vec::new(),
+            shared: VecDeque::new(),
+            next: 0,
+            next_id: 0,
+        })))
+    }
+
+    #[inline]
+    pub fn policy(&self) -> Policy {
+        self.0.lock().unwrap().policy
+    }
+}
+
+impl<T> Default for Queue<T> {
+    #[inline]
+    fn default() -> Self {
+        Queue::new(Policy::RoundRobin)
+    }
+}
+
+impl<T> QueueInterfaceCommon for Queue<T> {
+    type Item = T;
+
+    #[inline]
+    fn buffer_is_empty(&self) -> bool {
+        let inner = self.0.lock().unwrap();
+        inner.shared.is_empty() && inner.listeners.iter().all(|(_, events)| events.is_empty())
+    }
+}
+
+impl<T: Clone> EventEmitter for Queue<T> {
+    fn emit<'a>(&self, event: Cow<'a, T>) -> EmitResult<'a, T> {
+        let mut inner = self.0.lock().unwrap();
+        if inner.listeners.is_empty() {
+            EmitResult::Undelivered(event)
+        } else {
+            inner.assign(event.into_owned());
+            EmitResult::Delivered
+        }
+    }
+}
+
+impl<T: Clone> QueueInterfaceListable for Queue<T> {
+    type Listener = Listener<T>;
+
+    #[inline]
+    fn listen(&self) -> Listener<T> {
+        let mut inner = self.0.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.listeners.push((id, VecDeque::new()));
+        Listener { id, queue: self.clone() }
+    }
+}
+
+/// A listener of a work queue; events it receives aren't received by any other listener
+#[derive(Debug)]
+pub struct Listener<T> {
+    id: u64,
+    queue: Queue<T>,
+}
+
+impl<T> Listener<T> {
+    /// Takes a single event, leaving the rest to other listeners (if the policy permits it)
+    pub fn take(&self) -> Option<T> {
+        self.queue.0.lock().unwrap().events(self.id).pop_front()
+    }
+}
+
+impl<T> EventListen for Listener<T> {
+    type Item = T;
+
+    /// Takes all events available to this listener
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item]) -> R,
+    {
+        let mut inner = self.queue.0.lock().unwrap();
+        let events = inner.events(self.id);
+        let ret = f(events.make_contiguous());
+        events.clear();
+        ret
+    }
+}
+
+impl<T> EventListenPending for Listener<T> {
+    #[inline]
+    fn pending(&self) -> usize {
+        self.queue.0.lock().unwrap().events(self.id).len()
+    }
+}
+
+impl<T> Drop for Listener<T> {
+    fn drop(&mut self) {
+        if let Ok(mut inner) = self.queue.0.lock() {
+            // hand the events which weren't taken over to the remaining listeners
+            if let Some(idx) = inner.listeners.iter().position(|(id, _)| *id == self.id) {
+                let (_, events) = inner.listeners.remove(idx);
+                if !inner.listeners.is_empty() {
+                    for event in events {
+                        inner.assign(event);
+                    }
+                }
+            }
+            if inner.listeners.is_empty() {
+                inner.shared.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin() {
+        let queue = Queue::new(Policy::RoundRobin);
+        queue.emit_owned(0).to_result().unwrap_err();
+
+        let worker_1 = queue.listen();
+        let worker_2 = queue.listen();
+
+        for i in 1..=5 {
+            queue.emit_owned(i).to_result().unwrap();
+        }
+
+        assert_eq!(worker_1.pending(), 3);
+        assert_eq!(worker_1.take(), Some(1));
+        assert_eq!(worker_2.peek(), &[2, 4]);
+
+        // remaining events of a dropped worker are redistributed
+        drop(worker_1);
+        assert_eq!(worker_2.peek(), &[3, 5]);
+        assert!(queue.buffer_is_empty());
+    }
+
+    #[test]
+    fn test_first_ready() {
+        let queue = Queue::new(Policy::FirstReady);
+        let worker_1 = queue.listen();
+        let worker_2 = queue.listen();
+
+        for i in 1..=3 {
+            queue.emit_owned(i).to_result().unwrap();
+        }
+
+        assert_eq!(worker_2.take(), Some(1));
+        assert_eq!(worker_1.peek(), &[2, 3]);
+        assert!(worker_2.peek().is_empty());
+    }
+
+    #[test]
+    fn test_worker_threads() {
+        let queue = Queue::new(Policy::FirstReady);
+        let workers: Vec<_> = (0..4).map(|_| queue.listen()).collect();
+
+        for i in 0..100 {
+            queue.emit_owned(i).to_result().unwrap();
+        }
+
+        let handles: Vec<_> = workers
+            .into_iter()
+            .map(|worker| {
+                std::thread::spawn(move || {
+                    let mut taken = Vec::new();
+                    while let Some(event) = worker.take() {
+                        taken.push(event);
+                    }
+                    taken
+                })
+            })
+            .collect();
+
+        let mut taken: Vec<i32> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        taken.sort_unstable();
+        assert_eq!(taken, (0..100).collect::<Vec<_>>());
+    }
+}
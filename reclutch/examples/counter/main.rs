@@ -7,15 +7,16 @@ use {
         event_loop::{ControlFlow, EventLoop},
     },
     reclutch::{
+        animation::{Animator, Easing},
         display::{
-            self, Color, CommandGroup, DisplayCommand, DisplayListBuilder, FontInfo,
-            GraphicsDisplay, GraphicsDisplayPaint, Point, Rect, ResourceData, ResourceDescriptor,
-            ResourceReference, SharedData, Size, TextDisplayItem,
+            self, FontInfo, ResourceData, ResourceDescriptor, ResourceReference, SharedData,
+            TextDisplayItem,
         },
-        event::{RcEventListener, RcEventQueue},
+        draw::{FrameScheduler, FrameTiming, HasFrameTiming},
         prelude::*,
         WidgetChildren,
     },
+    std::time::Duration,
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -24,6 +25,18 @@ enum GlobalEvent {
     MouseMove(Point),
 }
 
+struct Globals {
+    frame: FrameTiming,
+    // whether a widget is animating, such that the frame after the current one is needed too.
+    animating: bool,
+}
+
+impl HasFrameTiming for Globals {
+    fn frame(&self) -> FrameTiming {
+        self.frame
+    }
+}
+
 #[derive(WidgetChildren)]
 struct Counter {
     count: i32,
@@ -40,10 +53,13 @@ struct Counter {
 }
 
 impl Counter {
-    pub fn new(global: &mut RcEventQueue<GlobalEvent>) -> Self {
-        let button_increase = Button::new(String::from("Count Up"), Point::new(10.0, 40.0), global);
+    pub fn new(global: &mut RcEventQueue<GlobalEvent>, scheduler: &FrameScheduler) -> Self {
+        let button_increase =
+            Button::new(String::from("Count Up"), Point::new(10.0, 40.0), global, scheduler);
         let button_decrease =
-            Button::new(String::from("Count Down"), Point::new(10.0, 100.0), global);
+            Button::new(String::from("Count Down"), Point::new(10.0, 100.0), global, scheduler);
+        let mut command_group = CommandGroup::new();
+        command_group.set_scheduler(scheduler);
         let button_increase_press_listener = button_increase.press_event.listen();
         let button_decrease_press_listener = button_decrease.press_event.listen();
 
@@ -53,7 +69,7 @@ impl Counter {
             button_decrease,
             button_increase_press_listener,
             button_decrease_press_listener,
-            command_group: CommandGroup::new(),
+            command_group,
             font_info: FontInfo::from_name(
                 "Arial",
                 &["Helvetica", "Segoe UI", "Lucida Grande"],
@@ -66,7 +82,7 @@ impl Counter {
 }
 
 impl Widget for Counter {
-    type UpdateAux = ();
+    type UpdateAux = Globals;
     type GraphicalAux = ();
     type DisplayObject = DisplayCommand;
 
@@ -74,10 +90,8 @@ impl Widget for Counter {
         Rect::new(Point::new(0.0, 0.0), Size::new(100.0, 100.0))
     }
 
-    fn update(&mut self, aux: &mut ()) {
-        for child in self.children_mut() {
-            child.update(aux);
-        }
+    fn update(&mut self, aux: &mut Globals) {
+        self.propagate_update(aux);
 
         for _event in self.button_increase_press_listener.peek() {
             self.count += 1;
@@ -119,9 +133,7 @@ impl Widget for Counter {
 
         self.command_group.push(display, &builder.build(), display::ZOrder(-1), None, None);
 
-        for child in self.children_mut() {
-            child.draw(display, aux);
-        }
+        self.propagate_draw(display, aux);
     }
 }
 
@@ -133,6 +145,7 @@ struct Button {
     pub position: Point,
 
     hover: bool,
+    color: Animator<Color>,
     global_listener: RcEventListener<GlobalEvent>,
     command_group: CommandGroup,
     font_info: FontInfo,
@@ -140,14 +153,27 @@ struct Button {
 }
 
 impl Button {
-    pub fn new(text: String, position: Point, global: &mut RcEventQueue<GlobalEvent>) -> Self {
+    pub fn new(
+        text: String,
+        position: Point,
+        global: &mut RcEventQueue<GlobalEvent>,
+        scheduler: &FrameScheduler,
+    ) -> Self {
+        let mut command_group = CommandGroup::new();
+        command_group.set_scheduler(scheduler);
+
         Self {
             press_event: RcEventQueue::default(),
             text,
             position,
             hover: false,
+            color: Animator::new(
+                Color::new(0.20, 0.55, 0.65, 1.0),
+                Duration::from_millis(150),
+                Easing::EaseOut,
+            ),
             global_listener: global.listen(),
-            command_group: CommandGroup::new(),
+            command_group,
             font_info: FontInfo::from_name(
                 "Arial",
                 &["Helvetica", "Segoe UI", "Lucida Grande"],
@@ -160,7 +186,7 @@ impl Button {
 }
 
 impl Widget for Button {
-    type UpdateAux = ();
+    type UpdateAux = Globals;
     type GraphicalAux = ();
     type DisplayObject = DisplayCommand;
 
@@ -168,7 +194,7 @@ impl Widget for Button {
         Rect::new(self.position, Size::new(150.0, 50.0))
     }
 
-    fn update(&mut self, _aux: &mut ()) {
+    fn update(&mut self, aux: &mut Globals) {
         let bounds = self.bounds();
 
         for event in self.global_listener.peek() {
@@ -181,11 +207,22 @@ impl Widget for Button {
                 GlobalEvent::MouseMove(pt) => {
                     let before = std::mem::replace(&mut self.hover, bounds.contains(pt));
                     if self.hover != before {
+                        self.color.animate_to(if self.hover {
+                            Color::new(0.25, 0.60, 0.70, 1.0)
+                        } else {
+                            Color::new(0.20, 0.55, 0.65, 1.0)
+                        });
                         self.command_group.repaint();
                     }
                 }
             }
         }
+
+        // the highlight fades by the time since the previous frame.
+        if self.color.update(aux) {
+            self.command_group.repaint();
+            aux.animating |= self.color.is_running();
+        }
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut ()) {
@@ -198,11 +235,7 @@ impl Widget for Button {
         }
 
         let bounds = self.bounds();
-        let color = if self.hover {
-            Color::new(0.25, 0.60, 0.70, 1.0)
-        } else {
-            Color::new(0.20, 0.55, 0.65, 1.0)
-        };
+        let color = *self.color.value();
 
         let mut builder = DisplayListBuilder::new();
 
@@ -255,14 +288,19 @@ fn main() {
 
     // set up the UI
     let mut window_q = RcEventQueue::default();
-    let mut counter = Counter::new(&mut window_q);
+    let mut scheduler = FrameScheduler::new();
+    let mut counter = Counter::new(&mut window_q, &scheduler);
     let mut cursor = Point::default();
+    let mut globals = Globals { frame: FrameTiming::default(), animating: false };
 
     let mut latest_window_size = window_size;
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Wait;
 
+        let redrawn =
+            matches!(event, WinitEvent::WindowEvent { event: WindowEvent::RedrawRequested, .. });
+
         match event {
             WinitEvent::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
                 if display.size().0 != latest_window_size.0 as _
@@ -271,6 +309,7 @@ fn main() {
                     display.resize((latest_window_size.0 as _, latest_window_size.1 as _)).unwrap();
                 }
 
+                globals.frame = scheduler.begin_frame();
                 counter.draw(&mut display, &mut ());
                 display.present(None).unwrap();
                 context.swap_buffers().unwrap();
@@ -300,11 +339,25 @@ fn main() {
             WinitEvent::WindowEvent { event: WindowEvent::Resized(size), .. } => {
                 let size = size.to_physical(context.window().hidpi_factor());
                 latest_window_size = (size.width as _, size.height as _);
+                scheduler.request_redraw();
             }
             _ => return,
         }
 
-        counter.update(&mut ());
-        context.window().request_redraw();
+        // animations only advance in the update following a frame, by the time since the previous frame,
+        // and only keep frames coming from there (an animation starting between frames repaints to begin with).
+        if !redrawn {
+            globals.frame.dt = Duration::default();
+        }
+        globals.animating = false;
+        counter.update(&mut globals);
+        if redrawn && globals.animating {
+            scheduler.request_animation_frame();
+        }
+
+        // only redraw once something changed, otherwise idle until the next event.
+        if scheduler.needs_redraw() {
+            context.window().request_redraw();
+        }
     });
 }
@@ -5,45 +5,39 @@ use {
         event_loop::{ControlFlow, EventLoop},
     },
     reclutch::{
+        asset::AssetRegistry,
+        behavior::{DragBehavior, DragEvent, DragInput, DragSource},
         display::{
             self, Color, CommandGroup, DisplayCommand, DisplayListBuilder, Filter, FontInfo,
             GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, ImageData, Point, Rect,
-            ResourceData, ResourceDescriptor, ResourceReference, SharedData, Size, TextDisplayItem,
-            Vector,
+            ResourceData, ResourceDescriptor, ResourceReference, SharedData, Size, StrokeAlignment,
+            TextDisplayItem, Vector,
         },
-        event::{merge::Merge, RcEventListener, RcEventQueue},
+        draw,
+        event::{merge::Merge, ListenerSet, RcEventListener, RcEventQueue},
+        input::{self, InputEvent, MouseButton, PropagatingEvent, PropagationPhase},
         prelude::*,
         WidgetChildren,
     },
 };
 
-#[derive(Clone)]
-struct ConsumableEvent<T>(std::rc::Rc<std::cell::RefCell<Option<T>>>);
-
-impl<T> ConsumableEvent<T> {
-    fn new(val: T) -> Self {
-        ConsumableEvent(std::rc::Rc::new(std::cell::RefCell::new(Some(val))))
-    }
-
-    fn with<P: FnMut(&T) -> bool>(&self, mut pred: P) -> Option<T> {
-        if self.0.borrow().is_some() {
-            if pred(self.0.borrow().as_ref().unwrap()) {
-                return self.0.replace(None);
-            }
-        }
-
-        None
-    }
-}
-
 #[derive(Clone)]
 enum GlobalEvent {
-    MouseClick(ConsumableEvent<Point>),
     MouseRelease(Point),
     MouseMove(Point),
     WindowResize,
 }
 
+impl DragSource for GlobalEvent {
+    fn drag_input(&self, area: &Rect) -> Option<DragInput> {
+        match self {
+            GlobalEvent::MouseMove(pos) => Some(DragInput::Move(*pos)),
+            GlobalEvent::MouseRelease(pos) => Some(DragInput::Release(*pos)),
+            GlobalEvent::WindowResize => None,
+        }
+    }
+}
+
 struct Globals {
     hidpi_factor: f64,
     cursor: Point,
@@ -59,9 +53,8 @@ enum TitlebarEvent {
 
 #[derive(WidgetChildren)]
 struct Titlebar {
-    pub move_event: RcEventQueue<TitlebarEvent>,
+    pub drag: DragBehavior<TitlebarEvent>,
     position: Point,
-    cursor_anchor: Option<Point>,
     global_listener: RcEventListener<GlobalEvent>,
     command_group: CommandGroup,
     width: f32,
@@ -78,11 +71,14 @@ impl Titlebar {
         global: &mut RcEventQueue<GlobalEvent>,
     ) -> Self {
         Titlebar {
-            move_event: RcEventQueue::default(),
+            drag: DragBehavior::with_output(|event| match event {
+                DragEvent::Started(position) => Some(TitlebarEvent::BeginClick(position)),
+                DragEvent::Moved { delta, .. } => Some(TitlebarEvent::Move(delta)),
+                DragEvent::Finished(_) => Some(TitlebarEvent::EndClick),
+            }),
             position,
-            cursor_anchor: None,
             global_listener: global.listen(),
-            command_group: CommandGroup::new(),
+            command_group: CommandGroup::with_label("titlebar"),
             width,
             text,
             font: FontInfo::from_name("Segoe UI", &["SF Display", "Arial"], None).unwrap(),
@@ -106,29 +102,16 @@ impl Widget for Titlebar {
     }
 
     fn update(&mut self, _aux: &mut Globals) {
+        let bounds = self.bounds();
         for event in self.global_listener.peek() {
-            match event {
-                GlobalEvent::MouseClick(click) => {
-                    if let Some(ref position) =
-                        click.with(|pos| self.bounds().contains(pos.clone()))
-                    {
-                        self.cursor_anchor = Some(position.clone());
-                        self.move_event.emit_owned(TitlebarEvent::BeginClick(position.clone()));
-                    }
-                }
-                GlobalEvent::MouseRelease(_) => {
-                    if self.cursor_anchor.is_some() {
-                        self.cursor_anchor = None;
-                        self.move_event.emit_owned(TitlebarEvent::EndClick);
-                    }
-                }
-                GlobalEvent::MouseMove(pos) => {
-                    if let Some(ref cursor_anchor) = self.cursor_anchor {
-                        self.move_event.emit_owned(TitlebarEvent::Move(pos - *cursor_anchor));
-                    }
-                }
-                _ => (),
-            }
+            self.drag.handle(&event, bounds);
+        }
+    }
+
+    fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, _aux: &mut Globals) {
+        // presses are dispatched, whereas moves and releases are global since they continue outside the titlebar.
+        if event.phase() == PropagationPhase::Target {
+            self.drag.handle(event.event(), self.bounds());
         }
     }
 
@@ -176,6 +159,11 @@ impl Widget for Titlebar {
     }
 }
 
+enum PanelEvent {
+    Titlebar(TitlebarEvent),
+    Global(GlobalEvent),
+}
+
 #[derive(WidgetChildren)]
 struct Panel {
     pub on_click: RcEventQueue<*const Panel>,
@@ -184,11 +172,13 @@ struct Panel {
     position_anchor: Option<Point>,
     position: Point,
     size: Size,
-    global_listener: RcEventListener<GlobalEvent>,
-    titlebar_move_listener: RcEventListener<TitlebarEvent>,
+    z_index: i32,
+    listeners: ListenerSet<'static, PanelEvent>,
     command_group: CommandGroup,
-    image_data: &'static [u8],
+    image_descriptor: ResourceDescriptor,
     image: Option<ResourceReference>,
+    // the image replaced by a dropped file, which is removed from the display on the next draw.
+    replaced_image: Option<ResourceReference>,
 }
 
 impl Panel {
@@ -196,11 +186,13 @@ impl Panel {
         position: Point,
         size: Size,
         text: String,
-        image_data: &'static [u8],
+        image_descriptor: ResourceDescriptor,
         global: &mut RcEventQueue<GlobalEvent>,
     ) -> Self {
         let titlebar = Titlebar::new(position.clone(), size.width - 1.0, text, global);
-        let titlebar_move_listener = titlebar.move_event.listen();
+        let listeners = ListenerSet::new()
+            .with(titlebar.drag.listen(), PanelEvent::Titlebar)
+            .with(global.listen(), PanelEvent::Global);
 
         Panel {
             on_click: RcEventQueue::default(),
@@ -208,35 +200,18 @@ impl Panel {
             position_anchor: None,
             position,
             size,
-            global_listener: global.listen(),
-            titlebar_move_listener,
-            command_group: CommandGroup::new(),
-            image_data,
+            z_index: 0,
+            listeners,
+            command_group: CommandGroup::with_label("panel"),
+            image_descriptor,
             image: None,
+            replaced_image: None,
         }
     }
 
     fn fit_in_window(&mut self, size: &Size) {
         let window_rect = Rect::new(Point::default(), size.clone());
-        let bounds = self.bounds();
-
-        let vert = if bounds.min_y() < window_rect.min_y() {
-            window_rect.min_y() - bounds.min_y()
-        } else if bounds.max_y() > window_rect.max_y() {
-            window_rect.max_y() - bounds.max_y()
-        } else {
-            0.0
-        };
-
-        let horiz = if bounds.min_x() < window_rect.min_x() {
-            window_rect.min_x() - bounds.min_x()
-        } else if bounds.max_x() > window_rect.max_x() {
-            window_rect.max_x() - bounds.max_x()
-        } else {
-            0.0
-        };
-
-        self.position += Vector::new(horiz, vert);
+        self.position = self.bounds().clamp_within(&window_rect).origin;
     }
 }
 
@@ -249,18 +224,22 @@ impl Widget for Panel {
         Rect::new(self.position, self.size)
     }
 
+    fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
     fn update(&mut self, aux: &mut Globals) {
         for child in self.children_mut() {
             child.update(aux);
         }
 
-        for event in self.titlebar_move_listener.peek() {
+        for event in self.listeners.peek() {
             match event {
-                TitlebarEvent::BeginClick(_) => {
+                PanelEvent::Titlebar(TitlebarEvent::BeginClick(_)) => {
                     self.position_anchor = Some(self.position);
                     self.on_click.emit_owned(self as _);
                 }
-                TitlebarEvent::Move(delta) => {
+                PanelEvent::Titlebar(TitlebarEvent::Move(delta)) => {
                     if let Some(position_anchor) = self.position_anchor {
                         self.position = position_anchor + delta;
 
@@ -270,22 +249,10 @@ impl Widget for Panel {
                         self.command_group.repaint();
                     }
                 }
-                TitlebarEvent::EndClick => {
+                PanelEvent::Titlebar(TitlebarEvent::EndClick) => {
                     self.position_anchor = None;
                 }
-            }
-        }
-
-        for event in self.global_listener.peek() {
-            match event {
-                GlobalEvent::MouseClick(click) => {
-                    if let Some(_) = click.with(|pos| self.bounds().contains(pos.clone())) {
-                        self.on_click.emit_owned(self as _);
-                        self.command_group.repaint();
-                        self.titlebar.command_group.repaint();
-                    }
-                }
-                GlobalEvent::WindowResize => {
+                PanelEvent::Global(GlobalEvent::WindowResize) => {
                     self.fit_in_window(&aux.size);
 
                     self.titlebar.set_position(self.position.clone());
@@ -296,13 +263,43 @@ impl Widget for Panel {
         }
     }
 
+    fn handle_input(&mut self, event: &mut PropagatingEvent<'_>, _aux: &mut Globals) {
+        if event.phase() == PropagationPhase::Capture {
+            return;
+        }
+
+        match event.event() {
+            InputEvent::PointerDown { .. } => {
+                // the click was on this panel (or its titlebar), so it shouldn't reach what's beneath.
+                event.stop_propagation();
+                self.on_click.emit_owned(self as _);
+                self.command_group.repaint();
+                self.titlebar.command_group.repaint();
+            }
+            InputEvent::FilesDropped { paths, .. } => {
+                // the panel shows the first of the dropped files; one which isn't an image is drawn as a placeholder.
+                if let Some(path) = paths.first() {
+                    self.image_descriptor = ResourceDescriptor::Image(ImageData::Encoded(
+                        ResourceData::File(path.clone()),
+                    ));
+                    self.replaced_image = self.image.take().or(self.replaced_image.take());
+                    event.stop_propagation();
+                    self.command_group.repaint();
+                }
+            }
+            _ => (),
+        }
+    }
+
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut ()) {
+        if let Some(replaced_image) = self.replaced_image.take() {
+            display.remove_resource(replaced_image);
+        }
+
         if self.image.is_none() {
-            self.image = display
-                .new_resource(ResourceDescriptor::Image(ImageData::Encoded(ResourceData::Data(
-                    SharedData::Static(self.image_data),
-                ))))
-                .ok();
+            // a corrupt image is drawn as a placeholder rather than leaving the panel empty.
+            let image = display.new_resource_or_placeholder(self.image_descriptor.clone());
+            self.image = display.report(image);
         }
 
         let bounds = self.bounds();
@@ -317,14 +314,17 @@ impl Widget for Panel {
             None,
         );
 
-        builder.push_image(None, bounds, self.image.clone().unwrap(), None);
+        if let Some(image) = self.image {
+            builder.push_image(None, bounds, image, None);
+        }
 
         builder.push_rectangle(
-            bounds.inflate(0.0, 0.5),
+            bounds,
             GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
                 color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
                 thickness: 1.0,
                 antialias: false,
+                alignment: StrokeAlignment::Outside,
                 ..Default::default()
             }),
             None,
@@ -340,7 +340,7 @@ impl Widget for Panel {
 
 #[derive(WidgetChildren)]
 struct PanelContainer {
-    #[vec_widget_child]
+    #[widget_children]
     panels: Vec<Panel>,
     listeners: Vec<RcEventListener<*const Panel>>,
 }
@@ -363,10 +363,7 @@ impl Widget for PanelContainer {
     type DisplayObject = DisplayCommand;
 
     fn update(&mut self, globals: &mut Globals) {
-        // propagate back to front so that panels rendered front-most get events first.
-        for child in self.children_mut().iter_mut().rev() {
-            child.update(globals);
-        }
+        self.propagate_update(globals);
 
         {
             // collect all the panel events into a single vec
@@ -376,17 +373,21 @@ impl Widget for PanelContainer {
             }
 
             for event in panel_events {
+                // raise the clicked panel above the others, rather than moving it to the end.
+                let top = self.panels.iter().map(|p| p.z_index).max().unwrap_or_default();
+                let at_top = self.panels.iter().filter(|p| p.z_index == top).count();
                 if let Some(panel_idx) = self.panels.iter().position(|p| p as *const Panel == event)
                 {
-                    let last = self.panels.len() - 1;
-                    self.panels.swap(panel_idx, last);
+                    if self.panels[panel_idx].z_index != top || at_top > 1 {
+                        self.panels[panel_idx].z_index = top + 1;
+                    }
                 }
             }
         }
     }
 
     fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut ()) {
-        for child in self.children_mut() {
+        for child in self.children_zsorted_mut() {
             child.draw(display, aux);
         }
     }
@@ -422,6 +423,8 @@ fn main() {
         },
     )
     .unwrap();
+    // keep at most 256 MiB of decoded images around.
+    display.set_image_budget(Some(256 << 20));
 
     display
         .push_command_group(
@@ -441,13 +444,23 @@ fn main() {
         size: Size::new(window_size.0 as _, window_size.1 as _),
     };
 
+    // images are read from the example directory (and reloaded when edited) in debug builds,
+    // falling back to the copies embedded in the executable.
+    let mut assets = AssetRegistry::new();
+    if cfg!(debug_assertions) {
+        assets =
+            assets.with_directory(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/image_viewer"));
+    }
+    let mut assets = assets
+        .with_bundle(reclutch::asset_bundle!("examples/image_viewer"; "ferris.png", "image.jpg"));
+
     let mut panel_container = PanelContainer::new();
 
     panel_container.add_panel(Panel::new(
         Point::new(10.0, 10.0),
         Size::new(288.0, 180.15),
         "Ferris".into(),
-        include_bytes!("ferris.png"),
+        assets.resolve("ferris").unwrap(),
         &mut global_q,
     ));
 
@@ -455,7 +468,7 @@ fn main() {
         Point::new(30.0, 30.0),
         Size::new(300.0, 200.0),
         "Forest".into(),
-        include_bytes!("image.jpg"),
+        assets.resolve("image").unwrap(),
         &mut global_q,
     ));
 
@@ -471,8 +484,13 @@ fn main() {
                 }
 
                 panel_container.draw(&mut display, &mut ());
-                display.present(None).unwrap();
-                context.swap_buffers().unwrap();
+                for error in display.take_errors() {
+                    eprintln!("failed to draw: {}", error);
+                }
+                // only what changed is redrawn, and nothing is swapped if nothing changed.
+                if !display.present_damage().unwrap().is_unchanged() {
+                    context.swap_buffers().unwrap();
+                }
             }
             Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
                 let position = position.to_physical(globals.hidpi_factor);
@@ -485,14 +503,32 @@ fn main() {
                 ..
             } => match state {
                 glutin::event::ElementState::Pressed => {
-                    global_q.emit_owned(GlobalEvent::MouseClick(ConsumableEvent::new(
-                        globals.cursor.clone(),
-                    )));
+                    // dispatched to the front-most panel under the cursor, rather than every panel.
+                    input::dispatch_pointer(
+                        &mut panel_container,
+                        &InputEvent::PointerDown {
+                            position: globals.cursor.clone(),
+                            button: MouseButton::Left,
+                            modifiers: Default::default(),
+                        },
+                        &mut globals,
+                    );
                 }
                 glutin::event::ElementState::Released => {
                     global_q.emit_owned(GlobalEvent::MouseRelease(globals.cursor.clone()));
                 }
             },
+            Event::WindowEvent { event: WindowEvent::DroppedFile(path), .. } => {
+                // each dropped file is reported separately, and opened by the panel it was dropped onto.
+                input::dispatch_pointer(
+                    &mut panel_container,
+                    &InputEvent::FilesDropped {
+                        paths: vec![path],
+                        position: globals.cursor.clone(),
+                    },
+                    &mut globals,
+                );
+            }
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
                 *control_flow = ControlFlow::Exit;
             }
@@ -503,6 +539,16 @@ fn main() {
                 globals.size.height = size.height as _;
                 global_q.emit_owned(GlobalEvent::WindowResize);
             }
+            Event::WindowEvent { event: WindowEvent::HiDpiFactorChanged(hidpi_factor), .. } => {
+                // the window was moved to a monitor with a different DPI.
+                globals.hidpi_factor = hidpi_factor;
+                draw::change_scale_factor(
+                    &mut panel_container,
+                    &mut display,
+                    hidpi_factor,
+                    &mut globals,
+                );
+            }
             _ => return,
         }
 
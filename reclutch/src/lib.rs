@@ -69,6 +69,26 @@ impl reclutch::widget::WidgetChildren for CounterWidget {
 }
 ```
 
+Collections of children are included with `#[widget_children]`, in the order they're iterated.
+This works for `Vec`, `Option`, arrays, `HashMap` and `BTreeMap` (of which the values are the children),
+as well as anything else which can be iterated by reference and has a `len`;
+```ignore
+#[widget_children]
+tabs: Vec<Tab>,
+#[widget_children]
+popup: Option<Popup>,
+```
+Note that the order of a `HashMap` is arbitrary, whereas later children are drawn over (and hit before) earlier ones,
+so a `BTreeMap` or `Vec` is preferable for overlapping children.
+
+A child whose auxiliary types differ from those of its parent (e.g. a widget from a library
+which only needs the theme part of the application's globals) can still be included
+by naming an [`AuxProjection`](widget/trait.AuxProjection.html) to view it through [`MapAux`](widget/struct.MapAux.html);
+```ignore
+#[widget_child(aux_map = "ThemeOnly")]
+color_picker: ColorPicker,
+```
+
 It should be noted that `Widget` and `WidgetChildren` aren't in a stable state.
 
 When/if trait specialization become stabilized, `WidgetChildren` can be merged into
@@ -141,6 +161,37 @@ mod tests {
             type DisplayObject = ();
         }
 
+        struct Tail;
+
+        impl reclutch::widget::AuxProjection for Tail {
+            type ParentUpdateAux = (i8, ());
+            type ParentGraphicalAux = ();
+            type UpdateAux = ();
+            type GraphicalAux = ();
+
+            fn update_aux(aux: &mut (i8, ())) -> &mut () {
+                &mut aux.1
+            }
+
+            fn graphical_aux(aux: &mut ()) -> &mut () {
+                aux
+            }
+        }
+
+        #[derive(WidgetChildren)]
+        struct Mapped {
+            #[widget_child(aux_map = "Tail")]
+            a: ExampleChild,
+            #[vec_widget_child(aux_map = "Tail")]
+            b: Vec<ExampleChild>,
+        }
+
+        impl Widget for Mapped {
+            type UpdateAux = (i8, ());
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
         let mut unnamed = Unnamed(ExampleChild(0), ExampleChild(1), vec![ExampleChild(2)]);
         let mut named = Named { a: ExampleChild(2), b: ExampleChild(3), c: vec![ExampleChild(4)] };
 
@@ -151,5 +202,82 @@ mod tests {
         assert_eq!(named.children_mut()[0].bounds().origin.x, 2.0);
         assert_eq!(named.children()[1].bounds().origin.x, 3.0);
         assert_eq!(named.children_mut()[2].bounds().origin.x, 4.0);
+
+        let mut mapped = Mapped { a: ExampleChild(5), b: vec![ExampleChild(6)] };
+        assert_eq!(mapped.children()[0].bounds().origin.x, 5.0);
+        for child in mapped.children_mut() {
+            child.update(&mut (0, ()));
+        }
+        assert_eq!(mapped.children_mut()[1].bounds().origin.x, 6.0);
+    }
+
+    #[cfg(feature = "reclutch_derive")]
+    #[test]
+    fn test_widget_children_derive() {
+        use crate as reclutch;
+        use reclutch::{
+            display::{Point, Rect},
+            prelude::*,
+        };
+        use std::collections::{BTreeMap, HashMap};
+
+        #[derive(WidgetChildren)]
+        struct Child(i8);
+
+        impl Widget for Child {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+
+            fn bounds(&self) -> Rect {
+                Rect::new(Point::new(self.0 as _, 0.0), Default::default())
+            }
+        }
+
+        #[derive(WidgetChildren)]
+        struct Collections {
+            #[widget_child]
+            first: Child,
+            #[widget_children]
+            list: Vec<Child>,
+            #[widget_children]
+            optional: Option<Child>,
+            #[widget_children]
+            array: [Child; 2],
+            #[widget_children]
+            map: HashMap<&'static str, Child>,
+            #[widget_children]
+            ordered: BTreeMap<u8, Child>,
+        }
+
+        impl Widget for Collections {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
+        let mut collections = Collections {
+            first: Child(0),
+            list: vec![Child(1), Child(2)],
+            optional: None,
+            array: [Child(3), Child(4)],
+            map: vec![("a", Child(5))].into_iter().collect(),
+            ordered: vec![(2, Child(7)), (1, Child(6))].into_iter().collect(),
+        };
+
+        let xs = |collections: &Collections| {
+            collections
+                .children()
+                .iter()
+                .map(|child| child.bounds().origin.x as i8)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(xs(&collections), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+
+        collections.optional = Some(Child(8));
+        collections.map.clear();
+        assert_eq!(xs(&collections), vec![0, 1, 2, 8, 3, 4, 6, 7]);
+        assert_eq!(collections.children_mut().len(), 8);
     }
 }